@@ -0,0 +1,51 @@
+use crate::VALIDATOR_DIR_FLAG;
+use clap::{App, Arg, ArgMatches};
+use slashing_protection::{SlashingDatabase, SLASHING_PROTECTION_FILENAME};
+use std::path::PathBuf;
+
+pub const CMD: &str = "compact-slashing-protection";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about(
+            "Removes the records from the slashing protection database which are not required \
+            for slashing protection, keeping only the latest block and attestation of each \
+            validator, and shrinks the database file. The validator client must not be running.",
+        )
+        .arg(
+            Arg::with_name(VALIDATOR_DIR_FLAG)
+                .long(VALIDATOR_DIR_FLAG)
+                .value_name("VALIDATOR_DIRECTORY")
+                .help(
+                    "The path to the validator client data directory, which contains the \
+                    slashing protection database. Defaults to ~/.lighthouse/validators",
+                )
+                .takes_value(true),
+        )
+}
+
+pub fn cli_run(matches: &ArgMatches) -> Result<(), String> {
+    let validator_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        VALIDATOR_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("validators"),
+    )?;
+    let db_path = validator_dir.join(SLASHING_PROTECTION_FILENAME);
+
+    if !db_path.exists() {
+        return Err(format!("No slashing protection database at {:?}", db_path));
+    }
+
+    let pruned = SlashingDatabase::open(&db_path)
+        .and_then(|db| db.compact())
+        .map_err(|e| {
+            format!(
+                "Unable to compact {:?}, is the validator client running? {:?}",
+                db_path, e
+            )
+        })?;
+
+    println!("Removed {} records from {:?}", pruned, db_path);
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use crate::{
+    common::{ensure_dir_exists, strip_off_newlines},
+    SECRETS_DIR_FLAG, VALIDATOR_DIR_FLAG,
+};
+use clap::{App, Arg, ArgMatches};
+use eth2_keystore::{Error as KeystoreError, Keystore, PlainText};
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use validator_dir::{Builder as ValidatorDirBuilder, BuilderError};
+
+pub const CMD: &str = "import";
+pub const DIRECTORY_FLAG: &str = "directory";
+pub const REUSE_PASSWORD_FLAG: &str = "reuse-password";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about(
+            "Imports EIP-2335 keystores (e.g., those generated by the eth2.0-deposit-cli) into \
+            validator directories, so that they can be used by the validator client. Each \
+            keystore is decrypted to check its password, which is then stored in the secrets \
+            directory. Keystores which have already been imported are skipped, so the command \
+            may safely be run again on the same directory.",
+        )
+        .arg(
+            Arg::with_name(DIRECTORY_FLAG)
+                .long(DIRECTORY_FLAG)
+                .value_name("KEYSTORES_DIRECTORY")
+                .help(
+                    "A directory containing the keystores to import, such as the validator_keys \
+                    directory created by the eth2.0-deposit-cli. Files named keystore*.json in \
+                    the directory and its subdirectories are imported.",
+                )
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name(REUSE_PASSWORD_FLAG)
+                .long(REUSE_PASSWORD_FLAG)
+                .help(
+                    "If present, the password entered for the first keystore is used for every \
+                    other keystore, rather than prompting for each one. A password is only \
+                    requested again if it does not decrypt a keystore.",
+                ),
+        )
+        .arg(
+            Arg::with_name(VALIDATOR_DIR_FLAG)
+                .long(VALIDATOR_DIR_FLAG)
+                .value_name("VALIDATOR_DIRECTORY")
+                .help(
+                    "The path where the validator directories will be created. \
+                    Defaults to ~/.lighthouse/validators",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SECRETS_DIR_FLAG)
+                .long(SECRETS_DIR_FLAG)
+                .value_name("SECRETS_DIR")
+                .help(
+                    "The path where the validator keystore passwords will be stored. \
+                    Defaults to ~/.lighthouse/secrets",
+                )
+                .takes_value(true),
+        )
+}
+
+pub fn cli_run(matches: &ArgMatches) -> Result<(), String> {
+    let keystores_dir: PathBuf = clap_utils::parse_required(matches, DIRECTORY_FLAG)?;
+    let reuse_password = matches.is_present(REUSE_PASSWORD_FLAG);
+    let validator_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        VALIDATOR_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("validators"),
+    )?;
+    let secrets_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        SECRETS_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("secrets"),
+    )?;
+
+    ensure_dir_exists(&validator_dir)?;
+    ensure_dir_exists(&secrets_dir)?;
+
+    let keystore_paths = keystore_paths(&keystores_dir)?;
+    if keystore_paths.is_empty() {
+        return Err(format!("No keystores found in {:?}", keystores_dir));
+    }
+
+    let mut reused_password: Option<PlainText> = None;
+
+    for path in keystore_paths {
+        let keystore = File::open(&path)
+            .map_err(|e| format!("Unable to open {:?}: {:?}", path, e))
+            .and_then(|file| {
+                Keystore::from_json_reader(file)
+                    .map_err(|e| format!("Unable to parse {:?}: {:?}", path, e))
+            })?;
+
+        // Skip keystores which have already been imported before asking for their password.
+        let voting_pubkey = format!("0x{}", keystore.pubkey());
+        if validator_dir.join(&voting_pubkey).exists() {
+            eprintln!("{}\talready exists, skipping", voting_pubkey);
+            continue;
+        }
+
+        let reused = reused_password
+            .as_ref()
+            .filter(|password| check_password(&keystore, password).unwrap_or(false))
+            .cloned();
+        let password = match reused {
+            Some(password) => password,
+            None => loop {
+                let password = read_password(&format!(
+                    "Enter the password for {:?}: ",
+                    path.file_name().unwrap_or_default()
+                ))?;
+                if check_password(&keystore, &password)
+                    .map_err(|e| format!("Invalid keystore {:?}: {}", path, e))?
+                {
+                    break password;
+                }
+                eprintln!("Invalid password, please try again.");
+            },
+        };
+
+        if reuse_password && reused_password.is_none() {
+            reused_password = Some(password.clone());
+        }
+
+        match ValidatorDirBuilder::new(validator_dir.clone(), secrets_dir.clone())
+            .voting_keystore(keystore, password.as_bytes())
+            .store_withdrawal_keystore(false)
+            .build()
+        {
+            Ok(_) => println!("{}", voting_pubkey),
+            Err(BuilderError::DirectoryAlreadyExists(_)) => {
+                eprintln!("{}\talready exists, skipping", voting_pubkey)
+            }
+            Err(e) => return Err(format!("Unable to build validator directory: {:?}", e)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the paths of the files named `keystore*.json` in `dir` and its subdirectories, in
+/// alphabetical order.
+///
+/// Symbolic links to directories are skipped, so that they cannot cause keystores from outside
+/// `dir` to be imported, or the search to never end.
+fn keystore_paths(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| format!("Unable to read {:?}: {:?}", dir, e))? {
+        let path = entry
+            .map_err(|e| format!("Unable to read {:?}: {:?}", dir, e))?
+            .path();
+        let file_type = fs::symlink_metadata(&path)
+            .map_err(|e| format!("Unable to read {:?}: {:?}", path, e))?
+            .file_type();
+        if file_type.is_dir() {
+            paths.append(&mut keystore_paths(&path)?);
+        } else if file_type.is_symlink() && path.is_dir() {
+            continue;
+        } else if path.is_file()
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.starts_with("keystore") && name.ends_with(".json")
+                })
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns `true` if `password` decrypts `keystore`.
+///
+/// Decryption verifies the keystore's checksum and that its secret key matches its public key,
+/// so an error is returned if the keystore is corrupt.
+fn check_password(keystore: &Keystore, password: &PlainText) -> Result<bool, String> {
+    match keystore.decrypt_keypair(password.as_bytes()) {
+        Ok(_) => Ok(true),
+        Err(KeystoreError::InvalidPassword) => Ok(false),
+        Err(e) => Err(format!("Unable to decrypt keystore: {:?}", e)),
+    }
+}
+
+/// Prompts for a password on stderr and reads it from stdin.
+fn read_password(prompt: &str) -> Result<PlainText, String> {
+    eprint!("{}", prompt);
+    io::stderr()
+        .flush()
+        .map_err(|e| format!("Unable to flush stderr: {:?}", e))?;
+
+    let mut password = String::new();
+    let bytes_read = io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .map_err(|e| format!("Unable to read password from stdin: {:?}", e))?;
+    if bytes_read == 0 {
+        return Err("Unable to read password from stdin: end of input".to_string());
+    }
+
+    Ok(strip_off_newlines(password.into_bytes()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn finds_keystores_in_subdirectories() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in &[
+            "keystore-b.json",
+            "sub/keystore-a.json",
+            "deposit_data.json",
+        ] {
+            File::create(dir.path().join(name)).unwrap();
+        }
+
+        assert_eq!(
+            keystore_paths(dir.path()).unwrap(),
+            vec![
+                dir.path().join("keystore-b.json"),
+                dir.path().join("sub/keystore-a.json")
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_symlinked_directories() {
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        File::create(dir.path().join("keystore-a.json")).unwrap();
+        File::create(outside.path().join("keystore-b.json")).unwrap();
+
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("outside")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+
+        assert_eq!(
+            keystore_paths(dir.path()).unwrap(),
+            vec![dir.path().join("keystore-a.json")]
+        );
+    }
+}
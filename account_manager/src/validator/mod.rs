@@ -1,5 +1,8 @@
+pub mod compact_slashing_protection;
 pub mod create;
 pub mod deposit;
+pub mod import;
+pub mod recover;
 
 use crate::common::base_wallet_dir;
 use clap::{App, Arg, ArgMatches};
@@ -18,16 +21,24 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("A path containing Eth2 EIP-2386 wallets. Defaults to ~/.lighthouse/wallets")
                 .takes_value(true),
         )
+        .subcommand(compact_slashing_protection::cli_app())
         .subcommand(create::cli_app())
         .subcommand(deposit::cli_app())
+        .subcommand(import::cli_app())
+        .subcommand(recover::cli_app())
 }
 
 pub fn cli_run<T: EthSpec>(matches: &ArgMatches, env: Environment<T>) -> Result<(), String> {
     let base_wallet_dir = base_wallet_dir(matches, "base-dir")?;
 
     match matches.subcommand() {
+        (compact_slashing_protection::CMD, Some(matches)) => {
+            compact_slashing_protection::cli_run(matches)
+        }
         (create::CMD, Some(matches)) => create::cli_run::<T>(matches, env, base_wallet_dir),
         (deposit::CMD, Some(matches)) => deposit::cli_run::<T>(matches, env),
+        (import::CMD, Some(matches)) => import::cli_run(matches),
+        (recover::CMD, Some(matches)) => recover::cli_run::<T>(matches, env),
         (unknown, _) => {
             return Err(format!(
                 "{} does not have a {} command. See --help",
@@ -0,0 +1,266 @@
+use super::create::STORE_WITHDRAW_FLAG;
+use crate::{
+    common::{ensure_dir_exists, random_password},
+    SECRETS_DIR_FLAG, VALIDATOR_DIR_FLAG,
+};
+use clap::{App, Arg, ArgMatches};
+use environment::Environment;
+use eth2_keystore::{keypair_from_secret, Keystore, KeystoreBuilder};
+use eth2_wallet::{
+    bip39::{Language, Mnemonic, Seed},
+    recover_validator_secret_from_seed, KeyType, PlainText,
+};
+use remote_beacon_node::RemoteBeaconNode;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use types::{EthSpec, Keypair};
+use validator_dir::{Builder as ValidatorDirBuilder, BuilderError};
+
+pub const CMD: &str = "recover";
+pub const MNEMONIC_FLAG: &str = "mnemonic-path";
+pub const FIRST_INDEX_FLAG: &str = "first-index";
+pub const COUNT_FLAG: &str = "count";
+pub const BEACON_SERVER_FLAG: &str = "beacon-node";
+pub const INCLUDE_UNKNOWN_FLAG: &str = "include-unknown";
+
+pub const DEFAULT_BEACON_SERVER: &str = "http://localhost:5052/";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about(
+            "Recovers validator keystores from the BIP-39 mnemonic of an EIP-2386 wallet, using \
+            the EIP-2333 HD key derivation scheme. Only the validators which are known to the \
+            beacon chain are recovered, unless --include-unknown is present.",
+        )
+        .arg(
+            Arg::with_name(MNEMONIC_FLAG)
+                .long(MNEMONIC_FLAG)
+                .value_name("MNEMONIC_PATH")
+                .help(
+                    "A path to a file containing the mnemonic. If not present, the mnemonic is \
+                    read from stdin.",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(FIRST_INDEX_FLAG)
+                .long(FIRST_INDEX_FLAG)
+                .value_name("FIRST_INDEX")
+                .help("The index of the first validator to recover.")
+                .default_value("0")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(COUNT_FLAG)
+                .long(COUNT_FLAG)
+                .value_name("COUNT")
+                .help("The number of validators to recover, starting at --first-index.")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name(BEACON_SERVER_FLAG)
+                .long(BEACON_SERVER_FLAG)
+                .value_name("NETWORK_ADDRESS")
+                .help("The HTTP API of a beacon node, used to check which validators are known.")
+                .default_value(DEFAULT_BEACON_SERVER)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(INCLUDE_UNKNOWN_FLAG)
+                .long(INCLUDE_UNKNOWN_FLAG)
+                .help(
+                    "If present, the validators are recovered without checking whether they are \
+                    known to the beacon chain. This may create validators which have never been \
+                    deposited.",
+                ),
+        )
+        .arg(
+            Arg::with_name(VALIDATOR_DIR_FLAG)
+                .long(VALIDATOR_DIR_FLAG)
+                .value_name("VALIDATOR_DIRECTORY")
+                .help(
+                    "The path where the validator directories will be created. \
+                    Defaults to ~/.lighthouse/validators",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(SECRETS_DIR_FLAG)
+                .long(SECRETS_DIR_FLAG)
+                .value_name("SECRETS_DIR")
+                .help(
+                    "The path where the validator keystore passwords will be stored. \
+                    Defaults to ~/.lighthouse/secrets",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(STORE_WITHDRAW_FLAG)
+                .long(STORE_WITHDRAW_FLAG)
+                .help(
+                    "If present, the withdrawal keystore will be stored alongside the voting \
+                    keypair. It is generally recommended to *not* store the withdrawal key and \
+                    instead generate them from the mnemonic when required.",
+                ),
+        )
+}
+
+pub fn cli_run<T: EthSpec>(matches: &ArgMatches, mut env: Environment<T>) -> Result<(), String> {
+    let mnemonic_path: Option<PathBuf> = clap_utils::parse_optional(matches, MNEMONIC_FLAG)?;
+    let first_index: u32 = clap_utils::parse_required(matches, FIRST_INDEX_FLAG)?;
+    let count: u32 = clap_utils::parse_required(matches, COUNT_FLAG)?;
+    let beacon_server: String = clap_utils::parse_required(matches, BEACON_SERVER_FLAG)?;
+    let validator_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        VALIDATOR_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("validators"),
+    )?;
+    let secrets_dir = clap_utils::parse_path_with_default_in_home_dir(
+        matches,
+        SECRETS_DIR_FLAG,
+        PathBuf::new().join(".lighthouse").join("secrets"),
+    )?;
+
+    let last_index = first_index
+        .checked_add(count)
+        .ok_or_else(|| format!("--{} plus --{} is too large", FIRST_INDEX_FLAG, COUNT_FLAG))?;
+
+    ensure_dir_exists(&validator_dir)?;
+    ensure_dir_exists(&secrets_dir)?;
+
+    let mnemonic = read_mnemonic(mnemonic_path)?;
+    // TODO: `bip39` does not use zeroize, as for `WalletBuilder::from_mnemonic`.
+    let seed = PlainText::from(Seed::new(&mnemonic, "").as_bytes().to_vec());
+
+    let derive = |index: u32, key_type: KeyType| -> Result<(Keypair, String), String> {
+        let (secret, path) = recover_validator_secret_from_seed(seed.as_bytes(), index, key_type)
+            .map_err(|e| format!("Unable to derive key {}: {:?}", index, e))?;
+        let keypair = keypair_from_secret(secret.as_bytes())
+            .map_err(|e| format!("Unable to derive key {}: {:?}", index, e))?;
+        Ok((keypair, format!("{}", path)))
+    };
+
+    let mut voting_keypairs = (first_index..last_index)
+        .map(|index| derive(index, KeyType::Voting).map(|keypair| (index, keypair)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Only recover the validators which have been deposited, so that keys which were never used
+    // are not mistaken for validators.
+    if !matches.is_present(INCLUDE_UNKNOWN_FLAG) {
+        let known = known_validators(&mut env, beacon_server, &voting_keypairs)?;
+        voting_keypairs = voting_keypairs
+            .into_iter()
+            .zip(known)
+            .filter_map(|((index, keypair), known)| {
+                if known {
+                    Some((index, keypair))
+                } else {
+                    eprintln!(
+                        "{}\t{}\tnot known to the beacon chain, skipping",
+                        index,
+                        keypair.0.pk.as_hex_string()
+                    );
+                    None
+                }
+            })
+            .collect();
+    }
+
+    for (index, (voting_keypair, voting_path)) in voting_keypairs {
+        let voting_pubkey = voting_keypair.pk.as_hex_string();
+        let voting_password = random_password();
+        let voting_keystore = build_keystore(&voting_keypair, &voting_password, voting_path)?;
+
+        let mut builder = ValidatorDirBuilder::new(validator_dir.clone(), secrets_dir.clone())
+            .voting_keystore(voting_keystore, voting_password.as_bytes());
+
+        if matches.is_present(STORE_WITHDRAW_FLAG) {
+            let (withdrawal_keypair, withdrawal_path) = derive(index, KeyType::Withdrawal)?;
+            let withdrawal_password = random_password();
+            let withdrawal_keystore =
+                build_keystore(&withdrawal_keypair, &withdrawal_password, withdrawal_path)?;
+            builder = builder
+                .withdrawal_keystore(withdrawal_keystore, withdrawal_password.as_bytes())
+                .store_withdrawal_keystore(true);
+        }
+
+        match builder.build() {
+            Ok(_) => println!("{}\t{}", index, voting_pubkey),
+            Err(BuilderError::DirectoryAlreadyExists(_)) => {
+                eprintln!("{}\t{}\talready exists, skipping", index, voting_pubkey)
+            }
+            Err(e) => return Err(format!("Unable to build validator directory: {:?}", e)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the mnemonic from the file at `path`, or from stdin if `path` is `None`.
+fn read_mnemonic(path: Option<PathBuf>) -> Result<Mnemonic, String> {
+    let phrase = match path {
+        Some(path) => {
+            fs::read_to_string(&path).map_err(|e| format!("Unable to read {:?}: {:?}", path, e))?
+        }
+        None => {
+            eprint!("Enter the mnemonic phrase: ");
+            io::stderr()
+                .flush()
+                .map_err(|e| format!("Unable to flush stderr: {:?}", e))?;
+            let mut phrase = String::new();
+            io::stdin()
+                .lock()
+                .read_line(&mut phrase)
+                .map_err(|e| format!("Unable to read mnemonic from stdin: {:?}", e))?;
+            phrase
+        }
+    };
+
+    Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| format!("Unable to parse mnemonic: {:?}", e))
+}
+
+/// Returns, for each of `keypairs`, whether the validator is known to the beacon node at
+/// `beacon_server` (i.e., whether its deposit has been processed).
+fn known_validators<T: EthSpec>(
+    env: &mut Environment<T>,
+    beacon_server: String,
+    keypairs: &[(u32, (Keypair, String))],
+) -> Result<Vec<bool>, String> {
+    let beacon_node = RemoteBeaconNode::<T>::new(beacon_server)?;
+    let pubkeys = keypairs
+        .iter()
+        .map(|(_, (keypair, _))| keypair.pk.clone())
+        .collect::<Vec<_>>();
+
+    let validators = env
+        .runtime()
+        .block_on(
+            beacon_node
+                .http
+                .beacon()
+                .get_validators(pubkeys.clone(), None),
+        )
+        .map_err(|e| format!("Unable to query validators from the beacon node: {:?}", e))?;
+
+    Ok(pubkeys
+        .iter()
+        .map(|pubkey| {
+            validators.iter().any(|validator| {
+                validator.validator_index.is_some() && validator.pubkey == pubkey.clone().into()
+            })
+        })
+        .collect())
+}
+
+fn build_keystore(
+    keypair: &Keypair,
+    password: &PlainText,
+    path: String,
+) -> Result<Keystore, String> {
+    KeystoreBuilder::new(keypair, password.as_bytes(), path)
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Unable to build keystore: {:?}", e))
+}
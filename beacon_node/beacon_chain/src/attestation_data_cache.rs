@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{Hash256, Slot};
+
+/// The maximum number of distinct `AttestationData` stored. On mainnet there are at most 64
+/// committees per slot, so honest attestations for a few forks fit comfortably.
+const MAX_ENTRIES: usize = 4_096;
+
+/// Stores the committee of each `AttestationData` which has passed all of the checks in
+/// attestation verification which do not depend upon the attester. That is, the head block and
+/// target are known and the committee exists.
+///
+/// Many validators sign the same `AttestationData`, so later attestations with the same data only
+/// need their per-validator checks and signature verified.
+///
+/// Entries are keyed by the tree hash root of the `AttestationData`. They are only useful whilst
+/// attestations for their slot may still be propagated, after which they are pruned.
+pub struct AttestationDataCache {
+    items: HashMap<Hash256, (Slot, Arc<Vec<usize>>)>,
+}
+
+impl AttestationDataCache {
+    pub fn new() -> Self {
+        Self {
+            items: HashMap::new(),
+        }
+    }
+
+    /// Returns the committee for the `AttestationData` with the given root, if it has been
+    /// verified.
+    pub fn get(&self, data_root: &Hash256) -> Option<Arc<Vec<usize>>> {
+        self.items
+            .get(data_root)
+            .map(|(_, committee)| committee.clone())
+    }
+
+    /// Stores the `committee` for the verified `AttestationData` with the given root and slot.
+    ///
+    /// Entries for slots prior to `earliest_permissible_slot` are pruned first. If the cache is
+    /// still full the entry is not stored.
+    pub fn insert(
+        &mut self,
+        data_root: Hash256,
+        slot: Slot,
+        committee: Arc<Vec<usize>>,
+        earliest_permissible_slot: Slot,
+    ) {
+        self.items
+            .retain(|_, (slot, _)| *slot >= earliest_permissible_slot);
+
+        if slot >= earliest_permissible_slot && self.items.len() < MAX_ENTRIES {
+            self.items.insert(data_root, (slot, committee));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn insert_get_and_prune() {
+        let mut cache = AttestationDataCache::new();
+        let committee = Arc::new(vec![1, 2, 3]);
+
+        assert!(cache.get(&root(1)).is_none());
+
+        cache.insert(root(1), Slot::new(4), committee.clone(), Slot::new(0));
+        assert_eq!(cache.get(&root(1)), Some(committee.clone()));
+        assert!(cache.get(&root(2)).is_none());
+
+        // Entries prior to the earliest permissible slot are neither stored nor kept.
+        cache.insert(root(2), Slot::new(3), committee.clone(), Slot::new(4));
+        assert!(cache.get(&root(2)).is_none());
+
+        cache.insert(root(3), Slot::new(6), committee.clone(), Slot::new(5));
+        assert!(cache.get(&root(1)).is_none());
+        assert_eq!(cache.get(&root(3)), Some(committee));
+        assert_eq!(cache.items.len(), 1);
+    }
+
+    #[test]
+    fn bounded_size() {
+        let mut cache = AttestationDataCache::new();
+        let committee = Arc::new(vec![0]);
+
+        for i in 0..MAX_ENTRIES as u64 + 1 {
+            cache.insert(root(i), Slot::new(1), committee.clone(), Slot::new(0));
+        }
+
+        assert_eq!(cache.items.len(), MAX_ENTRIES);
+        assert!(cache.get(&root(MAX_ENTRIES as u64)).is_none());
+    }
+}
@@ -28,8 +28,7 @@
 
 use crate::{
     beacon_chain::{
-        ATTESTATION_CACHE_LOCK_TIMEOUT, HEAD_LOCK_TIMEOUT, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
-        VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT,
+        ATTESTATION_CACHE_LOCK_TIMEOUT, HEAD_LOCK_TIMEOUT, VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT,
     },
     metrics,
     observed_attestations::ObserveOutcome,
@@ -49,10 +48,11 @@ use state_processing::{
     },
 };
 use std::borrow::Cow;
+use std::sync::Arc;
 use tree_hash::TreeHash;
 use types::{
-    Attestation, BeaconCommittee, CommitteeIndex, Epoch, EthSpec, Hash256, IndexedAttestation,
-    RelativeEpoch, SelectionProof, SignedAggregateAndProof, Slot,
+    Attestation, BeaconCommittee, BeaconState, CommitteeIndex, Epoch, EthSpec, Hash256,
+    IndexedAttestation, RelativeEpoch, SelectionProof, SignedAggregateAndProof, Slot,
 };
 
 /// Returned when an attestation was not successfully verified. It might not have been verified for
@@ -100,6 +100,12 @@ pub enum Error {
     BadTargetEpoch,
     /// The target root of the attestation points to a block that we have not verified.
     UnknownTargetRoot(Hash256),
+    /// The target root of the attestation is not the ancestor of `beacon_block_root` at the start
+    /// of the target epoch, so the LMD vote is inconsistent with the FFG vote.
+    InvalidTargetRoot {
+        attestation: Hash256,
+        expected: Option<Hash256>,
+    },
     /// A signature on the attestation is invalid.
     InvalidSignature,
     /// There is no committee for the slot and committee index of this attestation and the
@@ -233,7 +239,7 @@ impl<T: BeaconChainTypes> VerifiedAggregatedAttestation<T> {
         // MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance).
         //
         // We do not queue future attestations for later processing.
-        verify_propagation_slot_range(chain, attestation)?;
+        verify_propagation_slot_range(chain, attestation, "aggregated_attestation")?;
 
         // Ensure the aggregated attestation has not already been seen locally.
         //
@@ -276,41 +282,38 @@ impl<T: BeaconChainTypes> VerifiedAggregatedAttestation<T> {
         //
         // Attestations must be for a known block. If the block is unknown, we simply drop the
         // attestation and do not delay consideration for later.
-        verify_head_block_is_known(chain, &attestation)?;
+        let committee = verify_attestation_data(chain, &attestation)?;
 
-        let indexed_attestation = map_attestation_committee(chain, attestation, |committee| {
-            // Note: this clones the signature which is known to be a relatively slow operation.
-            //
-            // Future optimizations should remove this clone.
-            let selection_proof =
-                SelectionProof::from(signed_aggregate.message.selection_proof.clone());
-
-            if !selection_proof
-                .is_aggregator(committee.committee.len(), &chain.spec)
-                .map_err(|e| Error::BeaconChainError(e.into()))?
-            {
-                return Err(Error::InvalidSelectionProof { aggregator_index });
-            }
+        // Note: this clones the signature which is known to be a relatively slow operation.
+        //
+        // Future optimizations should remove this clone.
+        let selection_proof =
+            SelectionProof::from(signed_aggregate.message.selection_proof.clone());
 
-            /*
-             * I have raised a PR that will likely get merged in v0.12.0:
-             *
-             * https://github.com/ethereum/eth2.0-specs/pull/1732
-             *
-             * If this PR gets merged, uncomment this code and remove the code below.
-             *
-            if !committee
-                .committee
-                .iter()
-                .any(|validator_index| *validator_index as u64 == aggregator_index)
-            {
-                return Err(Error::AggregatorNotInCommittee { aggregator_index });
-            }
-            */
+        if !selection_proof
+            .is_aggregator(committee.len(), &chain.spec)
+            .map_err(|e| Error::BeaconChainError(e.into()))?
+        {
+            return Err(Error::InvalidSelectionProof { aggregator_index });
+        }
+
+        /*
+         * I have raised a PR that will likely get merged in v0.12.0:
+         *
+         * https://github.com/ethereum/eth2.0-specs/pull/1732
+         *
+         * If this PR gets merged, uncomment this code and remove the code below.
+         *
+        if !committee
+            .iter()
+            .any(|validator_index| *validator_index as u64 == aggregator_index)
+        {
+            return Err(Error::AggregatorNotInCommittee { aggregator_index });
+        }
+        */
 
-            get_indexed_attestation(committee.committee, &attestation)
-                .map_err(|e| BeaconChainError::from(e).into())
-        })?;
+        let indexed_attestation = get_indexed_attestation(&committee, &attestation)
+            .map_err(|e| BeaconChainError::from(e))?;
 
         // Ensure the aggregator is in the attestation.
         //
@@ -395,7 +398,7 @@ impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
         // MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance).
         //
         // We do not queue future attestations for later processing.
-        verify_propagation_slot_range(chain, &attestation)?;
+        verify_propagation_slot_range(chain, &attestation, "unaggregated_attestation")?;
 
         // Check to ensure that the attestation is "unaggregated". I.e., it has exactly one
         // aggregation bit set.
@@ -406,9 +409,10 @@ impl<T: BeaconChainTypes> VerifiedUnaggregatedAttestation<T> {
 
         // Attestations must be for a known block. If the block is unknown, we simply drop the
         // attestation and do not delay consideration for later.
-        verify_head_block_is_known(chain, &attestation)?;
+        let committee = verify_attestation_data(chain, &attestation)?;
 
-        let indexed_attestation = obtain_indexed_attestation(chain, &attestation)?;
+        let indexed_attestation = get_indexed_attestation(&committee, &attestation)
+            .map_err(|e| BeaconChainError::from(e))?;
 
         let validator_index = *indexed_attestation
             .attesting_indices
@@ -569,6 +573,20 @@ impl<'a, T: BeaconChainTypes> ForkChoiceVerifiedAttestation<'a, T> {
             });
         }
 
+        // The target must be the ancestor of the attested block at the start of the target epoch.
+        //
+        // Most attestations in an epoch are to the same few blocks, so the ancestor is cached by
+        // fork choice rather than walking the chain for each attestation.
+        let expected_target_root = chain
+            .fork_choice
+            .target_root(indexed_attestation.data.beacon_block_root, target.epoch);
+        if expected_target_root != Some(target.root) {
+            return Err(Error::InvalidTargetRoot {
+                attestation: target.root,
+                expected: expected_target_root,
+            });
+        }
+
         // Note: we're not checking the "attestations can only affect the fork choice of subsequent
         // slots" part of the spec, we do this upstream.
 
@@ -609,19 +627,76 @@ fn verify_head_block_is_known<T: BeaconChainTypes>(
     }
 }
 
+/// Verifies that the head block and target of the `attestation` are known, returning the committee
+/// which the attestation is for.
+///
+/// These checks depend only upon `attestation.data`, which is shared by every member of the
+/// committee. The result is cached in `chain.attestation_data_cache`, so that the checks are only
+/// performed once for each distinct `AttestationData`.
+///
+/// The `attestation` must already have passed `verify_propagation_slot_range`.
+fn verify_attestation_data<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    attestation: &Attestation<T::EthSpec>,
+) -> Result<Arc<Vec<usize>>, Error> {
+    let data_root = attestation.data.tree_hash_root();
+
+    let cached = chain
+        .attestation_data_cache
+        .try_read_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+        .ok_or_else(|| BeaconChainError::AttestationCacheLockTimeout)?
+        .get(&data_root);
+    if let Some(committee) = cached {
+        metrics::inc_counter(&metrics::ATTESTATION_DATA_CACHE_HITS);
+        return Ok(committee);
+    }
+    metrics::inc_counter(&metrics::ATTESTATION_DATA_CACHE_MISSES);
+
+    verify_head_block_is_known(chain, attestation)?;
+
+    let committee = Arc::new(map_attestation_committee(
+        chain,
+        attestation,
+        |committee| Ok(committee.committee.to_vec()),
+    )?);
+
+    // Taking advantage of saturating subtraction on `Slot`.
+    let earliest_permissible_slot = chain
+        .slot_clock
+        .now_with_past_tolerance(chain.spec.maximum_gossip_clock_disparity())
+        .ok_or_else(|| BeaconChainError::UnableToReadSlot)?
+        - chain.spec.attestation_propagation_slot_range;
+
+    chain
+        .attestation_data_cache
+        .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+        .ok_or_else(|| BeaconChainError::AttestationCacheLockTimeout)?
+        .insert(
+            data_root,
+            attestation.data.slot,
+            committee.clone(),
+            earliest_permissible_slot,
+        );
+
+    Ok(committee)
+}
+
 /// Verify that the `attestation` is within the acceptable gossip propagation range, with reference
 /// to the current slot of the `chain`.
 ///
-/// Accounts for `MAXIMUM_GOSSIP_CLOCK_DISPARITY`.
+/// Accounts for `MAXIMUM_GOSSIP_CLOCK_DISPARITY`. Attestations which are only within the range due
+/// to this allowance are counted in a metric, labelled with `gossip_type`.
 pub fn verify_propagation_slot_range<T: BeaconChainTypes>(
     chain: &BeaconChain<T>,
     attestation: &Attestation<T::EthSpec>,
+    gossip_type: &str,
 ) -> Result<(), Error> {
     let attestation_slot = attestation.data.slot;
+    let clock_disparity = chain.spec.maximum_gossip_clock_disparity();
 
     let latest_permissible_slot = chain
         .slot_clock
-        .now_with_future_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+        .now_with_future_tolerance(clock_disparity)
         .ok_or_else(|| BeaconChainError::UnableToReadSlot)?;
     if attestation_slot > latest_permissible_slot {
         return Err(Error::FutureSlot {
@@ -633,9 +708,9 @@ pub fn verify_propagation_slot_range<T: BeaconChainTypes>(
     // Taking advantage of saturating subtraction on `Slot`.
     let earliest_permissible_slot = chain
         .slot_clock
-        .now_with_past_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+        .now_with_past_tolerance(clock_disparity)
         .ok_or_else(|| BeaconChainError::UnableToReadSlot)?
-        - T::EthSpec::slots_per_epoch();
+        - chain.spec.attestation_propagation_slot_range;
     if attestation_slot < earliest_permissible_slot {
         return Err(Error::PastSlot {
             attestation_slot,
@@ -643,6 +718,14 @@ pub fn verify_propagation_slot_range<T: BeaconChainTypes>(
         });
     }
 
+    if let Some(current_slot) = chain.slot_clock.now() {
+        if attestation_slot > current_slot
+            || attestation_slot + chain.spec.attestation_propagation_slot_range < current_slot
+        {
+            metrics::inc_counter_vec(&metrics::GOSSIP_CLOCK_DISPARITY_TOLERATED, &[gossip_type]);
+        }
+    }
+
     Ok(())
 }
 
@@ -796,6 +879,16 @@ where
         .block_slot_and_state_root(&target.root)
         .ok_or_else(|| Error::UnknownTargetRoot(target.root))?;
 
+    // The shuffling cache is keyed by the block which determined the shuffling, so that any
+    // target which descends from it may share the same committees.
+    //
+    // The decision block may be absent from fork choice if it has been pruned, in which case it is
+    // read from the target state below.
+    let decision_slot = BeaconState::<T::EthSpec>::shuffling_decision_slot(attestation_epoch);
+    let decision_root = chain
+        .fork_choice
+        .ancestor_at_slot(&target.root, decision_slot);
+
     // Obtain the shuffling cache, timing how long we wait.
     let cache_wait_timer =
         metrics::start_timer(&metrics::ATTESTATION_PROCESSING_SHUFFLING_CACHE_WAIT_TIMES);
@@ -807,7 +900,9 @@ where
 
     metrics::stop_timer(cache_wait_timer);
 
-    if let Some(committee_cache) = shuffling_cache.get(attestation_epoch, target.root) {
+    if let Some(committee_cache) =
+        decision_root.and_then(|root| shuffling_cache.get(attestation_epoch, root))
+    {
         committee_cache
             .get_beacon_committee(attestation.data.slot, attestation.data.index)
             .map(map_fn)
@@ -865,11 +960,16 @@ where
             .committee_cache(relative_epoch)
             .map_err(|e| BeaconChainError::from(e))?;
 
-        chain
-            .shuffling_cache
-            .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
-            .ok_or_else(|| BeaconChainError::AttestationCacheLockTimeout)?
-            .insert(attestation_epoch, target.root, committee_cache);
+        // States at the genesis slot have no decision root, their caches are not worth sharing.
+        if let Some(decision_root) =
+            decision_root.or_else(|| state.shuffling_decision_root(relative_epoch).ok())
+        {
+            chain
+                .shuffling_cache
+                .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+                .ok_or_else(|| BeaconChainError::AttestationCacheLockTimeout)?
+                .insert(attestation_epoch, decision_root, committee_cache);
+        }
 
         metrics::stop_timer(committee_building_timer);
 
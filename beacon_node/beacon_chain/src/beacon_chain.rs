@@ -1,3 +1,4 @@
+use crate::attestation_data_cache::AttestationDataCache;
 use crate::attestation_verification::{
     Error as AttestationError, ForkChoiceVerifiedAttestation, IntoForkChoiceVerifiedAttestation,
     VerifiedAggregatedAttestation, VerifiedUnaggregatedAttestation,
@@ -6,25 +7,30 @@ use crate::block_verification::{
     check_block_relevancy, get_block_root, signature_verify_chain_segment, BlockError,
     FullyVerifiedBlock, GossipVerifiedBlock, IntoFullyVerifiedBlock,
 };
+use crate::chain_health::ChainHealth;
+use crate::divergence_guard::DivergenceGuard;
 use crate::errors::{BeaconChainError as Error, BlockProductionError};
 use crate::eth1_chain::{Eth1Chain, Eth1ChainBackend};
-use crate::events::{EventHandler, EventKind};
+use crate::events::{EventBus, EventHandler, EventKind, Reorg};
 use crate::fork_choice::{Error as ForkChoiceError, ForkChoice};
 use crate::head_tracker::HeadTracker;
 use crate::metrics;
 use crate::migrate::Migrate;
-use crate::naive_aggregation_pool::{Error as NaiveAggregationError, NaiveAggregationPool};
+use crate::naive_aggregation_pool::{
+    Error as NaiveAggregationError, NaiveAggregationPool, PersistedNaiveAggregationPool,
+};
 use crate::observed_attestations::{Error as AttestationObservationError, ObservedAttestations};
 use crate::observed_attesters::{ObservedAggregators, ObservedAttesters};
 use crate::observed_block_producers::ObservedBlockProducers;
 use crate::persisted_beacon_chain::PersistedBeaconChain;
-use crate::shuffling_cache::ShufflingCache;
+use crate::shuffling_cache::{PersistedShufflingCache, ShufflingCache};
+use crate::skip_slot_cache::SkipSlotCache;
 use crate::snapshot_cache::SnapshotCache;
 use crate::timeout_rw_lock::TimeoutRwLock;
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
 use crate::BeaconSnapshot;
 use itertools::process_results;
-use operation_pool::{OperationPool, PersistedOperationPool};
+use operation_pool::{AttestationPacking, OperationPool, PersistedOperationPool};
 use slog::{crit, debug, error, info, trace, warn, Logger};
 use slot_clock::SlotClock;
 use state_processing::per_block_processing::errors::{
@@ -40,7 +46,8 @@ use std::io::prelude::*;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use store::iter::{BlockRootsIterator, ParentRootBlockIterator, StateRootsIterator};
-use store::{Error as DBError, HotColdDB};
+use store::{Error as DBError, HotColdDB, StoreItem, StoreOp};
+use tokio::sync::broadcast;
 use types::*;
 
 // Text included in blocks.
@@ -68,6 +75,9 @@ pub const BEACON_CHAIN_DB_KEY: [u8; 32] = [0; 32];
 pub const OP_POOL_DB_KEY: [u8; 32] = [0; 32];
 pub const ETH1_CACHE_DB_KEY: [u8; 32] = [0; 32];
 pub const FORK_CHOICE_DB_KEY: [u8; 32] = [0; 32];
+pub const SHUFFLING_CACHE_DB_KEY: [u8; 32] = [0; 32];
+pub const DEPOSIT_SNAPSHOT_DB_KEY: [u8; 32] = [0; 32];
+pub const NAIVE_AGGREGATION_POOL_DB_KEY: [u8; 32] = [0; 32];
 
 /// The result of a chain segment processing.
 #[derive(Debug)]
@@ -82,11 +92,6 @@ pub enum ChainSegmentResult {
     },
 }
 
-/// The accepted clock drift for nodes gossiping blocks and attestations (spec v0.11.0). See:
-///
-/// https://github.com/ethereum/eth2.0-specs/blob/v0.11.0/specs/phase0/p2p-interface.md#configuration
-pub const MAXIMUM_GOSSIP_CLOCK_DISPARITY: Duration = Duration::from_millis(500);
-
 #[derive(Debug, PartialEq)]
 pub enum AttestationProcessingOutcome {
     Processed,
@@ -198,16 +203,27 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub fork_choice: ForkChoice<T>,
     /// A handler for events generated by the beacon chain.
     pub event_handler: T::EventHandler,
+    /// Distributes events generated by the beacon chain to in-process subscribers.
+    pub(crate) event_bus: EventBus<T::EthSpec>,
     /// Used to track the heads of the beacon chain.
     pub(crate) head_tracker: Arc<HeadTracker>,
     /// A cache dedicated to block processing.
     pub(crate) snapshot_cache: TimeoutRwLock<SnapshotCache<T::EthSpec>>,
     /// Caches the shuffling for a given epoch and state root.
     pub(crate) shuffling_cache: TimeoutRwLock<ShufflingCache>,
+    /// Caches the committee of each recently verified `AttestationData`.
+    pub(crate) attestation_data_cache: TimeoutRwLock<AttestationDataCache>,
+    /// Caches the head state after it has been advanced through skip slots.
+    pub(crate) skip_slot_cache: TimeoutRwLock<SkipSlotCache<T::EthSpec>>,
     /// Caches a map of `validator_index -> validator_pubkey`.
     pub(crate) validator_pubkey_cache: TimeoutRwLock<ValidatorPubkeyCache>,
     /// A list of any hard-coded forks that have been disabled.
     pub disabled_forks: Vec<String>,
+    /// Halts block production and attestation if the chain conflicts with the weak subjectivity
+    /// checkpoint or trusted peers.
+    pub(crate) divergence_guard: DivergenceGuard,
+    /// Tracks information for the chain health metrics which are updated each slot.
+    pub(crate) chain_health: ChainHealth,
     /// Logging to CLI, etc.
     pub(crate) log: Logger,
 }
@@ -275,6 +291,23 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Persists the unaggregated attestations in `self.naive_aggregation_pool` to disk, so that
+    /// they may still be aggregated and included in blocks after a restart.
+    pub fn persist_naive_aggregation_pool(&self) -> Result<(), Error> {
+        let timer = metrics::start_timer(&metrics::PERSIST_NAIVE_AGGREGATION_POOL);
+
+        self.store.put_item(
+            &Hash256::from_slice(&NAIVE_AGGREGATION_POOL_DB_KEY),
+            &PersistedNaiveAggregationPool::from_naive_aggregation_pool(
+                &self.naive_aggregation_pool,
+            ),
+        )?;
+
+        metrics::stop_timer(timer);
+
+        Ok(())
+    }
+
     /// Persists `self.eth1_chain` and its caches to disk.
     pub fn persist_eth1_cache(&self) -> Result<(), Error> {
         let timer = metrics::start_timer(&metrics::PERSIST_OP_POOL);
@@ -291,6 +324,54 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Persists a snapshot of the first `deposit_index` deposits, so that it can be served to
+    /// nodes which start their deposit cache from a snapshot.
+    ///
+    /// The `deposit_index` must be the `eth1_deposit_index` of the finalized state, since a node
+    /// started from the snapshot is unable to produce proofs for the deposits within it.
+    ///
+    /// Does nothing if there is no eth1 backend, or it uses dummy data.
+    fn persist_deposit_snapshot(&self, deposit_index: u64) -> Result<(), Error> {
+        let snapshot = match &self.eth1_chain {
+            Some(eth1_chain) => eth1_chain.get_deposit_snapshot(deposit_index)?,
+            None => None,
+        };
+
+        if let Some(snapshot) = snapshot {
+            self.store
+                .put_item(&Hash256::from_slice(&DEPOSIT_SNAPSHOT_DB_KEY), &snapshot)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the most recent snapshot of the finalized deposit tree, if any.
+    pub fn get_deposit_snapshot(&self) -> Result<Option<DepositTreeSnapshot>, Error> {
+        Ok(self
+            .store
+            .get_item(&Hash256::from_slice(&DEPOSIT_SNAPSHOT_DB_KEY))?)
+    }
+
+    /// Persists the committee caches in `self.shuffling_cache` to disk, so that attestation
+    /// verification does not need to rebuild them after a restart.
+    pub fn persist_shuffling_cache(&self) -> Result<(), Error> {
+        let timer = metrics::start_timer(&metrics::PERSIST_SHUFFLING_CACHE);
+
+        let persisted = PersistedShufflingCache::from_shuffling_cache(
+            &self
+                .shuffling_cache
+                .try_read_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+                .ok_or_else(|| Error::AttestationCacheLockTimeout)?,
+        );
+
+        self.store
+            .put_item(&Hash256::from_slice(&SHUFFLING_CACHE_DB_KEY), &persisted)?;
+
+        metrics::stop_timer(timer);
+
+        Ok(())
+    }
+
     /// Returns the slot _right now_ according to `self.slot_clock`. Returns `Err` if the slot is
     /// unavailable.
     ///
@@ -454,6 +535,60 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(self.store.get_state(state_root, slot)?)
     }
 
+    /// Ensures that the committee cache for `relative_epoch` is built on `state`.
+    ///
+    /// If another state with the same shuffling has already built the cache, it is copied from
+    /// `self.shuffling_cache` rather than recomputed. Otherwise the cache is built and added to
+    /// the shuffling cache so that it may be reused in turn.
+    pub fn attach_committee_cache(
+        &self,
+        state: &mut BeaconState<T::EthSpec>,
+        relative_epoch: RelativeEpoch,
+    ) -> Result<(), Error> {
+        if state.committee_cache(relative_epoch).is_ok() {
+            return Ok(());
+        }
+
+        // States at the genesis slot have no decision root, but their caches are cheap to build.
+        let decision_root = match state.shuffling_decision_root(relative_epoch) {
+            Ok(root) => root,
+            Err(_) => return Ok(state.build_committee_cache(relative_epoch, &self.spec)?),
+        };
+        let epoch = relative_epoch.into_epoch(state.current_epoch());
+
+        let cached = self
+            .shuffling_cache
+            .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+            .ok_or_else(|| Error::AttestationCacheLockTimeout)?
+            .get(epoch, decision_root)
+            .cloned();
+
+        if let Some(committee_cache) = cached {
+            state.set_committee_cache(relative_epoch, committee_cache)?;
+        } else {
+            // Build the cache without holding the lock on the shuffling cache.
+            state.build_committee_cache(relative_epoch, &self.spec)?;
+
+            self.shuffling_cache
+                .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+                .ok_or_else(|| Error::AttestationCacheLockTimeout)?
+                .insert(epoch, decision_root, state.committee_cache(relative_epoch)?);
+        }
+
+        Ok(())
+    }
+
+    /// Ensures that the previous, current and next epoch committee caches are built on `state`,
+    /// reusing caches from `self.shuffling_cache` where possible.
+    pub fn attach_all_committee_caches(
+        &self,
+        state: &mut BeaconState<T::EthSpec>,
+    ) -> Result<(), Error> {
+        self.attach_committee_cache(state, RelativeEpoch::Previous)?;
+        self.attach_committee_cache(state, RelativeEpoch::Current)?;
+        self.attach_committee_cache(state, RelativeEpoch::Next)
+    }
+
     /// Returns a `Checkpoint` representing the head block and state. Contains the "best block";
     /// the head of the canonical `BeaconChain`.
     ///
@@ -508,7 +643,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         slot: Slot,
         config: StateSkipConfig,
     ) -> Result<BeaconState<T::EthSpec>, Error> {
-        let head_state = self.head()?.beacon_state;
+        let head = self.head()?;
+        let head_state = head.beacon_state;
 
         match slot.cmp(&head_state.slot) {
             Ordering::Equal => Ok(head_state),
@@ -527,7 +663,22 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 let max_task_runtime = Duration::from_millis(self.spec.milliseconds_per_slot);
 
                 let head_state_slot = head_state.slot;
-                let mut state = head_state;
+
+                // Start from the head state as it was advanced in a previous slot, if possible.
+                let mut state = match self
+                    .skip_slot_cache
+                    .try_read_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+                    .and_then(|cache| cache.get_cloned(head.beacon_block_root, slot))
+                {
+                    Some(state) => {
+                        metrics::inc_counter(&metrics::SKIP_SLOT_CACHE_HITS);
+                        state
+                    }
+                    None => {
+                        metrics::inc_counter(&metrics::SKIP_SLOT_CACHE_MISSES);
+                        head_state
+                    }
+                };
 
                 let skip_state_root = match config {
                     StateSkipConfig::WithStateRoots => None,
@@ -746,6 +897,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         slot: Slot,
         index: CommitteeIndex,
     ) -> Result<Attestation<T::EthSpec>, Error> {
+        if let Some(reason) = self.divergence_guard.halted() {
+            return Err(Error::ChainDiverged(reason));
+        }
+
         // Note: we're taking a lock on the head. The work involved here should be trivial enough
         // that the lock should not be held for long.
         let head = self
@@ -955,6 +1110,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             }
         };
 
+        self.publish_attestation_imported(attestation);
+
         Ok(unaggregated_attestation)
     }
 
@@ -989,9 +1146,40 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 .map_err(Error::from)?;
         }
 
+        self.publish_attestation_imported(signed_aggregate.attestation());
+
         Ok(signed_aggregate)
     }
 
+    /// Publishes a `BeaconAttestationImported` event to the subscribers of the event bus.
+    ///
+    /// The event is not registered with the `event_handler`, since serializing every attestation
+    /// for the websocket API would be too costly.
+    fn publish_attestation_imported(&self, attestation: &Attestation<T::EthSpec>) {
+        if self.event_bus.has_subscribers() {
+            self.event_bus
+                .publish(&EventKind::BeaconAttestationImported {
+                    attestation: Box::new(attestation.clone()),
+                });
+        }
+    }
+
+    /// Registers `kind` with the `event_handler` and publishes it to the subscribers of the event
+    /// bus.
+    fn register_event(&self, kind: EventKind<T::EthSpec>) {
+        self.event_bus.publish(&kind);
+        let _ = self.event_handler.register(kind);
+    }
+
+    /// Returns a receiver for the events generated by the beacon chain from now on: head changes,
+    /// re-orgs, imported blocks and attestations, justification and finalization.
+    ///
+    /// A receiver which falls more than `events::EVENT_BUS_CAPACITY` events behind misses the
+    /// oldest events.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<EventKind<T::EthSpec>> {
+        self.event_bus.subscribe()
+    }
+
     /// Check that the shuffling at `block_root` is equal to one of the shufflings of `state`.
     ///
     /// The `target_epoch` argument determines which shuffling to check compatibility with, it
@@ -1366,7 +1554,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 // Increment the Prometheus counter for block processing successes.
                 metrics::inc_counter(&metrics::BLOCK_PROCESSING_SUCCESSES);
 
-                let _ = self.event_handler.register(EventKind::BeaconBlockImported {
+                self.register_event(EventKind::BeaconBlockImported {
                     block_root: block_root,
                     block: Box::new(block),
                 });
@@ -1382,7 +1570,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     "error" => format!("{:?}", e),
                 );
 
-                let _ = self.event_handler.register(EventKind::BeaconBlockRejected {
+                self.register_event(EventKind::BeaconBlockRejected {
                     reason: format!("Internal error: {:?}", e),
                     block: Box::new(block),
                 });
@@ -1397,7 +1585,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     "reason" => format!("{:?}", other),
                 );
 
-                let _ = self.event_handler.register(EventKind::BeaconBlockRejected {
+                self.register_event(EventKind::BeaconBlockRejected {
                     reason: format!("Invalid block: {:?}", other),
                     block: Box::new(block),
                 });
@@ -1427,6 +1615,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let state = fully_verified_block.state;
         let parent_block = fully_verified_block.parent_block;
         let intermediate_states = fully_verified_block.intermediate_states;
+        let epoch_summaries = fully_verified_block.epoch_summaries;
 
         let attestation_observation_timer =
             metrics::start_timer(&metrics::BLOCK_PROCESSING_ATTESTATION_OBSERVATION);
@@ -1471,17 +1660,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 .ok_or_else(|| Error::AttestationCacheLockTimeout)?;
 
             let committee_cache = state.committee_cache(RelativeEpoch::Current)?;
+            let decision_root = state.shuffling_decision_root(RelativeEpoch::Current)?;
 
-            let epoch_start_slot = state
-                .current_epoch()
-                .start_slot(T::EthSpec::slots_per_epoch());
-            let target_root = if state.slot == epoch_start_slot {
-                block_root
-            } else {
-                *state.get_block_root(epoch_start_slot)?
-            };
-
-            shuffling_cache.insert(state.current_epoch(), target_root, committee_cache);
+            shuffling_cache.insert(state.current_epoch(), decision_root, committee_cache);
         }
 
         // Register the new block with the fork choice service.
@@ -1506,18 +1687,26 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         let db_write_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_DB_WRITE);
 
-        // Store all the states between the parent block state and this block's slot before storing
-        // the final state.
-        intermediate_states.commit(&*self.store)?;
-
-        // Store the block and state.
-        // NOTE: we store the block *after* the state to guard against inconsistency in the event of
-        // a crash, as states are usually looked up from blocks, not the other way around. A better
-        // solution would be to use a database transaction (once our choice of database and API
-        // settles down).
+        // Store the block, its state, all the states between the parent block state and this
+        // block's slot, and the fork choice which now includes the block in a single atomic write.
+        // This ensures that a crash can never leave a block without its state, or fork choice
+        // referencing a block which is not in the database.
         // See: https://github.com/sigp/lighthouse/issues/692
-        self.store.put_state(&block.state_root, &state)?;
-        self.store.put_block(&block_root, signed_block.clone())?;
+        let mut ops = intermediate_states.as_store_ops();
+        ops.push(StoreOp::PutState(block.state_root.into(), &state));
+        ops.push(StoreOp::PutBlock(block_root.into(), signed_block.clone()));
+        for (state_root, epoch_summary) in &epoch_summaries {
+            ops.push(StoreOp::KeyValueOp(
+                epoch_summary.as_kv_store_op(*state_root),
+            ));
+        }
+        ops.push(StoreOp::KeyValueOp(
+            self.fork_choice
+                .as_ssz_container()
+                .as_kv_store_op(Hash256::from_slice(&FORK_CHOICE_DB_KEY)),
+        ));
+        self.store.do_atomically(&ops)?;
+        drop(ops);
 
         let parent_root = block.parent_root;
         let slot = block.slot;
@@ -1547,6 +1736,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         metrics::stop_timer(db_write_timer);
 
         metrics::inc_counter(&metrics::BLOCK_PROCESSING_SUCCESSES);
+        self.chain_health.observe_block_import();
 
         Ok(block_root)
     }
@@ -1555,16 +1745,27 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///
     /// The produced block will not be inherently valid, it must be signed by a block producer.
     /// Block signing is out of the scope of this function and should be done by a separate program.
+    ///
+    /// The block's graffiti is `validator_graffiti`, or the default `GRAFFITI` if it is `None`.
     pub fn produce_block(
         &self,
         randao_reveal: Signature,
         slot: Slot,
+        validator_graffiti: Option<[u8; 32]>,
     ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
+        let head_slot = self
+            .head_info()
+            .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?
+            .slot;
+
+        // Building upon the head, take the state at the block's own slot so that a head state
+        // already advanced through the skip slots by `advance_head_state` is used as-is.
+        let state_slot = if slot > head_slot { slot } else { slot - 1 };
         let state = self
-            .state_at_slot(slot - 1, StateSkipConfig::WithStateRoots)
+            .state_at_slot(state_slot, StateSkipConfig::WithStateRoots)
             .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?;
 
-        self.produce_block_on_state(state, slot, randao_reveal)
+        self.produce_block_on_state(state, slot, randao_reveal, validator_graffiti)
     }
 
     /// Produce a block for some `slot` upon the given `state`.
@@ -1580,10 +1781,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         mut state: BeaconState<T::EthSpec>,
         produce_at_slot: Slot,
         randao_reveal: Signature,
+        validator_graffiti: Option<[u8; 32]>,
     ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
         metrics::inc_counter(&metrics::BLOCK_PRODUCTION_REQUESTS);
         let timer = metrics::start_timer(&metrics::BLOCK_PRODUCTION_TIMES);
 
+        if let Some(reason) = self.divergence_guard.halted() {
+            return Err(BlockProductionError::ChainDiverged(reason));
+        }
+
         let eth1_chain = self
             .eth1_chain
             .as_ref()
@@ -1607,8 +1813,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             state.latest_block_header.canonical_root()
         };
 
-        let mut graffiti: [u8; 32] = [0; 32];
-        graffiti.copy_from_slice(GRAFFITI.as_bytes());
+        let graffiti = validator_graffiti.unwrap_or_else(|| {
+            let mut graffiti: [u8; 32] = [0; 32];
+            graffiti.copy_from_slice(GRAFFITI.as_bytes());
+            graffiti
+        });
 
         let (proposer_slashings, attester_slashings) =
             self.op_pool.get_slashings(&state, &self.spec);
@@ -1633,6 +1842,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 })
         };
 
+        let (attestations, attestation_packing) = self
+            .op_pool
+            .get_attestations_with_packing(&state, attestation_filter, &self.spec)
+            .map_err(BlockProductionError::OpPoolError)?;
+
         let mut block = SignedBeaconBlock {
             message: BeaconBlock {
                 slot: state.slot,
@@ -1645,11 +1859,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     graffiti,
                     proposer_slashings: proposer_slashings.into(),
                     attester_slashings: attester_slashings.into(),
-                    attestations: self
-                        .op_pool
-                        .get_attestations(&state, attestation_filter, &self.spec)
-                        .map_err(BlockProductionError::OpPoolError)?
-                        .into(),
+                    attestations: attestations.into(),
                     deposits,
                     voluntary_exits: self.op_pool.get_voluntary_exits(&state, &self.spec).into(),
                 },
@@ -1681,9 +1891,106 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             "slot" => block.message.slot
         );
 
+        self.report_block_packing(&block.message, attestation_packing);
+
         Ok((block.message, state))
     }
 
+    /// Logs and exports how full a locally produced block is relative to the contents of the op
+    /// pool, along with the estimated proposer reward for its attestations.
+    fn report_block_packing(
+        &self,
+        block: &BeaconBlock<T::EthSpec>,
+        attestation_packing: AttestationPacking,
+    ) {
+        let body = &block.body;
+        let operations = [
+            (
+                "attestations",
+                attestation_packing.included,
+                attestation_packing.available,
+            ),
+            (
+                "proposer_slashings",
+                body.proposer_slashings.len(),
+                self.op_pool.num_proposer_slashings(),
+            ),
+            (
+                "attester_slashings",
+                body.attester_slashings.len(),
+                self.op_pool.num_attester_slashings(),
+            ),
+            (
+                "voluntary_exits",
+                body.voluntary_exits.len(),
+                self.op_pool.num_voluntary_exits(),
+            ),
+        ];
+
+        for (operation, included, available) in operations.iter() {
+            if let Some(gauge) =
+                metrics::get_int_gauge(&metrics::BLOCK_PACKING_INCLUDED, &[operation])
+            {
+                gauge.set(*included as i64);
+            }
+            if let Some(gauge) =
+                metrics::get_int_gauge(&metrics::BLOCK_PACKING_AVAILABLE, &[operation])
+            {
+                gauge.set(*available as i64);
+            }
+        }
+        metrics::set_gauge(
+            &metrics::BLOCK_PACKING_ATTESTATION_REWARD,
+            attestation_packing.included_reward as i64,
+        );
+        metrics::set_gauge(
+            &metrics::BLOCK_PACKING_AVAILABLE_ATTESTATION_REWARD,
+            attestation_packing.available_reward as i64,
+        );
+
+        info!(
+            self.log,
+            "Block packing";
+            "slot" => block.slot,
+            "attestations" => format!("{}/{}", attestation_packing.included, attestation_packing.available),
+            "attestation_reward_gwei" => attestation_packing.included_reward,
+            "available_attestation_reward_gwei" => attestation_packing.available_reward,
+            "proposer_slashings" => format!("{}/{}", operations[1].1, operations[1].2),
+            "attester_slashings" => format!("{}/{}", operations[2].1, operations[2].2),
+            "voluntary_exits" => format!("{}/{}", operations[3].1, operations[3].2),
+        );
+    }
+
+    /// Returns the root and slot of the most recent block which is an ancestor of both
+    /// `old_head_root` and `new_head_root`, where `new_head_state` is the state of the new head.
+    ///
+    /// Returns `None` if the ancestor is further back than the block roots stored in
+    /// `new_head_state`.
+    fn find_common_ancestor(
+        &self,
+        old_head_root: Hash256,
+        new_head_root: Hash256,
+        new_head_state: &BeaconState<T::EthSpec>,
+    ) -> Result<Option<(Hash256, Slot)>, Error> {
+        for pair in ParentRootBlockIterator::new(&*self.store, old_head_root) {
+            let (block_root, block) = pair?;
+            let slot = block.slot();
+
+            if block_root == new_head_root {
+                return Ok(Some((block_root, slot)));
+            }
+            if slot < new_head_state.slot {
+                match new_head_state.get_block_root(slot) {
+                    Ok(root) if *root == block_root => return Ok(Some((block_root, slot))),
+                    Ok(_) => {}
+                    Err(_) => return Ok(None),
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Execute the fork choice algorithm and enthrone the result as the canonical head.
     pub fn fork_choice(&self) -> Result<(), Error> {
         metrics::inc_counter(&metrics::FORK_CHOICE_REQUESTS);
@@ -1741,10 +2048,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .and_then(|mut snapshot| {
                 // Regardless of where we got the state from, attempt to build the committee
                 // caches.
-                snapshot
-                    .beacon_state
-                    .build_all_committee_caches(&self.spec)
-                    .map_err(Into::into)
+                self.attach_all_committee_caches(&mut snapshot.beacon_state)
                     .map(|()| snapshot)
             })?;
 
@@ -1763,15 +2067,56 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         if is_reorg {
             metrics::inc_counter(&metrics::FORK_CHOICE_REORG_COUNT);
-            warn!(
-                self.log,
-                "Beacon chain re-org";
-                "previous_head" => format!("{}", current_head.block_root),
-                "previous_slot" => current_head.slot,
-                "new_head_parent" => format!("{}", new_head.beacon_block.parent_root()),
-                "new_head" => format!("{}", beacon_block_root),
-                "new_slot" => new_head.beacon_block.slot()
-            );
+
+            match self.find_common_ancestor(
+                current_head.block_root,
+                beacon_block_root,
+                &new_head.beacon_state,
+            ) {
+                Ok(Some((common_ancestor_root, common_ancestor_slot))) => {
+                    let depth = current_head.slot.as_u64() - common_ancestor_slot.as_u64();
+                    metrics::inc_counter_vec(
+                        &metrics::FORK_CHOICE_REORG_DEPTH,
+                        &[metrics::reorg_depth_bucket(depth)],
+                    );
+                    warn!(
+                        self.log,
+                        "Beacon chain re-org";
+                        "previous_head" => format!("{}", current_head.block_root),
+                        "previous_slot" => current_head.slot,
+                        "new_head_parent" => format!("{}", new_head.beacon_block.parent_root()),
+                        "new_head" => format!("{}", beacon_block_root),
+                        "new_slot" => new_head.beacon_block.slot(),
+                        "common_ancestor" => format!("{}", common_ancestor_root),
+                        "common_ancestor_slot" => common_ancestor_slot,
+                        "depth" => depth,
+                    );
+                    self.register_event(EventKind::BeaconChainReorg(Reorg {
+                        depth,
+                        common_ancestor_root,
+                        common_ancestor_slot,
+                        previous_head_root: current_head.block_root,
+                        previous_head_slot: current_head.slot,
+                        new_head_root: beacon_block_root,
+                        new_head_slot: new_head.beacon_block.slot(),
+                    }));
+                }
+                other => {
+                    if let Err(e) = other {
+                        debug!(self.log, "Unable to find re-org ancestor"; "error" => format!("{:?}", e));
+                    }
+                    warn!(
+                        self.log,
+                        "Beacon chain re-org";
+                        "previous_head" => format!("{}", current_head.block_root),
+                        "previous_slot" => current_head.slot,
+                        "new_head_parent" => format!("{}", new_head.beacon_block.parent_root()),
+                        "new_head" => format!("{}", beacon_block_root),
+                        "new_slot" => new_head.beacon_block.slot(),
+                        "common_ancestor" => "unknown",
+                    );
+                }
+            }
         } else {
             debug!(
                 self.log,
@@ -1785,6 +2130,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             );
         };
 
+        let old_justified_epoch = current_head.current_justified_checkpoint.epoch;
+        let new_justified_checkpoint = new_head.beacon_state.current_justified_checkpoint.clone();
         let old_finalized_epoch = current_head.finalized_checkpoint.epoch;
         let new_finalized_epoch = new_head.beacon_state.finalized_checkpoint.epoch;
         let finalized_root = new_head.beacon_state.finalized_checkpoint.root;
@@ -1833,6 +2180,19 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             });
 
         if new_finalized_epoch != old_finalized_epoch {
+            // A failure to check is not a reason to skip the finalization tasks.
+            if let Err(e) = self.verify_weak_subjectivity_checkpoint(Checkpoint {
+                epoch: new_finalized_epoch,
+                root: finalized_root,
+            }) {
+                error!(
+                    self.log,
+                    "Unable to check the weak subjectivity checkpoint";
+                    "error" => format!("{:?}", e),
+                    "finalized_epoch" => new_finalized_epoch,
+                );
+            }
+
             self.after_finalization(
                 old_finalized_epoch,
                 finalized_root,
@@ -1840,7 +2200,14 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             )?;
         }
 
-        let _ = self.event_handler.register(EventKind::BeaconHeadChanged {
+        if new_justified_checkpoint.epoch > old_justified_epoch {
+            self.register_event(EventKind::BeaconJustification {
+                epoch: new_justified_checkpoint.epoch,
+                root: new_justified_checkpoint.root,
+            });
+        }
+
+        self.register_event(EventKind::BeaconHeadChanged {
             reorg: is_reorg,
             previous_head_beacon_block_root: current_head.block_root,
             current_head_beacon_block_root: beacon_block_root,
@@ -1856,6 +2223,142 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         trace!(self.log, "Running beacon chain per slot tasks");
         if let Some(slot) = self.slot_clock.now() {
             self.naive_aggregation_pool.prune(slot);
+            self.chain_health.update_metrics(self, slot);
+        }
+    }
+
+    /// Advances the head state through any skip slots up to the present slot, storing the result
+    /// in `self.skip_slot_cache` so that block production at the present slot needs no slot
+    /// processing, and at the next slot only needs to process a single slot.
+    ///
+    /// Intended to be run at the start of each slot. To avoid excessive work whilst syncing, the
+    /// head state is only advanced from scratch if it is no more than an epoch old. A state which
+    /// has already been advanced is always advanced further.
+    pub fn advance_head_state(&self) -> Result<(), Error> {
+        let slot = self.slot()?;
+        let head_info = self.head_info()?;
+
+        if head_info.slot >= slot {
+            return Ok(());
+        }
+
+        let cached = self
+            .skip_slot_cache
+            .try_read_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+            .ok_or_else(|| Error::SkipSlotCacheLockTimeout)?
+            .get_cloned(head_info.block_root, slot);
+
+        let mut state = match cached {
+            Some(state) => state,
+            None if slot <= head_info.slot + T::EthSpec::slots_per_epoch() => {
+                self.head()?.beacon_state
+            }
+            None => return Ok(()),
+        };
+
+        if state.slot >= slot {
+            return Ok(());
+        }
+
+        let timer = metrics::start_timer(&metrics::ADVANCE_HEAD_STATE_TIMES);
+
+        while state.slot < slot {
+            per_slot_processing(&mut state, None, &self.spec)?;
+        }
+
+        metrics::stop_timer(timer);
+
+        debug!(
+            self.log,
+            "Advanced head state";
+            "head_slot" => head_info.slot,
+            "state_slot" => state.slot,
+        );
+
+        self.skip_slot_cache
+            .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+            .ok_or_else(|| Error::SkipSlotCacheLockTimeout)?
+            .insert(head_info.block_root, state);
+
+        Ok(())
+    }
+
+    /// Halts block production and attestation if the chain of the given `finalized` checkpoint
+    /// does not include the configured weak subjectivity checkpoint.
+    ///
+    /// Checkpoints prior to the weak subjectivity checkpoint, or whose history back to it is
+    /// unknown, leave the check pending. Once a checkpoint at or after it has been checked, later
+    /// checkpoints descend from that one and are not checked again.
+    pub fn verify_weak_subjectivity_checkpoint(&self, finalized: Checkpoint) -> Result<(), Error> {
+        let wss_checkpoint = match self
+            .divergence_guard
+            .weak_subjectivity_checkpoint_to_check()
+        {
+            Some(checkpoint) => checkpoint.clone(),
+            None => return Ok(()),
+        };
+
+        if finalized.epoch < wss_checkpoint.epoch || finalized.root == Hash256::zero() {
+            return Ok(());
+        }
+
+        let wss_slot = wss_checkpoint
+            .epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+        let ancestor_root = process_results(
+            self.rev_iter_block_roots_from(finalized.root)?,
+            |mut iter| {
+                iter.find(|(_, slot)| *slot <= wss_slot)
+                    .map(|(root, _)| root)
+            },
+        )?;
+
+        // the check remains pending until an ancestor at the checkpoint has been compared, e.g.
+        // if the blocks before the finalized block are not yet known
+        let ancestor_root = match ancestor_root {
+            Some(ancestor_root) => ancestor_root,
+            None => return Ok(()),
+        };
+
+        if ancestor_root != wss_checkpoint.root {
+            self.halt_on_divergence(format!(
+                "finalized epoch {} descends from {:?} at the weak subjectivity checkpoint \
+                 epoch {}, expected {:?}",
+                finalized.epoch, ancestor_root, wss_checkpoint.epoch, wss_checkpoint.root
+            ));
+        }
+
+        self.divergence_guard.set_weak_subjectivity_checked();
+
+        Ok(())
+    }
+
+    /// Records whether the finalized checkpoint in the latest `Status` of `peer_id` conflicts with
+    /// our chain, halting block production and attestation if a quorum of the trusted peers now
+    /// conflict.
+    pub fn observe_trusted_peer_status(&self, peer_id: &str, conflicting: bool) {
+        if let Some(reason) = self
+            .divergence_guard
+            .observe_peer_status(peer_id, conflicting)
+        {
+            self.halt_on_divergence(reason);
+        }
+    }
+
+    /// Returns the reason block production and attestation were halted, if they have been.
+    pub fn halted_on_divergence(&self) -> Option<String> {
+        self.divergence_guard.halted()
+    }
+
+    fn halt_on_divergence(&self, reason: String) {
+        if self.divergence_guard.halt(reason.clone()) {
+            crit!(
+                self.log,
+                "Halted block production and attestation";
+                "reason" => reason,
+                "msg" => "the chain conflicts with the weak subjectivity checkpoint or trusted \
+                          peers, check the chain and restart the node"
+            );
         }
     }
 
@@ -1907,6 +2410,24 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
             self.op_pool.prune_all(&finalized_state, &self.spec);
 
+            // Persist the freshly pruned fork choice, so that a restart does not need to load (or
+            // rebuild) the blocks and votes prior to finalization.
+            if let Err(e) = self.persist_head_and_fork_choice() {
+                warn!(
+                    self.log,
+                    "Failed to persist fork choice";
+                    "error" => format!("{:?}", e),
+                );
+            }
+
+            if let Err(e) = self.persist_deposit_snapshot(finalized_state.eth1_deposit_index) {
+                warn!(
+                    self.log,
+                    "Failed to persist deposit snapshot";
+                    "error" => format!("{:?}", e),
+                );
+            }
+
             // TODO: configurable max finality distance
             let max_finality_distance = 0;
             self.store_migrator.process_finalization(
@@ -1918,7 +2439,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 finalized_block_root.into(),
             );
 
-            let _ = self.event_handler.register(EventKind::BeaconFinalization {
+            self.register_event(EventKind::BeaconFinalization {
                 epoch: new_finalized_epoch,
                 root: finalized_block_root,
             });
@@ -1993,6 +2514,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.spec.enr_fork_id(slot, self.genesis_validators_root)
     }
 
+    /// Calculates the `EnrForkId` which will apply after the next fork, if one exists.
+    pub fn next_enr_fork_id(&self) -> Option<EnrForkId> {
+        let epoch = self.spec.next_fork_epoch()?;
+        Some(self.spec.enr_fork_id(
+            epoch.start_slot(T::EthSpec::slots_per_epoch()),
+            self.genesis_validators_root,
+        ))
+    }
+
     /// Calculates the `Duration` to the next fork, if one exists.
     pub fn duration_to_next_fork(&self) -> Option<Duration> {
         let epoch = self.spec.next_fork_epoch()?;
@@ -2099,7 +2629,9 @@ impl<T: BeaconChainTypes> Drop for BeaconChain<T> {
         let drop = || -> Result<(), Error> {
             self.persist_head_and_fork_choice()?;
             self.persist_op_pool()?;
-            self.persist_eth1_cache()
+            self.persist_naive_aggregation_pool()?;
+            self.persist_eth1_cache()?;
+            self.persist_shuffling_cache()
         };
 
         if let Err(e) = drop() {
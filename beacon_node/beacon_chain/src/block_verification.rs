@@ -40,12 +40,10 @@
 //!            END
 //!
 //! ```
+use crate::epoch_summary::{EpochProposals, EpochSummary};
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
 use crate::{
-    beacon_chain::{
-        BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
-        VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT,
-    },
+    beacon_chain::{BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT},
     metrics, BeaconChain, BeaconChainError, BeaconChainTypes, BeaconSnapshot,
 };
 use parking_lot::RwLockReadGuard;
@@ -269,6 +267,9 @@ pub struct FullyVerifiedBlock<T: BeaconChainTypes> {
     pub state: BeaconState<T::EthSpec>,
     pub parent_block: SignedBeaconBlock<T::EthSpec>,
     pub intermediate_states: StateBatch<T::EthSpec>,
+    /// The summaries of the epoch transitions between the parent state and the block, keyed by the
+    /// root of the state prior to each transition.
+    pub epoch_summaries: Vec<(Hash256, EpochSummary)>,
 }
 
 /// Implemented on types that can be converted into a `FullyVerifiedBlock`.
@@ -295,7 +296,7 @@ impl<T: BeaconChainTypes> GossipVerifiedBlock<T> {
         // Do not gossip or process blocks from future slots.
         let present_slot_with_tolerance = chain
             .slot_clock
-            .now_with_future_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+            .now_with_future_tolerance(chain.spec.maximum_gossip_clock_disparity())
             .ok_or_else(|| BeaconChainError::UnableToReadSlot)?;
         if block.slot() > present_slot_with_tolerance {
             return Err(BlockError::FutureSlot {
@@ -303,6 +304,13 @@ impl<T: BeaconChainTypes> GossipVerifiedBlock<T> {
                 block_slot: block.slot(),
             });
         }
+        if chain
+            .slot_clock
+            .now()
+            .map_or(false, |slot| block.slot() > slot)
+        {
+            metrics::inc_counter_vec(&metrics::GOSSIP_CLOCK_DISPARITY_TOLERATED, &["block"]);
+        }
 
         // Do not gossip a block from a finalized slot.
         check_block_against_finalized_slot(&block.message, chain)?;
@@ -559,6 +567,7 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
         }
 
         let mut summaries = vec![];
+        let mut epoch_summaries = vec![];
 
         // Transition the parent state to the block slot.
         let mut state = parent.beacon_state;
@@ -575,8 +584,22 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
                 state_root
             };
 
-            per_slot_processing(&mut state, Some(state_root), &chain.spec)?
-                .map(|summary| summaries.push(summary));
+            // The proposals of an epoch must be found before the state transitions to the next.
+            let proposals = if (state.slot + 1) % T::EthSpec::slots_per_epoch() == 0 {
+                Some(EpochProposals::new(&state, &chain.spec)?)
+            } else {
+                None
+            };
+
+            if let Some(summary) = per_slot_processing(&mut state, Some(state_root), &chain.spec)? {
+                if let Some(proposals) = proposals {
+                    epoch_summaries.push((
+                        state_root,
+                        EpochSummary::new(proposals, &summary.statuses, &summary.total_balances),
+                    ));
+                }
+                summaries.push(summary);
+            }
         }
 
         expose_participation_metrics(&summaries);
@@ -589,8 +612,8 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
 
         let committee_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_COMMITTEE);
 
-        state.build_committee_cache(RelativeEpoch::Previous, &chain.spec)?;
-        state.build_committee_cache(RelativeEpoch::Current, &chain.spec)?;
+        chain.attach_committee_cache(&mut state, RelativeEpoch::Previous)?;
+        chain.attach_committee_cache(&mut state, RelativeEpoch::Current)?;
 
         metrics::stop_timer(committee_timer);
 
@@ -659,6 +682,7 @@ impl<T: BeaconChainTypes> FullyVerifiedBlock<T> {
             state,
             parent_block: parent.beacon_block,
             intermediate_states,
+            epoch_summaries,
         })
     }
 }
@@ -923,7 +947,7 @@ fn expose_participation_metrics(summaries: &[EpochProcessingSummary]) {
     }
 }
 
-fn participation_ratio(section: u64, total: u64) -> Option<f64> {
+pub(crate) fn participation_ratio(section: u64, total: u64) -> Option<f64> {
     // Reduce the precision to help ensure we fit inside a u32.
     const PRECISION: u64 = 100_000_000;
 
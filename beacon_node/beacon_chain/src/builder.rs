@@ -1,13 +1,18 @@
+use crate::attestation_data_cache::AttestationDataCache;
 use crate::beacon_chain::{
-    BEACON_CHAIN_DB_KEY, ETH1_CACHE_DB_KEY, FORK_CHOICE_DB_KEY, OP_POOL_DB_KEY,
+    BEACON_CHAIN_DB_KEY, ETH1_CACHE_DB_KEY, FORK_CHOICE_DB_KEY, NAIVE_AGGREGATION_POOL_DB_KEY,
+    OP_POOL_DB_KEY, SHUFFLING_CACHE_DB_KEY,
 };
+use crate::divergence_guard::DivergenceGuard;
 use crate::eth1_chain::{CachingEth1Backend, SszEth1};
 use crate::events::NullEventHandler;
 use crate::fork_choice::SszForkChoice;
 use crate::head_tracker::HeadTracker;
 use crate::migrate::Migrate;
+use crate::naive_aggregation_pool::{NaiveAggregationPool, PersistedNaiveAggregationPool};
 use crate::persisted_beacon_chain::PersistedBeaconChain;
-use crate::shuffling_cache::ShufflingCache;
+use crate::shuffling_cache::{PersistedShufflingCache, ShufflingCache};
+use crate::skip_slot_cache::SkipSlotCache;
 use crate::snapshot_cache::{SnapshotCache, DEFAULT_SNAPSHOT_CACHE_SIZE};
 use crate::timeout_rw_lock::TimeoutRwLock;
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
@@ -26,7 +31,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use store::{HotColdDB, ItemStore};
 use types::{
-    BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256, Signature, SignedBeaconBlock, Slot,
+    BeaconBlock, BeaconState, ChainSpec, Checkpoint, EthSpec, Hash256, Signature,
+    SignedBeaconBlock, Slot,
 };
 
 pub const PUBKEY_CACHE_FILENAME: &str = "pubkey_cache.ssz";
@@ -99,6 +105,8 @@ pub struct BeaconChainBuilder<T: BeaconChainTypes> {
     pub finalized_snapshot: Option<BeaconSnapshot<T::EthSpec>>,
     genesis_block_root: Option<Hash256>,
     op_pool: Option<OperationPool<T::EthSpec>>,
+    naive_aggregation_pool: Option<NaiveAggregationPool<T::EthSpec>>,
+    shuffling_cache: Option<ShufflingCache>,
     fork_choice: Option<ForkChoice<T>>,
     eth1_chain: Option<Eth1Chain<T::Eth1Chain, T::EthSpec>>,
     event_handler: Option<T::EventHandler>,
@@ -109,6 +117,7 @@ pub struct BeaconChainBuilder<T: BeaconChainTypes> {
     validator_pubkey_cache: Option<ValidatorPubkeyCache>,
     spec: ChainSpec,
     disabled_forks: Vec<String>,
+    divergence_guard: DivergenceGuard,
     log: Option<Logger>,
 }
 
@@ -145,6 +154,8 @@ where
             finalized_snapshot: None,
             genesis_block_root: None,
             op_pool: None,
+            naive_aggregation_pool: None,
+            shuffling_cache: None,
             fork_choice: None,
             eth1_chain: None,
             event_handler: None,
@@ -153,6 +164,7 @@ where
             pubkey_cache_path: None,
             data_dir: None,
             disabled_forks: Vec::new(),
+            divergence_guard: <_>::default(),
             validator_pubkey_cache: None,
             spec: TEthSpec::default_spec(),
             log: None,
@@ -205,6 +217,17 @@ where
         self
     }
 
+    /// Sets the weak subjectivity checkpoint and trusted peers which the chain must agree with,
+    /// otherwise block production and attestation are halted.
+    pub fn divergence_guard(
+        mut self,
+        weak_subjectivity_checkpoint: Option<Checkpoint>,
+        trusted_peers: Vec<String>,
+    ) -> Self {
+        self.divergence_guard = DivergenceGuard::new(weak_subjectivity_checkpoint, trusted_peers);
+        self
+    }
+
     /// Attempt to load an existing eth1 cache from the builder's `Store`.
     pub fn get_persisted_eth1_backend(&self) -> Result<Option<SszEth1>, String> {
         let store = self
@@ -288,6 +311,23 @@ where
                 .unwrap_or_else(|| OperationPool::new()),
         );
 
+        self.naive_aggregation_pool = store
+            .get_item::<PersistedNaiveAggregationPool<TEthSpec>>(&Hash256::from_slice(
+                &NAIVE_AGGREGATION_POOL_DB_KEY,
+            ))
+            .map_err(|e| {
+                format!(
+                    "DB error whilst reading persisted naive aggregation pool: {:?}",
+                    e
+                )
+            })?
+            .map(PersistedNaiveAggregationPool::into_naive_aggregation_pool);
+
+        self.shuffling_cache = store
+            .get_item::<PersistedShufflingCache>(&Hash256::from_slice(&SHUFFLING_CACHE_DB_KEY))
+            .map_err(|e| format!("DB error whilst reading persisted shuffling cache: {:?}", e))?
+            .map(PersistedShufflingCache::into_shuffling_cache);
+
         let finalized_block_root = head_state.finalized_checkpoint.root;
         let finalized_block = store
             .get_item::<SignedBeaconBlock<TEthSpec>>(&finalized_block_root)
@@ -468,8 +508,7 @@ where
             op_pool: self
                 .op_pool
                 .ok_or_else(|| "Cannot build without op pool".to_string())?,
-            // TODO: allow for persisting and loading the pool from disk.
-            naive_aggregation_pool: <_>::default(),
+            naive_aggregation_pool: self.naive_aggregation_pool.unwrap_or_default(),
             // TODO: allow for persisting and loading the pool from disk.
             observed_attestations: <_>::default(),
             // TODO: allow for persisting and loading the pool from disk.
@@ -490,14 +529,21 @@ where
             event_handler: self
                 .event_handler
                 .ok_or_else(|| "Cannot build without an event handler".to_string())?,
+            event_bus: <_>::default(),
             head_tracker: Arc::new(self.head_tracker.unwrap_or_default()),
             snapshot_cache: TimeoutRwLock::new(SnapshotCache::new(
                 DEFAULT_SNAPSHOT_CACHE_SIZE,
                 canonical_head,
             )),
-            shuffling_cache: TimeoutRwLock::new(ShufflingCache::new()),
+            shuffling_cache: TimeoutRwLock::new(
+                self.shuffling_cache.unwrap_or_else(ShufflingCache::new),
+            ),
+            attestation_data_cache: TimeoutRwLock::new(AttestationDataCache::new()),
+            skip_slot_cache: TimeoutRwLock::new(SkipSlotCache::new()),
             validator_pubkey_cache: TimeoutRwLock::new(validator_pubkey_cache),
             disabled_forks: self.disabled_forks,
+            divergence_guard: self.divergence_guard,
+            chain_health: <_>::default(),
             log: log.clone(),
         };
 
@@ -513,6 +559,10 @@ where
             "head_slot" => format!("{}", head.beacon_block.slot()),
         );
 
+        beacon_chain
+            .verify_weak_subjectivity_checkpoint(head.beacon_state.finalized_checkpoint.clone())
+            .map_err(|e| format!("Unable to verify weak subjectivity checkpoint: {:?}", e))?;
+
         Ok(beacon_chain)
     }
 }
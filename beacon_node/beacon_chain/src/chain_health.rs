@@ -0,0 +1,95 @@
+//! Provides a set of gauges which summarize the health of the chain and are updated once per slot
+//! (see `BeaconChain::per_slot_task`).
+
+use crate::beacon_chain::HEAD_LOCK_TIMEOUT;
+use crate::block_verification::participation_ratio;
+use crate::metrics;
+use crate::{BeaconChain, BeaconChainTypes};
+use parking_lot::Mutex;
+use slot_clock::SlotClock;
+use state_processing::per_epoch_processing::ValidatorStatuses;
+use std::time::Instant;
+use types::{Epoch, EthSpec, Slot};
+
+/// Tracks the information required to compute the chain health gauges that cannot be read from
+/// the head of the chain.
+#[derive(Default)]
+pub struct ChainHealth {
+    /// The time at which a block was last imported.
+    last_block_import: Mutex<Option<Instant>>,
+    /// The epoch for which the participation rate was last computed.
+    participation_epoch: Mutex<Option<Epoch>>,
+}
+
+impl ChainHealth {
+    /// Records that a block has just been imported.
+    pub fn observe_block_import(&self) {
+        *self.last_block_import.lock() = Some(Instant::now());
+    }
+
+    /// Updates all chain health gauges, assuming that `current_slot` has just started.
+    pub fn update_metrics<T: BeaconChainTypes>(&self, chain: &BeaconChain<T>, current_slot: Slot) {
+        let head = if let Some(head) = chain.canonical_head.try_read_for(HEAD_LOCK_TIMEOUT) {
+            head
+        } else {
+            return;
+        };
+        let state = &head.beacon_state;
+
+        metrics::set_gauge(&metrics::HEALTH_HEAD_SLOT, state.slot.as_u64() as i64);
+        metrics::set_gauge(
+            &metrics::HEALTH_FINALIZED_EPOCH,
+            state.finalized_checkpoint.epoch.as_u64() as i64,
+        );
+        metrics::set_gauge(
+            &metrics::HEALTH_JUSTIFICATION_BITS,
+            state
+                .justification_bits
+                .iter()
+                .enumerate()
+                .filter(|(_, bit)| *bit)
+                .fold(0, |bits, (i, _)| bits | 1 << i),
+        );
+        // Taking advantage of saturating subtraction on `Slot`.
+        metrics::set_gauge(
+            &metrics::HEALTH_SYNC_DISTANCE,
+            (current_slot - state.slot).as_u64() as i64,
+        );
+
+        if let Some(last_block_import) = *self.last_block_import.lock() {
+            metrics::set_gauge(
+                &metrics::HEALTH_SECONDS_SINCE_LAST_BLOCK_IMPORT,
+                last_block_import.elapsed().as_secs() as i64,
+            );
+        }
+
+        // The previous epoch attestations are only complete at the end of the current epoch, so
+        // only compute the participation rate then (or if it has never been computed).
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        let is_last_slot_in_epoch = (current_slot + 1) % slots_per_epoch == 0;
+        let previous_epoch = state.previous_epoch();
+        let mut participation_epoch = self.participation_epoch.lock();
+
+        if *participation_epoch != Some(previous_epoch)
+            && (is_last_slot_in_epoch || participation_epoch.is_none())
+        {
+            let participation = ValidatorStatuses::new(state, &chain.spec)
+                .and_then(|mut statuses| {
+                    statuses.process_attestations(state, &chain.spec)?;
+                    Ok(statuses.total_balances)
+                })
+                .ok()
+                .and_then(|balances| {
+                    participation_ratio(
+                        balances.previous_epoch_target_attesters(),
+                        balances.previous_epoch(),
+                    )
+                });
+
+            if let Some(participation) = participation {
+                metrics::set_float_gauge(&metrics::HEALTH_PREV_EPOCH_PARTICIPATION, participation);
+                *participation_epoch = Some(previous_epoch);
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Halts block production and attestation if the node is found to be following a chain which
+//! conflicts with a configured weak subjectivity checkpoint, or with the finalized checkpoints
+//! reported by a quorum of configured trusted peers.
+//!
+//! Once halted, the node continues to import blocks and serve its peers, but it will not sign
+//! anything on the (presumably wrong) chain until it is restarted by an operator.
+
+use crate::metrics;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use types::Checkpoint;
+
+#[derive(Default)]
+pub struct DivergenceGuard {
+    weak_subjectivity_checkpoint: Option<Checkpoint>,
+    /// Set once a finalized checkpoint at or after the weak subjectivity checkpoint has been
+    /// checked, as all later finalized checkpoints descend from it.
+    weak_subjectivity_checked: AtomicBool,
+    /// The peer ids of the trusted peers, as strings.
+    trusted_peers: HashSet<String>,
+    /// The trusted peers whose latest `Status` conflicted with our finalized chain.
+    conflicting_trusted_peers: Mutex<HashSet<String>>,
+    /// The reason block production and attestation were halted, if they have been.
+    halted: RwLock<Option<String>>,
+}
+
+impl DivergenceGuard {
+    pub fn new(
+        weak_subjectivity_checkpoint: Option<Checkpoint>,
+        trusted_peers: Vec<String>,
+    ) -> Self {
+        Self {
+            weak_subjectivity_checkpoint,
+            trusted_peers: trusted_peers.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the weak subjectivity checkpoint, unless there is none or it has already been
+    /// checked.
+    pub fn weak_subjectivity_checkpoint_to_check(&self) -> Option<&Checkpoint> {
+        if self.weak_subjectivity_checked.load(Ordering::Relaxed) {
+            None
+        } else {
+            self.weak_subjectivity_checkpoint.as_ref()
+        }
+    }
+
+    /// Records that the weak subjectivity checkpoint no longer needs to be checked.
+    pub fn set_weak_subjectivity_checked(&self) {
+        self.weak_subjectivity_checked
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the reason block production and attestation were halted, if they have been.
+    pub fn halted(&self) -> Option<String> {
+        self.halted.read().clone()
+    }
+
+    /// Halts block production and attestation. Returns `false` if they were already halted.
+    pub fn halt(&self, reason: String) -> bool {
+        let mut halted = self.halted.write();
+        if halted.is_some() {
+            return false;
+        }
+        *halted = Some(reason);
+        metrics::set_gauge(&metrics::HALTED_ON_DIVERGENCE, 1);
+        true
+    }
+
+    /// Records whether the finalized checkpoint in the latest `Status` of `peer_id` conflicts with
+    /// our chain, returning the reason to halt if more than half of the trusted peers now
+    /// conflict. Peers which are not trusted are ignored.
+    pub fn observe_peer_status(&self, peer_id: &str, conflicting: bool) -> Option<String> {
+        if !self.trusted_peers.contains(peer_id) {
+            return None;
+        }
+
+        let mut conflicting_peers = self.conflicting_trusted_peers.lock();
+        if conflicting {
+            conflicting_peers.insert(peer_id.to_string());
+        } else {
+            conflicting_peers.remove(peer_id);
+        }
+
+        if conflicting_peers.len() * 2 > self.trusted_peers.len() {
+            Some(format!(
+                "{} of {} trusted peers are on a different finalized chain",
+                conflicting_peers.len(),
+                self.trusted_peers.len()
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trusted_peer_quorum() {
+        let guard = DivergenceGuard::new(
+            None,
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        );
+
+        assert_eq!(guard.observe_peer_status("untrusted", true), None);
+        assert_eq!(guard.observe_peer_status("a", true), None);
+        // a peer which agrees again no longer counts towards the quorum
+        assert_eq!(guard.observe_peer_status("a", false), None);
+        assert_eq!(guard.observe_peer_status("b", true), None);
+        assert!(guard.observe_peer_status("c", true).is_some());
+
+        assert_eq!(guard.halted(), None);
+        assert!(guard.halt("diverged".to_string()));
+        assert!(!guard.halt("diverged again".to_string()));
+        assert_eq!(guard.halted(), Some("diverged".to_string()));
+    }
+
+    #[test]
+    fn weak_subjectivity_checkpoint_is_checked_once() {
+        let guard = DivergenceGuard::new(Some(Checkpoint::default()), vec![]);
+
+        assert_eq!(
+            guard.weak_subjectivity_checkpoint_to_check(),
+            Some(&Checkpoint::default())
+        );
+        guard.set_weak_subjectivity_checked();
+        assert_eq!(guard.weak_subjectivity_checkpoint_to_check(), None);
+    }
+}
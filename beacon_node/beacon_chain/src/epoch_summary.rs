@@ -0,0 +1,267 @@
+//! A compact summary of each epoch transition of the chain, which is persisted when the transition
+//! is processed so that it remains available once the states themselves have been pruned.
+//!
+//! Summaries are keyed by the root of the state at the last slot of an epoch, prior to the
+//! processing of the epoch. Like the state, the summary reports the participation in the
+//! attestations of the _previous_ epoch, as they can be included until the end of the _current_
+//! epoch.
+
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use state_processing::per_epoch_processing::{TotalBalances, ValidatorStatus};
+use store::{DBColumn, Error as StoreError, StoreItem};
+use types::{BeaconState, BeaconStateError, ChainSpec, Epoch, EthSpec, Slot};
+
+/// Set in the participation of a validator which was active during the previous epoch.
+pub const PARTICIPATION_ACTIVE: u8 = 1;
+/// Set in the participation of a validator with an attestation included.
+pub const PARTICIPATION_ATTESTER: u8 = 1 << 1;
+/// Set in the participation of a validator with an attestation with the correct target included.
+pub const PARTICIPATION_TARGET_ATTESTER: u8 = 1 << 2;
+/// Set in the participation of a validator with an attestation with the correct head included.
+pub const PARTICIPATION_HEAD_ATTESTER: u8 = 1 << 3;
+/// Set in the participation of a validator which has ever been slashed.
+pub const PARTICIPATION_SLASHED: u8 = 1 << 4;
+
+/// The proposals of the slots of an epoch, which can only be computed from a state in that epoch.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct EpochProposals {
+    pub epoch: Epoch,
+    /// The proposer of each slot of the epoch.
+    pub proposers: Vec<u64>,
+    /// Whether a block was proposed at each slot of the epoch.
+    pub proposed: Vec<bool>,
+}
+
+impl EpochProposals {
+    /// Returns the proposals of the current epoch of `state`. Slots which `state` has not yet
+    /// reached are reported as without a block.
+    pub fn new<E: EthSpec>(
+        state: &BeaconState<E>,
+        spec: &ChainSpec,
+    ) -> Result<Self, BeaconStateError> {
+        let epoch = state.current_epoch();
+        let slots = epoch.slot_iter(E::slots_per_epoch()).collect::<Vec<_>>();
+        // Equivalent to `BeaconState::get_beacon_proposer_index`, without finding the active
+        // validators for each slot.
+        let active_indices = state.get_active_validator_indices(epoch);
+
+        Ok(Self {
+            epoch,
+            proposers: slots
+                .iter()
+                .map(|&slot| {
+                    let seed = state.get_beacon_proposer_seed(slot, spec)?;
+                    state
+                        .compute_proposer_index(&active_indices, &seed, spec)
+                        .map(|index| index as u64)
+                })
+                .collect::<Result<_, _>>()?,
+            proposed: slots
+                .iter()
+                .map(|&slot| block_proposed_at_slot(state, slot))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct EpochSummary {
+    pub current_epoch_proposals: EpochProposals,
+    /// The total effective balance of the validators active during the current epoch.
+    pub current_epoch_active_gwei: u64,
+    /// The total effective balance of the validators active during the previous epoch, and of
+    /// those with an attestation, or an attestation with the correct target or head, included.
+    pub previous_epoch_active_gwei: u64,
+    pub previous_epoch_attesting_gwei: u64,
+    pub previous_epoch_target_attesting_gwei: u64,
+    pub previous_epoch_head_attesting_gwei: u64,
+    /// The number of validators which have ever been slashed.
+    pub slashed_validators: u64,
+    /// The `PARTICIPATION_*` flags of each validator in the previous epoch.
+    pub previous_epoch_participation: Vec<u8>,
+    /// The inclusion delay of the earliest included attestation of each validator in the previous
+    /// epoch, or zero if none was included.
+    pub previous_epoch_inclusion_delays: Vec<u8>,
+}
+
+impl EpochSummary {
+    /// Summarizes an epoch from its `current_epoch_proposals`, and the `statuses` and
+    /// `total_balances` computed from its state for epoch processing.
+    pub fn new(
+        current_epoch_proposals: EpochProposals,
+        statuses: &[ValidatorStatus],
+        total_balances: &TotalBalances,
+    ) -> Self {
+        let previous_epoch_participation = statuses.iter().map(participation_flags).collect();
+        let previous_epoch_inclusion_delays = statuses
+            .iter()
+            .map(|status| {
+                status.inclusion_info.as_ref().map_or(0, |info| {
+                    std::cmp::min(info.delay, u64::from(u8::max_value())) as u8
+                })
+            })
+            .collect();
+
+        Self {
+            current_epoch_proposals,
+            current_epoch_active_gwei: total_balances.current_epoch(),
+            previous_epoch_active_gwei: total_balances.previous_epoch(),
+            previous_epoch_attesting_gwei: total_balances.previous_epoch_attesters(),
+            previous_epoch_target_attesting_gwei: total_balances.previous_epoch_target_attesters(),
+            previous_epoch_head_attesting_gwei: total_balances.previous_epoch_head_attesters(),
+            slashed_validators: statuses.iter().filter(|status| status.is_slashed).count() as u64,
+            previous_epoch_participation,
+            previous_epoch_inclusion_delays,
+        }
+    }
+
+    /// Returns the current epoch of the summarized state.
+    pub fn epoch(&self) -> Epoch {
+        self.current_epoch_proposals.epoch
+    }
+
+    /// Returns the `PARTICIPATION_*` flags of the validator with `validator_index` in the previous
+    /// epoch, if it existed.
+    pub fn participation(&self, validator_index: usize) -> Option<u8> {
+        self.previous_epoch_participation
+            .get(validator_index)
+            .copied()
+    }
+
+    /// Returns the inclusion delay of the earliest included attestation of the validator with
+    /// `validator_index` in the previous epoch, if one was included.
+    pub fn inclusion_delay(&self, validator_index: usize) -> Option<u64> {
+        self.previous_epoch_inclusion_delays
+            .get(validator_index)
+            .filter(|&&delay| delay > 0)
+            .map(|&delay| u64::from(delay))
+    }
+
+    /// Returns the number of validators with all of the given `PARTICIPATION_*` `flags` set.
+    pub fn count_participants(&self, flags: u8) -> u64 {
+        self.previous_epoch_participation
+            .iter()
+            .filter(|&&participation| participation & flags == flags)
+            .count() as u64
+    }
+}
+
+/// Returns the `PARTICIPATION_*` flags set for `status`.
+fn participation_flags(status: &ValidatorStatus) -> u8 {
+    let mut flags = 0;
+    if status.is_active_in_previous_epoch {
+        flags |= PARTICIPATION_ACTIVE;
+    }
+    if status.is_previous_epoch_attester {
+        flags |= PARTICIPATION_ATTESTER;
+    }
+    if status.is_previous_epoch_target_attester {
+        flags |= PARTICIPATION_TARGET_ATTESTER;
+    }
+    if status.is_previous_epoch_head_attester {
+        flags |= PARTICIPATION_HEAD_ATTESTER;
+    }
+    if status.is_slashed {
+        flags |= PARTICIPATION_SLASHED;
+    }
+    flags
+}
+
+/// Returns `true` if a block was proposed at `slot`. Slots which `state` has not yet reached are
+/// reported as without a block.
+fn block_proposed_at_slot<E: EthSpec>(
+    state: &BeaconState<E>,
+    slot: Slot,
+) -> Result<bool, BeaconStateError> {
+    if slot > state.slot || slot.as_u64() == 0 {
+        // The genesis block is not proposed.
+        Ok(false)
+    } else if slot == state.slot {
+        Ok(state.latest_block_header.slot == slot)
+    } else {
+        Ok(state.get_block_root(slot)? != state.get_block_root(slot - 1)?)
+    }
+}
+
+impl StoreItem for EpochSummary {
+    fn db_column() -> DBColumn {
+        DBColumn::EpochSummary
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state_processing::per_epoch_processing::validator_statuses::InclusionInfo;
+
+    #[test]
+    fn participation_and_inclusion_delays() {
+        let spec = ChainSpec::mainnet();
+        let statuses = vec![
+            ValidatorStatus {
+                is_active_in_previous_epoch: true,
+                is_previous_epoch_attester: true,
+                is_previous_epoch_target_attester: true,
+                inclusion_info: Some(InclusionInfo {
+                    delay: 1,
+                    proposer_index: 0,
+                }),
+                ..ValidatorStatus::default()
+            },
+            ValidatorStatus {
+                is_active_in_previous_epoch: true,
+                is_previous_epoch_attester: true,
+                inclusion_info: Some(InclusionInfo {
+                    delay: 1_000,
+                    proposer_index: 0,
+                }),
+                ..ValidatorStatus::default()
+            },
+            ValidatorStatus {
+                is_active_in_previous_epoch: true,
+                is_slashed: true,
+                ..ValidatorStatus::default()
+            },
+        ];
+        let proposals = EpochProposals {
+            epoch: Epoch::new(1),
+            proposers: vec![],
+            proposed: vec![],
+        };
+
+        let summary = EpochSummary::new(proposals, &statuses, &TotalBalances::new(&spec));
+
+        assert_eq!(summary.epoch(), Epoch::new(1));
+        assert_eq!(summary.slashed_validators, 1);
+        assert_eq!(summary.count_participants(PARTICIPATION_ACTIVE), 3);
+        assert_eq!(summary.count_participants(PARTICIPATION_ATTESTER), 2);
+        assert_eq!(
+            summary.count_participants(PARTICIPATION_ATTESTER | PARTICIPATION_TARGET_ATTESTER),
+            1
+        );
+        assert_eq!(
+            summary.participation(2),
+            Some(PARTICIPATION_ACTIVE | PARTICIPATION_SLASHED)
+        );
+        assert_eq!(summary.participation(3), None);
+
+        assert_eq!(summary.inclusion_delay(0), Some(1));
+        // delays which do not fit in a byte are capped
+        assert_eq!(summary.inclusion_delay(1), Some(255));
+        assert_eq!(summary.inclusion_delay(2), None);
+
+        assert_eq!(
+            EpochSummary::from_ssz_bytes(&summary.as_ssz_bytes()),
+            Ok(summary)
+        );
+    }
+}
@@ -60,6 +60,7 @@ pub enum BeaconChainError {
     SszTypesError(SszTypesError),
     CanonicalHeadLockTimeout,
     AttestationCacheLockTimeout,
+    SkipSlotCacheLockTimeout,
     ValidatorPubkeyCacheLockTimeout,
     IncorrectStateForAttestation(RelativeEpochError),
     InvalidValidatorPubkeyBytes(DecodeError),
@@ -74,6 +75,10 @@ pub enum BeaconChainError {
     ObservedAttestersError(ObservedAttestersError),
     ObservedBlockProducersError(ObservedBlockProducersError),
     ArithError(ArithError),
+    Eth1ChainError(Eth1ChainError),
+    /// Attestation has been halted because the node is on a chain which conflicts with the
+    /// weak subjectivity checkpoint or trusted peers.
+    ChainDiverged(String),
 }
 
 easy_from_to!(SlotProcessingError, BeaconChainError);
@@ -86,6 +91,7 @@ easy_from_to!(ObservedAttestersError, BeaconChainError);
 easy_from_to!(ObservedBlockProducersError, BeaconChainError);
 easy_from_to!(BlockSignatureVerifierError, BeaconChainError);
 easy_from_to!(ArithError, BeaconChainError);
+easy_from_to!(Eth1ChainError, BeaconChainError);
 
 #[derive(Debug)]
 pub enum BlockProductionError {
@@ -100,6 +106,9 @@ pub enum BlockProductionError {
     /// The `BeaconChain` was explicitly configured _without_ a connection to eth1, therefore it
     /// cannot produce blocks.
     NoEth1ChainConnection,
+    /// Block production has been halted because the node is on a chain which conflicts with the
+    /// weak subjectivity checkpoint or trusted peers.
+    ChainDiverged(String),
 }
 
 easy_from_to!(BlockProcessingError, BlockProductionError);
@@ -1,6 +1,6 @@
 use crate::metrics;
 use environment::TaskExecutor;
-use eth1::{Config as Eth1Config, Eth1Block, Service as HttpService};
+use eth1::{CacheStatus, Config as Eth1Config, EndpointStatus, Eth1Block, Service as HttpService};
 use eth2_hashing::hash;
 use slog::{debug, error, trace, Logger};
 use ssz::{Decode, Encode};
@@ -12,8 +12,8 @@ use std::iter::DoubleEndedIterator;
 use std::marker::PhantomData;
 use store::{DBColumn, Error as StoreError, StoreItem};
 use types::{
-    BeaconState, BeaconStateError, ChainSpec, Deposit, Eth1Data, EthSpec, Hash256, Slot, Unsigned,
-    DEPOSIT_TREE_DEPTH,
+    BeaconState, BeaconStateError, ChainSpec, Deposit, DepositTreeSnapshot, Eth1Data, EthSpec,
+    Hash256, Slot, Unsigned, DEPOSIT_TREE_DEPTH,
 };
 
 type BlockNumber = u64;
@@ -161,6 +161,36 @@ where
         }
     }
 
+    /// Returns the health of each eth1 endpoint, or an empty list if dummy data is used.
+    pub fn endpoints(&self) -> Vec<EndpointStatus> {
+        if self.use_dummy_backend {
+            vec![]
+        } else {
+            self.backend.endpoints()
+        }
+    }
+
+    /// Returns the contents of the eth1 caches, or `None` if dummy data is used.
+    pub fn cache_status(&self) -> Option<CacheStatus> {
+        if self.use_dummy_backend {
+            None
+        } else {
+            self.backend.cache_status()
+        }
+    }
+
+    /// Returns a snapshot of the first `deposit_index` deposits, or `None` if dummy data is used.
+    pub fn get_deposit_snapshot(
+        &self,
+        deposit_index: u64,
+    ) -> Result<Option<DepositTreeSnapshot>, Error> {
+        if self.use_dummy_backend {
+            Ok(None)
+        } else {
+            self.backend.get_deposit_snapshot(deposit_index)
+        }
+    }
+
     /// Consumes `self`, returning the backend.
     pub fn into_backend(self) -> T {
         self.backend
@@ -187,6 +217,19 @@ pub trait Eth1ChainBackend<T: EthSpec>: Sized + Send + Sync {
         spec: &ChainSpec,
     ) -> Result<Vec<Deposit>, Error>;
 
+    /// Returns the health of each eth1 endpoint the backend connects to.
+    fn endpoints(&self) -> Vec<EndpointStatus>;
+
+    /// Returns the contents of the backend's eth1 caches, if it has any.
+    fn cache_status(&self) -> Option<CacheStatus>;
+
+    /// Returns a snapshot of the first `deposit_index` deposits, or `None` if the backend does
+    /// not know of any deposits.
+    fn get_deposit_snapshot(
+        &self,
+        deposit_index: u64,
+    ) -> Result<Option<DepositTreeSnapshot>, Error>;
+
     /// Encode the `Eth1ChainBackend` instance to bytes.
     fn as_bytes(&self) -> Vec<u8>;
 
@@ -228,6 +271,24 @@ impl<T: EthSpec> Eth1ChainBackend<T> for DummyEth1ChainBackend<T> {
         Ok(vec![])
     }
 
+    /// The dummy back-end does not connect to any eth1 endpoints.
+    fn endpoints(&self) -> Vec<EndpointStatus> {
+        vec![]
+    }
+
+    /// The dummy back-end does not cache any eth1 data.
+    fn cache_status(&self) -> Option<CacheStatus> {
+        None
+    }
+
+    /// The dummy back-end has no deposits.
+    fn get_deposit_snapshot(
+        &self,
+        _deposit_index: u64,
+    ) -> Result<Option<DepositTreeSnapshot>, Error> {
+        Ok(None)
+    }
+
     /// Return empty Vec<u8> for dummy backend.
     fn as_bytes(&self) -> Vec<u8> {
         Vec::new()
@@ -383,6 +444,24 @@ impl<T: EthSpec> Eth1ChainBackend<T> for CachingEth1Backend<T> {
         }
     }
 
+    fn endpoints(&self) -> Vec<EndpointStatus> {
+        self.core.endpoints()
+    }
+
+    fn cache_status(&self) -> Option<CacheStatus> {
+        Some(self.core.cache_status())
+    }
+
+    fn get_deposit_snapshot(
+        &self,
+        deposit_index: u64,
+    ) -> Result<Option<DepositTreeSnapshot>, Error> {
+        self.core
+            .get_deposit_snapshot(deposit_index)
+            .map(Some)
+            .map_err(Error::BackendError)
+    }
+
     /// Return encoded byte representation of the block and deposit caches.
     fn as_bytes(&self) -> Vec<u8> {
         self.core.as_bytes()
@@ -689,6 +768,154 @@ mod test {
             })
         }
 
+        #[test]
+        fn deposits_after_snapshot() {
+            let spec = &E::default_spec();
+
+            let eth1_chain = get_eth1_chain();
+            let num_deposits = 16;
+            let deposit_index = 6;
+
+            let deposits: Vec<_> = (0..num_deposits)
+                .map(|i| get_deposit_log(i, spec))
+                .collect();
+            for log in &deposits {
+                eth1_chain
+                    .backend
+                    .core
+                    .deposits()
+                    .write()
+                    .cache
+                    .insert_log(log.clone())
+                    .expect("should insert log");
+            }
+
+            // Deposit `i` is included in block `i`.
+            for number in 1..num_deposits {
+                let (deposit_root, deposit_count) = {
+                    let deposits = eth1_chain.backend.core.deposits().read();
+                    (
+                        deposits.cache.get_deposit_root_from_cache(number),
+                        deposits.cache.get_deposit_count_from_cache(number),
+                    )
+                };
+                eth1_chain
+                    .backend
+                    .core
+                    .blocks()
+                    .write()
+                    .insert_root_or_child(Eth1Block {
+                        hash: Hash256::from_low_u64_be(number),
+                        timestamp: number,
+                        number,
+                        deposit_root,
+                        deposit_count,
+                    })
+                    .expect("should add block to cache");
+            }
+
+            // The finalized state has included fewer deposits than its `eth1_data` contains.
+            let mut state: BeaconState<E> = BeaconState::new(0, get_eth1_data(0), &spec);
+            state.eth1_deposit_index = deposit_index;
+            state.eth1_data.deposit_count = num_deposits;
+
+            let snapshot = eth1_chain
+                .get_deposit_snapshot(state.eth1_deposit_index)
+                .expect("should produce snapshot")
+                .expect("should not use dummy backend");
+            assert_eq!(snapshot.deposit_count, deposit_index);
+            assert_eq!(snapshot.execution_block_height, deposit_index - 1);
+
+            // A node started from the snapshot must be able to produce the next block.
+            let restored = Eth1Chain::<_, E>::new(CachingEth1Backend::new(
+                Eth1Config {
+                    deposit_snapshot: Some(snapshot),
+                    ..Eth1Config::default()
+                },
+                null_logger().unwrap(),
+            ));
+            for log in &deposits[deposit_index as usize..] {
+                restored
+                    .backend
+                    .core
+                    .deposits()
+                    .write()
+                    .cache
+                    .insert_log(log.clone())
+                    .expect("should insert log after snapshot");
+            }
+
+            let deposits_for_inclusion = restored
+                .deposits_for_block_inclusion(&state, &Eth1Data::default(), spec)
+                .expect("should get deposits after snapshot");
+            assert_eq!(
+                deposits_for_inclusion
+                    .into_iter()
+                    .map(|deposit| deposit.data)
+                    .collect::<Vec<_>>(),
+                deposits[deposit_index as usize..]
+                    .iter()
+                    .map(|log| log.deposit_data.clone())
+                    .collect::<Vec<_>>(),
+                "should include the deposits after the snapshot"
+            );
+        }
+
+        #[test]
+        fn deposit_snapshot_part_way_through_block() {
+            let spec = &E::default_spec();
+
+            let eth1_chain = get_eth1_chain();
+
+            // Deposits 0..4 are included in block 1 and deposits 4..8 in block 2.
+            for i in 0..8 {
+                let log = DepositLog {
+                    block_number: 1 + i / 4,
+                    ..get_deposit_log(i, spec)
+                };
+                eth1_chain
+                    .backend
+                    .core
+                    .deposits()
+                    .write()
+                    .cache
+                    .insert_log(log)
+                    .expect("should insert log");
+            }
+            for number in 1..3 {
+                eth1_chain
+                    .backend
+                    .core
+                    .blocks()
+                    .write()
+                    .insert_root_or_child(Eth1Block {
+                        hash: Hash256::from_low_u64_be(number),
+                        timestamp: number,
+                        number,
+                        deposit_root: None,
+                        deposit_count: Some(number * 4),
+                    })
+                    .expect("should add block to cache");
+            }
+
+            let snapshot = eth1_chain
+                .get_deposit_snapshot(4)
+                .expect("should produce snapshot at a block boundary")
+                .expect("should not use dummy backend");
+            assert_eq!(snapshot.deposit_count, 4);
+            assert_eq!(snapshot.execution_block_height, 1);
+            assert_eq!(snapshot.execution_block_hash, Hash256::from_low_u64_be(1));
+
+            match eth1_chain.get_deposit_snapshot(6) {
+                Err(Error::BackendError(e)) => assert!(
+                    e.contains("Deposit index 6"),
+                    "error should name the deposit index: {}",
+                    e
+                ),
+                other => panic!("expected a backend error, got {:?}", other),
+            }
+        }
+
         #[test]
         fn eth1_data_empty_cache() {
             let spec = &E::default_spec();
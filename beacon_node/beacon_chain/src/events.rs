@@ -4,7 +4,8 @@ use serde_derive::{Deserialize, Serialize};
 use slog::{error, Logger};
 use std::marker::PhantomData;
 use std::sync::Arc;
-use types::{Attestation, Epoch, EthSpec, Hash256, SignedBeaconBlock, SignedBeaconBlockHash};
+use tokio::sync::broadcast;
+use types::{Attestation, Epoch, EthSpec, Hash256, SignedBeaconBlock, SignedBeaconBlockHash, Slot};
 pub use websocket_server::WebSocketSender;
 
 pub trait EventHandler<T: EthSpec>: Sized + Send + Sync {
@@ -22,25 +23,78 @@ impl<T: EthSpec> EventHandler<T> for WebSocketSender<T> {
     }
 }
 
-pub struct ServerSentEvents<T: EthSpec> {
+/// The number of events which may be queued for each subscriber of an `EventBus`. A subscriber
+/// which falls further behind misses the oldest events.
+pub const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Distributes the events generated by the beacon chain to in-process subscribers, such as the
+/// validator monitor or an API server.
+///
+/// Unlike the `EventHandler`, which is fixed when the chain is built, any number of subscribers
+/// may be added whilst the chain is running. Events are only cloned whilst there is at least one
+/// subscriber.
+pub struct EventBus<T: EthSpec> {
+    sender: broadcast::Sender<EventKind<T>>,
+}
+
+impl<T: EthSpec> EventBus<T> {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Returns a receiver for all events published after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventKind<T>> {
+        self.sender.subscribe()
+    }
+
+    /// Returns true if there is at least one subscriber.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Sends `kind` to every subscriber.
+    pub fn publish(&self, kind: &EventKind<T>) {
+        if self.has_subscribers() {
+            // An error means that all subscribers have been dropped since the check above.
+            let _ = self.sender.send(kind.clone());
+        }
+    }
+}
+
+impl<T: EthSpec> Default for EventBus<T> {
+    fn default() -> Self {
+        Self::new(EVENT_BUS_CAPACITY)
+    }
+}
+
+/// The queues from which server-sent events are streamed to HTTP clients.
+#[derive(Clone)]
+pub struct ServerSentEventQueues {
     // Bus<> is itself Sync + Send.  We use Mutex<> here only because of the surrounding code does
     // not enforce mutability statically (i.e. relies on interior mutability).
-    head_changed_queue: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    pub head_changed: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    pub reorg: Arc<Mutex<Bus<Reorg>>>,
+}
+
+pub struct ServerSentEvents<T: EthSpec> {
+    queues: ServerSentEventQueues,
     log: Logger,
     _phantom: PhantomData<T>,
 }
 
 impl<T: EthSpec> ServerSentEvents<T> {
-    pub fn new(log: Logger) -> (Self, Arc<Mutex<Bus<SignedBeaconBlockHash>>>) {
-        let bus = Bus::new(T::slots_per_epoch() as usize);
-        let mutex = Mutex::new(bus);
-        let arc = Arc::new(mutex);
+    pub fn new(log: Logger) -> (Self, ServerSentEventQueues) {
+        let queues = ServerSentEventQueues {
+            head_changed: Arc::new(Mutex::new(Bus::new(T::slots_per_epoch() as usize))),
+            reorg: Arc::new(Mutex::new(Bus::new(T::slots_per_epoch() as usize))),
+        };
         let this = Self {
-            head_changed_queue: arc.clone(),
+            queues: queues.clone(),
             log: log,
             _phantom: PhantomData,
         };
-        (this, arc)
+        (this, queues)
     }
 }
 
@@ -51,7 +105,7 @@ impl<T: EthSpec> EventHandler<T> for ServerSentEvents<T> {
                 current_head_beacon_block_root,
                 ..
             } => {
-                let mut guard = self.head_changed_queue.lock();
+                let mut guard = self.queues.head_changed.lock();
                 if let Err(_) = guard.try_broadcast(current_head_beacon_block_root.into()) {
                     error!(
                         self.log,
@@ -61,6 +115,17 @@ impl<T: EthSpec> EventHandler<T> for ServerSentEvents<T> {
                 }
                 Ok(())
             }
+            EventKind::BeaconChainReorg(reorg) => {
+                let mut guard = self.queues.reorg.lock();
+                if let Err(reorg) = guard.try_broadcast(reorg) {
+                    error!(
+                        self.log,
+                        "Re-org streaming queue full";
+                        "dropped_reorg" => format!("{}", reorg.new_head_root),
+                    );
+                }
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
@@ -78,13 +143,13 @@ impl<E: EthSpec> TeeEventHandler<E> {
     pub fn new(
         log: Logger,
         websockets_handler: WebSocketSender<E>,
-    ) -> Result<(Self, Arc<Mutex<Bus<SignedBeaconBlockHash>>>), String> {
-        let (sse_handler, bus) = ServerSentEvents::new(log);
+    ) -> Result<(Self, ServerSentEventQueues), String> {
+        let (sse_handler, queues) = ServerSentEvents::new(log);
         let result = Self {
             websockets_handler: websockets_handler,
             sse_handler: sse_handler,
         };
-        Ok((result, bus))
+        Ok((result, queues))
     }
 }
 
@@ -108,6 +173,19 @@ impl<T: EthSpec> Default for NullEventHandler<T> {
     }
 }
 
+/// A change of head to a block which does not descend from the previous head.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Reorg {
+    /// The number of slots between the common ancestor and the previous head.
+    pub depth: u64,
+    pub common_ancestor_root: Hash256,
+    pub common_ancestor_slot: Slot,
+    pub previous_head_root: Hash256,
+    pub previous_head_slot: Slot,
+    pub new_head_root: Hash256,
+    pub new_head_slot: Slot,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(
     bound = "T: EthSpec",
@@ -121,6 +199,11 @@ pub enum EventKind<T: EthSpec> {
         current_head_beacon_block_root: Hash256,
         previous_head_beacon_block_root: Hash256,
     },
+    BeaconChainReorg(Reorg),
+    BeaconJustification {
+        epoch: Epoch,
+        root: Hash256,
+    },
     BeaconFinalization {
         epoch: Epoch,
         root: Hash256,
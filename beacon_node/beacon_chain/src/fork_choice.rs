@@ -2,14 +2,17 @@ mod checkpoint_manager;
 
 use crate::{errors::BeaconChainError, metrics, BeaconChain, BeaconChainTypes};
 use checkpoint_manager::{get_effective_balances, CheckpointManager, CheckpointWithBalances};
-use parking_lot::{RwLock, RwLockReadGuard};
-use proto_array_fork_choice::{core::ProtoArray, ProtoArrayForkChoice};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use proto_array_fork_choice::{core::ProtoArray, ProtoArrayForkChoice, ViableHead};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use state_processing::common::get_indexed_attestation;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use store::{DBColumn, Error as StoreError, StoreItem};
-use types::{BeaconBlock, BeaconState, BeaconStateError, Epoch, Hash256, IndexedAttestation, Slot};
+use types::{
+    BeaconBlock, BeaconState, BeaconStateError, Epoch, EthSpec, Hash256, IndexedAttestation, Slot,
+};
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -36,11 +39,16 @@ pub struct ForkChoice<T: BeaconChainTypes> {
     /// whenever the struct was instantiated.
     genesis_block_root: Hash256,
     checkpoint_manager: RwLock<CheckpointManager>,
+    /// The target root for attestations to each `(block_root, target_epoch)`, i.e., the ancestor
+    /// of `block_root` at the start of `target_epoch`.
+    ///
+    /// Entries prior to the finalized epoch are removed on `Self::prune`.
+    target_cache: Mutex<HashMap<(Hash256, Epoch), Option<Hash256>>>,
     _phantom: PhantomData<T>,
 }
 
 impl<T: BeaconChainTypes> PartialEq for ForkChoice<T> {
-    /// This implementation ignores the `store`.
+    /// This implementation ignores the `store` and the `target_cache`.
     fn eq(&self, other: &Self) -> bool {
         self.backend == other.backend
             && self.genesis_block_root == other.genesis_block_root
@@ -68,6 +76,7 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
             backend,
             genesis_block_root,
             checkpoint_manager: RwLock::new(CheckpointManager::new(genesis_checkpoint)),
+            target_cache: Mutex::new(HashMap::new()),
             _phantom: PhantomData,
         }
     }
@@ -112,6 +121,36 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         self.backend.block_slot_and_state_root(block_root)
     }
 
+    /// Returns the root of the block at `slot` in the chain ending at `block_root`, or of the
+    /// latest block prior to `slot` if it was skipped.
+    pub fn ancestor_at_slot(&self, block_root: &Hash256, slot: Slot) -> Option<Hash256> {
+        self.backend.ancestor_at_slot(block_root, slot)
+    }
+
+    /// Returns the root of the block at the start of `target_epoch` in the chain ending at
+    /// `block_root`, which is the only valid target root for an attestation to `block_root` in
+    /// `target_epoch`.
+    ///
+    /// The result is cached, so that the many attestations to the same block do not each walk
+    /// the chain.
+    pub fn target_root(&self, block_root: Hash256, target_epoch: Epoch) -> Option<Hash256> {
+        if let Some(target_root) = self.target_cache.lock().get(&(block_root, target_epoch)) {
+            metrics::inc_counter(&metrics::FORK_CHOICE_TARGET_CACHE_HITS);
+            return *target_root;
+        }
+        metrics::inc_counter(&metrics::FORK_CHOICE_TARGET_CACHE_MISSES);
+
+        let target_root = self.ancestor_at_slot(
+            &block_root,
+            target_epoch.start_slot(T::EthSpec::slots_per_epoch()),
+        );
+        self.target_cache
+            .lock()
+            .insert((block_root, target_epoch), target_root);
+
+        target_root
+    }
+
     /// Process all attestations in the given `block`.
     ///
     /// Assumes the block (and therefore its attestations) are valid. It is a logic error to
@@ -213,13 +252,24 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         self.backend.latest_message(validator_index)
     }
 
-    /// Trigger a prune on the underlying fork choice backend.
+    /// Trigger a prune on the underlying fork choice backend and the target cache.
     pub fn prune(&self) -> Result<()> {
-        let finalized_root = self.checkpoint_manager.read().current.finalized.root;
+        let finalized = self.checkpoint_manager.read().current.finalized.clone();
+        let finalized_root = finalized.root;
+
+        self.target_cache
+            .lock()
+            .retain(|(_, target_epoch), _| *target_epoch >= finalized.epoch);
 
         self.backend.maybe_prune(finalized_root).map_err(Into::into)
     }
 
+    /// Returns the leaves of the block tree which are viable to become the head, with their
+    /// weights as of the last run of fork choice.
+    pub fn viable_heads(&self) -> Vec<ViableHead> {
+        self.backend.viable_heads()
+    }
+
     /// Returns a read-lock to the core `ProtoArray` struct.
     ///
     /// Should only be used when encoding/decoding during troubleshooting.
@@ -246,6 +296,7 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
             backend,
             genesis_block_root: ssz_container.genesis_block_root,
             checkpoint_manager: RwLock::new(ssz_container.checkpoint_manager),
+            target_cache: Mutex::new(HashMap::new()),
             _phantom: PhantomData,
         })
     }
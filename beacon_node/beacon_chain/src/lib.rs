@@ -2,11 +2,15 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod attestation_data_cache;
 pub mod attestation_verification;
 mod beacon_chain;
 mod beacon_snapshot;
 mod block_verification;
 pub mod builder;
+mod chain_health;
+mod divergence_guard;
+pub mod epoch_summary;
 mod errors;
 pub mod eth1_chain;
 pub mod events;
@@ -20,6 +24,7 @@ mod observed_attesters;
 mod observed_block_producers;
 mod persisted_beacon_chain;
 mod shuffling_cache;
+mod skip_slot_cache;
 mod snapshot_cache;
 pub mod test_utils;
 mod timeout_rw_lock;
@@ -80,6 +80,28 @@ lazy_static! {
         "Number of attestations in a block"
     );
 
+    /*
+     * Block Packing
+     */
+    pub static ref BLOCK_PACKING_INCLUDED: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_block_packing_included",
+        "Count of operations included in the last locally produced block",
+        &["operation"]
+    );
+    pub static ref BLOCK_PACKING_AVAILABLE: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_block_packing_available",
+        "Count of operations available in the op pool when the last block was produced",
+        &["operation"]
+    );
+    pub static ref BLOCK_PACKING_ATTESTATION_REWARD: Result<IntGauge> = try_create_int_gauge(
+        "beacon_block_packing_attestation_reward_gwei",
+        "Estimated proposer reward for the attestations in the last locally produced block"
+    );
+    pub static ref BLOCK_PACKING_AVAILABLE_ATTESTATION_REWARD: Result<IntGauge> = try_create_int_gauge(
+        "beacon_block_packing_available_attestation_reward_gwei",
+        "Estimated proposer reward for including every valid attestation in the op pool"
+    );
+
     /*
      * Unaggregated Attestation Verification
      */
@@ -112,6 +134,16 @@ lazy_static! {
         "Full runtime of aggregated attestation gossip verification"
     );
 
+    /*
+     * Gossip Clock Disparity
+     */
+    pub static ref GOSSIP_CLOCK_DISPARITY_TOLERATED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_gossip_clock_disparity_tolerated_total",
+        "Count of gossip messages from outside the permissible slots of our clock which were only \
+        accepted due to the MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance",
+        &["type"]
+    );
+
     /*
      * General Attestation Processing
      */
@@ -180,6 +212,15 @@ lazy_static! {
         "Time spent on the signature verification of attestation processing"
     );
 
+    pub static ref ATTESTATION_DATA_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_data_cache_hits_total",
+        "Count of attestations whose data had already been verified"
+    );
+    pub static ref ATTESTATION_DATA_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_data_cache_misses_total",
+        "Count of attestations whose data had not already been verified"
+    );
+
     /*
      * Shuffling cache
      */
@@ -188,6 +229,22 @@ lazy_static! {
     pub static ref SHUFFLING_CACHE_MISSES: Result<IntCounter> =
         try_create_int_counter("beacon_shuffling_cache_misses_total", "Count of times shuffling cache fulfils request");
 
+    /*
+     * Skip slot cache
+     */
+    pub static ref SKIP_SLOT_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "beacon_skip_slot_cache_hits_total",
+        "Count of times a state was obtained from an already-advanced head state"
+    );
+    pub static ref SKIP_SLOT_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "beacon_skip_slot_cache_misses_total",
+        "Count of times the head state had to be advanced through skip slots on demand"
+    );
+    pub static ref ADVANCE_HEAD_STATE_TIMES: Result<Histogram> = try_create_histogram(
+        "beacon_advance_head_state_seconds",
+        "Time taken to advance the head state through skip slots ahead of time"
+    );
+
     /*
      * Attestation Production
      */
@@ -226,6 +283,11 @@ lazy_static! {
         "beacon_fork_choice_reorg_total",
         "Count of occasions fork choice has switched to a different chain"
     );
+    pub static ref FORK_CHOICE_REORG_DEPTH: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_fork_choice_reorg_depth_total",
+        "Count of re-orgs, labelled by the number of slots reverted",
+        &["depth"]
+    );
     pub static ref FORK_CHOICE_TIMES: Result<Histogram> =
         try_create_histogram("beacon_fork_choice_seconds", "Full runtime of fork choice");
     pub static ref FORK_CHOICE_FIND_HEAD_TIMES: Result<Histogram> =
@@ -238,6 +300,14 @@ lazy_static! {
         "beacon_fork_choice_process_attestation_seconds",
         "Time taken to add an attestation to fork choice"
     );
+    pub static ref FORK_CHOICE_TARGET_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "beacon_fork_choice_target_cache_hits_total",
+        "Count of times the target root of an attestation was found in the cache"
+    );
+    pub static ref FORK_CHOICE_TARGET_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "beacon_fork_choice_target_cache_misses_total",
+        "Count of times the target root of an attestation was not found in the cache"
+    );
     pub static ref BALANCES_CACHE_HITS: Result<IntCounter> =
         try_create_int_counter("beacon_balances_cache_hits_total", "Count of times balances cache fulfils request");
     pub static ref BALANCES_CACHE_MISSES: Result<IntCounter> =
@@ -252,6 +322,14 @@ lazy_static! {
         try_create_histogram("beacon_persist_op_pool", "Time taken to persist the operations pool");
     pub static ref PERSIST_ETH1_CACHE: Result<Histogram> =
         try_create_histogram("beacon_persist_eth1_cache", "Time taken to persist the eth1 caches");
+    pub static ref PERSIST_NAIVE_AGGREGATION_POOL: Result<Histogram> = try_create_histogram(
+        "beacon_persist_naive_aggregation_pool",
+        "Time taken to persist the naive aggregation pool"
+    );
+    pub static ref PERSIST_SHUFFLING_CACHE: Result<Histogram> = try_create_histogram(
+        "beacon_persist_shuffling_cache",
+        "Time taken to persist the shuffling cache"
+    );
     pub static ref PERSIST_FORK_CHOICE: Result<Histogram> =
         try_create_histogram("beacon_persist_fork_choice", "Time taken to persist the fork choice struct");
 
@@ -261,6 +339,14 @@ lazy_static! {
     pub static ref DEFAULT_ETH1_VOTES: Result<IntCounter> =
         try_create_int_counter("beacon_eth1_default_votes", "Count of times we have voted default value for eth1 data");
 
+    /*
+     * Divergence guard
+     */
+    pub static ref HALTED_ON_DIVERGENCE: Result<IntGauge> = try_create_int_gauge(
+        "beacon_chain_halted_on_divergence",
+        "Set to 1 if block production and attestation have been halted because the chain conflicts with the weak subjectivity checkpoint or trusted peers"
+    );
+
     /*
      * Chain Head
      */
@@ -325,6 +411,34 @@ lazy_static! {
         "Ratio of head-attesting balances to total balances"
     );
 
+    /*
+     * Chain Health Metrics (updated every slot)
+     */
+    pub static ref HEALTH_HEAD_SLOT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_health_head_slot",
+        "Slot of the head block, updated every slot"
+    );
+    pub static ref HEALTH_FINALIZED_EPOCH: Result<IntGauge> = try_create_int_gauge(
+        "beacon_health_finalized_epoch",
+        "Finalized epoch of the head state, updated every slot"
+    );
+    pub static ref HEALTH_JUSTIFICATION_BITS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_health_justification_bits",
+        "Justification bits of the head state as an integer (bit 0 is the current epoch)"
+    );
+    pub static ref HEALTH_SYNC_DISTANCE: Result<IntGauge> = try_create_int_gauge(
+        "beacon_health_sync_distance",
+        "Count of slots between the head slot and the current slot"
+    );
+    pub static ref HEALTH_SECONDS_SINCE_LAST_BLOCK_IMPORT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_health_seconds_since_last_block_import",
+        "Seconds elapsed since a block was last imported"
+    );
+    pub static ref HEALTH_PREV_EPOCH_PARTICIPATION: Result<Gauge> = try_create_float_gauge(
+        "beacon_health_prev_epoch_participation",
+        "Ratio of target-attesting balances to total balances in the previous epoch of the head"
+    );
+
     /*
      * Attestation Observation Metrics
      */
@@ -458,3 +572,15 @@ fn set_gauge_by_usize(gauge: &Result<IntGauge>, value: usize) {
 fn set_gauge_by_u64(gauge: &Result<IntGauge>, value: u64) {
     set_gauge(gauge, value as i64);
 }
+
+/// Returns the `FORK_CHOICE_REORG_DEPTH` label for a re-org which reverted `depth` slots.
+pub fn reorg_depth_bucket(depth: u64) -> &'static str {
+    match depth {
+        0..=1 => "1",
+        2 => "2",
+        3..=4 => "3-4",
+        5..=8 => "5-8",
+        9..=32 => "9-32",
+        _ => "33+",
+    }
+}
@@ -1,3 +1,4 @@
+use crate::epoch_summary::EpochSummary;
 use crate::errors::BeaconChainError;
 use crate::head_tracker::HeadTracker;
 use parking_lot::Mutex;
@@ -9,7 +10,7 @@ use std::sync::Arc;
 use std::thread;
 use store::hot_cold_store::{process_finalization, HotColdDBError};
 use store::iter::{ParentRootBlockIterator, RootsIterator};
-use store::{Error, ItemStore, StoreOp};
+use store::{get_key_for_col, Error, ItemStore, KeyValueStoreOp, StoreItem, StoreOp};
 pub use store::{HotColdDB, MemoryStore};
 use types::*;
 use types::{BeaconState, EthSpec, Hash256, Slot};
@@ -77,6 +78,7 @@ pub trait Migrate<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>:
         let mut abandoned_blocks: HashSet<SignedBeaconBlockHash> = HashSet::new();
         let mut abandoned_states: HashSet<(Slot, BeaconStateHash)> = HashSet::new();
         let mut abandoned_heads: HashSet<Hash256> = HashSet::new();
+        let mut abandoned_epoch_summaries: HashSet<BeaconStateHash> = HashSet::new();
 
         for (head_hash, head_slot) in head_tracker.heads() {
             let mut potentially_abandoned_head: Option<Hash256> = Some(head_hash);
@@ -149,10 +151,23 @@ pub trait Migrate<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>:
                         Some(state_hash) => Some((*slot, *state_hash)),
                     },
                 ));
+                // Epoch summaries are keyed by the state at the last slot of an epoch, which is
+                // only unique to the abandoned chain if one of its own blocks precedes it.
+                abandoned_epoch_summaries.extend(potentially_abandoned_blocks.iter().filter_map(
+                    |(slot, maybe_block_hash, maybe_state_hash)| match maybe_state_hash {
+                        Some(state_hash)
+                            if maybe_block_hash.is_some()
+                                && (*slot + 1) % E::slots_per_epoch() == 0 =>
+                        {
+                            Some(*state_hash)
+                        }
+                        _ => None,
+                    },
+                ));
             }
         }
 
-        let batch: Vec<StoreOp> = abandoned_blocks
+        let batch: Vec<StoreOp<E>> = abandoned_blocks
             .into_iter()
             .map(|block_hash| StoreOp::DeleteBlock(block_hash))
             .chain(
@@ -160,6 +175,13 @@ pub trait Migrate<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>:
                     .into_iter()
                     .map(|(slot, state_hash)| StoreOp::DeleteState(state_hash, slot)),
             )
+            .chain(abandoned_epoch_summaries.into_iter().map(|state_hash| {
+                let untyped_hash: Hash256 = state_hash.into();
+                StoreOp::KeyValueOp(KeyValueStoreOp::DeleteKey(get_key_for_col(
+                    EpochSummary::db_column().into(),
+                    untyped_hash.as_bytes(),
+                )))
+            }))
             .collect();
         store.do_atomically(&batch)?;
         for head_hash in abandoned_heads.into_iter() {
@@ -181,16 +203,39 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> Migrate<E, Hot, Cold> fo
 
 /// Migrator that immediately calls the store's migration function, blocking the current execution.
 ///
-/// Mostly useful for tests.
+/// Errors are not returned by `process_finalization`, so the first error since the last call to
+/// `take_error` is kept for the caller to check.
 pub struct BlockingMigrator<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> {
     db: Arc<HotColdDB<E, Hot, Cold>>,
+    error: Mutex<Option<BeaconChainError>>,
+    log: Logger,
+}
+
+impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BlockingMigrator<E, Hot, Cold> {
+    /// Returns the first migration or pruning error since the last call, if any.
+    pub fn take_error(&self) -> Option<BeaconChainError> {
+        self.error.lock().take()
+    }
+
+    fn record_error(&self, error: BeaconChainError) {
+        warn!(
+            self.log,
+            "Database migration failed";
+            "error" => format!("{:?}", error)
+        );
+        self.error.lock().get_or_insert(error);
+    }
 }
 
 impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> Migrate<E, Hot, Cold>
     for BlockingMigrator<E, Hot, Cold>
 {
-    fn new(db: Arc<HotColdDB<E, Hot, Cold>>, _: Logger) -> Self {
-        BlockingMigrator { db }
+    fn new(db: Arc<HotColdDB<E, Hot, Cold>>, log: Logger) -> Self {
+        BlockingMigrator {
+            db,
+            error: Mutex::new(None),
+            log,
+        }
     }
 
     fn process_finalization(
@@ -202,9 +247,16 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> Migrate<E, Hot, Cold>
         old_finalized_block_hash: SignedBeaconBlockHash,
         new_finalized_block_hash: SignedBeaconBlockHash,
     ) {
-        if let Err(e) = process_finalization(self.db.clone(), state_root, &new_finalized_state) {
-            // This migrator is only used for testing, so we just log to stderr without a logger.
-            eprintln!("Migration error: {:?}", e);
+        match process_finalization(self.db.clone(), state_root, &new_finalized_state) {
+            Ok(()) => {}
+            Err(Error::HotColdDBError(HotColdDBError::FreezeSlotUnaligned(slot))) => {
+                debug!(
+                    self.log,
+                    "Database migration postponed, unaligned finalized block";
+                    "slot" => slot.as_u64()
+                );
+            }
+            Err(e) => self.record_error(e.into()),
         }
 
         if let Err(e) = Self::prune_abandoned_forks(
@@ -214,7 +266,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> Migrate<E, Hot, Cold>
             new_finalized_block_hash,
             new_finalized_state.slot,
         ) {
-            eprintln!("Pruning error: {:?}", e);
+            self.record_error(e);
         }
     }
 }
@@ -352,6 +404,15 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
                     Ok(()) => {}
                     Err(e) => warn!(log, "Block pruning failed: {:?}", e),
                 }
+
+                // Reclaim the space used by the blocks and states that were just deleted.
+                if let Err(e) = db.compact_if_needed() {
+                    warn!(
+                        log,
+                        "Database compaction failed";
+                        "error" => format!("{:?}", e)
+                    );
+                }
             }
         });
 
@@ -1,6 +1,9 @@
 use crate::metrics;
 use parking_lot::RwLock;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
 use std::collections::HashMap;
+use store::{DBColumn, Error as StoreError, StoreItem};
 use types::{Attestation, AttestationData, EthSpec, Slot};
 
 /// The number of slots that will be stored in the pool.
@@ -265,6 +268,65 @@ impl<E: EthSpec> NaiveAggregationPool<E> {
     }
 }
 
+/// SSZ-serializable version of `NaiveAggregationPool`, allowing the unaggregated attestations to
+/// survive a restart.
+#[derive(Encode, Decode)]
+pub struct PersistedNaiveAggregationPool<E: EthSpec> {
+    lowest_permissible_slot: Slot,
+    /// The aggregate of each `AttestationData`.
+    attestations: Vec<Attestation<E>>,
+}
+
+impl<E: EthSpec> PersistedNaiveAggregationPool<E> {
+    /// Convert a `NaiveAggregationPool` into serializable form.
+    pub fn from_naive_aggregation_pool(pool: &NaiveAggregationPool<E>) -> Self {
+        let attestations = pool
+            .maps
+            .read()
+            .values()
+            .flat_map(|map| map.map.values().cloned())
+            .collect();
+
+        Self {
+            lowest_permissible_slot: *pool.lowest_permissible_slot.read(),
+            attestations,
+        }
+    }
+
+    /// Reconstruct a `NaiveAggregationPool`.
+    ///
+    /// The attestations are restored as they were persisted, without being pruned. The pool is
+    /// pruned as usual once the chain processes the current slot.
+    pub fn into_naive_aggregation_pool(self) -> NaiveAggregationPool<E> {
+        let mut maps: HashMap<Slot, AggregatedAttestationMap<E>> = HashMap::new();
+        for attestation in self.attestations {
+            maps.entry(attestation.data.slot)
+                .or_insert_with(|| AggregatedAttestationMap::new(0))
+                .map
+                .insert(attestation.data.clone(), attestation);
+        }
+
+        NaiveAggregationPool {
+            lowest_permissible_slot: RwLock::new(self.lowest_permissible_slot),
+            maps: RwLock::new(maps),
+        }
+    }
+}
+
+impl<E: EthSpec> StoreItem for PersistedNaiveAggregationPool<E> {
+    fn db_column() -> DBColumn {
+        DBColumn::NaiveAggregationPool
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -486,4 +548,49 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn persistence_round_trip() {
+        let mut a_0 = get_attestation(Slot::new(5));
+        let mut a_1 = a_0.clone();
+
+        let genesis_validators_root = Hash256::random();
+        sign(&mut a_0, 0, genesis_validators_root);
+        sign(&mut a_1, 1, genesis_validators_root);
+
+        let pool = NaiveAggregationPool::default();
+        pool.insert(&a_0).expect("should accept a_0");
+        pool.insert(&a_1).expect("should accept a_1");
+
+        let bytes =
+            PersistedNaiveAggregationPool::from_naive_aggregation_pool(&pool).as_store_bytes();
+        let restored = PersistedNaiveAggregationPool::<E>::from_store_bytes(&bytes)
+            .expect("should decode")
+            .into_naive_aggregation_pool();
+
+        assert_eq!(
+            restored.get(&a_0.data),
+            pool.get(&a_0.data),
+            "should restore the aggregated attestation"
+        );
+        assert_eq!(
+            *restored.lowest_permissible_slot.read(),
+            *pool.lowest_permissible_slot.read()
+        );
+
+        // Further signatures are aggregated into the restored attestation.
+        let mut a_2 = a_0.clone();
+        unset_bit(&mut a_2, 0);
+        sign(&mut a_2, 2, genesis_validators_root);
+        assert_eq!(
+            restored.insert(&a_2),
+            Ok(InsertOutcome::SignatureAggregated { committee_index: 2 }),
+            "should aggregate into the restored attestation"
+        );
+        assert_eq!(
+            restored.insert(&a_1),
+            Ok(InsertOutcome::SignatureAlreadyKnown { committee_index: 1 }),
+            "should know the restored signatures"
+        );
+    }
 }
@@ -1,5 +1,8 @@
 use crate::metrics;
 use lru::LruCache;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use store::{DBColumn, Error as StoreError, StoreItem};
 use types::{beacon_state::CommitteeCache, Epoch, Hash256};
 
 /// The size of the LRU cache that stores committee caches for quicker verification.
@@ -11,6 +14,10 @@ const CACHE_SIZE: usize = 16;
 
 /// Provides an LRU cache for `CommitteeCache`.
 ///
+/// Caches are keyed by their epoch and the root of the block at the shuffling decision slot of
+/// that epoch (see `BeaconState::shuffling_decision_slot`). This allows any state which descends
+/// from the decision block to reuse the cache, regardless of its slot or the attestation target.
+///
 /// It has been named `ShufflingCache` because `CommitteeCacheCache` is a bit weird and looks like
 /// a find/replace error.
 pub struct ShufflingCache {
@@ -24,8 +31,8 @@ impl ShufflingCache {
         }
     }
 
-    pub fn get(&mut self, epoch: Epoch, root: Hash256) -> Option<&CommitteeCache> {
-        let opt = self.cache.get(&(epoch, root));
+    pub fn get(&mut self, epoch: Epoch, decision_root: Hash256) -> Option<&CommitteeCache> {
+        let opt = self.cache.get(&(epoch, decision_root));
 
         if opt.is_some() {
             metrics::inc_counter(&metrics::SHUFFLING_CACHE_HITS);
@@ -36,11 +43,117 @@ impl ShufflingCache {
         opt
     }
 
-    pub fn insert(&mut self, epoch: Epoch, root: Hash256, committee_cache: &CommitteeCache) {
-        let key = (epoch, root);
+    pub fn insert(
+        &mut self,
+        epoch: Epoch,
+        decision_root: Hash256,
+        committee_cache: &CommitteeCache,
+    ) {
+        let key = (epoch, decision_root);
 
         if !self.cache.contains(&key) {
             self.cache.put(key, committee_cache.clone());
         }
     }
 }
+
+/// SSZ-serializable version of `ShufflingCache`, allowing the committee caches to survive a
+/// restart.
+#[derive(Encode, Decode)]
+pub struct PersistedShufflingCache {
+    /// Entries ordered from least to most recently used.
+    entries: Vec<PersistedCommitteeCache>,
+}
+
+#[derive(Encode, Decode)]
+struct PersistedCommitteeCache {
+    epoch: Epoch,
+    decision_root: Hash256,
+    committee_cache: CommitteeCache,
+}
+
+impl PersistedShufflingCache {
+    /// Convert a `ShufflingCache` into serializable form.
+    pub fn from_shuffling_cache(shuffling_cache: &ShufflingCache) -> Self {
+        let mut entries = shuffling_cache
+            .cache
+            .iter()
+            .map(
+                |((epoch, decision_root), committee_cache)| PersistedCommitteeCache {
+                    epoch: *epoch,
+                    decision_root: *decision_root,
+                    committee_cache: committee_cache.clone(),
+                },
+            )
+            .collect::<Vec<_>>();
+
+        // The LRU iterates from most to least recently used.
+        entries.reverse();
+
+        Self { entries }
+    }
+
+    /// Reconstruct a `ShufflingCache`, preserving the order of use.
+    pub fn into_shuffling_cache(self) -> ShufflingCache {
+        let mut shuffling_cache = ShufflingCache::new();
+
+        for entry in self.entries {
+            shuffling_cache.insert(entry.epoch, entry.decision_root, &entry.committee_cache);
+        }
+
+        shuffling_cache
+    }
+}
+
+impl StoreItem for PersistedShufflingCache {
+    fn db_column() -> DBColumn {
+        DBColumn::ShufflingCache
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn persistence_preserves_recency() {
+        let mut cache = ShufflingCache::new();
+        for i in 0..CACHE_SIZE as u64 {
+            cache.insert(Epoch::new(i), root(i), &CommitteeCache::default());
+        }
+        // Make the oldest entry the most recently used.
+        assert!(cache.get(Epoch::new(0), root(0)).is_some());
+
+        let bytes = PersistedShufflingCache::from_shuffling_cache(&cache).as_store_bytes();
+        let mut restored = PersistedShufflingCache::from_store_bytes(&bytes)
+            .expect("should decode")
+            .into_shuffling_cache();
+
+        assert_eq!(
+            restored
+                .cache
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>(),
+            cache.cache.iter().map(|(key, _)| *key).collect::<Vec<_>>()
+        );
+
+        // The least recently used entry is evicted first.
+        let epoch = Epoch::new(CACHE_SIZE as u64);
+        restored.insert(epoch, root(0), &CommitteeCache::default());
+        assert!(restored.get(Epoch::new(1), root(1)).is_none());
+        assert!(restored.get(Epoch::new(0), root(0)).is_some());
+    }
+}
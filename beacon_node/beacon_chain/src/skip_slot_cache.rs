@@ -0,0 +1,91 @@
+use types::{BeaconState, CloneConfig, EthSpec, Hash256, Slot};
+
+/// Stores a copy of the head state which has been advanced through one or more skip slots.
+///
+/// When the head block is several slots old, producing a block (or otherwise obtaining a state at
+/// the present slot) requires running `per_slot_processing` for each of the skipped slots. By
+/// advancing the cached state by one slot at the start of each slot, the work for a block at slot
+/// `N + k` is reduced to a single slot transition, regardless of `k`.
+///
+/// Only a single state is stored, which is replaced whenever the head changes.
+pub struct SkipSlotCache<T: EthSpec> {
+    /// The root of the head block and the head state advanced to some later slot.
+    entry: Option<(Hash256, BeaconState<T>)>,
+}
+
+impl<T: EthSpec> SkipSlotCache<T> {
+    pub fn new() -> Self {
+        Self { entry: None }
+    }
+
+    /// Returns a clone of the state advanced from `head_block_root` to the latest slot which is
+    /// less than or equal to `max_slot`, if any.
+    pub fn get_cloned(&self, head_block_root: Hash256, max_slot: Slot) -> Option<BeaconState<T>> {
+        self.entry
+            .as_ref()
+            .filter(|(root, state)| *root == head_block_root && state.slot <= max_slot)
+            .map(|(_, state)| state.clone_with(CloneConfig::all()))
+    }
+
+    /// Stores `state`, which has been advanced from `head_block_root`.
+    ///
+    /// The existing state is only replaced if it is for a different head block or if `state` has
+    /// been advanced further.
+    pub fn insert(&mut self, head_block_root: Hash256, state: BeaconState<T>) {
+        let is_better = self.entry.as_ref().map_or(true, |(root, existing)| {
+            *root != head_block_root || existing.slot < state.slot
+        });
+
+        if is_better {
+            self.entry = Some((head_block_root, state));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec};
+
+    fn get_state(slot: u64) -> BeaconState<MinimalEthSpec> {
+        let spec = MinimalEthSpec::default_spec();
+        let (mut state, _keypairs) =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(1, &spec).build();
+        state.slot = Slot::new(slot);
+        state
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let head = Hash256::from_low_u64_be(1);
+        let other_head = Hash256::from_low_u64_be(2);
+        let mut cache = SkipSlotCache::new();
+
+        assert!(cache.get_cloned(head, Slot::new(4)).is_none());
+
+        cache.insert(head, get_state(3));
+        assert_eq!(
+            cache.get_cloned(head, Slot::new(4)).map(|state| state.slot),
+            Some(Slot::new(3))
+        );
+        assert!(cache.get_cloned(head, Slot::new(2)).is_none());
+        assert!(cache.get_cloned(other_head, Slot::new(4)).is_none());
+
+        // A less advanced state for the same head is ignored.
+        cache.insert(head, get_state(2));
+        assert_eq!(
+            cache.get_cloned(head, Slot::new(4)).map(|state| state.slot),
+            Some(Slot::new(3))
+        );
+
+        // A state for a new head always replaces the existing one.
+        cache.insert(other_head, get_state(1));
+        assert!(cache.get_cloned(head, Slot::new(4)).is_none());
+        assert_eq!(
+            cache
+                .get_cloned(other_head, Slot::new(4))
+                .map(|state| state.slot),
+            Some(Slot::new(1))
+        );
+    }
+}
@@ -45,8 +45,11 @@ pub type BaseHarnessType<TStoreMigrator, TEthSpec, THotStore, TColdStore> = Witn
 >;
 
 pub type HarnessType<E> = BaseHarnessType<NullMigrator, E, MemoryStore<E>, MemoryStore<E>>;
-pub type DiskHarnessType<E> =
-    BaseHarnessType<BlockingMigrator<E, LevelDB<E>, LevelDB<E>>, E, LevelDB<E>, LevelDB<E>>;
+/// A harness which migrates finalized states to the freezer database as soon as they are finalized.
+pub type MigratingHarnessType<E, Hot, Cold> =
+    BaseHarnessType<BlockingMigrator<E, Hot, Cold>, E, Hot, Cold>;
+pub type EphemeralHarnessType<E> = MigratingHarnessType<E, MemoryStore<E>, MemoryStore<E>>;
+pub type DiskHarnessType<E> = MigratingHarnessType<E, LevelDB<E>, LevelDB<E>>;
 
 /// Indicates how the `BeaconChainHarness` should produce blocks.
 #[derive(Clone, Copy, Debug)]
@@ -139,11 +142,19 @@ impl<E: EthSpec> BeaconChainHarness<HarnessType<E>> {
     }
 }
 
-impl<E: EthSpec> BeaconChainHarness<DiskHarnessType<E>> {
+impl<E, Hot, Cold> BeaconChainHarness<MigratingHarnessType<E, Hot, Cold>>
+where
+    E: EthSpec,
+    Hot: ItemStore<E>,
+    Cold: ItemStore<E>,
+{
     /// Instantiate a new harness with `validator_count` initial validators.
-    pub fn new_with_disk_store(
+    ///
+    /// The `store` may use any backend, e.g. `HotColdDB::open_ephemeral` keeps the database in
+    /// memory.
+    pub fn new_with_store(
         eth_spec_instance: E,
-        store: Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>,
+        store: Arc<HotColdDB<E, Hot, Cold>>,
         keypairs: Vec<Keypair>,
     ) -> Self {
         let data_dir = tempdir().expect("should create temporary data_dir");
@@ -180,10 +191,10 @@ impl<E: EthSpec> BeaconChainHarness<DiskHarnessType<E>> {
         }
     }
 
-    /// Instantiate a new harness with `validator_count` initial validators.
-    pub fn resume_from_disk_store(
+    /// Instantiate a harness from the beacon chain persisted in `store`.
+    pub fn resume_from_store(
         eth_spec_instance: E,
-        store: Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>,
+        store: Arc<HotColdDB<E, Hot, Cold>>,
         keypairs: Vec<Keypair>,
         data_dir: TempDir,
     ) -> Self {
@@ -457,7 +468,7 @@ where
 
         let (block, state) = self
             .chain
-            .produce_block_on_state(state, slot, randao_reveal)
+            .produce_block_on_state(state, slot, randao_reveal, None)
             .expect("should produce block");
 
         let signed_block = block.sign(sk, &state.fork, state.genesis_validators_root, &self.spec);
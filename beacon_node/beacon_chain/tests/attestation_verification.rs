@@ -918,6 +918,33 @@ fn fork_choice_verification() {
         if slot == current_slot - 1
     );
 
+    /*
+     * This test ensures that:
+     *
+     * Spec v0.12.2
+     *
+     * # LMD vote must be consistent with FFG vote target
+     * target_slot = compute_start_slot_at_epoch(target.epoch)
+     * assert target.root == get_ancestor(store, attestation.data.beacon_block_root, target_slot)
+     */
+
+    let valid_target_root = attestation.attestation().data.target.root;
+    assert_invalid!(
+        "attestation with a target root which is not an ancestor",
+        {
+            let mut a = attestation.clone();
+
+            let indexed = a.__indexed_attestation_mut();
+            indexed.data.target.root = future_block.canonical_root();
+            a
+        },
+        AttnError::InvalidTargetRoot {
+            attestation: target_root,
+            expected,
+        }
+        if target_root == future_block.canonical_root() && expected == Some(valid_target_root)
+    );
+
     // Note: we're not checking the "attestations can only affect the fork choice of subsequent
     // slots" part of the spec, we do this upstream.
 
@@ -4,8 +4,9 @@
 extern crate lazy_static;
 
 use beacon_chain::attestation_verification::Error as AttnError;
+use beacon_chain::epoch_summary::EpochSummary;
 use beacon_chain::test_utils::{
-    AttestationStrategy, BeaconChainHarness, BlockStrategy, DiskHarnessType,
+    AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
 };
 use beacon_chain::BeaconSnapshot;
 use beacon_chain::StateSkipConfig;
@@ -15,8 +16,10 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
 use store::{
+    hot_cold_store::{RESTORE_POINT_INFO_DB_KEY, SCHEMA_VERSION_DB_KEY},
     iter::{BlockRootsIterator, StateRootsIterator},
-    HotColdDB, LevelDB, StoreConfig,
+    DBColumn, HotColdDB, ItemStore, KeyValueStore, LevelDB, MemoryStore, PartialBeaconStateV1,
+    StoreConfig,
 };
 use tempfile::{tempdir, TempDir};
 use tree_hash::TreeHash;
@@ -33,25 +36,36 @@ lazy_static! {
 }
 
 type E = MinimalEthSpec;
-type TestHarness = BeaconChainHarness<DiskHarnessType<E>>;
+type TestHarness = BeaconChainHarness<EphemeralHarnessType<E>>;
 
-fn get_store(db_path: &TempDir) -> Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>> {
+fn get_store() -> Arc<HotColdDB<E, MemoryStore<E>, MemoryStore<E>>> {
+    let spec = MinimalEthSpec::default_spec();
+    let config = StoreConfig::default();
+    let log = NullLoggerBuilder.build().expect("logger should build");
+    Arc::new(HotColdDB::open_ephemeral(config, spec, log).expect("memory store should initialize"))
+}
+
+fn get_disk_store(db_path: &TempDir) -> Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>> {
+    get_disk_store_with_config(db_path, StoreConfig::default())
+        .expect("disk store should initialize")
+}
+
+fn get_disk_store_with_config(
+    db_path: &TempDir,
+    config: StoreConfig,
+) -> Result<Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>, store::Error> {
     let spec = MinimalEthSpec::default_spec();
     let hot_path = db_path.path().join("hot_db");
     let cold_path = db_path.path().join("cold_db");
-    let config = StoreConfig::default();
     let log = NullLoggerBuilder.build().expect("logger should build");
-    Arc::new(
-        HotColdDB::open(&hot_path, &cold_path, config, spec, log)
-            .expect("disk store should initialize"),
-    )
+    HotColdDB::open(&hot_path, &cold_path, config, spec, log).map(Arc::new)
 }
 
 fn get_harness(
-    store: Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>,
+    store: Arc<HotColdDB<E, MemoryStore<E>, MemoryStore<E>>>,
     validator_count: usize,
 ) -> TestHarness {
-    let harness = BeaconChainHarness::new_with_disk_store(
+    let harness = BeaconChainHarness::new_with_store(
         MinimalEthSpec,
         store,
         KEYPAIRS[0..validator_count].to_vec(),
@@ -63,8 +77,7 @@ fn get_harness(
 #[test]
 fn full_participation_no_skips() {
     let num_blocks_produced = E::slots_per_epoch() * 5;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     harness.extend_chain(
@@ -83,8 +96,7 @@ fn full_participation_no_skips() {
 fn randomised_skips() {
     let num_slots = E::slots_per_epoch() * 5;
     let mut num_blocks_produced = 0;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
     let rng = &mut XorShiftRng::from_seed([42; 16]);
 
@@ -117,10 +129,64 @@ fn randomised_skips() {
     check_iterators(&harness);
 }
 
+#[test]
+fn frozen_roots_match_iterators() {
+    let num_slots = E::slots_per_historical_root() as u64 * 3;
+    let store = get_store();
+    let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+
+    // Skip every fifth slot, so that the freezer holds roots for skipped slots.
+    for slot in 1..=num_slots {
+        if slot % 5 != 0 {
+            harness.extend_chain(
+                1,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            );
+        }
+        harness.advance_slot();
+    }
+
+    assert!(
+        store.get_latest_restore_point_slot() > 0,
+        "the freezer should hold a restore point"
+    );
+
+    let mut num_frozen = 0;
+    for result in harness
+        .chain
+        .rev_iter_block_roots()
+        .expect("should get iter")
+    {
+        let (block_root, slot) = result.expect("should get block root");
+        if let Some(frozen_root) = store
+            .get_frozen_block_root(slot)
+            .expect("should read freezer")
+        {
+            assert_eq!(frozen_root, block_root, "block root at slot {}", slot);
+            num_frozen += 1;
+        }
+    }
+    assert_eq!(num_frozen, store.get_latest_restore_point_slot().as_u64());
+
+    for result in harness
+        .chain
+        .rev_iter_state_roots()
+        .expect("should get iter")
+    {
+        let (state_root, slot) = result.expect("should get state root");
+        if let Some(frozen_root) = store
+            .get_frozen_state_root(slot)
+            .expect("should read freezer")
+        {
+            assert_eq!(frozen_root, state_root, "state root at slot {}", slot);
+        }
+    }
+}
+
 #[test]
 fn long_skip() {
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     // Number of blocks to create in the first run, intentionally not falling on an epoch
@@ -170,8 +236,7 @@ fn long_skip() {
 #[test]
 fn randao_genesis_storage() {
     let validator_count = 8;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), validator_count);
 
     let num_slots = E::slots_per_epoch() * (E::epochs_per_historical_vector() - 1) as u64;
@@ -232,8 +297,13 @@ fn split_slot_restore() {
     let db_path = tempdir().unwrap();
 
     let split_slot = {
-        let store = get_store(&db_path);
-        let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+        let store = get_disk_store(&db_path);
+        let harness = BeaconChainHarness::new_with_store(
+            MinimalEthSpec,
+            store.clone(),
+            KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec(),
+        );
+        harness.advance_slot();
 
         let num_blocks = 4 * E::slots_per_epoch();
 
@@ -248,19 +318,182 @@ fn split_slot_restore() {
     assert_ne!(split_slot, Slot::new(0));
 
     // Re-open the store
-    let store = get_store(&db_path);
+    let store = get_disk_store(&db_path);
 
     assert_eq!(store.get_split_slot(), split_slot);
 }
 
+// Check that decreasing the restore point frequency leaves historic states intact and that the
+// missing restore points can be reconstructed, but that the frequency can't be increased.
+#[test]
+fn reconstruct_restore_points_after_decreasing_frequency() {
+    check_reconstruct_restore_points_after_decreasing_frequency(false);
+}
+
+// A freezer DB created before the `RestorePointInfo` was stored should be re-indexed from the
+// frequency of its restore points, not the configured one.
+#[test]
+fn reconstruct_legacy_restore_points_after_decreasing_frequency() {
+    check_reconstruct_restore_points_after_decreasing_frequency(true);
+}
+
+fn check_reconstruct_restore_points_after_decreasing_frequency(legacy: bool) {
+    let db_path = tempdir().unwrap();
+    let store_config = |slots_per_restore_point| StoreConfig {
+        slots_per_restore_point,
+        ..StoreConfig::default()
+    };
+
+    let (split_slot, state_roots) = {
+        let store = get_disk_store_with_config(&db_path, store_config(64)).unwrap();
+        let harness = BeaconChainHarness::new_with_store(
+            MinimalEthSpec,
+            store.clone(),
+            KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec(),
+        );
+        harness.advance_slot();
+
+        let num_blocks = 20 * E::slots_per_epoch();
+
+        harness.extend_chain(
+            num_blocks as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        );
+
+        let split_slot = store.get_split_slot();
+        let state_roots = (0..split_slot.as_u64())
+            .map(|slot| {
+                store
+                    .load_cold_state_by_slot(Slot::new(slot))
+                    .expect("should load cold state")
+                    .canonical_root()
+            })
+            .collect::<Vec<_>>();
+
+        (split_slot, state_roots)
+    };
+    assert!(split_slot > 64);
+
+    if legacy {
+        let cold_db = LevelDB::<E>::open(&db_path.path().join("cold_db")).unwrap();
+        cold_db
+            .key_delete(
+                DBColumn::BeaconMeta.into(),
+                RESTORE_POINT_INFO_DB_KEY.as_bytes(),
+            )
+            .unwrap();
+    }
+
+    let check_states = |store: &HotColdDB<E, LevelDB<E>, LevelDB<E>>| {
+        for (slot, state_root) in state_roots.iter().enumerate() {
+            let state = store
+                .load_cold_state_by_slot(Slot::new(slot as u64))
+                .expect("should load cold state");
+            assert_eq!(state.canonical_root(), *state_root, "slot {}", slot);
+        }
+    };
+
+    {
+        let store = get_disk_store_with_config(&db_path, store_config(16)).unwrap();
+
+        let info = store.restore_point_info();
+        assert_eq!(info.slots_per_restore_point, 16);
+        assert_eq!(info.reconstruction_target, split_slot);
+        assert!(!info.reconstruction_complete());
+        check_states(&store);
+
+        while !store
+            .reconstruct_restore_points(1)
+            .expect("should reconstruct restore points")
+        {}
+        assert!(store.restore_point_info().reconstruction_complete());
+    }
+
+    let store = get_disk_store_with_config(&db_path, store_config(16)).unwrap();
+    assert!(store.restore_point_info().reconstruction_complete());
+    check_states(&store);
+    drop(store);
+
+    assert!(get_disk_store_with_config(&db_path, store_config(32)).is_err());
+}
+
+// A freezer DB written before the validator registry was chunked should be migrated when opened.
+#[test]
+fn migrate_legacy_restore_points() {
+    let db_path = tempdir().unwrap();
+    let store_config = StoreConfig {
+        slots_per_restore_point: 64,
+        ..StoreConfig::default()
+    };
+
+    let (split_slot, states) = {
+        let store = get_disk_store_with_config(&db_path, store_config.clone()).unwrap();
+        let harness = BeaconChainHarness::new_with_store(
+            MinimalEthSpec,
+            store.clone(),
+            KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec(),
+        );
+        harness.advance_slot();
+        harness.extend_chain(
+            20 * E::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        );
+
+        let split_slot = store.get_split_slot();
+        let states = (0..split_slot.as_u64())
+            .map(|slot| {
+                store
+                    .load_cold_state_by_slot(Slot::new(slot))
+                    .expect("should load cold state")
+            })
+            .collect::<Vec<_>>();
+
+        (split_slot, states)
+    };
+    assert!(split_slot > 64);
+
+    // rewrite the restore points in the schema version 1 encoding
+    {
+        let cold_db = LevelDB::<E>::open(&db_path.path().join("cold_db")).unwrap();
+        for state in states.iter().step_by(64) {
+            cold_db
+                .put(
+                    &state.canonical_root(),
+                    &PartialBeaconStateV1::from_state_forgetful(state),
+                )
+                .unwrap();
+        }
+        cold_db
+            .key_delete(
+                DBColumn::BeaconMeta.into(),
+                SCHEMA_VERSION_DB_KEY.as_bytes(),
+            )
+            .unwrap();
+    }
+
+    let store = get_disk_store_with_config(&db_path, store_config).unwrap();
+    for (slot, state) in states.iter().enumerate() {
+        let migrated_state = store
+            .load_cold_state_by_slot(Slot::new(slot as u64))
+            .expect("should load migrated state");
+        assert_eq!(
+            migrated_state.canonical_root(),
+            state.canonical_root(),
+            "slot {}",
+            slot
+        );
+    }
+}
+
 // Check attestation processing and `load_epoch_boundary_state` in the presence of a split DB.
 // This is a bit of a monster test in that it tests lots of different things, but until they're
 // tested elsewhere, this is as good a place as any.
 #[test]
 fn epoch_boundary_state_attestation_processing() {
     let num_blocks_produced = E::slots_per_epoch() * 5;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     let late_validators = vec![0, 1];
@@ -345,8 +578,7 @@ fn epoch_boundary_state_attestation_processing() {
 
 #[test]
 fn delete_blocks_and_states() {
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     let unforked_blocks = 4 * E::slots_per_epoch();
@@ -447,9 +679,8 @@ fn multi_epoch_fork_valid_blocks_test(
     num_fork1_blocks: usize,
     num_fork2_blocks: usize,
     num_fork1_validators: usize,
-) -> (TempDir, TestHarness, Hash256, Hash256) {
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+) -> (TestHarness, Hash256, Hash256) {
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     // Create the initial portion of the chain
@@ -472,7 +703,7 @@ fn multi_epoch_fork_valid_blocks_test(
         num_fork2_blocks,
     );
 
-    (db_path, harness, head1, head2)
+    (harness, head1, head2)
 }
 
 // This is the minimal test of block production with different shufflings.
@@ -502,8 +733,7 @@ fn block_production_different_shuffling_long() {
 // This checks the correctness of the shuffling compatibility memoization.
 #[test]
 fn multiple_attestations_per_block() {
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store, HIGH_VALIDATOR_COUNT);
     let chain = &harness.chain;
 
@@ -534,8 +764,7 @@ fn multiple_attestations_per_block() {
 
 #[test]
 fn shuffling_compatible_linear_chain() {
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     // Skip the block at the end of the first epoch.
@@ -558,8 +787,7 @@ fn shuffling_compatible_linear_chain() {
 
 #[test]
 fn shuffling_compatible_missing_pivot_block() {
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
 
     // Skip the block at the end of the first epoch.
@@ -590,7 +818,7 @@ fn shuffling_compatible_missing_pivot_block() {
 #[test]
 fn shuffling_compatible_simple_fork() {
     let slots_per_epoch = E::slots_per_epoch() as usize;
-    let (db_path, harness, head1, head2) = multi_epoch_fork_valid_blocks_test(
+    let (harness, head1, head2) = multi_epoch_fork_valid_blocks_test(
         2 * slots_per_epoch,
         3 * slots_per_epoch,
         3 * slots_per_epoch,
@@ -604,14 +832,12 @@ fn shuffling_compatible_simple_fork() {
     check_shuffling_compatible(&harness, &head1_state, head2, false, false, None, None);
     check_shuffling_compatible(&harness, &head2_state, head1, false, false, None, None);
     check_shuffling_compatible(&harness, &head2_state, head2, true, true, None, None);
-
-    drop(db_path);
 }
 
 #[test]
 fn shuffling_compatible_short_fork() {
     let slots_per_epoch = E::slots_per_epoch() as usize;
-    let (db_path, harness, head1, head2) = multi_epoch_fork_valid_blocks_test(
+    let (harness, head1, head2) = multi_epoch_fork_valid_blocks_test(
         2 * slots_per_epoch - 2,
         slots_per_epoch + 2,
         slots_per_epoch + 2,
@@ -636,8 +862,6 @@ fn shuffling_compatible_short_fork() {
         Some(2 * E::slots_per_epoch() - 2),
         None,
     );
-
-    drop(db_path);
 }
 
 fn get_state_for_block(harness: &TestHarness, block_root: Hash256) -> BeaconState<E> {
@@ -720,8 +944,7 @@ fn check_shuffling_compatible(
 fn prunes_abandoned_fork_between_two_finalized_checkpoints() {
     const VALIDATOR_COUNT: usize = 24;
     const VALIDATOR_SUPERMAJORITY: usize = (VALIDATOR_COUNT / 3) * 2;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(Arc::clone(&store), VALIDATOR_COUNT);
     const HONEST_VALIDATOR_COUNT: usize = VALIDATOR_SUPERMAJORITY;
     let honest_validators: Vec<usize> = (0..HONEST_VALIDATOR_COUNT).collect();
@@ -816,8 +1039,7 @@ fn prunes_abandoned_fork_between_two_finalized_checkpoints() {
 fn pruning_does_not_touch_abandoned_block_shared_with_canonical_chain() {
     const VALIDATOR_COUNT: usize = 24;
     const VALIDATOR_SUPERMAJORITY: usize = (VALIDATOR_COUNT / 3) * 2;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(Arc::clone(&store), VALIDATOR_COUNT);
     const HONEST_VALIDATOR_COUNT: usize = VALIDATOR_SUPERMAJORITY;
     let honest_validators: Vec<usize> = (0..HONEST_VALIDATOR_COUNT).collect();
@@ -921,8 +1143,7 @@ fn pruning_does_not_touch_abandoned_block_shared_with_canonical_chain() {
 fn pruning_does_not_touch_blocks_prior_to_finalization() {
     const VALIDATOR_COUNT: usize = 24;
     const VALIDATOR_SUPERMAJORITY: usize = (VALIDATOR_COUNT / 3) * 2;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(Arc::clone(&store), VALIDATOR_COUNT);
     const HONEST_VALIDATOR_COUNT: usize = VALIDATOR_SUPERMAJORITY;
     let honest_validators: Vec<usize> = (0..HONEST_VALIDATOR_COUNT).collect();
@@ -1008,8 +1229,7 @@ fn pruning_does_not_touch_blocks_prior_to_finalization() {
 fn prunes_fork_running_past_finalized_checkpoint() {
     const VALIDATOR_COUNT: usize = 24;
     const VALIDATOR_SUPERMAJORITY: usize = (VALIDATOR_COUNT / 3) * 2;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(Arc::clone(&store), VALIDATOR_COUNT);
     const HONEST_VALIDATOR_COUNT: usize = VALIDATOR_SUPERMAJORITY;
     let honest_validators: Vec<usize> = (0..HONEST_VALIDATOR_COUNT).collect();
@@ -1026,8 +1246,13 @@ fn prunes_fork_running_past_finalized_checkpoint() {
     let (stray_blocks_first_epoch, stray_states_first_epoch, stray_slot, _, stray_state) =
         harness.add_stray_blocks(state.clone(), slot, slots_per_epoch, &faulty_validators);
 
-    let (canonical_blocks_first_epoch, _, canonical_slot, _, canonical_state) =
-        harness.add_canonical_chain_blocks(state, slot, slots_per_epoch, &honest_validators);
+    let (
+        canonical_blocks_first_epoch,
+        canonical_states_first_epoch,
+        canonical_slot,
+        _,
+        canonical_state,
+    ) = harness.add_canonical_chain_blocks(state, slot, slots_per_epoch, &honest_validators);
 
     // Fill up 2nd epoch.  Extends both the canonical chain and the fork.
     let (stray_blocks_second_epoch, stray_states_second_epoch, _, stray_head, _) = harness
@@ -1038,6 +1263,18 @@ fn prunes_fork_running_past_finalized_checkpoint() {
             &faulty_validators,
         );
 
+    // The summary of the 1st epoch of each chain is stored when its 2nd epoch is processed.
+    let last_slot_first_epoch = Slot::new(2 * slots_per_epoch as u64 - 1);
+    let stray_summary_root: Hash256 = stray_states_first_epoch[&last_slot_first_epoch].into();
+    let canonical_summary_root: Hash256 =
+        canonical_states_first_epoch[&last_slot_first_epoch].into();
+    assert!(
+        store
+            .item_exists::<EpochSummary>(&stray_summary_root)
+            .unwrap(),
+        "stray epoch summary should be stored"
+    );
+
     // Precondition: Ensure all stray_blocks blocks are still known
     let stray_blocks: HashMap<Slot, SignedBeaconBlockHash> = stray_blocks_first_epoch
         .into_iter()
@@ -1138,6 +1375,20 @@ fn prunes_fork_running_past_finalized_checkpoint() {
     }
 
     assert!(!harness.chain.knows_head(&stray_head));
+
+    // Postcondition: Only the epoch summary of the canonical chain remains
+    assert!(
+        !store
+            .item_exists::<EpochSummary>(&stray_summary_root)
+            .unwrap(),
+        "stray epoch summary should have been deleted"
+    );
+    assert!(
+        store
+            .item_exists::<EpochSummary>(&canonical_summary_root)
+            .unwrap(),
+        "canonical epoch summary should be kept"
+    );
 }
 
 // This is to check if state outside of normal block processing are pruned correctly.
@@ -1145,8 +1396,7 @@ fn prunes_fork_running_past_finalized_checkpoint() {
 fn prunes_skipped_slots_states() {
     const VALIDATOR_COUNT: usize = 24;
     const VALIDATOR_SUPERMAJORITY: usize = (VALIDATOR_COUNT / 3) * 2;
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+    let store = get_store();
     let harness = get_harness(Arc::clone(&store), VALIDATOR_COUNT);
     const HONEST_VALIDATOR_COUNT: usize = VALIDATOR_SUPERMAJORITY;
     let honest_validators: Vec<usize> = (0..HONEST_VALIDATOR_COUNT).collect();
@@ -1313,7 +1563,10 @@ fn check_finalization(harness: &TestHarness, expected_slot: u64) {
 }
 
 /// Check that the HotColdDB's split_slot is equal to the start slot of the last finalized epoch.
-fn check_split_slot(harness: &TestHarness, store: Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>) {
+fn check_split_slot(
+    harness: &TestHarness,
+    store: Arc<HotColdDB<E, MemoryStore<E>, MemoryStore<E>>>,
+) {
     let split_slot = store.get_split_slot();
     assert_eq!(
         harness
@@ -5,6 +5,7 @@ extern crate lazy_static;
 
 use beacon_chain::{
     attestation_verification::Error as AttnError,
+    events::EventKind,
     test_utils::{
         AttestationStrategy, BeaconChainHarness, BlockStrategy, HarnessType, OP_POOL_DB_KEY,
     },
@@ -14,7 +15,9 @@ use state_processing::{
     per_slot_processing, per_slot_processing::Error as SlotProcessingError, EpochProcessingError,
 };
 use store::config::StoreConfig;
-use types::{BeaconStateError, EthSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch, Slot};
+use types::{
+    BeaconStateError, EthSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch, Signature, Slot,
+};
 
 // Should ideally be divisible by 3.
 pub const VALIDATOR_COUNT: usize = 24;
@@ -221,6 +224,138 @@ fn finalizes_with_full_participation() {
     );
 }
 
+#[test]
+fn publishes_events_to_subscribers() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+    let mut events = harness.chain.subscribe_events();
+
+    let mut blocks_imported = 0;
+    let mut heads_changed = 0;
+    let mut attestations_imported = 0;
+    let mut justified_epochs = vec![];
+    let mut finalized_epochs = vec![];
+
+    // Extend the chain one block at a time so that the events of each block fit in the queue.
+    for _ in 0..num_blocks_produced {
+        harness.extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        );
+
+        while let Ok(event) = events.try_recv() {
+            match event {
+                EventKind::BeaconBlockImported { .. } => blocks_imported += 1,
+                EventKind::BeaconHeadChanged { reorg, .. } => {
+                    assert!(!reorg, "there should be no re-orgs");
+                    heads_changed += 1;
+                }
+                EventKind::BeaconAttestationImported { .. } => attestations_imported += 1,
+                EventKind::BeaconJustification { epoch, .. } => justified_epochs.push(epoch),
+                EventKind::BeaconFinalization { epoch, .. } => finalized_epochs.push(epoch),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+    }
+
+    let state = &harness.chain.head().expect("should get head").beacon_state;
+
+    assert_eq!(blocks_imported, num_blocks_produced);
+    assert_eq!(heads_changed, num_blocks_produced);
+    assert!(attestations_imported > 0, "should publish attestations");
+    assert_eq!(
+        justified_epochs.last(),
+        Some(&state.current_justified_checkpoint.epoch),
+        "the last justification event should match the head"
+    );
+    assert_eq!(
+        finalized_epochs.last(),
+        Some(&state.finalized_checkpoint.epoch),
+        "the last finalization event should match the head"
+    );
+}
+
+#[test]
+fn publishes_reorg_depth() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    // Build an initial chain where all validators agree.
+    let common_ancestor = harness.extend_chain(
+        3,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    let common_ancestor_slot = Slot::new(3);
+
+    // Extend it with two blocks which no validator attests to.
+    harness.advance_slot();
+    let previous_head = harness.extend_chain(
+        2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::SomeValidators(vec![]),
+    );
+    let previous_head_slot = common_ancestor_slot + 2;
+    assert_eq!(
+        harness
+            .chain
+            .head()
+            .expect("should get head")
+            .beacon_block_root,
+        previous_head,
+        "the unattested blocks should be the head before the fork"
+    );
+
+    let mut events = harness.chain.subscribe_events();
+
+    // Build a fork from the initial chain which all validators attest to.
+    let new_head = harness.extend_chain(
+        3,
+        BlockStrategy::ForkCanonicalChainAt {
+            previous_slot: common_ancestor_slot,
+            first_slot: previous_head_slot + 1,
+        },
+        AttestationStrategy::AllValidators,
+    );
+    assert_eq!(
+        harness
+            .chain
+            .head()
+            .expect("should get head")
+            .beacon_block_root,
+        new_head,
+        "the attested fork should be the canonical chain"
+    );
+
+    let mut reorgs = vec![];
+    let mut reorg_head_changes = 0;
+    while let Ok(event) = events.try_recv() {
+        match event {
+            EventKind::BeaconChainReorg(reorg) => reorgs.push(reorg),
+            EventKind::BeaconHeadChanged { reorg: true, .. } => reorg_head_changes += 1,
+            _ => {}
+        }
+    }
+
+    assert_eq!(reorgs.len(), 1, "should re-org once");
+    assert_eq!(
+        reorg_head_changes, 1,
+        "should report one head change as a re-org"
+    );
+
+    let reorg = &reorgs[0];
+    assert_eq!(reorg.depth, 2);
+    assert_eq!(reorg.common_ancestor_root, common_ancestor);
+    assert_eq!(reorg.common_ancestor_slot, common_ancestor_slot);
+    assert_eq!(reorg.previous_head_root, previous_head);
+    assert_eq!(reorg.previous_head_slot, previous_head_slot);
+    assert!(
+        reorg.new_head_slot > previous_head_slot,
+        "the new head should be on the fork"
+    );
+}
+
 #[test]
 fn finalizes_with_two_thirds_participation() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
@@ -606,3 +741,49 @@ fn produces_and_processes_with_genesis_skip_slots() {
         run_skip_slot_test(i)
     }
 }
+
+/// Returns the number of times a state was obtained from the advanced head state.
+fn skip_slot_cache_hits() -> f64 {
+    lighthouse_metrics::gather()
+        .iter()
+        .find(|family| family.get_name() == "beacon_skip_slot_cache_hits_total")
+        .and_then(|family| family.get_metric().first())
+        .map_or(0.0, |metric| metric.get_counter().get_value())
+}
+
+#[test]
+fn produces_block_from_advanced_head_state() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    let head = harness.chain.head().expect("should get head");
+
+    // The head block is three slots old by the time of the proposal.
+    for _ in 0..3 {
+        harness.advance_slot();
+    }
+    let slot = harness.chain.slot().expect("should have a slot");
+    assert_eq!(slot, head.beacon_block.slot() + 3);
+
+    harness
+        .chain
+        .advance_head_state()
+        .expect("should advance head state");
+
+    let hits = skip_slot_cache_hits();
+    let (block, _state) = harness
+        .chain
+        .produce_block(Signature::empty_signature(), slot, None)
+        .expect("should produce block");
+
+    assert!(
+        skip_slot_cache_hits() > hits,
+        "block production should use the advanced head state"
+    );
+    assert_eq!(block.slot, slot);
+    assert_eq!(block.parent_root, head.beacon_block_root);
+}
@@ -1,24 +1,23 @@
 use crate::config::{ClientGenesis, Config as ClientConfig};
 use crate::notifier::spawn_notifier;
+use crate::state_reconstruction::spawn_state_reconstruction;
 use crate::Client;
-use beacon_chain::events::TeeEventHandler;
+use beacon_chain::events::{ServerSentEventQueues, TeeEventHandler};
 use beacon_chain::{
     builder::{BeaconChainBuilder, Witness},
     eth1_chain::{CachingEth1Backend, Eth1Chain},
-    migrate::{BackgroundMigrator, Migrate},
+    migrate::{BackgroundMigrator, BlockingMigrator, Migrate},
     slot_clock::{SlotClock, SystemTimeSlotClock},
     store::{HotColdDB, ItemStore, LevelDB, StoreConfig},
     BeaconChain, BeaconChainTypes, Eth1ChainBackend, EventHandler,
 };
-use bus::Bus;
 use environment::RuntimeContext;
 use eth1::{Config as Eth1Config, Service as Eth1Service};
 use eth2_config::Eth2Config;
 use eth2_libp2p::NetworkGlobals;
 use genesis::{interop_genesis_state, Eth1GenesisService};
 use network::{NetworkConfig, NetworkMessage, NetworkService};
-use parking_lot::Mutex;
-use slog::info;
+use slog::{info, warn};
 use ssz::Decode;
 use std::net::SocketAddr;
 use std::path::Path;
@@ -26,10 +25,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use timer::spawn_timer;
 use tokio::sync::mpsc::UnboundedSender;
-use types::{
-    test_utils::generate_deterministic_keypairs, BeaconState, ChainSpec, EthSpec,
-    SignedBeaconBlockHash,
-};
+use types::{test_utils::generate_deterministic_keypairs, BeaconState, ChainSpec, EthSpec};
 use websocket_server::{Config as WebSocketConfig, WebSocketSender};
 
 /// Interval between polling the eth1 node for genesis information.
@@ -61,6 +57,7 @@ pub struct ClientBuilder<T: BeaconChainTypes> {
     network_globals: Option<Arc<NetworkGlobals<T::EthSpec>>>,
     network_send: Option<UnboundedSender<NetworkMessage<T::EthSpec>>>,
     http_listen_addr: Option<SocketAddr>,
+    grpc_listen_addr: Option<SocketAddr>,
     websocket_listen_addr: Option<SocketAddr>,
     eth_spec_instance: T::EthSpec,
 }
@@ -103,6 +100,7 @@ where
             network_globals: None,
             network_send: None,
             http_listen_addr: None,
+            grpc_listen_addr: None,
             websocket_listen_addr: None,
             eth_spec_instance,
         }
@@ -134,6 +132,8 @@ where
         let eth_spec_instance = self.eth_spec_instance.clone();
         let data_dir = config.data_dir.clone();
         let disabled_forks = config.disabled_forks.clone();
+        let weak_subjectivity_checkpoint = config.weak_subjectivity_checkpoint.clone();
+        let trusted_peers = config.trusted_peers.clone();
 
         let store =
             store.ok_or_else(|| "beacon_chain_start_method requires a store".to_string())?;
@@ -151,7 +151,8 @@ where
             .store_migrator(store_migrator)
             .data_dir(data_dir)
             .custom_spec(spec.clone())
-            .disabled_forks(disabled_forks);
+            .disabled_forks(disabled_forks)
+            .divergence_guard(weak_subjectivity_checkpoint, trusted_peers);
 
         let chain_exists = builder
             .store_contains_beacon_chain()
@@ -275,7 +276,7 @@ where
         mut self,
         client_config: &ClientConfig,
         eth2_config: &Eth2Config,
-        events: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+        events: ServerSentEventQueues,
     ) -> Result<Self, String> {
         let beacon_chain = self
             .beacon_chain
@@ -313,6 +314,7 @@ where
                 .map_err(|_| "unable to read freezer DB dir")?,
             eth2_config.clone(),
             events,
+            context.log_levels.clone(),
         )
         .map_err(|e| format!("Failed to start HTTP API: {:?}", e))?;
 
@@ -321,6 +323,35 @@ where
         Ok(self)
     }
 
+    /// Immediately starts the gRPC API server.
+    pub fn grpc_server(mut self, client_config: &ClientConfig) -> Result<Self, String> {
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "grpc_server requires a beacon chain")?;
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "grpc_server requires a runtime_context")?
+            .service_context("grpc".into());
+        let network_send = self
+            .network_send
+            .clone()
+            .ok_or_else(|| "grpc_server requires a libp2p network sender")?;
+
+        let listening_addr = rest_api::grpc::start_server(
+            context.executor,
+            &client_config.grpc,
+            beacon_chain,
+            network_send,
+        )
+        .map_err(|e| format!("Failed to start gRPC API: {}", e))?;
+
+        self.grpc_listen_addr = Some(listening_addr);
+
+        Ok(self)
+    }
+
     /// Immediately starts the service that periodically logs information each slot.
     pub fn notifier(self) -> Result<Self, String> {
         let context = self
@@ -353,6 +384,35 @@ where
         Ok(self)
     }
 
+    /// Immediately starts the service that reconstructs missing freezer DB restore points, if
+    /// there are any.
+    pub fn state_reconstruction(self, restore_points_per_slot: usize) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "state_reconstruction requires a runtime_context")?
+            .service_context("state_reconstruction".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "state_reconstruction requires a beacon chain")?;
+        let milliseconds_per_slot = self
+            .chain_spec
+            .as_ref()
+            .ok_or_else(|| "state_reconstruction requires a chain spec".to_string())?
+            .milliseconds_per_slot;
+
+        spawn_state_reconstruction(
+            context.executor,
+            beacon_chain,
+            milliseconds_per_slot,
+            restore_points_per_slot,
+        )
+        .map_err(|e| format!("Unable to start state reconstruction: {}", e))?;
+
+        Ok(self)
+    }
+
     /// Consumers the builder, returning a `Client` if all necessary components have been
     /// specified.
     ///
@@ -374,6 +434,7 @@ where
             beacon_chain: self.beacon_chain,
             network_globals: self.network_globals,
             http_listen_addr: self.http_listen_addr,
+            grpc_listen_addr: self.grpc_listen_addr,
             websocket_listen_addr: self.websocket_listen_addr,
         }
     }
@@ -495,7 +556,7 @@ where
     pub fn tee_event_handler(
         mut self,
         config: WebSocketConfig,
-    ) -> Result<(Self, Arc<Mutex<Bus<SignedBeaconBlockHash>>>), String> {
+    ) -> Result<(Self, ServerSentEventQueues), String> {
         let context = self
             .runtime_context
             .as_ref()
@@ -512,9 +573,9 @@ where
         };
 
         self.websocket_listen_addr = listening_addr;
-        let (tee_event_handler, bus) = TeeEventHandler::new(log, sender)?;
+        let (tee_event_handler, queues) = TeeEventHandler::new(log, sender)?;
         self.event_handler = Some(tee_event_handler);
-        Ok((self, bus))
+        Ok((self, queues))
     }
 }
 
@@ -595,6 +656,44 @@ where
     }
 }
 
+impl<TSlotClock, TEth1Backend, TEthSpec, TEventHandler, THotStore, TColdStore>
+    ClientBuilder<
+        Witness<
+            BlockingMigrator<TEthSpec, THotStore, TColdStore>,
+            TSlotClock,
+            TEth1Backend,
+            TEthSpec,
+            TEventHandler,
+            THotStore,
+            TColdStore,
+        >,
+    >
+where
+    TSlotClock: SlotClock + 'static,
+    TEth1Backend: Eth1ChainBackend<TEthSpec> + 'static,
+    TEthSpec: EthSpec + 'static,
+    TEventHandler: EventHandler<TEthSpec> + 'static,
+    THotStore: ItemStore<TEthSpec> + 'static,
+    TColdStore: ItemStore<TEthSpec> + 'static,
+{
+    /// Migrates finalized states to the freezer DB on the thread that runs fork choice.
+    ///
+    /// Useful for offline tools which must not exit before the freezer DB is up to date.
+    pub fn blocking_migrator(mut self) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "blocking_migrator requires a log".to_string())?
+            .service_context("freezer_db".into());
+        let store = self
+            .store
+            .clone()
+            .ok_or_else(|| "blocking_migrator requires the store to be initialized".to_string())?;
+        self.store_migrator = Some(BlockingMigrator::new(store, context.log().clone()));
+        Ok(self)
+    }
+}
+
 impl<TStoreMigrator, TSlotClock, TEthSpec, TEventHandler, THotStore, TColdStore>
     ClientBuilder<
         Witness<
@@ -643,17 +742,29 @@ where
 
             CachingEth1Backend::from_service(eth1_service_from_genesis)
         } else {
+            // A persisted cache takes precedence over `config.deposit_snapshot`, since it is
+            // likely to be more recent.
             beacon_chain_builder
                 .get_persisted_eth1_backend()?
-                .map(|persisted| {
+                .and_then(|persisted| {
                     Eth1Chain::from_ssz_container(
                         &persisted,
                         config.clone(),
                         &context.log().clone(),
                     )
                     .map(|chain| chain.into_backend())
+                    .map_err(|e| {
+                        // The cache can always be rebuilt from the eth1 node.
+                        warn!(
+                            context.log(),
+                            "Unable to load persisted eth1 cache";
+                            "error" => e,
+                            "info" => "the cache will be rebuilt from the eth1 node",
+                        )
+                    })
+                    .ok()
                 })
-                .unwrap_or_else(|| Ok(CachingEth1Backend::new(config, context.log().clone())))?
+                .unwrap_or_else(|| CachingEth1Backend::new(config, context.log().clone()))
         };
 
         self.eth1_service = None;
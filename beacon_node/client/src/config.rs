@@ -2,6 +2,7 @@ use network::NetworkConfig;
 use serde_derive::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use types::Checkpoint;
 
 pub const DEFAULT_DATADIR: &str = ".lighthouse";
 
@@ -55,6 +56,12 @@ pub struct Config {
     pub sync_eth1_chain: bool,
     /// A list of hard-coded forks that will be disabled.
     pub disabled_forks: Vec<String>,
+    /// If the chain conflicts with this checkpoint, block production and attestation are halted.
+    pub weak_subjectivity_checkpoint: Option<Checkpoint>,
+    /// The peer ids of peers whose finalized checkpoints we expect to agree with. If more than
+    /// half of them are on a different finalized chain, block production and attestation are
+    /// halted.
+    pub trusted_peers: Vec<String>,
     #[serde(skip)]
     /// The `genesis` field is not serialized or deserialized by `serde` to ensure it is defined
     /// via the CLI at runtime, instead of from a configuration file saved to disk.
@@ -62,6 +69,7 @@ pub struct Config {
     pub store: store::StoreConfig,
     pub network: network::NetworkConfig,
     pub rest_api: rest_api::Config,
+    pub grpc: rest_api::grpc::Config,
     pub websocket_server: websocket_server::Config,
     pub eth1: eth1::Config,
 }
@@ -78,12 +86,15 @@ impl Default for Config {
             store: <_>::default(),
             network: NetworkConfig::default(),
             rest_api: <_>::default(),
+            grpc: <_>::default(),
             websocket_server: <_>::default(),
             spec_constants: TESTNET_SPEC_CONSTANTS.into(),
             dummy_eth1_backend: false,
             sync_eth1_chain: false,
             eth1: <_>::default(),
             disabled_forks: Vec::new(),
+            weak_subjectivity_checkpoint: None,
+            trusted_peers: Vec::new(),
         }
     }
 }
@@ -3,6 +3,7 @@ extern crate slog;
 pub mod config;
 mod metrics;
 mod notifier;
+mod state_reconstruction;
 
 pub mod builder;
 pub mod error;
@@ -24,6 +25,7 @@ pub struct Client<T: BeaconChainTypes> {
     beacon_chain: Option<Arc<BeaconChain<T>>>,
     network_globals: Option<Arc<NetworkGlobals<T::EthSpec>>>,
     http_listen_addr: Option<SocketAddr>,
+    grpc_listen_addr: Option<SocketAddr>,
     websocket_listen_addr: Option<SocketAddr>,
 }
 
@@ -38,6 +40,11 @@ impl<T: BeaconChainTypes> Client<T> {
         self.http_listen_addr
     }
 
+    /// Returns the address of the client's gRPC API server, if it was started.
+    pub fn grpc_listen_addr(&self) -> Option<SocketAddr> {
+        self.grpc_listen_addr
+    }
+
     /// Returns the address of the client's WebSocket API server, if it was started.
     pub fn websocket_listen_addr(&self) -> Option<SocketAddr> {
         self.websocket_listen_addr
@@ -6,4 +6,8 @@ lazy_static! {
         "sync_slots_per_second",
         "The number of blocks being imported per second"
     );
+    pub static ref SECONDS_UNTIL_GENESIS: Result<IntGauge> = try_create_int_gauge(
+        "notifier_seconds_until_genesis",
+        "The number of seconds until genesis, or zero once genesis has passed"
+    );
 }
@@ -51,6 +51,7 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
                 // If the duration to the next slot is greater than the slot duration, then we are
                 // waiting for genesis.
                 Some(next_slot) if next_slot > slot_duration => {
+                    metrics::set_gauge(&metrics::SECONDS_UNTIL_GENESIS, next_slot.as_secs() as i64);
                     info!(
                         log,
                         "Waiting for genesis";
@@ -62,6 +63,7 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
                 _ => break,
             }
         }
+        metrics::set_gauge(&metrics::SECONDS_UNTIL_GENESIS, 0);
 
         // Perform post-genesis logging.
         while let Some(_) = interval.next().await {
@@ -0,0 +1,83 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use futures::stream::StreamExt;
+use slog::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Spawns a service which reconstructs the freezer DB restore points that are missing because
+/// `slots_per_restore_point` was decreased, reconstructing up to `restore_points_per_slot` of
+/// them each slot.
+///
+/// Does nothing if no restore points are missing.
+pub fn spawn_state_reconstruction<T: BeaconChainTypes>(
+    executor: environment::TaskExecutor,
+    beacon_chain: Arc<BeaconChain<T>>,
+    milliseconds_per_slot: u64,
+    restore_points_per_slot: usize,
+) -> Result<(), String> {
+    let log = executor.log().clone();
+    let info = beacon_chain.store.restore_point_info();
+    if info.reconstruction_complete() {
+        return Ok(());
+    }
+    if restore_points_per_slot == 0 {
+        return Err("state reconstruction rate must be greater than zero".into());
+    }
+
+    info!(
+        log,
+        "Reconstructing historic states";
+        "from_slot" => info.reconstructed_slot,
+        "to_slot" => info.reconstruction_target,
+        "slots_per_restore_point" => info.slots_per_restore_point,
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_millis(milliseconds_per_slot));
+    // Set whilst a batch is being reconstructed, so that slow batches are not run concurrently.
+    let in_progress = Arc::new(AtomicBool::new(false));
+    let inner_executor = executor.clone();
+
+    let reconstruction_future = async move {
+        while interval.next().await.is_some() {
+            if beacon_chain
+                .store
+                .restore_point_info()
+                .reconstruction_complete()
+            {
+                info!(log, "Historic state reconstruction complete");
+                break;
+            }
+
+            if in_progress.swap(true, Ordering::SeqCst) {
+                continue;
+            }
+
+            // Replaying blocks is expensive, so it is done on a blocking thread to avoid stalling
+            // the runtime.
+            let chain = beacon_chain.clone();
+            let in_progress = in_progress.clone();
+            let log = log.clone();
+            inner_executor.spawn_blocking(
+                move || {
+                    if let Err(e) = chain
+                        .store
+                        .reconstruct_restore_points(restore_points_per_slot)
+                    {
+                        error!(
+                            log,
+                            "Historic state reconstruction failed";
+                            "error" => format!("{:?}", e)
+                        );
+                    }
+                    in_progress.store(false, Ordering::SeqCst);
+                },
+                "state_reconstruction_batch",
+            );
+        }
+    };
+
+    executor.spawn(reconstruction_future, "state_reconstruction");
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use ssz_derive::{Decode, Encode};
 use state_processing::common::DepositDataTree;
 use std::cmp::Ordering;
 use tree_hash::TreeHash;
-use types::{Deposit, Hash256, DEPOSIT_TREE_DEPTH};
+use types::{Deposit, DepositTreeSnapshot, Hash256, DEPOSIT_TREE_DEPTH};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
@@ -25,6 +25,11 @@ pub enum Error {
     ///
     /// E.g., you cannot request deposit 10 when the deposit count is 9.
     DepositCountInvalid { deposit_count: u64, range_end: u64 },
+    /// The requested deposits are part of the snapshot the cache was created from, so their logs
+    /// are unknown.
+    DepositsInSnapshot { start: u64, snapshot_count: u64 },
+    /// The deposit snapshot is not consistent with its deposit root.
+    InvalidSnapshot,
     /// Error with the merkle tree for deposits.
     DepositTreeError(merkle_proof::MerkleTreeError),
     /// An unexpected condition was encountered.
@@ -37,6 +42,7 @@ pub struct SszDepositCache {
     leaves: Vec<Hash256>,
     deposit_contract_deploy_block: u64,
     deposit_roots: Vec<Hash256>,
+    snapshot: Option<DepositTreeSnapshot>,
 }
 
 impl SszDepositCache {
@@ -46,17 +52,18 @@ impl SszDepositCache {
             leaves: cache.leaves.clone(),
             deposit_contract_deploy_block: cache.deposit_contract_deploy_block,
             deposit_roots: cache.deposit_roots.clone(),
+            snapshot: cache.snapshot.clone(),
         }
     }
 
     pub fn to_deposit_cache(&self) -> Result<DepositCache, String> {
-        let deposit_tree =
-            DepositDataTree::create(&self.leaves, self.leaves.len(), DEPOSIT_TREE_DEPTH);
+        let deposit_tree = build_deposit_tree(self.snapshot.as_ref(), &self.leaves)
+            .map_err(|e| format!("Invalid SszDepositCache: {:?}", e))?;
         // Check for invalid SszDepositCache conditions
         if self.leaves.len() != self.logs.len() {
             return Err("Invalid SszDepositCache: logs and leaves should have equal length".into());
         }
-        // `deposit_roots` also includes the zero root
+        // `deposit_roots` also includes the root of the empty tree or snapshot
         if self.leaves.len() + 1 != self.deposit_roots.len() {
             return Err(
                 "Invalid SszDepositCache: deposit_roots length must be only one more than leaves"
@@ -67,24 +74,58 @@ impl SszDepositCache {
             logs: self.logs.clone(),
             leaves: self.leaves.clone(),
             deposit_contract_deploy_block: self.deposit_contract_deploy_block,
+            snapshot: self.snapshot.clone(),
             deposit_tree,
             deposit_roots: self.deposit_roots.clone(),
         })
     }
 }
 
+/// Builds the deposit tree from an optional snapshot followed by `leaves`.
+fn build_deposit_tree(
+    snapshot: Option<&DepositTreeSnapshot>,
+    leaves: &[Hash256],
+) -> Result<DepositDataTree, Error> {
+    build_deposit_tree_with_depth(snapshot, leaves, DEPOSIT_TREE_DEPTH)
+}
+
+fn build_deposit_tree_with_depth(
+    snapshot: Option<&DepositTreeSnapshot>,
+    leaves: &[Hash256],
+    depth: usize,
+) -> Result<DepositDataTree, Error> {
+    match snapshot {
+        Some(snapshot) => {
+            let mut tree =
+                DepositDataTree::from_snapshot(snapshot, depth).map_err(Error::DepositTreeError)?;
+            for leaf in leaves {
+                tree.push_leaf(*leaf).map_err(Error::DepositTreeError)?;
+            }
+            Ok(tree)
+        }
+        None => Ok(DepositDataTree::create(leaves, leaves.len(), depth)),
+    }
+}
+
 /// Mirrors the merkle tree of deposits in the eth1 deposit contract.
 ///
 /// Provides `Deposit` objects with merkle proofs included.
+///
+/// The cache may be created from a `DepositTreeSnapshot` rather than from the first deposit, in
+/// which case only the deposits after the snapshot are stored and proofs can only be generated
+/// for them.
 pub struct DepositCache {
+    /// The logs of the deposits after the snapshot (if any).
     logs: Vec<DepositLog>,
     leaves: Vec<Hash256>,
     deposit_contract_deploy_block: u64,
+    /// The snapshot the cache was created from, if any.
+    snapshot: Option<DepositTreeSnapshot>,
     /// An incremental merkle tree which represents the current state of the
     /// deposit contract tree.
     deposit_tree: DepositDataTree,
     /// Vector of deposit roots. `deposit_roots[i]` denotes `deposit_root` at
-    /// `deposit_index` `i`.
+    /// `deposit_index` `i + snapshot_count`.
     deposit_roots: Vec<Hash256>,
 }
 
@@ -96,6 +137,7 @@ impl Default for DepositCache {
             logs: Vec::new(),
             leaves: Vec::new(),
             deposit_contract_deploy_block: 1,
+            snapshot: None,
             deposit_tree,
             deposit_roots,
         }
@@ -112,14 +154,70 @@ impl DepositCache {
         }
     }
 
-    /// Returns the number of deposits available in the cache.
+    /// Create a new `DepositCache` containing the deposits in `snapshot`, ready for the deposit
+    /// logs after `snapshot.execution_block_height` to be inserted.
+    pub fn from_snapshot(
+        snapshot: DepositTreeSnapshot,
+        deposit_contract_deploy_block: u64,
+    ) -> Result<Self, Error> {
+        if !snapshot.is_valid() {
+            return Err(Error::InvalidSnapshot);
+        }
+
+        let deposit_tree = build_deposit_tree(Some(&snapshot), &[])?;
+        Ok(DepositCache {
+            deposit_contract_deploy_block,
+            deposit_roots: vec![snapshot.deposit_root],
+            snapshot: Some(snapshot),
+            deposit_tree,
+            ..Self::default()
+        })
+    }
+
+    /// Returns a snapshot of the first `deposit_count` deposits, which were all included by the
+    /// eth1 block `block_hash` at `block_height`.
+    pub fn get_snapshot(
+        &self,
+        deposit_count: u64,
+        block_hash: Hash256,
+        block_height: u64,
+    ) -> Result<DepositTreeSnapshot, Error> {
+        let deposit_root = deposit_count
+            .checked_sub(self.snapshot_count())
+            .and_then(|i| self.deposit_roots.get(i as usize))
+            .ok_or_else(|| Error::InsufficientDeposits {
+                known_deposits: self.len(),
+                requested: deposit_count,
+            })?;
+        let finalized = self
+            .deposit_tree
+            .finalized_hashes(deposit_count as usize)
+            .map_err(Error::DepositTreeError)?;
+
+        Ok(DepositTreeSnapshot {
+            finalized,
+            deposit_root: *deposit_root,
+            deposit_count,
+            execution_block_hash: block_hash,
+            execution_block_height: block_height,
+        })
+    }
+
+    /// The number of deposits in the snapshot the cache was created from, or zero.
+    fn snapshot_count(&self) -> u64 {
+        self.snapshot
+            .as_ref()
+            .map_or(0, |snapshot| snapshot.deposit_count)
+    }
+
+    /// Returns the number of deposits known to the cache, including those in the snapshot.
     pub fn len(&self) -> usize {
-        self.logs.len()
+        self.snapshot_count() as usize + self.logs.len()
     }
 
-    /// True if the cache does not store any blocks.
+    /// True if the cache does not know of any deposits.
     pub fn is_empty(&self) -> bool {
-        self.logs.is_empty()
+        self.len() == 0
     }
 
     /// Returns the block number for the most recent deposit in the cache.
@@ -127,14 +225,16 @@ impl DepositCache {
         self.logs.last().map(|log| log.block_number)
     }
 
-    /// Returns an iterator over all the logs in `self`.
+    /// Returns an iterator over all the logs in `self`, which excludes the deposits in the
+    /// snapshot.
     pub fn iter(&self) -> impl Iterator<Item = &DepositLog> {
         self.logs.iter()
     }
 
-    /// Returns the i'th deposit log.
+    /// Returns the i'th deposit log, or `None` if it is part of the snapshot.
     pub fn get(&self, i: usize) -> Option<&DepositLog> {
-        self.logs.get(i)
+        self.logs
+            .get(i.checked_sub(self.snapshot_count() as usize)?)
     }
 
     /// Adds `log` to self.
@@ -146,8 +246,10 @@ impl DepositCache {
     ///
     /// - If a log with index `log.index - 1` is not already present in `self` (ignored when empty).
     /// - If a log with `log.index` is already known, but the given `log` is distinct to it.
+    ///
+    /// Logs of deposits in the snapshot are ignored.
     pub fn insert_log(&mut self, log: DepositLog) -> Result<(), Error> {
-        match log.index.cmp(&(self.logs.len() as u64)) {
+        match log.index.cmp(&(self.len() as u64)) {
             Ordering::Equal => {
                 let deposit = log.deposit_data.tree_hash_root();
                 self.leaves.push(deposit);
@@ -158,16 +260,13 @@ impl DepositCache {
                 self.deposit_roots.push(self.deposit_tree.root());
                 Ok(())
             }
-            Ordering::Less => {
-                if self.logs[log.index as usize] == log {
-                    Ok(())
-                } else {
-                    Err(Error::DuplicateDistinctLog(log.index))
-                }
-            }
+            Ordering::Less => match self.get(log.index as usize) {
+                Some(known) if *known != log => Err(Error::DuplicateDistinctLog(log.index)),
+                _ => Ok(()),
+            },
             Ordering::Greater => Err(Error::NonConsecutive {
                 log_index: log.index,
-                expected: self.logs.len(),
+                expected: self.len(),
             }),
         }
     }
@@ -183,6 +282,7 @@ impl DepositCache {
     ///
     /// - If `deposit_count` is larger than `end`.
     /// - There are not sufficient deposits in the tree to generate the proof.
+    /// - If `start` is within the snapshot the cache was created from.
     pub fn get_deposits(
         &self,
         start: u64,
@@ -196,23 +296,36 @@ impl DepositCache {
                 deposit_count,
                 range_end: end,
             })
-        } else if end > self.logs.len() as u64 {
+        } else if end > self.len() as u64 {
             // The range of requested deposits exceeds the deposits stored locally.
             Err(Error::InsufficientDeposits {
                 requested: end,
-                known_deposits: self.logs.len(),
+                known_deposits: self.len(),
             })
-        } else if deposit_count > self.leaves.len() as u64 {
+        } else if deposit_count > self.len() as u64 {
             // There are not `deposit_count` known deposit roots, so we can't build the merkle tree
             // to prove into.
             Err(Error::InsufficientDeposits {
                 requested: deposit_count,
-                known_deposits: self.logs.len(),
+                known_deposits: self.len(),
+            })
+        } else if (start < end && start < self.snapshot_count())
+            || deposit_count < self.snapshot_count()
+        {
+            // The logs of deposits in the snapshot are unknown, and the tree can't be rebuilt at a
+            // count before the snapshot.
+            Err(Error::DepositsInSnapshot {
+                start,
+                snapshot_count: self.snapshot_count(),
             })
         } else {
+            let offset = self.snapshot_count();
+            // An empty range may start within the snapshot.
+            let start = std::cmp::max(start, offset);
+            let end = std::cmp::max(end, offset);
             let leaves = self
                 .leaves
-                .get(0..deposit_count as usize)
+                .get(0..(deposit_count - offset) as usize)
                 .ok_or_else(|| Error::InternalError("Unable to get known leaves".into()))?;
 
             // Note: there is likely a more optimal solution than recreating the `DepositDataTree`
@@ -222,22 +335,24 @@ impl DepositCache {
             // last finalized eth1 deposit count. Then, that tree could be cloned and extended for
             // each of these calls.
 
-            let tree = DepositDataTree::create(leaves, deposit_count as usize, tree_depth);
+            let tree = build_deposit_tree_with_depth(self.snapshot.as_ref(), leaves, tree_depth)?;
 
             let deposits = self
                 .logs
-                .get(start as usize..end as usize)
+                .get((start - offset) as usize..(end - offset) as usize)
                 .ok_or_else(|| Error::InternalError("Unable to get known log".into()))?
                 .iter()
                 .map(|deposit_log| {
-                    let (_leaf, proof) = tree.generate_proof(deposit_log.index as usize);
+                    let (_leaf, proof) = tree
+                        .generate_proof(deposit_log.index as usize)
+                        .map_err(Error::DepositTreeError)?;
 
-                    Deposit {
+                    Ok(Deposit {
                         proof: proof.into(),
                         data: deposit_log.deposit_data.clone(),
-                    }
+                    })
                 })
-                .collect();
+                .collect::<Result<_, _>>()?;
 
             Ok((tree.root(), deposits))
         }
@@ -248,7 +363,8 @@ impl DepositCache {
     /// Fetches the `DepositLog` that was emitted at or just before `block_number`
     /// and returns the deposit count as `index + 1`.
     ///
-    /// Returns `None` if block number queried is 0 or less than deposit_contract_deployed block.
+    /// Returns `None` if block number queried is 0 or less than deposit_contract_deployed block,
+    /// or is before the block of the snapshot the cache was created from.
     pub fn get_deposit_count_from_cache(&self, block_number: u64) -> Option<u64> {
         // Contract cannot be deployed in 0'th block
         if block_number == 0 {
@@ -257,10 +373,15 @@ impl DepositCache {
         if block_number < self.deposit_contract_deploy_block {
             return None;
         }
-        // Return 0 if block_num queried is before first deposit
+        if let Some(snapshot) = &self.snapshot {
+            if block_number < snapshot.execution_block_height {
+                return None;
+            }
+        }
+        // Return the snapshot count if block_num queried is before first deposit
         if let Some(first_deposit) = self.logs.first() {
             if first_deposit.block_number > block_number {
-                return Some(0);
+                return Some(self.snapshot_count());
             }
         }
         let index = self
@@ -269,9 +390,9 @@ impl DepositCache {
         match index {
             Ok(index) => self.logs.get(index).map(|x| x.index + 1),
             Err(next) => Some(
-                self.logs
-                    .get(next.saturating_sub(1))
-                    .map_or(0, |x| x.index + 1),
+                next.checked_sub(1)
+                    .and_then(|i| self.logs.get(i))
+                    .map_or(self.snapshot_count(), |x| x.index + 1),
             ),
         }
     }
@@ -281,7 +402,9 @@ impl DepositCache {
     /// Fetches the `deposit_count` on or just before the queried `block_number`
     /// and queries the `deposit_roots` map to get the corresponding `deposit_root`.
     pub fn get_deposit_root_from_cache(&self, block_number: u64) -> Option<Hash256> {
-        let index = self.get_deposit_count_from_cache(block_number)?;
+        let index = self
+            .get_deposit_count_from_cache(block_number)?
+            .checked_sub(self.snapshot_count())?;
         Some(*self.deposit_roots.get(index as usize)?)
     }
 }
@@ -439,4 +562,92 @@ pub mod tests {
         // Range higher than count.
         assert!(tree.get_deposits(0, 4, 2, TREE_DEPTH).is_err());
     }
+
+    fn example_logs(n: u64) -> Vec<DepositLog> {
+        (0..n)
+            .map(|i| {
+                let mut log = example_log();
+                log.index = i;
+                log.block_number = i;
+                log.deposit_data.withdrawal_credentials = Hash256::from_low_u64_be(i);
+                log
+            })
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let n = 64;
+        let finalized = 40;
+        let mut full = DepositCache::default();
+        for log in example_logs(n) {
+            full.insert_log(log).expect("should add consecutive logs");
+        }
+
+        let snapshot = full
+            .get_snapshot(finalized, Hash256::from_low_u64_be(42), finalized - 1)
+            .expect("should get snapshot");
+        assert!(snapshot.is_valid());
+        assert_eq!(
+            Some(snapshot.deposit_root),
+            full.get_deposit_root_from_cache(finalized - 1)
+        );
+
+        let mut cache =
+            DepositCache::from_snapshot(snapshot, 1).expect("should create from snapshot");
+        assert_eq!(cache.len(), finalized as usize);
+        for log in example_logs(n) {
+            cache
+                .insert_log(log)
+                .expect("should ignore snapshot logs and add the rest");
+        }
+        assert_eq!(cache.len(), n as usize);
+        assert_eq!(cache.get(0), None);
+        assert_eq!(cache.get(finalized as usize), full.get(finalized as usize));
+
+        // The deposits after the snapshot have the same proofs.
+        assert_eq!(
+            cache
+                .get_deposits(finalized, n, n, TREE_DEPTH)
+                .expect("should get deposits after the snapshot"),
+            full.get_deposits(finalized, n, n, TREE_DEPTH)
+                .expect("should get deposits from the full cache"),
+        );
+        assert!(cache.get_deposits(0, n, n, TREE_DEPTH).is_err());
+        assert!(cache
+            .get_deposits(finalized, finalized, finalized - 1, TREE_DEPTH)
+            .is_err());
+
+        assert_eq!(cache.get_deposit_count_from_cache(finalized - 2), None);
+        assert_eq!(
+            cache.get_deposit_root_from_cache(n - 1),
+            full.get_deposit_root_from_cache(n - 1)
+        );
+
+        // A snapshot of a snapshot is the same as a snapshot of the full cache.
+        assert_eq!(
+            cache.get_snapshot(n, Hash256::zero(), n - 1),
+            full.get_snapshot(n, Hash256::zero(), n - 1)
+        );
+
+        let decoded = SszDepositCache::from_deposit_cache(&cache)
+            .to_deposit_cache()
+            .expect("should decode cache with snapshot");
+        assert_eq!(decoded.len(), cache.len());
+        assert_eq!(decoded.deposit_tree.root(), cache.deposit_tree.root());
+    }
+
+    #[test]
+    fn invalid_snapshot() {
+        let mut full = DepositCache::default();
+        for log in example_logs(8) {
+            full.insert_log(log).expect("should add consecutive logs");
+        }
+        let mut snapshot = full
+            .get_snapshot(8, Hash256::zero(), 7)
+            .expect("should get snapshot");
+        snapshot.deposit_root = Hash256::zero();
+
+        assert!(DepositCache::from_snapshot(snapshot, 1).is_err());
+    }
 }
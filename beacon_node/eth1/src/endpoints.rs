@@ -0,0 +1,295 @@
+//! Scores the health of each configured eth1 endpoint, so that requests are sent to the healthiest
+//! one and the others serve as fallbacks.
+//!
+//! An endpoint which reports the wrong chain id is never used. Of the others, endpoints which are
+//! known to be synced are preferred, followed by the lowest score. The score is the latency of
+//! `eth_blockNumber` plus a penalty for the recent error rate.
+
+use crate::Config;
+use serde::Serialize;
+use std::time::Duration;
+
+/// The weight of the newest observation in the moving averages.
+const SMOOTHING_FACTOR: f64 = 0.3;
+/// The penalty for an error rate of 100%, in milliseconds of latency.
+const ERROR_RATE_PENALTY_MILLIS: f64 = 10_000.0;
+/// The endpoint in use is only replaced by an endpoint whose score is lower by at least this
+/// much, so that requests are not bounced between endpoints with similar scores.
+const SWITCH_THRESHOLD_MILLIS: f64 = 1_000.0;
+
+/// The result of querying the block number, sync status and chain id of an endpoint.
+pub struct HealthCheck {
+    pub latency: Duration,
+    pub is_syncing: bool,
+    pub chain_id: u64,
+}
+
+/// The observed health of an eth1 endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    /// A moving average of the latency of `eth_blockNumber`, in milliseconds.
+    pub latency_millis: Option<f64>,
+    /// A moving average of the fraction of requests which failed.
+    pub error_rate: f64,
+    pub requests: u64,
+    pub errors: u64,
+    /// `None` until the endpoint has been checked.
+    pub is_syncing: Option<bool>,
+    /// `None` until the endpoint has been checked.
+    pub chain_id: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl EndpointHealth {
+    fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            latency_millis: None,
+            error_rate: 0.0,
+            requests: 0,
+            errors: 0,
+            is_syncing: None,
+            chain_id: None,
+            last_error: None,
+        }
+    }
+
+    /// Lower is better.
+    pub fn score(&self) -> f64 {
+        self.latency_millis.unwrap_or(0.0) + self.error_rate * ERROR_RATE_PENALTY_MILLIS
+    }
+
+    /// Returns `None` if the endpoint must not be used, otherwise a rank where lower is better.
+    fn rank(&self, expected_chain_id: Option<u64>) -> Option<u8> {
+        match (expected_chain_id, self.chain_id) {
+            (Some(expected), Some(actual)) if expected != actual => None,
+            (Some(_), None) => Some(1),
+            _ if self.is_syncing == Some(false) => Some(0),
+            _ => Some(1),
+        }
+    }
+}
+
+/// The status of an endpoint, as returned by the HTTP API.
+#[derive(Clone, Debug, Serialize)]
+pub struct EndpointStatus {
+    #[serde(flatten)]
+    pub health: EndpointHealth,
+    pub score: f64,
+    /// False if the endpoint reports the wrong chain id.
+    pub usable: bool,
+    pub in_use: bool,
+}
+
+/// The configured eth1 endpoints, the first being the primary.
+#[derive(Default)]
+pub struct Endpoints {
+    endpoints: Vec<EndpointHealth>,
+    expected_chain_id: Option<u64>,
+    /// The index of the endpoint which requests are sent to, if any is usable.
+    in_use: Option<usize>,
+}
+
+impl Endpoints {
+    pub fn new(endpoints: Vec<String>, expected_chain_id: Option<u64>) -> Self {
+        Self {
+            in_use: if endpoints.is_empty() { None } else { Some(0) },
+            endpoints: endpoints.into_iter().map(EndpointHealth::new).collect(),
+            expected_chain_id,
+        }
+    }
+
+    /// Creates the endpoints given by `config`, the primary `endpoint` followed by the
+    /// `fallback_endpoints`.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.endpoints(), config.chain_id)
+    }
+
+    /// Returns the endpoint which requests should be sent to.
+    pub fn in_use(&self) -> Option<&str> {
+        self.in_use
+            .and_then(|index| self.endpoints.get(index))
+            .map(|health| health.endpoint.as_str())
+    }
+
+    /// Returns every endpoint, in the configured order.
+    pub fn urls(&self) -> Vec<String> {
+        self.endpoints
+            .iter()
+            .map(|health| health.endpoint.clone())
+            .collect()
+    }
+
+    pub fn record_health_check(&mut self, endpoint: &str, check: Result<HealthCheck, String>) {
+        match check {
+            Ok(check) => {
+                if let Some(health) = self.get_mut(endpoint) {
+                    health.latency_millis = Some(smooth(
+                        health.latency_millis,
+                        check.latency.as_millis() as f64,
+                    ));
+                    health.is_syncing = Some(check.is_syncing);
+                    health.chain_id = Some(check.chain_id);
+                }
+                self.record_success(endpoint);
+            }
+            Err(e) => self.record_error(endpoint, e),
+        }
+    }
+
+    pub fn record_success(&mut self, endpoint: &str) {
+        if let Some(health) = self.get_mut(endpoint) {
+            health.requests += 1;
+            health.error_rate = smooth(Some(health.error_rate), 0.0);
+        }
+    }
+
+    pub fn record_error(&mut self, endpoint: &str, error: String) {
+        if let Some(health) = self.get_mut(endpoint) {
+            health.requests += 1;
+            health.errors += 1;
+            health.error_rate = smooth(Some(health.error_rate), 1.0);
+            health.last_error = Some(error);
+        }
+    }
+
+    /// Selects the endpoint which requests should be sent to, returning true if it changed.
+    pub fn select(&mut self) -> bool {
+        let expected_chain_id = self.expected_chain_id;
+        let key = |health: &EndpointHealth| {
+            health
+                .rank(expected_chain_id)
+                .map(|rank| (rank, health.score()))
+        };
+
+        // Ties are won by the endpoint configured first.
+        let best = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(index, health)| key(health).map(|key| (index, key)))
+            .fold(
+                None,
+                |best: Option<(usize, (u8, f64))>, (index, key)| match best {
+                    Some((_, best_key)) if best_key <= key => best,
+                    _ => Some((index, key)),
+                },
+            );
+
+        let current = self
+            .in_use
+            .and_then(|index| Some((index, key(self.endpoints.get(index)?)?)));
+
+        let selected = match (current, best) {
+            (
+                Some((current, (current_rank, current_score))),
+                Some((_, (best_rank, best_score))),
+            ) if current_rank == best_rank
+                && current_score < best_score + SWITCH_THRESHOLD_MILLIS =>
+            {
+                Some(current)
+            }
+            (_, best) => best.map(|(index, _)| index),
+        };
+
+        let changed = selected != self.in_use;
+        self.in_use = selected;
+        changed
+    }
+
+    /// Returns the status of every endpoint, in the configured order.
+    pub fn statuses(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, health)| EndpointStatus {
+                health: health.clone(),
+                score: health.score(),
+                usable: health.rank(self.expected_chain_id).is_some(),
+                in_use: self.in_use == Some(index),
+            })
+            .collect()
+    }
+
+    fn get_mut(&mut self, endpoint: &str) -> Option<&mut EndpointHealth> {
+        self.endpoints
+            .iter_mut()
+            .find(|health| health.endpoint == endpoint)
+    }
+}
+
+fn smooth(average: Option<f64>, value: f64) -> f64 {
+    average.map_or(value, |average| {
+        average + SMOOTHING_FACTOR * (value - average)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY: &str = "http://primary:8545";
+    const FALLBACK: &str = "http://fallback:8545";
+
+    fn check(latency_millis: u64, is_syncing: bool, chain_id: u64) -> Result<HealthCheck, String> {
+        Ok(HealthCheck {
+            latency: Duration::from_millis(latency_millis),
+            is_syncing,
+            chain_id,
+        })
+    }
+
+    fn endpoints(expected_chain_id: Option<u64>) -> Endpoints {
+        Endpoints::new(
+            vec![PRIMARY.to_string(), FALLBACK.to_string()],
+            expected_chain_id,
+        )
+    }
+
+    #[test]
+    fn prefers_primary_when_healthy() {
+        let mut endpoints = endpoints(Some(5));
+        assert_eq!(endpoints.in_use(), Some(PRIMARY));
+
+        // A slightly faster fallback does not replace the primary.
+        endpoints.record_health_check(PRIMARY, check(200, false, 5));
+        endpoints.record_health_check(FALLBACK, check(100, false, 5));
+        assert!(!endpoints.select());
+        assert_eq!(endpoints.in_use(), Some(PRIMARY));
+    }
+
+    #[test]
+    fn fails_over_on_errors_and_syncing() {
+        let mut endpoints = endpoints(None);
+        endpoints.record_health_check(PRIMARY, check(100, false, 5));
+        endpoints.record_health_check(FALLBACK, check(100, false, 5));
+
+        endpoints.record_error(PRIMARY, "timeout".to_string());
+        assert!(endpoints.select());
+        assert_eq!(endpoints.in_use(), Some(FALLBACK));
+
+        // The primary recovers, but the fallback is now syncing.
+        endpoints.record_health_check(PRIMARY, check(100, false, 5));
+        endpoints.record_health_check(FALLBACK, check(100, true, 5));
+        assert!(endpoints.select());
+        assert_eq!(endpoints.in_use(), Some(PRIMARY));
+    }
+
+    #[test]
+    fn never_uses_wrong_chain() {
+        let mut endpoints = endpoints(Some(5));
+        endpoints.record_health_check(PRIMARY, check(100, false, 1));
+        assert!(endpoints.select());
+        assert_eq!(endpoints.in_use(), Some(FALLBACK));
+
+        endpoints.record_health_check(FALLBACK, check(100, false, 1));
+        assert!(endpoints.select());
+        assert_eq!(endpoints.in_use(), None);
+
+        let statuses = endpoints.statuses();
+        assert!(statuses
+            .iter()
+            .all(|status| !status.usable && !status.in_use));
+    }
+}
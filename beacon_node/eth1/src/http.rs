@@ -51,6 +51,35 @@ pub async fn get_block_number(endpoint: &str, timeout: Duration) -> Result<u64,
     .map_err(|e| format!("Failed to get block number: {}", e))
 }
 
+/// Returns the chain id of the eth1 network (e.g., `5` for Goerli).
+///
+/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+pub async fn get_chain_id(endpoint: &str, timeout: Duration) -> Result<u64, String> {
+    let response_body = send_rpc_request(endpoint, "eth_chainId", json!([]), timeout).await?;
+    hex_to_u64_be(
+        response_result(&response_body)?
+            .ok_or_else(|| "No result field was returned for chain id".to_string())?
+            .as_str()
+            .ok_or_else(|| "Data was not string")?,
+    )
+    .map_err(|e| format!("Failed to get chain id: {}", e))
+}
+
+/// Returns `true` if the eth1 node is still syncing.
+///
+/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+pub async fn is_syncing(endpoint: &str, timeout: Duration) -> Result<bool, String> {
+    let response_body = send_rpc_request(endpoint, "eth_syncing", json!([]), timeout).await?;
+    // The result is `false` when synced, otherwise an object describing the sync progress.
+    match response_result(&response_body)?
+        .ok_or_else(|| "No result field was returned for syncing".to_string())?
+    {
+        Value::Bool(syncing) => Ok(syncing),
+        Value::Object(_) => Ok(true),
+        other => Err(format!("Unexpected syncing result: {}", other)),
+    }
+}
+
 /// Gets a block hash by block number.
 ///
 /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
@@ -2,10 +2,12 @@ use crate::Config;
 use crate::{
     block_cache::BlockCache,
     deposit_cache::{DepositCache, SszDepositCache},
+    endpoints::Endpoints,
 };
 use parking_lot::RwLock;
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
+use types::DepositTreeSnapshot;
 
 #[derive(Default)]
 pub struct DepositUpdater {
@@ -21,12 +23,28 @@ impl DepositUpdater {
             last_processed_block: None,
         }
     }
+
+    /// Creates a cache of the deposits in `snapshot`, which will be updated from the block after
+    /// the snapshot.
+    pub fn from_snapshot(
+        snapshot: DepositTreeSnapshot,
+        deposit_contract_deploy_block: u64,
+    ) -> Result<Self, String> {
+        let last_processed_block = Some(snapshot.execution_block_height);
+        let cache = DepositCache::from_snapshot(snapshot, deposit_contract_deploy_block)
+            .map_err(|e| format!("Invalid deposit snapshot: {:?}", e))?;
+        Ok(DepositUpdater {
+            cache,
+            last_processed_block,
+        })
+    }
 }
 
 #[derive(Default)]
 pub struct Inner {
     pub block_cache: RwLock<BlockCache>,
     pub deposit_cache: RwLock<DepositUpdater>,
+    pub endpoints: RwLock<Endpoints>,
     pub config: RwLock<Config>,
 }
 
@@ -79,6 +97,7 @@ impl SszEth1Cache {
                 cache: self.deposit_cache.to_deposit_cache()?,
                 last_processed_block: self.last_processed_block,
             }),
+            endpoints: RwLock::new(Endpoints::from_config(&config)),
             config: RwLock::new(config),
         })
     }
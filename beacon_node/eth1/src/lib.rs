@@ -4,6 +4,7 @@ extern crate lazy_static;
 mod block_cache;
 mod deposit_cache;
 mod deposit_log;
+mod endpoints;
 pub mod http;
 mod inner;
 mod metrics;
@@ -12,5 +13,8 @@ mod service;
 pub use block_cache::{BlockCache, Eth1Block};
 pub use deposit_cache::DepositCache;
 pub use deposit_log::DepositLog;
+pub use endpoints::{EndpointHealth, EndpointStatus};
 pub use inner::SszEth1Cache;
-pub use service::{BlockCacheUpdateOutcome, Config, DepositCacheUpdateOutcome, Error, Service};
+pub use service::{
+    BlockCacheUpdateOutcome, CacheStatus, Config, DepositCacheUpdateOutcome, Error, Service,
+};
@@ -16,4 +16,12 @@ lazy_static! {
         try_create_int_gauge("eth1_deposit_cache_len", "Number of deposits in the eth1 cache");
     pub static ref HIGHEST_PROCESSED_DEPOSIT_BLOCK: Result<IntGauge> =
         try_create_int_gauge("eth1_highest_processed_deposit_block", "Number of the last block checked for deposits");
+
+    /*
+     * Eth1 endpoints
+     */
+    pub static ref ENDPOINT_ERRORS: Result<IntCounter> =
+        try_create_int_counter("eth1_endpoint_errors_total", "Count of failed requests to eth1 endpoints");
+    pub static ref ENDPOINT_SWITCHES: Result<IntCounter> =
+        try_create_int_counter("eth1_endpoint_switches_total", "Count of changes to the eth1 endpoint in use");
 }
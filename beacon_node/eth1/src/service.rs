@@ -2,18 +2,20 @@ use crate::metrics;
 use crate::{
     block_cache::{BlockCache, Error as BlockCacheError, Eth1Block},
     deposit_cache::Error as DepositCacheError,
-    http::{get_block, get_block_number, get_deposit_logs_in_range, Log},
+    endpoints::{EndpointStatus, Endpoints, HealthCheck},
+    http::{get_block, get_block_number, get_chain_id, get_deposit_logs_in_range, is_syncing, Log},
     inner::{DepositUpdater, Inner},
     DepositLog,
 };
 use futures::{future::TryFutureExt, stream, stream::TryStreamExt, StreamExt};
 use parking_lot::{RwLock, RwLockReadGuard};
 use serde::{Deserialize, Serialize};
-use slog::{debug, error, info, trace, Logger};
+use slog::{debug, error, info, trace, warn, Logger};
 use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{interval_at, Duration, Instant};
+use types::DepositTreeSnapshot;
 
 const STANDARD_TIMEOUT_MILLIS: u64 = 15_000;
 
@@ -23,6 +25,8 @@ const BLOCK_NUMBER_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 const GET_BLOCK_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 /// Timeout when doing an eth_getLogs to read the deposit contract logs.
 const GET_DEPOSIT_LOG_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
+/// Timeout for each of the eth_blockNumber, eth_syncing and eth_chainId calls of a health check.
+const HEALTH_CHECK_TIMEOUT_MILLIS: u64 = 5_000;
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
@@ -52,6 +56,8 @@ pub enum Error {
         block_range: Range<u64>,
         error: String,
     },
+    /// Every configured eth1 endpoint reports the wrong chain id.
+    NoUsableEndpoint,
     /// There was an unexpected internal error.
     Internal(String),
 }
@@ -69,10 +75,26 @@ pub struct DepositCacheUpdateOutcome {
     pub logs_imported: usize,
 }
 
+/// The contents of the block and deposit caches, as returned by the HTTP API.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct CacheStatus {
+    pub block_cache_len: usize,
+    pub lowest_block_number: Option<u64>,
+    pub highest_block_number: Option<u64>,
+    pub latest_block_timestamp: Option<u64>,
+    pub deposit_cache_len: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// An Eth1 node (e.g., Geth) running a HTTP JSON-RPC endpoint.
     pub endpoint: String,
+    /// Eth1 nodes to fall back to, in order of preference, when `endpoint` is unhealthy.
+    #[serde(default)]
+    pub fallback_endpoints: Vec<String>,
+    /// The chain id the eth1 nodes must report. Endpoints on another chain are never used.
+    #[serde(default)]
+    pub chain_id: Option<u64>,
     /// The address the `BlockCache` and `DepositCache` should assume is the canonical deposit contract.
     pub deposit_contract_address: String,
     /// Defines the first block that the `DepositCache` will start searching for deposit logs.
@@ -97,12 +119,18 @@ pub struct Config {
     pub max_log_requests_per_update: Option<usize>,
     /// The maximum number of log requests per update.
     pub max_blocks_per_update: Option<usize>,
+    /// A snapshot of the deposit tree to start the deposit cache from, instead of downloading
+    /// every deposit log since `deposit_contract_deploy_block`.
+    #[serde(default)]
+    pub deposit_snapshot: Option<DepositTreeSnapshot>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             endpoint: "http://localhost:8545".into(),
+            fallback_endpoints: vec![],
+            chain_id: None,
             deposit_contract_address: "0x0000000000000000000000000000000000000000".into(),
             deposit_contract_deploy_block: 1,
             lowest_cached_block_number: 1,
@@ -112,10 +140,20 @@ impl Default for Config {
             blocks_per_log_query: 1_000,
             max_log_requests_per_update: None,
             max_blocks_per_update: None,
+            deposit_snapshot: None,
         }
     }
 }
 
+impl Config {
+    /// Returns the primary endpoint followed by the fallback endpoints.
+    pub fn endpoints(&self) -> Vec<String> {
+        std::iter::once(self.endpoint.clone())
+            .chain(self.fallback_endpoints.iter().cloned())
+            .collect()
+    }
+}
+
 /// Provides a set of Eth1 caches and async functions to update them.
 ///
 /// Stores the following caches:
@@ -130,12 +168,40 @@ pub struct Service {
 
 impl Service {
     /// Creates a new service. Does not attempt to connect to the eth1 node.
+    ///
+    /// If `config.deposit_snapshot` is invalid it is ignored and every deposit log is downloaded.
     pub fn new(config: Config, log: Logger) -> Self {
+        let deposit_updater = match config.deposit_snapshot.clone() {
+            Some(snapshot) => {
+                let deposit_count = snapshot.deposit_count;
+                match DepositUpdater::from_snapshot(snapshot, config.deposit_contract_deploy_block)
+                {
+                    Ok(deposit_updater) => {
+                        info!(
+                            log,
+                            "Starting deposit cache from snapshot";
+                            "deposit_count" => deposit_count,
+                            "eth1_block" => deposit_updater.last_processed_block,
+                        );
+                        deposit_updater
+                    }
+                    Err(e) => {
+                        error!(
+                            log,
+                            "Ignoring deposit snapshot";
+                            "error" => e,
+                        );
+                        DepositUpdater::new(config.deposit_contract_deploy_block)
+                    }
+                }
+            }
+            None => DepositUpdater::new(config.deposit_contract_deploy_block),
+        };
+
         Self {
             inner: Arc::new(Inner {
-                deposit_cache: RwLock::new(DepositUpdater::new(
-                    config.deposit_contract_deploy_block,
-                )),
+                deposit_cache: RwLock::new(deposit_updater),
+                endpoints: RwLock::new(Endpoints::from_config(&config)),
                 config: RwLock::new(config),
                 ..Inner::default()
             }),
@@ -204,6 +270,161 @@ impl Service {
         self.deposits().read().cache.len()
     }
 
+    /// Returns the number of blocks and deposits in the caches, and the range of cached blocks.
+    pub fn cache_status(&self) -> CacheStatus {
+        let block_cache = self.blocks().read();
+        CacheStatus {
+            block_cache_len: block_cache.len(),
+            lowest_block_number: block_cache.lowest_block_number(),
+            highest_block_number: block_cache.highest_block_number(),
+            latest_block_timestamp: block_cache.latest_block_timestamp(),
+            deposit_cache_len: self.deposit_cache_len(),
+        }
+    }
+
+    /// Returns a snapshot of the first `deposit_index` deposits, which are those included in the
+    /// finalized beacon state.
+    ///
+    /// The snapshot is built from the deposit cache and taken at the eth1 block which included
+    /// the last deposit within it, so that a cache started from it resumes at the next deposit.
+    ///
+    /// Returns an error naming `deposit_index` if the log of the last deposit is not cached, the
+    /// next deposit was included by the same eth1 block, or that block is not in the block cache.
+    pub fn get_deposit_snapshot(&self, deposit_index: u64) -> Result<DepositTreeSnapshot, String> {
+        let deposits = self.deposits().read();
+
+        let block_number = deposit_index
+            .checked_sub(1)
+            .and_then(|i| deposits.cache.get(i as usize))
+            .map(|log| log.block_number)
+            .ok_or_else(|| {
+                format!(
+                    "The last deposit before deposit index {} is not in the deposit cache",
+                    deposit_index
+                )
+            })?;
+
+        if deposits
+            .cache
+            .get(deposit_index as usize)
+            .map_or(false, |log| log.block_number == block_number)
+        {
+            return Err(format!(
+                "Deposit index {} is part way through the deposits of eth1 block {}",
+                deposit_index, block_number
+            ));
+        }
+
+        let block_hash = self
+            .blocks()
+            .read()
+            .block_by_number(block_number)
+            .map(|block| block.hash)
+            .ok_or_else(|| {
+                format!(
+                    "Eth1 block {} of deposit index {} is not in the block cache",
+                    block_number, deposit_index
+                )
+            })?;
+
+        deposits
+            .cache
+            .get_snapshot(deposit_index, block_hash, block_number)
+            .map_err(|e| {
+                format!(
+                    "Unable to build deposit snapshot at deposit index {}: {:?}",
+                    deposit_index, e
+                )
+            })
+    }
+
+    /// Returns the health of each eth1 endpoint, in the configured order.
+    pub fn endpoints(&self) -> Vec<EndpointStatus> {
+        self.inner.endpoints.read().statuses()
+    }
+
+    /// Returns the eth1 endpoint which requests should currently be sent to.
+    fn endpoint(&self) -> Result<String, Error> {
+        self.inner
+            .endpoints
+            .read()
+            .in_use()
+            .map(String::from)
+            .ok_or(Error::NoUsableEndpoint)
+    }
+
+    /// Records the outcome of a cache update against the endpoint it used.
+    fn record_result<T>(&self, endpoint: &str, result: &Result<T, Error>) {
+        match result {
+            Ok(_) => self.inner.endpoints.write().record_success(endpoint),
+            // Errors in our own caches are not the fault of the endpoint.
+            Err(Error::FailedToInsertEth1Block(_))
+            | Err(Error::FailedToInsertDeposit(_))
+            | Err(Error::Internal(_)) => {}
+            Err(e) => {
+                metrics::inc_counter(&metrics::ENDPOINT_ERRORS);
+                self.inner
+                    .endpoints
+                    .write()
+                    .record_error(endpoint, format!("{:?}", e))
+            }
+        }
+    }
+
+    /// Checks the latency, sync status and chain id of every eth1 endpoint, then selects the
+    /// healthiest for subsequent requests.
+    pub async fn update_endpoint_health(service: Self) {
+        let endpoints = service.inner.endpoints.read().urls();
+        let checks = futures::future::join_all(endpoints.iter().map(|endpoint| async move {
+            let timeout = Duration::from_millis(HEALTH_CHECK_TIMEOUT_MILLIS);
+            let start = Instant::now();
+            get_block_number(endpoint, timeout).await?;
+            let latency = start.elapsed();
+
+            Ok::<_, String>(HealthCheck {
+                latency,
+                is_syncing: is_syncing(endpoint, timeout).await?,
+                chain_id: get_chain_id(endpoint, timeout).await?,
+            })
+        }))
+        .await;
+
+        let mut endpoints_lock = service.inner.endpoints.write();
+        for (endpoint, check) in endpoints.iter().zip(checks) {
+            if let Err(e) = &check {
+                debug!(
+                    service.log,
+                    "Eth1 endpoint health check failed";
+                    "endpoint" => endpoint,
+                    "error" => e,
+                );
+            }
+            endpoints_lock.record_health_check(endpoint, check);
+        }
+
+        if endpoints_lock.select() {
+            metrics::inc_counter(&metrics::ENDPOINT_SWITCHES);
+            match endpoints_lock.in_use() {
+                Some(endpoint) if Some(endpoint) == endpoints.first().map(String::as_str) => info!(
+                    service.log,
+                    "Switched back to primary eth1 endpoint";
+                    "endpoint" => endpoint,
+                ),
+                Some(endpoint) => warn!(
+                    service.log,
+                    "Switched to fallback eth1 endpoint";
+                    "endpoint" => endpoint,
+                ),
+                None => error!(
+                    service.log,
+                    "No usable eth1 endpoint";
+                    "expected_chain_id" => service.config().chain_id,
+                    "endpoints" => endpoints.len(),
+                ),
+            }
+        }
+    }
+
     /// Read the service's configuration.
     pub fn config(&self) -> RwLockReadGuard<Config> {
         self.inner.config.read()
@@ -219,6 +440,11 @@ impl Service {
             // This may be possible, I just haven't looked into the details to ensure it's safe.
             Err("Updating deposit_contract_deploy_block is not supported".to_string())
         } else {
+            if new_config.endpoints() != old_config.endpoints()
+                || new_config.chain_id != old_config.chain_id
+            {
+                *self.inner.endpoints.write() = Endpoints::from_config(&new_config);
+            }
             *old_config = new_config;
 
             // Prevents a locking condition when calling prune_blocks.
@@ -248,6 +474,8 @@ impl Service {
     pub async fn update(
         service: Self,
     ) -> Result<(DepositCacheUpdateOutcome, BlockCacheUpdateOutcome), String> {
+        Service::update_endpoint_health(service.clone()).await;
+
         let update_deposit_cache = async {
             let outcome = Service::update_deposit_cache(service.clone())
                 .await
@@ -339,7 +567,16 @@ impl Service {
     ///
     /// Emits logs for debugging and errors.
     pub async fn update_deposit_cache(service: Self) -> Result<DepositCacheUpdateOutcome, Error> {
-        let endpoint = service.config().endpoint.clone();
+        let endpoint = service.endpoint()?;
+        let result = Service::update_deposit_cache_from(service.clone(), &endpoint).await;
+        service.record_result(&endpoint, &result);
+        result
+    }
+
+    async fn update_deposit_cache_from(
+        service: Self,
+        endpoint: &str,
+    ) -> Result<DepositCacheUpdateOutcome, Error> {
         let follow_distance = service.config().follow_distance;
         let deposit_contract_address = service.config().deposit_contract_address.clone();
 
@@ -356,7 +593,7 @@ impl Service {
             .map(|n| n + 1)
             .unwrap_or_else(|| service.config().deposit_contract_deploy_block);
 
-        let range = get_new_block_numbers(&endpoint, next_required_block, follow_distance).await?;
+        let range = get_new_block_numbers(endpoint, next_required_block, follow_distance).await?;
 
         let block_number_chunks = if let Some(range) = range {
             range
@@ -379,7 +616,7 @@ impl Service {
                     Some(chunk) => {
                         let chunk_1 = chunk.clone();
                         match get_deposit_logs_in_range(
-                            &endpoint,
+                            endpoint,
                             &deposit_contract_address,
                             chunk,
                             Duration::from_millis(GET_DEPOSIT_LOG_TIMEOUT_MILLIS),
@@ -472,6 +709,16 @@ impl Service {
     ///
     /// Emits logs for debugging and errors.
     pub async fn update_block_cache(service: Self) -> Result<BlockCacheUpdateOutcome, Error> {
+        let endpoint = service.endpoint()?;
+        let result = Service::update_block_cache_from(service.clone(), &endpoint).await;
+        service.record_result(&endpoint, &result);
+        result
+    }
+
+    async fn update_block_cache_from(
+        service: Self,
+        endpoint: &str,
+    ) -> Result<BlockCacheUpdateOutcome, Error> {
         let block_cache_truncation = service.config().block_cache_truncation;
         let max_blocks_per_update = service
             .config()
@@ -486,10 +733,9 @@ impl Service {
             .map(|n| n + 1)
             .unwrap_or_else(|| service.config().lowest_cached_block_number);
 
-        let endpoint = service.config().endpoint.clone();
         let follow_distance = service.config().follow_distance;
 
-        let range = get_new_block_numbers(&endpoint, next_required_block, follow_distance).await?;
+        let range = get_new_block_numbers(endpoint, next_required_block, follow_distance).await?;
         // Map the range of required blocks into a Vec.
         //
         // If the required range is larger than the size of the cache, drop the exiting cache
@@ -540,7 +786,9 @@ impl Service {
             |mut block_numbers| async {
                 match block_numbers.next() {
                     Some(block_number) => {
-                        match download_eth1_block(service.inner.clone(), block_number).await {
+                        match download_eth1_block(service.inner.clone(), endpoint, block_number)
+                            .await
+                        {
                             Ok(eth1_block) => Ok(Some((eth1_block, block_numbers))),
                             Err(e) => Err(e),
                         }
@@ -659,9 +907,11 @@ async fn get_new_block_numbers<'a>(
 /// `block_number`.
 ///
 /// Performs three async calls to an Eth1 HTTP JSON RPC endpoint.
-async fn download_eth1_block(cache: Arc<Inner>, block_number: u64) -> Result<Eth1Block, Error> {
-    let endpoint = cache.config.read().endpoint.clone();
-
+async fn download_eth1_block(
+    cache: Arc<Inner>,
+    endpoint: &str,
+    block_number: u64,
+) -> Result<Eth1Block, Error> {
     let deposit_root = cache
         .deposit_cache
         .read()
@@ -676,7 +926,7 @@ async fn download_eth1_block(cache: Arc<Inner>, block_number: u64) -> Result<Eth
 
     // Performs a `get_blockByNumber` call to an eth1 node.
     let http_block = get_block(
-        &endpoint,
+        endpoint,
         block_number,
         Duration::from_millis(GET_BLOCK_TIMEOUT_MILLIS),
     )
@@ -758,3 +758,47 @@ mod persist {
         );
     }
 }
+
+mod fallback {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fallback_when_primary_unreachable() {
+        let log = null_logger();
+
+        let eth1 = GanacheEth1Instance::new()
+            .await
+            .expect("should start eth1 environment");
+        let deposit_contract = &eth1.deposit_contract;
+        let web3 = eth1.web3();
+
+        let now = get_block_number(&web3).await;
+        let service = Service::new(
+            Config {
+                // Nothing listens on this port.
+                endpoint: "http://127.0.0.1:1".to_string(),
+                fallback_endpoints: vec![eth1.endpoint()],
+                deposit_contract_address: deposit_contract.address(),
+                deposit_contract_deploy_block: now,
+                lowest_cached_block_number: now,
+                follow_distance: 0,
+                ..Config::default()
+            },
+            log,
+        );
+
+        Service::update(service.clone())
+            .await
+            .expect("should update using the fallback endpoint");
+
+        let endpoints = service.endpoints();
+        assert_eq!(endpoints.len(), 2);
+        assert!(
+            !endpoints[0].in_use,
+            "unreachable primary should not be used"
+        );
+        assert!(endpoints[0].health.errors > 0);
+        assert!(endpoints[1].in_use, "fallback should be used");
+        assert_eq!(endpoints[1].health.is_syncing, Some(false));
+    }
+}
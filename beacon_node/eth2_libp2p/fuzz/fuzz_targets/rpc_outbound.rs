@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use types::MainnetEthSpec;
+
+// Arbitrary bytes sent to us by a peer in response to one of our RPC requests.
+fuzz_target!(|data: &[u8]| {
+    eth2_libp2p::rpc::fuzz::decode_outbound::<MainnetEthSpec>(data);
+});
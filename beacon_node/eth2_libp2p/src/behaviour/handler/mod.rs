@@ -37,8 +37,8 @@ impl<TSpec: EthSpec> BehaviourHandler<TSpec> {
 #[derive(Clone)]
 pub enum BehaviourHandlerIn<TSpec: EthSpec> {
     Delegate(DelegateIn<TSpec>),
-    /// Start the shutdown process.
-    Shutdown(Option<(RequestId, RPCRequest<TSpec>)>),
+    /// Start the shutdown process, sending the given final messages to the peer.
+    Shutdown(Vec<(RequestId, RPCRequest<TSpec>)>),
 }
 
 pub enum BehaviourHandlerOut<TSpec: EthSpec> {
@@ -78,9 +78,9 @@ impl<TSpec: EthSpec> ProtocolsHandler for BehaviourHandler<TSpec> {
         match event {
             BehaviourHandlerIn::Delegate(delegated_ev) => self.delegate.inject_event(delegated_ev),
             /* Events comming from the behaviour */
-            BehaviourHandlerIn::Shutdown(last_message) => {
+            BehaviourHandlerIn::Shutdown(final_messages) => {
                 self.shutting_down = true;
-                self.delegate.rpc_mut().shutdown(last_message);
+                self.delegate.rpc_mut().shutdown(final_messages);
             }
         }
     }
@@ -1,6 +1,9 @@
-use crate::peer_manager::{PeerManager, PeerManagerEvent};
+use crate::peer_manager::{DisconnectCause, PeerManager, PeerManagerEvent};
 use crate::rpc::*;
-use crate::types::{GossipEncoding, GossipKind, GossipTopic};
+use crate::types::{
+    GossipEncoding, GossipKind, GossipTopic, NetworkSnapshot, PeerSnapshot, RoutingTableSnapshot,
+    SyncnetsBitfield,
+};
 use crate::Eth2Enr;
 use crate::{error, Enr, NetworkConfig, NetworkGlobals, PubsubMessage, TopicHash};
 use futures::prelude::*;
@@ -20,16 +23,19 @@ use libp2p::{
     PeerId,
 };
 use lru::LruCache;
+use rpc_streams::RpcStreams;
 use slog::{crit, debug, o};
 use std::{
+    collections::HashSet,
     marker::PhantomData,
     sync::Arc,
     task::{Context, Poll},
-    time::Instant,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 use types::{EnrForkId, EthSpec, SignedBeaconBlock, SubnetId};
 
 mod handler;
+mod rpc_streams;
 
 const MAX_IDENTIFY_ADDRESSES: usize = 10;
 
@@ -63,6 +69,17 @@ pub struct Behaviour<TSpec: EthSpec> {
     // NOTE: This can be accessed via the network_globals ENR. However we keep it here for quick
     // lookups for every gossipsub message send.
     enr_fork_id: EnrForkId,
+    /// The fork digest of the next or previous fork while transitioning between forks, whose
+    /// topics are subscribed to alongside those of the current fork.
+    transition_fork_digest: Option<[u8; 4]>,
+    /// The maximum size of an uncompressed gossip message.
+    gossip_max_size: usize,
+    /// Topics which are never subscribed to.
+    disabled_topics: HashSet<GossipKind>,
+    /// Topics which are never unsubscribed from.
+    forced_topics: HashSet<GossipKind>,
+    /// The RPC requests sent or received whose responses have not yet ended.
+    rpc_streams: RpcStreams,
     /// Logger for behaviour actions.
     log: slog::Logger,
 }
@@ -93,6 +110,8 @@ impl<TSpec: EthSpec> NetworkBehaviour for Behaviour<TSpec> {
     }
 
     fn inject_disconnected(&mut self, peer_id: &PeerId) {
+        self.peer_manager.swarm_disconnected(peer_id);
+        self.rpc_streams.peer_disconnected(peer_id);
         delegate_to_behaviours!(self, inject_disconnected, peer_id);
     }
 
@@ -102,6 +121,8 @@ impl<TSpec: EthSpec> NetworkBehaviour for Behaviour<TSpec> {
         conn_id: &ConnectionId,
         endpoint: &ConnectedPoint,
     ) {
+        self.peer_manager
+            .swarm_connection_established(peer_id, endpoint);
         delegate_to_behaviours!(
             self,
             inject_connection_established,
@@ -257,13 +278,20 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             .bitfield::<TSpec>()
             .expect("Local ENR must have subnet bitfield");
 
-        let meta_data = MetaData {
+        let meta_data = MetaData::V2(MetaDataV2 {
             seq_number: 1,
             attnets,
-        };
+            syncnets: SyncnetsBitfield::<TSpec>::new(),
+        });
+
+        let eth2_rpc = RPC::new(net_conf.constants.clone(), log.clone());
+        #[cfg(feature = "network-testing")]
+        {
+            *eth2_rpc.faults().write() = net_conf.rpc_faults.clone();
+        }
 
         Ok(Behaviour {
-            eth2_rpc: RPC::new(log.clone()),
+            eth2_rpc,
             gossipsub: Gossipsub::new(local_peer_id, net_conf.gs_config.clone()),
             identify,
             peer_manager: PeerManager::new(local_key, net_conf, network_globals.clone(), log)?,
@@ -273,6 +301,11 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             meta_data,
             network_globals,
             enr_fork_id,
+            transition_fork_digest: None,
+            gossip_max_size: net_conf.constants.gossip_max_size,
+            disabled_topics: net_conf.disabled_topics.iter().cloned().collect(),
+            forced_topics: net_conf.forced_topics.iter().cloned().collect(),
+            rpc_streams: RpcStreams::default(),
             log: behaviour_log,
         })
     }
@@ -291,7 +324,17 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
 
     /// Subscribes to a gossipsub topic kind, letting the network service determine the
     /// encoding and fork version.
+    ///
+    /// During a fork transition, the topic of the other fork is also subscribed to. Returns
+    /// whether the topic of the current fork was subscribed to.
     pub fn subscribe_kind(&mut self, kind: GossipKind) -> bool {
+        if let Some(fork_digest) = self.transition_fork_digest {
+            self.subscribe(GossipTopic::new(
+                kind.clone(),
+                GossipEncoding::default(),
+                fork_digest,
+            ));
+        }
         let gossip_topic = GossipTopic::new(
             kind,
             GossipEncoding::default(),
@@ -302,7 +345,20 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
 
     /// Unsubscribes from a gossipsub topic kind, letting the network service determine the
     /// encoding and fork version.
+    ///
+    /// During a fork transition, the topic of the other fork is also unsubscribed from.
     pub fn unsubscribe_kind(&mut self, kind: GossipKind) -> bool {
+        if self.forced_topics.contains(&kind) {
+            debug!(self.log, "Not unsubscribing from forced topic"; "topic" => kind.to_string());
+            return false;
+        }
+        if let Some(fork_digest) = self.transition_fork_digest {
+            self.unsubscribe(GossipTopic::new(
+                kind.clone(),
+                GossipEncoding::default(),
+                fork_digest,
+            ));
+        }
         let gossip_topic = GossipTopic::new(
             kind,
             GossipEncoding::default(),
@@ -313,26 +369,25 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
 
     /// Subscribes to a specific subnet id;
     pub fn subscribe_to_subnet(&mut self, subnet_id: SubnetId) -> bool {
-        let topic = GossipTopic::new(
-            subnet_id.into(),
-            GossipEncoding::default(),
-            self.enr_fork_id.fork_digest,
-        );
-        self.subscribe(topic)
+        self.subscribe_kind(subnet_id.into())
     }
 
     /// Un-Subscribes from a specific subnet id;
     pub fn unsubscribe_from_subnet(&mut self, subnet_id: SubnetId) -> bool {
-        let topic = GossipTopic::new(
-            subnet_id.into(),
-            GossipEncoding::default(),
-            self.enr_fork_id.fork_digest,
-        );
-        self.unsubscribe(topic)
+        if self.forced_topics.contains(&GossipKind::from(subnet_id)) {
+            debug!(self.log, "Not unsubscribing from forced subnet"; "subnet" => *subnet_id);
+            return false;
+        }
+        self.unsubscribe_kind(subnet_id.into())
     }
 
-    /// Subscribes to a gossipsub topic.
+    /// Subscribes to a gossipsub topic, unless its kind is disabled.
     fn subscribe(&mut self, topic: GossipTopic) -> bool {
+        if self.disabled_topics.contains(topic.kind()) {
+            debug!(self.log, "Not subscribing to disabled topic"; "topic" => topic.kind().to_string());
+            return false;
+        }
+
         // update the network globals
         self.network_globals
             .gossipsub_subscriptions
@@ -359,7 +414,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     pub fn publish(&mut self, messages: Vec<PubsubMessage<TSpec>>) {
         for message in messages {
             for topic in message.topics(GossipEncoding::default(), self.enr_fork_id.fork_digest) {
-                match message.encode(GossipEncoding::default()) {
+                match message.encode(GossipEncoding::default(), self.gossip_max_size) {
                     Ok(message_data) => {
                         self.gossipsub.publish(&topic.into(), message_data);
                     }
@@ -380,8 +435,13 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
 
     /// Send a request to a peer over RPC.
     pub fn send_request(&mut self, peer_id: PeerId, request_id: RequestId, request: Request) {
-        self.eth2_rpc
-            .send_request(peer_id, request_id, request.into())
+        if let Request::BlocksByRange(_) = &request {
+            self.peer_manager.blocks_by_range_sent(&peer_id, request_id);
+        }
+        let request: RPCRequest<TSpec> = request.into();
+        self.rpc_streams
+            .outbound_opened(&peer_id, request_id, request.protocol());
+        self.eth2_rpc.send_request(peer_id, request_id, request)
     }
 
     /// Send a successful response to a peer over RPC.
@@ -391,6 +451,12 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         id: PeerRequestId,
         response: Response<TSpec>,
     ) {
+        // a `Status` has a single response, the blocks are followed by a stream termination
+        if let Response::Status(_) | Response::BlocksByRange(None) | Response::BlocksByRoot(None) =
+            &response
+        {
+            self.rpc_streams.inbound_closed(&peer_id, id);
+        }
         self.eth2_rpc.send_response(peer_id, id, response.into())
     }
 
@@ -402,6 +468,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         error: RPCResponseErrorCode,
         reason: String,
     ) {
+        self.rpc_streams.inbound_closed(&peer_id, id);
         self.eth2_rpc.send_response(
             peer_id,
             id,
@@ -424,6 +491,64 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         self.peer_manager.discovery_mut().table_entries_enr()
     }
 
+    /// Takes a diagnostic snapshot of the state of the network, for debugging.
+    pub fn network_snapshot(&mut self) -> NetworkSnapshot<TSpec> {
+        let routing_table = self.enr_entries();
+
+        let peers = self
+            .network_globals
+            .peers
+            .read()
+            .peers()
+            .map(|(peer_id, peer_info)| PeerSnapshot {
+                peer_id: peer_id.to_string(),
+                peer_info: peer_info.clone(),
+            })
+            .collect();
+
+        let mut subscriptions = self
+            .network_globals
+            .gossipsub_subscriptions
+            .read()
+            .iter()
+            .map(|topic| topic.clone().into())
+            .collect::<Vec<String>>();
+        subscriptions.sort();
+
+        let mesh_peers = subscriptions
+            .iter()
+            .map(|topic| {
+                let mut peers = self
+                    .gossipsub
+                    .mesh_peers(&TopicHash::from_raw(topic.clone()))
+                    .map(PeerId::to_string)
+                    .collect::<Vec<_>>();
+                peers.sort();
+                (topic.clone(), peers)
+            })
+            .collect();
+
+        NetworkSnapshot {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64),
+            peers,
+            routing_table: RoutingTableSnapshot {
+                entries: routing_table.len(),
+                node_ids: routing_table
+                    .iter()
+                    .map(|enr| enr.node_id().to_string())
+                    .collect(),
+            },
+            subscriptions,
+            mesh_peers,
+            rpc_streams: self.rpc_streams.snapshot(),
+            range_requests: self.peer_manager.pending_range_requests(),
+            sync_state: self.network_globals.sync_state(),
+            sync_status: self.network_globals.sync_status(),
+        }
+    }
+
     /// Add an ENR to the routing table of the discovery mechanism.
     pub fn add_enr(&mut self, enr: Enr) {
         self.peer_manager.discovery_mut().add_enr(enr);
@@ -450,56 +575,150 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         self.peer_manager.discover_subnet_peers(subnet_id, min_ttl)
     }
 
-    /// Updates the local ENR's "eth2" field with the latest EnrForkId.
+    /// Sets the `key` field of the local ENR to `value`.
+    ///
+    /// If the ENR changes, the metadata sequence number is incremented so that our peers request
+    /// our updated metadata on their next PING.
+    pub fn update_enr_field(&mut self, key: &str, value: Vec<u8>) -> Result<(), String> {
+        if self
+            .peer_manager
+            .discovery_mut()
+            .update_enr_field(key, value)?
+        {
+            self.update_metadata();
+        }
+        Ok(())
+    }
+
+    /// Attempts to discover and connect to new peers whose ENR satisfies `predicate`.
+    pub fn discover_peers_with_predicate<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Enr) -> bool + Send + Sync + 'static,
+    {
+        self.peer_manager.discover_peers_with_predicate(predicate)
+    }
+
+    /// Updates the local ENR's "eth2" field with the latest EnrForkId and subscribes to the
+    /// new fork's counterparts of our gossip topics.
+    ///
+    /// The topics of the previous fork remain subscribed, so that messages from peers which have
+    /// not yet transitioned are still received, until `end_fork_transition` is called.
     pub fn update_fork_version(&mut self, enr_fork_id: EnrForkId) {
         self.peer_manager
             .discovery_mut()
             .update_eth2_enr(enr_fork_id.clone());
 
-        // unsubscribe from all gossip topics and re-subscribe to their new fork counterparts
-        let subscribed_topics = self
+        let previous_fork_digest = self.enr_fork_id.fork_digest;
+        if enr_fork_id.fork_digest != previous_fork_digest {
+            self.subscribe_fork_counterparts(enr_fork_id.fork_digest);
+            self.transition_fork_digest = Some(previous_fork_digest);
+        }
+
+        // update the local reference
+        self.enr_fork_id = enr_fork_id;
+
+        self.reconcile_forced_topics();
+    }
+
+    /// Subscribes to the next fork's counterparts of our gossip topics ahead of the fork, so that
+    /// no messages are missed when it activates. The topics of the current fork remain
+    /// subscribed.
+    pub fn begin_fork_transition(&mut self, next_fork_digest: [u8; 4]) {
+        if next_fork_digest == self.enr_fork_id.fork_digest {
+            return;
+        }
+        debug!(self.log, "Subscribing to the topics of the next fork";
+            "fork_digest" => hex::encode(next_fork_digest));
+        self.subscribe_fork_counterparts(next_fork_digest);
+        self.transition_fork_digest = Some(next_fork_digest);
+    }
+
+    /// Unsubscribes from the topics of the fork other than the current one, once the fork
+    /// transition has completed.
+    pub fn end_fork_transition(&mut self) {
+        let fork_digest = match self.transition_fork_digest.take() {
+            Some(fork_digest) => fork_digest,
+            None => return,
+        };
+        debug!(self.log, "Unsubscribing from the topics of the previous fork";
+            "fork_digest" => hex::encode(fork_digest));
+
+        let transition_topics = self
             .network_globals
             .gossipsub_subscriptions
             .read()
             .iter()
+            .filter(|topic| topic.fork_digest() == fork_digest)
             .cloned()
-            .collect::<Vec<GossipTopic>>();
-
-        //  unsubscribe from all topics
-        for topic in &subscribed_topics {
-            self.unsubscribe(topic.clone());
+            .collect::<Vec<_>>();
+        for topic in transition_topics {
+            self.unsubscribe(topic);
         }
+    }
 
-        // re-subscribe modifying the fork version
-        for mut topic in subscribed_topics {
-            *topic.digest() = enr_fork_id.fork_digest;
+    /// Subscribes to the counterparts of the current fork's topics with `fork_digest`.
+    fn subscribe_fork_counterparts(&mut self, fork_digest: [u8; 4]) {
+        let current_fork_digest = self.enr_fork_id.fork_digest;
+        let counterparts = self
+            .network_globals
+            .gossipsub_subscriptions
+            .read()
+            .iter()
+            .filter(|topic| topic.fork_digest() == current_fork_digest)
+            .map(|topic| {
+                let mut topic = topic.clone();
+                *topic.digest() = fork_digest;
+                topic
+            })
+            .collect::<Vec<_>>();
+        for topic in counterparts {
             self.subscribe(topic);
         }
+    }
 
-        // update the local reference
-        self.enr_fork_id = enr_fork_id;
+    /// Subscribes to any forced topics which we are not subscribed to on the current fork.
+    pub fn reconcile_forced_topics(&mut self) {
+        let fork_digest = self.enr_fork_id.fork_digest;
+        let missing_topics = self
+            .forced_topics
+            .iter()
+            .map(|kind| GossipTopic::new(kind.clone(), GossipEncoding::default(), fork_digest))
+            .filter(|topic| {
+                !self
+                    .network_globals
+                    .gossipsub_subscriptions
+                    .read()
+                    .contains(topic)
+            })
+            .collect::<Vec<_>>();
+
+        for topic in missing_topics {
+            self.subscribe(topic);
+        }
     }
 
     /* Private internal functions */
 
     /// Updates the current meta data of the node to match the local ENR.
     fn update_metadata(&mut self) {
-        self.meta_data.seq_number += 1;
-        self.meta_data.attnets = self
+        let attnets = self
             .peer_manager
             .discovery()
             .local_enr()
             .bitfield::<TSpec>()
             .expect("Local discovery must have bitfield");
+        self.meta_data.set_attnets(attnets);
     }
 
     /// Sends a Ping request to the peer.
     fn ping(&mut self, id: RequestId, peer_id: PeerId) {
         let ping = crate::rpc::Ping {
-            data: self.meta_data.seq_number,
+            data: self.meta_data.seq_number(),
         };
         debug!(self.log, "Sending Ping"; "request_id" => id, "peer_id" => peer_id.to_string());
 
+        self.rpc_streams
+            .outbound_opened(&peer_id, id, Protocol::Ping);
         self.eth2_rpc
             .send_request(peer_id, id, RPCRequest::Ping(ping));
     }
@@ -507,7 +726,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     /// Sends a Pong response to the peer.
     fn pong(&mut self, id: PeerRequestId, peer_id: PeerId) {
         let ping = crate::rpc::Ping {
-            data: self.meta_data.seq_number,
+            data: self.meta_data.seq_number(),
         };
         debug!(self.log, "Sending Pong"; "request_id" => id.1, "peer_id" => peer_id.to_string());
         let event = RPCCodedResponse::Success(RPCResponse::Pong(ping));
@@ -517,6 +736,8 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     /// Sends a METADATA request to a peer.
     fn send_meta_data_request(&mut self, peer_id: PeerId) {
         let event = RPCRequest::MetaData(PhantomData);
+        self.rpc_streams
+            .outbound_opened(&peer_id, RequestId::Behaviour, Protocol::MetaData);
         self.eth2_rpc
             .send_request(peer_id, RequestId::Behaviour, event);
     }
@@ -527,6 +748,13 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
         self.eth2_rpc.send_response(peer_id, id, event);
     }
 
+    /// A handle to the faults injected into the RPC responses we send, which may be changed at
+    /// runtime.
+    #[cfg(feature = "network-testing")]
+    pub fn rpc_faults(&self) -> crate::rpc::fault_injection::RPCFaultsHandle {
+        self.eth2_rpc.faults()
+    }
+
     /// Returns a reference to the peer manager to allow the swarm to notify the manager of peer
     /// status
     pub fn peer_manager(&mut self) -> &mut PeerManager<TSpec> {
@@ -554,10 +782,10 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             };
 
             // use this as a baseline, until we get the actual meta-data
-            let meta_data = MetaData {
+            let meta_data = MetaData::V1(MetaDataV1 {
                 seq_number: 0,
                 attnets: bitfield,
-            };
+            });
             // TODO: Shift to the peer manager
             self.network_globals
                 .peers
@@ -570,10 +798,16 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     fn on_gossip_event(&mut self, event: GossipsubEvent) {
         match event {
             GossipsubEvent::Message(propagation_source, id, gs_msg) => {
+                // peers held until they prove to be members of our private network are ignored
+                if self.peer_manager.is_unverified(&propagation_source) {
+                    debug!(self.log, "Ignoring gossip from an unverified peer"; "peer_id" => propagation_source.to_string());
+                    return;
+                }
                 // Note: We are keeping track here of the peer that sent us the message, not the
                 // peer that originally published the message.
                 if self.seen_gossip_messages.put(id.clone(), ()).is_none() {
-                    match PubsubMessage::decode(&gs_msg.topics, &gs_msg.data) {
+                    match PubsubMessage::decode(&gs_msg.topics, &gs_msg.data, self.gossip_max_size)
+                    {
                         Err(e) => {
                             debug!(self.log, "Could not decode gossipsub message"; "error" => format!("{}", e))
                         }
@@ -588,7 +822,11 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                         }
                     }
                 } else {
-                    match PubsubMessage::<TSpec>::decode(&gs_msg.topics, &gs_msg.data) {
+                    match PubsubMessage::<TSpec>::decode(
+                        &gs_msg.topics,
+                        &gs_msg.data,
+                        self.gossip_max_size,
+                    ) {
                         Err(e) => {
                             debug!(self.log, "Could not decode gossipsub message"; "error" => format!("{}", e))
                         }
@@ -629,15 +867,22 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
     fn on_rpc_event(&mut self, message: RPCMessage<TSpec>) {
         let peer_id = message.peer_id;
         let handler_id = message.conn_id;
+        // peers held until they prove to be members of our private network may only say goodbye
+        if self.peer_manager.is_unverified(&peer_id)
+            && !matches!(
+                message.event,
+                Ok(RPCReceived::Request(_, RPCRequest::Goodbye(_)))
+            )
+        {
+            debug!(self.log, "Ignoring RPC from an unverified peer"; "peer_id" => peer_id.to_string());
+            return;
+        }
         // The METADATA and PING RPC responses are handled within the behaviour and not propagated
         match message.event {
             Err(handler_err) => {
                 match handler_err {
-                    HandlerErr::Inbound {
-                        id: _,
-                        proto,
-                        error,
-                    } => {
+                    HandlerErr::Inbound { id, proto, error } => {
+                        self.rpc_streams.inbound_closed(&peer_id, (handler_id, id));
                         if matches!(error, RPCError::HandlerRejected) {
                             // this peer's request got canceled
                             // TODO: cancel processing for this request
@@ -648,8 +893,12 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                         self.peer_manager.handle_rpc_error(&peer_id, proto, &error);
                     }
                     HandlerErr::Outbound { id, proto, error } => {
+                        self.rpc_streams.outbound_closed(&peer_id, id, proto);
                         // Inform the peer manager that a request we sent to the peer failed
                         self.peer_manager.handle_rpc_error(&peer_id, proto, &error);
+                        if let Protocol::BlocksByRange = proto {
+                            self.peer_manager.blocks_by_range_failed(&peer_id, id);
+                        }
                         // inform failures of requests comming outside the behaviour
                         if !matches!(id, RequestId::Behaviour) {
                             self.events
@@ -660,6 +909,14 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             }
             Ok(RPCReceived::Request(id, request)) => {
                 let peer_request_id = (handler_id, id);
+                // requests answered by the behaviour are responded to immediately
+                if let RPCRequest::Status(_)
+                | RPCRequest::BlocksByRange(_)
+                | RPCRequest::BlocksByRoot(_) = &request
+                {
+                    self.rpc_streams
+                        .inbound_opened(&peer_id, peer_request_id, request.protocol());
+                }
                 match request {
                     /* Behaviour managed protocols: Ping and Metadata */
                     RPCRequest::Ping(ping) => {
@@ -675,7 +932,10 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                     }
                     RPCRequest::Goodbye(reason) => {
                         // let the peer manager know this peer is in the process of disconnecting
-                        self.peer_manager._disconnecting_peer(&peer_id);
+                        self.peer_manager.disconnecting_peer(
+                            &peer_id,
+                            DisconnectCause::Remote(Some(reason.clone())),
+                        );
                         // queue for disconnection without a goodbye message
                         debug!(self.log, "Received a Goodbye, queueing for disconnection";
                             "peer_id" => peer_id.to_string());
@@ -683,6 +943,11 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                         // TODO: do not propagate
                         self.propagate_request(peer_request_id, peer_id, Request::Goodbye(reason));
                     }
+                    RPCRequest::PeerExchange(peer_exchange) => {
+                        // the peer manager decides whether to dial the suggested peers
+                        self.peer_manager
+                            .peer_exchange_received(&peer_id, peer_exchange.enrs);
+                    }
                     /* Protocols propagated to the Network */
                     RPCRequest::Status(msg) => {
                         // inform the peer manager that we have received a status from a peer
@@ -701,6 +966,22 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                 }
             }
             Ok(RPCReceived::Response(id, resp)) => {
+                // blocks are followed by a stream termination, other responses end the stream
+                match &resp {
+                    RPCResponse::Pong(_) => {
+                        self.rpc_streams
+                            .outbound_closed(&peer_id, id, Protocol::Ping)
+                    }
+                    RPCResponse::MetaData(_) => {
+                        self.rpc_streams
+                            .outbound_closed(&peer_id, id, Protocol::MetaData)
+                    }
+                    RPCResponse::Status(_) => {
+                        self.rpc_streams
+                            .outbound_closed(&peer_id, id, Protocol::Status)
+                    }
+                    RPCResponse::BlocksByRange(_) | RPCResponse::BlocksByRoot(_) => {}
+                }
                 match resp {
                     /* Behaviour managed protocols */
                     RPCResponse::Pong(ping) => self.peer_manager.pong_response(&peer_id, ping.data),
@@ -715,6 +996,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                         self.propagate_response(id, peer_id, Response::Status(msg));
                     }
                     RPCResponse::BlocksByRange(resp) => {
+                        self.peer_manager.blocks_by_range_block(&peer_id, id);
                         self.propagate_response(id, peer_id, Response::BlocksByRange(Some(resp)))
                     }
                     RPCResponse::BlocksByRoot(resp) => {
@@ -724,8 +1006,17 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             }
             Ok(RPCReceived::EndOfStream(id, termination)) => {
                 let response = match termination {
-                    ResponseTermination::BlocksByRange => Response::BlocksByRange(None),
-                    ResponseTermination::BlocksByRoot => Response::BlocksByRoot(None),
+                    ResponseTermination::BlocksByRange => {
+                        self.peer_manager.blocks_by_range_completed(&peer_id, id);
+                        self.rpc_streams
+                            .outbound_closed(&peer_id, id, Protocol::BlocksByRange);
+                        Response::BlocksByRange(None)
+                    }
+                    ResponseTermination::BlocksByRoot => {
+                        self.rpc_streams
+                            .outbound_closed(&peer_id, id, Protocol::BlocksByRoot);
+                        Response::BlocksByRoot(None)
+                    }
                 };
                 self.propagate_response(id, peer_id, response);
             }
@@ -742,7 +1033,7 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
             return Poll::Ready(NBAction::NotifyHandler {
                 peer_id: self.peers_to_dc.remove(0),
                 handler: NotifyHandler::All,
-                event: BehaviourHandlerIn::Shutdown(None),
+                event: BehaviourHandlerIn::Shutdown(Vec::new()),
             });
         }
 
@@ -773,21 +1064,48 @@ impl<TSpec: EthSpec> Behaviour<TSpec> {
                     PeerManagerEvent::MetaData(peer_id) => {
                         self.send_meta_data_request(peer_id);
                     }
-                    PeerManagerEvent::DisconnectPeer(peer_id) => {
+                    PeerManagerEvent::DisconnectPeer(peer_id, reason) => {
                         debug!(self.log, "PeerManager requested to disconnect a peer";
-                            "peer_id" => peer_id.to_string());
+                            "peer_id" => peer_id.to_string(), "reason" => reason.to_string());
+                        self.peer_manager
+                            .disconnecting_peer(&peer_id, DisconnectCause::Local(reason.clone()));
+                        let mut final_messages = Vec::new();
+                        // offer the peer some alternatives before telling it we are full
+                        if reason == GoodbyeReason::TooManyPeers {
+                            let enrs = self.peer_manager.peer_exchange_enrs(&peer_id);
+                            if !enrs.is_empty() {
+                                final_messages.push((
+                                    RequestId::Behaviour,
+                                    RPCRequest::PeerExchange(PeerExchange { enrs }),
+                                ));
+                            }
+                        }
+                        final_messages.push((RequestId::Behaviour, RPCRequest::Goodbye(reason)));
                         // queue for disabling
                         self.peers_to_dc.push(peer_id.clone());
                         // send one goodbye
                         return Poll::Ready(NBAction::NotifyHandler {
                             peer_id,
                             handler: NotifyHandler::Any,
-                            event: BehaviourHandlerIn::Shutdown(Some((
-                                RequestId::Behaviour,
-                                RPCRequest::Goodbye(GoodbyeReason::Fault),
-                            ))),
+                            event: BehaviourHandlerIn::Shutdown(final_messages),
                         });
                     }
+                    PeerManagerEvent::BanPeer(peer_id) => {
+                        // the swarm is only accessible to the service, which bans the peer
+                        return Poll::Ready(NBAction::GenerateEvent(BehaviourEvent::PeerBanned(
+                            peer_id,
+                        )));
+                    }
+                    PeerManagerEvent::UnbanPeer(peer_id) => {
+                        return Poll::Ready(NBAction::GenerateEvent(BehaviourEvent::PeerUnbanned(
+                            peer_id,
+                        )));
+                    }
+                    PeerManagerEvent::AdmitPeer(peer_id, endpoint) => {
+                        return Poll::Ready(NBAction::GenerateEvent(BehaviourEvent::PeerAdmitted(
+                            peer_id, endpoint,
+                        )));
+                    }
                 },
                 Poll::Pending => break,
                 Poll::Ready(None) => break, // peer manager ended
@@ -939,4 +1257,14 @@ pub enum BehaviourEvent<TSpec: EthSpec> {
     PeerSubscribed(PeerId, TopicHash),
     /// Inform the network to send a Status to this peer.
     StatusPeer(PeerId),
+    /// The peer manager has banned the peer. This is handled by the libp2p service, which bans
+    /// the peer from the swarm.
+    PeerBanned(PeerId),
+    /// The peer manager has unbanned the peer. This is handled by the libp2p service, which
+    /// unbans the peer from the swarm.
+    PeerUnbanned(PeerId),
+    /// The peer manager has admitted a peer whose connection was held until it proved to be a
+    /// member of our private network. This is handled by the libp2p service, which reports the
+    /// peer as connected.
+    PeerAdmitted(PeerId, ConnectedPoint),
 }
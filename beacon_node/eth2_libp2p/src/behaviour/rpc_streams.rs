@@ -0,0 +1,136 @@
+use super::PeerRequestId;
+use crate::rpc::{Protocol, RequestId};
+use crate::types::RpcStreamSnapshot;
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Tracks the RPC requests awaiting or sending responses, so that they can be reported in a
+/// network snapshot.
+///
+/// Requests which are not responded to, such as `Goodbye`, are not tracked.
+#[derive(Default)]
+pub struct RpcStreams {
+    /// The requests we have sent whose responses have not yet ended, and when they were sent.
+    outbound: HashMap<(PeerId, RequestId, Protocol), Instant>,
+    /// The requests we have received whose responses have not yet ended, and when they were
+    /// received.
+    inbound: HashMap<(PeerId, PeerRequestId), (Protocol, Instant)>,
+}
+
+impl RpcStreams {
+    /// A request was sent to the peer.
+    pub fn outbound_opened(&mut self, peer_id: &PeerId, id: RequestId, protocol: Protocol) {
+        if let Protocol::Goodbye = protocol {
+            return;
+        }
+        self.outbound
+            .insert((peer_id.clone(), id, protocol), Instant::now());
+    }
+
+    /// The response to a request sent to the peer has ended, or the request failed.
+    pub fn outbound_closed(&mut self, peer_id: &PeerId, id: RequestId, protocol: Protocol) {
+        self.outbound.remove(&(peer_id.clone(), id, protocol));
+    }
+
+    /// A request was received from the peer.
+    pub fn inbound_opened(&mut self, peer_id: &PeerId, id: PeerRequestId, protocol: Protocol) {
+        if let Protocol::Goodbye = protocol {
+            return;
+        }
+        self.inbound
+            .insert((peer_id.clone(), id), (protocol, Instant::now()));
+    }
+
+    /// Our response to a request from the peer has ended, or the request failed.
+    pub fn inbound_closed(&mut self, peer_id: &PeerId, id: PeerRequestId) {
+        self.inbound.remove(&(peer_id.clone(), id));
+    }
+
+    /// The peer has disconnected, closing all of its streams.
+    pub fn peer_disconnected(&mut self, peer_id: &PeerId) {
+        self.outbound.retain(|(id, _, _), _| id != peer_id);
+        self.inbound.retain(|(id, _), _| id != peer_id);
+    }
+
+    /// Returns the open streams, oldest first.
+    pub fn snapshot(&self) -> Vec<RpcStreamSnapshot> {
+        let now = Instant::now();
+        let outbound = self.outbound.iter().map(|((peer_id, id, protocol), sent)| {
+            (peer_id, "outbound", format!("{:?}", id), protocol, sent)
+        });
+        let inbound = self
+            .inbound
+            .iter()
+            .map(|((peer_id, (_, id)), (protocol, received))| {
+                (peer_id, "inbound", format!("{:?}", id), protocol, received)
+            });
+        let mut streams = outbound
+            .chain(inbound)
+            .map(
+                |(peer_id, direction, request_id, protocol, opened)| RpcStreamSnapshot {
+                    peer_id: peer_id.to_string(),
+                    direction: direction.to_string(),
+                    protocol: protocol.to_string(),
+                    request_id,
+                    age_millis: now.saturating_duration_since(*opened).as_millis() as u64,
+                },
+            )
+            .collect::<Vec<_>>();
+        streams.sort_by(|a, b| b.age_millis.cmp(&a.age_millis));
+        streams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::SubstreamId;
+    use libp2p::core::connection::ConnectionId;
+
+    fn protocols(streams: &RpcStreams) -> Vec<(String, String)> {
+        let mut protocols = streams
+            .snapshot()
+            .into_iter()
+            .map(|stream| (stream.direction, stream.protocol))
+            .collect::<Vec<_>>();
+        protocols.sort();
+        protocols
+    }
+
+    #[test]
+    fn streams_are_reported_until_closed() {
+        let mut streams = RpcStreams::default();
+        let peer_id = PeerId::random();
+        let inbound_id = (ConnectionId::new(0), SubstreamId::new(0));
+
+        streams.outbound_opened(&peer_id, RequestId::Behaviour, Protocol::Ping);
+        streams.outbound_opened(&peer_id, RequestId::Behaviour, Protocol::MetaData);
+        streams.outbound_opened(&peer_id, RequestId::Sync(1), Protocol::BlocksByRange);
+        streams.outbound_opened(&peer_id, RequestId::Behaviour, Protocol::Goodbye);
+        streams.inbound_opened(&peer_id, inbound_id, Protocol::BlocksByRoot);
+
+        assert_eq!(
+            protocols(&streams),
+            vec![
+                ("inbound".into(), "beacon_blocks_by_root".into()),
+                ("outbound".into(), "beacon_blocks_by_range".into()),
+                ("outbound".into(), "metadata".into()),
+                ("outbound".into(), "ping".into()),
+            ]
+        );
+
+        streams.outbound_closed(&peer_id, RequestId::Behaviour, Protocol::Ping);
+        streams.inbound_closed(&peer_id, inbound_id);
+        assert_eq!(
+            protocols(&streams),
+            vec![
+                ("outbound".into(), "beacon_blocks_by_range".into()),
+                ("outbound".into(), "metadata".into()),
+            ]
+        );
+
+        streams.peer_disconnected(&peer_id);
+        assert!(streams.snapshot().is_empty());
+    }
+}
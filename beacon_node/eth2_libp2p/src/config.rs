@@ -1,15 +1,73 @@
+use crate::peer_manager::ReputationConfig;
+use crate::rpc::MAX_REQUEST_BLOCKS;
 use crate::types::GossipKind;
 use crate::Enr;
+use crate::NetworkConstants;
 use discv5::{Discv5Config, Discv5ConfigBuilder};
 use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder, GossipsubMessage, MessageId};
 use libp2p::Multiaddr;
 use serde_derive::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use snap::raw::{decompress_len, Decoder};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
+/// The target number of peers of a node in proposer-only mode.
+pub const PROPOSER_ONLY_MAX_PEERS: usize = 10;
+
+/// The target number of peers of a node which does not accept inbound connections, as a
+/// percentage of the target it would otherwise have.
+pub const OUTBOUND_ONLY_PEERS_PERCENT: usize = 150;
+
+/// The default maximum size of a gossip message, as per the specification.
+///
+/// The size used by the node is configured by `NetworkConstants::gossip_max_size`. This value is
+/// only used to bound decompression when computing message ids, since the message id function
+/// cannot access the config.
 pub const GOSSIP_MAX_SIZE: usize = 1_048_576;
 
+/// Domain prepended to the decompressed message data when computing the id of a gossip message
+/// with a valid snappy encoding.
+pub const MESSAGE_DOMAIN_VALID_SNAPPY: [u8; 4] = [1, 0, 0, 0];
+/// Domain prepended to the raw message data when computing the id of a gossip message which
+/// cannot be snappy decompressed.
+pub const MESSAGE_DOMAIN_INVALID_SNAPPY: [u8; 4] = [0, 0, 0, 0];
+/// The number of bytes of the SHA256 digest used as a gossip message id.
+pub const MESSAGE_ID_LEN: usize = 20;
+
+/// A profile of how much bandwidth the node is willing to use, which determines the number of
+/// peers, the gossipsub mesh and how many blocks we serve to syncing peers.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkLoad {
+    /// Suitable for nodes with constrained uplinks, e.g., Raspberry Pis.
+    Low,
+    /// The default profile.
+    Normal,
+    /// Suitable for well-connected nodes which wish to contribute more to the network.
+    High,
+}
+
+impl Default for NetworkLoad {
+    fn default() -> Self {
+        NetworkLoad::Normal
+    }
+}
+
+impl FromStr for NetworkLoad {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(NetworkLoad::Low),
+            "normal" => Ok(NetworkLoad::Normal),
+            "high" => Ok(NetworkLoad::High),
+            other => Err(format!("Unknown network load: {}", other)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 /// Network configuration for lighthouse.
@@ -20,6 +78,13 @@ pub struct Config {
     /// IP address to listen on.
     pub listen_address: std::net::IpAddr,
 
+    /// Further IP addresses that libp2p listens on, to bind to multiple interfaces.
+    pub additional_listen_addresses: Vec<std::net::IpAddr>,
+
+    /// IP address that discovery listens on. `None` indicates that discovery listens on
+    /// `listen_address`.
+    pub discovery_address: Option<std::net::IpAddr>,
+
     /// The TCP port that libp2p listens on.
     pub libp2p_port: u16,
 
@@ -36,9 +101,42 @@ pub struct Config {
     /// The tcp port to broadcast to peers in order to reach back for libp2p services.
     pub enr_tcp_port: Option<u16>,
 
+    /// Custom key-value pairs published in the local ENR, e.g. to tag the node.
+    pub enr_custom_fields: Vec<(String, Vec<u8>)>,
+
     /// Target number of connected peers.
     pub max_peers: usize,
 
+    /// The minimum number of seconds a peer must be connected before it may be disconnected to
+    /// bring the number of peers down to `max_peers`.
+    pub min_connection_age_secs: u64,
+
+    /// The number of gossip messages from a peer which may fail validation within a minute before
+    /// the peer is penalized.
+    pub gossip_reject_limit: u64,
+
+    /// The bandwidth profile last applied with `Config::set_network_load`.
+    pub network_load: NetworkLoad,
+
+    /// The maximum number of blocks served in response to a single `BlocksByRange` request.
+    pub blocks_by_range_quota: u64,
+
+    /// Whether the node only publishes blocks and follows the head, without joining attestation
+    /// subnets. Set with `Config::set_proposer_only`.
+    pub proposer_only: bool,
+
+    /// Whether the node refuses inbound connections, e.g. because it is behind a strict firewall.
+    /// Set with `Config::set_disable_inbound`.
+    pub disable_inbound: bool,
+
+    /// The maximum rate, in MB/s, at which blocks are served to peers over RPC. Requests beyond
+    /// this rate receive a rate limited error. `None` if the rate is not limited.
+    pub block_serving_rate_limit: Option<u64>,
+
+    /// Whether serving blocks to syncing peers and searching for new peers are deferred until
+    /// after the first third of each slot, when blocks are imported and attestations produced.
+    pub slot_aware_scheduling: bool,
+
     /// Gossipsub configuration parameters.
     #[serde(skip)]
     pub gs_config: GossipsubConfig,
@@ -50,14 +148,52 @@ pub struct Config {
     /// List of nodes to initially connect to.
     pub boot_nodes: Vec<Enr>,
 
+    /// URLs of ENR trees published over DNS (`enrtree://<public key>@<domain>`), whose nodes are
+    /// used alongside the boot nodes.
+    pub dns_discovery: Vec<String>,
+
     /// List of libp2p nodes to initially connect to.
     pub libp2p_nodes: Vec<Multiaddr>,
 
+    /// A file of banned peers, IP addresses and subnets which is loaded on startup and updated
+    /// as peers are banned.
+    pub ban_list_file: Option<PathBuf>,
+
+    /// Only connect to peers whose node id is in `private_network_allowlist`, or whose ENR proves
+    /// that they know `private_network_secret`.
+    pub private_network: bool,
+
+    /// A file of the node ids which may join the private network, one per line.
+    pub private_network_allowlist: Option<PathBuf>,
+
+    /// The shared secret of the private network.
+    #[serde(skip)]
+    pub private_network_secret: Option<String>,
+
+    /// The faults injected into the RPC responses we send, for testing.
+    #[cfg(feature = "network-testing")]
+    #[serde(skip)]
+    pub rpc_faults: crate::rpc::fault_injection::RPCFaults,
+
     /// Client version
     pub client_version: String,
 
     /// List of extra topics to initially subscribe to as strings.
     pub topics: Vec<GossipKind>,
+
+    /// Topics which are never subscribed to, even when requested, e.g. by a validator duty.
+    pub disabled_topics: Vec<GossipKind>,
+
+    /// Topics which are always subscribed to, even when an unsubscription is requested.
+    pub forced_topics: Vec<GossipKind>,
+
+    /// The networking constants from the specification.
+    #[serde(skip)]
+    pub constants: NetworkConstants,
+
+    /// The reputation changes applied to peers for each kind of action, and how quickly a poor
+    /// reputation is forgiven.
+    pub reputation: ReputationConfig,
 }
 
 impl Default for Config {
@@ -76,20 +212,13 @@ impl Default for Config {
             GossipKind::AttesterSlashing,
         ];
 
-        // The function used to generate a gossipsub message id
-        // We use base64(SHA256(data)) for content addressing
-        let gossip_message_id = |message: &GossipsubMessage| {
-            MessageId(base64::encode_config(
-                &Sha256::digest(&message.data),
-                base64::URL_SAFE_NO_PAD,
-            ))
-        };
+        let constants = NetworkConstants::default();
 
         // gossipsub configuration
         // Note: The topics by default are sent as plain strings. Hashes are an optional
         // parameter.
         let gs_config = GossipsubConfigBuilder::new()
-            .max_transmit_size(GOSSIP_MAX_SIZE)
+            .max_transmit_size(constants.gossip_max_size)
             .heartbeat_interval(Duration::from_secs(1))
             .manual_propagation() // require validation before propagation
             .no_source_id()
@@ -111,21 +240,210 @@ impl Default for Config {
             .build();
 
         // NOTE: Some of these get overridden by the corresponding CLI default values.
-        Config {
+        let mut config = Config {
             network_dir,
             listen_address: "0.0.0.0".parse().expect("valid ip address"),
+            additional_listen_addresses: vec![],
+            discovery_address: None,
             libp2p_port: 9000,
             discovery_port: 9000,
             enr_address: None,
             enr_udp_port: None,
             enr_tcp_port: None,
+            enr_custom_fields: vec![],
             max_peers: 50,
+            min_connection_age_secs: 120,
+            gossip_reject_limit: 5,
+            network_load: NetworkLoad::Normal,
+            blocks_by_range_quota: MAX_REQUEST_BLOCKS as u64,
+            block_serving_rate_limit: None,
+            slot_aware_scheduling: false,
+            proposer_only: false,
+            disable_inbound: false,
             gs_config,
             discv5_config,
             boot_nodes: vec![],
+            dns_discovery: vec![],
             libp2p_nodes: vec![],
+            ban_list_file: None,
+            private_network: false,
+            private_network_allowlist: None,
+            private_network_secret: None,
+            #[cfg(feature = "network-testing")]
+            rpc_faults: <_>::default(),
             client_version: version::version(),
             topics,
+            disabled_topics: vec![],
+            forced_topics: vec![],
+            constants,
+            reputation: ReputationConfig::default(),
+        };
+        config.set_network_load(NetworkLoad::Normal);
+        config
+    }
+}
+
+impl Config {
+    /// All IP addresses that libp2p listens on, starting with `listen_address`.
+    pub fn libp2p_listen_addresses(&self) -> impl Iterator<Item = std::net::IpAddr> + '_ {
+        std::iter::once(self.listen_address).chain(self.additional_listen_addresses.iter().copied())
+    }
+
+    /// The IP address that discovery listens on.
+    pub fn discovery_listen_address(&self) -> std::net::IpAddr {
+        self.discovery_address.unwrap_or(self.listen_address)
+    }
+
+    /// Sets the networking constants, e.g., those read from the `ChainSpec` of a testnet.
+    pub fn set_constants(&mut self, constants: NetworkConstants) {
+        self.gs_config.max_transmit_size = constants.gossip_max_size;
+        self.constants = constants;
+    }
+
+    /// Applies a bandwidth profile, setting the target number of peers, the gossipsub mesh size,
+    /// the amount of lazy gossip (which leads to peers sending us messages we have already seen)
+    /// and the `BlocksByRange` quota.
+    pub fn set_network_load(&mut self, network_load: NetworkLoad) {
+        let (max_peers, mesh_n_low, mesh_n, mesh_n_high, gossip_lazy, history_gossip, quota) =
+            match network_load {
+                NetworkLoad::Low => (25, 3, 4, 6, 3, 2, 64),
+                NetworkLoad::Normal => (50, 4, 6, 12, 6, 3, MAX_REQUEST_BLOCKS as u64),
+                NetworkLoad::High => (100, 6, 8, 12, 8, 3, MAX_REQUEST_BLOCKS as u64),
+            };
+
+        self.network_load = network_load;
+        self.max_peers = max_peers;
+        self.gs_config.mesh_n_low = mesh_n_low;
+        self.gs_config.mesh_n = mesh_n;
+        self.gs_config.mesh_n_high = mesh_n_high;
+        self.gs_config.gossip_lazy = gossip_lazy;
+        self.gs_config.history_gossip = history_gossip;
+        self.blocks_by_range_quota = quota;
+    }
+
+    /// Configures a lightweight node which keeps a small set of peers to publish blocks and follow
+    /// the head. It does not subscribe to aggregate attestations or attestation subnets, so its
+    /// peers are chosen solely by their reputation rather than the subnets they serve.
+    pub fn set_proposer_only(&mut self) {
+        self.set_network_load(NetworkLoad::Low);
+        self.proposer_only = true;
+        self.max_peers = PROPOSER_ONLY_MAX_PEERS;
+        self.topics
+            .retain(|topic| *topic != GossipKind::BeaconAggregateAndProof);
+    }
+
+    /// Configures a node which cannot accept inbound connections. Its TCP port is not advertised
+    /// in the ENR and peers which dial it are sent a `Goodbye`, so the target number of peers is
+    /// raised for our own dials to make up for the peers which would otherwise connect to us.
+    pub fn set_disable_inbound(&mut self) {
+        self.disable_inbound = true;
+        self.max_peers = self.max_peers * OUTBOUND_ONLY_PEERS_PERCENT / 100;
+    }
+}
+
+/// Computes the id of a gossipsub message as per the Eth2 networking specification, so that
+/// duplicate messages are detected in the same way as other clients.
+///
+/// The id is the first `MESSAGE_ID_LEN` bytes of `SHA256(MESSAGE_DOMAIN_VALID_SNAPPY ++
+/// snappy_decompress(data))`, or `SHA256(MESSAGE_DOMAIN_INVALID_SNAPPY ++ data)` if the data
+/// cannot be decompressed. The id is base64 encoded since libp2p represents message ids as
+/// strings.
+pub fn gossip_message_id(message: &GossipsubMessage) -> MessageId {
+    compute_message_id(&message.data)
+}
+
+fn compute_message_id(data: &[u8]) -> MessageId {
+    let mut hasher = Sha256::new();
+
+    match decompress_gossip_data(data) {
+        Some(decompressed) => {
+            hasher.input(&MESSAGE_DOMAIN_VALID_SNAPPY);
+            hasher.input(&decompressed);
         }
+        None => {
+            hasher.input(&MESSAGE_DOMAIN_INVALID_SNAPPY);
+            hasher.input(data);
+        }
+    }
+
+    MessageId(base64::encode_config(
+        &hasher.result()[..MESSAGE_ID_LEN],
+        base64::URL_SAFE_NO_PAD,
+    ))
+}
+
+/// Decompresses snappy-encoded gossip data, returning `None` if the data is invalid or would
+/// decompress to more than `GOSSIP_MAX_SIZE` bytes.
+fn decompress_gossip_data(data: &[u8]) -> Option<Vec<u8>> {
+    match decompress_len(data) {
+        Ok(n) if n <= GOSSIP_MAX_SIZE => Decoder::new().decompress_vec(data).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snap::raw::Encoder;
+
+    fn expected_id(domain: [u8; 4], data: &[u8]) -> MessageId {
+        let mut preimage = domain.to_vec();
+        preimage.extend_from_slice(data);
+        MessageId(base64::encode_config(
+            &Sha256::digest(&preimage)[..MESSAGE_ID_LEN],
+            base64::URL_SAFE_NO_PAD,
+        ))
+    }
+
+    #[test]
+    fn network_load_profiles() {
+        let mut config = Config::default();
+        assert_eq!(config.network_load, NetworkLoad::Normal);
+
+        config.set_network_load("low".parse().unwrap());
+        assert_eq!(config.network_load, NetworkLoad::Low);
+        assert!(config.max_peers < Config::default().max_peers);
+        assert!(config.gs_config.mesh_n_low <= config.gs_config.mesh_n);
+        assert!(config.gs_config.mesh_n <= config.gs_config.mesh_n_high);
+        assert!(config.blocks_by_range_quota < MAX_REQUEST_BLOCKS as u64);
+
+        config.set_network_load(NetworkLoad::High);
+        assert!(config.max_peers > Config::default().max_peers);
+        assert!(config.gs_config.mesh_n_low <= config.gs_config.mesh_n);
+        assert!(config.gs_config.mesh_n <= config.gs_config.mesh_n_high);
+
+        assert!("extreme".parse::<NetworkLoad>().is_err());
+    }
+
+    #[test]
+    fn disable_inbound_raises_peer_target() {
+        let mut config = Config::default();
+        config.set_disable_inbound();
+        assert!(config.disable_inbound);
+        assert_eq!(
+            config.max_peers,
+            Config::default().max_peers * OUTBOUND_ONLY_PEERS_PERCENT / 100
+        );
+    }
+
+    #[test]
+    fn message_id_valid_snappy() {
+        let data = vec![42; 128];
+        let compressed = Encoder::new().compress_vec(&data).unwrap();
+
+        assert_eq!(
+            compute_message_id(&compressed),
+            expected_id(MESSAGE_DOMAIN_VALID_SNAPPY, &data)
+        );
+    }
+
+    #[test]
+    fn message_id_invalid_snappy() {
+        let data = vec![0xff; 128];
+
+        assert_eq!(
+            compute_message_id(&data),
+            expected_id(MESSAGE_DOMAIN_INVALID_SNAPPY, &data)
+        );
     }
 }
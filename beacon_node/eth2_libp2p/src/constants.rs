@@ -0,0 +1,42 @@
+//! The networking constants defined by the Eth2 specification.
+//!
+//! These are read from the `ChainSpec` rather than hard-coded, so that testnets which use
+//! different values may be joined without code changes.
+
+use std::time::Duration;
+use types::ChainSpec;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkConstants {
+    /// The maximum allowed size of uncompressed gossip messages.
+    pub gossip_max_size: usize,
+    /// The maximum allowed size of a single RPC response chunk.
+    pub max_chunk_size: usize,
+    /// The maximum time to wait for the first byte of a request or response.
+    pub ttfb_timeout: Duration,
+    /// The maximum time for a complete RPC request or response chunk to be transferred.
+    pub resp_timeout: Duration,
+    /// The maximum number of slots during which an attestation may be propagated.
+    pub attestation_propagation_slot_range: u64,
+    /// The maximum tolerated clock disparity of a gossip message.
+    pub maximum_gossip_clock_disparity: Duration,
+}
+
+impl NetworkConstants {
+    pub fn from_spec(spec: &ChainSpec) -> Self {
+        Self {
+            gossip_max_size: spec.gossip_max_size as usize,
+            max_chunk_size: spec.max_chunk_size as usize,
+            ttfb_timeout: Duration::from_secs(spec.ttfb_timeout),
+            resp_timeout: Duration::from_secs(spec.resp_timeout),
+            attestation_propagation_slot_range: spec.attestation_propagation_slot_range,
+            maximum_gossip_clock_disparity: spec.maximum_gossip_clock_disparity(),
+        }
+    }
+}
+
+impl Default for NetworkConstants {
+    fn default() -> Self {
+        Self::from_spec(&ChainSpec::mainnet())
+    }
+}
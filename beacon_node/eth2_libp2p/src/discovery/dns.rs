@@ -0,0 +1,352 @@
+//! Resolves node lists which are published over DNS as ENR trees, as described in EIP-1459.
+//!
+//! A tree is identified by a URL of the form `enrtree://<public key>@<domain>`. The TXT record at
+//! `<domain>` is the root of the tree, which is signed by the public key. The root commits to the
+//! hash of the first entry of the tree and each branch commits to the hashes of its children, so
+//! every entry is verified before it is used.
+
+use crate::Enr;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::str::FromStr;
+use tiny_keccak::{Hasher, Keccak};
+use trust_dns_resolver::TokioAsyncResolver;
+
+const URL_PREFIX: &str = "enrtree://";
+const ROOT_PREFIX: &str = "enrtree-root:v1";
+const BRANCH_PREFIX: &str = "enrtree-branch:";
+const LINK_PREFIX: &str = "enrtree://";
+const ENR_PREFIX: &str = "enr:";
+
+/// The maximum number of entries which are looked up in a single tree, so that a misbehaving tree
+/// cannot keep us resolving indefinitely.
+const MAX_TREE_ENTRIES: usize = 2_000;
+
+/// The location of an ENR tree and the key which signs its root.
+#[derive(Debug, Clone)]
+pub struct EnrTreeUrl {
+    pub public_key: secp256k1::PublicKey,
+    pub domain: String,
+}
+
+impl FromStr for EnrTreeUrl {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        if !url.starts_with(URL_PREFIX) {
+            return Err(format!("ENR tree URL must start with {}", URL_PREFIX));
+        }
+
+        let mut parts = url[URL_PREFIX.len()..].splitn(2, '@');
+        let key = parts.next().unwrap_or("");
+        let domain = parts
+            .next()
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| "ENR tree URL has no domain".to_string())?;
+
+        let key_bytes = data_encoding::BASE32_NOPAD
+            .decode(key.to_uppercase().as_bytes())
+            .map_err(|e| format!("Invalid ENR tree public key encoding: {:?}", e))?;
+        if key_bytes.len() != 33 {
+            return Err(format!(
+                "ENR tree public key must be 33 bytes, not {}",
+                key_bytes.len()
+            ));
+        }
+        let mut compressed = [0; 33];
+        compressed.copy_from_slice(&key_bytes);
+        let public_key = secp256k1::PublicKey::parse_compressed(&compressed)
+            .map_err(|e| format!("Invalid ENR tree public key: {:?}", e))?;
+
+        Ok(Self {
+            public_key,
+            domain: domain.to_string(),
+        })
+    }
+}
+
+/// The root of an ENR tree. The subtree of links to other trees is not followed.
+struct Root {
+    /// The hash of the first entry of the subtree of ENRs.
+    enr_root: String,
+}
+
+impl Root {
+    /// Parses the root record and verifies its signature.
+    fn parse_and_verify(record: &str, public_key: &secp256k1::PublicKey) -> Result<Self, String> {
+        if !record.starts_with(ROOT_PREFIX) {
+            return Err(format!("Root record does not start with {}", ROOT_PREFIX));
+        }
+
+        let sig_start = record
+            .find(" sig=")
+            .ok_or_else(|| "Root record has no signature".to_string())?;
+        let (signed, sig) = record.split_at(sig_start);
+
+        let sig_bytes = base64::decode_config(&sig[" sig=".len()..], base64::URL_SAFE_NO_PAD)
+            .map_err(|e| format!("Invalid root signature encoding: {:?}", e))?;
+        // The signature is `r || s || v`, the recovery id `v` is not needed.
+        if sig_bytes.len() != 65 {
+            return Err(format!(
+                "Root signature must be 65 bytes, not {}",
+                sig_bytes.len()
+            ));
+        }
+        let mut sig_array = [0; 64];
+        sig_array.copy_from_slice(&sig_bytes[..64]);
+        let signature = secp256k1::Signature::parse(&sig_array);
+        let message = secp256k1::Message::parse(&keccak256(signed.as_bytes()));
+        if !secp256k1::verify(&message, &signature, public_key) {
+            return Err("Invalid root signature".to_string());
+        }
+
+        let enr_root = signed[ROOT_PREFIX.len()..]
+            .split_whitespace()
+            .find(|field| field.starts_with("e="))
+            .map(|field| field[2..].to_string())
+            .ok_or_else(|| "Root record has no ENR root".to_string())?;
+
+        Ok(Self { enr_root })
+    }
+}
+
+/// An entry of an ENR tree, other than the root.
+#[derive(Debug)]
+enum Entry {
+    Branch(Vec<String>),
+    Enr(Box<Enr>),
+    /// A link to another tree. Links are not followed.
+    Link,
+}
+
+impl FromStr for Entry {
+    type Err = String;
+
+    fn from_str(record: &str) -> Result<Self, Self::Err> {
+        if record.starts_with(BRANCH_PREFIX) {
+            Ok(Entry::Branch(
+                record[BRANCH_PREFIX.len()..]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|hash| !hash.is_empty())
+                    .map(String::from)
+                    .collect(),
+            ))
+        } else if record.starts_with(ENR_PREFIX) {
+            record
+                .parse()
+                .map(|enr| Entry::Enr(Box::new(enr)))
+                .map_err(|e| format!("Invalid ENR entry: {}", e))
+        } else if record.starts_with(LINK_PREFIX) {
+            Ok(Entry::Link)
+        } else {
+            Err(format!("Unknown ENR tree entry: {}", record))
+        }
+    }
+}
+
+/// Returns the subdomain of an entry, which is the base32 encoding of the first 16 bytes of the
+/// keccak256 hash of its record.
+fn entry_hash(record: &str) -> String {
+    data_encoding::BASE32_NOPAD.encode(&keccak256(record.as_bytes())[..16])
+}
+
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(bytes);
+    let mut output = [0; 32];
+    keccak.finalize(&mut output);
+    output
+}
+
+/// Resolves every ENR in the tree at `url`, using `lookup_txt` to read the TXT record of a domain.
+///
+/// Entries which fail verification are an error, since the tree cannot be trusted.
+pub async fn resolve_enr_tree<F, R>(url: &EnrTreeUrl, mut lookup_txt: F) -> Result<Vec<Enr>, String>
+where
+    F: FnMut(String) -> R,
+    R: Future<Output = Result<String, String>>,
+{
+    let root_record = lookup_txt(url.domain.clone()).await?;
+    let root = Root::parse_and_verify(&root_record, &url.public_key)?;
+
+    let mut enrs = vec![];
+    let mut lookups = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(root.enr_root);
+
+    while let Some(hash) = queue.pop_front() {
+        lookups += 1;
+        if lookups > MAX_TREE_ENTRIES {
+            return Err(format!(
+                "ENR tree has more than {} entries",
+                MAX_TREE_ENTRIES
+            ));
+        }
+
+        let record = lookup_txt(format!("{}.{}", hash, url.domain)).await?;
+        if !entry_hash(&record).eq_ignore_ascii_case(&hash) {
+            return Err(format!("ENR tree entry {} does not match its hash", hash));
+        }
+
+        match record.parse()? {
+            Entry::Branch(children) => queue.extend(children),
+            Entry::Enr(enr) => enrs.push(*enr),
+            Entry::Link => {}
+        }
+    }
+
+    Ok(enrs)
+}
+
+/// Resolves every ENR in the tree at `url` using the system's DNS configuration.
+pub async fn resolve_enr_tree_over_dns(url: EnrTreeUrl) -> Result<Vec<Enr>, String> {
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()
+        .await
+        .map_err(|e| format!("Unable to create DNS resolver: {}", e))?;
+
+    resolve_enr_tree(&url, |domain| lookup_txt(resolver.clone(), domain)).await
+}
+
+/// Reads the TXT record of `domain`. The strings of the record are concatenated.
+async fn lookup_txt(resolver: TokioAsyncResolver, domain: String) -> Result<String, String> {
+    let lookup = resolver
+        .txt_lookup(domain.as_str())
+        .await
+        .map_err(|e| format!("DNS lookup of {} failed: {}", domain, e))?;
+
+    let txt = lookup
+        .iter()
+        .next()
+        .ok_or_else(|| format!("No TXT record for {}", domain))?;
+    let bytes = txt
+        .txt_data()
+        .iter()
+        .flat_map(|string| string.iter().copied())
+        .collect::<Vec<u8>>();
+
+    String::from_utf8(bytes).map_err(|e| format!("TXT record of {} is not UTF-8: {}", domain, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{CombinedKey, CombinedKeyExt, Keypair};
+    use discv5::enr::{EnrBuilder, NodeId};
+    use futures::executor::block_on;
+    use std::collections::HashMap;
+
+    const DOMAIN: &str = "nodes.example.org";
+
+    fn secret_key() -> secp256k1::SecretKey {
+        secp256k1::SecretKey::parse(&[7; 32]).unwrap()
+    }
+
+    fn tree_url() -> EnrTreeUrl {
+        let public_key = secp256k1::PublicKey::from_secret_key(&secret_key());
+        let key = data_encoding::BASE32_NOPAD.encode(&public_key.serialize_compressed());
+        format!("{}{}@{}", URL_PREFIX, key, DOMAIN).parse().unwrap()
+    }
+
+    fn root_record(enr_root: &str, secret_key: &secp256k1::SecretKey) -> String {
+        let signed = format!(
+            "{} e={} l=FDXN3SN67NA5DKA4J2GOK7BVQI seq=1",
+            ROOT_PREFIX, enr_root
+        );
+        let message = secp256k1::Message::parse(&keccak256(signed.as_bytes()));
+        let (signature, recovery_id) = secp256k1::sign(&message, secret_key);
+        let mut sig_bytes = signature.serialize().to_vec();
+        sig_bytes.push(recovery_id.serialize());
+        format!(
+            "{} sig={}",
+            signed,
+            base64::encode_config(&sig_bytes, base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    fn enr_record() -> (Enr, String) {
+        let key = CombinedKey::from_libp2p(&Keypair::generate_secp256k1()).unwrap();
+        let enr = EnrBuilder::new("v4").build(&key).unwrap();
+        let record = enr.to_base64();
+        (enr, record)
+    }
+
+    /// Builds a tree with a branch of two ENRs and a link, returning the records by domain.
+    fn build_tree(root_key: &secp256k1::SecretKey) -> (Vec<NodeId>, HashMap<String, String>) {
+        let (enr_a, record_a) = enr_record();
+        let (enr_b, record_b) = enr_record();
+        let link = format!("{}AAAA@other.example.org", LINK_PREFIX);
+        let branch = format!(
+            "{}{},{},{}",
+            BRANCH_PREFIX,
+            entry_hash(&record_a),
+            entry_hash(&record_b),
+            entry_hash(&link)
+        );
+
+        let mut records = HashMap::new();
+        records.insert(
+            DOMAIN.to_string(),
+            root_record(&entry_hash(&branch), root_key),
+        );
+        for record in vec![branch, record_a, record_b, link] {
+            records.insert(format!("{}.{}", entry_hash(&record), DOMAIN), record);
+        }
+
+        (vec![enr_a.node_id(), enr_b.node_id()], records)
+    }
+
+    fn resolve(records: &HashMap<String, String>) -> Result<Vec<NodeId>, String> {
+        block_on(resolve_enr_tree(&tree_url(), |domain| {
+            let result = records
+                .get(&domain)
+                .cloned()
+                .ok_or_else(|| format!("No record for {}", domain));
+            async move { result }
+        }))
+        .map(|enrs| enrs.iter().map(|enr| enr.node_id()).collect())
+    }
+
+    #[test]
+    fn parse_url() {
+        let url = tree_url();
+        assert_eq!(url.domain, DOMAIN);
+
+        assert!("enrtree://@nodes.example.org"
+            .parse::<EnrTreeUrl>()
+            .is_err());
+        assert!(format!("{}{}", URL_PREFIX, DOMAIN)
+            .parse::<EnrTreeUrl>()
+            .is_err());
+        assert!("https://nodes.example.org".parse::<EnrTreeUrl>().is_err());
+    }
+
+    #[test]
+    fn resolves_valid_tree() {
+        let (enrs, records) = build_tree(&secret_key());
+        assert_eq!(resolve(&records), Ok(enrs));
+    }
+
+    #[test]
+    fn rejects_wrong_root_signer() {
+        let other_key = secp256k1::SecretKey::parse(&[8; 32]).unwrap();
+        let (_, records) = build_tree(&other_key);
+        assert_eq!(resolve(&records), Err("Invalid root signature".to_string()));
+    }
+
+    #[test]
+    fn rejects_modified_entry() {
+        let (_, mut records) = build_tree(&secret_key());
+        let (_, replacement) = enr_record();
+        for (domain, record) in records.iter_mut() {
+            if record.starts_with(ENR_PREFIX) && domain != DOMAIN {
+                *record = replacement.clone();
+                break;
+            }
+        }
+
+        assert!(resolve(&records)
+            .unwrap_err()
+            .contains("does not match its hash"));
+    }
+}
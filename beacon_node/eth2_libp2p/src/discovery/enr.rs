@@ -4,6 +4,7 @@ pub use discv5::enr::{self, CombinedKey, EnrBuilder};
 
 use super::enr_ext::CombinedKeyExt;
 use super::ENR_FILENAME;
+use crate::peer_manager::{secret_proof, PRIVATE_NETWORK_ENR_KEY};
 use crate::types::{Enr, EnrBitfield};
 use crate::NetworkConfig;
 use libp2p::core::identity::Keypair;
@@ -20,6 +21,25 @@ use types::{EnrForkId, EthSpec};
 pub const ETH2_ENR_KEY: &'static str = "eth2";
 /// The ENR field specifying the subnet bitfield.
 pub const BITFIELD_ENR_KEY: &'static str = "attnets";
+/// The maximum length of the key of a custom ENR field.
+pub const MAX_CUSTOM_ENR_KEY_LEN: usize = 16;
+/// The maximum length of the value of a custom ENR field, in bytes. The ENR as a whole may not
+/// exceed 300 bytes, which is checked when it is built.
+pub const MAX_CUSTOM_ENR_VALUE_LEN: usize = 64;
+/// The ENR fields set by the ENR scheme or by lighthouse, which may not be set as custom fields.
+const RESERVED_ENR_KEYS: &[&str] = &[
+    "id",
+    "secp256k1",
+    "ip",
+    "ip6",
+    "tcp",
+    "tcp6",
+    "udp",
+    "udp6",
+    ETH2_ENR_KEY,
+    BITFIELD_ENR_KEY,
+    PRIVATE_NETWORK_ENR_KEY,
+];
 
 /// Extension trait for ENR's within Eth2.
 pub trait Eth2Enr {
@@ -112,13 +132,41 @@ pub fn build_enr<T: EthSpec>(
     if let Some(enr_address) = config.enr_address {
         builder.ip(enr_address);
     }
-    if let Some(udp_port) = config.enr_udp_port {
-        builder.udp(udp_port);
+    // discovery binds a single address, which need not be an IPv4 one
+    let discovery_address = config.discovery_listen_address();
+    if discovery_address.is_ipv4() || discovery_address.is_unspecified() {
+        if let Some(udp_port) = config.enr_udp_port {
+            builder.udp(udp_port);
+        }
     }
-    // we always give it our listening tcp port
+    // we give it our listening tcp port, unless peers cannot connect to us
     // TODO: Add uPnP support to map udp and tcp ports
     let tcp_port = config.enr_tcp_port.unwrap_or_else(|| config.libp2p_port);
-    builder.tcp(tcp_port);
+    if !config.disable_inbound {
+        builder.tcp(tcp_port);
+    }
+
+    // if we also listen on an address of the other IP version, advertise it too so that peers of
+    // either version can reach us. Libp2p may listen on several addresses, but discovery binds a
+    // single one, so the other version's ports are only advertised for the protocols listening on it
+    if let Some(enr_address) = config.enr_address {
+        let is_other_version = |address: &std::net::IpAddr| {
+            address.is_ipv4() != enr_address.is_ipv4() && !address.is_unspecified()
+        };
+        let libp2p_address = config.libp2p_listen_addresses().find(is_other_version);
+        let discovery_address = Some(config.discovery_listen_address()).filter(is_other_version);
+        if let Some(other_address) = libp2p_address.or(discovery_address) {
+            builder.ip(other_address);
+        }
+        if (enr_address.is_ipv6() || libp2p_address.is_some()) && !config.disable_inbound {
+            builder.tcp6(tcp_port);
+        }
+        if enr_address.is_ipv6() || discovery_address.is_some() {
+            if let Some(udp_port) = config.enr_udp_port {
+                builder.udp6(udp_port);
+            }
+        }
+    }
 
     // set the `eth2` field on our ENR
     builder.add_value(ETH2_ENR_KEY.into(), enr_fork_id.as_ssz_bytes());
@@ -128,8 +176,28 @@ pub fn build_enr<T: EthSpec>(
 
     builder.add_value(BITFIELD_ENR_KEY.into(), bitfield.as_ssz_bytes());
 
+    // set the custom fields of the operator, which are kept as discovery updates the ENR
+    for (key, value) in &config.enr_custom_fields {
+        builder.add_value(key.clone(), value.clone());
+    }
+
+    if !config.disable_inbound {
+        builder.tcp(config.libp2p_port);
+    }
+
+    // prove that we know the secret of our private network, if any
+    if let Some(secret) = &config.private_network_secret {
+        let node_id = builder
+            .build(enr_key)
+            .map_err(|e| format!("Could not build Local ENR: {:?}", e))?
+            .node_id();
+        builder.add_value(
+            PRIVATE_NETWORK_ENR_KEY.into(),
+            secret_proof(secret.as_bytes(), &node_id).to_vec(),
+        );
+    }
+
     builder
-        .tcp(config.libp2p_port)
         .build(enr_key)
         .map_err(|e| format!("Could not build Local ENR: {:?}", e))
 }
@@ -139,6 +207,7 @@ pub fn build_enr<T: EthSpec>(
 fn compare_enr(local_enr: &Enr, disk_enr: &Enr) -> bool {
     // take preference over disk_enr address if one is not specified
     (local_enr.ip().is_none() || local_enr.ip() == disk_enr.ip())
+        && (local_enr.ip6().is_none() || local_enr.ip6() == disk_enr.ip6())
         // tcp ports must match
         && local_enr.tcp() == disk_enr.tcp()
         // must match on the same fork
@@ -148,6 +217,66 @@ fn compare_enr(local_enr: &Enr, disk_enr: &Enr) -> bool {
         // we need the BITFIELD_ENR_KEY key to match, otherwise we use a new ENR. This will likely only
         // be true for non-validating nodes
         && local_enr.get(BITFIELD_ENR_KEY) == disk_enr.get(BITFIELD_ENR_KEY)
+        // the private network proof must match, in case the secret has changed
+        && local_enr.get(PRIVATE_NETWORK_ENR_KEY) == disk_enr.get(PRIVATE_NETWORK_ENR_KEY)
+        // the custom fields must match, in case they have been changed, added or removed
+        && custom_fields(local_enr) == custom_fields(disk_enr)
+}
+
+/// Returns the fields of `enr` which are not set by the ENR scheme or by lighthouse, in order of
+/// their keys.
+fn custom_fields(enr: &Enr) -> Vec<(&[u8], &[u8])> {
+    enr.iter()
+        .map(|(key, value)| (key.as_slice(), value))
+        .filter(|(key, _)| {
+            !RESERVED_ENR_KEYS
+                .iter()
+                .any(|reserved| reserved.as_bytes() == *key)
+        })
+        .collect()
+}
+
+/// Parses a custom ENR field of the form `key=hexvalue`, e.g. `operator=0x6c68`.
+///
+/// The key must be printable ASCII and may not be one of the fields set by the ENR scheme or by
+/// lighthouse. Both the key and the value are limited in length.
+pub fn parse_custom_enr_field(field: &str) -> Result<(String, Vec<u8>), String> {
+    let mut parts = field.splitn(2, '=');
+    let (key, value) = match (parts.next(), parts.next()) {
+        (Some(key), Some(value)) => (key.trim(), value.trim()),
+        _ => {
+            return Err(format!(
+                "Invalid ENR field {}, expected KEY=HEXVALUE",
+                field
+            ))
+        }
+    };
+
+    if key.is_empty() || key.len() > MAX_CUSTOM_ENR_KEY_LEN {
+        return Err(format!(
+            "ENR field key {} must be between 1 and {} characters",
+            key, MAX_CUSTOM_ENR_KEY_LEN
+        ));
+    }
+    if !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(format!("ENR field key {} must be printable ASCII", key));
+    }
+    if RESERVED_ENR_KEYS.contains(&key) {
+        return Err(format!("ENR field {} is reserved", key));
+    }
+
+    let value = hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid hex value of ENR field {}: {:?}", key, e))?;
+    if value.len() > MAX_CUSTOM_ENR_VALUE_LEN {
+        return Err(format!(
+            "ENR field {} is {} bytes, the maximum is {}",
+            key,
+            value.len(),
+            MAX_CUSTOM_ENR_VALUE_LEN
+        ));
+    }
+
+    Ok((key.to_string(), value))
 }
 
 /// Saves an ENR to disk
@@ -167,3 +296,88 @@ pub fn save_enr_to_disk(dir: &Path, enr: &Enr, log: &slog::Logger) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::MinimalEthSpec;
+
+    #[test]
+    fn custom_enr_fields() {
+        assert_eq!(
+            parse_custom_enr_field("operator=0x6c68"),
+            Ok(("operator".to_string(), vec![0x6c, 0x68]))
+        );
+        assert_eq!(
+            parse_custom_enr_field(" tag = 01 "),
+            Ok(("tag".to_string(), vec![0x01]))
+        );
+
+        assert!(parse_custom_enr_field("operator").is_err());
+        assert!(parse_custom_enr_field("=0x01").is_err());
+        assert!(parse_custom_enr_field("operator=0xzz").is_err());
+        assert!(parse_custom_enr_field("eth2=0x01").is_err());
+        assert!(parse_custom_enr_field(&format!("{}=0x01", "k".repeat(17))).is_err());
+        assert!(parse_custom_enr_field(&format!("tag=0x{}", "ff".repeat(65))).is_err());
+
+        let mut config = NetworkConfig::default();
+        config.enr_custom_fields = vec![("operator".to_string(), vec![0x6c, 0x68])];
+        let enr_key = CombinedKey::generate_secp256k1();
+        let enr = build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert_eq!(enr.get("operator"), Some(&[0x6c, 0x68][..]));
+    }
+
+    #[test]
+    fn compare_custom_enr_fields() {
+        let enr_key = CombinedKey::generate_secp256k1();
+        let mut config = NetworkConfig::default();
+        config.enr_custom_fields = vec![("operator".to_string(), vec![0x6c, 0x68])];
+        let disk_enr =
+            build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert!(compare_enr(&disk_enr, &disk_enr));
+
+        // a removed field is not kept from the disk ENR
+        config.enr_custom_fields = vec![];
+        let local_enr =
+            build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert!(!compare_enr(&local_enr, &disk_enr));
+
+        // nor is a changed one
+        config.enr_custom_fields = vec![("operator".to_string(), vec![0x00])];
+        let local_enr =
+            build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert!(!compare_enr(&local_enr, &disk_enr));
+    }
+
+    #[test]
+    fn other_ip_version_ports() {
+        let enr_key = CombinedKey::generate_secp256k1();
+        let mut config = NetworkConfig::default();
+        config.enr_address = Some("1.2.3.4".parse().unwrap());
+        config.enr_udp_port = Some(9000);
+        config.listen_address = "0.0.0.0".parse().unwrap();
+
+        // libp2p alone listens on IPv6, so discovery's port is not advertised for it
+        config.additional_listen_addresses = vec!["2001:db8::1".parse().unwrap()];
+        let enr = build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert!(enr.get("ip6").is_some());
+        assert!(enr.get("tcp6").is_some());
+        assert!(enr.get("udp6").is_none());
+
+        assert!(enr.get("udp").is_some());
+
+        // discovery binds the IPv6 address instead of the IPv4 one
+        config.discovery_address = Some("2001:db8::1".parse().unwrap());
+        let enr = build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert!(enr.get("tcp6").is_some());
+        assert!(enr.get("udp6").is_some());
+        assert!(enr.get("udp").is_none());
+
+        // discovery alone listens on IPv6
+        config.additional_listen_addresses = vec![];
+        let enr = build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        assert!(enr.get("ip6").is_some());
+        assert!(enr.get("tcp6").is_none());
+        assert!(enr.get("udp6").is_some());
+    }
+}
@@ -1,18 +1,21 @@
 ///! This manages the discovery and management of peers.
+pub mod dns;
 pub(crate) mod enr;
 pub mod enr_ext;
 
 // Allow external use of the lighthouse ENR builder
-pub use enr::{build_enr, CombinedKey, Eth2Enr};
+pub use enr::{build_enr, parse_custom_enr_field, CombinedKey, Eth2Enr};
 pub use enr_ext::{CombinedKeyExt, EnrExt};
 pub use libp2p::core::identity::Keypair;
 
 use crate::metrics;
 use crate::{error, Enr, NetworkConfig, NetworkGlobals};
 use discv5::{enr::NodeId, Discv5, Discv5Event};
+use dns::EnrTreeUrl;
 use enr::{BITFIELD_ENR_KEY, ETH2_ENR_KEY};
 use futures::prelude::*;
 use futures::stream::FuturesUnordered;
+use libp2p::core::multiaddr::{Multiaddr, Protocol};
 use libp2p::core::PeerId;
 use lru::LruCache;
 use slog::{crit, debug, info, trace, warn};
@@ -25,7 +28,7 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::Instant,
+    time::{Duration, Instant},
 };
 use tokio::sync::mpsc;
 use types::{EnrForkId, EthSpec, SubnetId};
@@ -46,6 +49,8 @@ const MAX_CONCURRENT_QUERIES: usize = 1;
 /// We could reduce this constant to speed up queries however at the cost of security. It will
 /// make it easier to peers to eclipse this node. Kademlia suggests a value of 16.
 const FIND_NODE_QUERY_CLOSEST_PEERS: usize = 16;
+/// The time between resolutions of each ENR tree published over DNS.
+const DNS_TREE_REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
 
 /// The events emitted by polling discovery.
 pub enum DiscoveryEvent {
@@ -54,6 +59,8 @@ pub enum DiscoveryEvent {
     QueryResult(Option<Instant>, Box<Vec<Enr>>),
     /// This indicates that our local UDP socketaddr has been updated and we should inform libp2p.
     SocketUpdated(SocketAddr),
+    /// The ENR of a peer has been received in response to `Discovery::request_enr`, and cached.
+    EnrReceived(PeerId),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -66,13 +73,34 @@ enum QueryType {
     },
     /// We are searching for more peers without ENR or time constraints.
     FindPeers,
+    /// We are searching for peers whose ENR satisfies a custom predicate.
+    Predicate(PredicateQuery),
+}
+
+/// A query for peers whose ENR satisfies a predicate supplied by a higher layer.
+#[derive(Clone)]
+struct PredicateQuery {
+    predicate: Arc<dyn Fn(&Enr) -> bool + Send + Sync>,
+}
+
+/// Queries are equal only if they share the same predicate.
+impl PartialEq for PredicateQuery {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.predicate, &other.predicate)
+    }
+}
+
+impl std::fmt::Debug for PredicateQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("PredicateQuery")
+    }
 }
 
 impl QueryType {
     /// Returns true if this query has expired.
     pub fn expired(&self) -> bool {
         match self {
-            Self::FindPeers => false,
+            Self::FindPeers | Self::Predicate(_) => false,
             Self::Subnet { min_ttl, .. } => {
                 if let Some(ttl) = min_ttl {
                     ttl > &Instant::now()
@@ -89,15 +117,46 @@ impl QueryType {
     /// connected peers with this `min_ttl`
     pub fn min_ttl(&self) -> Option<Instant> {
         match self {
-            Self::FindPeers => None,
+            Self::FindPeers | Self::Predicate(_) => None,
             Self::Subnet { min_ttl, .. } => min_ttl.clone(),
         }
     }
+
+    /// Returns the predicate the ENRs found by this query must satisfy. Regardless of the query,
+    /// this only accepts ENRs with the given `enr_fork_id`.
+    fn predicate<TSpec: EthSpec>(
+        &self,
+        enr_fork_id: EnrForkId,
+        log: &slog::Logger,
+    ) -> Box<dyn Fn(&Enr) -> bool + Send> {
+        // predicate for finding nodes with a matching fork
+        let eth2_fork_predicate = move |enr: &Enr| enr.eth2() == Ok(enr_fork_id.clone());
+
+        match self {
+            Self::FindPeers => Box::new(eth2_fork_predicate),
+            Self::Subnet { subnet_id, .. } => {
+                // build the subnet predicate as a combination of the eth2_fork_predicate and the
+                // subnet predicate
+                let subnet_predicate = subnet_predicate::<TSpec>(subnet_id.clone(), log);
+                Box::new(move |enr: &Enr| eth2_fork_predicate(enr) && subnet_predicate(enr))
+            }
+            Self::Predicate(PredicateQuery { predicate }) => {
+                let predicate = predicate.clone();
+                Box::new(move |enr: &Enr| eth2_fork_predicate(enr) && predicate(enr))
+            }
+        }
+    }
 }
 
 /// The result of a query.
 struct QueryResult(QueryType, Result<Vec<Enr>, discv5::QueryError>);
 
+/// The result of resolving an ENR tree published over DNS.
+struct DnsTreeResult(EnrTreeUrl, Result<Vec<Enr>, String>);
+
+/// The result of requesting the ENR of a peer.
+struct EnrRequestResult(PeerId, Result<Option<Enr>, String>);
+
 // Awaiting the event stream future
 enum EventStream {
     /// Awaiting an event stream to be generated. This is required due to the poll nature of
@@ -136,12 +195,23 @@ pub struct Discovery<TSpec: EthSpec> {
     /// a time, regardless of the query concurrency.
     find_peer_active: bool,
 
+    /// Indicates if searches for new peers are paused, e.g. whilst time-critical duties are
+    /// performed. Searches for subnet peers are not paused.
+    paused: bool,
+
     /// A queue of discovery queries to be processed.
     queued_queries: VecDeque<QueryType>,
 
     /// Active discovery queries.
     active_queries: FuturesUnordered<std::pin::Pin<Box<dyn Future<Output = QueryResult> + Send>>>,
 
+    /// Resolutions of the ENR trees published over DNS, each of which is repeated periodically.
+    dns_trees: FuturesUnordered<std::pin::Pin<Box<dyn Future<Output = DnsTreeResult> + Send>>>,
+
+    /// Requests for the ENRs of connected peers.
+    enr_requests:
+        FuturesUnordered<std::pin::Pin<Box<dyn Future<Output = EnrRequestResult> + Send>>>,
+
     /// The discv5 event stream.
     event_stream: EventStream,
 
@@ -168,7 +238,8 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
 
         info!(log, "ENR Initialised"; "enr" => local_enr.to_base64(), "seq" => local_enr.seq(), "id"=> format!("{}",local_enr.node_id()), "ip" => format!("{:?}", local_enr.ip()), "udp"=> format!("{:?}", local_enr.udp()), "tcp" => format!("{:?}", local_enr.tcp()));
 
-        let listen_socket = SocketAddr::new(config.listen_address, config.discovery_port);
+        let listen_socket =
+            SocketAddr::new(config.discovery_listen_address(), config.discovery_port);
 
         // convert the keypair into an ENR key
         let enr_key: CombinedKey = CombinedKey::from_libp2p(&local_key)?;
@@ -203,12 +274,29 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         // Obtain the event stream
         let event_stream = EventStream::Awaiting(Box::pin(discv5.event_stream()));
 
+        // Start resolving the ENR trees published over DNS.
+        let dns_trees = FuturesUnordered::new();
+        for url in &config.dns_discovery {
+            match url.parse::<EnrTreeUrl>() {
+                Ok(url) => dns_trees.push(resolve_dns_tree(url, None)),
+                Err(e) => warn!(
+                    log,
+                    "Invalid ENR tree URL";
+                    "url" => url,
+                    "error" => e
+                ),
+            }
+        }
+
         Ok(Self {
             cached_enrs: LruCache::new(50),
             network_globals,
             find_peer_active: false,
+            paused: false,
             queued_queries: VecDeque::with_capacity(10),
             active_queries: FuturesUnordered::new(),
+            dns_trees,
+            enr_requests: FuturesUnordered::new(),
             discv5,
             event_stream,
             log,
@@ -237,11 +325,39 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         }
     }
 
+    /// Pauses or resumes searches for new peers. Queries queued whilst paused are started once
+    /// resumed.
+    ///
+    /// Searches for peers on a subnet are never paused, as they are needed for upcoming duties.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused != paused {
+            trace!(self.log, "Peer discovery paused"; "paused" => paused);
+            self.paused = paused;
+        }
+    }
+
     /// Processes a request to search for more peers on a subnet.
     pub fn discover_subnet_peers(&mut self, subnet_id: SubnetId, min_ttl: Option<Instant>) {
         self.add_subnet_query(subnet_id, min_ttl, 0);
     }
 
+    /// Queues a search for peers whose ENR satisfies `predicate`, for example peers advertising a
+    /// custom ENR field.
+    ///
+    /// Only peers on our fork which satisfy the predicate are returned by the query and therefore
+    /// dialed by the peer manager.
+    pub fn discover_peers_with_predicate<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Enr) -> bool + Send + Sync + 'static,
+    {
+        trace!(self.log, "Queuing a predicate discovery request");
+        self.queued_queries
+            .push_back(QueryType::Predicate(PredicateQuery {
+                predicate: Arc::new(predicate),
+            }));
+        metrics::set_gauge(&metrics::DISCOVERY_QUEUE, self.queued_queries.len() as i64);
+    }
+
     /// Adds a subnet query if one doesn't exist. If a subnet query already exists, this
     /// updates the min_ttl field.
     fn add_subnet_query(&mut self, subnet_id: SubnetId, min_ttl: Option<Instant>, retries: usize) {
@@ -322,6 +438,32 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         }
     }
 
+    /// Requests the ENR of a peer from its discovery service, which is assumed to listen on the
+    /// UDP port of the same number as the TCP port of `address`. If it is received, the ENR is
+    /// cached and its peer is returned by `Self::poll`.
+    pub fn request_enr(&mut self, peer_id: &PeerId, address: &Multiaddr) {
+        let mut multiaddr = Multiaddr::empty();
+        for protocol in address.iter() {
+            match protocol {
+                Protocol::Ip4(_) | Protocol::Ip6(_) => multiaddr.push(protocol),
+                Protocol::Tcp(port) => multiaddr.push(Protocol::Udp(port)),
+                _ => {}
+            }
+        }
+        if multiaddr.iter().count() != 2 {
+            trace!(self.log, "Cannot request the ENR of a peer without an IP and TCP address"; "peer_id" => peer_id.to_string(), "address" => address.to_string());
+            return;
+        }
+        multiaddr.push(Protocol::P2p(peer_id.clone().into()));
+
+        debug!(self.log, "Requesting the ENR of a peer"; "peer_id" => peer_id.to_string(), "address" => multiaddr.to_string());
+        let peer_id = peer_id.clone();
+        let request = self.discv5.request_enr(multiaddr.to_string());
+        self.enr_requests.push(Box::pin(async move {
+            EnrRequestResult(peer_id, request.await.map_err(|e| format!("{:?}", e)))
+        }));
+    }
+
     /// Adds/Removes a subnet from the ENR Bitfield
     pub fn update_enr_bitfield(&mut self, subnet_id: SubnetId, value: bool) -> Result<(), String> {
         let id = *subnet_id as usize;
@@ -354,12 +496,7 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
             .map_err(|_| String::from("Subnet ID out of bounds, could not set subnet ID"))?;
 
         // insert the bitfield into the ENR record
-        let _ = self
-            .discv5
-            .enr_insert(BITFIELD_ENR_KEY, current_bitfield.as_ssz_bytes());
-
-        // replace the global version
-        *self.network_globals.local_enr.write() = self.discv5.local_enr().clone();
+        self.update_enr_field(BITFIELD_ENR_KEY, current_bitfield.as_ssz_bytes())?;
         Ok(())
     }
 
@@ -379,19 +516,43 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
             "next_fork_epoch" => next_fork_epoch_log,
         );
 
-        let _ = self
-            .discv5
-            .enr_insert(ETH2_ENR_KEY.into(), enr_fork_id.as_ssz_bytes())
-            .map_err(|e| {
-                warn!(
-                    self.log,
-                    "Could not update eth2 ENR field";
-                    "error" => format!("{:?}", e)
-                )
-            });
+        if let Err(e) = self.update_enr_field(ETH2_ENR_KEY, enr_fork_id.as_ssz_bytes()) {
+            warn!(self.log, "Could not update eth2 ENR field"; "error" => e);
+        }
+    }
 
+    /// Sets the `key` field of our local ENR to `value`.
+    ///
+    /// If the field changes, the ENR's sequence number is incremented and the ENR is re-signed,
+    /// saved to the network directory and shared with the rest of the node. Returns `Ok(false)`
+    /// without modifying the ENR if the field already holds `value`.
+    pub fn update_enr_field(&mut self, key: &str, value: Vec<u8>) -> Result<bool, String> {
+        let local_enr = self.discv5.local_enr();
+        if local_enr
+            .get(key)
+            .map_or(false, |current| current[..] == value[..])
+        {
+            return Ok(false);
+        }
+
+        self.discv5
+            .enr_insert(key, value)
+            .map_err(|e| format!("Could not update ENR field {}: {:?}", key, e))?;
+
+        let enr = self.discv5.local_enr();
+        if enr.seq() <= local_enr.seq() {
+            return Err(format!(
+                "ENR sequence number was not incremented. Previous: {}, current: {}",
+                local_enr.seq(),
+                enr.seq()
+            ));
+        }
+        debug!(self.log, "Updated local ENR"; "key" => key, "seq" => enr.seq());
+
+        enr::save_enr_to_disk(Path::new(&self.enr_dir), &enr, &self.log);
         // replace the global version with discovery version
-        *self.network_globals.local_enr.write() = self.discv5.local_enr().clone();
+        *self.network_globals.local_enr.write() = enr;
+        Ok(true)
     }
 
     /* Internal Functions */
@@ -403,10 +564,18 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         // Sanitize the queue, removing any out-dated subnet queries
         self.queued_queries.retain(|query| !query.expired());
 
+        // Queries which are paused, to be returned to the front of the queue
+        let mut paused_queries = Vec::new();
+
         // Check that we are within our query concurrency limit
         while !self.at_capacity() && !self.queued_queries.is_empty() {
             // consume and process the query queue
             match self.queued_queries.pop_front() {
+                Some(query @ QueryType::FindPeers) | Some(query @ QueryType::Predicate(_))
+                    if self.paused =>
+                {
+                    paused_queries.push(query);
+                }
                 Some(QueryType::FindPeers) => {
                     // Only permit one FindPeers query at a time
                     if self.find_peer_active {
@@ -426,9 +595,16 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
                     // This query is for searching for peers of a particular subnet
                     self.start_subnet_query(subnet_id, min_ttl, retries);
                 }
+                Some(query @ QueryType::Predicate(_)) => {
+                    debug!(self.log, "Searching for peers matching a predicate");
+                    self.start_query(query, FIND_NODE_QUERY_CLOSEST_PEERS);
+                }
                 None => {} // Queue is empty
             }
         }
+        for query in paused_queries.into_iter().rev() {
+            self.queued_queries.push_front(query);
+        }
         // Update the queue metric
         metrics::set_gauge(&metrics::DISCOVERY_QUEUE, self.queued_queries.len() as i64);
     }
@@ -501,19 +677,7 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
                 return;
             }
         };
-        // predicate for finding nodes with a matching fork
-        let eth2_fork_predicate = move |enr: &Enr| enr.eth2() == Ok(enr_fork_id.clone());
-
-        // General predicate
-        let predicate: Box<dyn Fn(&Enr) -> bool + Send> = match &query {
-            QueryType::FindPeers => Box::new(eth2_fork_predicate),
-            QueryType::Subnet { subnet_id, .. } => {
-                // build the subnet predicate as a combination of the eth2_fork_predicate and the
-                // subnet predicate
-                let subnet_predicate = subnet_predicate::<TSpec>(subnet_id.clone(), &self.log);
-                Box::new(move |enr: &Enr| eth2_fork_predicate(enr) && subnet_predicate(enr))
-            }
-        };
+        let predicate = query.predicate::<TSpec>(enr_fork_id, &self.log);
 
         // Build the future
         let query_future = self
@@ -565,6 +729,78 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
                         }
                     }
                 }
+                QueryType::Predicate(_) => match query_future.1 {
+                    Ok(r) if r.is_empty() => {
+                        debug!(self.log, "Predicate discovery query yielded no results.");
+                    }
+                    Ok(r) => {
+                        debug!(self.log, "Predicate discovery query completed"; "peers_found" => r.len());
+                        return Some((None, r));
+                    }
+                    Err(e) => {
+                        warn!(self.log, "Predicate discovery query failed"; "error" => e.to_string());
+                    }
+                },
+            }
+        }
+        None
+    }
+
+    /// Drives the resolution of the ENR trees published over DNS, returning the ENRs of any
+    /// completed resolution which are on our fork.
+    ///
+    /// The ENRs are added to the routing table, as bootnodes are, so that they can be dialed and
+    /// used as a starting point for discovery queries.
+    fn poll_dns_trees(&mut self, cx: &mut Context) -> Option<Vec<Enr>> {
+        while let Poll::Ready(Some(DnsTreeResult(url, result))) = self.dns_trees.poll_next_unpin(cx)
+        {
+            self.dns_trees.push(resolve_dns_tree(
+                url.clone(),
+                Some(DNS_TREE_REFRESH_INTERVAL),
+            ));
+
+            let enrs = match result {
+                Ok(enrs) => enrs,
+                Err(e) => {
+                    warn!(self.log, "Unable to resolve ENR tree"; "domain" => &url.domain, "error" => e);
+                    continue;
+                }
+            };
+
+            let local_fork = self.local_enr().eth2();
+            let enrs = enrs
+                .into_iter()
+                .filter(|enr| local_fork.is_ok() && enr.eth2() == local_fork)
+                .collect::<Vec<_>>();
+            debug!(self.log, "Resolved ENR tree"; "domain" => &url.domain, "peers_found" => enrs.len());
+
+            for enr in &enrs {
+                self.add_enr(enr.clone());
+            }
+            if !enrs.is_empty() {
+                return Some(enrs);
+            }
+        }
+        None
+    }
+
+    /// Drives the requests for the ENRs of peers, caching and returning the peer of any ENR
+    /// received.
+    fn poll_enr_requests(&mut self, cx: &mut Context) -> Option<PeerId> {
+        while let Poll::Ready(Some(EnrRequestResult(peer_id, result))) =
+            self.enr_requests.poll_next_unpin(cx)
+        {
+            match result {
+                Ok(Some(enr)) if enr.peer_id() == peer_id => {
+                    self.cached_enrs.put(peer_id.clone(), enr);
+                    return Some(peer_id);
+                }
+                Ok(_) => {
+                    debug!(self.log, "Peer did not provide its ENR"; "peer_id" => peer_id.to_string())
+                }
+                Err(e) => {
+                    debug!(self.log, "Failed to request the ENR of a peer"; "peer_id" => peer_id.to_string(), "error" => e)
+                }
             }
         }
         None
@@ -575,6 +811,11 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         // Process the query queue
         self.process_queue();
 
+        // Return the peers of any ENR trees which have been resolved
+        if let Some(result) = self.poll_dns_trees(cx) {
+            return Poll::Ready(DiscoveryEvent::QueryResult(None, Box::new(result)));
+        }
+
         // Drive the queries and return any results from completed queries
         if let Some((min_ttl, result)) = self.poll_queries(cx) {
             // cache the found ENR's
@@ -585,6 +826,11 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
             return Poll::Ready(DiscoveryEvent::QueryResult(min_ttl, Box::new(result)));
         }
 
+        // Return the peers whose requested ENRs have been received
+        if let Some(peer_id) = self.poll_enr_requests(cx) {
+            return Poll::Ready(DiscoveryEvent::EnrReceived(peer_id));
+        }
+
         // Process the server event stream
         match self.event_stream {
             EventStream::Awaiting(ref mut fut) => {
@@ -635,3 +881,104 @@ impl<TSpec: EthSpec> Discovery<TSpec> {
         Poll::Pending
     }
 }
+
+/// Resolves the ENR tree at `url` over DNS, after waiting for `delay` if it is specified.
+fn resolve_dns_tree(
+    url: EnrTreeUrl,
+    delay: Option<Duration>,
+) -> Pin<Box<dyn Future<Output = DnsTreeResult> + Send>> {
+    Box::pin(async move {
+        if let Some(delay) = delay {
+            tokio::time::delay_for(delay).await;
+        }
+        let result = dns::resolve_enr_tree_over_dns(url.clone()).await;
+        DnsTreeResult(url, result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::MinimalEthSpec;
+
+    fn build_enr_with_fields(enr_fork_id: EnrForkId, fields: Vec<(String, Vec<u8>)>) -> Enr {
+        let mut config = NetworkConfig::default();
+        config.enr_custom_fields = fields;
+        build_enr::<MinimalEthSpec>(&CombinedKey::generate_secp256k1(), &config, enr_fork_id)
+            .unwrap()
+    }
+
+    #[test]
+    fn predicate_query_matches_enrs_on_our_fork() {
+        let log = slog::Logger::root(slog::Discard, slog::o!());
+        let enr_fork_id = EnrForkId::default();
+        let other_fork_id = EnrForkId {
+            fork_digest: [1, 2, 3, 4],
+            ..EnrForkId::default()
+        };
+        let operator = vec![("operator".to_string(), vec![0x6c, 0x68])];
+
+        let query = QueryType::Predicate(PredicateQuery {
+            predicate: Arc::new(|enr: &Enr| enr.get("operator") == Some(&[0x6c, 0x68][..])),
+        });
+        let predicate = query.predicate::<MinimalEthSpec>(enr_fork_id.clone(), &log);
+
+        assert!(predicate(&build_enr_with_fields(
+            enr_fork_id.clone(),
+            operator.clone()
+        )));
+        // the predicate is not satisfied
+        assert!(!predicate(&build_enr_with_fields(
+            enr_fork_id.clone(),
+            vec![]
+        )));
+        assert!(!predicate(&build_enr_with_fields(
+            enr_fork_id,
+            vec![("operator".to_string(), vec![0x00])]
+        )));
+        // the peer is on another fork
+        assert!(!predicate(&build_enr_with_fields(other_fork_id, operator)));
+    }
+
+    #[tokio::test]
+    async fn update_enr_field_increments_seq_and_persists() {
+        let log = slog::Logger::root(slog::Discard, slog::o!());
+        let network_dir = tempdir::TempDir::new("discovery").unwrap();
+        let mut config = NetworkConfig::default();
+        config.network_dir = network_dir.path().into();
+        config.listen_address = "127.0.0.1".parse().unwrap();
+        config.discovery_port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .and_then(|socket| socket.local_addr())
+            .unwrap()
+            .port();
+
+        let keypair = Keypair::generate_secp256k1();
+        let enr_key = CombinedKey::from_libp2p(&keypair).unwrap();
+        let enr = build_enr::<MinimalEthSpec>(&enr_key, &config, EnrForkId::default()).unwrap();
+        let network_globals = Arc::new(NetworkGlobals::new(enr, 0, config.discovery_port, &log));
+        let mut discovery =
+            Discovery::<MinimalEthSpec>::new(&keypair, &config, network_globals.clone(), &log)
+                .unwrap();
+        let seq = discovery.local_enr().seq();
+
+        assert_eq!(
+            discovery.update_enr_field("operator", vec![0x6c, 0x68]),
+            Ok(true)
+        );
+        let disk_enr = std::fs::read_to_string(network_dir.path().join(ENR_FILENAME))
+            .unwrap()
+            .parse::<Enr>()
+            .unwrap();
+        for enr in &[discovery.local_enr(), network_globals.local_enr(), disk_enr] {
+            assert_eq!(enr.seq(), seq + 1);
+            assert_eq!(enr.get("operator"), Some(&[0x6c, 0x68][..]));
+        }
+
+        // setting the same value leaves the ENR unchanged
+        assert_eq!(
+            discovery.update_enr_field("operator", vec![0x6c, 0x68]),
+            Ok(false)
+        );
+        assert_eq!(discovery.local_enr().seq(), seq + 1);
+    }
+}
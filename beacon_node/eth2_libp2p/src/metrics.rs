@@ -17,6 +17,30 @@ lazy_static! {
         "libp2p_peer_disconnect_event_total",
         "Count of libp2p peer disconnect events"
     );
+    pub static ref PEERDB_RECORDS: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "libp2p_peerdb_records",
+        "The number of peers stored in the peer db, by connection status",
+        &["status"]
+    );
+    pub static ref PEERDB_SWARM_DISCREPANCIES: Result<IntCounterVec> = try_create_int_counter_vec(
+        "libp2p_peerdb_swarm_discrepancies_total",
+        "Count of peers whose connection status in the peer db disagreed with the swarm, by the kind of disagreement",
+        &["kind"]
+    );
+    pub static ref RPC_ERRORS_PER_CLIENT: Result<IntCounterVec> = try_create_int_counter_vec(
+        "libp2p_rpc_errors_per_client_total",
+        "Count of RPC errors with peers, by the client they run and the kind of error",
+        &["client", "version", "error"]
+    );
+    pub static ref GOSSIP_REJECTS_PER_CLIENT: Result<IntCounterVec> = try_create_int_counter_vec(
+        "libp2p_gossip_rejects_per_client_total",
+        "Count of gossip messages which failed validation, by the client of the sender and topic",
+        &["client", "version", "topic"]
+    );
+    pub static ref PEERS_NOT_FOLLOWING_NEXT_FORK: Result<Gauge> = try_create_float_gauge(
+        "libp2p_peers_not_following_next_fork_ratio",
+        "The fraction of connected peers with a known ENR which have not scheduled our next fork"
+    );
     pub static ref DISCOVERY_QUEUE: Result<IntGauge> = try_create_int_gauge(
         "discovery_queue_size",
         "The number of discovery queries awaiting execution"
@@ -0,0 +1,313 @@
+//! A list of banned peers, IP addresses and subnets, optionally persisted to a file so that it can
+//! be shared between nodes.
+//!
+//! The file contains one entry per line, each being a `PeerId`, an IP address or a subnet in CIDR
+//! notation. A `PeerId` may be followed by the unix time in seconds at which its ban expires, as
+//! is the case for the peers banned at runtime. Expired entries are dropped when the file is
+//! loaded. Empty lines and lines starting with `#` are ignored.
+
+use crate::PeerId;
+use ipnet::IpNet;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The collection of peers, IP addresses and subnets that we refuse to connect to.
+#[derive(Default)]
+pub struct BanList {
+    /// Banned peers, with the unix time at which their ban expires if it is not permanent.
+    peers: HashMap<PeerId, Option<Duration>>,
+    /// Banned IP addresses.
+    ips: HashSet<IpAddr>,
+    /// Banned subnets.
+    subnets: Vec<IpNet>,
+    /// The file the list is written to whenever it changes, if any.
+    file: Option<PathBuf>,
+}
+
+impl BanList {
+    /// Loads the ban list from `file`, which will be kept up to date with any changes.
+    ///
+    /// A missing file is treated as an empty list and is created when the first ban occurs.
+    pub fn load(file: PathBuf) -> Result<Self, String> {
+        let mut ban_list = BanList {
+            file: Some(file.clone()),
+            ..BanList::default()
+        };
+
+        if !file.exists() {
+            return Ok(ban_list);
+        }
+
+        let contents = fs::read_to_string(&file)
+            .map_err(|e| format!("Unable to read ban list file {:?}: {:?}", file, e))?;
+
+        let now = unix_time_now();
+        let mut expired = false;
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let entry = fields.next().unwrap_or_default();
+            let expiry = fields
+                .next()
+                .map(|secs| {
+                    secs.parse::<u64>()
+                        .map(Duration::from_secs)
+                        .map_err(|_| format!("Invalid ban list expiry: {}", line))
+                })
+                .transpose()?;
+            if fields.next().is_some() {
+                return Err(format!("Invalid ban list entry: {}", line));
+            }
+
+            if expiry.is_none() {
+                if let Ok(ip) = entry.parse::<IpAddr>() {
+                    ban_list.ips.insert(ip);
+                    continue;
+                } else if let Ok(subnet) = entry.parse::<IpNet>() {
+                    ban_list.subnets.push(subnet);
+                    continue;
+                }
+            }
+
+            if let Ok(peer_id) = entry.parse::<PeerId>() {
+                if expiry.map_or(false, |expiry| expiry <= now) {
+                    expired = true;
+                } else {
+                    ban_list.peers.insert(peer_id, expiry);
+                }
+            } else {
+                return Err(format!("Invalid ban list entry: {}", line));
+            }
+        }
+
+        if expired {
+            ban_list.persist()?;
+        }
+
+        Ok(ban_list)
+    }
+
+    /// Returns true if the peer is banned.
+    pub fn contains_peer(&self, peer_id: &PeerId) -> bool {
+        self.peers.contains_key(peer_id)
+    }
+
+    /// Returns true if the IP address is banned, either directly or by a banned subnet.
+    pub fn contains_ip(&self, ip: &IpAddr) -> bool {
+        self.ips.contains(ip) || self.subnets.iter().any(|subnet| subnet.contains(ip))
+    }
+
+    /// Returns an iterator over the banned peers.
+    pub fn peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.peers.keys()
+    }
+
+    /// Returns an iterator over the peers whose ban expires, along with the time remaining until
+    /// it does (zero if it has already expired).
+    pub fn expiring_peers(&self) -> impl Iterator<Item = (&PeerId, Duration)> {
+        let now = unix_time_now();
+        self.peers.iter().filter_map(move |(peer_id, expiry)| {
+            expiry.map(|expiry| (peer_id, expiry.checked_sub(now).unwrap_or_default()))
+        })
+    }
+
+    /// Returns every entry in the ban list, in the format of the ban list file.
+    pub fn entries(&self) -> Vec<String> {
+        let mut entries = self
+            .peers
+            .iter()
+            .map(|(peer_id, expiry)| match expiry {
+                Some(expiry) => format!("{} {}", peer_id, expiry.as_secs()),
+                None => peer_id.to_string(),
+            })
+            .chain(self.ips.iter().map(|ip| ip.to_string()))
+            .chain(self.subnets.iter().map(|subnet| subnet.to_string()))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    /// Adds a peer to the list for `ban_duration` and persists the list. A permanent ban of the
+    /// peer is kept.
+    pub(super) fn add_peer(
+        &mut self,
+        peer_id: &PeerId,
+        ban_duration: Duration,
+    ) -> Result<(), String> {
+        let expiry = self
+            .peers
+            .entry(peer_id.clone())
+            .or_insert(Some(Duration::from_secs(0)));
+        if let Some(expiry) = expiry {
+            *expiry = unix_time_now() + ban_duration;
+            self.persist()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Removes a peer whose ban expires from the list, persisting the list if it has changed. A
+    /// permanent ban of the peer is kept.
+    pub(super) fn remove_peer(&mut self, peer_id: &PeerId) -> Result<(), String> {
+        if let Some(Some(_)) = self.peers.get(peer_id) {
+            self.peers.remove(peer_id);
+            self.persist()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes the list to its file, if any.
+    fn persist(&self) -> Result<(), String> {
+        if let Some(file) = &self.file {
+            let mut contents = self.entries().join("\n");
+            contents.push('\n');
+            fs::write(file, contents)
+                .map_err(|e| format!("Unable to write ban list file {:?}: {:?}", file, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the time since the unix epoch.
+fn unix_time_now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_load_and_persist() {
+        let dir = TempDir::new("ban_list").unwrap();
+        let file = dir.path().join("ban_list.txt");
+        let peer_id = PeerId::random();
+
+        fs::write(
+            &file,
+            format!("# comment\n{}\n\n10.0.0.1\n192.168.0.0/16\n", peer_id),
+        )
+        .unwrap();
+
+        let mut ban_list = BanList::load(file.clone()).unwrap();
+        assert!(ban_list.contains_peer(&peer_id));
+        assert!(ban_list.contains_ip(&"10.0.0.1".parse().unwrap()));
+        assert!(!ban_list.contains_ip(&"10.0.0.2".parse().unwrap()));
+        assert!(ban_list.contains_ip(&"192.168.4.2".parse().unwrap()));
+        assert!(!ban_list.contains_ip(&"192.169.0.1".parse().unwrap()));
+
+        let new_peer_id = PeerId::random();
+        ban_list
+            .add_peer(&new_peer_id, Duration::from_secs(3600))
+            .unwrap();
+        // only the runtime ban is removed, the permanent ban from the file is kept
+        ban_list.remove_peer(&peer_id).unwrap();
+        assert!(ban_list.contains_peer(&peer_id));
+        ban_list.remove_peer(&new_peer_id).unwrap();
+        assert!(!ban_list.contains_peer(&new_peer_id));
+        ban_list
+            .add_peer(&new_peer_id, Duration::from_secs(3600))
+            .unwrap();
+
+        let reloaded = BanList::load(file).unwrap();
+        assert!(reloaded.contains_peer(&new_peer_id));
+        assert!(reloaded.contains_peer(&peer_id));
+        assert_eq!(reloaded.entries(), ban_list.entries());
+
+        // the runtime ban is reloaded with its expiry
+        let expiring = reloaded.expiring_peers().collect::<Vec<_>>();
+        assert_eq!(expiring.len(), 1);
+        assert_eq!(expiring[0].0, &new_peer_id);
+        assert!(expiring[0].1 <= Duration::from_secs(3600));
+        assert!(expiring[0].1 > Duration::from_secs(3500));
+    }
+
+    #[test]
+    fn test_expired_entries_are_dropped() {
+        let dir = TempDir::new("ban_list").unwrap();
+        let file = dir.path().join("ban_list.txt");
+        let expired = PeerId::random();
+        let expiring = PeerId::random();
+        let permanent = PeerId::random();
+        let now = unix_time_now().as_secs();
+
+        fs::write(
+            &file,
+            format!(
+                "{} {}\n{} {}\n{}\n",
+                expired,
+                now - 1,
+                expiring,
+                now + 60,
+                permanent
+            ),
+        )
+        .unwrap();
+
+        let ban_list = BanList::load(file.clone()).unwrap();
+        assert!(!ban_list.contains_peer(&expired));
+        assert!(ban_list.contains_peer(&expiring));
+        assert!(ban_list.contains_peer(&permanent));
+        assert_eq!(
+            ban_list
+                .expiring_peers()
+                .map(|(peer_id, _)| peer_id.clone())
+                .collect::<Vec<_>>(),
+            vec![expiring]
+        );
+
+        // the expired entry is removed from the file
+        assert_eq!(BanList::load(file).unwrap().entries(), ban_list.entries());
+        assert!(!fs::read_to_string(dir.path().join("ban_list.txt"))
+            .unwrap()
+            .contains(&expired.to_string()));
+    }
+
+    #[test]
+    fn test_permanent_ban_is_kept() {
+        let dir = TempDir::new("ban_list").unwrap();
+        let file = dir.path().join("ban_list.txt");
+        let peer_id = PeerId::random();
+        fs::write(&file, format!("{}\n", peer_id)).unwrap();
+
+        let mut ban_list = BanList::load(file).unwrap();
+        ban_list
+            .add_peer(&peer_id, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(ban_list.expiring_peers().count(), 0);
+    }
+
+    #[test]
+    fn test_invalid_entry() {
+        let dir = TempDir::new("ban_list").unwrap();
+        let file = dir.path().join("ban_list.txt");
+        fs::write(&file, "not a peer\n").unwrap();
+
+        assert!(BanList::load(file).is_err());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = TempDir::new("ban_list").unwrap();
+        let file = dir.path().join("ban_list.txt");
+
+        let mut ban_list = BanList::load(file.clone()).unwrap();
+        assert!(ban_list.entries().is_empty());
+
+        // the file is created on the first ban
+        ban_list
+            .add_peer(&PeerId::random(), Duration::from_secs(60))
+            .unwrap();
+        assert!(file.exists());
+    }
+}
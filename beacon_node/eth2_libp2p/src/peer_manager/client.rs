@@ -61,6 +61,19 @@ impl Client {
     }
 }
 
+impl ClientKind {
+    /// The name of the client, used to label metrics.
+    pub fn as_static_str(&self) -> &'static str {
+        match self {
+            ClientKind::Lighthouse => "lighthouse",
+            ClientKind::Nimbus => "nimbus",
+            ClientKind::Teku => "teku",
+            ClientKind::Prysm => "prysm",
+            ClientKind::Unknown => "unknown",
+        }
+    }
+}
+
 impl std::fmt::Display for Client {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.kind {
@@ -0,0 +1,137 @@
+//! Counts the RPC errors and rejected gossip messages of peers by the client they run, to help
+//! diagnose interoperability bugs.
+//!
+//! Unlike the `PeerInfo` of each peer, these counts are kept after a peer is dropped from the
+//! `PeerDB`.
+
+use super::client::Client;
+use crate::metrics;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The maximum number of client versions for which errors are counted. Errors from further
+/// versions are counted under `OTHER_VERSION`, as the version is chosen by the peer.
+const MAX_CLIENT_VERSIONS: usize = 64;
+
+/// The version under which errors are counted once `MAX_CLIENT_VERSIONS` is reached.
+const OTHER_VERSION: &str = "other";
+
+/// The errors attributed to peers running a single version of a client.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ClientErrorCounts {
+    /// The kind of client, e.g. `lighthouse`.
+    pub client: String,
+    /// The version of the client.
+    pub version: String,
+    /// The number of RPC errors, by the kind of error.
+    pub rpc_errors: BTreeMap<String, u64>,
+    /// The number of gossip messages which failed validation, by gossip topic.
+    pub gossip_rejects: BTreeMap<String, u64>,
+}
+
+/// The errors attributed to each client kind and version.
+#[derive(Default)]
+pub struct ClientErrors {
+    by_client: BTreeMap<(String, String), ClientErrorCounts>,
+}
+
+impl ClientErrors {
+    /// Records an RPC error with a peer running `client`.
+    pub fn record_rpc_error(&mut self, client: &Client, error: &str) {
+        let counts = self.counts_mut(client);
+        *counts.rpc_errors.entry(error.to_string()).or_default() += 1;
+        metrics::inc_counter_vec(
+            &metrics::RPC_ERRORS_PER_CLIENT,
+            &[&counts.client, &counts.version, error],
+        );
+    }
+
+    /// Records a gossip message on `topic` from a peer running `client` which failed validation.
+    pub fn record_gossip_reject(&mut self, client: &Client, topic: &str) {
+        let counts = self.counts_mut(client);
+        *counts.gossip_rejects.entry(topic.to_string()).or_default() += 1;
+        metrics::inc_counter_vec(
+            &metrics::GOSSIP_REJECTS_PER_CLIENT,
+            &[&counts.client, &counts.version, topic],
+        );
+    }
+
+    /// The errors of each client kind and version, ordered by client and version.
+    pub fn summary(&self) -> Vec<ClientErrorCounts> {
+        self.by_client.values().cloned().collect()
+    }
+
+    fn counts_mut(&mut self, client: &Client) -> &mut ClientErrorCounts {
+        let kind = client.kind.as_static_str().to_string();
+        let mut key = (kind, client.version.clone());
+        if !self.by_client.contains_key(&key) && self.by_client.len() >= MAX_CLIENT_VERSIONS {
+            key.1 = OTHER_VERSION.to_string();
+        }
+
+        let (client, version) = key.clone();
+        self.by_client
+            .entry(key)
+            .or_insert_with(|| ClientErrorCounts {
+                client,
+                version,
+                ..ClientErrorCounts::default()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer_manager::client::ClientKind;
+
+    fn client(kind: ClientKind, version: &str) -> Client {
+        Client {
+            kind,
+            version: version.to_string(),
+            ..Client::default()
+        }
+    }
+
+    #[test]
+    fn counts_by_client() {
+        let mut errors = ClientErrors::default();
+        let teku = client(ClientKind::Teku, "v0.12.0");
+
+        errors.record_rpc_error(&teku, "ssz_decode_error");
+        errors.record_rpc_error(&teku, "ssz_decode_error");
+        errors.record_gossip_reject(&teku, "beacon_block");
+        errors.record_rpc_error(&client(ClientKind::Prysm, "v1.0.0"), "io_error");
+
+        let summary = errors.summary();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].client, "prysm");
+        assert_eq!(summary[1].client, "teku");
+        assert_eq!(summary[1].version, "v0.12.0");
+        assert_eq!(summary[1].rpc_errors.get("ssz_decode_error"), Some(&2));
+        assert_eq!(summary[1].gossip_rejects.get("beacon_block"), Some(&1));
+    }
+
+    #[test]
+    fn versions_are_bounded() {
+        let mut errors = ClientErrors::default();
+
+        for i in 0..MAX_CLIENT_VERSIONS + 10 {
+            errors.record_rpc_error(
+                &client(ClientKind::Unknown, &i.to_string()),
+                "stream_timeout",
+            );
+        }
+        // known versions are still counted separately
+        errors.record_rpc_error(&client(ClientKind::Unknown, "0"), "stream_timeout");
+
+        let summary = errors.summary();
+        assert_eq!(summary.len(), MAX_CLIENT_VERSIONS + 1);
+        let other = summary
+            .iter()
+            .find(|counts| counts.version == OTHER_VERSION)
+            .unwrap();
+        assert_eq!(other.rpc_errors.get("stream_timeout"), Some(&10));
+        let first = summary.iter().find(|counts| counts.version == "0").unwrap();
+        assert_eq!(first.rpc_errors.get("stream_timeout"), Some(&2));
+    }
+}
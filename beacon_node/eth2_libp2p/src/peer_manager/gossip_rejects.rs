@@ -0,0 +1,90 @@
+//! Counts the gossip messages from each peer which failed validation, so that peers flooding us
+//! with invalid messages can be penalized and eventually banned.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// The window over which the rate of rejected messages is measured.
+const REJECT_WINDOW: Duration = Duration::from_secs(60);
+
+/// The gossip messages from a peer which failed validation.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GossipRejects {
+    /// The number of rejected messages, by gossip topic.
+    pub by_topic: BTreeMap<String, u64>,
+    /// The number of times the peer has been penalized for exceeding the rate limit.
+    pub penalties: u64,
+    /// The times of the rejected messages within the last `REJECT_WINDOW`, oldest first.
+    #[serde(skip)]
+    recent: VecDeque<Instant>,
+}
+
+impl GossipRejects {
+    /// The total number of rejected messages.
+    pub fn total(&self) -> u64 {
+        self.by_topic.values().sum()
+    }
+
+    /// Records a rejected message on `topic`, received at `now`.
+    ///
+    /// Returns true if more than `limit` messages have been rejected within the last minute, in
+    /// which case the peer should be penalized and the count for the rate starts again.
+    pub fn record(&mut self, topic: String, now: Instant, limit: u64) -> bool {
+        *self.by_topic.entry(topic).or_default() += 1;
+
+        while self.recent.front().map_or(false, |time| {
+            now.saturating_duration_since(*time) >= REJECT_WINDOW
+        }) {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(now);
+
+        if self.recent.len() as u64 > limit {
+            self.recent.clear();
+            self.penalties += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOPIC: &str = "beacon_aggregate_and_proof";
+
+    #[test]
+    fn test_penalize_above_limit() {
+        let mut rejects = GossipRejects::default();
+        let start = Instant::now();
+
+        for i in 0..5 {
+            assert!(!rejects.record(TOPIC.to_string(), start + Duration::from_secs(i), 5));
+        }
+        assert!(rejects.record(TOPIC.to_string(), start + Duration::from_secs(5), 5));
+        assert_eq!(rejects.penalties, 1);
+
+        // The count for the rate starts again after a penalty.
+        assert!(!rejects.record(TOPIC.to_string(), start + Duration::from_secs(6), 5));
+        assert_eq!(rejects.total(), 7);
+    }
+
+    #[test]
+    fn test_old_rejects_expire() {
+        let mut rejects = GossipRejects::default();
+        let start = Instant::now();
+
+        for i in 0..20 {
+            assert!(!rejects.record(
+                "beacon_block".to_string(),
+                start + Duration::from_secs(i * 15),
+                5
+            ));
+        }
+        assert_eq!(rejects.penalties, 0);
+        assert_eq!(rejects.by_topic.get("beacon_block"), Some(&20));
+    }
+}
@@ -1,47 +1,84 @@
 //! Implementation of a Lighthouse's peer management system.
 
 pub use self::peerdb::*;
+use crate::discovery::enr_ext::peer_id_to_node_id;
 use crate::discovery::{Discovery, DiscoveryEvent};
-use crate::rpc::{MetaData, Protocol, RPCError, RPCResponseErrorCode};
+use crate::rpc::{
+    methods::MAX_PEER_EXCHANGE_ENRS, GoodbyeReason, MetaData, Protocol, RPCError,
+    RPCResponseErrorCode, RequestId,
+};
+use crate::types::RangeRequestSnapshot;
 use crate::{error, metrics};
-use crate::{Enr, EnrExt, NetworkConfig, NetworkGlobals, PeerId};
+use crate::{ConnectedPoint, Enr, EnrExt, Eth2Enr, NetworkConfig, NetworkGlobals, PeerId};
 use futures::prelude::*;
 use futures::Stream;
 use hashset_delay::HashSetDelay;
 use libp2p::core::multiaddr::Protocol as MProtocol;
 use libp2p::identify::IdentifyInfo;
-use slog::{crit, debug, error};
+use slog::{crit, debug, error, info, warn};
 use smallvec::SmallVec;
 use std::{
+    collections::HashMap,
     net::SocketAddr,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
     time::{Duration, Instant},
 };
-use types::{EthSpec, SubnetId};
+use types::{EnrForkId, EthSpec, SubnetId, Unsigned};
 
 pub use libp2p::core::{identity::Keypair, Multiaddr};
 
+mod ban_list;
 pub mod client;
+mod client_errors;
+mod gossip_rejects;
 mod peer_info;
 mod peer_sync_status;
 mod peerdb;
-
-pub use peer_info::{PeerConnectionStatus::*, PeerInfo};
+mod ping_stats;
+mod private_network;
+mod range_performance;
+mod score;
+mod tests;
+
+pub use ban_list::BanList;
+pub use client_errors::{ClientErrorCounts, ClientErrors};
+pub use gossip_rejects::GossipRejects;
+pub use peer_info::{DisconnectCause, PeerConnectionStatus::*, PeerInfo};
 pub use peer_sync_status::{PeerSyncStatus, SyncInfo};
-/// The minimum reputation before a peer is disconnected.
+pub use ping_stats::PingStats;
+use ping_stats::DEFAULT_PING_INTERVAL;
+pub use private_network::{secret_proof, Membership, PrivateNetwork, PRIVATE_NETWORK_ENR_KEY};
+use range_performance::PendingRangeRequest;
+pub use range_performance::RangePerformance;
+pub use score::{ReputationConfig, ReputationTime, ScoreChange, MAX_SCORE_HISTORY};
+/// The minimum reputation before a peer is disconnected and banned.
 // Most likely this needs tweaking.
-const _MIN_REP_BEFORE_BAN: Rep = 10;
+const MIN_REP_BEFORE_BAN: Rep = 10;
+/// The time in seconds a peer is banned for when its reputation falls below `MIN_REP_BEFORE_BAN`.
+const BAN_DURATION: u64 = 3600;
 /// The time in seconds between re-status's peers.
 const STATUS_INTERVAL: u64 = 300;
-/// The time in seconds between PING events. We do not send a ping if the other peer as PING'd us within
-/// this time frame (Seconds)
-const PING_INTERVAL: u64 = 30;
+/// The time in seconds between re-status's of quarantined peers, so that they are promoted
+/// back to normal peers soon after they agree with our finalized chain.
+const QUARANTINE_STATUS_INTERVAL: u64 = 60;
+/// The time in seconds a newly connected peer has to exchange a STATUS with us, after which it is
+/// disconnected.
+const STATUS_HANDSHAKE_TIMEOUT: u64 = 15;
+/// The time in seconds a peer connected to our private network has for its ENR to become known to
+/// us, proving that it is a member, after which it is banned.
+const PRIVATE_NETWORK_ENR_TIMEOUT: u64 = 30;
+/// The time in seconds after a peer disconnected us for having too many peers during which it is
+/// only dialed once the other discovered peers have been.
+const FULL_PEER_REDIAL_BACKOFF: u64 = 600;
 
 /// The heartbeat performs regular updates such as updating reputations and performing discovery
 /// requests. This defines the interval in seconds.  
 const HEARTBEAT_INTERVAL: u64 = 30;
+/// The minimum time in seconds between warnings that connected peers have not scheduled our next
+/// fork.
+const FORK_READINESS_WARNING_INTERVAL: u64 = 600;
 
 /// The main struct that handles peer's reputation and connection status.
 pub struct PeerManager<TSpec: EthSpec> {
@@ -49,16 +86,47 @@ pub struct PeerManager<TSpec: EthSpec> {
     network_globals: Arc<NetworkGlobals<TSpec>>,
     /// A queue of events that the `PeerManager` is waiting to produce.
     events: SmallVec<[PeerManagerEvent; 16]>,
-    /// A collection of peers awaiting to be Ping'd.
+    /// A collection of peers awaiting to be Ping'd. Each peer is pinged after the interval in its
+    /// `PingStats`, unless it pings us first.
     ping_peers: HashSetDelay<PeerId>,
     /// A collection of peers awaiting to be Status'd.
     status_peers: HashSetDelay<PeerId>,
+    /// Newly connected peers which have not yet exchanged a STATUS with us.
+    handshaking_peers: HashSetDelay<PeerId>,
+    /// Connected peers whose membership of our private network is not yet known, as we do not know
+    /// their ENR.
+    unverified_peers: HashSetDelay<PeerId>,
+    /// The connections of the unverified peers, which are held back from the rest of the node
+    /// until the peer is admitted.
+    unverified_connections: HashMap<PeerId, ConnectedPoint>,
     /// The target number of peers we would like to connect to.
     target_peers: usize,
+    /// Whether peers which connect to us are disconnected, as we only make outbound connections.
+    disable_inbound: bool,
+    /// The minimum time a peer must be connected before it may be pruned by the heartbeat.
+    min_connection_age: Duration,
+    /// The reputation changes applied for each `PeerAction` and the rate of decay.
+    reputation_config: ReputationConfig,
+    /// The number of gossip messages from a peer which may fail validation within a minute.
+    gossip_reject_limit: u64,
+    /// The peers banned for their reputation, and when they were banned.
+    reputation_bans: HashMap<PeerId, ReputationTime>,
+    /// The `BlocksByRange` requests we have sent whose responses have not yet ended.
+    range_requests: HashMap<(PeerId, RequestId), PendingRangeRequest>,
+    /// The peers connected to the swarm, and whether their first connection was ingoing, as
+    /// reported by the behaviour. The `PeerDB` is reconciled against these on each heartbeat.
+    swarm_peers: HashMap<PeerId, bool>,
+    /// The peers in the process of disconnecting after a `Goodbye` was sent or received, which
+    /// side said goodbye first, and whether a heartbeat has passed since.
+    disconnecting_peers: HashMap<PeerId, (DisconnectCause, bool)>,
     /// The discovery service.
     discovery: Discovery<TSpec>,
+    /// The peers which may connect to us, if we are running on a private network.
+    private_network: Option<PrivateNetwork>,
     /// The heartbeat interval to perform routine maintenance.
     heartbeat: tokio::time::Interval,
+    /// The last time we warned that connected peers have not scheduled our next fork.
+    last_fork_readiness_warning: Option<Instant>,
     /// The logger associated with the `PeerManager`.
     log: slog::Logger,
 }
@@ -90,17 +158,30 @@ pub enum PeerAction {
 }
 
 impl PeerAction {
-    fn rep_change(&self) -> RepChange {
+    fn rep_change(&self, config: &ReputationConfig) -> RepChange {
         match self {
-            PeerAction::Fatal => RepChange::worst(),
-            PeerAction::LowToleranceError => RepChange::bad(60),
-            PeerAction::MidToleranceError => RepChange::bad(25),
-            PeerAction::HighToleranceError => RepChange::bad(15),
-            PeerAction::_ValidMessage => RepChange::good(20),
+            PeerAction::Fatal => RepChange::bad(config.fatal),
+            PeerAction::LowToleranceError => RepChange::bad(config.low_tolerance_error),
+            PeerAction::MidToleranceError => RepChange::bad(config.mid_tolerance_error),
+            PeerAction::HighToleranceError => RepChange::bad(config.high_tolerance_error),
+            PeerAction::_ValidMessage => RepChange::good(config.valid_message),
         }
     }
 }
 
+impl std::fmt::Display for PeerAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let repr = match self {
+            PeerAction::Fatal => "Fatal",
+            PeerAction::LowToleranceError => "Low Tolerance Error",
+            PeerAction::MidToleranceError => "Mid Tolerance Error",
+            PeerAction::HighToleranceError => "High Tolerance Error",
+            PeerAction::_ValidMessage => "Valid Message",
+        };
+        f.write_str(repr)
+    }
+}
+
 /// The events that the `PeerManager` outputs (requests).
 pub enum PeerManagerEvent {
     /// Dial a PeerId.
@@ -113,8 +194,15 @@ pub enum PeerManagerEvent {
     Ping(PeerId),
     /// Request METADATA from a peer.
     MetaData(PeerId),
-    /// The peer should be disconnected.
-    DisconnectPeer(PeerId),
+    /// The peer should be disconnected with the given reason.
+    DisconnectPeer(PeerId, GoodbyeReason),
+    /// The peer has been banned, so the swarm should refuse its connections.
+    BanPeer(PeerId),
+    /// The peer's ban has expired, so the swarm may accept its connections again.
+    UnbanPeer(PeerId),
+    /// The held connection of a peer proven to be a member of our private network should now be
+    /// established with the rest of the node.
+    AdmitPeer(PeerId, ConnectedPoint),
 }
 
 impl<TSpec: EthSpec> PeerManager<TSpec> {
@@ -125,6 +213,19 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         network_globals: Arc<NetworkGlobals<TSpec>>,
         log: &slog::Logger,
     ) -> error::Result<Self> {
+        let private_network = if config.private_network {
+            let private_network = PrivateNetwork::new(
+                config.private_network_allowlist.as_deref(),
+                config.private_network_secret.as_deref(),
+            )?;
+            info!(log, "Running on a private network";
+                "allowlist_entries" => private_network.allowlist_len(),
+                "shared_secret" => config.private_network_secret.is_some());
+            Some(private_network)
+        } else {
+            None
+        };
+
         // start the discovery service
         let mut discovery = Discovery::new(local_key, config, network_globals.clone(), log)?;
 
@@ -133,14 +234,43 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
 
         let heartbeat = tokio::time::interval(tokio::time::Duration::from_secs(HEARTBEAT_INTERVAL));
 
+        // peers banned for their reputation before the network stack was restarted are unbanned
+        // when the expiry recorded in the ban list is reached
+        let reputation_bans = {
+            let peerdb = network_globals.peers.read();
+            let now = ReputationTime::now();
+            let ban_duration = Duration::from_secs(BAN_DURATION);
+            peerdb
+                .ban_list()
+                .expiring_peers()
+                .filter(|(peer_id, remaining)| {
+                    peerdb.peer_banned(peer_id) && *remaining <= ban_duration
+                })
+                .map(|(peer_id, remaining)| (peer_id.clone(), now.rewind(ban_duration - remaining)))
+                .collect()
+        };
+
         Ok(PeerManager {
             network_globals,
             events: SmallVec::new(),
-            ping_peers: HashSetDelay::new(Duration::from_secs(PING_INTERVAL)),
+            ping_peers: HashSetDelay::new(DEFAULT_PING_INTERVAL),
             status_peers: HashSetDelay::new(Duration::from_secs(STATUS_INTERVAL)),
+            handshaking_peers: HashSetDelay::new(Duration::from_secs(STATUS_HANDSHAKE_TIMEOUT)),
+            unverified_peers: HashSetDelay::new(Duration::from_secs(PRIVATE_NETWORK_ENR_TIMEOUT)),
+            unverified_connections: HashMap::new(),
             target_peers: config.max_peers, //TODO: Add support for target peers and max peers
+            disable_inbound: config.disable_inbound,
+            min_connection_age: Duration::from_secs(config.min_connection_age_secs),
+            reputation_config: config.reputation.clone(),
+            gossip_reject_limit: config.gossip_reject_limit,
+            reputation_bans,
+            range_requests: HashMap::new(),
+            swarm_peers: HashMap::new(),
+            disconnecting_peers: HashMap::new(),
             discovery,
+            private_network,
             heartbeat,
+            last_fork_readiness_warning: None,
             log: log.clone(),
         })
     }
@@ -159,6 +289,94 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         &mut self.discovery
     }
 
+    /// Returns whether a newly connected peer may connect to us. This is always the case unless we
+    /// are running on a private network, in which case the peer must be in the allowlist or have
+    /// an ENR proving that it knows the shared secret.
+    ///
+    /// The connection of a peer whose ENR is not yet known is held, and its ENR is requested. The
+    /// peer is admitted with `PeerManagerEvent::AdmitPeer` once its ENR proves that it is a member,
+    /// and is banned otherwise, or if its ENR does not become known within
+    /// `PRIVATE_NETWORK_ENR_TIMEOUT`.
+    pub fn admit_to_private_network(
+        &mut self,
+        peer_id: &PeerId,
+        endpoint: &ConnectedPoint,
+    ) -> Membership {
+        let membership = self.private_network_membership(peer_id);
+        if membership == Membership::Unknown {
+            debug!(self.log, "Awaiting the ENR of a peer on the private network"; "peer_id" => peer_id.to_string());
+            self.unverified_peers.insert(peer_id.clone());
+            self.unverified_connections
+                .insert(peer_id.clone(), endpoint.clone());
+            if let ConnectedPoint::Dialer { address } = endpoint {
+                self.discovery.request_enr(peer_id, address);
+            }
+        }
+        membership
+    }
+
+    /// Returns true if the peer is connected, but is held until it proves to be a member of our
+    /// private network. Its messages should be ignored.
+    pub fn is_unverified(&self, peer_id: &PeerId) -> bool {
+        self.unverified_connections.contains_key(peer_id)
+    }
+
+    /// Returns whether the peer is a member of our private network, if we are running on one.
+    fn private_network_membership(&mut self, peer_id: &PeerId) -> Membership {
+        let private_network = match &self.private_network {
+            Some(private_network) => private_network,
+            None => return Membership::Member,
+        };
+
+        match peer_id_to_node_id(peer_id) {
+            Ok(node_id) => {
+                let enr = self.discovery.enr_of_peer(peer_id);
+                private_network.membership(&node_id, enr.as_ref())
+            }
+            Err(_) => Membership::NonMember,
+        }
+    }
+
+    /// Checks whether a connected peer whose ENR was not known is a member of our private network,
+    /// admitting it if it is and banning it if it is not.
+    fn verify_private_network_member(&mut self, peer_id: &PeerId) {
+        if !self.unverified_peers.contains(peer_id) {
+            return;
+        }
+
+        match self.private_network_membership(peer_id) {
+            Membership::Member => self.admit_private_network_member(peer_id),
+            Membership::NonMember => {
+                debug!(self.log, "Banning peer outside the private network"; "peer_id" => peer_id.to_string());
+                self.ban_private_network_non_member(peer_id);
+            }
+            Membership::Unknown => {}
+        }
+    }
+
+    /// Releases the held connection of a peer which has proven to be a member of our private
+    /// network.
+    fn admit_private_network_member(&mut self, peer_id: &PeerId) {
+        self.unverified_peers.remove(peer_id);
+        if let Some(endpoint) = self.unverified_connections.remove(peer_id) {
+            debug!(self.log, "Admitting peer to the private network"; "peer_id" => peer_id.to_string());
+            self.events
+                .push(PeerManagerEvent::AdmitPeer(peer_id.clone(), endpoint));
+        }
+    }
+
+    /// Bans a held peer which has not proven to be a member of our private network, so that it
+    /// cannot keep reconnecting whilst hiding its ENR.
+    fn ban_private_network_non_member(&mut self, peer_id: &PeerId) {
+        self.unverified_peers.remove(peer_id);
+        self.unverified_connections.remove(peer_id);
+        self.events.push(PeerManagerEvent::DisconnectPeer(
+            peer_id.clone(),
+            GoodbyeReason::IrrelevantNetwork,
+        ));
+        self.ban_peer(peer_id, ReputationTime::now());
+    }
+
     /// A request to find peers on a given subnet.
     pub fn discover_subnet_peers(&mut self, subnet_id: SubnetId, min_ttl: Option<Instant>) {
         // Extend the time to maintain peers if required.
@@ -173,19 +391,50 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         self.discovery.discover_subnet_peers(subnet_id, min_ttl);
     }
 
+    /// A request to find peers whose ENR satisfies `predicate`.
+    pub fn discover_peers_with_predicate<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Enr) -> bool + Send + Sync + 'static,
+    {
+        self.discovery.discover_peers_with_predicate(predicate);
+    }
+
     /// A STATUS message has been received from a peer. This resets the status timer.
     pub fn peer_statusd(&mut self, peer_id: &PeerId) {
+        self.handshaking_peers.remove(peer_id);
         self.status_peers.insert(peer_id.clone());
+        self.network_globals.peers.write().status_received(peer_id);
+    }
+
+    /// A new connection has been established with a peer. The peer is disconnected if it does not
+    /// exchange a STATUS with us within `STATUS_HANDSHAKE_TIMEOUT`.
+    pub fn await_status(&mut self, peer_id: &PeerId) {
+        self.handshaking_peers.insert(peer_id.clone());
     }
 
     /// Updates the state of the peer as disconnected.
-    pub fn notify_disconnect(&mut self, peer_id: &PeerId) {
-        //self.update_reputations();
-        self.network_globals.peers.write().disconnect(peer_id);
+    ///
+    /// The `cause` reported by the transport is overridden if a `Goodbye` was sent or received
+    /// before the connection ended.
+    pub fn notify_disconnect(&mut self, peer_id: &PeerId, cause: DisconnectCause) {
+        let cause = self
+            .disconnecting_peers
+            .remove(peer_id)
+            .map_or(cause, |(goodbye_cause, _)| goodbye_cause);
+        debug!(self.log, "Peer disconnected"; "peer_id" => peer_id.to_string(), "cause" => format!("{:?}", cause));
+        {
+            let mut peerdb = self.network_globals.peers.write();
+            peerdb.record_disconnect(peer_id, cause);
+            peerdb.disconnect(peer_id);
+        }
 
         // remove the ping and status timer for the peer
         self.ping_peers.remove(peer_id);
         self.status_peers.remove(peer_id);
+        self.handshaking_peers.remove(peer_id);
+        self.unverified_peers.remove(peer_id);
+        self.unverified_connections.remove(peer_id);
+        self.range_requests.retain(|(id, _), _| id != peer_id);
         metrics::inc_counter(&metrics::PEER_DISCONNECT_EVENT_COUNT);
         metrics::set_gauge(
             &metrics::PEERS_CONNECTED,
@@ -193,10 +442,46 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         );
     }
 
+    /// The swarm has established a connection with a peer.
+    pub fn swarm_connection_established(&mut self, peer_id: &PeerId, endpoint: &ConnectedPoint) {
+        let ingoing = match endpoint {
+            ConnectedPoint::Listener { .. } => true,
+            ConnectedPoint::Dialer { .. } => false,
+        };
+        self.swarm_peers.entry(peer_id.clone()).or_insert(ingoing);
+    }
+
+    /// The swarm has closed its last connection with a peer.
+    pub fn swarm_disconnected(&mut self, peer_id: &PeerId) {
+        self.swarm_peers.remove(peer_id);
+    }
+
     /// Sets a peer as connected as long as their reputation allows it
     /// Informs if the peer was accepted
+    ///
+    /// If we are over our peer limit the peer is disconnected with `TooManyPeers`. If inbound
+    /// connections are disabled, the peer is not accepted and is disconnected with `TooManyPeers`.
     pub fn connect_ingoing(&mut self, peer_id: &PeerId) -> bool {
-        self.connect_peer(peer_id, ConnectingType::IngoingConnected)
+        if self.disable_inbound {
+            debug!(self.log, "Inbound connections are disabled, disconnecting ingoing peer"; "peer_id" => peer_id.to_string());
+            self.events.push(PeerManagerEvent::DisconnectPeer(
+                peer_id.clone(),
+                GoodbyeReason::TooManyPeers,
+            ));
+            return false;
+        }
+
+        let accepted = self.connect_peer(peer_id, ConnectingType::IngoingConnected);
+
+        if self.network_globals.connected_peers() > self.target_peers {
+            debug!(self.log, "Too many peers, disconnecting ingoing peer"; "peer_id" => peer_id.to_string());
+            self.events.push(PeerManagerEvent::DisconnectPeer(
+                peer_id.clone(),
+                GoodbyeReason::TooManyPeers,
+            ));
+        }
+
+        accepted
     }
 
     /// Sets a peer as connected as long as their reputation allows it
@@ -210,27 +495,187 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         self.connect_peer(peer_id, ConnectingType::Dialing)
     }
 
-    /// Updates the database informing that a peer is being disconnected.
-    pub fn _disconnecting_peer(&mut self, _peer_id: &PeerId) -> bool {
-        // TODO: implement
-        true
+    /// A `Goodbye` has been sent to or received from a peer, which is now disconnecting. Only the
+    /// first `Goodbye` is recorded as the cause of the disconnection.
+    pub fn disconnecting_peer(&mut self, peer_id: &PeerId, cause: DisconnectCause) {
+        self.disconnecting_peers
+            .entry(peer_id.clone())
+            .or_insert((cause, false));
     }
 
-    /// Reports a peer for some action.
+    /// Reports a peer for some action. The `reason` is recorded in the peer's score history.
+    ///
+    /// If the peer's reputation falls below `MIN_REP_BEFORE_BAN`, it is disconnected and banned.
     ///
     /// If the peer doesn't exist, log a warning and insert defaults.
-    pub fn report_peer(&mut self, peer_id: &PeerId, action: PeerAction) {
-        //TODO: Check these. There are double disconnects for example
-        // self.update_reputations();
+    pub fn report_peer(&mut self, peer_id: &PeerId, action: PeerAction, reason: &str) {
+        let mut peerdb = self.network_globals.peers.write();
+        peerdb.add_reputation(
+            peer_id,
+            action.rep_change(&self.reputation_config),
+            format!("{}: {}", action, reason),
+        );
+        let ban = peerdb.reputation(peer_id) < MIN_REP_BEFORE_BAN
+            && peerdb
+                .connection_status(peer_id)
+                .map_or(false, |status| !status.is_banned());
+        drop(peerdb);
+
+        if ban {
+            self.ban_peer(peer_id, ReputationTime::now());
+        }
+    }
+
+    /// A gossip message on `topic` from the peer failed validation. The peer is penalized if it
+    /// sends more than `gossip_reject_limit` such messages in a minute, so that a peer which
+    /// persists is eventually banned.
+    pub fn gossip_rejected(&mut self, peer_id: &PeerId, topic: String) {
+        let client = self.network_globals.client(peer_id);
         self.network_globals
+            .client_errors
+            .write()
+            .record_gossip_reject(&client, &topic);
+
+        let limit = self.gossip_reject_limit;
+        let penalize = self
+            .network_globals
             .peers
             .write()
-            .add_reputation(peer_id, action.rep_change());
-        // self.update_reputations();
+            .peer_info_mut(peer_id)
+            .map_or(false, |info| {
+                info.gossip_rejects.record(topic, Instant::now(), limit)
+            });
+
+        if penalize {
+            debug!(self.log, "Peer is flooding invalid gossip messages"; "peer_id" => peer_id.to_string());
+            self.report_peer(
+                peer_id,
+                PeerAction::LowToleranceError,
+                "too many invalid gossip messages",
+            );
+        }
+    }
+
+    /// The `BlocksByRange` requests we have sent whose responses have not yet ended.
+    pub fn pending_range_requests(&self) -> Vec<RangeRequestSnapshot> {
+        self.range_requests
+            .iter()
+            .map(|((peer_id, id), request)| RangeRequestSnapshot {
+                peer_id: peer_id.to_string(),
+                request_id: format!("{:?}", id),
+                age_millis: request.age().as_millis() as u64,
+                blocks: request.blocks(),
+            })
+            .collect()
+    }
+
+    /// A `BlocksByRange` request has been sent to a peer.
+    pub fn blocks_by_range_sent(&mut self, peer_id: &PeerId, id: RequestId) {
+        self.range_requests
+            .insert((peer_id.clone(), id), PendingRangeRequest::new());
+    }
+
+    /// A block has been received in response to a `BlocksByRange` request.
+    pub fn blocks_by_range_block(&mut self, peer_id: &PeerId, id: RequestId) {
+        if let Some(request) = self.range_requests.get_mut(&(peer_id.clone(), id)) {
+            request.on_block();
+        }
+    }
+
+    /// A `BlocksByRange` response has ended. The peer is penalized if its responses are
+    /// consistently slow.
+    pub fn blocks_by_range_completed(&mut self, peer_id: &PeerId, id: RequestId) {
+        let response = match self.range_requests.remove(&(peer_id.clone(), id)) {
+            Some(request) => request.complete(),
+            None => return,
+        };
+
+        let penalize = self
+            .network_globals
+            .peers
+            .write()
+            .peer_info_mut(peer_id)
+            .map_or(false, |info| info.range_performance.record(response));
+
+        if penalize {
+            debug!(self.log, "Peer is consistently slow to respond to BlocksByRange"; "peer_id" => peer_id.to_string());
+            self.report_peer(
+                peer_id,
+                PeerAction::HighToleranceError,
+                "slow BlocksByRange responses",
+            );
+        }
+    }
+
+    /// A `BlocksByRange` request has failed. The error itself is scored by `handle_rpc_error`.
+    pub fn blocks_by_range_failed(&mut self, peer_id: &PeerId, id: RequestId) {
+        self.range_requests.remove(&(peer_id.clone(), id));
+    }
+
+    /// Returns the ENRs of our best connected peers, other than `peer_id`. These are sent to a
+    /// peer we are disconnecting due to `TooManyPeers`, so it can find other peers quickly.
+    pub fn peer_exchange_enrs(&mut self, peer_id: &PeerId) -> Vec<Enr> {
+        let mut peers = self
+            .network_globals
+            .peers
+            .read()
+            .connected_peers()
+            .filter(|(id, info)| {
+                *id != peer_id
+                    && info.reputation >= DEFAULT_REPUTATION
+                    && !info.sync_status.is_quarantined()
+            })
+            .map(|(id, info)| (id.clone(), info.reputation))
+            .collect::<Vec<_>>();
+        // prefer the peers with the highest reputation
+        peers.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        peers
+            .into_iter()
+            .filter_map(|(id, _)| self.discovery.enr_of_peer(&id))
+            .take(MAX_PEER_EXCHANGE_ENRS)
+            .collect()
+    }
+
+    /// A peer has sent us the ENRs of other peers, usually before disconnecting us due to
+    /// `TooManyPeers`. The ENRs are added to the routing table and dialed if we need more peers.
+    ///
+    /// Records are only accepted from peers with at least the default reputation, so that a
+    /// misbehaving peer cannot steer us towards peers of its choosing.
+    pub fn peer_exchange_received(&mut self, peer_id: &PeerId, enrs: Vec<Enr>) {
+        let reputation = self.network_globals.peers.read().reputation(peer_id);
+        if reputation < DEFAULT_REPUTATION {
+            debug!(self.log, "Ignoring peer exchange from untrusted peer"; "peer_id" => peer_id.to_string(), "reputation" => reputation);
+            return;
+        }
+
+        debug!(self.log, "Received peer exchange"; "peer_id" => peer_id.to_string(), "enrs" => enrs.len());
+        let local_peer_id = self.network_globals.local_peer_id();
+        let enrs = enrs
+            .into_iter()
+            .filter(|enr| enr.peer_id() != local_peer_id)
+            .collect::<Vec<_>>();
+
+        for enr in &enrs {
+            self.discovery.add_enr(enr.clone());
+        }
+        self.peers_discovered(enrs, None);
     }
 
     /// Updates `PeerInfo` with `identify` information.
     pub fn identify(&mut self, peer_id: &PeerId, info: &IdentifyInfo) {
+        if self.unverified_peers.contains(peer_id) {
+            // discovery may have learned the peer's ENR since it connected, otherwise the peer
+            // may be asked for it at the addresses it listens on
+            self.verify_private_network_member(peer_id);
+            if self.unverified_peers.contains(peer_id) {
+                for address in &info.listen_addrs {
+                    self.discovery.request_enr(peer_id, address);
+                }
+            }
+            return;
+        }
+
         if let Some(peer_info) = self.network_globals.peers.write().peer_info_mut(peer_id) {
             peer_info.client = client::Client::from_identify_info(info);
             peer_info.listening_addresses = info.listen_addrs.clone();
@@ -242,6 +687,10 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
     pub fn handle_rpc_error(&mut self, peer_id: &PeerId, protocol: Protocol, err: &RPCError) {
         let client = self.network_globals.client(peer_id);
         debug!(self.log, "RPCError"; "protocol" => protocol.to_string(), "err" => err.to_string(), "client" => client.to_string());
+        self.network_globals
+            .client_errors
+            .write()
+            .record_rpc_error(&client, err.as_static_str());
 
         // Map this error to a `PeerAction` (if any)
         let peer_action = match err {
@@ -265,6 +714,8 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
                 RPCResponseErrorCode::Unknown => PeerAction::HighToleranceError,
                 RPCResponseErrorCode::ServerError => PeerAction::MidToleranceError,
                 RPCResponseErrorCode::InvalidRequest => PeerAction::LowToleranceError,
+                // The responder is busy, which is not the fault of either peer
+                RPCResponseErrorCode::RateLimited => return,
             },
             RPCError::SSZDecodeError(_) => PeerAction::Fatal,
             RPCError::UnsupportedProtocol => {
@@ -280,22 +731,28 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
                     Protocol::BlocksByRange => return,
                     Protocol::BlocksByRoot => return,
                     Protocol::Goodbye => return,
+                    // Peer exchange is a Lighthouse extension.
+                    Protocol::PeerExchange => return,
                     Protocol::MetaData => PeerAction::LowToleranceError,
                     Protocol::Status => PeerAction::LowToleranceError,
                 }
             }
-            RPCError::StreamTimeout => match protocol {
+            RPCError::StreamTimeout | RPCError::TTFBTimeout => match protocol {
                 Protocol::Ping => PeerAction::LowToleranceError,
                 Protocol::BlocksByRange => PeerAction::MidToleranceError,
                 Protocol::BlocksByRoot => PeerAction::MidToleranceError,
                 Protocol::Goodbye => return,
+                Protocol::PeerExchange => return,
                 Protocol::MetaData => return,
                 Protocol::Status => return,
             },
+            // The peer is responding, just slowly. This is common for long `BlocksByRange`
+            // responses from peers with a poor connection, so it is tolerated.
+            RPCError::ChunkTimeout => PeerAction::HighToleranceError,
             RPCError::NegotiationTimeout => PeerAction::HighToleranceError,
         };
 
-        self.report_peer(peer_id, peer_action);
+        self.report_peer(peer_id, peer_action, &format!("{} on {}", err, protocol));
     }
 
     /// A ping request has been received.
@@ -306,13 +763,14 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
             // received a ping
             // reset the to-ping timer for this peer
             debug!(self.log, "Received a ping request"; "peer_id" => peer_id.to_string(), "seq_no" => seq);
-            self.ping_peers.insert(peer_id.clone());
+            self.ping_peers
+                .insert_at(peer_id.clone(), peer_info.ping_stats.interval());
 
             // if the sequence number is unknown send an update the meta data of the peer.
             if let Some(meta_data) = &peer_info.meta_data {
-                if meta_data.seq_number < seq {
+                if meta_data.seq_number() < seq {
                     debug!(self.log, "Requesting new metadata from peer";
-                        "peer_id" => peer_id.to_string(), "known_seq_no" => meta_data.seq_number, "ping_seq_no" => seq);
+                        "peer_id" => peer_id.to_string(), "known_seq_no" => meta_data.seq_number(), "ping_seq_no" => seq);
                     self.events
                         .push(PeerManagerEvent::MetaData(peer_id.clone()));
                 }
@@ -332,14 +790,15 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
     /// A PONG has been returned from a peer.
     // TODO: Update last seen
     pub fn pong_response(&mut self, peer_id: &PeerId, seq: u64) {
-        if let Some(peer_info) = self.network_globals.peers.read().peer_info(peer_id) {
+        if let Some(peer_info) = self.network_globals.peers.write().peer_info_mut(peer_id) {
             // received a pong
+            peer_info.ping_stats.pong_received();
 
             // if the sequence number is unknown send update the meta data of the peer.
             if let Some(meta_data) = &peer_info.meta_data {
-                if meta_data.seq_number < seq {
+                if meta_data.seq_number() < seq {
                     debug!(self.log, "Requesting new metadata from peer";
-                        "peer_id" => peer_id.to_string(), "known_seq_no" => meta_data.seq_number, "pong_seq_no" => seq);
+                        "peer_id" => peer_id.to_string(), "known_seq_no" => meta_data.seq_number(), "pong_seq_no" => seq);
                     self.events
                         .push(PeerManagerEvent::MetaData(peer_id.clone()));
                 }
@@ -360,18 +819,18 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
     pub fn meta_data_response(&mut self, peer_id: &PeerId, meta_data: MetaData<TSpec>) {
         if let Some(peer_info) = self.network_globals.peers.write().peer_info_mut(peer_id) {
             if let Some(known_meta_data) = &peer_info.meta_data {
-                if known_meta_data.seq_number < meta_data.seq_number {
+                if known_meta_data.seq_number() < meta_data.seq_number() {
                     debug!(self.log, "Updating peer's metadata";
-                        "peer_id" => peer_id.to_string(), "known_seq_no" => known_meta_data.seq_number, "new_seq_no" => meta_data.seq_number);
+                        "peer_id" => peer_id.to_string(), "known_seq_no" => known_meta_data.seq_number(), "new_seq_no" => meta_data.seq_number());
                     peer_info.meta_data = Some(meta_data);
                 } else {
                     debug!(self.log, "Received old metadata";
-                        "peer_id" => peer_id.to_string(), "known_seq_no" => known_meta_data.seq_number, "new_seq_no" => meta_data.seq_number);
+                        "peer_id" => peer_id.to_string(), "known_seq_no" => known_meta_data.seq_number(), "new_seq_no" => meta_data.seq_number());
                 }
             } else {
                 // we have no meta-data for this peer, update
                 debug!(self.log, "Obtained peer's metadata";
-                    "peer_id" => peer_id.to_string(), "new_seq_no" => meta_data.seq_number);
+                    "peer_id" => peer_id.to_string(), "new_seq_no" => meta_data.seq_number());
                 peer_info.meta_data = Some(meta_data);
             }
         } else {
@@ -397,6 +856,10 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
             });
 
             out_list
+        } else if let Some(peer_info) = self.network_globals.peers.read().peer_info(peer_id) {
+            // Peers which are not known to discovery (e.g. those dialed by multiaddr) have no ENR,
+            // so fall back to the addresses they reported listening on via Identify.
+            peer_info.listening_addresses.clone()
         } else {
             // PeerId is not known
             Vec::new()
@@ -423,17 +886,65 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
     /// multiaddr here, however this could relate to duplicate PeerId's etc. If the lookup
     /// proves resource constraining, we should switch to multiaddr dialling here.
     fn peers_discovered(&mut self, peers: Vec<Enr>, min_ttl: Option<Instant>) {
+        // peers which recently told us they were full are only dialed once the others have been.
+        // Otherwise the peers on the subnets we have the fewest peers on are dialed first.
+        let backoff = Duration::from_secs(FULL_PEER_REDIAL_BACKOFF);
+        let peers = {
+            let peerdb = self.network_globals.peers.read();
+            let subnet_peers = (0..TSpec::SubnetBitfieldLength::to_u64())
+                .map(|subnet_id| peerdb.peers_on_subnet(SubnetId::new(subnet_id)).count())
+                .collect::<Vec<_>>();
+            let mut peers = peers
+                .into_iter()
+                .map(|enr| {
+                    let recently_full = peerdb
+                        .peer_info(&enr.peer_id())
+                        .map_or(false, |info| info.recently_full(backoff));
+                    let score = subnet_share_score::<TSpec>(&enr, &subnet_peers);
+                    (recently_full, score, enr)
+                })
+                .collect::<Vec<_>>();
+            peers.sort_by(|(full_a, score_a, _), (full_b, score_b, _)| {
+                full_a.cmp(full_b).then_with(|| {
+                    score_b
+                        .partial_cmp(score_a)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+            });
+            peers.into_iter().map(|(_, _, enr)| enr).collect::<Vec<_>>()
+        };
+
+        // dialed peers are only counted once they connect, so the number of dials is capped up
+        // front to avoid overshooting the target
+        let mut dials_remaining = self
+            .target_peers
+            .saturating_sub(self.network_globals.connected_or_dialing_peers());
+
         for enr in peers {
             let peer_id = enr.peer_id();
+            self.network_globals.peers.write().peer_seen(&peer_id);
 
             // if we need more peers, attempt a connection
-            if self.network_globals.connected_or_dialing_peers() < self.target_peers
+            if dials_remaining > 0
                 && !self
                     .network_globals
                     .peers
                     .read()
                     .is_connected_or_dialing(&peer_id)
                 && !self.network_globals.peers.read().peer_banned(&peer_id)
+                && !enr.ip().map_or(false, |ip| {
+                    self.network_globals
+                        .peers
+                        .read()
+                        .ban_list()
+                        .contains_ip(&ip.into())
+                })
+                && self
+                    .private_network
+                    .as_ref()
+                    .map_or(true, |private_network| {
+                        private_network.allows(&enr.node_id(), Some(&enr))
+                    })
             {
                 debug!(self.log, "Dialing discovered peer"; "peer_id"=> peer_id.to_string());
                 // TODO: Update output
@@ -446,6 +957,7 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
                         .update_min_ttl(&peer_id, min_ttl);
                 }
                 self.events.push(PeerManagerEvent::Dial(peer_id));
+                dials_remaining -= 1;
             }
         }
     }
@@ -461,15 +973,20 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         // TODO: remove after timed updates
         //self.update_reputations();
 
-        {
-            let mut peerdb = self.network_globals.peers.write();
-            if peerdb.connection_status(peer_id).map(|c| c.is_banned()) == Some(true) {
-                // don't connect if the peer is banned
-                // TODO: Handle this case. If peer is banned this shouldn't be reached. It will put
-                // our connection/disconnection out of sync with libp2p
-                // return false;
+        // a peer banned for its reputation is disconnected again until its ban expires
+        if self.reputation_bans.contains_key(peer_id) {
+            if let ConnectingType::IngoingConnected | ConnectingType::OutgoingConnected = connection
+            {
+                self.events.push(PeerManagerEvent::DisconnectPeer(
+                    peer_id.clone(),
+                    GoodbyeReason::Fault,
+                ));
             }
+            return false;
+        }
 
+        {
+            let mut peerdb = self.network_globals.peers.write();
             match connection {
                 ConnectingType::Dialing => peerdb.dialing_peer(peer_id),
                 ConnectingType::IngoingConnected => peerdb.connect_outgoing(peer_id),
@@ -478,7 +995,13 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         }
 
         // start a ping and status timer for the peer
-        self.ping_peers.insert(peer_id.clone());
+        let ping_interval = self
+            .network_globals
+            .peers
+            .read()
+            .peer_info(peer_id)
+            .map_or(DEFAULT_PING_INTERVAL, |info| info.ping_stats.interval());
+        self.ping_peers.insert_at(peer_id.clone(), ping_interval);
         self.status_peers.insert(peer_id.clone());
 
         // increment prometheus metrics
@@ -496,95 +1019,131 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
         self.network_globals.peers.write().dialing_peer(peer_id);
     }
 
-    /// Updates the reputation of known peers according to their connection
-    /// status and the time that has passed.
-    ///
-    /// **Disconnected peers** get a 1rep hit every hour they stay disconnected.
-    /// **Banned peers** get a 1rep gain for every hour to slowly allow them back again.
+    /// Updates the reputation of known peers for the time that has passed, banning those whose
+    /// reputation has fallen below `MIN_REP_BEFORE_BAN` and unbanning those whose ban has lasted
+    /// `BAN_DURATION`.
     ///
-    /// A banned(disconnected) peer that gets its rep above(below) MIN_REP_BEFORE_BAN is
-    /// now considered a disconnected(banned) peer.
-    // TODO: Implement when reputation is added.
-    fn _update_reputations(&mut self) {
-        /*
-        // avoid locking the peerdb too often
-        // TODO: call this on a timer
-
-        let now = Instant::now();
-
-        // Check for peers that get banned, unbanned and that should be disconnected
-        let mut ban_queue = Vec::new();
-        let mut unban_queue = Vec::new();
-
-        /* Check how long have peers been in this state and update their reputations if needed */
-        let mut pdb = self.network_globals.peers.write();
+    /// Unbanned peers are given a reputation of `MIN_REP_BEFORE_BAN`, from which they slowly
+    /// recover, so that a peer which misbehaves again is soon banned again.
+    fn update_reputations(&mut self, now: ReputationTime) {
+        // Slowly forgive peers with a poor reputation
+        self.network_globals.peers.write().decay_reputations(
+            self.reputation_config.decay_per_heartbeat,
+            Duration::from_secs(HEARTBEAT_INTERVAL),
+            now,
+        );
 
-        for (id, info) in pdb._peers_mut() {
-            // Update reputations
-            match info.connection_status {
-                Connected { .. } => {
-                    // Connected peers gain reputation by sending useful messages
-                }
-                Disconnected { since } | Banned { since } => {
-                    // For disconnected peers, lower their reputation by 1 for every hour they
-                    // stay disconnected. This helps us slowly forget disconnected peers.
-                    // In the same way, slowly allow banned peers back again.
-                    let dc_hours = now
-                        .checked_duration_since(since)
-                        .unwrap_or_else(|| Duration::from_secs(0))
-                        .as_secs()
-                        / 3600;
-                    let last_dc_hours = self
-                        ._last_updated
-                        .checked_duration_since(since)
-                        .unwrap_or_else(|| Duration::from_secs(0))
-                        .as_secs()
-                        / 3600;
-                    if dc_hours > last_dc_hours {
-                        // this should be 1 most of the time
-                        let rep_dif = (dc_hours - last_dc_hours)
-                            .try_into()
-                            .unwrap_or(Rep::max_value());
-
-                        info.reputation = if info.connection_status.is_banned() {
-                            info.reputation.saturating_add(rep_dif)
-                        } else {
-                            info.reputation.saturating_sub(rep_dif)
-                        };
-                    }
-                }
-                Dialing { since } => {
-                    // A peer shouldn't be dialing for more than 2 minutes
-                    if since.elapsed().as_secs() > 120 {
-                        warn!(self.log,"Peer has been dialing for too long"; "peer_id" => id.to_string());
-                        // TODO: decide how to handle this
-                    }
+        let ban_duration = Duration::from_secs(BAN_DURATION);
+        let to_unban = self
+            .reputation_bans
+            .iter()
+            .filter(|(_, banned_at)| now.duration_since(banned_at) >= ban_duration)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect::<Vec<_>>();
+        for peer_id in to_unban {
+            debug!(self.log, "Peer ban expired"; "peer_id" => peer_id.to_string());
+            self.reputation_bans.remove(&peer_id);
+            {
+                let mut peerdb = self.network_globals.peers.write();
+                // peers permanently banned by the operator remain banned
+                if !peerdb.unban(&peer_id) {
+                    continue;
                 }
-                Unknown => {} //TODO: Handle this case
-            }
-            // Check if the peer gets banned or unbanned and if it should be disconnected
-            if info.reputation < _MIN_REP_BEFORE_BAN && !info.connection_status.is_banned() {
-                // This peer gets banned. Check if we should request disconnection
-                ban_queue.push(id.clone());
-            } else if info.reputation >= _MIN_REP_BEFORE_BAN && info.connection_status.is_banned() {
-                // This peer gets unbanned
-                unban_queue.push(id.clone());
+                peerdb.set_reputation(&peer_id, MIN_REP_BEFORE_BAN);
             }
+            self.events.push(PeerManagerEvent::UnbanPeer(peer_id));
         }
 
-        for id in ban_queue {
-            pdb.ban(&id);
+        let to_ban = self
+            .network_globals
+            .peers
+            .read()
+            .peers()
+            .filter(|(_, info)| {
+                info.reputation < MIN_REP_BEFORE_BAN && !info.connection_status.is_banned()
+            })
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect::<Vec<_>>();
+        for peer_id in to_ban {
+            self.ban_peer(&peer_id, now);
+        }
+    }
 
-            self.events
-                .push(PeerManagerEvent::DisconnectPeer(id.clone()));
+    /// Corrects the connection status of peers in the `PeerDB` which disagree with the connections
+    /// of the swarm, in case an event updating the `PeerDB` was missed or mishandled.
+    ///
+    /// Peers recorded as connected which the swarm is not connected to are disconnected. Peers the
+    /// swarm is connected to which are not recorded as connected are connected, unless they are
+    /// banned, in which case they are disconnected again.
+    fn reconcile_connections(&mut self) {
+        let (stale, untracked) = {
+            let peerdb = self.network_globals.peers.read();
+            let stale = peerdb
+                .connected_peer_ids()
+                .filter(|peer_id| !self.swarm_peers.contains_key(peer_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            // peers which are saying goodbye are expected to linger in the swarm until their
+            // connection closes, and peers held until they are admitted to our private network
+            // are not yet connected
+            let untracked = self
+                .swarm_peers
+                .iter()
+                .filter(|(peer_id, _)| {
+                    !peerdb.is_connected(peer_id)
+                        && !self.disconnecting_peers.contains_key(peer_id)
+                        && !self.unverified_connections.contains_key(peer_id)
+                })
+                .map(|(peer_id, ingoing)| (peer_id.clone(), *ingoing, peerdb.peer_banned(peer_id)))
+                .collect::<Vec<_>>();
+            (stale, untracked)
+        };
+
+        for peer_id in stale {
+            warn!(self.log, "Peer recorded as connected is not connected to the swarm"; "peer_id" => peer_id.to_string());
+            metrics::inc_counter_vec(&metrics::PEERDB_SWARM_DISCREPANCIES, &["stale"]);
+            self.notify_disconnect(&peer_id, DisconnectCause::TransportError);
         }
 
-        for id in unban_queue {
-            pdb.disconnect(&id);
+        for (peer_id, ingoing, banned) in untracked {
+            warn!(self.log, "Peer connected to the swarm is not recorded as connected"; "peer_id" => peer_id.to_string(), "banned" => banned);
+            if banned {
+                metrics::inc_counter_vec(&metrics::PEERDB_SWARM_DISCREPANCIES, &["banned"]);
+                self.events.push(PeerManagerEvent::DisconnectPeer(
+                    peer_id,
+                    GoodbyeReason::Fault,
+                ));
+            } else {
+                metrics::inc_counter_vec(&metrics::PEERDB_SWARM_DISCREPANCIES, &["untracked"]);
+                if ingoing {
+                    self.connect_ingoing(&peer_id);
+                } else {
+                    self.connect_outgoing(&peer_id);
+                }
+            }
         }
+    }
 
-        self._last_updated = Instant::now();
-        */
+    /// Bans a peer for `BAN_DURATION`, e.g. because of its reputation, disconnecting it if
+    /// connected.
+    ///
+    /// The ban is recorded in the ban list along with its expiry, so that it is lifted at the same
+    /// time if the node is restarted.
+    fn ban_peer(&mut self, peer_id: &PeerId, now: ReputationTime) {
+        let mut peerdb = self.network_globals.peers.write();
+        let connected = peerdb.is_connected(peer_id);
+        peerdb.ban_for(peer_id, Duration::from_secs(BAN_DURATION));
+        drop(peerdb);
+
+        debug!(self.log, "Banning peer"; "peer_id" => peer_id.to_string());
+        self.reputation_bans.insert(peer_id.clone(), now);
+        if connected {
+            self.events.push(PeerManagerEvent::DisconnectPeer(
+                peer_id.clone(),
+                GoodbyeReason::Fault,
+            ));
+        }
+        self.events.push(PeerManagerEvent::BanPeer(peer_id.clone()));
     }
 
     /// The Peer manager's heartbeat maintains the peer count and maintains peer reputations.
@@ -602,11 +1161,154 @@ impl<TSpec: EthSpec> PeerManager<TSpec> {
             self.discovery.discover_peers();
         }
 
-        // TODO: If we have too many peers, remove peers that are not required for subnet
-        // validation.
+        // If we have too many peers, disconnect those we value least, sparing recently
+        // connected peers and those required for subnets.
+        let connected_peers = self.network_globals.connected_peers();
+        if connected_peers > self.target_peers {
+            let to_prune = self
+                .network_globals
+                .peers
+                .read()
+                .peers_to_prune(connected_peers - self.target_peers, self.min_connection_age);
+            for peer_id in to_prune {
+                debug!(self.log, "Pruning peer"; "peer_id" => peer_id.to_string());
+                self.events.push(PeerManagerEvent::DisconnectPeer(
+                    peer_id,
+                    GoodbyeReason::TooManyPeers,
+                ));
+            }
+        }
+
+        // Check the peers whose membership of our private network was unknown, in case discovery
+        // has since learned their ENR
+        let unverified_peers = self.unverified_peers.keys().cloned().collect::<Vec<_>>();
+        for peer_id in unverified_peers {
+            self.verify_private_network_member(&peer_id);
+        }
 
-        // TODO: Perform peer reputation maintenance here
+        // Re-status quarantined peers more often than others, in case they have reconciled
+        let now = Instant::now();
+        let quarantined_to_status = self
+            .network_globals
+            .peers
+            .read()
+            .quarantined_peers()
+            .filter(|(_, info)| {
+                info.last_status.map_or(true, |last_status| {
+                    now.saturating_duration_since(last_status)
+                        >= Duration::from_secs(QUARANTINE_STATUS_INTERVAL)
+                })
+            })
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect::<Vec<_>>();
+        for peer_id in quarantined_to_status {
+            self.events.push(PeerManagerEvent::Status(peer_id));
+        }
+
+        self.reconcile_connections();
+        self.expire_disconnecting_peers();
+        self.update_reputations(ReputationTime::now());
+
+        self.check_fork_readiness();
+
+        self.network_globals.peers.read().scrape_metrics();
+    }
+
+    /// Forgets the `Goodbye` of peers which are no longer connected to the swarm, or which have
+    /// not disconnected within a heartbeat of it, so that the cause is not misattributed to a
+    /// later connection.
+    fn expire_disconnecting_peers(&mut self) {
+        let swarm_peers = &self.swarm_peers;
+        self.disconnecting_peers
+            .retain(|peer_id, (_, seen_by_heartbeat)| {
+                let keep = !*seen_by_heartbeat && swarm_peers.contains_key(peer_id);
+                *seen_by_heartbeat = true;
+                keep
+            });
     }
+
+    /// Checks whether our connected peers have scheduled the same next fork as us, according to
+    /// their ENRs, so that operators can assess whether the network is ready for the fork.
+    fn check_fork_readiness(&mut self) {
+        // to avoid having a reference to the spec constant we assume FAR_FUTURE_EPOCH is
+        // u64::max_value(), as when updating the ENR
+        let local_fork_id = match self.discovery.local_enr().eth2() {
+            Ok(fork_id) if fork_id.next_fork_epoch != u64::max_value() => fork_id,
+            _ => {
+                metrics::set_float_gauge(&metrics::PEERS_NOT_FOLLOWING_NEXT_FORK, 0.0);
+                return;
+            }
+        };
+
+        let connected_peers = self
+            .network_globals
+            .peers
+            .read()
+            .connected_peer_ids()
+            .cloned()
+            .collect::<Vec<_>>();
+        let (mut following, mut not_following, mut unknown) = (0, 0, 0);
+        for peer_id in &connected_peers {
+            match self
+                .discovery
+                .enr_of_peer(peer_id)
+                .and_then(|enr| enr.eth2().ok())
+            {
+                Some(fork_id) if follows_next_fork(&local_fork_id, &fork_id) => following += 1,
+                Some(_) => not_following += 1,
+                None => unknown += 1,
+            }
+        }
+
+        let known = following + not_following;
+        let not_following_ratio = if known == 0 {
+            0.0
+        } else {
+            not_following as f64 / known as f64
+        };
+        metrics::set_float_gauge(&metrics::PEERS_NOT_FOLLOWING_NEXT_FORK, not_following_ratio);
+
+        let warning_due = self
+            .last_fork_readiness_warning
+            .map_or(true, |last_warning| {
+                last_warning.elapsed() >= Duration::from_secs(FORK_READINESS_WARNING_INTERVAL)
+            });
+        if not_following > 0 && warning_due {
+            warn!(self.log, "Connected peers have not scheduled the next fork";
+                "next_fork_epoch" => local_fork_id.next_fork_epoch.as_u64(),
+                "next_fork_version" => format!("{:?}", local_fork_id.next_fork_version),
+                "not_following" => not_following,
+                "following" => following,
+                "unknown" => unknown,
+            );
+            self.last_fork_readiness_warning = Some(Instant::now());
+        }
+    }
+}
+
+/// Scores a discovered peer by the attestation subnets in its ENR, given the number of our
+/// connected peers on each subnet. Each of its subnets adds `1 / (n + 1)`, where `n` is the number
+/// of our peers on the subnet, so that peers on the subnets we are short of are preferred. A peer
+/// without a valid bitfield scores zero.
+fn subnet_share_score<TSpec: EthSpec>(enr: &Enr, subnet_peers: &[usize]) -> f64 {
+    let bitfield = match enr.bitfield::<TSpec>() {
+        Ok(bitfield) => bitfield,
+        Err(_) => return 0.0,
+    };
+    subnet_peers
+        .iter()
+        .enumerate()
+        .filter(|(subnet_id, _)| bitfield.get(*subnet_id).unwrap_or(false))
+        .map(|(_, peers)| 1.0 / (*peers as f64 + 1.0))
+        .sum()
+}
+
+/// Returns true if a peer with `peer_fork_id` in its ENR is on our current fork and has scheduled
+/// the same next fork as our `local_fork_id`, so that it will follow us through the fork.
+fn follows_next_fork(local_fork_id: &EnrForkId, peer_fork_id: &EnrForkId) -> bool {
+    peer_fork_id.fork_digest == local_fork_id.fork_digest
+        && peer_fork_id.next_fork_version == local_fork_id.next_fork_version
+        && peer_fork_id.next_fork_epoch == local_fork_id.next_fork_epoch
 }
 
 impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
@@ -625,6 +1327,9 @@ impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
                 DiscoveryEvent::QueryResult(min_ttl, peers) => {
                     self.peers_discovered(*peers, min_ttl)
                 }
+                DiscoveryEvent::EnrReceived(peer_id) => {
+                    self.verify_private_network_member(&peer_id)
+                }
             }
         }
 
@@ -632,7 +1337,16 @@ impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
         loop {
             match self.ping_peers.poll_next_unpin(cx) {
                 Poll::Ready(Some(Ok(peer_id))) => {
-                    self.ping_peers.insert(peer_id.clone());
+                    let ping_interval = self
+                        .network_globals
+                        .peers
+                        .write()
+                        .peer_info_mut(&peer_id)
+                        .map_or(DEFAULT_PING_INTERVAL, |info| {
+                            info.ping_stats.ping_sent();
+                            info.ping_stats.interval()
+                        });
+                    self.ping_peers.insert_at(peer_id.clone(), ping_interval);
                     self.events.push(PeerManagerEvent::Ping(peer_id));
                 }
                 Poll::Ready(Some(Err(e))) => {
@@ -655,6 +1369,44 @@ impl<TSpec: EthSpec> Stream for PeerManager<TSpec> {
             }
         }
 
+        loop {
+            match self.unverified_peers.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(peer_id))) => {
+                    if self.private_network_membership(&peer_id) == Membership::Member {
+                        self.admit_private_network_member(&peer_id);
+                        continue;
+                    }
+                    debug!(self.log, "Banning peer whose private network membership is unknown"; "peer_id" => peer_id.to_string());
+                    self.ban_private_network_non_member(&peer_id);
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    error!(self.log, "Failed to check for peers outside the private network"; "error" => format!("{}",e))
+                }
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+
+        loop {
+            match self.handshaking_peers.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(peer_id))) => {
+                    debug!(self.log, "Peer did not send a STATUS after connecting"; "peer_id" => peer_id.to_string());
+                    self.report_peer(
+                        &peer_id,
+                        PeerAction::HighToleranceError,
+                        "No STATUS after connecting",
+                    );
+                    self.events.push(PeerManagerEvent::DisconnectPeer(
+                        peer_id,
+                        GoodbyeReason::Fault,
+                    ));
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    error!(self.log, "Failed to check for peers without a STATUS"; "error" => format!("{}",e))
+                }
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+
         if !self.events.is_empty() {
             return Poll::Ready(Some(self.events.remove(0)));
         } else {
@@ -1,13 +1,18 @@
 use super::client::Client;
+use super::gossip_rejects::GossipRejects;
 use super::peerdb::{Rep, DEFAULT_REPUTATION};
+use super::ping_stats::PingStats;
+use super::range_performance::RangePerformance;
+use super::score::{ReputationTime, ScoreChange};
 use super::PeerSyncStatus;
-use crate::rpc::MetaData;
+use crate::rpc::{GoodbyeReason, MetaData};
 use crate::Multiaddr;
 use serde::{
     ser::{SerializeStructVariant, Serializer},
     Serialize,
 };
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use types::{EthSpec, SubnetId};
 use PeerConnectionStatus::*;
 
@@ -31,10 +36,33 @@ pub struct PeerInfo<T: EthSpec> {
     /// The ENR subnet bitfield of the peer. This may be determined after it's initial
     /// connection.
     pub meta_data: Option<MetaData<T>>,
+    /// How quickly the peer serves our `BlocksByRange` requests.
+    pub range_performance: RangePerformance,
+    /// How reliably the peer answers our pings.
+    pub ping_stats: PingStats,
+    /// The gossip messages from the peer which failed validation.
+    pub gossip_rejects: GossipRejects,
     /// The time we would like to retain this peer. After this time, the peer is no longer
     /// necessary.
     #[serde(skip)]
     pub min_ttl: Option<Instant>,
+    /// The most recent changes to the peer's reputation, oldest first.
+    #[serde(skip)]
+    pub score_history: VecDeque<ScoreChange>,
+    /// The time up to which the peer's reputation has decayed.
+    #[serde(skip)]
+    pub reputation_decayed_to: ReputationTime,
+    /// The time the peer's current connection was established, if it is connected.
+    #[serde(skip)]
+    pub connected_at: Option<Instant>,
+    /// The last time we received a STATUS from the peer, if ever.
+    #[serde(skip)]
+    pub last_status: Option<Instant>,
+    /// Why the peer's last connection ended, if it has disconnected.
+    pub last_disconnect: Option<DisconnectCause>,
+    /// The time the peer's last connection ended.
+    #[serde(skip)]
+    pub last_disconnect_at: Option<Instant>,
 }
 
 impl<TSpec: EthSpec> Default for PeerInfo<TSpec> {
@@ -47,7 +75,16 @@ impl<TSpec: EthSpec> Default for PeerInfo<TSpec> {
             listening_addresses: vec![],
             sync_status: PeerSyncStatus::Unknown,
             meta_data: None,
+            range_performance: RangePerformance::default(),
+            ping_stats: PingStats::default(),
+            gossip_rejects: GossipRejects::default(),
             min_ttl: None,
+            score_history: VecDeque::new(),
+            reputation_decayed_to: ReputationTime::now(),
+            connected_at: None,
+            last_status: None,
+            last_disconnect: None,
+            last_disconnect_at: None,
         }
     }
 }
@@ -57,12 +94,42 @@ impl<T: EthSpec> PeerInfo<T> {
     pub fn on_subnet(&self, subnet_id: SubnetId) -> bool {
         if let Some(meta_data) = &self.meta_data {
             return meta_data
-                .attnets
+                .attnets()
                 .get(*subnet_id as usize)
                 .unwrap_or_else(|_| false);
         }
         false
     }
+
+    /// Returns if the peer is subscribed to the given sync committee subnet. This is only known
+    /// for peers which answered a version 2 METADATA request.
+    pub fn on_sync_subnet(&self, subnet_id: u64) -> bool {
+        self.meta_data
+            .as_ref()
+            .and_then(|meta_data| meta_data.syncnets())
+            .and_then(|syncnets| syncnets.get(subnet_id as usize).ok())
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the peer disconnected from us within `backoff` because it had too many
+    /// peers, in which case it is unlikely to accept another connection.
+    pub fn recently_full(&self, backoff: Duration) -> bool {
+        self.last_disconnect == Some(DisconnectCause::Remote(Some(GoodbyeReason::TooManyPeers)))
+            && self
+                .last_disconnect_at
+                .map_or(false, |at| at.elapsed() < backoff)
+    }
+}
+
+/// Why a connection to a peer ended.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum DisconnectCause {
+    /// We disconnected the peer, for the given reason.
+    Local(GoodbyeReason),
+    /// The peer disconnected from us, with the reason from its `Goodbye` if it sent one.
+    Remote(Option<GoodbyeReason>),
+    /// The connection failed, or could not be established.
+    TransportError,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -178,6 +245,17 @@ impl PeerConnectionStatus {
         }
     }
 
+    /// The name of the status, used to label metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Connected { .. } => "connected",
+            Disconnected { .. } => "disconnected",
+            Banned { .. } => "banned",
+            Dialing { .. } => "dialing",
+            Unknown => "unknown",
+        }
+    }
+
     /// Modifies the status to Connected and increases the number of ingoing
     /// connections by one
     pub fn connect_ingoing(&mut self) {
@@ -1,6 +1,7 @@
 //! Handles individual sync status for peers.
 
 use serde::Serialize;
+use std::time::Instant;
 use types::{Epoch, Hash256, Slot};
 
 #[derive(Clone, Debug, Serialize)]
@@ -14,6 +15,15 @@ pub enum PeerSyncStatus {
     Behind { info: SyncInfo },
     /// Not currently known as a STATUS handshake has not occurred.
     Unknown,
+    /// Has a different finalized block to us at an epoch we have finalized. The peer may be on a
+    /// minority fork rather than malicious, so it is kept but not used for sync until it agrees
+    /// with us again.
+    Quarantined {
+        info: SyncInfo,
+        /// The time the peer was first quarantined.
+        #[serde(skip)]
+        since: Instant,
+    },
 }
 
 /// This is stored inside the PeerSyncStatus and is very similar to `PeerSyncInfo` in the
@@ -51,6 +61,14 @@ impl PeerSyncStatus {
         }
     }
 
+    /// Returns true if the peer disagrees with our finalized chain.
+    pub fn is_quarantined(&self) -> bool {
+        match self {
+            PeerSyncStatus::Quarantined { .. } => true,
+            _ => false,
+        }
+    }
+
     /// Updates the sync state given a fully synced peer.
     /// Returns true if the state has changed.
     pub fn update_synced(&mut self, info: SyncInfo) -> bool {
@@ -1,10 +1,13 @@
-use super::peer_info::{PeerConnectionStatus, PeerInfo};
-use super::peer_sync_status::PeerSyncStatus;
+use super::ban_list::BanList;
+use super::peer_info::{DisconnectCause, PeerConnectionStatus, PeerInfo};
+use super::peer_sync_status::{PeerSyncStatus, SyncInfo};
+use super::score::{ReputationTime, ScoreChange, MAX_SCORE_HISTORY};
+use crate::metrics;
 use crate::rpc::methods::MetaData;
 use crate::PeerId;
 use slog::{crit, debug, trace, warn};
 use std::collections::{hash_map::Entry, HashMap};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use types::{EthSpec, SubnetId};
 
 /// A peer's reputation (perceived potential usefulness)
@@ -16,9 +19,14 @@ pub struct RepChange {
     diff: Rep,
 }
 
-/// Max number of disconnected nodes to remember
+/// Max number of disconnected nodes to remember. Beyond this, the disconnected peers which were
+/// seen least recently are forgotten.
 const MAX_DC_PEERS: usize = 30;
 
+/// Max number of banned nodes to remember. Beyond this, the peers which were banned least
+/// recently are forgotten, starting with those which are not in the ban list.
+const MAX_BANNED_PEERS: usize = 1000;
+
 /// The default starting reputation for an unknown peer.
 pub const DEFAULT_REPUTATION: Rep = 50;
 
@@ -28,6 +36,8 @@ pub struct PeerDB<TSpec: EthSpec> {
     peers: HashMap<PeerId, PeerInfo<TSpec>>,
     /// Tracking of number of disconnected nodes
     n_dc: usize,
+    /// The peers, IP addresses and subnets we refuse to connect to.
+    ban_list: BanList,
     /// PeerDB's logger
     log: slog::Logger,
 }
@@ -58,6 +68,7 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         Self {
             log: log.clone(),
             n_dc: 0,
+            ban_list: BanList::default(),
             peers: HashMap::new(),
         }
     }
@@ -106,14 +117,19 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         }
     }
 
-    /// Returns true if the Peer is banned.
+    /// Returns true if the Peer is banned, or is in the ban list.
     pub fn peer_banned(&self, peer_id: &PeerId) -> bool {
         match self.peers.get(peer_id).map(|info| &info.connection_status) {
             Some(status) => status.is_banned(),
-            None => false,
+            None => self.ban_list.contains_peer(peer_id),
         }
     }
 
+    /// Returns the list of banned peers, IP addresses and subnets.
+    pub fn ban_list(&self) -> &BanList {
+        &self.ban_list
+    }
+
     /// Gives the ids of all known connected peers.
     pub fn connected_peers(&self) -> impl Iterator<Item = (&PeerId, &PeerInfo<TSpec>)> {
         self.peers
@@ -152,6 +168,12 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
             .map(|(peer_id, _)| peer_id)
     }
 
+    /// Gives the ids and info of all connected peers which are quarantined.
+    pub fn quarantined_peers(&self) -> impl Iterator<Item = (&PeerId, &PeerInfo<TSpec>)> {
+        self.connected_peers()
+            .filter(|(_, info)| info.sync_status.is_quarantined())
+    }
+
     /// Gives an iterator of all peers on a given subnet.
     pub fn peers_on_subnet(&self, subnet_id: SubnetId) -> impl Iterator<Item = &PeerId> {
         self.peers
@@ -193,6 +215,37 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         by_status
     }
 
+    /// Returns up to `count` connected peers to disconnect when we have too many peers, in the
+    /// order they should be disconnected.
+    ///
+    /// Peers connected for less than `min_connection_age` and peers required for a subnet (see
+    /// `min_ttl`) are never selected. Quarantined peers are preferred, then peers that have never
+    /// sent us a STATUS, followed by those with the lowest reputation.
+    pub fn peers_to_prune(&self, count: usize, min_connection_age: Duration) -> Vec<PeerId> {
+        let now = Instant::now();
+        let mut candidates = self
+            .connected_peers()
+            .filter(|(_, info)| {
+                info.connected_at.map_or(true, |connected_at| {
+                    now.saturating_duration_since(connected_at) >= min_connection_age
+                }) && info.min_ttl.map_or(true, |min_ttl| min_ttl <= now)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, info)| {
+            (
+                !info.sync_status.is_quarantined(),
+                info.last_status.is_some(),
+                info.reputation,
+            )
+        });
+
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect()
+    }
+
     /// Returns the peer with highest reputation that satisfies `is_status`
     pub fn best_by_status<F>(&self, is_status: F) -> Option<&PeerId>
     where
@@ -286,6 +339,9 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         if info.connection_status.is_disconnected() {
             self.n_dc = self.n_dc.saturating_sub(1);
         }
+        if !info.connection_status.is_connected() {
+            info.connected_at = Some(Instant::now());
+        }
         info.connection_status.connect_ingoing();
     }
 
@@ -296,9 +352,19 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         if info.connection_status.is_disconnected() {
             self.n_dc = self.n_dc.saturating_sub(1);
         }
+        if !info.connection_status.is_connected() {
+            info.connected_at = Some(Instant::now());
+        }
         info.connection_status.connect_outgoing();
     }
 
+    /// Records that a STATUS has been received from the peer.
+    pub fn status_received(&mut self, peer_id: &PeerId) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.last_status = Some(Instant::now());
+        }
+    }
+
     /// Sets the peer as disconnected. A banned peer remains banned
     pub fn disconnect(&mut self, peer_id: &PeerId) {
         let log_ref = &self.log;
@@ -311,27 +377,73 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
             info.connection_status.disconnect();
             self.n_dc += 1;
         }
+        info.connected_at = None;
+        info.ping_stats.disconnected();
         self.shrink_to_fit();
     }
 
-    /// Drops the peers with the lowest reputation so that the number of
-    /// disconnected peers is less than MAX_DC_PEERS
+    /// Records why the last connection to a peer ended.
+    pub fn record_disconnect(&mut self, peer_id: &PeerId, cause: DisconnectCause) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.last_disconnect = Some(cause);
+            info.last_disconnect_at = Some(Instant::now());
+        }
+    }
+
+    /// Records that a peer has been seen, e.g. returned by a discovery query. If the peer is
+    /// disconnected, this delays it being dropped by `shrink_to_fit`.
+    pub fn peer_seen(&mut self, peer_id: &PeerId) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            if let PeerConnectionStatus::Disconnected { since } = &mut info.connection_status {
+                *since = Instant::now();
+            }
+        }
+    }
+
+    /// Drops the disconnected peers which were seen least recently so that the number of
+    /// disconnected peers is at most MAX_DC_PEERS, and the peers which were banned least recently
+    /// so that the number of banned peers is at most MAX_BANNED_PEERS.
+    ///
+    /// Banned peers which are not in the ban list are dropped first. A dropped peer which is in
+    /// the ban list remains banned.
     pub fn shrink_to_fit(&mut self) {
         // for caution, but the difference should never be > 1
         while self.n_dc > MAX_DC_PEERS {
             let to_drop = self
                 .peers
                 .iter()
-                .filter(|(_, info)| info.connection_status.is_disconnected())
-                .min_by_key(|(_, info)| info.reputation)
+                .filter_map(|(id, info)| match info.connection_status {
+                    PeerConnectionStatus::Disconnected { since } => Some((id, since)),
+                    _ => None,
+                })
+                .min_by_key(|(_, since)| *since)
                 .map(|(id, _)| id.clone())
                 .unwrap(); // should be safe since n_dc > MAX_DC_PEERS > 0
             self.peers.remove(&to_drop);
             self.n_dc = self.n_dc.saturating_sub(1);
         }
+
+        let ban_list = &self.ban_list;
+        let mut banned = self
+            .peers
+            .iter()
+            .filter_map(|(id, info)| match info.connection_status {
+                PeerConnectionStatus::Banned { since } => {
+                    Some((ban_list.contains_peer(id), since, id.clone()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if banned.len() > MAX_BANNED_PEERS {
+            banned.sort_by_key(|(in_ban_list, since, _)| (*in_ban_list, *since));
+            let excess = banned.len() - MAX_BANNED_PEERS;
+            for (_, _, to_drop) in banned.into_iter().take(excess) {
+                self.peers.remove(&to_drop);
+            }
+        }
     }
 
-    /// Sets a peer as banned
+    /// Sets a peer as banned for the lifetime of the `PeerDB`. The ban is not added to the ban list.
     pub fn ban(&mut self, peer_id: &PeerId) {
         let log_ref = &self.log;
         let info = self.peers.entry(peer_id.clone()).or_insert_with(|| {
@@ -343,6 +455,68 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
             self.n_dc = self.n_dc.saturating_sub(1);
         }
         info.connection_status.ban();
+        self.shrink_to_fit();
+    }
+
+    /// Sets a peer as banned and adds it to the ban list, along with the time its ban expires.
+    ///
+    /// The ban is not lifted automatically, `Self::unban` must be called once `ban_duration` has
+    /// passed.
+    pub fn ban_for(&mut self, peer_id: &PeerId, ban_duration: Duration) {
+        if let Err(e) = self.ban_list.add_peer(peer_id, ban_duration) {
+            warn!(self.log, "Failed to update the ban list"; "error" => e);
+        }
+
+        self.ban(peer_id);
+    }
+
+    /// Sets a banned peer as disconnected and removes its expiring ban from the ban list.
+    ///
+    /// A peer permanently banned by the ban list remains banned. Returns `true` if the peer is no
+    /// longer banned.
+    pub fn unban(&mut self, peer_id: &PeerId) -> bool {
+        if let Err(e) = self.ban_list.remove_peer(peer_id) {
+            warn!(self.log, "Failed to update the ban list"; "error" => e);
+        }
+        if self.ban_list.contains_peer(peer_id) {
+            return false;
+        }
+
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            if info.connection_status.is_banned() {
+                info.connection_status.disconnect();
+                self.n_dc += 1;
+            }
+        }
+        self.shrink_to_fit();
+        true
+    }
+
+    /// Replaces the ban list, banning every peer in the new list.
+    pub fn set_ban_list(&mut self, ban_list: BanList) {
+        for peer_id in ban_list.peers() {
+            let info = self.peers.entry(peer_id.clone()).or_default();
+            if info.connection_status.is_disconnected() {
+                self.n_dc = self.n_dc.saturating_sub(1);
+            }
+            info.connection_status.ban();
+        }
+        self.ban_list = ban_list;
+        self.shrink_to_fit();
+    }
+
+    /// Updates the gauges of the number of stored peers for each connection status.
+    pub fn scrape_metrics(&self) {
+        let mut counts: HashMap<&'static str, i64> = HashMap::new();
+        for info in self.peers.values() {
+            *counts.entry(info.connection_status.as_str()).or_default() += 1;
+        }
+
+        for status in &["connected", "dialing", "disconnected", "banned", "unknown"] {
+            if let Some(gauge) = metrics::get_int_gauge(&metrics::PEERDB_RECORDS, &[status]) {
+                gauge.set(counts.get(status).copied().unwrap_or(0));
+            }
+        }
     }
 
     /// Add the meta data of a peer.
@@ -355,9 +529,11 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
     }
 
     /// Sets the reputation of peer.
-    #[allow(dead_code)]
     pub(super) fn set_reputation(&mut self, peer_id: &PeerId, rep: Rep) {
         if let Some(peer_info) = self.peers.get_mut(peer_id) {
+            if peer_info.reputation >= DEFAULT_REPUTATION {
+                peer_info.reputation_decayed_to = ReputationTime::now();
+            }
             peer_info.reputation = rep;
         } else {
             crit!(self.log, "Tried to modify reputation for an unknown peer"; "peer_id" => peer_id.to_string());
@@ -373,9 +549,39 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
         }
     }
 
+    /// Quarantines a peer whose STATUS conflicts with our finalized chain.
+    ///
+    /// Returns the time the peer was first quarantined, which is kept if the peer was already
+    /// quarantined, or `None` if the peer is unknown.
+    pub fn quarantine(&mut self, peer_id: &PeerId, info: SyncInfo) -> Option<Instant> {
+        let peer_info = self.peers.get_mut(peer_id)?;
+        let since = match peer_info.sync_status {
+            PeerSyncStatus::Quarantined { since, .. } => since,
+            _ => Instant::now(),
+        };
+        peer_info.sync_status = PeerSyncStatus::Quarantined { info, since };
+        Some(since)
+    }
+
+    /// Releases a peer from quarantine, e.g. once its STATUS agrees with our finalized chain.
+    /// Its sync status becomes unknown until it is next determined.
+    ///
+    /// Returns true if the peer was quarantined.
+    pub fn end_quarantine(&mut self, peer_id: &PeerId) -> bool {
+        match self.peers.get_mut(peer_id) {
+            Some(peer_info) if peer_info.sync_status.is_quarantined() => {
+                peer_info.sync_status = PeerSyncStatus::Unknown;
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Adds to a peer's reputation by `change`. If the reputation exceeds Rep's
     /// upper (lower) bounds, it stays at the maximum (minimum) value.
-    pub(super) fn add_reputation(&mut self, peer_id: &PeerId, change: RepChange) {
+    ///
+    /// The change is recorded in the peer's score history along with the `reason`.
+    pub(super) fn add_reputation(&mut self, peer_id: &PeerId, change: RepChange, reason: String) {
         let log_ref = &self.log;
         let info = match self.peers.entry(peer_id.clone()) {
             Entry::Vacant(_) => {
@@ -386,19 +592,80 @@ impl<TSpec: EthSpec> PeerDB<TSpec> {
             Entry::Occupied(e) => e.into_mut(),
         };
 
+        let previous = info.reputation;
+        let time = ReputationTime::now();
+        // a reputation only decays from the time it falls below the default
+        if previous >= DEFAULT_REPUTATION {
+            info.reputation_decayed_to = time;
+        }
         info.reputation = if change.is_good {
             info.reputation.saturating_add(change.diff)
         } else {
             info.reputation.saturating_sub(change.diff)
         };
+
+        if info.score_history.len() >= MAX_SCORE_HISTORY {
+            info.score_history.pop_front();
+        }
+        info.score_history.push_back(ScoreChange {
+            reason,
+            change: i16::from(info.reputation) - i16::from(previous),
+            reputation: info.reputation,
+            time,
+        });
+    }
+
+    /// Moves the reputation of every peer below `DEFAULT_REPUTATION` up by `decay` for each
+    /// `interval` that has passed since its reputation last decayed, without exceeding
+    /// `DEFAULT_REPUTATION`. Banned peers are not affected.
+    ///
+    /// The remainder of an `interval` is carried over to the next call, so the decay only depends
+    /// on the time that has passed and not on how often this is called.
+    ///
+    /// Decay is not recorded in the score history.
+    pub(super) fn decay_reputations(
+        &mut self,
+        decay: Rep,
+        interval: Duration,
+        now: ReputationTime,
+    ) {
+        let interval_nanos = interval.as_nanos().max(1);
+        for info in self.peers.values_mut() {
+            if info.reputation >= DEFAULT_REPUTATION
+                || info.connection_status.is_banned()
+                || decay == 0
+            {
+                info.reputation_decayed_to = now;
+                continue;
+            }
+
+            let elapsed = now.duration_since(&info.reputation_decayed_to);
+            let intervals = elapsed.as_nanos() / interval_nanos;
+            let total_decay = intervals
+                .saturating_mul(u128::from(decay))
+                .min(u128::from(Rep::max_value())) as Rep;
+            info.reputation = info
+                .reputation
+                .saturating_add(total_decay)
+                .min(DEFAULT_REPUTATION);
+
+            if info.reputation >= DEFAULT_REPUTATION {
+                info.reputation_decayed_to = now;
+            } else {
+                let remainder = Duration::from_nanos((elapsed.as_nanos() % interval_nanos) as u64);
+                info.reputation_decayed_to =
+                    info.reputation_decayed_to.advance(elapsed - remainder);
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck_macros::quickcheck;
     use slog::{o, Drain};
-    use types::MinimalEthSpec;
+    use types::{Epoch, Hash256, MinimalEthSpec, Slot};
     type M = MinimalEthSpec;
 
     pub fn build_log(level: slog::Level, enabled: bool) -> slog::Logger {
@@ -474,23 +741,121 @@ mod tests {
         let random_peer = PeerId::random();
         let change = RepChange::good(0);
         pdb.connect_ingoing(&random_peer);
-        pdb.add_reputation(&random_peer, change);
+        pdb.add_reputation(&random_peer, change, "test".into());
         assert_eq!(pdb.reputation(&random_peer), DEFAULT_REPUTATION);
 
         // overflowing change is capped
         let random_peer = PeerId::random();
         let change = RepChange::worst();
         pdb.connect_ingoing(&random_peer);
-        pdb.add_reputation(&random_peer, change);
+        pdb.add_reputation(&random_peer, change, "test".into());
         assert_eq!(pdb.reputation(&random_peer), Rep::min_value());
 
         let random_peer = PeerId::random();
         let change = RepChange::good(Rep::max_value());
         pdb.connect_ingoing(&random_peer);
-        pdb.add_reputation(&random_peer, change);
+        pdb.add_reputation(&random_peer, change, "test".into());
         assert_eq!(pdb.reputation(&random_peer), Rep::max_value());
     }
 
+    #[test]
+    fn test_score_history() {
+        let mut pdb = get_db();
+        let random_peer = PeerId::random();
+        pdb.connect_ingoing(&random_peer);
+
+        pdb.add_reputation(&random_peer, RepChange::bad(20), "first".into());
+        pdb.add_reputation(&random_peer, RepChange::worst(), "second".into());
+
+        let history = &pdb.peer_info(&random_peer).unwrap().score_history;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].reason, "first");
+        assert_eq!(history[0].change, -20);
+        assert_eq!(history[0].reputation, DEFAULT_REPUTATION - 20);
+        // the recorded change is bounded by the reputation
+        assert_eq!(history[1].change, -i16::from(DEFAULT_REPUTATION - 20));
+        assert_eq!(history[1].reputation, Rep::min_value());
+
+        // only the most recent changes are kept
+        for i in 0..MAX_SCORE_HISTORY {
+            pdb.add_reputation(&random_peer, RepChange::good(1), i.to_string());
+        }
+        let history = &pdb.peer_info(&random_peer).unwrap().score_history;
+        assert_eq!(history.len(), MAX_SCORE_HISTORY);
+        assert_eq!(history[0].reason, "0");
+    }
+
+    #[test]
+    fn test_reputation_decay() {
+        let mut pdb = get_db();
+
+        let (low, high, banned) = (PeerId::random(), PeerId::random(), PeerId::random());
+        pdb.connect_ingoing(&low);
+        pdb.connect_ingoing(&high);
+        pdb.connect_ingoing(&banned);
+        pdb.set_reputation(&low, DEFAULT_REPUTATION - 3);
+        pdb.set_reputation(&high, DEFAULT_REPUTATION + 3);
+        pdb.set_reputation(&banned, 0);
+        pdb.ban(&banned);
+
+        let interval = Duration::from_secs(30);
+        let start = pdb.peer_info(&low).unwrap().reputation_decayed_to;
+
+        // no decay until a whole interval has passed
+        pdb.decay_reputations(2, interval, start.advance(interval / 2));
+        assert_eq!(pdb.reputation(&low), DEFAULT_REPUTATION - 3);
+
+        pdb.decay_reputations(2, interval, start.advance(interval));
+        assert_eq!(pdb.reputation(&low), DEFAULT_REPUTATION - 1);
+        assert_eq!(pdb.reputation(&high), DEFAULT_REPUTATION + 3);
+        assert_eq!(pdb.reputation(&banned), 0);
+
+        // decay stops at the default reputation
+        pdb.decay_reputations(2, interval, start.advance(interval * 2));
+        assert_eq!(pdb.reputation(&low), DEFAULT_REPUTATION);
+    }
+
+    #[test]
+    fn test_reputation_decay_after_suspend() {
+        let mut pdb = get_db();
+
+        let peer = PeerId::random();
+        pdb.connect_ingoing(&peer);
+        pdb.set_reputation(&peer, DEFAULT_REPUTATION - 10);
+
+        // a single heartbeat after a long pause decays as much as the missed heartbeats would have
+        let interval = Duration::from_secs(30);
+        let start = pdb.peer_info(&peer).unwrap().reputation_decayed_to;
+        pdb.decay_reputations(1, interval, start.advance(interval * 4));
+        assert_eq!(pdb.reputation(&peer), DEFAULT_REPUTATION - 6);
+    }
+
+    #[quickcheck]
+    fn quickcheck_decay_is_independent_of_heartbeats(
+        reputation: Rep,
+        decay: Rep,
+        heartbeats: Vec<u16>,
+    ) -> bool {
+        let interval = Duration::from_secs(30);
+        let (mut stepped, mut single) = (get_db(), get_db());
+        let peer = PeerId::random();
+        for pdb in &mut [&mut stepped, &mut single] {
+            pdb.connect_ingoing(&peer);
+            pdb.set_reputation(&peer, reputation);
+        }
+
+        let start = stepped.peer_info(&peer).unwrap().reputation_decayed_to;
+        let mut now = start;
+        for secs in heartbeats {
+            now = now.advance(Duration::from_secs(secs.into()));
+            stepped.decay_reputations(decay, interval, now);
+        }
+        single.peers.get_mut(&peer).unwrap().reputation_decayed_to = start;
+        single.decay_reputations(decay, interval, now);
+
+        stepped.reputation(&peer) == single.reputation(&peer)
+    }
+
     #[test]
     fn test_disconnected_are_bounded() {
         let mut pdb = get_db();
@@ -508,6 +873,111 @@ mod tests {
         assert_eq!(pdb.n_dc, MAX_DC_PEERS);
     }
 
+    #[test]
+    fn test_least_recently_seen_are_dropped() {
+        let mut pdb = get_db();
+
+        let peers = (0..MAX_DC_PEERS + 1)
+            .map(|_| PeerId::random())
+            .collect::<Vec<_>>();
+        for p in &peers {
+            pdb.connect_ingoing(p);
+        }
+        for p in &peers[..MAX_DC_PEERS] {
+            pdb.disconnect(p);
+        }
+
+        // the first two peers were seen long ago, but the second has since been rediscovered
+        for p in &peers[..2] {
+            pdb.peer_info_mut(p).unwrap().connection_status = PeerConnectionStatus::Disconnected {
+                since: Instant::now() - Duration::from_secs(60),
+            };
+        }
+        pdb.peer_seen(&peers[1]);
+
+        pdb.disconnect(&peers[MAX_DC_PEERS]);
+        assert_eq!(pdb.n_dc, MAX_DC_PEERS);
+        assert!(pdb.peer_info(&peers[0]).is_none());
+        assert!(pdb.peer_info(&peers[1]).is_some());
+    }
+
+    #[test]
+    fn test_banned_are_not_dropped() {
+        let mut pdb = get_db();
+
+        let banned = PeerId::random();
+        pdb.connect_ingoing(&banned);
+        pdb.ban(&banned);
+
+        for _ in 0..MAX_DC_PEERS + 1 {
+            let p = PeerId::random();
+            pdb.connect_ingoing(&p);
+            pdb.disconnect(&p);
+        }
+
+        assert_eq!(pdb.n_dc, MAX_DC_PEERS);
+        assert!(pdb.peer_banned(&banned));
+    }
+
+    #[test]
+    fn test_banned_are_bounded() {
+        let mut pdb = get_db();
+
+        // the two oldest bans are of a peer in the ban list and a peer which is not
+        let listed = PeerId::random();
+        let unlisted = PeerId::random();
+        pdb.ban_for(&listed, Duration::from_secs(3600));
+        pdb.ban(&unlisted);
+        for p in &[&listed, &unlisted] {
+            pdb.peer_info_mut(p).unwrap().connection_status = PeerConnectionStatus::Banned {
+                since: Instant::now() - Duration::from_secs(60),
+            };
+        }
+
+        for _ in 0..MAX_BANNED_PEERS - 1 {
+            pdb.ban(&PeerId::random());
+        }
+
+        assert_eq!(pdb.banned_peers().count(), MAX_BANNED_PEERS);
+        assert!(pdb.peer_info(&unlisted).is_none());
+        assert!(!pdb.peer_banned(&unlisted));
+        assert!(pdb.peer_banned(&listed));
+
+        // peers in the ban list are dropped once no others are left, but remain banned
+        for p in pdb.banned_peers().cloned().collect::<Vec<_>>() {
+            if p != listed {
+                pdb.ban_for(&p, Duration::from_secs(3600));
+            }
+        }
+        pdb.ban_for(&PeerId::random(), Duration::from_secs(3600));
+        assert_eq!(pdb.banned_peers().count(), MAX_BANNED_PEERS);
+        assert!(pdb.peer_info(&listed).is_none());
+        assert!(pdb.peer_banned(&listed));
+    }
+
+    #[test]
+    fn test_unban_keeps_permanent_bans() {
+        let dir = tempdir::TempDir::new("peerdb").unwrap();
+        let file = dir.path().join("ban_list.txt");
+        let permanent = PeerId::random();
+        std::fs::write(&file, format!("{}\n", permanent)).unwrap();
+
+        let mut pdb = get_db();
+        pdb.set_ban_list(BanList::load(file).unwrap());
+
+        let expiring = PeerId::random();
+        pdb.ban_for(&permanent, Duration::from_secs(3600));
+        pdb.ban_for(&expiring, Duration::from_secs(3600));
+
+        assert!(!pdb.unban(&permanent));
+        assert!(pdb.peer_banned(&permanent));
+        assert!(pdb.ban_list().contains_peer(&permanent));
+
+        assert!(pdb.unban(&expiring));
+        assert!(!pdb.peer_banned(&expiring));
+        assert!(!pdb.ban_list().contains_peer(&expiring));
+    }
+
     #[test]
     fn test_best_peers() {
         let mut pdb = get_db();
@@ -528,6 +998,95 @@ mod tests {
             .eq(best_peers.into_iter().map(|p| p.0)));
     }
 
+    #[test]
+    fn test_peers_to_prune_ordering() {
+        let mut pdb = get_db();
+
+        let statusd_low = PeerId::random();
+        let statusd_high = PeerId::random();
+        let no_status_low = PeerId::random();
+        let no_status_high = PeerId::random();
+        for peer in &[&statusd_low, &statusd_high, &no_status_low, &no_status_high] {
+            pdb.connect_ingoing(peer);
+        }
+        pdb.status_received(&statusd_low);
+        pdb.status_received(&statusd_high);
+        pdb.set_reputation(&statusd_low, 10);
+        pdb.set_reputation(&statusd_high, 90);
+        pdb.set_reputation(&no_status_low, 20);
+        pdb.set_reputation(&no_status_high, 80);
+
+        // peers without a STATUS come first, then the lowest reputation
+        assert_eq!(
+            pdb.peers_to_prune(4, Duration::from_secs(0)),
+            vec![no_status_low, no_status_high, statusd_low, statusd_high]
+        );
+        assert_eq!(pdb.peers_to_prune(1, Duration::from_secs(0)).len(), 1);
+    }
+
+    #[test]
+    fn test_quarantine() {
+        let mut pdb = get_db();
+
+        let peer = PeerId::random();
+        let other_peer = PeerId::random();
+        pdb.connect_ingoing(&peer);
+        pdb.connect_ingoing(&other_peer);
+        pdb.status_received(&peer);
+        pdb.status_received(&other_peer);
+        pdb.set_reputation(&other_peer, 10);
+
+        let info = || SyncInfo {
+            status_head_slot: Slot::new(0),
+            status_head_root: Hash256::zero(),
+            status_finalized_epoch: Epoch::new(0),
+            status_finalized_root: Hash256::zero(),
+        };
+        assert_eq!(pdb.quarantine(&PeerId::random(), info()), None);
+        let since = pdb.quarantine(&peer, info()).unwrap();
+        // quarantining again keeps the original time
+        assert_eq!(pdb.quarantine(&peer, info()), Some(since));
+        assert_eq!(pdb.quarantined_peers().count(), 1);
+
+        // quarantined peers are pruned first
+        assert_eq!(
+            pdb.peers_to_prune(1, Duration::from_secs(0)),
+            vec![peer.clone()]
+        );
+
+        assert!(pdb.end_quarantine(&peer));
+        assert!(!pdb.end_quarantine(&peer));
+        assert_eq!(pdb.quarantined_peers().count(), 0);
+        assert!(!pdb.peer_synced(&peer));
+    }
+
+    #[test]
+    fn test_peers_to_prune_min_age() {
+        let mut pdb = get_db();
+
+        let old_peer = PeerId::random();
+        let new_peer = PeerId::random();
+        let required_peer = PeerId::random();
+        pdb.connect_ingoing(&old_peer);
+        pdb.connect_ingoing(&new_peer);
+        pdb.connect_ingoing(&required_peer);
+
+        let an_hour_ago = Instant::now() - Duration::from_secs(3600);
+        pdb.peer_info_mut(&old_peer).unwrap().connected_at = Some(an_hour_ago);
+        pdb.peer_info_mut(&required_peer).unwrap().connected_at = Some(an_hour_ago);
+        pdb.update_min_ttl(&required_peer, Instant::now() + Duration::from_secs(3600));
+
+        // recently connected and required peers are never pruned
+        assert_eq!(
+            pdb.peers_to_prune(3, Duration::from_secs(60)),
+            vec![old_peer]
+        );
+
+        // a disconnected peer loses its connection time
+        pdb.disconnect(&new_peer);
+        assert!(pdb.peer_info(&new_peer).unwrap().connected_at.is_none());
+    }
+
     #[test]
     fn test_the_best_peer() {
         let mut pdb = get_db();
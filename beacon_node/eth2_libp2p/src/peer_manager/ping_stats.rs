@@ -0,0 +1,168 @@
+//! Measures the round trip time of our pings to a peer and the pongs it misses, so that stable
+//! peers are pinged less often and flaky peers more often.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// The interval between pings to a peer we know nothing about.
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
+/// The shortest interval between pings, to which the interval falls after repeated missed pongs.
+const MIN_PING_INTERVAL: Duration = Duration::from_secs(10);
+/// The longest interval between pings, to which the interval grows for stable peers.
+const MAX_PING_INTERVAL: Duration = Duration::from_secs(120);
+/// The number of consecutive stable pongs after which the interval is doubled.
+const STABLE_PONGS_BEFORE_BACKOFF: u8 = 3;
+/// A round trip time which differs from the average by more than this fraction of it is not
+/// stable.
+const MAX_RTT_DEVIATION: f64 = 0.5;
+/// The weight of the newest round trip time in the moving average.
+const SMOOTHING_FACTOR: f64 = 0.3;
+
+/// The measured responsiveness of a peer to our pings.
+#[derive(Clone, Debug, Serialize)]
+pub struct PingStats {
+    /// A moving average of the round trip time of our pings, in milliseconds.
+    pub rtt_millis: Option<f64>,
+    /// The number of our pings which were not answered before the next was due.
+    pub missed_pongs: u64,
+    /// The number of consecutive stable pongs since the interval last grew.
+    pub stable_pongs: u8,
+    /// The interval between our pings to the peer, in seconds.
+    pub interval_secs: u64,
+    /// The time our unanswered ping was sent, if there is one.
+    #[serde(skip)]
+    ping_sent: Option<Instant>,
+}
+
+impl Default for PingStats {
+    fn default() -> Self {
+        PingStats {
+            rtt_millis: None,
+            missed_pongs: 0,
+            stable_pongs: 0,
+            interval_secs: DEFAULT_PING_INTERVAL.as_secs(),
+            ping_sent: None,
+        }
+    }
+}
+
+impl PingStats {
+    /// The interval after which the peer should next be pinged.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    /// A ping is being sent to the peer. If the previous ping is still unanswered, its pong is
+    /// considered missed and the interval is halved.
+    pub fn ping_sent(&mut self) {
+        if self.ping_sent.replace(Instant::now()).is_some() {
+            self.record_miss();
+        }
+    }
+
+    /// A pong has been received from the peer. Pongs to pings we did not send are ignored.
+    pub fn pong_received(&mut self) {
+        if let Some(sent) = self.ping_sent.take() {
+            self.record_rtt(sent.elapsed());
+        }
+    }
+
+    /// The connection to the peer has ended, so the answer to any outstanding ping is not missed.
+    pub fn disconnected(&mut self) {
+        self.ping_sent = None;
+    }
+
+    fn record_miss(&mut self) {
+        self.missed_pongs += 1;
+        self.stable_pongs = 0;
+        self.set_interval(self.interval() / 2);
+    }
+
+    fn record_rtt(&mut self, rtt: Duration) {
+        let rtt = rtt.as_millis() as f64;
+        let is_stable = self.rtt_millis.map_or(true, |average| {
+            (rtt - average).abs() <= average * MAX_RTT_DEVIATION
+        });
+        self.rtt_millis = Some(
+            self.rtt_millis
+                .map_or(rtt, |average| average + SMOOTHING_FACTOR * (rtt - average)),
+        );
+
+        if !is_stable {
+            self.stable_pongs = 0;
+            return;
+        }
+
+        self.stable_pongs += 1;
+        if self.stable_pongs >= STABLE_PONGS_BEFORE_BACKOFF {
+            self.stable_pongs = 0;
+            self.set_interval(self.interval() * 2);
+        }
+    }
+
+    fn set_interval(&mut self, interval: Duration) {
+        self.interval_secs = interval
+            .max(MIN_PING_INTERVAL)
+            .min(MAX_PING_INTERVAL)
+            .as_secs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rtt(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_stable_peer_pinged_less_often() {
+        let mut stats = PingStats::default();
+
+        for _ in 0..STABLE_PONGS_BEFORE_BACKOFF {
+            stats.record_rtt(rtt(100));
+        }
+        assert_eq!(stats.interval(), DEFAULT_PING_INTERVAL * 2);
+
+        for _ in 0..STABLE_PONGS_BEFORE_BACKOFF * 4 {
+            stats.record_rtt(rtt(100));
+        }
+        assert_eq!(stats.interval(), MAX_PING_INTERVAL);
+    }
+
+    #[test]
+    fn test_unstable_rtt_resets_count() {
+        let mut stats = PingStats::default();
+
+        stats.record_rtt(rtt(100));
+        stats.record_rtt(rtt(100));
+        stats.record_rtt(rtt(1_000));
+        assert_eq!(stats.stable_pongs, 0);
+        assert_eq!(stats.interval(), DEFAULT_PING_INTERVAL);
+    }
+
+    #[test]
+    fn test_missed_pongs_shorten_interval() {
+        let mut stats = PingStats::default();
+
+        stats.ping_sent();
+        assert_eq!(stats.missed_pongs, 0);
+        stats.ping_sent();
+        assert_eq!(stats.missed_pongs, 1);
+        assert_eq!(stats.interval(), DEFAULT_PING_INTERVAL / 2);
+        stats.ping_sent();
+        stats.ping_sent();
+        assert_eq!(stats.interval(), MIN_PING_INTERVAL);
+
+        // a pong is awaited for the last ping only
+        stats.pong_received();
+        stats.ping_sent();
+        assert_eq!(stats.missed_pongs, 3);
+
+        // the outstanding ping is not missed if the peer disconnects
+        stats.disconnected();
+        stats.ping_sent();
+        assert_eq!(stats.missed_pongs, 3);
+    }
+}
@@ -0,0 +1,195 @@
+//! Restricts the peers we connect to, so that a private network can be run on the public internet.
+//!
+//! A peer is accepted if its node id is in the allowlist file, or if its ENR proves that it knows
+//! the shared secret of the network. The proof is the keccak256 hash of the secret followed by the
+//! node id, so it cannot be copied into the ENR of another node and does not reveal the secret.
+//!
+//! The allowlist file contains one hex-encoded node id per line. Empty lines and lines starting
+//! with `#` are ignored.
+
+use crate::Enr;
+use discv5::enr::NodeId;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
+
+/// The ENR field which holds the proof that a node knows the shared secret.
+pub const PRIVATE_NETWORK_ENR_KEY: &str = "pnet";
+
+/// Whether a node is a member of a private network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Membership {
+    Member,
+    NonMember,
+    /// The node is not in the allowlist and its ENR, which may prove that it knows the shared
+    /// secret, is not known.
+    Unknown,
+}
+
+/// The peers which may join a private network.
+pub struct PrivateNetwork {
+    /// The node ids in the allowlist.
+    node_ids: HashSet<NodeId>,
+    /// The shared secret of the network, if any.
+    secret: Option<Vec<u8>>,
+}
+
+impl PrivateNetwork {
+    /// Creates a private network from an allowlist file and/or a shared secret, at least one of
+    /// which must be given.
+    pub fn new(allowlist_file: Option<&Path>, secret: Option<&str>) -> Result<Self, String> {
+        if allowlist_file.is_none() && secret.is_none() {
+            return Err("A private network requires an allowlist or a shared secret".to_string());
+        }
+
+        let node_ids = match allowlist_file {
+            Some(file) => load_allowlist(file)?,
+            None => HashSet::new(),
+        };
+
+        Ok(Self {
+            node_ids,
+            secret: secret.map(|secret| secret.as_bytes().to_vec()),
+        })
+    }
+
+    /// Returns true if the node may join the network. `enr` is the node's ENR, if it is known.
+    pub fn allows(&self, node_id: &NodeId, enr: Option<&Enr>) -> bool {
+        self.membership(node_id, enr) == Membership::Member
+    }
+
+    /// Returns whether the node is a member of the network. `enr` is the node's ENR, if it is
+    /// known.
+    pub fn membership(&self, node_id: &NodeId, enr: Option<&Enr>) -> Membership {
+        if self.node_ids.contains(node_id) {
+            return Membership::Member;
+        }
+
+        match (&self.secret, enr) {
+            (Some(secret), Some(enr)) => {
+                if enr.node_id() == *node_id
+                    && enr.get(PRIVATE_NETWORK_ENR_KEY)
+                        == Some(&secret_proof(secret, node_id).to_vec())
+                {
+                    Membership::Member
+                } else {
+                    Membership::NonMember
+                }
+            }
+            (Some(_), None) => Membership::Unknown,
+            (None, _) => Membership::NonMember,
+        }
+    }
+
+    /// The number of node ids in the allowlist.
+    pub fn allowlist_len(&self) -> usize {
+        self.node_ids.len()
+    }
+}
+
+/// Returns the value of the `PRIVATE_NETWORK_ENR_KEY` field of the ENR of `node_id`, proving that
+/// it knows `secret`.
+pub fn secret_proof(secret: &[u8], node_id: &NodeId) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(secret);
+    keccak.update(&node_id.raw());
+    let mut output = [0; 32];
+    keccak.finalize(&mut output);
+    output
+}
+
+fn load_allowlist(file: &Path) -> Result<HashSet<NodeId>, String> {
+    let contents = fs::read_to_string(file)
+        .map_err(|e| format!("Unable to read allowlist file {:?}: {:?}", file, e))?;
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let bytes = hex::decode(line.trim_start_matches("0x"))
+                .map_err(|e| format!("Invalid node id {}: {:?}", line, e))?;
+            NodeId::parse(&bytes).map_err(|e| format!("Invalid node id {}: {}", line, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{CombinedKey, CombinedKeyExt, Keypair};
+    use discv5::enr::EnrBuilder;
+    use std::io::Write;
+
+    fn enr_with_proof(secret: &[u8]) -> Enr {
+        let key = CombinedKey::from_libp2p(&Keypair::generate_secp256k1()).unwrap();
+        let node_id = EnrBuilder::new("v4").build(&key).unwrap().node_id();
+        EnrBuilder::new("v4")
+            .add_value(
+                PRIVATE_NETWORK_ENR_KEY.into(),
+                secret_proof(secret, &node_id).to_vec(),
+            )
+            .build(&key)
+            .unwrap()
+    }
+
+    #[test]
+    fn allows_listed_node_ids() {
+        let allowed = NodeId::random();
+        let mut file = tempdir::TempDir::new("private_network")
+            .unwrap()
+            .into_path()
+            .join("allowlist.txt");
+        let mut f = fs::File::create(&file).unwrap();
+        writeln!(f, "# allowed nodes\n\n0x{}", hex::encode(allowed.raw())).unwrap();
+
+        let network = PrivateNetwork::new(Some(&file), None).unwrap();
+        assert_eq!(network.allowlist_len(), 1);
+        assert!(network.allows(&allowed, None));
+        assert!(!network.allows(&NodeId::random(), None));
+
+        file.set_file_name("missing.txt");
+        assert!(PrivateNetwork::new(Some(&file), None).is_err());
+    }
+
+    #[test]
+    fn allows_enrs_with_secret_proof() {
+        let network = PrivateNetwork::new(None, Some("secret")).unwrap();
+
+        let member = enr_with_proof(b"secret");
+        assert!(network.allows(&member.node_id(), Some(&member)));
+        assert!(!network.allows(&member.node_id(), None));
+        // The proof is not valid for another node.
+        assert!(!network.allows(&NodeId::random(), Some(&member)));
+
+        let outsider = enr_with_proof(b"guess");
+        assert!(!network.allows(&outsider.node_id(), Some(&outsider)));
+    }
+
+    #[test]
+    fn membership_is_unknown_without_enr() {
+        let network = PrivateNetwork::new(None, Some("secret")).unwrap();
+        let member = enr_with_proof(b"secret");
+        assert_eq!(
+            network.membership(&member.node_id(), None),
+            Membership::Unknown
+        );
+        assert_eq!(
+            network.membership(&member.node_id(), Some(&member)),
+            Membership::Member
+        );
+
+        // Without a shared secret, only the allowlist can make a node a member.
+        let file = tempdir::TempDir::new("private_network")
+            .unwrap()
+            .into_path()
+            .join("allowlist.txt");
+        fs::File::create(&file).unwrap();
+        let network = PrivateNetwork::new(Some(&file), None).unwrap();
+        assert_eq!(
+            network.membership(&member.node_id(), None),
+            Membership::NonMember
+        );
+    }
+}
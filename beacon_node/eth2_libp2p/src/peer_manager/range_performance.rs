@@ -0,0 +1,187 @@
+//! Measures how quickly peers serve `BlocksByRange` requests, so that peers which would stall
+//! range sync can be penalized and avoided.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// The minimum rate at which a peer must send the blocks of a `BlocksByRange` response, once the
+/// first block has been received.
+const MIN_BLOCKS_PER_SECOND: f64 = 8.0;
+/// Responses with fewer blocks than this are too short to give a meaningful block rate.
+const MIN_BLOCKS_FOR_RATE: usize = 8;
+/// The maximum time a peer may take to send the first chunk of a `BlocksByRange` response.
+const MAX_TIME_TO_FIRST_CHUNK: Duration = Duration::from_secs(2);
+/// The number of consecutive slow responses after which a peer is penalized.
+const SLOW_RESPONSES_BEFORE_PENALTY: u8 = 3;
+/// The weight of the newest response in the moving averages.
+const SMOOTHING_FACTOR: f64 = 0.3;
+
+/// The measured performance of a peer serving `BlocksByRange` requests.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RangePerformance {
+    /// A moving average of the rate at which blocks are sent, once the first has been received.
+    pub blocks_per_second: Option<f64>,
+    /// A moving average of the time taken to send the first chunk, in milliseconds.
+    pub time_to_first_chunk_millis: Option<f64>,
+    /// The number of consecutive responses which were slow.
+    pub slow_responses: u8,
+}
+
+impl RangePerformance {
+    /// Returns true if the averages are below the required performance. Sync should prefer other
+    /// peers for its requests.
+    pub fn is_slow(&self) -> bool {
+        self.blocks_per_second
+            .map_or(false, |rate| rate < MIN_BLOCKS_PER_SECOND)
+            || self.time_to_first_chunk_millis.map_or(false, |ttfc| {
+                ttfc > MAX_TIME_TO_FIRST_CHUNK.as_millis() as f64
+            })
+    }
+
+    /// Records a completed response.
+    ///
+    /// Returns true if this was the last of `SLOW_RESPONSES_BEFORE_PENALTY` consecutive slow
+    /// responses, in which case the peer should be penalized and the count starts again.
+    pub fn record(&mut self, response: CompletedRangeRequest) -> bool {
+        let mut is_slow = response.time_to_first_chunk > MAX_TIME_TO_FIRST_CHUNK;
+        self.time_to_first_chunk_millis = Some(smooth(
+            self.time_to_first_chunk_millis,
+            response.time_to_first_chunk.as_millis() as f64,
+        ));
+
+        if response.blocks >= MIN_BLOCKS_FOR_RATE {
+            // Avoid an infinite rate when every block arrives at once.
+            let secs = response.transfer_time.as_secs_f64().max(0.001);
+            let rate = response.blocks as f64 / secs;
+            is_slow |= rate < MIN_BLOCKS_PER_SECOND;
+            self.blocks_per_second = Some(smooth(self.blocks_per_second, rate));
+        }
+
+        if !is_slow {
+            self.slow_responses = 0;
+            return false;
+        }
+
+        self.slow_responses += 1;
+        if self.slow_responses >= SLOW_RESPONSES_BEFORE_PENALTY {
+            self.slow_responses = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn smooth(average: Option<f64>, value: f64) -> f64 {
+    average.map_or(value, |average| {
+        average + SMOOTHING_FACTOR * (value - average)
+    })
+}
+
+/// A `BlocksByRange` request which has been sent and whose response has not yet ended.
+pub struct PendingRangeRequest {
+    sent: Instant,
+    first_chunk: Option<Instant>,
+    blocks: usize,
+}
+
+impl PendingRangeRequest {
+    pub fn new() -> Self {
+        PendingRangeRequest {
+            sent: Instant::now(),
+            first_chunk: None,
+            blocks: 0,
+        }
+    }
+
+    /// The time since the request was sent.
+    pub fn age(&self) -> Duration {
+        self.sent.elapsed()
+    }
+
+    /// The number of blocks received so far.
+    pub fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    /// A block has been received.
+    pub fn on_block(&mut self) {
+        self.first_chunk.get_or_insert_with(Instant::now);
+        self.blocks += 1;
+    }
+
+    /// The response has ended. An empty response ends with its first chunk.
+    pub fn complete(self) -> CompletedRangeRequest {
+        let now = Instant::now();
+        let first_chunk = self.first_chunk.unwrap_or(now);
+        CompletedRangeRequest {
+            blocks: self.blocks,
+            time_to_first_chunk: first_chunk.duration_since(self.sent),
+            transfer_time: now.duration_since(first_chunk),
+        }
+    }
+}
+
+/// The timing of a complete `BlocksByRange` response.
+pub struct CompletedRangeRequest {
+    /// The number of blocks in the response.
+    pub blocks: usize,
+    /// The time between sending the request and receiving the first chunk.
+    pub time_to_first_chunk: Duration,
+    /// The time between receiving the first chunk and the end of the response.
+    pub transfer_time: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(
+        blocks: usize,
+        time_to_first_chunk: u64,
+        transfer_time: u64,
+    ) -> CompletedRangeRequest {
+        CompletedRangeRequest {
+            blocks,
+            time_to_first_chunk: Duration::from_millis(time_to_first_chunk),
+            transfer_time: Duration::from_millis(transfer_time),
+        }
+    }
+
+    #[test]
+    fn test_penalize_consistently_slow() {
+        let mut performance = RangePerformance::default();
+        assert!(!performance.is_slow());
+
+        // 64 blocks in 16 seconds
+        assert!(!performance.record(response(64, 100, 16_000)));
+        assert!(performance.is_slow());
+        assert!(!performance.record(response(64, 100, 16_000)));
+        assert!(performance.record(response(64, 100, 16_000)));
+        assert_eq!(performance.slow_responses, 0);
+    }
+
+    #[test]
+    fn test_fast_response_resets_count() {
+        let mut performance = RangePerformance::default();
+
+        // a slow first chunk
+        assert!(!performance.record(response(64, 3_000, 1_000)));
+        assert!(!performance.record(response(64, 3_000, 1_000)));
+        assert!(!performance.record(response(64, 100, 1_000)));
+        assert_eq!(performance.slow_responses, 0);
+        assert!(!performance.record(response(64, 3_000, 1_000)));
+        assert_eq!(performance.slow_responses, 1);
+    }
+
+    #[test]
+    fn test_short_responses_ignore_rate() {
+        let mut performance = RangePerformance::default();
+
+        for _ in 0..SLOW_RESPONSES_BEFORE_PENALTY {
+            assert!(!performance.record(response(2, 100, 5_000)));
+        }
+        assert!(performance.blocks_per_second.is_none());
+        assert!(!performance.is_slow());
+    }
+}
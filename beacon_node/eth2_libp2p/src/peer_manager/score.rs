@@ -0,0 +1,236 @@
+//! Configuration of reputation changes and the record of changes made to a peer's reputation.
+
+use super::peerdb::Rep;
+use serde::{
+    de::Deserializer,
+    ser::{SerializeStruct, Serializer},
+    Deserialize, Serialize,
+};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The number of reputation changes remembered for each peer.
+pub const MAX_SCORE_HISTORY: usize = 20;
+
+/// The reputation changes applied for each `PeerAction`, along with how quickly a poor reputation
+/// is forgiven.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReputationConfig {
+    /// Reputation lost for a `PeerAction::Fatal`.
+    pub fatal: Rep,
+    /// Reputation lost for a `PeerAction::LowToleranceError`.
+    pub low_tolerance_error: Rep,
+    /// Reputation lost for a `PeerAction::MidToleranceError`.
+    pub mid_tolerance_error: Rep,
+    /// Reputation lost for a `PeerAction::HighToleranceError`.
+    pub high_tolerance_error: Rep,
+    /// Reputation gained for a `PeerAction::ValidMessage`.
+    pub valid_message: Rep,
+    /// The reputation regained by each peer below `DEFAULT_REPUTATION` for every heartbeat
+    /// interval that passes, until it reaches `DEFAULT_REPUTATION`. Time spent suspended counts
+    /// towards the decay. Banned peers do not recover. A value of zero disables decay.
+    pub decay_per_heartbeat: Rep,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        ReputationConfig {
+            fatal: Rep::max_value(),
+            low_tolerance_error: 60,
+            mid_tolerance_error: 25,
+            high_tolerance_error: 15,
+            valid_message: 20,
+            decay_per_heartbeat: 1,
+        }
+    }
+}
+
+/// A change made to a peer's reputation.
+#[derive(Clone, Debug)]
+pub struct ScoreChange {
+    /// The action which caused the change and its context.
+    pub reason: String,
+    /// The change in reputation. This may be smaller than the configured weight if the
+    /// reputation reached its bounds.
+    pub change: i16,
+    /// The reputation of the peer after the change.
+    pub reputation: Rep,
+    /// The time the change was made.
+    pub time: ReputationTime,
+}
+
+/// Serialization for http requests.
+impl Serialize for ScoreChange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ScoreChange", 4)?;
+        s.serialize_field("reason", &self.reason)?;
+        s.serialize_field("change", &self.change)?;
+        s.serialize_field("reputation", &self.reputation)?;
+        s.serialize_field("seconds_ago", &self.time.elapsed().as_secs())?;
+        s.end()
+    }
+}
+
+/// A point in time used for reputation changes and decay, combining the monotonic clock with the
+/// wall clock.
+///
+/// An `Instant` cannot be persisted and, on some platforms, does not advance while the machine is
+/// suspended, whereas the wall clock may be stepped backwards. The time between two
+/// `ReputationTime`s is therefore measured by the monotonic clock, extended by the wall clock if
+/// it has advanced further (e.g., across a suspend), or by the wall clock alone if the monotonic
+/// time is unknown (e.g., after being loaded from disk). It is never negative.
+///
+/// Only the wall clock time is serialized, as milliseconds since the unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReputationTime {
+    /// The monotonic time, unknown if this time was deserialized.
+    instant: Option<Instant>,
+    /// The time since the unix epoch.
+    unix: Duration,
+}
+
+impl ReputationTime {
+    /// Returns the current time.
+    pub fn now() -> Self {
+        Self {
+            instant: Some(Self::monotonic_now()),
+            unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_else(|_| Duration::from_secs(0)),
+        }
+    }
+
+    #[cfg(not(test))]
+    fn monotonic_now() -> Instant {
+        Instant::now()
+    }
+
+    /// In tests the monotonic time is read from tokio's clock, so that it follows the clock when
+    /// it is paused and advanced.
+    #[cfg(test)]
+    fn monotonic_now() -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    /// Returns the time elapsed from `earlier` to `self`, or zero if `earlier` is later.
+    pub fn duration_since(&self, earlier: &Self) -> Duration {
+        let wall_clock = self.unix.checked_sub(earlier.unix);
+        match (self.instant, earlier.instant) {
+            (Some(now), Some(then)) => {
+                let monotonic = now.saturating_duration_since(then);
+                wall_clock.map_or(monotonic, |wall_clock| wall_clock.max(monotonic))
+            }
+            _ => wall_clock.unwrap_or_else(|| Duration::from_secs(0)),
+        }
+    }
+
+    /// Returns the time elapsed since `self`.
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(self)
+    }
+
+    /// Returns the time `duration` after `self`.
+    pub fn advance(&self, duration: Duration) -> Self {
+        Self {
+            instant: self.instant.map(|instant| instant + duration),
+            unix: self.unix + duration,
+        }
+    }
+
+    /// Returns the time `duration` before `self`. The monotonic time is dropped if it would
+    /// precede the start of the clock.
+    pub fn rewind(&self, duration: Duration) -> Self {
+        Self {
+            instant: self
+                .instant
+                .and_then(|instant| instant.checked_sub(duration)),
+            unix: self.unix.checked_sub(duration).unwrap_or_default(),
+        }
+    }
+}
+
+impl Serialize for ReputationTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.unix.as_millis() as u64)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReputationTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(|millis| Self {
+            instant: None,
+            unix: Duration::from_millis(millis),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+
+    fn time(unix_secs: u32) -> ReputationTime {
+        ReputationTime {
+            instant: Some(Instant::now()),
+            unix: Duration::from_secs(unix_secs.into()),
+        }
+    }
+
+    fn reload(time: &ReputationTime) -> ReputationTime {
+        serde_json::from_str(&serde_json::to_string(time).unwrap()).unwrap()
+    }
+
+    #[quickcheck]
+    fn quickcheck_elapsed_is_additive(start: u32, a: u16, b: u16) -> bool {
+        let (a, b) = (Duration::from_secs(a.into()), Duration::from_secs(b.into()));
+        let t0 = time(start);
+        let t1 = t0.advance(a);
+        let t2 = t1.advance(b);
+        t1.duration_since(&t0) == a
+            && t2.duration_since(&t1) == b
+            && t2.duration_since(&t0) == a + b
+            && t0.duration_since(&t2) == Duration::from_secs(0)
+    }
+
+    #[quickcheck]
+    fn quickcheck_elapsed_survives_reload(start: u32, a: u16) -> bool {
+        let a = Duration::from_secs(a.into());
+        let t0 = time(start);
+        let t1 = t0.advance(a);
+        reload(&t0)
+            == ReputationTime {
+                instant: None,
+                ..t0
+            }
+            && t1.duration_since(&reload(&t0)) == a
+            && reload(&t1).duration_since(&reload(&t0)) == a
+    }
+
+    #[quickcheck]
+    fn quickcheck_wall_clock_extends_monotonic(
+        start: u32,
+        monotonic: u16,
+        wall_clock: i32,
+    ) -> TestResult {
+        let t0 = time(start);
+        let unix = match i64::from(start).checked_add(wall_clock.into()) {
+            Some(unix) if unix >= 0 => Duration::from_secs(unix as u64),
+            _ => return TestResult::discard(),
+        };
+        let monotonic = Duration::from_secs(monotonic.into());
+        let t1 = ReputationTime {
+            instant: t0.instant.map(|instant| instant + monotonic),
+            unix,
+        };
+
+        // suspended machines advance the wall clock further, which is counted, whereas the
+        // wall clock being stepped backwards is ignored
+        let expected = if wall_clock > 0 {
+            monotonic.max(Duration::from_secs(wall_clock as u64))
+        } else {
+            monotonic
+        };
+        TestResult::from_bool(t1.duration_since(&t0) == expected)
+    }
+}
@@ -0,0 +1,824 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::discovery::{build_enr, CombinedKey, CombinedKeyExt};
+    use discv5::enr::EnrBuilder;
+    use futures::FutureExt;
+    use slog::o;
+    use tempdir::TempDir;
+    use types::{EnrForkId, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    const HEARTBEAT: Duration = Duration::from_secs(HEARTBEAT_INTERVAL);
+
+    /// Builds a `PeerManager` with the default reputation config and no boot nodes.
+    ///
+    /// Must be called within a runtime, as it starts the discovery service.
+    fn build_peer_manager(network_dir: &TempDir) -> PeerManager<E> {
+        build_peer_manager_with_config(network_dir, NetworkConfig::default())
+    }
+
+    /// As `build_peer_manager`, with the given config.
+    fn build_peer_manager_with_config(
+        network_dir: &TempDir,
+        mut config: NetworkConfig,
+    ) -> PeerManager<E> {
+        let log = slog::Logger::root(slog::Discard, o!());
+        config.network_dir = network_dir.path().to_path_buf();
+        config.discovery_port = 0;
+
+        let keypair = Keypair::generate_secp256k1();
+        let enr_key = CombinedKey::from_libp2p(&keypair).unwrap();
+        let enr = build_enr::<E>(&enr_key, &config, EnrForkId::default()).unwrap();
+        let network_globals = Arc::new(NetworkGlobals::new(enr, 0, 0, &log));
+
+        PeerManager::new(&keypair, &config, network_globals, &log).unwrap()
+    }
+
+    /// Builds a new `PeerManager` sharing the `NetworkGlobals` of `peer_manager`, as when the
+    /// network stack is restarted.
+    fn restart_peer_manager(
+        peer_manager: &PeerManager<E>,
+        network_dir: &TempDir,
+    ) -> PeerManager<E> {
+        let log = slog::Logger::root(slog::Discard, o!());
+        let mut config = NetworkConfig::default();
+        config.network_dir = network_dir.path().to_path_buf();
+        config.discovery_port = 0;
+
+        let keypair = Keypair::generate_secp256k1();
+        PeerManager::new(
+            &keypair,
+            &config,
+            peer_manager.network_globals.clone(),
+            &log,
+        )
+        .unwrap()
+    }
+
+    /// Advances the paused clock by `duration`, then returns the peer manager's pending events.
+    async fn advance(
+        peer_manager: &mut PeerManager<E>,
+        duration: Duration,
+    ) -> Vec<PeerManagerEvent> {
+        tokio::time::advance(duration).await;
+        pending_events(peer_manager)
+    }
+
+    /// Returns the events the peer manager emits without waiting, running any heartbeats which
+    /// are due.
+    fn pending_events(peer_manager: &mut PeerManager<E>) -> Vec<PeerManagerEvent> {
+        std::iter::from_fn(|| peer_manager.next().now_or_never().flatten()).collect()
+    }
+
+    /// Returns the peers disconnected by `events`, with the reason given.
+    fn disconnects(events: &[PeerManagerEvent]) -> Vec<(PeerId, GoodbyeReason)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                PeerManagerEvent::DisconnectPeer(peer_id, reason) => {
+                    Some((peer_id.clone(), reason.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the peers dialed by `events`.
+    fn dials(events: &[PeerManagerEvent]) -> Vec<PeerId> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                PeerManagerEvent::Dial(peer_id) => Some(peer_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Connects a peer as the swarm would, returning whether the peer manager accepted it.
+    fn connect(peer_manager: &mut PeerManager<E>, peer_id: &PeerId, ingoing: bool) -> bool {
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/9000".parse().unwrap();
+        let endpoint = if ingoing {
+            ConnectedPoint::Listener {
+                local_addr: address.clone(),
+                send_back_addr: address,
+            }
+        } else {
+            ConnectedPoint::Dialer { address }
+        };
+        peer_manager.swarm_connection_established(peer_id, &endpoint);
+        if ingoing {
+            peer_manager.connect_ingoing(peer_id)
+        } else {
+            peer_manager.connect_outgoing(peer_id)
+        }
+    }
+
+    /// Returns the endpoint of an ingoing connection.
+    fn listener_endpoint() -> ConnectedPoint {
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/9000".parse().unwrap();
+        ConnectedPoint::Listener {
+            local_addr: address.clone(),
+            send_back_addr: address,
+        }
+    }
+
+    /// Disconnects a peer as the swarm would.
+    fn disconnect(peer_manager: &mut PeerManager<E>, peer_id: &PeerId, cause: DisconnectCause) {
+        peer_manager.swarm_disconnected(peer_id);
+        peer_manager.notify_disconnect(peer_id, cause);
+    }
+
+    fn reputation(peer_manager: &PeerManager<E>, peer_id: &PeerId) -> Rep {
+        peer_manager
+            .network_globals
+            .peers
+            .read()
+            .reputation(peer_id)
+    }
+
+    /// Returns the time remaining until the peer's ban expires, according to the ban list.
+    fn ban_list_expiry(peer_manager: &PeerManager<E>, peer_id: &PeerId) -> Option<Duration> {
+        peer_manager
+            .network_globals
+            .peers
+            .read()
+            .ban_list()
+            .expiring_peers()
+            .find(|(banned, _)| *banned == peer_id)
+            .map(|(_, remaining)| remaining)
+    }
+
+    fn is_banned(peer_manager: &PeerManager<E>, peer_id: &PeerId) -> bool {
+        peer_manager
+            .network_globals
+            .peers
+            .read()
+            .connection_status(peer_id)
+            .map_or(false, |status| status.is_banned())
+    }
+
+    #[tokio::test]
+    async fn ban_decay_unban_lifecycle() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        let peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &peer_id, true));
+
+        // a single error lowers the reputation without banning the peer
+        peer_manager.report_peer(&peer_id, PeerAction::MidToleranceError, "test");
+        assert_eq!(reputation(&peer_manager, &peer_id), 25);
+        assert!(disconnects(&pending_events(&mut peer_manager)).is_empty());
+
+        // the reputation recovers by one for each heartbeat interval
+        let events = advance(&mut peer_manager, HEARTBEAT * 10).await;
+        assert!(disconnects(&events).is_empty());
+        assert_eq!(reputation(&peer_manager, &peer_id), 35);
+
+        // further errors take it below the ban threshold, disconnecting the peer
+        peer_manager.report_peer(&peer_id, PeerAction::MidToleranceError, "test");
+        assert!(!is_banned(&peer_manager, &peer_id));
+        peer_manager.report_peer(&peer_id, PeerAction::MidToleranceError, "test");
+        assert!(is_banned(&peer_manager, &peer_id));
+        assert_eq!(
+            disconnects(&pending_events(&mut peer_manager)),
+            vec![(peer_id.clone(), GoodbyeReason::Fault)]
+        );
+
+        // the ban is recorded in the ban list with its expiry, so that it is not permanent if the
+        // node is restarted
+        let expiry = ban_list_expiry(&peer_manager, &peer_id).expect("ban should expire");
+        assert!(expiry <= Duration::from_secs(BAN_DURATION));
+        assert!(expiry > Duration::from_secs(BAN_DURATION) - HEARTBEAT);
+
+        // the peer remains banned once disconnected, and is disconnected again if it reconnects
+        disconnect(
+            &mut peer_manager,
+            &peer_id,
+            DisconnectCause::Local(GoodbyeReason::Fault),
+        );
+        assert!(is_banned(&peer_manager, &peer_id));
+        assert!(!connect(&mut peer_manager, &peer_id, true));
+        assert_eq!(
+            disconnects(&pending_events(&mut peer_manager)),
+            vec![(peer_id.clone(), GoodbyeReason::Fault)]
+        );
+        disconnect(
+            &mut peer_manager,
+            &peer_id,
+            DisconnectCause::Local(GoodbyeReason::Fault),
+        );
+
+        // a banned peer's reputation does not recover
+        let ban_duration = Duration::from_secs(BAN_DURATION);
+        let events = advance(&mut peer_manager, ban_duration - HEARTBEAT).await;
+        assert!(disconnects(&events).is_empty());
+        assert!(is_banned(&peer_manager, &peer_id));
+        assert_eq!(reputation(&peer_manager, &peer_id), 0);
+
+        // the ban expires after `BAN_DURATION`, after which the reputation recovers from the ban
+        // threshold
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert!(disconnects(&events).is_empty());
+        assert!(!is_banned(&peer_manager, &peer_id));
+        assert!(ban_list_expiry(&peer_manager, &peer_id).is_none());
+        assert_eq!(reputation(&peer_manager, &peer_id), MIN_REP_BEFORE_BAN);
+
+        advance(&mut peer_manager, HEARTBEAT * 5).await;
+        assert_eq!(reputation(&peer_manager, &peer_id), MIN_REP_BEFORE_BAN + 5);
+
+        // the peer may connect again
+        assert!(connect(&mut peer_manager, &peer_id, true));
+        assert!(disconnects(&pending_events(&mut peer_manager)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn reputation_ban_expires_after_restart() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        let peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &peer_id, true));
+        for _ in 0..3 {
+            peer_manager.report_peer(&peer_id, PeerAction::MidToleranceError, "test");
+        }
+        assert!(is_banned(&peer_manager, &peer_id));
+        disconnect(
+            &mut peer_manager,
+            &peer_id,
+            DisconnectCause::Local(GoodbyeReason::Fault),
+        );
+
+        // the new peer manager takes over the ban from the ban list
+        let mut peer_manager = restart_peer_manager(&peer_manager, &network_dir);
+        pending_events(&mut peer_manager);
+        assert!(peer_manager.reputation_bans.contains_key(&peer_id));
+
+        let ban_duration = Duration::from_secs(BAN_DURATION);
+        advance(&mut peer_manager, ban_duration - HEARTBEAT).await;
+        assert!(is_banned(&peer_manager, &peer_id));
+
+        advance(&mut peer_manager, HEARTBEAT).await;
+        assert!(!is_banned(&peer_manager, &peer_id));
+        assert!(ban_list_expiry(&peer_manager, &peer_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn private_network_peer_without_enr() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut config = NetworkConfig::default();
+        config.private_network = true;
+        config.private_network_secret = Some("secret".to_string());
+        let mut peer_manager = build_peer_manager_with_config(&network_dir, config);
+        pending_events(&mut peer_manager);
+
+        // the peer's ENR is unknown, so its connection is held until it should have become known
+        let peer_id = PeerId::from(Keypair::generate_secp256k1().public());
+        let endpoint = listener_endpoint();
+        peer_manager.swarm_connection_established(&peer_id, &endpoint);
+        assert_eq!(
+            peer_manager.admit_to_private_network(&peer_id, &endpoint),
+            Membership::Unknown
+        );
+        assert!(peer_manager.is_unverified(&peer_id));
+
+        let timeout = Duration::from_secs(PRIVATE_NETWORK_ENR_TIMEOUT);
+        let events = advance(&mut peer_manager, timeout - HEARTBEAT).await;
+        assert!(disconnects(&events).is_empty());
+        assert!(!peer_manager
+            .network_globals
+            .peers
+            .read()
+            .is_connected(&peer_id));
+
+        // the peer is then banned, so that it cannot immediately try again
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert_eq!(
+            disconnects(&events),
+            vec![(peer_id.clone(), GoodbyeReason::IrrelevantNetwork)]
+        );
+        assert_eq!(swarm_bans(&events), vec![(peer_id.clone(), true)]);
+        assert!(is_banned(&peer_manager, &peer_id));
+        assert!(!peer_manager.is_unverified(&peer_id));
+    }
+
+    #[tokio::test]
+    async fn private_network_peer_admitted_once_enr_known() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut config = NetworkConfig::default();
+        config.private_network = true;
+        config.private_network_secret = Some("secret".to_string());
+        let mut peer_manager = build_peer_manager_with_config(&network_dir, config);
+        pending_events(&mut peer_manager);
+
+        let key = CombinedKey::from_libp2p(&Keypair::generate_secp256k1()).unwrap();
+        let node_id = EnrBuilder::new("v4").build(&key).unwrap().node_id();
+        let enr = EnrBuilder::new("v4")
+            .add_value(
+                PRIVATE_NETWORK_ENR_KEY.into(),
+                secret_proof(b"secret", &node_id).to_vec(),
+            )
+            .build(&key)
+            .unwrap();
+        let peer_id = enr.peer_id();
+
+        let endpoint = listener_endpoint();
+        peer_manager.swarm_connection_established(&peer_id, &endpoint);
+        assert_eq!(
+            peer_manager.admit_to_private_network(&peer_id, &endpoint),
+            Membership::Unknown
+        );
+
+        // the held peer is not mistaken for a connection the peer manager missed
+        let events = advance(&mut peer_manager, HEARTBEAT / 2).await;
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, PeerManagerEvent::AdmitPeer(..))));
+        assert!(!peer_manager
+            .network_globals
+            .peers
+            .read()
+            .is_connected(&peer_id));
+
+        // once its ENR is known, the peer is admitted when it identifies itself
+        peer_manager.discovery_mut().add_enr(enr);
+        let info = IdentifyInfo {
+            public_key: Keypair::generate_secp256k1().public(),
+            protocol_version: "lighthouse/test".to_string(),
+            agent_version: "Lighthouse/v0.0.0/x86_64-linux".to_string(),
+            listen_addrs: vec![],
+            protocols: vec![],
+        };
+        peer_manager.identify(&peer_id, &info);
+        assert!(!peer_manager.is_unverified(&peer_id));
+        let admitted = pending_events(&mut peer_manager)
+            .into_iter()
+            .filter_map(|event| match event {
+                PeerManagerEvent::AdmitPeer(peer_id, _) => Some(peer_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(admitted, vec![peer_id]);
+    }
+
+    #[tokio::test]
+    async fn addresses_of_peer_without_enr() {
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+
+        let peer_id = PeerId::random();
+        assert!(peer_manager.addresses_of_peer(&peer_id).is_empty());
+
+        // a peer dialed by multiaddr is unknown to discovery, so its identified listening
+        // addresses are used
+        assert!(peer_manager.connect_outgoing(&peer_id));
+        let listen_addrs: Vec<Multiaddr> = vec!["/ip4/127.0.0.1/tcp/9000".parse().unwrap()];
+        let info = IdentifyInfo {
+            public_key: Keypair::generate_secp256k1().public(),
+            protocol_version: "lighthouse/test".to_string(),
+            agent_version: "Lighthouse/v0.0.0/x86_64-linux".to_string(),
+            listen_addrs: listen_addrs.clone(),
+            protocols: vec![],
+        };
+        peer_manager.identify(&peer_id, &info);
+
+        assert_eq!(peer_manager.addresses_of_peer(&peer_id), listen_addrs);
+    }
+
+    fn last_disconnect(peer_manager: &PeerManager<E>, peer_id: &PeerId) -> Option<DisconnectCause> {
+        peer_manager
+            .network_globals
+            .peers
+            .read()
+            .peer_info(peer_id)
+            .and_then(|info| info.last_disconnect.clone())
+    }
+
+    #[tokio::test]
+    async fn disconnect_causes() {
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+
+        // without a goodbye, the cause reported by the transport is recorded
+        let peer_id = PeerId::random();
+        assert!(peer_manager.connect_outgoing(&peer_id));
+        peer_manager.notify_disconnect(&peer_id, DisconnectCause::TransportError);
+        assert_eq!(
+            last_disconnect(&peer_manager, &peer_id),
+            Some(DisconnectCause::TransportError)
+        );
+
+        // the first goodbye takes precedence over the transport
+        assert!(peer_manager.connect_outgoing(&peer_id));
+        peer_manager.disconnecting_peer(
+            &peer_id,
+            DisconnectCause::Local(GoodbyeReason::IrrelevantNetwork),
+        );
+        peer_manager.disconnecting_peer(
+            &peer_id,
+            DisconnectCause::Remote(Some(GoodbyeReason::ClientShutdown)),
+        );
+        peer_manager.notify_disconnect(&peer_id, DisconnectCause::Remote(None));
+        assert_eq!(
+            last_disconnect(&peer_manager, &peer_id),
+            Some(DisconnectCause::Local(GoodbyeReason::IrrelevantNetwork))
+        );
+
+        // the goodbye only applies to the connection it was exchanged on
+        assert!(peer_manager.connect_outgoing(&peer_id));
+        peer_manager.notify_disconnect(&peer_id, DisconnectCause::Remote(None));
+        assert_eq!(
+            last_disconnect(&peer_manager, &peer_id),
+            Some(DisconnectCause::Remote(None))
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_goodbyes_expire() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        // a peer which never closed the connection after a goodbye
+        let lingering_peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &lingering_peer_id, false));
+        peer_manager.disconnecting_peer(
+            &lingering_peer_id,
+            DisconnectCause::Local(GoodbyeReason::IrrelevantNetwork),
+        );
+
+        // a peer whose connection closed without the peer manager being notified
+        let closed_peer_id = PeerId::random();
+        peer_manager.disconnecting_peer(
+            &closed_peer_id,
+            DisconnectCause::Remote(Some(GoodbyeReason::ClientShutdown)),
+        );
+
+        advance(&mut peer_manager, HEARTBEAT).await;
+        assert!(peer_manager
+            .disconnecting_peers
+            .contains_key(&lingering_peer_id));
+        assert!(!peer_manager
+            .disconnecting_peers
+            .contains_key(&closed_peer_id));
+
+        advance(&mut peer_manager, HEARTBEAT).await;
+        assert!(peer_manager.disconnecting_peers.is_empty());
+
+        // the stale goodbye does not apply to the eventual disconnection
+        disconnect(
+            &mut peer_manager,
+            &lingering_peer_id,
+            DisconnectCause::TransportError,
+        );
+        assert_eq!(
+            last_disconnect(&peer_manager, &lingering_peer_id),
+            Some(DisconnectCause::TransportError)
+        );
+    }
+
+    #[tokio::test]
+    async fn full_peers_are_dialed_last() {
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        let config = NetworkConfig::default();
+
+        let enrs = (0..2)
+            .map(|_| {
+                let enr_key = CombinedKey::generate_secp256k1();
+                build_enr::<E>(&enr_key, &config, EnrForkId::default()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let full_peer_id = enrs[0].peer_id();
+        let other_peer_id = enrs[1].peer_id();
+
+        assert!(peer_manager.connect_outgoing(&full_peer_id));
+        peer_manager.disconnecting_peer(
+            &full_peer_id,
+            DisconnectCause::Remote(Some(GoodbyeReason::TooManyPeers)),
+        );
+        peer_manager.notify_disconnect(&full_peer_id, DisconnectCause::Remote(None));
+
+        peer_manager.events.clear();
+        peer_manager.peers_discovered(enrs, None);
+        let dialed = dials(&peer_manager.events);
+        assert_eq!(dialed, vec![other_peer_id, full_peer_id]);
+    }
+
+    #[tokio::test]
+    async fn discovered_peers_dialed_up_to_target() {
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let config = NetworkConfig {
+            max_peers: 3,
+            ..NetworkConfig::default()
+        };
+        let mut peer_manager = build_peer_manager_with_config(&network_dir, config.clone());
+        assert!(connect(&mut peer_manager, &PeerId::random(), false));
+
+        let enrs = (0..4)
+            .map(|_| {
+                let enr_key = CombinedKey::generate_secp256k1();
+                build_enr::<E>(&enr_key, &config, EnrForkId::default()).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        peer_manager.events.clear();
+        peer_manager.peers_discovered(enrs, None);
+        assert_eq!(dials(&peer_manager.events).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fatal_action_bans_immediately() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        let peer_id = PeerId::random();
+        let other_peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &peer_id, false));
+        assert!(connect(&mut peer_manager, &other_peer_id, false));
+
+        peer_manager.report_peer(&peer_id, PeerAction::Fatal, "test");
+        assert!(is_banned(&peer_manager, &peer_id));
+        assert!(!is_banned(&peer_manager, &other_peer_id));
+        assert_eq!(
+            disconnects(&pending_events(&mut peer_manager)),
+            vec![(peer_id.clone(), GoodbyeReason::Fault)]
+        );
+
+        // reporting a banned peer does not disconnect it again
+        peer_manager.report_peer(&peer_id, PeerAction::Fatal, "test");
+        assert!(disconnects(&pending_events(&mut peer_manager)).is_empty());
+    }
+
+    /// Returns the peers banned and unbanned from the swarm by `events`.
+    fn swarm_bans(events: &[PeerManagerEvent]) -> Vec<(PeerId, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                PeerManagerEvent::BanPeer(peer_id) => Some((peer_id.clone(), true)),
+                PeerManagerEvent::UnbanPeer(peer_id) => Some((peer_id.clone(), false)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn bans_are_applied_to_the_swarm() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        let peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &peer_id, false));
+        peer_manager.report_peer(&peer_id, PeerAction::Fatal, "test");
+        assert_eq!(
+            swarm_bans(&pending_events(&mut peer_manager)),
+            vec![(peer_id.clone(), true)]
+        );
+        disconnect(
+            &mut peer_manager,
+            &peer_id,
+            DisconnectCause::Local(GoodbyeReason::Fault),
+        );
+
+        let ban_duration = Duration::from_secs(BAN_DURATION);
+        let events = advance(&mut peer_manager, ban_duration - HEARTBEAT).await;
+        assert!(swarm_bans(&events).is_empty());
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert_eq!(swarm_bans(&events), vec![(peer_id, false)]);
+    }
+
+    #[tokio::test]
+    async fn heartbeat_bans_peers_below_threshold() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        let peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &peer_id, true));
+        peer_manager
+            .network_globals
+            .peers
+            .write()
+            .set_reputation(&peer_id, 0);
+        assert!(!is_banned(&peer_manager, &peer_id));
+
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert_eq!(
+            disconnects(&events),
+            vec![(peer_id.clone(), GoodbyeReason::Fault)]
+        );
+        assert!(is_banned(&peer_manager, &peer_id));
+        disconnect(
+            &mut peer_manager,
+            &peer_id,
+            DisconnectCause::Local(GoodbyeReason::Fault),
+        );
+
+        // disconnected peers are banned without being disconnected again
+        let disconnected_peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &disconnected_peer_id, true));
+        disconnect(
+            &mut peer_manager,
+            &disconnected_peer_id,
+            DisconnectCause::Remote(None),
+        );
+        peer_manager
+            .network_globals
+            .peers
+            .write()
+            .set_reputation(&disconnected_peer_id, 0);
+
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert!(disconnects(&events).is_empty());
+        assert!(is_banned(&peer_manager, &disconnected_peer_id));
+    }
+
+    #[tokio::test]
+    async fn heartbeat_reconciles_with_swarm() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        // a peer whose disconnection was missed
+        let stale_peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &stale_peer_id, true));
+        peer_manager.swarm_disconnected(&stale_peer_id);
+
+        // a peer whose connection was missed
+        let untracked_peer_id = PeerId::random();
+        peer_manager.swarm_connection_established(
+            &untracked_peer_id,
+            &ConnectedPoint::Dialer {
+                address: "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            },
+        );
+
+        // a banned peer which connected regardless
+        let banned_peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &banned_peer_id, true));
+        peer_manager.report_peer(&banned_peer_id, PeerAction::Fatal, "test");
+        pending_events(&mut peer_manager);
+
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert_eq!(
+            disconnects(&events),
+            vec![(banned_peer_id.clone(), GoodbyeReason::Fault)]
+        );
+        let peerdb = peer_manager.network_globals.peers.read();
+        assert!(!peerdb.is_connected(&stale_peer_id));
+        assert!(peerdb.is_connected(&untracked_peer_id));
+        assert!(peerdb.peer_banned(&banned_peer_id));
+    }
+
+    #[tokio::test]
+    async fn banned_peers_saying_goodbye_are_not_disconnected_again() {
+        tokio::time::pause();
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        pending_events(&mut peer_manager);
+
+        let peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &peer_id, true));
+        peer_manager.report_peer(&peer_id, PeerAction::Fatal, "test");
+        let events = pending_events(&mut peer_manager);
+        assert_eq!(
+            disconnects(&events),
+            vec![(peer_id.clone(), GoodbyeReason::Fault)]
+        );
+        peer_manager.disconnecting_peer(&peer_id, DisconnectCause::Local(GoodbyeReason::Fault));
+
+        // the swarm keeps the connection open while the goodbye is delivered
+        let events = advance(&mut peer_manager, HEARTBEAT).await;
+        assert!(disconnects(&events).is_empty());
+
+        disconnect(&mut peer_manager, &peer_id, DisconnectCause::Remote(None));
+        assert_eq!(
+            last_disconnect(&peer_manager, &peer_id),
+            Some(DisconnectCause::Local(GoodbyeReason::Fault))
+        );
+    }
+
+    #[tokio::test]
+    async fn inbound_peers_rejected_when_disabled() {
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+        peer_manager.disable_inbound = true;
+        peer_manager.events.clear();
+
+        let ingoing_peer_id = PeerId::random();
+        let outgoing_peer_id = PeerId::random();
+        assert!(!connect(&mut peer_manager, &ingoing_peer_id, true));
+        assert!(connect(&mut peer_manager, &outgoing_peer_id, false));
+
+        let peerdb = peer_manager.network_globals.peers.read();
+        assert!(!peerdb.is_connected(&ingoing_peer_id));
+        assert!(peerdb.is_connected(&outgoing_peer_id));
+        drop(peerdb);
+        assert_eq!(
+            disconnects(&peer_manager.events),
+            vec![(ingoing_peer_id, GoodbyeReason::TooManyPeers)]
+        );
+    }
+
+    #[tokio::test]
+    async fn peers_on_short_subnets_are_dialed_first() {
+        use crate::discovery::enr::{EnrBuilder, BITFIELD_ENR_KEY};
+        use crate::rpc::MetaDataV1;
+        use ssz::Encode;
+        use types::BitVector;
+
+        let network_dir = TempDir::new("peer_manager_test").unwrap();
+        let mut peer_manager = build_peer_manager(&network_dir);
+
+        let attnets = |subnet_id: usize| {
+            let mut bitfield = BitVector::new();
+            bitfield.set(subnet_id, true).unwrap();
+            bitfield
+        };
+
+        // we are connected to a peer on subnet 0 only
+        let connected_peer_id = PeerId::random();
+        assert!(connect(&mut peer_manager, &connected_peer_id, false));
+        peer_manager.network_globals.peers.write().add_metadata(
+            &connected_peer_id,
+            MetaData::V1(MetaDataV1 {
+                seq_number: 0,
+                attnets: attnets(0),
+            }),
+        );
+
+        let enrs = [0, 1]
+            .iter()
+            .map(|subnet_id| {
+                let enr_key = CombinedKey::generate_secp256k1();
+                EnrBuilder::new("v4")
+                    .add_value(BITFIELD_ENR_KEY.into(), attnets(*subnet_id).as_ssz_bytes())
+                    .build(&enr_key)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let covered_peer_id = enrs[0].peer_id();
+        let uncovered_peer_id = enrs[1].peer_id();
+
+        peer_manager.events.clear();
+        peer_manager.peers_discovered(enrs, None);
+        let dialed = dials(&peer_manager.events);
+        assert_eq!(dialed, vec![uncovered_peer_id, covered_peer_id]);
+    }
+
+    #[test]
+    fn peers_follow_next_fork_only_if_scheduled() {
+        use types::Epoch;
+
+        let unscheduled = EnrForkId {
+            fork_digest: [1, 2, 3, 4],
+            next_fork_version: [0, 0, 0, 0],
+            next_fork_epoch: Epoch::new(u64::max_value()),
+        };
+        let scheduled = EnrForkId {
+            next_fork_version: [1, 0, 0, 0],
+            next_fork_epoch: Epoch::new(100),
+            ..unscheduled.clone()
+        };
+
+        // peers which have scheduled the same next fork as us
+        assert!(follows_next_fork(&scheduled, &scheduled));
+
+        // peers which have not scheduled the next fork
+        assert!(!follows_next_fork(&scheduled, &unscheduled));
+
+        // peers which have scheduled it at another epoch or version
+        let other_epoch = EnrForkId {
+            next_fork_epoch: Epoch::new(200),
+            ..scheduled.clone()
+        };
+        assert!(!follows_next_fork(&scheduled, &other_epoch));
+        let other_version = EnrForkId {
+            next_fork_version: [2, 0, 0, 0],
+            ..scheduled.clone()
+        };
+        assert!(!follows_next_fork(&scheduled, &other_version));
+
+        // peers which are not on our current fork
+        let other_digest = EnrForkId {
+            fork_digest: [4, 3, 2, 1],
+            ..scheduled.clone()
+        };
+        assert!(!follows_next_fork(&scheduled, &other_digest));
+    }
+}
@@ -177,6 +177,7 @@ mod tests {
     use super::super::ssz::*;
     use super::super::ssz_snappy::*;
     use super::*;
+    use crate::rpc::methods::StatusMessage;
     use crate::rpc::protocol::*;
 
     #[test]
@@ -214,4 +215,162 @@ mod tests {
         let _ = dbg!(snappy_decoded_chunk);
         let _ = dbg!(ssz_decoded_chunk);
     }
+
+    #[test]
+    fn test_decode_length_prefix_out_of_bounds() {
+        type Spec = types::MainnetEthSpec;
+
+        // A `Status` message has a fixed length, so any other length prefix is invalid.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&[0x80, 0x80, 0x40]); // varint of 1_048_576
+
+        let snappy_protocol_id =
+            ProtocolId::new(Protocol::Status, Version::V1, Encoding::SSZSnappy);
+        let mut snappy_outbound_codec =
+            SSZSnappyOutboundCodec::<Spec>::new(snappy_protocol_id, 1_048_576);
+
+        assert!(matches!(
+            snappy_outbound_codec.decode(&mut buf),
+            Err(RPCError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn test_decode_block_length_prefix_exceeds_full_block() {
+        type Spec = types::MinimalEthSpec;
+
+        // The length of a block is bounded by the presets of the spec, well below the maximum
+        // size of a chunk.
+        let limits = RpcLimits::signed_beacon_block::<Spec>();
+        assert!(limits.max < 1_048_576);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(unsigned_varint::encode::usize(
+            limits.max + 1,
+            &mut unsigned_varint::encode::usize_buffer(),
+        ));
+
+        let snappy_protocol_id =
+            ProtocolId::new(Protocol::BlocksByRange, Version::V1, Encoding::SSZSnappy);
+        let mut snappy_outbound_codec =
+            SSZSnappyOutboundCodec::<Spec>::new(snappy_protocol_id, 1_048_576);
+
+        assert!(matches!(
+            snappy_outbound_codec.decode(&mut buf),
+            Err(RPCError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn test_decode_exceeds_max_compressed_len() {
+        type Spec = types::MainnetEthSpec;
+
+        let status_len = <StatusMessage as ssz::Encode>::ssz_fixed_len();
+        let max_compressed_len = snap::raw::max_compress_len(status_len);
+
+        // A valid length prefix and snappy stream identifier, followed by a padding chunk which is
+        // longer than any valid encoding of a `Status` message.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&[status_len as u8]);
+        buf.extend_from_slice(&[0xff, 0x06, 0x00, 0x00, 0x73, 0x4e, 0x61, 0x50, 0x70, 0x59]);
+        buf.extend_from_slice(&[0xfe, max_compressed_len as u8, 0x00, 0x00]);
+        buf.extend_from_slice(&vec![0; max_compressed_len]);
+
+        let snappy_protocol_id =
+            ProtocolId::new(Protocol::Status, Version::V1, Encoding::SSZSnappy);
+        let mut snappy_outbound_codec =
+            SSZSnappyOutboundCodec::<Spec>::new(snappy_protocol_id, 1_048_576);
+
+        assert!(matches!(
+            snappy_outbound_codec.decode(&mut buf),
+            Err(RPCError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn test_peer_exchange_round_trip() {
+        use crate::discovery::enr::EnrBuilder;
+        use crate::discovery::{CombinedKey, CombinedKeyExt};
+        use crate::rpc::methods::PeerExchange;
+        use libp2p::core::identity::Keypair;
+
+        type Spec = types::MainnetEthSpec;
+
+        let enrs = (0..3)
+            .map(|_| {
+                let key = CombinedKey::from_libp2p(&Keypair::generate_secp256k1()).unwrap();
+                EnrBuilder::new("v4").build(&key).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let request = RPCRequest::<Spec>::PeerExchange(PeerExchange { enrs });
+
+        for encoding in &[Encoding::SSZSnappy, Encoding::SSZ] {
+            let protocol_id =
+                ProtocolId::new(Protocol::PeerExchange, Version::V1, encoding.clone());
+
+            let mut buf = BytesMut::new();
+            let decoded = match encoding {
+                Encoding::SSZSnappy => {
+                    SSZSnappyOutboundCodec::<Spec>::new(protocol_id.clone(), 1_048_576)
+                        .encode(request.clone(), &mut buf)
+                        .unwrap();
+                    SSZSnappyInboundCodec::<Spec>::new(protocol_id, 1_048_576).decode(&mut buf)
+                }
+                Encoding::SSZ => {
+                    SSZOutboundCodec::<Spec>::new(protocol_id.clone(), 1_048_576)
+                        .encode(request.clone(), &mut buf)
+                        .unwrap();
+                    SSZInboundCodec::<Spec>::new(protocol_id, 1_048_576).decode(&mut buf)
+                }
+            };
+
+            assert_eq!(decoded.unwrap(), Some(request.clone()));
+        }
+    }
+
+    #[test]
+    fn test_metadata_versions() {
+        use crate::rpc::methods::{MetaData, MetaDataV1, MetaDataV2};
+        use types::BitVector;
+
+        type Spec = types::MainnetEthSpec;
+
+        let mut syncnets = BitVector::new();
+        syncnets.set(1, true).unwrap();
+        let meta_data = MetaData::<Spec>::V2(MetaDataV2 {
+            seq_number: 7,
+            attnets: BitVector::new(),
+            syncnets,
+        });
+
+        // a peer which only supports the first version receives the metadata without syncnets
+        for (version, expected) in &[
+            (
+                Version::V1,
+                MetaData::V1(MetaDataV1 {
+                    seq_number: 7,
+                    attnets: BitVector::new(),
+                }),
+            ),
+            (Version::V2, meta_data.clone()),
+        ] {
+            let protocol_id =
+                ProtocolId::new(Protocol::MetaData, version.clone(), Encoding::SSZSnappy);
+
+            let mut buf = BytesMut::new();
+            SSZSnappyInboundCodec::<Spec>::new(protocol_id.clone(), 1_048_576)
+                .encode(
+                    RPCCodedResponse::Success(RPCResponse::MetaData(meta_data.clone())),
+                    &mut buf,
+                )
+                .unwrap();
+            let decoded =
+                SSZSnappyOutboundCodec::<Spec>::new(protocol_id, 1_048_576).decode(&mut buf);
+
+            assert_eq!(
+                decoded.unwrap(),
+                Some(RPCResponse::MetaData(expected.clone()))
+            );
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use crate::rpc::methods::*;
 use crate::rpc::{
     codec::base::OutboundCodec,
-    protocol::{Encoding, Protocol, ProtocolId, RPCError, Version},
+    protocol::{Encoding, Protocol, ProtocolId, RPCError, RpcLimits, Version},
 };
 use crate::rpc::{RPCCodedResponse, RPCRequest, RPCResponse};
 use libp2p::bytes::{BufMut, Bytes, BytesMut};
@@ -50,10 +50,15 @@ impl<TSpec: EthSpec> Encoder<RPCCodedResponse<TSpec>> for SSZInboundCodec<TSpec>
                 RPCResponse::BlocksByRange(res) => res.as_ssz_bytes(),
                 RPCResponse::BlocksByRoot(res) => res.as_ssz_bytes(),
                 RPCResponse::Pong(res) => res.data.as_ssz_bytes(),
-                RPCResponse::MetaData(res) => res.as_ssz_bytes(),
+                RPCResponse::MetaData(res) => match self.protocol.version {
+                    // peers which only support the first version are sent it without the syncnets
+                    Version::V1 => res.v1().as_ssz_bytes(),
+                    Version::V2 => res.v2().as_ssz_bytes(),
+                },
             },
             RPCCodedResponse::InvalidRequest(err) => err.into_bytes().as_ssz_bytes(),
             RPCCodedResponse::ServerError(err) => err.into_bytes().as_ssz_bytes(),
+            RPCCodedResponse::RateLimited(err) => err.into_bytes().as_ssz_bytes(),
             RPCCodedResponse::Unknown(err) => err.into_bytes().as_ssz_bytes(),
             RPCCodedResponse::StreamTermination(_) => {
                 unreachable!("Code error - attempting to encode a stream termination")
@@ -81,34 +86,48 @@ impl<TSpec: EthSpec> Decoder for SSZInboundCodec<TSpec> {
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         match self.inner.decode(src).map_err(RPCError::from) {
+            // Reject requests which cannot be the SSZ encoding of a request for the protocol.
+            Ok(Some(packet))
+                if self
+                    .protocol
+                    .rpc_request_limits()
+                    .is_out_of_bounds(packet.len()) =>
+            {
+                Err(RPCError::InvalidData)
+            }
             Ok(Some(packet)) => match self.protocol.message_name {
                 Protocol::Status => match self.protocol.version {
                     Version::V1 => Ok(Some(RPCRequest::Status(StatusMessage::from_ssz_bytes(
                         &packet,
                     )?))),
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::Goodbye => match self.protocol.version {
                     Version::V1 => Ok(Some(RPCRequest::Goodbye(GoodbyeReason::from_ssz_bytes(
                         &packet,
                     )?))),
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::BlocksByRange => match self.protocol.version {
                     Version::V1 => Ok(Some(RPCRequest::BlocksByRange(
                         BlocksByRangeRequest::from_ssz_bytes(&packet)?,
                     ))),
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::BlocksByRoot => match self.protocol.version {
                     Version::V1 => Ok(Some(RPCRequest::BlocksByRoot(BlocksByRootRequest {
                         block_roots: Vec::from_ssz_bytes(&packet)?,
                     }))),
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::Ping => match self.protocol.version {
                     Version::V1 => Ok(Some(RPCRequest::Ping(Ping {
                         data: u64::from_ssz_bytes(&packet)?,
                     }))),
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::MetaData => match self.protocol.version {
-                    Version::V1 => {
+                    Version::V1 | Version::V2 => {
                         if packet.len() > 0 {
                             Err(RPCError::InvalidData)
                         } else {
@@ -116,6 +135,12 @@ impl<TSpec: EthSpec> Decoder for SSZInboundCodec<TSpec> {
                         }
                     }
                 },
+                Protocol::PeerExchange => match self.protocol.version {
+                    Version::V1 => Ok(Some(RPCRequest::PeerExchange(
+                        PeerExchange::from_ssz_bytes(&packet)?,
+                    ))),
+                    Version::V2 => Err(RPCError::InvalidData),
+                },
             },
             Ok(None) => Ok(None),
             Err(e) => Err(e),
@@ -128,6 +153,8 @@ impl<TSpec: EthSpec> Decoder for SSZInboundCodec<TSpec> {
 pub struct SSZOutboundCodec<TSpec: EthSpec> {
     inner: UviBytes,
     protocol: ProtocolId,
+    /// The bounds on the length of successful responses, which are computed once per stream.
+    response_limits: RpcLimits,
     phantom: PhantomData<TSpec>,
 }
 
@@ -141,6 +168,7 @@ impl<TSpec: EthSpec> SSZOutboundCodec<TSpec> {
 
         SSZOutboundCodec {
             inner: uvi_codec,
+            response_limits: protocol.rpc_response_limits::<TSpec>(),
             protocol,
             phantom: PhantomData,
         }
@@ -159,6 +187,7 @@ impl<TSpec: EthSpec> Encoder<RPCRequest<TSpec>> for SSZOutboundCodec<TSpec> {
             RPCRequest::BlocksByRoot(req) => req.block_roots.as_ssz_bytes(),
             RPCRequest::Ping(req) => req.as_ssz_bytes(),
             RPCRequest::MetaData(_) => return Ok(()), // no metadata to encode
+            RPCRequest::PeerExchange(req) => req.as_ssz_bytes(),
         };
         // length-prefix
         self.inner
@@ -184,23 +213,31 @@ impl<TSpec: EthSpec> Decoder for SSZOutboundCodec<TSpec> {
             match self.protocol.message_name {
                 Protocol::Status => match self.protocol.version {
                     Version::V1 => Err(RPCError::IncompleteStream), // cannot have an empty HELLO message. The stream has terminated unexpectedly
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
-                Protocol::Goodbye => Err(RPCError::InvalidData),
+                Protocol::Goodbye | Protocol::PeerExchange => Err(RPCError::InvalidData),
                 Protocol::BlocksByRange => match self.protocol.version {
                     Version::V1 => Err(RPCError::IncompleteStream), // cannot have an empty block message.
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::BlocksByRoot => match self.protocol.version {
                     Version::V1 => Err(RPCError::IncompleteStream), // cannot have an empty block message.
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::Ping => match self.protocol.version {
                     Version::V1 => Err(RPCError::IncompleteStream), // cannot have an empty block message.
+                    Version::V2 => Err(RPCError::InvalidData),
                 },
                 Protocol::MetaData => match self.protocol.version {
-                    Version::V1 => Err(RPCError::IncompleteStream), // cannot have an empty block message.
+                    Version::V1 | Version::V2 => Err(RPCError::IncompleteStream), // cannot have an empty block message.
                 },
             }
         } else {
             match self.inner.decode(src).map_err(RPCError::from) {
+                // Reject responses which cannot be the SSZ encoding of a response for the protocol.
+                Ok(Some(packet)) if self.response_limits.is_out_of_bounds(packet.len()) => {
+                    Err(RPCError::InvalidData)
+                }
                 Ok(Some(mut packet)) => {
                     // take the bytes from the buffer
                     let raw_bytes = packet.split();
@@ -210,27 +247,34 @@ impl<TSpec: EthSpec> Decoder for SSZOutboundCodec<TSpec> {
                             Version::V1 => Ok(Some(RPCResponse::Status(
                                 StatusMessage::from_ssz_bytes(&raw_bytes)?,
                             ))),
+                            Version::V2 => Err(RPCError::InvalidData),
                         },
-                        Protocol::Goodbye => Err(RPCError::InvalidData),
+                        Protocol::Goodbye | Protocol::PeerExchange => Err(RPCError::InvalidData),
                         Protocol::BlocksByRange => match self.protocol.version {
                             Version::V1 => Ok(Some(RPCResponse::BlocksByRange(Box::new(
                                 SignedBeaconBlock::from_ssz_bytes(&raw_bytes)?,
                             )))),
+                            Version::V2 => Err(RPCError::InvalidData),
                         },
                         Protocol::BlocksByRoot => match self.protocol.version {
                             Version::V1 => Ok(Some(RPCResponse::BlocksByRoot(Box::new(
                                 SignedBeaconBlock::from_ssz_bytes(&raw_bytes)?,
                             )))),
+                            Version::V2 => Err(RPCError::InvalidData),
                         },
                         Protocol::Ping => match self.protocol.version {
                             Version::V1 => Ok(Some(RPCResponse::Pong(Ping {
                                 data: u64::from_ssz_bytes(&raw_bytes)?,
                             }))),
+                            Version::V2 => Err(RPCError::InvalidData),
                         },
                         Protocol::MetaData => match self.protocol.version {
-                            Version::V1 => Ok(Some(RPCResponse::MetaData(
-                                MetaData::from_ssz_bytes(&raw_bytes)?,
-                            ))),
+                            Version::V1 => Ok(Some(RPCResponse::MetaData(MetaData::V1(
+                                MetaDataV1::from_ssz_bytes(&raw_bytes)?,
+                            )))),
+                            Version::V2 => Ok(Some(RPCResponse::MetaData(MetaData::V2(
+                                MetaDataV2::from_ssz_bytes(&raw_bytes)?,
+                            )))),
                         },
                     }
                 }
@@ -246,6 +290,9 @@ impl<TSpec: EthSpec> OutboundCodec<RPCRequest<TSpec>> for SSZOutboundCodec<TSpec
 
     fn decode_error(&mut self, src: &mut BytesMut) -> Result<Option<Self::ErrorType>, RPCError> {
         match self.inner.decode(src).map_err(RPCError::from) {
+            Ok(Some(packet)) if ProtocolId::rpc_error_limits().is_out_of_bounds(packet.len()) => {
+                Err(RPCError::InvalidData)
+            }
             Ok(Some(packet)) => Ok(Some(
                 String::from_utf8_lossy(&<Vec<u8>>::from_ssz_bytes(&packet)?).into(),
             )),
@@ -1,7 +1,7 @@
 use crate::rpc::methods::*;
 use crate::rpc::{
     codec::base::OutboundCodec,
-    protocol::{Encoding, Protocol, ProtocolId, RPCError, Version},
+    protocol::{Encoding, Protocol, ProtocolId, RPCError, RpcLimits, Version},
 };
 use crate::rpc::{RPCCodedResponse, RPCRequest, RPCResponse};
 use libp2p::bytes::BytesMut;
@@ -58,10 +58,15 @@ impl<TSpec: EthSpec> Encoder<RPCCodedResponse<TSpec>> for SSZSnappyInboundCodec<
                 RPCResponse::BlocksByRange(res) => res.as_ssz_bytes(),
                 RPCResponse::BlocksByRoot(res) => res.as_ssz_bytes(),
                 RPCResponse::Pong(res) => res.data.as_ssz_bytes(),
-                RPCResponse::MetaData(res) => res.as_ssz_bytes(),
+                RPCResponse::MetaData(res) => match self.protocol.version {
+                    // peers which only support the first version are sent it without the syncnets
+                    Version::V1 => res.v1().as_ssz_bytes(),
+                    Version::V2 => res.v2().as_ssz_bytes(),
+                },
             },
             RPCCodedResponse::InvalidRequest(err) => err.into_bytes().as_ssz_bytes(),
             RPCCodedResponse::ServerError(err) => err.into_bytes().as_ssz_bytes(),
+            RPCCodedResponse::RateLimited(err) => err.into_bytes().as_ssz_bytes(),
             RPCCodedResponse::Unknown(err) => err.into_bytes().as_ssz_bytes(),
             RPCCodedResponse::StreamTermination(_) => {
                 unreachable!("Code error - attempting to encode a stream termination")
@@ -107,17 +112,25 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyInboundCodec<TSpec> {
 
         let length = self.len.expect("length should be Some");
 
-        // Should not attempt to decode rpc chunks with length > max_packet_size
-        if length > self.max_packet_size {
+        // Should not attempt to decode rpc chunks with `length > max_packet_size` or not within
+        // the bounds of the SSZ encoding of a request for the protocol. This check happens before
+        // allocating the buffer, so that a malicious length prefix cannot exhaust our memory.
+        if length > self.max_packet_size
+            || self.protocol.rpc_request_limits().is_out_of_bounds(length)
+        {
             return Err(RPCError::InvalidData);
         }
-        let mut reader = FrameDecoder::new(Cursor::new(&src));
+
+        // Only read up to the worst-case compressed length of `length` bytes from `src`.
+        let max_compressed_len = snap::raw::max_compress_len(length) as u64;
+        let limit_reader = Cursor::new(src.as_ref()).take(max_compressed_len);
+        let mut reader = FrameDecoder::new(limit_reader);
         let mut decoded_buffer = vec![0; length];
 
         match reader.read_exact(&mut decoded_buffer) {
             Ok(()) => {
                 // `n` is how many bytes the reader read in the compressed stream
-                let n = reader.get_ref().position();
+                let n = reader.get_ref().get_ref().position();
                 self.len = None;
                 let _read_bytes = src.split_to(n as usize);
                 match self.protocol.message_name {
@@ -125,29 +138,34 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyInboundCodec<TSpec> {
                         Version::V1 => Ok(Some(RPCRequest::Status(StatusMessage::from_ssz_bytes(
                             &decoded_buffer,
                         )?))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::Goodbye => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCRequest::Goodbye(
                             GoodbyeReason::from_ssz_bytes(&decoded_buffer)?,
                         ))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::BlocksByRange => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCRequest::BlocksByRange(
                             BlocksByRangeRequest::from_ssz_bytes(&decoded_buffer)?,
                         ))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::BlocksByRoot => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCRequest::BlocksByRoot(BlocksByRootRequest {
                             block_roots: Vec::from_ssz_bytes(&decoded_buffer)?,
                         }))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::Ping => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCRequest::Ping(Ping::from_ssz_bytes(
                             &decoded_buffer,
                         )?))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::MetaData => match self.protocol.version {
-                        Version::V1 => {
+                        Version::V1 | Version::V2 => {
                             if decoded_buffer.len() > 0 {
                                 Err(RPCError::InvalidData)
                             } else {
@@ -155,16 +173,15 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyInboundCodec<TSpec> {
                             }
                         }
                     },
+                    Protocol::PeerExchange => match self.protocol.version {
+                        Version::V1 => Ok(Some(RPCRequest::PeerExchange(
+                            PeerExchange::from_ssz_bytes(&decoded_buffer)?,
+                        ))),
+                        Version::V2 => Err(RPCError::InvalidData),
+                    },
                 }
             }
-            Err(e) => match e.kind() {
-                // Haven't received enough bytes to decode yet
-                // TODO: check if this is the only Error variant where we return `Ok(None)`
-                ErrorKind::UnexpectedEof => {
-                    return Ok(None);
-                }
-                _ => return Err(e).map_err(RPCError::from),
-            },
+            Err(e) => handle_error(e, reader.get_ref().get_ref().position(), max_compressed_len),
         }
     }
 }
@@ -176,6 +193,8 @@ pub struct SSZSnappyOutboundCodec<TSpec: EthSpec> {
     protocol: ProtocolId,
     /// Maximum bytes that can be sent in one req/resp chunked responses.
     max_packet_size: usize,
+    /// The bounds on the length of successful responses, which are computed once per stream.
+    response_limits: RpcLimits,
     phantom: PhantomData<TSpec>,
 }
 
@@ -187,6 +206,7 @@ impl<TSpec: EthSpec> SSZSnappyOutboundCodec<TSpec> {
 
         SSZSnappyOutboundCodec {
             inner: uvi_codec,
+            response_limits: protocol.rpc_response_limits::<TSpec>(),
             protocol,
             max_packet_size,
             len: None,
@@ -207,6 +227,7 @@ impl<TSpec: EthSpec> Encoder<RPCRequest<TSpec>> for SSZSnappyOutboundCodec<TSpec
             RPCRequest::BlocksByRoot(req) => req.block_roots.as_ssz_bytes(),
             RPCRequest::Ping(req) => req.as_ssz_bytes(),
             RPCRequest::MetaData(_) => return Ok(()), // no metadata to encode
+            RPCRequest::PeerExchange(req) => req.as_ssz_bytes(),
         };
         // SSZ encoded bytes should be within `max_packet_size`
         if bytes.len() > self.max_packet_size {
@@ -253,16 +274,22 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyOutboundCodec<TSpec> {
 
         let length = self.len.expect("length should be Some");
 
-        // Should not attempt to decode rpc chunks with length > max_packet_size
-        if length > self.max_packet_size {
+        // Should not attempt to decode rpc chunks with `length > max_packet_size` or not within
+        // the bounds of the SSZ encoding of a response for the protocol. This check happens before
+        // allocating the buffer, so that a malicious length prefix cannot exhaust our memory.
+        if length > self.max_packet_size || self.response_limits.is_out_of_bounds(length) {
             return Err(RPCError::InvalidData);
         }
-        let mut reader = FrameDecoder::new(Cursor::new(&src));
+
+        // Only read up to the worst-case compressed length of `length` bytes from `src`.
+        let max_compressed_len = snap::raw::max_compress_len(length) as u64;
+        let limit_reader = Cursor::new(src.as_ref()).take(max_compressed_len);
+        let mut reader = FrameDecoder::new(limit_reader);
         let mut decoded_buffer = vec![0; length];
         match reader.read_exact(&mut decoded_buffer) {
             Ok(()) => {
                 // `n` is how many bytes the reader read in the compressed stream
-                let n = reader.get_ref().position();
+                let n = reader.get_ref().get_ref().position();
                 self.len = None;
                 let _read_byts = src.split_to(n as usize);
                 match self.protocol.message_name {
@@ -270,41 +297,41 @@ impl<TSpec: EthSpec> Decoder for SSZSnappyOutboundCodec<TSpec> {
                         Version::V1 => Ok(Some(RPCResponse::Status(
                             StatusMessage::from_ssz_bytes(&decoded_buffer)?,
                         ))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
-                    Protocol::Goodbye => {
-                        // Goodbye does not have a response
+                    Protocol::Goodbye | Protocol::PeerExchange => {
+                        // Goodbye and PeerExchange do not have a response
                         Err(RPCError::InvalidData)
                     }
                     Protocol::BlocksByRange => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCResponse::BlocksByRange(Box::new(
                             SignedBeaconBlock::from_ssz_bytes(&decoded_buffer)?,
                         )))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::BlocksByRoot => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCResponse::BlocksByRoot(Box::new(
                             SignedBeaconBlock::from_ssz_bytes(&decoded_buffer)?,
                         )))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::Ping => match self.protocol.version {
                         Version::V1 => Ok(Some(RPCResponse::Pong(Ping {
                             data: u64::from_ssz_bytes(&decoded_buffer)?,
                         }))),
+                        Version::V2 => Err(RPCError::InvalidData),
                     },
                     Protocol::MetaData => match self.protocol.version {
-                        Version::V1 => Ok(Some(RPCResponse::MetaData(MetaData::from_ssz_bytes(
-                            &decoded_buffer,
-                        )?))),
+                        Version::V1 => Ok(Some(RPCResponse::MetaData(MetaData::V1(
+                            MetaDataV1::from_ssz_bytes(&decoded_buffer)?,
+                        )))),
+                        Version::V2 => Ok(Some(RPCResponse::MetaData(MetaData::V2(
+                            MetaDataV2::from_ssz_bytes(&decoded_buffer)?,
+                        )))),
                     },
                 }
             }
-            Err(e) => match e.kind() {
-                // Haven't received enough bytes to decode yet
-                // TODO: check if this is the only Error variant where we return `Ok(None)`
-                ErrorKind::UnexpectedEof => {
-                    return Ok(None);
-                }
-                _ => return Err(e).map_err(RPCError::from),
-            },
+            Err(e) => handle_error(e, reader.get_ref().get_ref().position(), max_compressed_len),
         }
     }
 }
@@ -325,30 +352,54 @@ impl<TSpec: EthSpec> OutboundCodec<RPCRequest<TSpec>> for SSZSnappyOutboundCodec
 
         let length = self.len.expect("length should be Some");
 
-        // Should not attempt to decode rpc chunks with length > max_packet_size
-        if length > self.max_packet_size {
+        // Should not attempt to decode rpc chunks with `length > max_packet_size` or not within
+        // the bounds of the SSZ encoding of an error response for the protocol. This check happens before
+        // allocating the buffer, so that a malicious length prefix cannot exhaust our memory.
+        if length > self.max_packet_size || ProtocolId::rpc_error_limits().is_out_of_bounds(length)
+        {
             return Err(RPCError::InvalidData);
         }
-        let mut reader = FrameDecoder::new(Cursor::new(&src));
+
+        // Only read up to the worst-case compressed length of `length` bytes from `src`.
+        let max_compressed_len = snap::raw::max_compress_len(length) as u64;
+        let limit_reader = Cursor::new(src.as_ref()).take(max_compressed_len);
+        let mut reader = FrameDecoder::new(limit_reader);
         let mut decoded_buffer = vec![0; length];
         match reader.read_exact(&mut decoded_buffer) {
             Ok(()) => {
                 // `n` is how many bytes the reader read in the compressed stream
-                let n = reader.get_ref().position();
+                let n = reader.get_ref().get_ref().position();
                 self.len = None;
                 let _read_bytes = src.split_to(n as usize);
                 Ok(Some(
                     String::from_utf8_lossy(&<Vec<u8>>::from_ssz_bytes(&decoded_buffer)?).into(),
                 ))
             }
-            Err(e) => match e.kind() {
-                // Haven't received enough bytes to decode yet
-                // TODO: check if this is the only Error variant where we return `Ok(None)`
-                ErrorKind::UnexpectedEof => {
-                    return Ok(None);
-                }
-                _ => return Err(e).map_err(RPCError::from),
-            },
+            Err(e) => handle_error(e, reader.get_ref().get_ref().position(), max_compressed_len),
+        }
+    }
+}
+
+/// Handles an error from the snappy decoder whilst reading an RPC chunk from `src`.
+///
+/// `num_bytes` is the number of bytes the decoder has read from `src` and `max_compressed_len` is
+/// the worst-case compressed length of the chunk.
+fn handle_error<T>(
+    err: std::io::Error,
+    num_bytes: u64,
+    max_compressed_len: u64,
+) -> Result<Option<T>, RPCError> {
+    match err.kind() {
+        ErrorKind::UnexpectedEof => {
+            if num_bytes >= max_compressed_len {
+                // The decoder has read more bytes than any valid encoding of the chunk could
+                // contain, the peer is sending us garbage.
+                Err(RPCError::InvalidData)
+            } else {
+                // Haven't received enough bytes to decode yet, wait for more.
+                Ok(None)
+            }
         }
+        _ => Err(err).map_err(RPCError::from),
     }
 }
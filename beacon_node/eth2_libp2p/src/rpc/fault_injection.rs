@@ -0,0 +1,249 @@
+//! Injects faults into the RPC responses we send, so that the sync and peer scoring of our peers
+//! can be exercised in integration tests and local devnets.
+//!
+//! Only compiled with the `network-testing` feature. The faults are held behind a shared handle
+//! and may be changed whilst the node is running.
+
+use super::{RPCCodedResponse, RPCResponse};
+use parking_lot::RwLock;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::time::{delay_until, Delay};
+use types::{EthSpec, Hash256};
+
+/// The faults to inject into the RPC responses we send. No faults are injected by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RPCFaults {
+    /// The probability, between 0 and 1, that a response chunk is silently dropped.
+    pub drop_probability: f64,
+    /// The time each response chunk is held for before it is sent.
+    pub latency: Duration,
+    /// The probability, between 0 and 1, that the block in a response chunk is corrupted so that
+    /// it fails processing.
+    pub corrupt_probability: f64,
+}
+
+/// Parses a comma-separated list of faults, e.g. `drop=0.1,latency_ms=500,corrupt=0.05`.
+/// Faults which are not listed are disabled.
+impl FromStr for RPCFaults {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut faults = RPCFaults::default();
+
+        for fault in string.split(',').filter(|fault| !fault.is_empty()) {
+            let mut parts = fault.splitn(2, '=');
+            let (name, value) = match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) => (name.trim(), value.trim()),
+                _ => return Err(format!("Fault must be of the form name=value: {}", fault)),
+            };
+
+            match name {
+                "drop" => faults.drop_probability = parse_probability(value)?,
+                "corrupt" => faults.corrupt_probability = parse_probability(value)?,
+                "latency_ms" => {
+                    faults.latency = Duration::from_millis(
+                        value
+                            .parse()
+                            .map_err(|e| format!("Invalid latency {}: {:?}", value, e))?,
+                    )
+                }
+                other => return Err(format!("Unknown RPC fault: {}", other)),
+            }
+        }
+
+        Ok(faults)
+    }
+}
+
+fn parse_probability(value: &str) -> Result<f64, String> {
+    let probability = value
+        .parse::<f64>()
+        .map_err(|e| format!("Invalid probability {}: {:?}", value, e))?;
+    if probability >= 0.0 && probability <= 1.0 {
+        Ok(probability)
+    } else {
+        Err(format!("Probability must be between 0 and 1: {}", value))
+    }
+}
+
+/// A handle to the faults injected by the RPC behaviour, which may be changed at runtime.
+pub type RPCFaultsHandle = Arc<RwLock<RPCFaults>>;
+
+/// Applies the current `RPCFaults` to the responses sent by the RPC behaviour. Each response is
+/// paired with its destination `D`.
+pub(super) struct FaultInjector<T: EthSpec, D> {
+    faults: RPCFaultsHandle,
+    /// Responses held back by the latency fault, along with the time they may be sent.
+    delayed: VecDeque<(Instant, D, RPCCodedResponse<T>)>,
+    /// Wakes the behaviour when the first delayed response may be sent.
+    timer: Option<Delay>,
+}
+
+impl<T: EthSpec, D> FaultInjector<T, D> {
+    pub fn new() -> Self {
+        Self {
+            faults: Arc::new(RwLock::new(RPCFaults::default())),
+            delayed: VecDeque::new(),
+            timer: None,
+        }
+    }
+
+    /// A handle to the faults which are injected.
+    pub fn faults(&self) -> RPCFaultsHandle {
+        self.faults.clone()
+    }
+
+    /// Applies the faults to a response which is about to be sent.
+    ///
+    /// Returns the response if it should be sent now, otherwise it has been dropped or delayed.
+    pub fn inject(
+        &mut self,
+        destination: D,
+        mut response: RPCCodedResponse<T>,
+    ) -> Option<(D, RPCCodedResponse<T>)> {
+        let faults = self.faults.read().clone();
+        let mut rng = rand::thread_rng();
+
+        if faults.drop_probability > 0.0 && rng.gen_bool(faults.drop_probability) {
+            return None;
+        }
+
+        if faults.corrupt_probability > 0.0 && rng.gen_bool(faults.corrupt_probability) {
+            corrupt(&mut response);
+        }
+
+        // responses queued behind a delayed response must wait for it, to preserve their order
+        if faults.latency > Duration::from_secs(0) || !self.delayed.is_empty() {
+            self.delayed
+                .push_back((Instant::now() + faults.latency, destination, response));
+            return None;
+        }
+
+        Some((destination, response))
+    }
+
+    /// Returns the next delayed response which may now be sent.
+    pub fn poll_delayed(&mut self, cx: &mut Context) -> Poll<(D, RPCCodedResponse<T>)> {
+        while let Some((send_at, _, _)) = self.delayed.front() {
+            if *send_at <= Instant::now() {
+                self.timer = None;
+                if let Some((_, destination, response)) = self.delayed.pop_front() {
+                    return Poll::Ready((destination, response));
+                }
+            }
+
+            let send_at = tokio::time::Instant::from_std(*send_at);
+            let timer = self.timer.get_or_insert_with(|| delay_until(send_at));
+            if timer.deadline() != send_at {
+                timer.reset(send_at);
+            }
+            match Pin::new(timer).poll(cx) {
+                Poll::Ready(()) => continue,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Corrupts the block in a response, if any, by changing its state root. Other responses are not
+/// modified.
+fn corrupt<T: EthSpec>(response: &mut RPCCodedResponse<T>) {
+    match response {
+        RPCCodedResponse::Success(RPCResponse::BlocksByRange(block))
+        | RPCCodedResponse::Success(RPCResponse::BlocksByRoot(block)) => {
+            block.message.state_root = Hash256::repeat_byte(0xde);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::methods::Ping;
+    use types::{BeaconBlock, MinimalEthSpec, Signature, SignedBeaconBlock};
+
+    type E = MinimalEthSpec;
+
+    fn pong() -> RPCCodedResponse<E> {
+        RPCCodedResponse::Success(RPCResponse::Pong(Ping { data: 1 }))
+    }
+
+    #[test]
+    fn parse_faults() {
+        assert_eq!(
+            "drop=0.1,latency_ms=500,corrupt=1".parse::<RPCFaults>(),
+            Ok(RPCFaults {
+                drop_probability: 0.1,
+                latency: Duration::from_millis(500),
+                corrupt_probability: 1.0,
+            })
+        );
+        assert_eq!("".parse::<RPCFaults>(), Ok(RPCFaults::default()));
+        assert!("drop=2".parse::<RPCFaults>().is_err());
+        assert!("latency=5".parse::<RPCFaults>().is_err());
+        assert!("drop".parse::<RPCFaults>().is_err());
+    }
+
+    #[test]
+    fn no_faults_by_default() {
+        let mut injector = FaultInjector::<E, u64>::new();
+        assert!(injector.inject(0, pong()).is_some());
+    }
+
+    #[test]
+    fn drop_responses() {
+        let mut injector = FaultInjector::<E, u64>::new();
+        injector.faults().write().drop_probability = 1.0;
+        assert!(injector.inject(0, pong()).is_none());
+        assert!(injector.delayed.is_empty());
+    }
+
+    #[test]
+    fn corrupt_blocks() {
+        let mut injector = FaultInjector::<E, u64>::new();
+        injector.faults().write().corrupt_probability = 1.0;
+
+        let block = SignedBeaconBlock {
+            message: BeaconBlock::empty(&E::default_spec()),
+            signature: Signature::empty_signature(),
+        };
+        let response =
+            RPCCodedResponse::Success(RPCResponse::BlocksByRange(Box::new(block.clone())));
+
+        match injector.inject(0, response) {
+            Some((_, RPCCodedResponse::Success(RPCResponse::BlocksByRange(corrupted)))) => {
+                assert_ne!(corrupted.message.state_root, block.message.state_root)
+            }
+            _ => panic!("the corrupted block should be sent"),
+        }
+    }
+
+    #[tokio::test]
+    async fn delay_responses_in_order() {
+        let mut injector = FaultInjector::<E, u64>::new();
+        injector.faults().write().latency = Duration::from_millis(50);
+
+        assert!(injector.inject(0, pong()).is_none());
+        assert!(injector.inject(1, pong()).is_none());
+        // later responses without latency wait behind the delayed responses
+        injector.faults().write().latency = Duration::from_secs(0);
+        assert!(injector.inject(2, pong()).is_none());
+
+        let start = Instant::now();
+        for expected in 0..3 {
+            let (destination, _) = futures::future::poll_fn(|cx| injector.poll_delayed(cx)).await;
+            assert_eq!(destination, expected);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+}
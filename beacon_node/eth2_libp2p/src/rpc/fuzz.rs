@@ -0,0 +1,77 @@
+//! Entry points for fuzzing the RPC codecs with arbitrary bytes from a peer.
+//!
+//! Only compiled with the `fuzzing` feature. See the `fuzz` directory of this crate for the
+//! `cargo-fuzz` targets which use these functions.
+
+use super::codec::base::{BaseInboundCodec, BaseOutboundCodec};
+use super::codec::ssz::{SSZInboundCodec, SSZOutboundCodec};
+use super::codec::ssz_snappy::{SSZSnappyInboundCodec, SSZSnappyOutboundCodec};
+use super::codec::{InboundCodec, OutboundCodec};
+use super::protocol::{Encoding, ProtocolId};
+use super::RPCProtocol;
+use crate::NetworkConstants;
+use libp2p::bytes::BytesMut;
+use libp2p::core::UpgradeInfo;
+use std::marker::PhantomData;
+use tokio_util::codec::Decoder;
+use types::EthSpec;
+
+/// Decodes `data` as a stream of requests from a peer, for each of the supported protocols.
+pub fn decode_inbound<T: EthSpec>(data: &[u8]) {
+    for protocol in supported_protocols::<T>() {
+        let codec = match protocol.encoding {
+            Encoding::SSZSnappy => InboundCodec::SSZSnappy(BaseInboundCodec::new(
+                SSZSnappyInboundCodec::<T>::new(protocol, max_rpc_size()),
+            )),
+            Encoding::SSZ => InboundCodec::SSZ(BaseInboundCodec::new(SSZInboundCodec::<T>::new(
+                protocol,
+                max_rpc_size(),
+            ))),
+        };
+        decode_all(codec, data);
+    }
+}
+
+/// Decodes `data` as a stream of responses from a peer, for each of the supported protocols.
+pub fn decode_outbound<T: EthSpec>(data: &[u8]) {
+    for protocol in supported_protocols::<T>() {
+        let codec = match protocol.encoding {
+            Encoding::SSZSnappy => OutboundCodec::SSZSnappy(BaseOutboundCodec::new(
+                SSZSnappyOutboundCodec::<T>::new(protocol, max_rpc_size()),
+            )),
+            Encoding::SSZ => OutboundCodec::SSZ(BaseOutboundCodec::new(
+                SSZOutboundCodec::<T>::new(protocol, max_rpc_size()),
+            )),
+        };
+        decode_all(codec, data);
+    }
+}
+
+fn supported_protocols<T: EthSpec>() -> Vec<ProtocolId> {
+    let constants = NetworkConstants::default();
+    RPCProtocol::<T> {
+        max_rpc_size: constants.max_chunk_size,
+        ttfb_timeout: constants.ttfb_timeout,
+        resp_timeout: constants.resp_timeout,
+        phantom: PhantomData,
+    }
+    .protocol_info()
+}
+
+fn max_rpc_size() -> usize {
+    NetworkConstants::default().max_chunk_size
+}
+
+/// Decodes items from `data` until the codec errors, requires more bytes or stops consuming
+/// bytes.
+fn decode_all<D: Decoder>(mut codec: D, data: &[u8]) {
+    let mut src = BytesMut::from(data);
+
+    loop {
+        let remaining = src.len();
+        match codec.decode(&mut src) {
+            Ok(Some(_)) if !src.is_empty() && src.len() < remaining => continue,
+            _ => break,
+        }
+    }
+}
@@ -2,7 +2,7 @@
 #![allow(clippy::cognitive_complexity)]
 
 use super::methods::{RPCCodedResponse, RequestId, ResponseTermination};
-use super::protocol::{Protocol, RPCError, RPCProtocol, RPCRequest};
+use super::protocol::{OutboundRequestContainer, Protocol, RPCError, RPCProtocol, RPCRequest};
 use super::{RPCReceived, RPCSend};
 use crate::rpc::protocol::{InboundFramed, OutboundFramed};
 use fnv::FnvHashMap;
@@ -27,9 +27,6 @@ use types::EthSpec;
 
 //TODO: Implement check_timeout() on the substream types
 
-/// The time (in seconds) before a substream that is awaiting a response from the user times out.
-pub const RESPONSE_TIMEOUT: u64 = 10;
-
 /// The number of times to retry an outbound upgrade in the case of IO errors.
 const IO_ERROR_RETRIES: u8 = 3;
 
@@ -40,6 +37,13 @@ const SHUTDOWN_TIMEOUT_SECS: u8 = 15;
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub struct SubstreamId(usize);
 
+#[cfg(test)]
+impl SubstreamId {
+    pub(crate) fn new(id: usize) -> Self {
+        SubstreamId(id)
+    }
+}
+
 /// An error encoutered by the handler.
 pub enum HandlerErr {
     /// An error ocurred for this peer's request. This can occurr during protocol negotiation,
@@ -153,6 +157,9 @@ struct OutboundInfo<TSpec: EthSpec> {
     delay_key: delay_queue::Key,
     /// Info over the protocol this substream is handling.
     proto: Protocol,
+    /// Whether a chunk of the peer's response has been received, after which the substream's
+    /// timeout applies to each subsequent chunk.
+    first_chunk_received: bool,
     /// Number of chunks to be seen from the peer's response.
     // TODO: removing the option could allow clossing the streams after the number of
     // expected responses is met for all protocols.
@@ -321,8 +328,9 @@ where
         &mut self.listen_protocol
     }
 
-    /// Initiates the handler's shutdown process, sending an optional last message to the peer.
-    pub fn shutdown(&mut self, final_msg: Option<(RequestId, RPCRequest<TSpec>)>) {
+    /// Initiates the handler's shutdown process, sending the given final messages (if any) to the
+    /// peer in order.
+    pub fn shutdown(&mut self, final_msgs: Vec<(RequestId, RPCRequest<TSpec>)>) {
         if matches!(self.state, HandlerState::Active) {
             debug!(self.log, "Starting handler shutdown"; "unsent_queued_requests" => self.dial_queue.len());
             // we now drive to completion communications already dialed/established
@@ -334,10 +342,8 @@ where
                 })
             }
 
-            // Queue our final message, if any
-            if let Some((id, req)) = final_msg {
-                self.dial_queue.push((id, req));
-            }
+            // Queue our final messages, if any
+            self.dial_queue.extend(final_msgs);
 
             self.state = HandlerState::ShuttingDown(delay_until(
                 TInstant::now() + Duration::from_secs(SHUTDOWN_TIMEOUT_SECS as u64),
@@ -386,6 +392,7 @@ where
         match response {
             RPCCodedResponse::InvalidRequest(ref reason)
             | RPCCodedResponse::ServerError(ref reason)
+            | RPCCodedResponse::RateLimited(ref reason)
             | RPCCodedResponse::Unknown(ref reason) => {
                 let code = &response
                     .error_code()
@@ -526,7 +533,7 @@ where
     type OutEvent = Result<RPCReceived<TSpec>, HandlerErr>;
     type Error = RPCError;
     type InboundProtocol = RPCProtocol<TSpec>;
-    type OutboundProtocol = RPCRequest<TSpec>;
+    type OutboundProtocol = OutboundRequestContainer<TSpec>;
     type OutboundOpenInfo = (RequestId, RPCRequest<TSpec>); // Keep track of the id and the request
 
     fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol> {
@@ -549,7 +556,7 @@ where
             // Store the stream and tag the output.
             let delay_key = self.inbound_substreams_delay.insert(
                 self.current_inbound_substream_id,
-                Duration::from_secs(RESPONSE_TIMEOUT),
+                self.listen_protocol.upgrade().resp_timeout,
             );
             let awaiting_stream = InboundSubstreamState::ResponseIdle(substream);
             self.inbound_substreams.insert(
@@ -588,9 +595,13 @@ where
         let expected_responses = request.expected_responses();
         if expected_responses > 0 {
             // new outbound request. Store the stream and tag the output.
+            //
+            // The peer has `ttfb_timeout` to start responding and then `resp_timeout` to send the
+            // first chunk. Each subsequent chunk must arrive within `resp_timeout` of the last.
+            let protocol = self.listen_protocol.upgrade();
             let delay_key = self.outbound_substreams_delay.insert(
                 self.current_outbound_substream_id,
-                Duration::from_secs(RESPONSE_TIMEOUT),
+                protocol.ttfb_timeout + protocol.resp_timeout,
             );
             let awaiting_stream = OutboundSubstreamState::RequestPendingResponse {
                 substream: out,
@@ -610,6 +621,7 @@ where
                         state: awaiting_stream,
                         delay_key,
                         proto,
+                        first_chunk_received: false,
                         remaining_chunks: expected_responses,
                         req_id: id,
                     },
@@ -763,15 +775,25 @@ where
         loop {
             match self.outbound_substreams_delay.poll_next_unpin(cx) {
                 Poll::Ready(Some(Ok(outbound_id))) => {
-                    if let Some(OutboundInfo { proto, req_id, .. }) =
-                        self.outbound_substreams.remove(outbound_id.get_ref())
+                    if let Some(OutboundInfo {
+                        proto,
+                        req_id,
+                        first_chunk_received,
+                        ..
+                    }) = self.outbound_substreams.remove(outbound_id.get_ref())
                     {
                         self.update_keep_alive();
 
+                        // distinguish an unresponsive peer from one which is merely slow
+                        let error = if first_chunk_received {
+                            RPCError::ChunkTimeout
+                        } else {
+                            RPCError::TTFBTimeout
+                        };
                         let outbound_err = HandlerErr::Outbound {
                             id: req_id,
                             proto,
-                            error: RPCError::StreamTimeout,
+                            error,
                         };
                         // notify the user
                         return Poll::Ready(ProtocolsHandlerEvent::Custom(Err(outbound_err)));
@@ -1025,6 +1047,7 @@ where
                     Poll::Ready(Some(Ok(response))) => {
                         if request.expected_responses() > 1 && !response.is_error() {
                             let substream_entry = entry.get_mut();
+                            substream_entry.first_chunk_received = true;
                             let delay_key = &substream_entry.delay_key;
                             // chunks left after this one
                             let remaining_chunks = substream_entry
@@ -1043,7 +1066,7 @@ where
                                     };
                                 substream_entry.remaining_chunks = Some(remaining_chunks);
                                 self.outbound_substreams_delay
-                                    .reset(delay_key, Duration::from_secs(RESPONSE_TIMEOUT));
+                                    .reset(delay_key, self.listen_protocol.upgrade().resp_timeout);
                             }
                         } else {
                             // either this is a single response request or we received an
@@ -1062,6 +1085,7 @@ where
                             RPCCodedResponse::Success(resp) => Ok(RPCReceived::Response(id, resp)),
                             RPCCodedResponse::InvalidRequest(ref r)
                             | RPCCodedResponse::ServerError(ref r)
+                            | RPCCodedResponse::RateLimited(ref r)
                             | RPCCodedResponse::Unknown(ref r) => {
                                 let code = response.error_code().expect(
                                     "Response indicating and error should map to an error code",
@@ -1169,7 +1193,10 @@ where
             self.dial_queue.shrink_to_fit();
             self.update_keep_alive();
             return Poll::Ready(ProtocolsHandlerEvent::OutboundSubstreamRequest {
-                protocol: SubstreamProtocol::new(req.clone()),
+                protocol: SubstreamProtocol::new(OutboundRequestContainer {
+                    req: req.clone(),
+                    max_rpc_size: self.listen_protocol.upgrade().max_rpc_size,
+                }),
                 info: (id, req),
             });
         }
@@ -1,6 +1,7 @@
 //! Available RPC methods types and ids.
 
-use crate::types::EnrBitfield;
+use crate::types::{EnrBitfield, SyncnetsBitfield};
+use crate::Enr;
 use serde::Serialize;
 use ssz_derive::{Decode, Encode};
 use types::{Epoch, EthSpec, Hash256, SignedBeaconBlock, Slot};
@@ -13,7 +14,7 @@ use types::{Epoch, EthSpec, Hash256, SignedBeaconBlock, Slot};
 ///
 // NOTE: The handler stores the `RequestId` to inform back of responses and errors, but it's execution
 // is independent of the contents on this type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RequestId {
     Router,
     Sync(usize),
@@ -46,22 +47,103 @@ pub struct Ping {
     pub data: u64,
 }
 
-/// The METADATA response structure.
+/// The METADATA response structure of version 1 of the protocol.
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Serialize)]
 #[serde(bound = "T: EthSpec")]
-pub struct MetaData<T: EthSpec> {
+pub struct MetaDataV1<T: EthSpec> {
     /// A sequential counter indicating when data gets modified.
     pub seq_number: u64,
     /// The persistent subnet bitfield.
     pub attnets: EnrBitfield<T>,
 }
 
+/// The METADATA response structure of version 2 of the protocol, which adds the sync committee
+/// subnets.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Serialize)]
+#[serde(bound = "T: EthSpec")]
+pub struct MetaDataV2<T: EthSpec> {
+    /// A sequential counter indicating when data gets modified.
+    pub seq_number: u64,
+    /// The persistent subnet bitfield.
+    pub attnets: EnrBitfield<T>,
+    /// The persistent sync committee subnet bitfield.
+    pub syncnets: SyncnetsBitfield<T>,
+}
+
+/// The METADATA of a peer, in whichever version of the protocol it was received.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(bound = "T: EthSpec", untagged)]
+pub enum MetaData<T: EthSpec> {
+    V1(MetaDataV1<T>),
+    V2(MetaDataV2<T>),
+}
+
+impl<T: EthSpec> MetaData<T> {
+    /// A sequential counter indicating when data gets modified.
+    pub fn seq_number(&self) -> u64 {
+        match self {
+            MetaData::V1(meta_data) => meta_data.seq_number,
+            MetaData::V2(meta_data) => meta_data.seq_number,
+        }
+    }
+
+    /// The persistent subnet bitfield.
+    pub fn attnets(&self) -> &EnrBitfield<T> {
+        match self {
+            MetaData::V1(meta_data) => &meta_data.attnets,
+            MetaData::V2(meta_data) => &meta_data.attnets,
+        }
+    }
+
+    /// The persistent sync committee subnet bitfield, if the peer sent a version 2 METADATA.
+    pub fn syncnets(&self) -> Option<&SyncnetsBitfield<T>> {
+        match self {
+            MetaData::V1(_) => None,
+            MetaData::V2(meta_data) => Some(&meta_data.syncnets),
+        }
+    }
+
+    /// Bumps the sequence number and replaces the subnet bitfield.
+    pub fn set_attnets(&mut self, attnets: EnrBitfield<T>) {
+        match self {
+            MetaData::V1(meta_data) => {
+                meta_data.seq_number += 1;
+                meta_data.attnets = attnets;
+            }
+            MetaData::V2(meta_data) => {
+                meta_data.seq_number += 1;
+                meta_data.attnets = attnets;
+            }
+        }
+    }
+
+    /// Returns the version 1 METADATA, dropping the sync committee subnets.
+    pub fn v1(&self) -> MetaDataV1<T> {
+        MetaDataV1 {
+            seq_number: self.seq_number(),
+            attnets: self.attnets().clone(),
+        }
+    }
+
+    /// Returns the version 2 METADATA. A version 1 METADATA has no sync committee subnets.
+    pub fn v2(&self) -> MetaDataV2<T> {
+        MetaDataV2 {
+            seq_number: self.seq_number(),
+            attnets: self.attnets().clone(),
+            syncnets: self
+                .syncnets()
+                .cloned()
+                .unwrap_or_else(SyncnetsBitfield::<T>::new),
+        }
+    }
+}
+
 /// The reason given for a `Goodbye` message.
 ///
 /// Note: any unknown `u64::into(n)` will resolve to `Goodbye::Unknown` for any unknown `n`,
 /// however `GoodbyeReason::Unknown.into()` will go into `0_u64`. Therefore de-serializing then
 /// re-serializing may not return the same bytes.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum GoodbyeReason {
     /// This node has shutdown.
     ClientShutdown = 1,
@@ -72,6 +154,9 @@ pub enum GoodbyeReason {
     /// Error/fault in the RPC.
     Fault = 3,
 
+    /// The node has reached its peer limit. A `PeerExchange` may have been sent beforehand.
+    TooManyPeers = 129,
+
     /// Unknown reason.
     Unknown = 0,
 }
@@ -82,6 +167,7 @@ impl From<u64> for GoodbyeReason {
             1 => GoodbyeReason::ClientShutdown,
             2 => GoodbyeReason::IrrelevantNetwork,
             3 => GoodbyeReason::Fault,
+            129 => GoodbyeReason::TooManyPeers,
             _ => GoodbyeReason::Unknown,
         }
     }
@@ -150,6 +236,75 @@ pub struct BlocksByRootRequest {
     pub block_roots: Vec<Hash256>,
 }
 
+/// The maximum number of ENRs sent in a single `PeerExchange`.
+pub const MAX_PEER_EXCHANGE_ENRS: usize = 16;
+
+/// The maximum size of an RLP-encoded ENR, as defined by EIP-778.
+pub const MAX_ENR_SIZE: usize = 300;
+
+/// A list of peers, sent to a peer that is about to be disconnected due to `TooManyPeers` so
+/// that it can find other peers to connect to.
+///
+/// Each ENR is encoded as its RLP bytes within an SSZ list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerExchange {
+    /// The ENRs of peers the sender considers good.
+    pub enrs: Vec<Enr>,
+}
+
+impl ssz::Encode for PeerExchange {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        self.rlp_encoded().ssz_bytes_len()
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        self.rlp_encoded().ssz_append(buf)
+    }
+}
+
+impl ssz::Decode for PeerExchange {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+        let encoded_enrs = <Vec<Vec<u8>>>::from_ssz_bytes(bytes)?;
+
+        if encoded_enrs.len() > MAX_PEER_EXCHANGE_ENRS {
+            return Err(ssz::DecodeError::BytesInvalid(format!(
+                "Too many ENRs in peer exchange: {}",
+                encoded_enrs.len()
+            )));
+        }
+
+        let enrs = encoded_enrs
+            .iter()
+            .map(|bytes| {
+                if bytes.len() > MAX_ENR_SIZE {
+                    return Err(ssz::DecodeError::BytesInvalid(format!(
+                        "ENR exceeds maximum size: {}",
+                        bytes.len()
+                    )));
+                }
+                rlp::decode(bytes)
+                    .map_err(|e| ssz::DecodeError::BytesInvalid(format!("Invalid ENR: {:?}", e)))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PeerExchange { enrs })
+    }
+}
+
+impl PeerExchange {
+    fn rlp_encoded(&self) -> Vec<Vec<u8>> {
+        self.enrs.iter().map(|enr| rlp::encode(enr)).collect()
+    }
+}
+
 /* RPC Handling and Grouping */
 // Collection of enums and structs used by the Codecs to encode/decode RPC messages
 
@@ -195,6 +350,9 @@ pub enum RPCCodedResponse<T: EthSpec> {
     /// The response indicates a server error.
     ServerError(String),
 
+    /// The request was not served as the responder is sending too much data.
+    RateLimited(String),
+
     /// There was an unknown response.
     Unknown(String),
 
@@ -207,6 +365,7 @@ pub enum RPCCodedResponse<T: EthSpec> {
 pub enum RPCResponseErrorCode {
     InvalidRequest,
     ServerError,
+    RateLimited,
     Unknown,
 }
 
@@ -217,6 +376,7 @@ impl<T: EthSpec> RPCCodedResponse<T> {
             RPCCodedResponse::Success(_) => Some(0),
             RPCCodedResponse::InvalidRequest(_) => Some(1),
             RPCCodedResponse::ServerError(_) => Some(2),
+            RPCCodedResponse::RateLimited(_) => Some(139),
             RPCCodedResponse::Unknown(_) => Some(255),
             RPCCodedResponse::StreamTermination(_) => None,
         }
@@ -235,6 +395,7 @@ impl<T: EthSpec> RPCCodedResponse<T> {
         match response_code {
             1 => RPCCodedResponse::InvalidRequest(err),
             2 => RPCCodedResponse::ServerError(err),
+            139 => RPCCodedResponse::RateLimited(err),
             _ => RPCCodedResponse::Unknown(err),
         }
     }
@@ -244,6 +405,7 @@ impl<T: EthSpec> RPCCodedResponse<T> {
         match response_code {
             RPCResponseErrorCode::InvalidRequest => RPCCodedResponse::InvalidRequest(err),
             RPCResponseErrorCode::ServerError => RPCCodedResponse::ServerError(err),
+            RPCResponseErrorCode::RateLimited => RPCCodedResponse::RateLimited(err),
             RPCResponseErrorCode::Unknown => RPCCodedResponse::Unknown(err),
         }
     }
@@ -260,6 +422,7 @@ impl<T: EthSpec> RPCCodedResponse<T> {
             },
             RPCCodedResponse::InvalidRequest(_) => true,
             RPCCodedResponse::ServerError(_) => true,
+            RPCCodedResponse::RateLimited(_) => true,
             RPCCodedResponse::Unknown(_) => true,
             // Stream terminations are part of responses that have chunks
             RPCCodedResponse::StreamTermination(_) => true,
@@ -281,6 +444,7 @@ impl<T: EthSpec> RPCCodedResponse<T> {
             RPCCodedResponse::StreamTermination(_) => None,
             RPCCodedResponse::InvalidRequest(_) => Some(RPCResponseErrorCode::InvalidRequest),
             RPCCodedResponse::ServerError(_) => Some(RPCResponseErrorCode::ServerError),
+            RPCCodedResponse::RateLimited(_) => Some(RPCResponseErrorCode::RateLimited),
             RPCCodedResponse::Unknown(_) => Some(RPCResponseErrorCode::Unknown),
         }
     }
@@ -291,6 +455,7 @@ impl std::fmt::Display for RPCResponseErrorCode {
         let repr = match self {
             RPCResponseErrorCode::InvalidRequest => "The request was invalid",
             RPCResponseErrorCode::ServerError => "Server error occurred",
+            RPCResponseErrorCode::RateLimited => "The request was rate limited",
             RPCResponseErrorCode::Unknown => "Unknown error occurred",
         };
         f.write_str(repr)
@@ -314,7 +479,7 @@ impl<T: EthSpec> std::fmt::Display for RPCResponse<T> {
                 write!(f, "BlocksByRoot: BLock slot: {}", block.message.slot)
             }
             RPCResponse::Pong(ping) => write!(f, "Pong: {}", ping.data),
-            RPCResponse::MetaData(metadata) => write!(f, "Metadata: {}", metadata.seq_number),
+            RPCResponse::MetaData(metadata) => write!(f, "Metadata: {}", metadata.seq_number()),
         }
     }
 }
@@ -325,6 +490,7 @@ impl<T: EthSpec> std::fmt::Display for RPCCodedResponse<T> {
             RPCCodedResponse::Success(res) => write!(f, "{}", res),
             RPCCodedResponse::InvalidRequest(err) => write!(f, "Invalid Request: {:?}", err),
             RPCCodedResponse::ServerError(err) => write!(f, "Server Error: {:?}", err),
+            RPCCodedResponse::RateLimited(err) => write!(f, "Rate Limited: {:?}", err),
             RPCCodedResponse::Unknown(err) => write!(f, "Unknown Error: {:?}", err),
             RPCCodedResponse::StreamTermination(_) => write!(f, "Stream Termination"),
         }
@@ -337,6 +503,7 @@ impl std::fmt::Display for GoodbyeReason {
             GoodbyeReason::ClientShutdown => write!(f, "Client Shutdown"),
             GoodbyeReason::IrrelevantNetwork => write!(f, "Irrelevant Network"),
             GoodbyeReason::Fault => write!(f, "Fault"),
+            GoodbyeReason::TooManyPeers => write!(f, "Too Many Peers"),
             GoodbyeReason::Unknown => write!(f, "Unknown Reason"),
         }
     }
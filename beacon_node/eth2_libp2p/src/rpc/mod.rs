@@ -4,6 +4,7 @@
 //! direct peer-to-peer communication primarily for sending/receiving chain information for
 //! syncing.
 
+use crate::NetworkConstants;
 use handler::RPCHandler;
 use libp2p::core::{connection::ConnectionId, ConnectedPoint};
 use libp2p::swarm::{
@@ -18,17 +19,21 @@ use std::time::Duration;
 use types::EthSpec;
 
 pub(crate) use handler::HandlerErr;
-pub(crate) use methods::{MetaData, Ping, RPCCodedResponse, RPCResponse};
+pub(crate) use methods::{MetaData, MetaDataV1, MetaDataV2, Ping, RPCCodedResponse, RPCResponse};
 pub(crate) use protocol::{RPCProtocol, RPCRequest};
 
 pub use handler::SubstreamId;
 pub use methods::{
-    BlocksByRangeRequest, BlocksByRootRequest, GoodbyeReason, RPCResponseErrorCode, RequestId,
-    ResponseTermination, StatusMessage,
+    BlocksByRangeRequest, BlocksByRootRequest, GoodbyeReason, PeerExchange, RPCResponseErrorCode,
+    RequestId, ResponseTermination, StatusMessage,
 };
-pub use protocol::{Protocol, RPCError};
+pub use protocol::{Protocol, RPCError, MAX_REQUEST_BLOCKS};
 
 pub(crate) mod codec;
+#[cfg(feature = "network-testing")]
+pub mod fault_injection;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
 mod handler;
 pub mod methods;
 mod protocol;
@@ -91,15 +96,23 @@ pub struct RPCMessage<TSpec: EthSpec> {
 pub struct RPC<TSpec: EthSpec> {
     /// Queue of events to be processed.
     events: Vec<NetworkBehaviourAction<RPCSend<TSpec>, RPCMessage<TSpec>>>,
+    /// The size limits and timeouts applied to RPC messages.
+    constants: NetworkConstants,
+    /// Drops, delays and corrupts the responses we send, for testing.
+    #[cfg(feature = "network-testing")]
+    fault_injector: fault_injection::FaultInjector<TSpec, (PeerId, (ConnectionId, SubstreamId))>,
     /// Slog logger for RPC behaviour.
     log: slog::Logger,
 }
 
 impl<TSpec: EthSpec> RPC<TSpec> {
-    pub fn new(log: slog::Logger) -> Self {
+    pub fn new(constants: NetworkConstants, log: slog::Logger) -> Self {
         let log = log.new(o!("service" => "libp2p_rpc"));
         RPC {
             events: Vec::new(),
+            constants,
+            #[cfg(feature = "network-testing")]
+            fault_injector: fault_injection::FaultInjector::new(),
             log,
         }
     }
@@ -112,6 +125,22 @@ impl<TSpec: EthSpec> RPC<TSpec> {
         peer_id: PeerId,
         id: (ConnectionId, SubstreamId),
         event: RPCCodedResponse<TSpec>,
+    ) {
+        #[cfg(feature = "network-testing")]
+        let ((peer_id, id), event) = match self.fault_injector.inject((peer_id, id), event) {
+            Some(response) => response,
+            None => return,
+        };
+
+        self.notify_response(peer_id, id, event);
+    }
+
+    /// Passes a response to the handler of the connection it is to be sent over.
+    fn notify_response(
+        &mut self,
+        peer_id: PeerId,
+        id: (ConnectionId, SubstreamId),
+        event: RPCCodedResponse<TSpec>,
     ) {
         self.events.push(NetworkBehaviourAction::NotifyHandler {
             peer_id,
@@ -120,6 +149,13 @@ impl<TSpec: EthSpec> RPC<TSpec> {
         });
     }
 
+    /// A handle to the faults injected into the responses we send, which may be changed at
+    /// runtime.
+    #[cfg(feature = "network-testing")]
+    pub fn faults(&self) -> fault_injection::RPCFaultsHandle {
+        self.fault_injector.faults()
+    }
+
     /// Submits an RPC request.
     ///
     /// The peer must be connected for this to succeed.
@@ -147,6 +183,9 @@ where
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
         RPCHandler::new(
             SubstreamProtocol::new(RPCProtocol {
+                max_rpc_size: self.constants.max_chunk_size,
+                ttfb_timeout: self.constants.ttfb_timeout,
+                resp_timeout: self.constants.resp_timeout,
                 phantom: PhantomData,
             }),
             Duration::from_secs(30),
@@ -214,6 +253,11 @@ where
             Self::OutEvent,
         >,
     > {
+        #[cfg(feature = "network-testing")]
+        while let Poll::Ready(((peer_id, id), event)) = self.fault_injector.poll_delayed(_cx) {
+            self.notify_response(peer_id, id, event);
+        }
+
         if !self.events.is_empty() {
             return Poll::Ready(self.events.remove(0));
         }
@@ -14,6 +14,7 @@ use futures::future::Ready;
 use futures::prelude::*;
 use futures::prelude::{AsyncRead, AsyncWrite};
 use libp2p::core::{InboundUpgrade, OutboundUpgrade, ProtocolName, UpgradeInfo};
+use ssz::Encode;
 use std::io;
 use std::marker::PhantomData;
 use std::pin::Pin;
@@ -23,20 +24,17 @@ use tokio_util::{
     codec::Framed,
     compat::{Compat, FuturesAsyncReadCompatExt},
 };
-use types::EthSpec;
+use types::{BeaconBlock, EthSpec, Hash256, Signature, SignedBeaconBlock};
 
-/// The maximum bytes that can be sent across the RPC.
-const MAX_RPC_SIZE: usize = 1_048_576; // 1M
+/// The maximum number of block roots in a `BlocksByRoot` request.
+pub const MAX_REQUEST_BLOCKS: usize = 1024;
+/// The maximum length of the error message in an RPC error response.
+pub const MAX_ERROR_LEN: usize = 256;
 /// The protocol prefix the RPC protocol id.
 const PROTOCOL_PREFIX: &str = "/eth2/beacon_chain/req";
-/// Time allowed for the first byte of a request to arrive before we time out (Time To First Byte).
-const TTFB_TIMEOUT: u64 = 5;
-/// The number of seconds to wait for the first bytes of a request once a protocol has been
-/// established before the stream is terminated.
-const REQUEST_TIMEOUT: u64 = 15;
 
 /// Protocol names to be used.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Protocol {
     /// The Status protocol name.
     Status,
@@ -50,6 +48,10 @@ pub enum Protocol {
     Ping,
     /// The `MetaData` protocol name.
     MetaData,
+    /// The `PeerExchange` protocol name.
+    ///
+    /// This is a Lighthouse extension and is not part of the specification.
+    PeerExchange,
 }
 
 /// RPC Versions
@@ -57,6 +59,8 @@ pub enum Protocol {
 pub enum Version {
     /// Version 1 of RPC
     V1,
+    /// Version 2 of RPC, which only the `MetaData` protocol supports.
+    V2,
 }
 
 /// RPC Encondings supported.
@@ -75,6 +79,7 @@ impl std::fmt::Display for Protocol {
             Protocol::BlocksByRoot => "beacon_blocks_by_root",
             Protocol::Ping => "ping",
             Protocol::MetaData => "metadata",
+            Protocol::PeerExchange => "peer_exchange",
         };
         f.write_str(repr)
     }
@@ -94,6 +99,7 @@ impl std::fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let repr = match self {
             Version::V1 => "1",
+            Version::V2 => "2",
         };
         f.write_str(repr)
     }
@@ -101,6 +107,14 @@ impl std::fmt::Display for Version {
 
 #[derive(Debug, Clone)]
 pub struct RPCProtocol<TSpec: EthSpec> {
+    /// The maximum number of bytes in a request.
+    pub max_rpc_size: usize,
+    /// Time allowed for the first byte of a request to arrive before we time out (Time To First
+    /// Byte).
+    pub ttfb_timeout: Duration,
+    /// Time allowed for the entire request to arrive once the protocol has been established,
+    /// before the stream is terminated.
+    pub resp_timeout: Duration,
     pub phantom: PhantomData<TSpec>,
 }
 
@@ -121,8 +135,12 @@ impl<TSpec: EthSpec> UpgradeInfo for RPCProtocol<TSpec> {
             ProtocolId::new(Protocol::BlocksByRoot, Version::V1, Encoding::SSZ),
             ProtocolId::new(Protocol::Ping, Version::V1, Encoding::SSZSnappy),
             ProtocolId::new(Protocol::Ping, Version::V1, Encoding::SSZ),
+            ProtocolId::new(Protocol::MetaData, Version::V2, Encoding::SSZSnappy),
+            ProtocolId::new(Protocol::MetaData, Version::V2, Encoding::SSZ),
             ProtocolId::new(Protocol::MetaData, Version::V1, Encoding::SSZSnappy),
             ProtocolId::new(Protocol::MetaData, Version::V1, Encoding::SSZ),
+            ProtocolId::new(Protocol::PeerExchange, Version::V1, Encoding::SSZSnappy),
+            ProtocolId::new(Protocol::PeerExchange, Version::V1, Encoding::SSZ),
         ]
     }
 }
@@ -160,6 +178,111 @@ impl ProtocolId {
     }
 }
 
+/// The bounds on the length of the SSZ bytes of an RPC message.
+#[derive(Debug, PartialEq)]
+pub struct RpcLimits {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl RpcLimits {
+    pub fn new(min: usize, max: usize) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the bounds on the length of a `SignedBeaconBlock` under `T`, from an empty block to
+    /// one holding the maximum number of each operation.
+    ///
+    /// Note: this builds and encodes a full block, so it should not be called for every message.
+    pub fn signed_beacon_block<T: EthSpec>() -> Self {
+        let spec = T::default_spec();
+        let signed_block_len = |message| {
+            SignedBeaconBlock::<T> {
+                message,
+                signature: Signature::empty_signature(),
+            }
+            .ssz_bytes_len()
+        };
+
+        Self::new(
+            signed_block_len(BeaconBlock::empty(&spec)),
+            signed_block_len(BeaconBlock::full(&spec)),
+        )
+    }
+
+    /// Returns `true` if a message of `length` bytes cannot be valid.
+    pub fn is_out_of_bounds(&self, length: usize) -> bool {
+        length < self.min || length > self.max
+    }
+}
+
+impl ProtocolId {
+    /// Returns the bounds on the length of requests for this protocol.
+    pub fn rpc_request_limits(&self) -> RpcLimits {
+        match self.message_name {
+            Protocol::Status => RpcLimits::new(
+                <StatusMessage as Encode>::ssz_fixed_len(),
+                <StatusMessage as Encode>::ssz_fixed_len(),
+            ),
+            Protocol::Goodbye => RpcLimits::new(
+                <GoodbyeReason as Encode>::ssz_fixed_len(),
+                <GoodbyeReason as Encode>::ssz_fixed_len(),
+            ),
+            Protocol::BlocksByRange => RpcLimits::new(
+                <BlocksByRangeRequest as Encode>::ssz_fixed_len(),
+                <BlocksByRangeRequest as Encode>::ssz_fixed_len(),
+            ),
+            Protocol::BlocksByRoot => {
+                RpcLimits::new(0, MAX_REQUEST_BLOCKS * <Hash256 as Encode>::ssz_fixed_len())
+            }
+            Protocol::Ping => RpcLimits::new(
+                <Ping as Encode>::ssz_fixed_len(),
+                <Ping as Encode>::ssz_fixed_len(),
+            ),
+            Protocol::MetaData => RpcLimits::new(0, 0),
+            Protocol::PeerExchange => RpcLimits::new(
+                0,
+                MAX_PEER_EXCHANGE_ENRS * (MAX_ENR_SIZE + ssz::BYTES_PER_LENGTH_OFFSET),
+            ),
+        }
+    }
+
+    /// Returns the bounds on the length of successful responses for this protocol, under the
+    /// presets of `T`.
+    pub fn rpc_response_limits<T: EthSpec>(&self) -> RpcLimits {
+        match self.message_name {
+            Protocol::Status => RpcLimits::new(
+                <StatusMessage as Encode>::ssz_fixed_len(),
+                <StatusMessage as Encode>::ssz_fixed_len(),
+            ),
+            // Goodbye and PeerExchange do not have a response.
+            Protocol::Goodbye | Protocol::PeerExchange => RpcLimits::new(0, 0),
+            Protocol::BlocksByRange | Protocol::BlocksByRoot => {
+                RpcLimits::signed_beacon_block::<T>()
+            }
+            Protocol::Ping => RpcLimits::new(
+                <Ping as Encode>::ssz_fixed_len(),
+                <Ping as Encode>::ssz_fixed_len(),
+            ),
+            Protocol::MetaData => match self.version {
+                Version::V1 => RpcLimits::new(
+                    <MetaDataV1<T> as Encode>::ssz_fixed_len(),
+                    <MetaDataV1<T> as Encode>::ssz_fixed_len(),
+                ),
+                Version::V2 => RpcLimits::new(
+                    <MetaDataV2<T> as Encode>::ssz_fixed_len(),
+                    <MetaDataV2<T> as Encode>::ssz_fixed_len(),
+                ),
+            },
+        }
+    }
+
+    /// Returns the bounds on the length of error responses.
+    pub fn rpc_error_limits() -> RpcLimits {
+        RpcLimits::new(0, MAX_ERROR_LEN)
+    }
+}
+
 impl ProtocolName for ProtocolId {
     fn protocol_name(&self) -> &[u8] {
         self.protocol_id.as_bytes()
@@ -198,16 +321,17 @@ where
         let codec = match protocol.encoding {
             Encoding::SSZSnappy => {
                 let ssz_snappy_codec =
-                    BaseInboundCodec::new(SSZSnappyInboundCodec::new(protocol, MAX_RPC_SIZE));
+                    BaseInboundCodec::new(SSZSnappyInboundCodec::new(protocol, self.max_rpc_size));
                 InboundCodec::SSZSnappy(ssz_snappy_codec)
             }
             Encoding::SSZ => {
-                let ssz_codec = BaseInboundCodec::new(SSZInboundCodec::new(protocol, MAX_RPC_SIZE));
+                let ssz_codec =
+                    BaseInboundCodec::new(SSZInboundCodec::new(protocol, self.max_rpc_size));
                 InboundCodec::SSZ(ssz_codec)
             }
         };
         let mut timed_socket = TimeoutStream::new(socket);
-        timed_socket.set_read_timeout(Some(Duration::from_secs(TTFB_TIMEOUT)));
+        timed_socket.set_read_timeout(Some(self.ttfb_timeout));
 
         let socket = Framed::new(timed_socket, codec);
 
@@ -218,7 +342,7 @@ where
             }
 
             _ => future::Either::Right(
-                tokio::time::timeout(Duration::from_secs(REQUEST_TIMEOUT), socket.into_future())
+                tokio::time::timeout(self.resp_timeout, socket.into_future())
                     .map_err(RPCError::from as FnMapErr)
                     .and_then({
                         |(req, stream)| match req {
@@ -244,16 +368,7 @@ pub enum RPCRequest<TSpec: EthSpec> {
     BlocksByRoot(BlocksByRootRequest),
     Ping(Ping),
     MetaData(PhantomData<TSpec>),
-}
-
-impl<TSpec: EthSpec> UpgradeInfo for RPCRequest<TSpec> {
-    type Info = ProtocolId;
-    type InfoIter = Vec<Self::Info>;
-
-    // add further protocols as we support more encodings/versions
-    fn protocol_info(&self) -> Self::InfoIter {
-        self.supported_protocols()
-    }
+    PeerExchange(PeerExchange),
 }
 
 /// Implements the encoding per supported protocol for `RPCRequest`.
@@ -281,10 +396,17 @@ impl<TSpec: EthSpec> RPCRequest<TSpec> {
                 ProtocolId::new(Protocol::Ping, Version::V1, Encoding::SSZSnappy),
                 ProtocolId::new(Protocol::Ping, Version::V1, Encoding::SSZ),
             ],
+            // the second version is preferred, falling back to the first for peers without it
             RPCRequest::MetaData(_) => vec![
+                ProtocolId::new(Protocol::MetaData, Version::V2, Encoding::SSZSnappy),
+                ProtocolId::new(Protocol::MetaData, Version::V2, Encoding::SSZ),
                 ProtocolId::new(Protocol::MetaData, Version::V1, Encoding::SSZSnappy),
                 ProtocolId::new(Protocol::MetaData, Version::V1, Encoding::SSZ),
             ],
+            RPCRequest::PeerExchange(_) => vec![
+                ProtocolId::new(Protocol::PeerExchange, Version::V1, Encoding::SSZSnappy),
+                ProtocolId::new(Protocol::PeerExchange, Version::V1, Encoding::SSZ),
+            ],
         }
     }
 
@@ -299,6 +421,7 @@ impl<TSpec: EthSpec> RPCRequest<TSpec> {
             RPCRequest::BlocksByRoot(req) => req.block_roots.len(),
             RPCRequest::Ping(_) => 1,
             RPCRequest::MetaData(_) => 1,
+            RPCRequest::PeerExchange(_) => 0,
         }
     }
 
@@ -311,6 +434,7 @@ impl<TSpec: EthSpec> RPCRequest<TSpec> {
             RPCRequest::BlocksByRoot(_) => Protocol::BlocksByRoot,
             RPCRequest::Ping(_) => Protocol::Ping,
             RPCRequest::MetaData(_) => Protocol::MetaData,
+            RPCRequest::PeerExchange(_) => Protocol::PeerExchange,
         }
     }
 
@@ -326,6 +450,7 @@ impl<TSpec: EthSpec> RPCRequest<TSpec> {
             RPCRequest::Goodbye(_) => unreachable!(),
             RPCRequest::Ping(_) => unreachable!(),
             RPCRequest::MetaData(_) => unreachable!(),
+            RPCRequest::PeerExchange(_) => unreachable!(),
         }
     }
 }
@@ -334,9 +459,27 @@ impl<TSpec: EthSpec> RPCRequest<TSpec> {
 
 /* Outbound upgrades */
 
+/// Wraps an `RPCRequest` with the limits to apply to the responses to the request.
+#[derive(Debug, Clone)]
+pub struct OutboundRequestContainer<TSpec: EthSpec> {
+    pub req: RPCRequest<TSpec>,
+    /// The maximum number of bytes in a response chunk.
+    pub max_rpc_size: usize,
+}
+
+impl<TSpec: EthSpec> UpgradeInfo for OutboundRequestContainer<TSpec> {
+    type Info = ProtocolId;
+    type InfoIter = Vec<Self::Info>;
+
+    // add further protocols as we support more encodings/versions
+    fn protocol_info(&self) -> Self::InfoIter {
+        self.req.supported_protocols()
+    }
+}
+
 pub type OutboundFramed<TSocket, TSpec> = Framed<Compat<TSocket>, OutboundCodec<TSpec>>;
 
-impl<TSocket, TSpec> OutboundUpgrade<TSocket> for RPCRequest<TSpec>
+impl<TSocket, TSpec> OutboundUpgrade<TSocket> for OutboundRequestContainer<TSpec>
 where
     TSpec: EthSpec + Send + 'static,
     TSocket: AsyncRead + AsyncWrite + Unpin + Send + 'static,
@@ -350,20 +493,22 @@ where
         let socket = socket.compat();
         let codec = match protocol.encoding {
             Encoding::SSZSnappy => {
-                let ssz_snappy_codec =
-                    BaseOutboundCodec::new(SSZSnappyOutboundCodec::new(protocol, MAX_RPC_SIZE));
+                let ssz_snappy_codec = BaseOutboundCodec::new(SSZSnappyOutboundCodec::new(
+                    protocol,
+                    self.max_rpc_size,
+                ));
                 OutboundCodec::SSZSnappy(ssz_snappy_codec)
             }
             Encoding::SSZ => {
                 let ssz_codec =
-                    BaseOutboundCodec::new(SSZOutboundCodec::new(protocol, MAX_RPC_SIZE));
+                    BaseOutboundCodec::new(SSZOutboundCodec::new(protocol, self.max_rpc_size));
                 OutboundCodec::SSZ(ssz_codec)
             }
         };
 
         let mut socket = Framed::new(socket, codec);
 
-        let future = async { socket.send(self).await.map(|_| socket) };
+        let future = async { socket.send(self.req).await.map(|_| socket) };
         Box::pin(future)
     }
 }
@@ -380,6 +525,10 @@ pub enum RPCError {
     ErrorResponse(RPCResponseErrorCode, String),
     /// Timed out waiting for a response.
     StreamTimeout,
+    /// The peer did not send the first chunk of its response in time.
+    TTFBTimeout,
+    /// The peer started responding, but did not send the next chunk of its response in time.
+    ChunkTimeout,
     /// Peer does not support the protocol.
     UnsupportedProtocol,
     /// Stream ended unexpectedly.
@@ -394,6 +543,26 @@ pub enum RPCError {
     HandlerRejected,
 }
 
+impl RPCError {
+    /// The kind of error, used to label metrics.
+    pub fn as_static_str(&self) -> &'static str {
+        match self {
+            RPCError::SSZDecodeError(_) => "ssz_decode_error",
+            RPCError::IoError(_) => "io_error",
+            RPCError::ErrorResponse(_, _) => "error_response",
+            RPCError::StreamTimeout => "stream_timeout",
+            RPCError::TTFBTimeout => "ttfb_timeout",
+            RPCError::ChunkTimeout => "chunk_timeout",
+            RPCError::UnsupportedProtocol => "unsupported_protocol",
+            RPCError::IncompleteStream => "incomplete_stream",
+            RPCError::InvalidData => "invalid_data",
+            RPCError::InternalError(_) => "internal_error",
+            RPCError::NegotiationTimeout => "negotiation_timeout",
+            RPCError::HandlerRejected => "handler_rejected",
+        }
+    }
+}
+
 impl From<ssz::DecodeError> for RPCError {
     #[inline]
     fn from(err: ssz::DecodeError) -> Self {
@@ -425,6 +594,8 @@ impl std::fmt::Display for RPCError {
                 code, reason
             ),
             RPCError::StreamTimeout => write!(f, "Stream Timeout"),
+            RPCError::TTFBTimeout => write!(f, "Timed out waiting for the first response chunk"),
+            RPCError::ChunkTimeout => write!(f, "Timed out waiting for the next response chunk"),
             RPCError::UnsupportedProtocol => write!(f, "Peer does not support the protocol"),
             RPCError::IncompleteStream => write!(f, "Stream ended unexpectedly"),
             RPCError::InternalError(ref err) => write!(f, "Internal error: {}", err),
@@ -441,6 +612,8 @@ impl std::error::Error for RPCError {
             RPCError::SSZDecodeError(_) => None,
             RPCError::IoError(_) => None,
             RPCError::StreamTimeout => None,
+            RPCError::TTFBTimeout => None,
+            RPCError::ChunkTimeout => None,
             RPCError::UnsupportedProtocol => None,
             RPCError::IncompleteStream => None,
             RPCError::InvalidData => None,
@@ -461,6 +634,7 @@ impl<TSpec: EthSpec> std::fmt::Display for RPCRequest<TSpec> {
             RPCRequest::BlocksByRoot(req) => write!(f, "Blocks by root: {:?}", req),
             RPCRequest::Ping(ping) => write!(f, "Ping: {}", ping.data),
             RPCRequest::MetaData(_) => write!(f, "MetaData request"),
+            RPCRequest::PeerExchange(req) => write!(f, "Peer exchange: {} ENRs", req.enrs.len()),
         }
     }
 }
@@ -1,12 +1,14 @@
 use crate::behaviour::{Behaviour, BehaviourEvent, PeerRequestId, Request, Response};
 use crate::discovery::enr;
 use crate::multiaddr::Protocol;
+use crate::peer_manager::{BanList, DisconnectCause, Membership};
 use crate::rpc::{RPCResponseErrorCode, RequestId};
 use crate::types::{error, GossipKind};
 use crate::EnrExt;
 use crate::{NetworkConfig, NetworkGlobals};
 use futures::prelude::*;
 use libp2p::core::{
+    connection::ConnectionError,
     identity::Keypair,
     multiaddr::Multiaddr,
     muxing::StreamMuxerBox,
@@ -23,6 +25,7 @@ use slog::{crit, debug, info, o, trace, warn};
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{Error, ErrorKind};
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
@@ -73,8 +76,9 @@ pub struct Service<TSpec: EthSpec> {
     /// Used for managing the state of peers.
     network_globals: Arc<NetworkGlobals<TSpec>>,
 
-    /// A current list of peers to ban after a given timeout.
-    peers_to_ban: DelayQueue<PeerId>,
+    /// A current list of peers to ban after a given timeout, along with the duration of the ban if
+    /// the peer is not already banned in the `PeerDB`.
+    peers_to_ban: DelayQueue<(PeerId, Option<Duration>)>,
 
     /// A list of timeouts after which peers become unbanned.
     peer_ban_timeout: DelayQueue<PeerId>,
@@ -100,17 +104,73 @@ impl<TSpec: EthSpec> Service<TSpec> {
         let enr =
             enr::build_or_load_enr::<TSpec>(local_keypair.clone(), config, enr_fork_id, &log)?;
 
-        let local_peer_id = enr.peer_id();
         // set up a collection of variables accessible outside of the network crate
         let network_globals = Arc::new(NetworkGlobals::new(
-            enr.clone(),
+            enr,
             config.libp2p_port,
             config.discovery_port,
             &log,
         ));
 
-        info!(log, "Libp2p Service"; "peer_id" => format!("{:?}", enr.peer_id()));
-        debug!(log, "Attempting to open listening ports"; "address" => format!("{}", config.listen_address), "tcp_port" => config.libp2p_port, "udp_port" => config.discovery_port);
+        let service = Self::build(
+            executor,
+            config,
+            local_keypair,
+            network_globals.clone(),
+            log,
+        )?;
+
+        Ok((network_globals, service))
+    }
+
+    /// Builds a libp2p stack to replace one which has been dropped, e.g. to listen on new ports or
+    /// to recover from a wedged transport. The previous stack must be dropped first, so that its
+    /// ports are released. Its `NetworkGlobals` are kept, so that the `PeerDB` survives.
+    pub fn restart(
+        executor: environment::TaskExecutor,
+        config: &NetworkConfig,
+        enr_fork_id: EnrForkId,
+        network_globals: Arc<NetworkGlobals<TSpec>>,
+        log: &slog::Logger,
+    ) -> error::Result<Self> {
+        let log = log.new(o!("service"=> "libp2p"));
+        info!(log, "Libp2p Service restarting");
+
+        let local_keypair = load_private_key(config, &log);
+        let enr =
+            enr::build_or_load_enr::<TSpec>(local_keypair.clone(), config, enr_fork_id, &log)?;
+        network_globals.restart(enr, config.libp2p_port, config.discovery_port);
+
+        let mut service = Self::build(executor, config, local_keypair, network_globals, log)?;
+
+        // the bans of the previous swarm are lost with it
+        let banned_peers = service
+            .network_globals
+            .peers
+            .read()
+            .banned_peers()
+            .cloned()
+            .collect::<Vec<_>>();
+        for peer_id in banned_peers {
+            Swarm::ban_peer_id(&mut service.swarm, peer_id);
+        }
+
+        Ok(service)
+    }
+
+    /// Builds the swarm for the local ENR in `network_globals`, listens on the configured
+    /// addresses, dials the boot nodes and subscribes to the configured topics.
+    fn build(
+        executor: environment::TaskExecutor,
+        config: &NetworkConfig,
+        local_keypair: Keypair,
+        network_globals: Arc<NetworkGlobals<TSpec>>,
+        log: slog::Logger,
+    ) -> error::Result<Self> {
+        let local_peer_id = network_globals.local_peer_id();
+
+        info!(log, "Libp2p Service"; "peer_id" => format!("{:?}", local_peer_id));
+        debug!(log, "Attempting to open listening ports"; "addresses" => format!("{:?}", config.libp2p_listen_addresses().collect::<Vec<_>>()), "tcp_port" => config.libp2p_port, "discovery_address" => format!("{}", config.discovery_listen_address()), "udp_port" => config.discovery_port);
 
         let mut swarm = {
             // Set up the transport - tcp/ws with noise/secio and mplex/yamux
@@ -132,29 +192,48 @@ impl<TSpec: EthSpec> Service<TSpec> {
                 .build()
         };
 
-        // listen on the specified address
-        let listen_multiaddr = {
-            let mut m = Multiaddr::from(config.listen_address);
-            m.push(Protocol::Tcp(config.libp2p_port));
-            m
-        };
-
-        match Swarm::listen_on(&mut swarm, listen_multiaddr.clone()) {
-            Ok(_) => {
-                let mut log_address = listen_multiaddr;
-                log_address.push(Protocol::P2p(local_peer_id.clone().into()));
-                info!(log, "Listening established"; "address" => format!("{}", log_address));
+        // load the ban list and ban the listed peers, until their ban expires if it does
+        if let Some(ban_list_file) = &config.ban_list_file {
+            let ban_list = BanList::load(ban_list_file.clone())?;
+            info!(log, "Loaded ban list"; "file" => format!("{:?}", ban_list_file), "entries" => ban_list.entries().len());
+            network_globals.peers.write().set_ban_list(ban_list);
+        }
+        let mut peer_ban_timeout = DelayQueue::new();
+        {
+            let peerdb = network_globals.peers.read();
+            for peer_id in peerdb.ban_list().peers() {
+                Swarm::ban_peer_id(&mut swarm, peer_id.clone());
             }
-            Err(err) => {
-                crit!(
-                    log,
-                    "Unable to listen on libp2p address";
-                    "error" => format!("{:?}", err),
-                    "listen_multiaddr" => format!("{}", listen_multiaddr),
-                );
-                return Err("Libp2p was unable to listen on the given listen address.".into());
+            for (peer_id, remaining) in peerdb.ban_list().expiring_peers() {
+                peer_ban_timeout.insert(peer_id.clone(), remaining);
             }
-        };
+        }
+
+        // listen on each of the specified addresses
+        for listen_address in config.libp2p_listen_addresses() {
+            let listen_multiaddr = {
+                let mut m = Multiaddr::from(listen_address);
+                m.push(Protocol::Tcp(config.libp2p_port));
+                m
+            };
+
+            match Swarm::listen_on(&mut swarm, listen_multiaddr.clone()) {
+                Ok(_) => {
+                    let mut log_address = listen_multiaddr;
+                    log_address.push(Protocol::P2p(local_peer_id.clone().into()));
+                    info!(log, "Listening established"; "address" => format!("{}", log_address));
+                }
+                Err(err) => {
+                    crit!(
+                        log,
+                        "Unable to listen on libp2p address";
+                        "error" => format!("{:?}", err),
+                        "listen_multiaddr" => format!("{}", listen_multiaddr),
+                    );
+                    return Err("Libp2p was unable to listen on the given listen address.".into());
+                }
+            };
+        }
 
         // helper closure for dialing peers
         let mut dial_addr = |multiaddr: &Multiaddr| {
@@ -195,32 +274,35 @@ impl<TSpec: EthSpec> Service<TSpec> {
         }
 
         let mut subscribed_topics: Vec<GossipKind> = vec![];
-        for topic_kind in &config.topics {
+        for topic_kind in config
+            .topics
+            .iter()
+            .filter(|kind| !config.disabled_topics.contains(kind))
+        {
             if swarm.subscribe_kind(topic_kind.clone()) {
                 subscribed_topics.push(topic_kind.clone());
             } else {
                 warn!(log, "Could not subscribe to topic"; "topic" => format!("{}",topic_kind));
             }
         }
-        info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics));
+        swarm.reconcile_forced_topics();
+        info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics), "forced" => format!("{:?}", config.forced_topics), "disabled" => format!("{:?}", config.disabled_topics));
 
-        let service = Service {
+        Ok(Service {
             local_peer_id,
             swarm,
-            network_globals: network_globals.clone(),
+            network_globals,
             peers_to_ban: DelayQueue::new(),
-            peer_ban_timeout: DelayQueue::new(),
+            peer_ban_timeout,
             log,
-        };
-
-        Ok((network_globals, service))
+        })
     }
 
     /// Adds a peer to be banned for a period of time, specified by a timeout.
     pub fn disconnect_and_ban_peer(&mut self, peer_id: PeerId, timeout: Duration) {
         warn!(self.log, "Disconnecting and banning peer"; "peer_id" => peer_id.to_string(), "timeout" => format!("{:?}", timeout));
         self.peers_to_ban.insert(
-            peer_id.clone(),
+            (peer_id.clone(), Some(timeout)),
             Duration::from_millis(BAN_PEER_WAIT_TIMEOUT),
         );
         self.peer_ban_timeout.insert(peer_id, timeout);
@@ -247,11 +329,46 @@ impl<TSpec: EthSpec> Service<TSpec> {
         self.swarm.send_successful_response(peer_id, id, response);
     }
 
+    /// Registers the first connection of a peer, which must exchange a STATUS with us shortly,
+    /// and reports it to the network layer.
+    fn peer_connected(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) -> Libp2pEvent<TSpec> {
+        self.swarm.peer_manager().await_status(&peer_id);
+        // update the peerdb
+        match endpoint {
+            ConnectedPoint::Listener { .. } => {
+                self.swarm.peer_manager().connect_ingoing(&peer_id);
+            }
+            ConnectedPoint::Dialer { .. } => self
+                .network_globals
+                .peers
+                .write()
+                .connect_outgoing(&peer_id),
+        }
+        Libp2pEvent::PeerConnected { peer_id, endpoint }
+    }
+
     pub async fn next_event(&mut self) -> Libp2pEvent<TSpec> {
         loop {
             tokio::select! {
                 event = self.swarm.next_event() => {
                     match event {
+                        SwarmEvent::Behaviour(BehaviourEvent::PeerBanned(peer_id)) => {
+                            // close the peer's connections and refuse new ones once its Goodbye
+                            // has had time to be sent
+                            self.peers_to_ban.insert(
+                                (peer_id, None),
+                                Duration::from_millis(BAN_PEER_WAIT_TIMEOUT),
+                            );
+                        }
+                        SwarmEvent::Behaviour(BehaviourEvent::PeerAdmitted(peer_id, endpoint)) => {
+                            // the peer has proven to be a member of our private network
+                            return self.peer_connected(peer_id, endpoint);
+                        }
+                        SwarmEvent::Behaviour(BehaviourEvent::PeerUnbanned(peer_id)) => {
+                            debug!(self.log, "Peer ban expired, unbanning from the swarm"; "peer_id" => peer_id.to_string());
+                            self.swarm.peer_unbanned(&peer_id);
+                            Swarm::unban_peer_id(&mut self.swarm, peer_id);
+                        }
                         SwarmEvent::Behaviour(behaviour) => {
                             return Libp2pEvent::Behaviour(behaviour)
                         }
@@ -261,22 +378,42 @@ impl<TSpec: EthSpec> Service<TSpec> {
                             num_established,
                         } => {
                             debug!(self.log, "Connection established"; "peer_id" => peer_id.to_string(), "connections" => num_established.get());
-                            // if this is the first connection inform the network layer a new connection
-                            // has been established and update the db
-                            if num_established.get() == 1 {
-                                // update the peerdb
-                                match endpoint {
-                                    ConnectedPoint::Listener { .. } => {
-                                        self.swarm.peer_manager().connect_ingoing(&peer_id);
-                                    }
-                                    ConnectedPoint::Dialer { .. } => self
-                                        .network_globals
-                                        .peers
-                                        .write()
-                                        .connect_outgoing(&peer_id),
+                            // reject peers connecting from a banned IP address or subnet
+                            let remote_addr = match &endpoint {
+                                ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr,
+                                ConnectedPoint::Dialer { address } => address,
+                            };
+                            if ip_of(remote_addr).map_or(false, |ip| {
+                                self.network_globals.peers.read().ban_list().contains_ip(&ip)
+                            }) {
+                                debug!(self.log, "Banning peer connected from a banned address"; "peer_id" => peer_id.to_string(), "address" => remote_addr.to_string());
+                                self.network_globals.peers.write().ban(&peer_id);
+                                Swarm::ban_peer_id(&mut self.swarm, peer_id);
+                                continue;
+                            }
+                            // only the first connection of a peer is reported
+                            if num_established.get() != 1 {
+                                continue;
+                            }
+                            // reject peers which may not join our private network, if any, and
+                            // hold those which have yet to prove that they may
+                            match self
+                                .swarm
+                                .peer_manager()
+                                .admit_to_private_network(&peer_id, &endpoint)
+                            {
+                                Membership::Member => {}
+                                Membership::NonMember => {
+                                    debug!(self.log, "Disconnecting peer outside the private network"; "peer_id" => peer_id.to_string());
+                                    // banning the peer closes its connections, it is unbanned so
+                                    // that it may connect again once its ENR proves it is a member
+                                    Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
+                                    Swarm::unban_peer_id(&mut self.swarm, peer_id);
+                                    continue;
                                 }
-                                return Libp2pEvent::PeerConnected { peer_id, endpoint };
+                                Membership::Unknown => continue,
                             }
+                            return self.peer_connected(peer_id, endpoint);
                         }
                         SwarmEvent::ConnectionClosed {
                             peer_id,
@@ -286,8 +423,17 @@ impl<TSpec: EthSpec> Service<TSpec> {
                         } => {
                             debug!(self.log, "Connection closed"; "peer_id"=> peer_id.to_string(), "cause" => cause.to_string(), "connections" => num_established);
                             if num_established == 0 {
+                                // a peer held until it proved to be a member of our private
+                                // network was never reported as connected
+                                let was_reported =
+                                    !self.swarm.peer_manager().is_unverified(&peer_id);
                                 // update the peer_db
-                                self.swarm.peer_manager().notify_disconnect(&peer_id);
+                                self.swarm
+                                    .peer_manager()
+                                    .notify_disconnect(&peer_id, disconnect_cause(&cause));
+                                if !was_reported {
+                                    continue;
+                                }
                                 // the peer has disconnected
                                 return Libp2pEvent::PeerDisconnected {
                                     peer_id,
@@ -325,7 +471,9 @@ impl<TSpec: EthSpec> Service<TSpec> {
                             attempts_remaining,
                         } => {
                             debug!(self.log, "Failed to dial address"; "peer_id" => peer_id.to_string(), "address" => address.to_string(), "error" => error.to_string(), "attempts_remaining" => attempts_remaining);
-                            self.swarm.peer_manager().notify_disconnect(&peer_id);
+                            self.swarm
+                                .peer_manager()
+                                .notify_disconnect(&peer_id, DisconnectCause::TransportError);
                         }
                         SwarmEvent::UnknownPeerUnreachableAddr { address, error } => {
                             debug!(self.log, "Peer not known at dialed address"; "address" => address.to_string(), "error" => error.to_string());
@@ -345,8 +493,12 @@ impl<TSpec: EthSpec> Service<TSpec> {
                     }
                 }
                 Some(Ok(peer_to_ban)) = self.peers_to_ban.next() => {
-                    let peer_id = peer_to_ban.into_inner();
+                    let (peer_id, ban_duration) = peer_to_ban.into_inner();
                     Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
+                    // peers banned by the peer manager are already banned in the peerdb
+                    if let Some(ban_duration) = ban_duration {
+                        self.network_globals.peers.write().ban_for(&peer_id, ban_duration);
+                    }
                     // TODO: Correctly notify protocols of the disconnect
                     // TODO: Also remove peer from the DHT: https://github.com/sigp/lighthouse/issues/629
                     self.swarm.inject_disconnected(&peer_id);
@@ -354,8 +506,12 @@ impl<TSpec: EthSpec> Service<TSpec> {
                     self.swarm.peer_banned(peer_id);
                 }
                 Some(Ok(peer_to_unban)) = self.peer_ban_timeout.next() => {
-                    debug!(self.log, "Peer has been unbanned"; "peer" => format!("{:?}", peer_to_unban));
                     let unban_peer = peer_to_unban.into_inner();
+                    // peers permanently banned by the operator remain banned
+                    if !self.network_globals.peers.write().unban(&unban_peer) {
+                        continue;
+                    }
+                    debug!(self.log, "Peer has been unbanned"; "peer" => format!("{:?}", unban_peer));
                     self.swarm.peer_unbanned(&unban_peer);
                     Swarm::unban_peer_id(&mut self.swarm, unban_peer);
                 }
@@ -364,6 +520,30 @@ impl<TSpec: EthSpec> Service<TSpec> {
     }
 }
 
+/// Returns the IP address of a multiaddr, if any.
+fn ip_of(multiaddr: &Multiaddr) -> Option<IpAddr> {
+    multiaddr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(ip.into()),
+        Protocol::Ip6(ip) => Some(ip.into()),
+        _ => None,
+    })
+}
+
+/// Classifies a closed connection for which no `Goodbye` was exchanged. The peer closing the
+/// connection surfaces as an unexpected end of the stream, while any other I/O error or a failure
+/// of our own connection handler is a failure of the transport.
+fn disconnect_cause<E>(error: &ConnectionError<E>) -> DisconnectCause {
+    match error {
+        ConnectionError::IO(e) => match e.kind() {
+            ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset | ErrorKind::BrokenPipe => {
+                DisconnectCause::Remote(None)
+            }
+            _ => DisconnectCause::TransportError,
+        },
+        ConnectionError::Handler(_) => DisconnectCause::TransportError,
+    }
+}
+
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, noise/secio as the encryption
 /// layer, and mplex or yamux as the multiplexing layer.
 fn build_transport(
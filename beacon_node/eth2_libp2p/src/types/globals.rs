@@ -1,14 +1,14 @@
 //! A collection of variables that are accessible outside of the network thread itself.
-use crate::peer_manager::PeerDB;
-use crate::rpc::methods::MetaData;
-use crate::types::SyncState;
+use crate::peer_manager::{ClientErrors, PeerDB};
+use crate::rpc::methods::{MetaData, MetaDataV2};
+use crate::types::{SyncState, SyncStatus, SyncnetsBitfield};
 use crate::Client;
 use crate::EnrExt;
 use crate::{Enr, Eth2Enr, GossipTopic, Multiaddr, PeerId};
 use parking_lot::RwLock;
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicU16, Ordering};
-use types::EthSpec;
+use types::{EthSpec, SubnetId};
 
 pub struct NetworkGlobals<TSpec: EthSpec> {
     /// The current local ENR.
@@ -29,17 +29,22 @@ pub struct NetworkGlobals<TSpec: EthSpec> {
     pub gossipsub_subscriptions: RwLock<HashSet<GossipTopic>>,
     /// The current sync status of the node.
     pub sync_state: RwLock<SyncState>,
+    /// The most recent snapshot of the sync manager's internals.
+    pub sync_status: RwLock<SyncStatus>,
+    /// The RPC errors and rejected gossip messages of peers, by the client they run.
+    pub client_errors: RwLock<ClientErrors>,
 }
 
 impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
     pub fn new(enr: Enr, tcp_port: u16, udp_port: u16, log: &slog::Logger) -> Self {
         // set up the local meta data of the node
-        let meta_data = RwLock::new(MetaData {
+        let meta_data = RwLock::new(MetaData::V2(MetaDataV2 {
             seq_number: 0,
             attnets: enr
                 .bitfield::<TSpec>()
                 .expect("Local ENR must have a bitfield specified"),
-        });
+            syncnets: SyncnetsBitfield::<TSpec>::new(),
+        }));
 
         NetworkGlobals {
             local_enr: RwLock::new(enr.clone()),
@@ -51,6 +56,32 @@ impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
             peers: RwLock::new(PeerDB::new(log)),
             gossipsub_subscriptions: RwLock::new(HashSet::new()),
             sync_state: RwLock::new(SyncState::Stalled),
+            sync_status: RwLock::new(SyncStatus::default()),
+            client_errors: RwLock::new(ClientErrors::default()),
+        }
+    }
+
+    /// Prepares the globals for a new libp2p stack replacing the current one. The peers connected
+    /// to or dialed by the current stack are disconnected, but are otherwise kept in the `PeerDB`.
+    pub(crate) fn restart(&self, enr: Enr, tcp_port: u16, udp_port: u16) {
+        self.meta_data.write().set_attnets(
+            enr.bitfield::<TSpec>()
+                .expect("Local ENR must have a bitfield specified"),
+        );
+        *self.peer_id.write() = enr.peer_id();
+        *self.local_enr.write() = enr;
+        self.listen_multiaddrs.write().clear();
+        self.listen_port_tcp.store(tcp_port, Ordering::Relaxed);
+        self.listen_port_udp.store(udp_port, Ordering::Relaxed);
+        self.gossipsub_subscriptions.write().clear();
+
+        let mut peerdb = self.peers.write();
+        let connected = peerdb
+            .connected_or_dialing_peers()
+            .cloned()
+            .collect::<Vec<_>>();
+        for peer_id in connected {
+            peerdb.disconnect(&peer_id);
         }
     }
 
@@ -85,6 +116,12 @@ impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
         self.peers.read().connected_peer_ids().count()
     }
 
+    /// Returns the number of libp2p connected peers which are subscribed to the given
+    /// attestation subnet.
+    pub fn connected_peers_on_subnet(&self, subnet_id: SubnetId) -> usize {
+        self.peers.read().peers_on_subnet(subnet_id).count()
+    }
+
     /// Returns the number of libp2p peers that are either connected or being dialed.
     pub fn connected_or_dialing_peers(&self) -> usize {
         self.peers.read().connected_or_dialing_peers().count()
@@ -100,6 +137,11 @@ impl<TSpec: EthSpec> NetworkGlobals<TSpec> {
         self.sync_state.read().clone()
     }
 
+    /// Returns the most recent snapshot of the sync manager's internals.
+    pub fn sync_status(&self) -> SyncStatus {
+        self.sync_status.read().clone()
+    }
+
     /// Returns a `Client` type if one is known for the `PeerId`.
     pub fn client(&self, peer_id: &PeerId) -> Client {
         self.peers
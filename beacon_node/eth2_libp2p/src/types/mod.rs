@@ -1,6 +1,7 @@
 pub mod error;
 mod globals;
 mod pubsub;
+mod snapshot;
 mod sync_state;
 mod topics;
 
@@ -8,10 +9,17 @@ use types::{BitVector, EthSpec};
 
 #[allow(type_alias_bounds)]
 pub type EnrBitfield<T: EthSpec> = BitVector<T::SubnetBitfieldLength>;
+#[allow(type_alias_bounds)]
+pub type SyncnetsBitfield<T: EthSpec> = BitVector<T::SyncCommitteeSubnetCount>;
 
 pub type Enr = discv5::enr::Enr<discv5::enr::CombinedKey>;
 
 pub use globals::NetworkGlobals;
 pub use pubsub::PubsubMessage;
-pub use sync_state::SyncState;
+pub use snapshot::{
+    NetworkSnapshot, PeerSnapshot, RangeRequestSnapshot, RoutingTableSnapshot, RpcStreamSnapshot,
+};
+pub use sync_state::{
+    BatchState, BatchStatus, ChainStatus, ChainType, SyncMode, SyncState, SyncStatus,
+};
 pub use topics::{GossipEncoding, GossipKind, GossipTopic};
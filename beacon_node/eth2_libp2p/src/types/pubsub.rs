@@ -1,6 +1,5 @@
 //! Handles the encoding and decoding of pubsub messages.
 
-use crate::config::GOSSIP_MAX_SIZE;
 use crate::types::{GossipEncoding, GossipKind, GossipTopic};
 use crate::TopicHash;
 use snap::raw::{decompress_len, Decoder, Encoder};
@@ -59,7 +58,11 @@ impl<T: EthSpec> PubsubMessage<T> {
      * Also note that a message can be associated with many topics. As soon as one of the topics is
      * known we match. If none of the topics are known we return an unknown state.
      */
-    pub fn decode(topics: &[TopicHash], data: &[u8]) -> Result<Self, String> {
+    pub fn decode(
+        topics: &[TopicHash],
+        data: &[u8],
+        gossip_max_size: usize,
+    ) -> Result<Self, String> {
         let mut unknown_topics = Vec::new();
         for topic in topics {
             match GossipTopic::decode(topic.as_str()) {
@@ -73,8 +76,8 @@ impl<T: EthSpec> PubsubMessage<T> {
                         // group each part by encoding type
                         GossipEncoding::SSZSnappy => {
                             match decompress_len(data) {
-                                Ok(n) if n > GOSSIP_MAX_SIZE => {
-                                    return Err("ssz_snappy decoded data > GOSSIP_MAX_SIZE".into());
+                                Ok(n) if n > gossip_max_size => {
+                                    return Err("ssz_snappy decoded data > gossip_max_size".into());
                                 }
                                 Ok(n) => decompressed_data.resize(n, 0),
                                 Err(e) => {
@@ -142,7 +145,11 @@ impl<T: EthSpec> PubsubMessage<T> {
 
     /// Encodes a `PubsubMessage` based on the topic encodings. The first known encoding is used. If
     /// no encoding is known, and error is returned.
-    pub fn encode(&self, encoding: GossipEncoding) -> Result<Vec<u8>, String> {
+    pub fn encode(
+        &self,
+        encoding: GossipEncoding,
+        gossip_max_size: usize,
+    ) -> Result<Vec<u8>, String> {
         let data = match &self {
             PubsubMessage::BeaconBlock(data) => data.as_ssz_bytes(),
             PubsubMessage::AggregateAndProofAttestation(data) => data.as_ssz_bytes(),
@@ -153,8 +160,8 @@ impl<T: EthSpec> PubsubMessage<T> {
         };
         match encoding {
             GossipEncoding::SSZ => {
-                if data.len() > GOSSIP_MAX_SIZE {
-                    return Err("ssz encoded data > GOSSIP_MAX_SIZE".into());
+                if data.len() > gossip_max_size {
+                    return Err("ssz encoded data > gossip_max_size".into());
                 } else {
                     Ok(data)
                 }
@@ -162,8 +169,8 @@ impl<T: EthSpec> PubsubMessage<T> {
             GossipEncoding::SSZSnappy => {
                 let mut encoder = Encoder::new();
                 match encoder.compress_vec(&data) {
-                    Ok(compressed) if compressed.len() > GOSSIP_MAX_SIZE => {
-                        Err("ssz_snappy Encoded data > GOSSIP_MAX_SIZE".into())
+                    Ok(compressed) if compressed.len() > gossip_max_size => {
+                        Err("ssz_snappy Encoded data > gossip_max_size".into())
                     }
                     Ok(compressed) => Ok(compressed),
                     Err(e) => Err(format!("{}", e)),
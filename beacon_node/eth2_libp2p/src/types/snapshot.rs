@@ -0,0 +1,69 @@
+use crate::types::{SyncState, SyncStatus};
+use crate::PeerInfo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use types::EthSpec;
+
+/// A diagnostic snapshot of the state of the network, to help debug a misbehaving node.
+#[derive(Clone, Debug, Serialize)]
+#[serde(bound = "T: EthSpec")]
+pub struct NetworkSnapshot<T: EthSpec> {
+    /// The time the snapshot was taken, in milliseconds since the unix epoch.
+    pub timestamp: u64,
+    /// Every peer in the `PeerDB`, connected or not.
+    pub peers: Vec<PeerSnapshot<T>>,
+    /// A summary of the discovery routing table.
+    pub routing_table: RoutingTableSnapshot,
+    /// The gossip topics we are subscribed to.
+    pub subscriptions: Vec<String>,
+    /// The peers in our gossipsub mesh for each subscribed topic.
+    pub mesh_peers: BTreeMap<String, Vec<String>>,
+    /// The RPC requests sent or received whose responses have not yet ended, oldest first.
+    pub rpc_streams: Vec<RpcStreamSnapshot>,
+    /// The `BlocksByRange` requests we have sent whose responses have not yet ended.
+    pub range_requests: Vec<RangeRequestSnapshot>,
+    /// The sync state published by the sync manager.
+    pub sync_state: SyncState,
+    /// The state of the sync manager, including its batches.
+    pub sync_status: SyncStatus,
+}
+
+/// A peer in a `NetworkSnapshot`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(bound = "T: EthSpec")]
+pub struct PeerSnapshot<T: EthSpec> {
+    pub peer_id: String,
+    pub peer_info: PeerInfo<T>,
+}
+
+/// A summary of the discovery routing table in a `NetworkSnapshot`.
+#[derive(Clone, Debug, Serialize)]
+pub struct RoutingTableSnapshot {
+    /// The number of ENRs in the routing table.
+    pub entries: usize,
+    /// The node ids of the ENRs in the routing table.
+    pub node_ids: Vec<String>,
+}
+
+/// An RPC request in a `NetworkSnapshot` whose response has not yet ended.
+#[derive(Clone, Debug, Serialize)]
+pub struct RpcStreamSnapshot {
+    pub peer_id: String,
+    /// Either `inbound`, for a request from the peer, or `outbound`, for a request we sent.
+    pub direction: String,
+    pub protocol: String,
+    pub request_id: String,
+    /// The time since the request was sent or received, in milliseconds.
+    pub age_millis: u64,
+}
+
+/// A pending `BlocksByRange` request in a `NetworkSnapshot`.
+#[derive(Clone, Debug, Serialize)]
+pub struct RangeRequestSnapshot {
+    pub peer_id: String,
+    pub request_id: String,
+    /// The time since the request was sent, in milliseconds.
+    pub age_millis: u64,
+    /// The number of blocks received so far.
+    pub blocks: usize,
+}
@@ -54,6 +54,93 @@ impl SyncState {
     }
 }
 
+/// A snapshot of the internals of the sync manager, published for monitoring.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// The kind of sync currently in progress.
+    pub mode: SyncMode,
+    /// The long-range (batch) sync chains, whether or not they are being synced.
+    pub range_chains: Vec<ChainStatus>,
+    /// The number of blocks with unknown parents which are being looked up.
+    pub parent_lookups: usize,
+    /// The number of blocks being looked up by root, e.g. because an attestation referenced them.
+    pub single_block_lookups: usize,
+    /// The estimated number of seconds until the target head slot of the range sync is reached,
+    /// based on the rate at which blocks have been imported since the range sync started.
+    pub estimated_seconds_to_head: Option<u64>,
+}
+
+/// The kind of sync performed by the sync manager.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SyncMode {
+    /// A long-range (batch) sync of a finalized or head chain.
+    Range,
+    /// Only looking up the parents of blocks with unknown parents.
+    ParentLookup,
+    /// No sync is in progress.
+    Idle,
+}
+
+impl Default for SyncMode {
+    fn default() -> Self {
+        SyncMode::Idle
+    }
+}
+
+/// The status of a chain being downloaded by range sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainStatus {
+    pub id: u64,
+    pub chain_type: ChainType,
+    /// Whether blocks are being downloaded for this chain.
+    pub syncing: bool,
+    pub start_slot: Slot,
+    pub target_head_slot: Slot,
+    pub target_head_root: Hash256,
+    /// The number of peers which this chain may be downloaded from.
+    pub peers: usize,
+    pub batches: Vec<BatchStatus>,
+}
+
+/// The kind of chain being downloaded by range sync.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChainType {
+    /// A chain which is finalized by our peers.
+    Finalized,
+    /// A chain of blocks which are not yet finalized.
+    Head,
+}
+
+/// The status of a batch of blocks requested by range sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchStatus {
+    pub id: u64,
+    /// The first slot of the batch, inclusive.
+    pub start_slot: Slot,
+    /// The last slot of the batch, exclusive.
+    pub end_slot: Slot,
+    /// The peer that is currently assigned to the batch.
+    pub peer_id: String,
+    pub state: BatchState,
+    /// The number of times the batch has been requested again due to a failed request.
+    pub retries: u8,
+    /// The number of times the batch has been downloaded again because it could not be processed.
+    pub reprocess_retries: u8,
+}
+
+/// The stage of the range sync pipeline that a batch is in.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BatchState {
+    /// The blocks have been requested from a peer.
+    Downloading,
+    /// The blocks have been downloaded and are waiting to be processed.
+    AwaitingProcessing,
+    /// The blocks are being imported.
+    Processing,
+    /// The blocks have been imported and the batch is kept until the next batch confirms them.
+    AwaitingValidation,
+}
+
 impl std::fmt::Display for SyncState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -10,16 +10,17 @@ pub const SSZ_ENCODING_POSTFIX: &str = "ssz";
 pub const SSZ_SNAPPY_ENCODING_POSTFIX: &str = "ssz_snappy";
 pub const BEACON_BLOCK_TOPIC: &str = "beacon_block";
 pub const BEACON_AGGREGATE_AND_PROOF_TOPIC: &str = "beacon_aggregate_and_proof";
-// for speed and easier string manipulation, committee topic index is split into a prefix and a
-// postfix. The topic is committee_index{}_beacon_attestation where {} is an integer.
-pub const COMMITEE_INDEX_TOPIC_PREFIX: &str = "committee_index";
-pub const COMMITEE_INDEX_TOPIC_POSTFIX: &str = "_beacon_attestation";
+// The attestation subnet topics are of the form beacon_attestation_{} where {} is the subnet id.
+pub const BEACON_ATTESTATION_PREFIX: &str = "beacon_attestation_";
 pub const VOLUNTARY_EXIT_TOPIC: &str = "voluntary_exit";
 pub const PROPOSER_SLASHING_TOPIC: &str = "proposer_slashing";
 pub const ATTESTER_SLASHING_TOPIC: &str = "attester_slashing";
 
 /// A gossipsub topic which encapsulates the type of messages that should be sent and received over
 /// the pubsub protocol and the way the messages should be encoded.
+///
+/// The topic names follow the Eth2 networking specification, i.e.,
+/// `/eth2/{fork_digest}/{name}/{encoding}`. Topics are sent as plain strings, not hashes.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct GossipTopic {
     /// The encoding of the topic.
@@ -53,7 +54,9 @@ impl std::fmt::Display for GossipKind {
         match self {
             GossipKind::BeaconBlock => write!(f, "beacon_block"),
             GossipKind::BeaconAggregateAndProof => write!(f, "beacon_aggregate_and_proof"),
-            GossipKind::CommitteeIndex(subnet_id) => write!(f, "committee_index_{}", **subnet_id),
+            GossipKind::CommitteeIndex(subnet_id) => {
+                write!(f, "beacon_attestation_{}", **subnet_id)
+            }
             GossipKind::VoluntaryExit => write!(f, "voluntary_exit"),
             GossipKind::ProposerSlashing => write!(f, "proposer_slashing"),
             GossipKind::AttesterSlashing => write!(f, "attester_slashing"),
@@ -61,6 +64,26 @@ impl std::fmt::Display for GossipKind {
     }
 }
 
+/// Parses the name of a topic kind, as given by `Display`, e.g. `beacon_block` or
+/// `beacon_attestation_7`.
+impl std::str::FromStr for GossipKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        Ok(match kind {
+            BEACON_BLOCK_TOPIC => GossipKind::BeaconBlock,
+            BEACON_AGGREGATE_AND_PROOF_TOPIC => GossipKind::BeaconAggregateAndProof,
+            VOLUNTARY_EXIT_TOPIC => GossipKind::VoluntaryExit,
+            PROPOSER_SLASHING_TOPIC => GossipKind::ProposerSlashing,
+            ATTESTER_SLASHING_TOPIC => GossipKind::AttesterSlashing,
+            kind => match committee_topic_index(kind) {
+                Some(subnet_id) => GossipKind::CommitteeIndex(subnet_id),
+                None => return Err(format!("Unknown topic: {}", kind)),
+            },
+        })
+    }
+}
+
 /// The known encoding types for gossipsub messages.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum GossipEncoding {
@@ -90,6 +113,11 @@ impl GossipTopic {
         &self.encoding
     }
 
+    /// Returns the fork digest of the gossipsub topic.
+    pub fn fork_digest(&self) -> [u8; 4] {
+        self.fork_digest
+    }
+
     /// Returns a mutable reference to the fork digest of the gossipsub topic.
     pub fn digest(&mut self) -> &mut [u8; 4] {
         &mut self.fork_digest
@@ -121,17 +149,7 @@ impl GossipTopic {
                 SSZ_SNAPPY_ENCODING_POSTFIX => GossipEncoding::SSZSnappy,
                 _ => return Err(format!("Unknown encoding: {}", topic)),
             };
-            let kind = match topic_parts[3] {
-                BEACON_BLOCK_TOPIC => GossipKind::BeaconBlock,
-                BEACON_AGGREGATE_AND_PROOF_TOPIC => GossipKind::BeaconAggregateAndProof,
-                VOLUNTARY_EXIT_TOPIC => GossipKind::VoluntaryExit,
-                PROPOSER_SLASHING_TOPIC => GossipKind::ProposerSlashing,
-                ATTESTER_SLASHING_TOPIC => GossipKind::AttesterSlashing,
-                topic => match committee_topic_index(topic) {
-                    Some(subnet_id) => GossipKind::CommitteeIndex(subnet_id),
-                    None => return Err(format!("Unknown topic: {}", topic)),
-                },
-            };
+            let kind = topic_parts[3].parse()?;
 
             return Ok(GossipTopic {
                 encoding,
@@ -163,10 +181,7 @@ impl Into<String> for GossipTopic {
             GossipKind::VoluntaryExit => VOLUNTARY_EXIT_TOPIC.into(),
             GossipKind::ProposerSlashing => PROPOSER_SLASHING_TOPIC.into(),
             GossipKind::AttesterSlashing => ATTESTER_SLASHING_TOPIC.into(),
-            GossipKind::CommitteeIndex(index) => format!(
-                "{}{}{}",
-                COMMITEE_INDEX_TOPIC_PREFIX, *index, COMMITEE_INDEX_TOPIC_POSTFIX
-            ),
+            GossipKind::CommitteeIndex(index) => format!("{}{}", BEACON_ATTESTATION_PREFIX, *index),
         };
         format!(
             "/{}/{}/{}/{}",
@@ -186,20 +201,55 @@ impl From<SubnetId> for GossipKind {
 
 // helper functions
 
-// Determines if a string is a committee topic.
+// Determines if a string is an attestation subnet topic.
 fn committee_topic_index(topic: &str) -> Option<SubnetId> {
-    if topic.starts_with(COMMITEE_INDEX_TOPIC_PREFIX)
-        && topic.ends_with(COMMITEE_INDEX_TOPIC_POSTFIX)
-    {
+    if topic.starts_with(BEACON_ATTESTATION_PREFIX) {
         return Some(SubnetId::new(
-            u64::from_str_radix(
-                topic
-                    .trim_start_matches(COMMITEE_INDEX_TOPIC_PREFIX)
-                    .trim_end_matches(COMMITEE_INDEX_TOPIC_POSTFIX),
-                10,
-            )
-            .ok()?,
+            u64::from_str_radix(topic.trim_start_matches(BEACON_ATTESTATION_PREFIX), 10).ok()?,
         ));
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_round_trip() {
+        let kinds = vec![
+            GossipKind::BeaconBlock,
+            GossipKind::BeaconAggregateAndProof,
+            GossipKind::CommitteeIndex(SubnetId::new(42)),
+            GossipKind::VoluntaryExit,
+            GossipKind::ProposerSlashing,
+            GossipKind::AttesterSlashing,
+        ];
+
+        for kind in kinds {
+            for encoding in &[GossipEncoding::SSZ, GossipEncoding::SSZSnappy] {
+                let topic =
+                    GossipTopic::new(kind.clone(), encoding.clone(), [0xb5, 0x30, 0x3f, 0x2a]);
+                let topic_str: String = topic.clone().into();
+                assert_eq!(GossipTopic::decode(&topic_str), Ok(topic));
+            }
+            assert_eq!(kind.to_string().parse(), Ok(kind));
+        }
+        assert!("beacon_attestation_x".parse::<GossipKind>().is_err());
+    }
+
+    #[test]
+    fn topic_names_match_spec() {
+        let topic: String = GossipTopic::new(
+            GossipKind::CommitteeIndex(SubnetId::new(7)),
+            GossipEncoding::SSZSnappy,
+            [0xb5, 0x30, 0x3f, 0x2a],
+        )
+        .into();
+        assert_eq!(topic, "/eth2/b5303f2a/beacon_attestation_7/ssz_snappy");
+
+        let topic: String =
+            GossipTopic::new(GossipKind::BeaconBlock, GossipEncoding::SSZ, [0; 4]).into();
+        assert_eq!(topic, "/eth2/00000000/beacon_block/ssz");
+    }
+}
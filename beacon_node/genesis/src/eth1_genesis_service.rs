@@ -1,6 +1,7 @@
 pub use crate::{common::genesis_deposits, interop::interop_genesis_state};
 pub use eth1::Config as Eth1Config;
 
+use crate::metrics;
 use eth1::{DepositLog, Eth1Block, Service as Eth1Service};
 use slog::{debug, error, info, trace, Logger};
 use state_processing::{
@@ -11,7 +12,7 @@ use std::sync::{
     atomic::{AtomicU64, AtomicUsize, Ordering},
     Arc,
 };
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::time::delay_for;
 use types::{BeaconState, ChainSpec, Deposit, Eth1Data, EthSpec, Hash256};
 
@@ -61,6 +62,8 @@ impl Eth1GenesisService {
             // a `MIN_GENESIS_SECONDS`, so after `MIN_GENESIS_VALIDATOR_COUNT`
             // has been reached only a single block needs to be read.
             max_blocks_per_update: Some(BLOCKS_PER_GENESIS_POLL),
+            // Every deposit is required to build the genesis state.
+            deposit_snapshot: None,
             ..config
         };
 
@@ -112,7 +115,14 @@ impl Eth1GenesisService {
             "Importing eth1 deposit logs";
         );
 
+        metrics::set_gauge(
+            &metrics::GENESIS_MIN_ACTIVE_VALIDATOR_COUNT,
+            spec.min_genesis_active_validator_count as i64,
+        );
+
         loop {
+            Eth1Service::update_endpoint_health(eth1_service.clone()).await;
+
             let update_result = Eth1Service::update_deposit_cache(eth1_service.clone())
                 .await
                 .map_err(|e| format!("{:?}", e));
@@ -128,6 +138,10 @@ impl Eth1GenesisService {
             self.stats
                 .total_deposit_count
                 .store(eth1_service.deposit_cache_len(), Ordering::Relaxed);
+            metrics::set_gauge(
+                &metrics::GENESIS_DEPOSIT_COUNT,
+                eth1_service.deposit_cache_len() as i64,
+            );
 
             if !sync_blocks {
                 if let Some(viable_eth1_block) = self
@@ -185,6 +199,7 @@ impl Eth1GenesisService {
                     "Genesis ceremony complete";
                     "genesis_validators" => genesis_state.get_active_validator_indices(E::genesis_epoch()).len(),
                     "genesis_time" => genesis_state.genesis_time,
+                    "seconds_until_genesis" => seconds_until(genesis_state.genesis_time),
                 );
                 break Ok(genesis_state);
             }
@@ -270,6 +285,10 @@ impl Eth1GenesisService {
                 self.stats
                     .latest_timestamp
                     .store(block.timestamp, Ordering::Relaxed);
+                metrics::set_gauge(
+                    &metrics::GENESIS_LATEST_ETH1_TIMESTAMP,
+                    block.timestamp as i64,
+                );
 
                 *highest_processed_block = Some(block.number)
             }
@@ -298,6 +317,10 @@ impl Eth1GenesisService {
             self.stats
                 .active_validator_count
                 .store(active_validator_count, Ordering::Relaxed);
+            metrics::set_gauge(
+                &metrics::GENESIS_ACTIVE_VALIDATOR_COUNT,
+                active_validator_count as i64,
+            );
 
             if is_valid_genesis_state(&state, spec) {
                 let genesis_state = self
@@ -447,6 +470,14 @@ impl Eth1GenesisService {
     }
 }
 
+/// Returns the number of seconds from now until `timestamp`, or zero if it has passed.
+fn seconds_until(timestamp: u64) -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| timestamp.saturating_sub(now.as_secs()))
+        .unwrap_or(0)
+}
+
 /// Returns `false` for a timestamp that would result in a genesis time that is earlier than
 /// `MIN_GENESIS_TIME`.
 fn timestamp_can_trigger_genesis(timestamp: u64, spec: &ChainSpec) -> Result<bool, String> {
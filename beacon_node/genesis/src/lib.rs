@@ -1,6 +1,7 @@
 mod common;
 mod eth1_genesis_service;
 mod interop;
+mod metrics;
 
 pub use eth1::Config as Eth1Config;
 pub use eth1_genesis_service::{Eth1GenesisService, Statistics};
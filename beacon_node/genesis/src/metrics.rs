@@ -0,0 +1,22 @@
+use lazy_static::lazy_static;
+pub use lighthouse_metrics::*;
+
+lazy_static! {
+    pub static ref GENESIS_DEPOSIT_COUNT: Result<IntGauge> = try_create_int_gauge(
+        "genesis_eth1_deposit_count",
+        "The number of deposits imported from the deposit contract whilst waiting for genesis"
+    );
+    pub static ref GENESIS_ACTIVE_VALIDATOR_COUNT: Result<IntGauge> = try_create_int_gauge(
+        "genesis_active_validator_count",
+        "The number of validators that would be active if genesis were triggered by the latest \
+         eth1 block"
+    );
+    pub static ref GENESIS_MIN_ACTIVE_VALIDATOR_COUNT: Result<IntGauge> = try_create_int_gauge(
+        "genesis_min_active_validator_count",
+        "The number of active validators required to trigger genesis"
+    );
+    pub static ref GENESIS_LATEST_ETH1_TIMESTAMP: Result<IntGauge> = try_create_int_gauge(
+        "genesis_latest_eth1_block_timestamp",
+        "The timestamp of the latest eth1 block scanned whilst waiting for genesis"
+    );
+}
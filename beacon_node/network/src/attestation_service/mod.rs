@@ -2,6 +2,7 @@
 //! given time. It schedules subscriptions to shard subnets, requests peer discoveries and
 //! determines whether attestations should be aggregated and/or passed to the beacon node.
 
+use crate::metrics;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::{types::GossipKind, MessageId, NetworkGlobals, PeerId};
 use futures::prelude::*;
@@ -34,6 +35,12 @@ const LAST_SEEN_VALIDATOR_TIMEOUT: u32 = 150;
 ///
 /// Note: The time is calculated as `time = milliseconds_per_slot / ADVANCE_SUBSCRIPTION_TIME`.
 const ADVANCE_SUBSCRIBE_TIME: u32 = 3;
+/// The minimum number of connected peers we require on a subnet needed for an upcoming duty. If
+/// there are fewer, a peer discovery is triggered.
+const MIN_SUBNET_PEERS: usize = 2;
+/// The time (in slots) after a peer discovery for a subnet before another discovery is triggered
+/// for the subnet due to it having too few peers.
+const SUBNET_DISCOVERY_RETRY_SLOTS: u32 = 8;
 /// The default number of slots before items in hash delay sets used by this class should expire.
 const DEFAULT_EXPIRATION_TIMEOUT: u32 = 3;
 // 36s at 12s slot time
@@ -124,6 +131,13 @@ pub struct AttestationService<T: BeaconChainTypes> {
     /// A collection timeouts to track the existence of aggregate validator subscriptions at an `ExactSubnet`.
     aggregate_validators_on_subnet: HashSetDelay<ExactSubnet>,
 
+    /// A collection of timeouts for when to next check the number of peers on a subnet required
+    /// for an upcoming duty.
+    subnet_peer_checks: HashSetDelay<ExactSubnet>,
+
+    /// The subnets for which a peer discovery has recently been requested.
+    recent_discoveries: HashSetDelay<SubnetId>,
+
     /// A collection of seen validators. These dictate how many random subnets we should be
     /// subscribed to. As these time out, we unsubscribe for the required random subnets and update
     /// our ENR.
@@ -159,6 +173,9 @@ impl<T: BeaconChainTypes> AttestationService<T> {
         let default_timeout = slot_duration
             .checked_mul(DEFAULT_EXPIRATION_TIMEOUT)
             .expect("DEFAULT_EXPIRATION_TIMEOUT must not be ridiculoustly large");
+        let discovery_retry_timeout = slot_duration
+            .checked_mul(SUBNET_DISCOVERY_RETRY_SLOTS)
+            .expect("SUBNET_DISCOVERY_RETRY_SLOTS must not be ridiculously large");
 
         AttestationService {
             events: VecDeque::with_capacity(10),
@@ -169,6 +186,8 @@ impl<T: BeaconChainTypes> AttestationService<T> {
             subscriptions: HashSetDelay::new(default_timeout),
             unsubscriptions: HashSetDelay::new(default_timeout),
             aggregate_validators_on_subnet: HashSetDelay::new(default_timeout),
+            subnet_peer_checks: HashSetDelay::new(slot_duration),
+            recent_discoveries: HashSetDelay::new(discovery_retry_timeout),
             known_validators: HashSetDelay::new(last_seen_val_timeout),
             log,
         }
@@ -211,6 +230,9 @@ impl<T: BeaconChainTypes> AttestationService<T> {
                 warn!(self.log, "Discovery lookup request error"; "error" => e);
             }
 
+            // keep checking that we have enough peers on the subnet until the duty has passed
+            self.subnet_peer_checks.insert(exact_subnet.clone());
+
             // determine if the validator is an aggregator. If so, we subscribe to the subnet and
             // if successful add the validator to a mapping of known aggregators for that exact
             // subnet.
@@ -374,6 +396,7 @@ impl<T: BeaconChainTypes> AttestationService<T> {
             self.events
                 .push_back(AttServiceMessage::DiscoverPeers { subnet_id, min_ttl });
         }
+        self.recent_discoveries.insert(subnet_id);
     }
 
     /// Checks the current random subnets and subscriptions to determine if a new subscription for this
@@ -551,6 +574,42 @@ impl<T: BeaconChainTypes> AttestationService<T> {
         self.send_or_update_discovery_event(exact_subnet.subnet_id, min_ttl)
     }
 
+    /// Checks the number of peers on a subnet required for an upcoming duty.
+    ///
+    /// If there are too few and no discovery has recently been requested for the subnet, a peer
+    /// discovery is requested. The check is repeated every slot until the duty has passed.
+    fn handle_subnet_peer_check(&mut self, exact_subnet: ExactSubnet) {
+        let current_slot = match self.beacon_chain.slot_clock.now() {
+            Some(slot) => slot,
+            None => return,
+        };
+        if exact_subnet.slot <= current_slot {
+            return;
+        }
+
+        // discoveries for duties further ahead are handled by `discover_peers`
+        if exact_subnet.slot < current_slot.saturating_add(TARGET_PEER_DISCOVERY_SLOT_LOOK_AHEAD) {
+            let peer_count = self
+                .network_globals
+                .connected_peers_on_subnet(exact_subnet.subnet_id);
+
+            if peer_count < MIN_SUBNET_PEERS {
+                metrics::inc_counter(&metrics::ATTESTATION_SUBNET_PEERS_LOW);
+                warn!(self.log, "Too few peers on subnet for upcoming duty";
+                    "subnet" => *exact_subnet.subnet_id,
+                    "target_slot" => exact_subnet.slot.as_u64(),
+                    "peers" => peer_count,
+                );
+
+                if !self.recent_discoveries.contains(&exact_subnet.subnet_id) {
+                    self.handle_discover_peers(exact_subnet.clone());
+                }
+            }
+        }
+
+        self.subnet_peer_checks.insert(exact_subnet);
+    }
+
     /// A queued subscription is ready.
     ///
     /// We add subscriptions events even if we are already subscribed to a random subnet (as these
@@ -747,7 +806,19 @@ impl<T: BeaconChainTypes> Stream for AttestationService<T> {
             }
             Poll::Ready(None) | Poll::Pending => {}
         }
+        // process any subnet peer count checks
+        match self.subnet_peer_checks.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(exact_subnet))) => self.handle_subnet_peer_check(exact_subnet),
+            Poll::Ready(Some(Err(e))) => {
+                error!(self.log, "Failed to check for subnet peer counts"; "error"=> format!("{}", e));
+            }
+            Poll::Ready(None) | Poll::Pending => {}
+        }
+
         // poll to remove entries on expiration, no need to act on expiration events
+        if let Poll::Ready(Some(Err(e))) = self.recent_discoveries.poll_next_unpin(cx) {
+            error!(self.log, "Failed to check for recent discovery expirations"; "error"=> format!("{}", e));
+        }
         if let Poll::Ready(Some(Err(e))) = self.aggregate_validators_on_subnet.poll_next_unpin(cx) {
             error!(self.log, "Failed to check for aggregate validator on subnet expirations"; "error"=> format!("{}", e));
         }
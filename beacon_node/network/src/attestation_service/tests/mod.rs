@@ -584,4 +584,45 @@ mod tests {
         assert_eq!(enr_add_count, 64);
         assert_eq!(unexpected_msg_count, 0);
     }
+
+    #[tokio::test]
+    async fn rediscover_peers_on_subnet_with_few_peers() {
+        // subscription config
+        let validator_index = 1;
+        let committee_index = 1;
+        let subscription_slot = 4;
+
+        // create the attestation service and subscriptions
+        let mut attestation_service = get_attestation_service();
+        let current_slot = attestation_service
+            .beacon_chain
+            .slot_clock
+            .now()
+            .expect("Could not get current slot");
+
+        let subscriptions = vec![get_subscription(
+            validator_index,
+            committee_index,
+            current_slot + Slot::new(subscription_slot),
+        )];
+
+        // submit the subscriptions
+        attestation_service
+            .validator_subscriptions(subscriptions)
+            .unwrap();
+
+        // drop the initial events and pretend the initial discovery was long ago
+        attestation_service.events.clear();
+        attestation_service.recent_discoveries.clear();
+
+        // there are no peers on the subnet, so the next check should trigger a discovery
+        let events = get_events(&mut attestation_service, 1, 2).await;
+
+        match &events[..] {
+            [AttServiceMessage::DiscoverPeers { subnet_id, .. }] => {
+                assert_eq!(*subnet_id, SubnetId::new(committee_index))
+            }
+            _ => panic!("expected a single discovery, got {:?}", events),
+        }
+    }
 }
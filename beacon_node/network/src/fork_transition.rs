@@ -0,0 +1,104 @@
+//! Schedules the transition of our gossip subscriptions from one fork to the next.
+//!
+//! Peers do not all switch to the topics of a new fork at exactly the same moment, so the topics
+//! of the next fork are subscribed to a few epochs before it activates, and those of the previous
+//! fork are kept for a few epochs afterwards. Both sets of topics are subscribed to in between.
+
+use std::time::Duration;
+
+/// The number of epochs before and after a fork during which the topics of both forks are
+/// subscribed to.
+pub const FORK_TRANSITION_EPOCHS: u32 = 2;
+
+/// The time after the start of the fork epoch at which the fork is activated, so that it is
+/// activated within the new fork period.
+const ACTIVATION_DELAY: Duration = Duration::from_millis(200);
+
+/// A step in the transition of our gossip subscriptions from one fork to the next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForkTransitionStep {
+    /// Subscribe to the topics of the next fork, alongside those of the current fork.
+    SubscribeNextFork,
+    /// Switch to the next fork, keeping the topics of the previous fork.
+    ActivateFork,
+    /// Unsubscribe from the topics of the previous fork.
+    UnsubscribePreviousFork,
+}
+
+/// Returns the step to take after `previous_step` and the time until it should be taken, given
+/// the time until the next fork, if any, and the duration of the transition window on either side
+/// of the fork.
+///
+/// `previous_step` is `None` when no transition has been started, e.g. at start-up.
+pub fn next_fork_transition_step(
+    previous_step: Option<ForkTransitionStep>,
+    until_next_fork: Option<Duration>,
+    transition_window: Duration,
+) -> Option<(ForkTransitionStep, Duration)> {
+    match previous_step {
+        Some(ForkTransitionStep::SubscribeNextFork) => until_next_fork.map(|until_fork| {
+            (
+                ForkTransitionStep::ActivateFork,
+                until_fork + ACTIVATION_DELAY,
+            )
+        }),
+        Some(ForkTransitionStep::ActivateFork) => Some((
+            ForkTransitionStep::UnsubscribePreviousFork,
+            transition_window,
+        )),
+        None | Some(ForkTransitionStep::UnsubscribePreviousFork) => {
+            until_next_fork.map(|until_fork| {
+                (
+                    ForkTransitionStep::SubscribeNextFork,
+                    until_fork
+                        .checked_sub(transition_window)
+                        .unwrap_or_else(|| Duration::from_secs(0)),
+                )
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ForkTransitionStep::*;
+
+    #[test]
+    fn transition_around_fork() {
+        let window = Duration::from_secs(768);
+        let secs = Duration::from_secs;
+
+        // no transition without a scheduled fork
+        assert_eq!(next_fork_transition_step(None, None, window), None);
+
+        // the next fork is subscribed to ahead of time, or immediately if it is close
+        assert_eq!(
+            next_fork_transition_step(None, Some(secs(1000)), window),
+            Some((SubscribeNextFork, secs(232)))
+        );
+        assert_eq!(
+            next_fork_transition_step(None, Some(secs(100)), window),
+            Some((SubscribeNextFork, secs(0)))
+        );
+
+        assert_eq!(
+            next_fork_transition_step(Some(SubscribeNextFork), Some(secs(768)), window),
+            Some((ActivateFork, secs(768) + ACTIVATION_DELAY))
+        );
+        assert_eq!(
+            next_fork_transition_step(Some(ActivateFork), None, window),
+            Some((UnsubscribePreviousFork, window))
+        );
+
+        // the transition to any later fork follows once the previous has completed
+        assert_eq!(
+            next_fork_transition_step(Some(UnsubscribePreviousFork), None, window),
+            None
+        );
+        assert_eq!(
+            next_fork_transition_step(Some(UnsubscribePreviousFork), Some(secs(5000)), window),
+            Some((SubscribeNextFork, secs(4232)))
+        );
+    }
+}
@@ -6,9 +6,11 @@ pub mod error;
 pub mod service;
 
 mod attestation_service;
+mod fork_transition;
 mod metrics;
 mod persisted_dht;
 mod router;
+mod scheduler;
 mod sync;
 
 pub use eth2_libp2p::NetworkConfig;
@@ -21,6 +21,31 @@ lazy_static! {
         "Count of gossip aggregated attestations received"
     );
 
+    pub static ref GOSSIP_EARLY_MESSAGES_QUEUED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_early_messages_queued_total",
+        "Count of gossip blocks and attestations queued until the start of their slot"
+    );
+    pub static ref GOSSIP_EARLY_MESSAGES_DROPPED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_early_messages_dropped_total",
+        "Count of gossip blocks and attestations dropped as the queue for early messages was full"
+    );
+    pub static ref GOSSIP_LOW_PRIORITY_MESSAGES_QUEUED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_low_priority_messages_queued_total",
+        "Count of gossip messages from quarantined peers queued until no other messages are waiting"
+    );
+    pub static ref GOSSIP_LOW_PRIORITY_MESSAGES_DROPPED: Result<IntCounter> = try_create_int_counter(
+        "network_gossip_low_priority_messages_dropped_total",
+        "Count of gossip messages from quarantined peers dropped as the low priority queue was full"
+    );
+
+    /*
+     * Attestation subnets
+     */
+    pub static ref ATTESTATION_SUBNET_PEERS_LOW: Result<IntCounter> = try_create_int_counter(
+        "network_attestation_subnet_peers_low_total",
+        "Count of checks which found too few peers on a subnet required for an upcoming duty"
+    );
+
     /*
      * Gossip Tx
      */
@@ -36,4 +61,12 @@ lazy_static! {
         "network_gossip_aggregated_attestations_tx_total",
         "Count of gossip aggregated attestations transmitted"
     );
+
+    /*
+     * RPC Tx
+     */
+    pub static ref RPC_BLOCK_REQUESTS_RATE_LIMITED: Result<IntCounter> = try_create_int_counter(
+        "network_rpc_block_requests_rate_limited_total",
+        "Count of block requests ended early as serving them would exceed the rate limit"
+    );
 }
@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+/// Limits the rate at which bytes are sent, using a token bucket.
+///
+/// The bucket holds up to one second of bytes, so a short burst may exceed the rate whilst the
+/// average does not.
+pub struct BandwidthLimiter {
+    /// The number of bytes which may be sent per second.
+    bytes_per_sec: u64,
+    /// The number of bytes which may be sent now.
+    available: u64,
+    /// The last time `available` was refilled.
+    last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+    /// Creates a limiter which allows `mb_per_sec` megabytes to be sent per second.
+    pub fn new(mb_per_sec: u64) -> Self {
+        let bytes_per_sec = mb_per_sec.saturating_mul(1 << 20);
+        Self {
+            bytes_per_sec,
+            available: bytes_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Returns true if `bytes` may be sent at `now`, in which case they are deducted from the
+    /// bytes available.
+    ///
+    /// A message larger than the bucket may be sent when the bucket is full, so that large blocks
+    /// are not refused forever.
+    pub fn try_send(&mut self, bytes: u64, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        if elapsed > Duration::from_secs(0) {
+            let refill = (elapsed.as_secs_f64() * self.bytes_per_sec as f64) as u64;
+            self.available = self
+                .available
+                .saturating_add(refill)
+                .min(self.bytes_per_sec);
+            self.last_refill = now;
+        }
+
+        if bytes <= self.available || self.available == self.bytes_per_sec {
+            self.available = self.available.saturating_sub(bytes);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1 << 20;
+
+    #[test]
+    fn limits_rate() {
+        let mut limiter = BandwidthLimiter::new(2);
+        let start = Instant::now();
+
+        assert!(limiter.try_send(MB, start));
+        assert!(limiter.try_send(MB, start));
+        assert!(!limiter.try_send(MB, start));
+
+        // half a second refills one megabyte
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.try_send(MB, later));
+        assert!(!limiter.try_send(MB, later));
+
+        // the bucket never holds more than one second of bytes
+        let much_later = later + Duration::from_secs(60);
+        assert!(limiter.try_send(2 * MB, much_later));
+        assert!(!limiter.try_send(1, much_later));
+    }
+
+    #[test]
+    fn sends_large_messages_when_full() {
+        let mut limiter = BandwidthLimiter::new(1);
+        let start = Instant::now();
+
+        assert!(limiter.try_send(3 * MB, start));
+        assert!(!limiter.try_send(3 * MB, start + Duration::from_millis(500)));
+        assert!(limiter.try_send(3 * MB, start + Duration::from_secs(2)));
+    }
+}
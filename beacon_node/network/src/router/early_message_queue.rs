@@ -0,0 +1,140 @@
+use eth2_libp2p::PeerId;
+use std::collections::HashMap;
+use types::Slot;
+
+/// The maximum number of messages which may be queued.
+pub const MAX_QUEUED_MESSAGES: usize = 1_024;
+
+/// The maximum number of messages from a single peer which may be queued, so that one peer cannot
+/// fill the queue.
+pub const MAX_QUEUED_MESSAGES_PER_PEER: usize = 64;
+
+/// Holds gossip messages which arrived shortly before the start of their slot, so that they can
+/// be processed once it starts rather than being dropped.
+pub struct EarlyMessageQueue<T> {
+    /// The queued messages, in the order they were received.
+    messages: Vec<(Slot, PeerId, T)>,
+    /// The number of queued messages from each peer.
+    per_peer: HashMap<PeerId, usize>,
+}
+
+impl<T> Default for EarlyMessageQueue<T> {
+    fn default() -> Self {
+        Self {
+            messages: vec![],
+            per_peer: HashMap::new(),
+        }
+    }
+}
+
+impl<T> EarlyMessageQueue<T> {
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Queues `message` from `peer_id` until `slot` starts.
+    ///
+    /// Returns `false` if the message was dropped because the queue, or the quota of the peer, is
+    /// full.
+    pub fn push(&mut self, slot: Slot, peer_id: PeerId, message: T) -> bool {
+        if self.messages.len() >= MAX_QUEUED_MESSAGES {
+            return false;
+        }
+
+        let peer_count = self.per_peer.entry(peer_id.clone()).or_default();
+        if *peer_count >= MAX_QUEUED_MESSAGES_PER_PEER {
+            return false;
+        }
+
+        *peer_count += 1;
+        self.messages.push((slot, peer_id, message));
+        true
+    }
+
+    /// Returns the earliest slot of the queued messages, if any.
+    pub fn earliest_slot(&self) -> Option<Slot> {
+        self.messages.iter().map(|(slot, _, _)| *slot).min()
+    }
+
+    /// Removes and returns the messages for `current_slot` or earlier, in the order they were
+    /// received.
+    pub fn pop_ready(&mut self, current_slot: Slot) -> Vec<(PeerId, T)> {
+        let (ready, pending) = self
+            .messages
+            .drain(..)
+            .partition::<Vec<_>, _>(|(slot, _, _)| *slot <= current_slot);
+        self.messages = pending;
+
+        ready
+            .into_iter()
+            .map(|(_, peer_id, message)| {
+                if let Some(count) = self.per_peer.get_mut(&peer_id) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.per_peer.remove(&peer_id);
+                    }
+                }
+                (peer_id, message)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn releases_messages_at_their_slot() {
+        let mut queue = EarlyMessageQueue::default();
+        let peer_id = PeerId::random();
+
+        assert!(queue.push(Slot::new(2), peer_id.clone(), "a"));
+        assert!(queue.push(Slot::new(1), peer_id.clone(), "b"));
+        assert!(queue.push(Slot::new(2), peer_id.clone(), "c"));
+        assert_eq!(queue.earliest_slot(), Some(Slot::new(1)));
+
+        assert!(queue.pop_ready(Slot::new(0)).is_empty());
+        assert_eq!(queue.pop_ready(Slot::new(1)), vec![(peer_id.clone(), "b")]);
+        assert_eq!(
+            queue.pop_ready(Slot::new(3)),
+            vec![(peer_id.clone(), "a"), (peer_id, "c")]
+        );
+        assert!(queue.is_empty());
+        assert_eq!(queue.earliest_slot(), None);
+    }
+
+    #[test]
+    fn limits_messages_per_peer() {
+        let mut queue = EarlyMessageQueue::default();
+        let peer_id = PeerId::random();
+
+        for _ in 0..MAX_QUEUED_MESSAGES_PER_PEER {
+            assert!(queue.push(Slot::new(1), peer_id.clone(), ()));
+        }
+        assert!(!queue.push(Slot::new(1), peer_id.clone(), ()));
+        // other peers have their own quota
+        assert!(queue.push(Slot::new(1), PeerId::random(), ()));
+
+        // the quota is freed once the messages are released
+        queue.pop_ready(Slot::new(1));
+        assert!(queue.push(Slot::new(2), peer_id, ()));
+    }
+
+    #[test]
+    fn limits_total_messages() {
+        let mut queue = EarlyMessageQueue::default();
+
+        for _ in 0..MAX_QUEUED_MESSAGES / MAX_QUEUED_MESSAGES_PER_PEER {
+            let peer_id = PeerId::random();
+            for _ in 0..MAX_QUEUED_MESSAGES_PER_PEER {
+                assert!(queue.push(Slot::new(1), peer_id.clone(), ()));
+            }
+        }
+        assert!(!queue.push(Slot::new(1), PeerId::random(), ()));
+    }
+}
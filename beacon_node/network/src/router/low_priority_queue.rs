@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+/// The maximum number of messages which may be queued.
+pub const MAX_QUEUED_MESSAGES: usize = 1_024;
+
+/// Holds gossip messages which are only to be processed when no other messages are waiting, such
+/// as those from quarantined peers.
+///
+/// Once the queue is full the oldest message is dropped, as it is the least likely to still be
+/// relevant.
+pub struct LowPriorityQueue<T> {
+    /// The queued messages, in the order they were received.
+    messages: VecDeque<T>,
+}
+
+impl<T> Default for LowPriorityQueue<T> {
+    fn default() -> Self {
+        Self {
+            messages: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> LowPriorityQueue<T> {
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Queues `message`, returning `false` if the oldest message was dropped to make room for it.
+    pub fn push(&mut self, message: T) -> bool {
+        let dropped = if self.messages.len() >= MAX_QUEUED_MESSAGES {
+            self.messages.pop_front().is_some()
+        } else {
+            false
+        };
+
+        self.messages.push_back(message);
+        !dropped
+    }
+
+    /// Removes and returns the oldest queued message, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        self.messages.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_the_oldest_message_when_full() {
+        let mut queue = LowPriorityQueue::default();
+        for i in 0..MAX_QUEUED_MESSAGES {
+            assert!(queue.push(i));
+        }
+        assert!(!queue.push(MAX_QUEUED_MESSAGES));
+        assert_eq!(queue.len(), MAX_QUEUED_MESSAGES);
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+    }
+}
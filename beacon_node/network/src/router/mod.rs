@@ -4,27 +4,46 @@
 //! and processes those that are
 #![allow(clippy::unit_arg)]
 
+mod bandwidth_limiter;
+mod early_message_queue;
+mod low_priority_queue;
 pub mod processor;
 
 use crate::error;
+use crate::metrics;
+use crate::scheduler::SlotScheduler;
 use crate::service::NetworkMessage;
 use beacon_chain::{BeaconChain, BeaconChainTypes, BlockError};
+use early_message_queue::EarlyMessageQueue;
 use eth2_libp2p::{
-    rpc::{RPCError, RequestId},
+    rpc::{BlocksByRangeRequest, RPCError, RequestId},
     MessageId, NetworkGlobals, PeerId, PeerRequestId, PubsubMessage, Request, Response,
 };
 use futures::prelude::*;
+use low_priority_queue::LowPriorityQueue;
 use processor::Processor;
 use slog::{debug, info, o, trace, warn};
+use slot_clock::SlotClock;
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
-use types::EthSpec;
+use types::{EthSpec, Slot};
+
+/// The time, beyond the maximum gossip clock disparity, before the start of their slot for which
+/// gossip blocks and attestations are queued until their slot starts, rather than being dropped.
+///
+/// This is kept well within the gossipsub message cache (five one-second heartbeats), so that a
+/// queued message can still be propagated once it has been validated.
+const MAX_EARLY_MESSAGE_DELAY: Duration = Duration::from_secs(1);
 
 /// Handles messages received from the network and client and organises syncing. This
 /// functionality of this struct is to validate an decode messages from the network before
 /// passing them to the internal message processor. The message processor spawns a syncing thread
 /// which manages which blocks need to be requested and processed.
 pub struct Router<T: BeaconChainTypes> {
+    /// Used to determine whether gossip messages have arrived before their slot.
+    chain: Arc<BeaconChain<T>>,
     /// A channel to the network service to allow for gossip propagation.
     network_send: mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
     /// Access to the peer db for logging.
@@ -32,6 +51,14 @@ pub struct Router<T: BeaconChainTypes> {
     /// Processes validated and decoded messages from the network. Has direct access to the
     /// sync manager.
     processor: Processor<T>,
+    /// Defers serving blocks to syncing peers during the start of each slot, if enabled.
+    scheduler: Option<SlotScheduler<T>>,
+    /// `BlocksByRange` requests deferred by the `scheduler`, in the order they were received.
+    deferred_requests: VecDeque<(PeerId, PeerRequestId, BlocksByRangeRequest)>,
+    /// Gossip messages which arrived before their slot, held until it starts.
+    early_messages: EarlyMessageQueue<(MessageId, PubsubMessage<T::EthSpec>)>,
+    /// Gossip messages from quarantined peers, processed only when no other messages are waiting.
+    low_priority_gossip: LowPriorityQueue<(MessageId, PeerId, PubsubMessage<T::EthSpec>)>,
     /// The `Router` logger.
     log: slog::Logger,
 }
@@ -74,28 +101,44 @@ impl<T: BeaconChainTypes> Router<T> {
         beacon_chain: Arc<BeaconChain<T>>,
         network_globals: Arc<NetworkGlobals<T::EthSpec>>,
         network_send: mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
+        blocks_by_range_quota: u64,
+        block_serving_rate_limit: Option<u64>,
+        slot_aware_scheduling: bool,
         executor: environment::TaskExecutor,
         log: slog::Logger,
     ) -> error::Result<mpsc::UnboundedSender<RouterMessage<T::EthSpec>>> {
         let message_handler_log = log.new(o!("service"=> "router"));
         trace!(message_handler_log, "Service starting");
 
-        let (handler_send, handler_recv) = mpsc::unbounded_channel();
+        let (handler_send, mut handler_recv) = mpsc::unbounded_channel();
+
+        let scheduler = if slot_aware_scheduling {
+            Some(SlotScheduler::new(beacon_chain.clone()))
+        } else {
+            None
+        };
 
         // Initialise a message instance, which itself spawns the syncing thread.
         let processor = Processor::new(
             executor.clone(),
-            beacon_chain,
+            beacon_chain.clone(),
             network_globals.clone(),
             network_send.clone(),
+            blocks_by_range_quota,
+            block_serving_rate_limit,
             &log,
         );
 
         // generate the Message handler
         let mut handler = Router {
+            chain: beacon_chain,
             network_send,
             network_globals,
             processor,
+            scheduler,
+            deferred_requests: VecDeque::new(),
+            early_messages: <_>::default(),
+            low_priority_gossip: <_>::default(),
             log: message_handler_log,
         };
 
@@ -103,9 +146,35 @@ impl<T: BeaconChainTypes> Router<T> {
         executor.spawn(
             async move {
                 debug!(log, "Network message router started");
-                handler_recv
-                    .for_each(move |msg| future::ready(handler.handle_message(msg)))
-                    .await;
+                loop {
+                    let delay = vec![
+                        handler.deferred_requests_delay(),
+                        handler.early_messages_delay(),
+                        handler.low_priority_gossip_delay(),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .min();
+                    let message = match delay {
+                        // Serve the deferred requests, early messages and low priority gossip if no
+                        // message arrives before they are due.
+                        Some(delay) => match tokio::time::timeout(delay, handler_recv.next()).await
+                        {
+                            Ok(message) => message,
+                            Err(_) => {
+                                handler.process_deferred_requests();
+                                handler.process_early_messages();
+                                handler.process_low_priority_gossip();
+                                continue;
+                            }
+                        },
+                        None => handler_recv.next().await,
+                    };
+                    match message {
+                        Some(message) => handler.handle_message(message),
+                        None => break,
+                    }
+                }
             },
             "router",
         );
@@ -115,6 +184,9 @@ impl<T: BeaconChainTypes> Router<T> {
 
     /// Handle all messages incoming from the network service.
     fn handle_message(&mut self, message: RouterMessage<T::EthSpec>) {
+        self.process_deferred_requests();
+        self.process_early_messages();
+
         match message {
             // we have initiated a connection to a peer or the peer manager has requested a
             // re-status
@@ -175,15 +247,151 @@ impl<T: BeaconChainTypes> Router<T> {
                 );
                 self.processor.on_disconnect(peer_id);
             }
-            Request::BlocksByRange(request) => self
-                .processor
-                .on_blocks_by_range_request(peer_id, id, request),
+            Request::BlocksByRange(request) => {
+                if self.in_critical_window() {
+                    trace!(
+                        self.log,
+                        "Deferring BlocksByRange request";
+                        "peer_id" => peer_id.to_string(),
+                        "queued" => self.deferred_requests.len(),
+                    );
+                    self.deferred_requests.push_back((peer_id, id, request));
+                } else {
+                    self.processor
+                        .on_blocks_by_range_request(peer_id, id, request)
+                }
+            }
             Request::BlocksByRoot(request) => self
                 .processor
                 .on_blocks_by_root_request(peer_id, id, request),
         }
     }
 
+    /// Returns true if serving blocks to syncing peers should be deferred.
+    fn in_critical_window(&self) -> bool {
+        self.scheduler.as_ref().map_or(false, |scheduler| {
+            scheduler.critical_time_remaining().is_some()
+        })
+    }
+
+    /// Returns the time until the deferred requests may be served, or `None` if there are none.
+    fn deferred_requests_delay(&self) -> Option<Duration> {
+        if self.deferred_requests.is_empty() {
+            return None;
+        }
+        Some(
+            self.scheduler
+                .as_ref()
+                .and_then(SlotScheduler::critical_time_remaining)
+                .unwrap_or_else(|| Duration::from_secs(0)),
+        )
+    }
+
+    /// Serves the deferred `BlocksByRange` requests, unless they are still to be deferred.
+    fn process_deferred_requests(&mut self) {
+        if self.deferred_requests.is_empty() || self.in_critical_window() {
+            return;
+        }
+
+        debug!(
+            self.log,
+            "Serving deferred BlocksByRange requests";
+            "count" => self.deferred_requests.len(),
+        );
+        while let Some((peer_id, id, request)) = self.deferred_requests.pop_front() {
+            self.processor
+                .on_blocks_by_range_request(peer_id, id, request);
+        }
+    }
+
+    /// Returns the slot of a gossip block or attestation which is too early to be accepted by our
+    /// clock, but whose slot starts within `MAX_EARLY_MESSAGE_DELAY` of that, or `None` if it should
+    /// be processed now.
+    fn early_message_slot(&self, gossip_message: &PubsubMessage<T::EthSpec>) -> Option<Slot> {
+        let slot = match gossip_message {
+            PubsubMessage::BeaconBlock(block) => block.slot(),
+            PubsubMessage::Attestation(subnet_attestation) => subnet_attestation.1.data.slot,
+            PubsubMessage::AggregateAndProofAttestation(aggregate_and_proof) => {
+                aggregate_and_proof.message.aggregate.data.slot
+            }
+            _ => return None,
+        };
+        let current_slot = self.current_slot_with_tolerance()?;
+        let latest_early_slot = self.chain.slot_clock.now_with_future_tolerance(
+            self.chain.spec.maximum_gossip_clock_disparity() + MAX_EARLY_MESSAGE_DELAY,
+        )?;
+
+        if slot > current_slot && slot <= latest_early_slot {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the latest slot for which gossip messages are accepted, allowing for the maximum
+    /// gossip clock disparity.
+    fn current_slot_with_tolerance(&self) -> Option<Slot> {
+        self.chain
+            .slot_clock
+            .now_with_future_tolerance(self.chain.spec.maximum_gossip_clock_disparity())
+    }
+
+    /// Returns the time until the earliest queued gossip message may be processed, or `None` if
+    /// there are none.
+    fn early_messages_delay(&self) -> Option<Duration> {
+        let slot = self.early_messages.earliest_slot()?;
+        Some(
+            self.chain
+                .slot_clock
+                .duration_to_slot(slot)
+                .and_then(|duration| {
+                    duration.checked_sub(self.chain.spec.maximum_gossip_clock_disparity())
+                })
+                .unwrap_or_else(|| Duration::from_secs(0)),
+        )
+    }
+
+    /// Processes the queued gossip messages whose slot has started.
+    fn process_early_messages(&mut self) {
+        if self.early_messages.is_empty() {
+            return;
+        }
+
+        if let Some(current_slot) = self.current_slot_with_tolerance() {
+            for (peer_id, (id, gossip_message)) in self.early_messages.pop_ready(current_slot) {
+                self.handle_gossip(id, peer_id, gossip_message);
+            }
+        }
+    }
+
+    /// Returns a zero delay if there are queued low priority gossip messages, so that one is
+    /// processed as soon as no other message is waiting, or `None` if there are none.
+    fn low_priority_gossip_delay(&self) -> Option<Duration> {
+        if self.low_priority_gossip.is_empty() {
+            None
+        } else {
+            Some(Duration::from_secs(0))
+        }
+    }
+
+    /// Processes the oldest low priority gossip message, if any.
+    ///
+    /// Only a single message is processed so that messages which arrive meanwhile are not delayed.
+    fn process_low_priority_gossip(&mut self) {
+        if let Some((id, peer_id, gossip_message)) = self.low_priority_gossip.pop() {
+            self.process_gossip(id, peer_id, gossip_message);
+        }
+    }
+
+    /// Returns true if the peer is quarantined as it disagrees with our finalized chain.
+    fn is_quarantined(&self, peer_id: &PeerId) -> bool {
+        self.network_globals
+            .peers
+            .read()
+            .peer_info(peer_id)
+            .map_or(false, |info| info.sync_status.is_quarantined())
+    }
+
     /// An RPC response has been received from the network.
     // we match on id and ignore responses past the timeout.
     fn handle_rpc_response(
@@ -214,6 +422,60 @@ impl<T: BeaconChainTypes> Router<T> {
         id: MessageId,
         peer_id: PeerId,
         gossip_message: PubsubMessage<T::EthSpec>,
+    ) {
+        if let Some(slot) = self.early_message_slot(&gossip_message) {
+            if self
+                .early_messages
+                .push(slot, peer_id.clone(), (id, gossip_message))
+            {
+                metrics::inc_counter(&metrics::GOSSIP_EARLY_MESSAGES_QUEUED);
+                trace!(
+                    self.log,
+                    "Queued gossip message until its slot";
+                    "peer_id" => peer_id.to_string(),
+                    "slot" => slot,
+                    "queued" => self.early_messages.len(),
+                );
+            } else {
+                metrics::inc_counter(&metrics::GOSSIP_EARLY_MESSAGES_DROPPED);
+                debug!(
+                    self.log,
+                    "Dropped early gossip message";
+                    "reason" => "queue full",
+                    "peer_id" => peer_id.to_string(),
+                    "slot" => slot,
+                );
+            }
+            return;
+        }
+
+        if self.is_quarantined(&peer_id) {
+            if self
+                .low_priority_gossip
+                .push((id, peer_id.clone(), gossip_message))
+            {
+                metrics::inc_counter(&metrics::GOSSIP_LOW_PRIORITY_MESSAGES_QUEUED);
+            } else {
+                metrics::inc_counter(&metrics::GOSSIP_LOW_PRIORITY_MESSAGES_DROPPED);
+                debug!(
+                    self.log,
+                    "Dropped low priority gossip message";
+                    "reason" => "queue full",
+                    "peer_id" => peer_id.to_string(),
+                );
+            }
+            return;
+        }
+
+        self.process_gossip(id, peer_id, gossip_message);
+    }
+
+    /// Verifies, propagates and imports a gossip message.
+    fn process_gossip(
+        &mut self,
+        id: MessageId,
+        peer_id: PeerId,
+        gossip_message: PubsubMessage<T::EthSpec>,
     ) {
         match gossip_message {
             // Attestations should never reach the router.
@@ -233,6 +495,7 @@ impl<T: BeaconChainTypes> Router<T> {
                 if let Some(gossip_verified) =
                     self.processor.verify_unaggregated_attestation_for_gossip(
                         peer_id.clone(),
+                        subnet_attestation.0,
                         subnet_attestation.1.clone(),
                     )
                 {
@@ -1,3 +1,5 @@
+use super::bandwidth_limiter::BandwidthLimiter;
+use crate::metrics;
 use crate::service::NetworkMessage;
 use crate::sync::{PeerSyncInfo, SyncMessage};
 use beacon_chain::{
@@ -8,15 +10,16 @@ use beacon_chain::{
     BeaconChain, BeaconChainTypes, BlockError, BlockProcessingOutcome, GossipVerifiedBlock,
 };
 use eth2_libp2p::rpc::*;
-use eth2_libp2p::{NetworkGlobals, PeerId, PeerRequestId, Request, Response};
+use eth2_libp2p::{types::GossipKind, NetworkGlobals, PeerId, PeerRequestId, Request, Response};
 use itertools::process_results;
 use slog::{debug, error, o, trace, warn};
 use ssz::Encode;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use types::{
     Attestation, ChainSpec, Epoch, EthSpec, Hash256, SignedAggregateAndProof, SignedBeaconBlock,
-    Slot,
+    Slot, SubnetId,
 };
 
 //TODO: Rate limit requests
@@ -25,15 +28,25 @@ use types::{
 /// Otherwise we queue it.
 pub(crate) const FUTURE_SLOT_TOLERANCE: u64 = 1;
 
+/// The time a peer may remain quarantined for disagreeing with our finalized chain before it is
+/// disconnected.
+const QUARANTINE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
 /// Processes validated messages from the network. It relays necessary data to the syncing thread
 /// and processes blocks from the pubsub network.
 pub struct Processor<T: BeaconChainTypes> {
     /// A reference to the underlying beacon chain.
     chain: Arc<BeaconChain<T>>,
+    /// A collection of global variables, accessible outside of the network thread.
+    network_globals: Arc<NetworkGlobals<T::EthSpec>>,
     /// A channel to the syncing thread.
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
     /// A network context to return and handle RPC requests.
     network: HandlerNetworkContext<T::EthSpec>,
+    /// The maximum number of blocks served in response to a single `BlocksByRange` request.
+    blocks_by_range_quota: u64,
+    /// Limits the rate at which blocks are served, if a limit is configured.
+    block_serving_limiter: Option<BandwidthLimiter>,
     /// The `RPCHandler` logger.
     log: slog::Logger,
 }
@@ -45,6 +58,8 @@ impl<T: BeaconChainTypes> Processor<T> {
         beacon_chain: Arc<BeaconChain<T>>,
         network_globals: Arc<NetworkGlobals<T::EthSpec>>,
         network_send: mpsc::UnboundedSender<NetworkMessage<T::EthSpec>>,
+        blocks_by_range_quota: u64,
+        block_serving_rate_limit: Option<u64>,
         log: &slog::Logger,
     ) -> Self {
         let sync_logger = log.new(o!("service"=> "sync"));
@@ -53,15 +68,18 @@ impl<T: BeaconChainTypes> Processor<T> {
         let sync_send = crate::sync::manager::spawn(
             executor,
             beacon_chain.clone(),
-            network_globals,
+            network_globals.clone(),
             network_send.clone(),
             sync_logger,
         );
 
         Processor {
             chain: beacon_chain,
+            network_globals,
             sync_send,
             network: HandlerNetworkContext::new(network_send, log.clone()),
+            blocks_by_range_quota,
+            block_serving_limiter: block_serving_rate_limit.map(BandwidthLimiter::new),
             log: log.clone(),
         }
     }
@@ -180,6 +198,32 @@ impl<T: BeaconChainTypes> Processor<T> {
 
         let start_slot = |epoch: Epoch| epoch.start_slot(T::EthSpec::slots_per_epoch());
 
+        let conflicting_finalized_root = remote.finalized_epoch <= local.finalized_epoch
+            && remote.finalized_root != Hash256::zero()
+            && local.finalized_root != Hash256::zero()
+            && self
+                .chain
+                .root_at_slot(start_slot(remote.finalized_epoch))
+                .map(|root_opt| root_opt != Some(remote.finalized_root))
+                .unwrap_or_else(|_| false);
+
+        // Block production and attestation are halted if a quorum of trusted peers is on a
+        // different finalized chain.
+        self.chain
+            .observe_trusted_peer_status(&peer_id.to_string(), conflicting_finalized_root);
+
+        // A quarantined peer which now agrees with our finalized chain is promoted back to a
+        // normal peer. This must happen before the peer is handed to sync, which sets its new
+        // sync status.
+        if !conflicting_finalized_root
+            && self.network_globals.peers.write().end_quarantine(&peer_id)
+        {
+            debug!(
+                self.log, "Peer released from quarantine";
+                "peer" => format!("{:?}", peer_id),
+            );
+        }
+
         if local.fork_digest != remote.fork_digest {
             // The node is on a different network/fork, disconnect them.
             debug!(
@@ -208,26 +252,42 @@ impl<T: BeaconChainTypes> Processor<T> {
             );
             self.network
                 .disconnect(peer_id, GoodbyeReason::IrrelevantNetwork);
-        } else if remote.finalized_epoch <= local.finalized_epoch
-            && remote.finalized_root != Hash256::zero()
-            && local.finalized_root != Hash256::zero()
-            && self
-                .chain
-                .root_at_slot(start_slot(remote.finalized_epoch))
-                .map(|root_opt| root_opt != Some(remote.finalized_root))
-                .unwrap_or_else(|_| false)
-        {
+        } else if conflicting_finalized_root {
             // The remotes finalized epoch is less than or greater than ours, but the block root is
             // different to the one in our chain.
             //
-            // Therefore, the node is on a different chain and we should not communicate with them.
-            debug!(
-                self.log, "Handshake Failure";
-                "peer" => format!("{:?}", peer_id),
-                "reason" => "different finalized chain"
-            );
-            self.network
-                .disconnect(peer_id, GoodbyeReason::IrrelevantNetwork);
+            // Therefore, the node is on a different chain. It may be malicious, or it may be on a
+            // minority fork which will eventually reconcile with ours. Rather than disconnecting
+            // it straight away, we quarantine it: we stop syncing from it and re-status it
+            // periodically, only disconnecting it if it remains on a different chain.
+            let quarantined_since = self
+                .network_globals
+                .peers
+                .write()
+                .quarantine(&peer_id, remote.into());
+            self.send_to_sync(SyncMessage::Disconnect(peer_id.clone()));
+
+            match quarantined_since {
+                Some(since) if since.elapsed() >= QUARANTINE_TIMEOUT => {
+                    debug!(
+                        self.log, "Handshake Failure";
+                        "peer" => format!("{:?}", peer_id),
+                        "reason" => "different finalized chain"
+                    );
+                    self.network
+                        .disconnect(peer_id, GoodbyeReason::IrrelevantNetwork);
+                }
+                Some(_) => {
+                    debug!(
+                        self.log, "Peer quarantined";
+                        "peer" => format!("{:?}", peer_id),
+                        "reason" => "different finalized chain",
+                        "remote_finalized_epoch" => remote.finalized_epoch,
+                        "remote_finalized_root" => format!("{}", remote.finalized_root),
+                    );
+                }
+                None => {}
+            }
         } else if remote.finalized_epoch < local.finalized_epoch {
             // The node has a lower finalized epoch, their chain is not useful to us. There are two
             // cases where a node can have a lower finalized epoch:
@@ -289,6 +349,9 @@ impl<T: BeaconChainTypes> Processor<T> {
         let mut send_block_count = 0;
         for root in request.block_roots.iter() {
             if let Ok(Some(block)) = self.chain.store.get_block(root) {
+                if !self.within_rate_limit(&block) {
+                    return self.send_rate_limited(peer_id, request_id, send_block_count);
+                }
                 self.network.send_response(
                     peer_id.clone(),
                     Response::BlocksByRoot(Some(Box::new(block))),
@@ -322,7 +385,7 @@ impl<T: BeaconChainTypes> Processor<T> {
         &mut self,
         peer_id: PeerId,
         request_id: PeerRequestId,
-        req: BlocksByRangeRequest,
+        mut req: BlocksByRangeRequest,
     ) {
         debug!(
             self.log,
@@ -341,6 +404,19 @@ impl<T: BeaconChainTypes> Processor<T> {
             return;
         }
 
+        // Limit the bandwidth spent on a single request. The peer may request the remaining
+        // blocks later.
+        if req.count > self.blocks_by_range_quota {
+            debug!(
+                self.log,
+                "Limiting BlocksByRange request to quota";
+                "peer" => format!("{:?}", peer_id),
+                "requested" => req.count,
+                "quota" => self.blocks_by_range_quota,
+            );
+            req.count = self.blocks_by_range_quota;
+        }
+
         let forwards_block_root_iter = match self
             .chain
             .forwards_iter_block_roots(Slot::from(req.start_slot))
@@ -395,6 +471,9 @@ impl<T: BeaconChainTypes> Processor<T> {
                 if block.slot() >= req.start_slot
                     && block.slot() < req.start_slot + req.count * req.step
                 {
+                    if !self.within_rate_limit(&block) {
+                        return self.send_rate_limited(peer_id, request_id, blocks_sent);
+                    }
                     blocks_sent += 1;
                     self.network.send_response(
                         peer_id.clone(),
@@ -437,6 +516,38 @@ impl<T: BeaconChainTypes> Processor<T> {
             .send_response(peer_id, Response::BlocksByRange(None), request_id);
     }
 
+    /// Returns true if `block` may be served to a peer without exceeding the block serving rate
+    /// limit.
+    fn within_rate_limit(&mut self, block: &SignedBeaconBlock<T::EthSpec>) -> bool {
+        match &mut self.block_serving_limiter {
+            Some(limiter) => limiter.try_send(block.ssz_bytes_len() as u64, Instant::now()),
+            None => true,
+        }
+    }
+
+    /// Ends the response to a block request with a rate limited error, rather than delaying the
+    /// remaining blocks until the rate limit allows them.
+    fn send_rate_limited(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        blocks_sent: usize,
+    ) {
+        debug!(
+            self.log,
+            "Block request rate limited";
+            "peer" => format!("{:?}", peer_id),
+            "returned" => blocks_sent,
+        );
+        metrics::inc_counter(&metrics::RPC_BLOCK_REQUESTS_RATE_LIMITED);
+        self.network.send_error_response(
+            peer_id,
+            request_id,
+            RPCResponseErrorCode::RateLimited,
+            "Serving too many blocks".to_string(),
+        );
+    }
+
     /// Handle a `BlocksByRange` response from the peer.
     /// A `beacon_block` behaves as a stream which is terminated on a `None` response.
     pub fn on_blocks_by_range_response(
@@ -501,10 +612,22 @@ impl<T: BeaconChainTypes> Processor<T> {
     ) -> Result<GossipVerifiedBlock<T>, BlockError> {
         let result = self.chain.verify_block_for_gossip(*block.clone());
 
-        if let Err(BlockError::ParentUnknown(_)) = result {
-            // if we don't know the parent, start a parent lookup
-            // TODO: Modify the return to avoid the block clone.
-            self.send_to_sync(SyncMessage::UnknownBlock(peer_id.clone(), block));
+        match result {
+            Err(BlockError::ParentUnknown(_)) => {
+                // if we don't know the parent, start a parent lookup
+                // TODO: Modify the return to avoid the block clone.
+                self.send_to_sync(SyncMessage::UnknownBlock(peer_id.clone(), block));
+            }
+            Err(BlockError::GenesisBlock)
+            | Err(BlockError::IncorrectBlockProposer { .. })
+            | Err(BlockError::ProposalSignatureInvalid)
+            | Err(BlockError::UnknownValidator(_))
+            | Err(BlockError::BlockIsNotLaterThanParent { .. }) => {
+                // The peer has published an invalid block.
+                self.network
+                    .gossip_rejected(peer_id.clone(), GossipKind::BeaconBlock);
+            }
+            _ => {}
         }
         result
     }
@@ -588,6 +711,7 @@ impl<T: BeaconChainTypes> Processor<T> {
         peer_id: PeerId,
         beacon_block_root: Hash256,
         attestation_type: &str,
+        topic: &GossipKind,
         error: AttnError,
     ) {
         debug!(
@@ -616,6 +740,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::EmptyAggregationBitfield => {
                 /*
@@ -643,6 +768,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::AggregatorNotInCommittee { .. } => {
                 /*
@@ -658,6 +784,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::AttestationAlreadyKnown { .. } => {
                 /*
@@ -689,6 +816,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::UnknownHeadBlock { beacon_block_root } => {
                 // Note: its a little bit unclear as to whether or not this block is unknown or
@@ -723,6 +851,15 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
+            }
+            AttnError::InvalidTargetRoot { .. } => {
+                /*
+                 * The target root is not an ancestor of the attested block.
+                 *
+                 * The peer has published an invalid consensus message.
+                 */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::BadTargetEpoch => {
                 /*
@@ -731,6 +868,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::NoCommitteeForSlotAndIndex { .. } => {
                 /*
@@ -738,6 +876,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::NotExactlyOneAggregationBitSet(_) => {
                 /*
@@ -745,6 +884,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::AttestsToFutureBlock { .. } => {
                 /*
@@ -752,6 +892,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::Invalid(_) => {
                 /*
@@ -759,6 +900,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                  *
                  * The peer has published an invalid consensus message.
                  */
+                self.network.gossip_rejected(peer_id.clone(), topic.clone());
             }
             AttnError::BeaconChainError(e) => {
                 /*
@@ -793,6 +935,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                     peer_id,
                     beacon_block_root,
                     "aggregated",
+                    &GossipKind::BeaconAggregateAndProof,
                     e,
                 )
             })
@@ -827,6 +970,7 @@ impl<T: BeaconChainTypes> Processor<T> {
     pub fn verify_unaggregated_attestation_for_gossip(
         &mut self,
         peer_id: PeerId,
+        subnet_id: SubnetId,
         unaggregated_attestation: Attestation<T::EthSpec>,
     ) -> Option<VerifiedUnaggregatedAttestation<T>> {
         // This is provided to the error handling function to assist with debugging.
@@ -839,6 +983,7 @@ impl<T: BeaconChainTypes> Processor<T> {
                     peer_id,
                     beacon_block_root,
                     "unaggregated",
+                    &GossipKind::CommitteeIndex(subnet_id),
                     e,
                 )
             })
@@ -939,6 +1084,11 @@ impl<T: EthSpec> HandlerNetworkContext<T> {
             .unwrap_or_else(|_| warn!(self.log, "Could not send message to the network service"))
     }
 
+    /// Informs the network that a gossip message on `topic` from the peer failed validation.
+    pub fn gossip_rejected(&mut self, peer_id: PeerId, topic: GossipKind) {
+        self.inform_network(NetworkMessage::GossipRejected { peer_id, topic })
+    }
+
     pub fn disconnect(&mut self, peer_id: PeerId, reason: GoodbyeReason) {
         warn!(
             &self.log,
@@ -965,7 +1115,7 @@ impl<T: EthSpec> HandlerNetworkContext<T> {
             response,
         })
     }
-    pub fn _send_error_response(
+    pub fn send_error_response(
         &mut self,
         peer_id: PeerId,
         id: PeerRequestId,
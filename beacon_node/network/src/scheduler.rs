@@ -0,0 +1,78 @@
+//! Defers non-critical network work until after the start of each slot.
+//!
+//! Blocks are imported and attestations are produced during the first third of each slot (the
+//! first 4 seconds on mainnet). On machines with few cores, serving blocks to syncing peers and
+//! searching for new peers during this window can delay these duties past their deadlines, so
+//! such work is deferred until the window has passed.
+
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use slot_clock::SlotClock;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Determines whether non-critical work should be deferred.
+pub struct SlotScheduler<T: BeaconChainTypes> {
+    chain: Arc<BeaconChain<T>>,
+    /// The duration at the start of each slot during which non-critical work is deferred.
+    critical_window: Duration,
+}
+
+impl<T: BeaconChainTypes> SlotScheduler<T> {
+    /// Creates a scheduler which defers work during the first third of each slot.
+    pub fn new(chain: Arc<BeaconChain<T>>) -> Self {
+        let critical_window = chain.slot_clock.slot_duration() / 3;
+        Self {
+            chain,
+            critical_window,
+        }
+    }
+
+    /// Returns the time remaining in the critical window of the current slot, or `None` if the
+    /// window has passed and non-critical work may proceed.
+    pub fn critical_time_remaining(&self) -> Option<Duration> {
+        let slot_clock = &self.chain.slot_clock;
+        critical_time_remaining_at(slot_clock, self.critical_window, slot_clock.now_duration()?)
+    }
+
+    /// Returns the time until the critical window of the next slot begins.
+    pub fn duration_to_next_window(&self) -> Option<Duration> {
+        self.chain.slot_clock.duration_to_next_slot()
+    }
+}
+
+/// Returns the time remaining at `now` in the `critical_window` at the start of the slot.
+fn critical_time_remaining_at<S: SlotClock>(
+    slot_clock: &S,
+    critical_window: Duration,
+    now: Duration,
+) -> Option<Duration> {
+    let slot_start = slot_clock.start_of(slot_clock.slot_of(now)?)?;
+    let elapsed = now.checked_sub(slot_start)?;
+    critical_window
+        .checked_sub(elapsed)
+        .filter(|remaining| *remaining > Duration::from_secs(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slot_clock::ManualSlotClock;
+    use types::Slot;
+
+    #[test]
+    fn defers_during_first_third_of_slot() {
+        let genesis = Duration::from_secs(100);
+        let slot_clock = ManualSlotClock::new(Slot::new(0), genesis, Duration::from_secs(12));
+        let window = Duration::from_secs(4);
+        let at = |now| critical_time_remaining_at(&slot_clock, window, now);
+        let after_genesis = |secs| at(genesis + Duration::from_secs(secs));
+
+        assert_eq!(after_genesis(0), Some(Duration::from_secs(4)));
+        assert_eq!(after_genesis(3), Some(Duration::from_secs(1)));
+        assert_eq!(after_genesis(4), None);
+        assert_eq!(after_genesis(11), None);
+        assert_eq!(after_genesis(13), Some(Duration::from_secs(3)));
+        // work is not deferred before genesis
+        assert_eq!(at(genesis - Duration::from_secs(1)), None);
+    }
+}
@@ -1,5 +1,9 @@
+use crate::fork_transition::{
+    next_fork_transition_step, ForkTransitionStep, FORK_TRANSITION_EPOCHS,
+};
 use crate::persisted_dht::{load_dht, persist_dht};
 use crate::router::{Router, RouterMessage};
+use crate::scheduler::SlotScheduler;
 use crate::{
     attestation_service::{AttServiceMessage, AttestationService},
     NetworkConfig,
@@ -9,24 +13,44 @@ use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::Service as LibP2PService;
 use eth2_libp2p::{
     rpc::{RPCResponseErrorCode, RequestId},
+    types::{GossipKind, NetworkSnapshot},
     Libp2pEvent, PeerRequestId, PubsubMessage, Request, Response,
 };
-use eth2_libp2p::{BehaviourEvent, MessageId, NetworkGlobals, PeerId};
+use eth2_libp2p::{BehaviourEvent, Eth2Enr, MessageId, NetworkGlobals, PeerId};
 use futures::prelude::*;
 use rest_types::ValidatorSubscription;
-use slog::{debug, error, info, o, trace};
+use slog::{debug, error, info, o, trace, warn};
+use slot_clock::SlotClock;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::net::{TcpListener, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use store::HotColdDB;
 use tokio::sync::mpsc;
 use tokio::time::Delay;
-use types::EthSpec;
+use types::{EthSpec, SubnetId};
 
 mod tests;
 
 /// The time in seconds that a peer will be banned and prevented from reconnecting.
 const BAN_PEER_TIMEOUT: u64 = 30;
 
+/// The time to wait between attempts to restart the libp2p service.
+const RESTART_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The maximum time to wait for the ports of a stopped libp2p service to be released.
+const PORT_RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The interval at which the ports of a stopped libp2p service are polled.
+const PORT_RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The directory, within the network directory, to which network snapshots are written.
+const SNAPSHOT_DIR: &str = "snapshots";
+
 /// Service that handles communication between internal services and the `eth2_libp2p` network service.
 pub struct NetworkService<T: BeaconChainTypes> {
     /// A reference to the underlying beacon chain.
@@ -44,8 +68,25 @@ pub struct NetworkService<T: BeaconChainTypes> {
     store: Arc<HotColdDB<T::EthSpec, T::HotStore, T::ColdStore>>,
     /// A collection of global variables, accessible outside of the network service.
     network_globals: Arc<NetworkGlobals<T::EthSpec>>,
-    /// A delay that expires when a new fork takes place.
-    next_fork_update: Option<Delay>,
+    /// The next step in the transition of our gossip topics to the next fork, and a delay that
+    /// expires when it should be taken.
+    next_fork_transition: Option<(ForkTransitionStep, Delay)>,
+    /// Pauses peer discovery during the start of each slot, if enabled.
+    scheduler: Option<SlotScheduler<T>>,
+    /// A delay that expires when discovery should next be paused or resumed.
+    next_schedule_update: Option<Delay>,
+    /// Whether validator subscriptions to attestation subnets are ignored.
+    proposer_only: bool,
+    /// Requests to write a network snapshot, made by sending the process a `SIGUSR1`.
+    snapshot_signals: Pin<Box<dyn Stream<Item = ()> + Send>>,
+    /// The directory to which network snapshots are written.
+    snapshot_dir: PathBuf,
+    /// The config of the running libp2p service, from which it is rebuilt on a restart.
+    config: NetworkConfig,
+    /// The executor on which the libp2p service spawns its tasks.
+    executor: environment::TaskExecutor,
+    /// The new listening ports of a restart requested while handling the current event, if any.
+    pending_restart: Option<(Option<u16>, Option<u16>)>,
     /// The logger for the network service.
     log: slog::Logger,
 }
@@ -68,8 +109,8 @@ impl<T: BeaconChainTypes> NetworkService<T> {
         // build the current enr_fork_id for adding to our local ENR
         let enr_fork_id = beacon_chain.enr_fork_id();
 
-        // keep track of when our fork_id needs to be updated
-        let next_fork_update = next_fork_delay(&beacon_chain);
+        // keep track of when our gossip topics and fork_id need to be updated
+        let next_fork_transition = next_fork_transition(&beacon_chain, None);
 
         // launch libp2p service
         let (network_globals, mut libp2p) =
@@ -92,6 +133,9 @@ impl<T: BeaconChainTypes> NetworkService<T> {
             beacon_chain.clone(),
             network_globals.clone(),
             network_send.clone(),
+            config.blocks_by_range_quota,
+            config.block_serving_rate_limit,
+            config.slot_aware_scheduling,
             executor.clone(),
             network_log.clone(),
         )?;
@@ -100,6 +144,12 @@ impl<T: BeaconChainTypes> NetworkService<T> {
         let attestation_service =
             AttestationService::new(beacon_chain.clone(), network_globals.clone(), &network_log);
 
+        let scheduler = if config.slot_aware_scheduling {
+            Some(SlotScheduler::new(beacon_chain.clone()))
+        } else {
+            None
+        };
+
         // create the network service and spawn the task
         let network_log = network_log.new(o!("service"=> "network"));
         let network_service = NetworkService {
@@ -110,7 +160,15 @@ impl<T: BeaconChainTypes> NetworkService<T> {
             router_send,
             store,
             network_globals: network_globals.clone(),
-            next_fork_update,
+            next_fork_transition,
+            scheduler,
+            next_schedule_update: None,
+            proposer_only: config.proposer_only,
+            snapshot_signals: snapshot_signals(&network_log),
+            snapshot_dir: config.network_dir.join(SNAPSHOT_DIR),
+            config: config.clone(),
+            executor: executor.clone(),
+            pending_restart: None,
             log: network_log,
         };
 
@@ -206,14 +264,40 @@ fn spawn_service<T: BeaconChainTypes>(
                                 std::time::Duration::from_secs(BAN_PEER_TIMEOUT),
                             );
                         }
+                        NetworkMessage::GossipRejected { peer_id, topic } => {
+                            service
+                                .libp2p
+                                .swarm
+                                .peer_manager()
+                                .gossip_rejected(&peer_id, topic.to_string());
+                        }
+                        NetworkMessage::Snapshot => {
+                            save_snapshot(&mut service);
+                        }
+                        NetworkMessage::Restart { libp2p_port, discovery_port } => {
+                            // the libp2p service is borrowed until the event has been handled
+                            service.pending_restart = Some((libp2p_port, discovery_port));
+                        }
                         NetworkMessage::Subscribe { subscriptions } => {
-                            // the result is dropped as it used solely for ergonomics
-                            let _ = service
-                                .attestation_service
-                                .validator_subscriptions(subscriptions);
+                            if service.proposer_only {
+                                debug!(
+                                    service.log,
+                                    "Ignoring subnet subscriptions in proposer-only mode";
+                                    "count" => subscriptions.len()
+                                );
+                            } else {
+                                // the result is dropped as it used solely for ergonomics
+                                let _ = service
+                                    .attestation_service
+                                    .validator_subscriptions(subscriptions);
+                            }
                         }
                     }
                 }
+                // write a network snapshot when signalled
+                Some(()) = service.snapshot_signals.next() => {
+                    save_snapshot(&mut service);
+                }
                 // process any attestation service events
                 Some(attestation_service_message) = service.attestation_service.next() => {
                     match attestation_service_message {
@@ -274,6 +358,11 @@ fn spawn_service<T: BeaconChainTypes>(
                                     });
 
                             }
+                            BehaviourEvent::PeerBanned(_)
+                            | BehaviourEvent::PeerUnbanned(_)
+                            | BehaviourEvent::PeerAdmitted(..) => {
+                                // handled by the libp2p service
+                            }
                             BehaviourEvent::StatusPeer(peer_id) => {
                                 let _ = service
                                     .router_send
@@ -352,30 +441,99 @@ fn spawn_service<T: BeaconChainTypes>(
                 }
             }
 
-            if let Some(delay) = &service.next_fork_update {
-                if delay.is_elapsed() {
-                    service
-                        .libp2p
-                        .swarm
-                        .update_fork_version(service.beacon_chain.enr_fork_id());
-                    service.next_fork_update = next_fork_delay(&service.beacon_chain);
-                }
+            if let Some((libp2p_port, discovery_port)) = service.pending_restart.take() {
+                service = restart_libp2p(service, libp2p_port, discovery_port).await;
             }
+
+            update_fork_transition(&mut service);
+
+            update_discovery_schedule(&mut service);
         }
     }, "network");
 
     Ok(())
 }
 
-/// Returns a `Delay` that triggers shortly after the next change in the beacon chain fork version.
-/// If there is no scheduled fork, `None` is returned.
-fn next_fork_delay<T: BeaconChainTypes>(
+/// Pauses peer discovery during the critical window at the start of each slot and resumes it
+/// afterwards, if slot-aware scheduling is enabled.
+fn update_discovery_schedule<T: BeaconChainTypes>(service: &mut NetworkService<T>) {
+    let scheduler = match &service.scheduler {
+        Some(scheduler) => scheduler,
+        None => return,
+    };
+    if let Some(delay) = &service.next_schedule_update {
+        if !delay.is_elapsed() {
+            return;
+        }
+    }
+
+    let critical_time_remaining = scheduler.critical_time_remaining();
+    let until_next_update = critical_time_remaining
+        .or_else(|| scheduler.duration_to_next_window())
+        .unwrap_or_else(|| Duration::from_secs(1));
+
+    service
+        .libp2p
+        .swarm
+        .peer_manager()
+        .discovery_mut()
+        .set_paused(critical_time_remaining.is_some());
+    service.next_schedule_update = Some(tokio::time::delay_until(
+        tokio::time::Instant::now() + until_next_update,
+    ));
+}
+
+/// Takes the next step in the transition of our gossip topics to the next fork, if it is due.
+fn update_fork_transition<T: BeaconChainTypes>(service: &mut NetworkService<T>) {
+    let step = match &service.next_fork_transition {
+        Some((step, delay)) if delay.is_elapsed() => *step,
+        _ => return,
+    };
+
+    match step {
+        ForkTransitionStep::SubscribeNextFork => {
+            if let Some(enr_fork_id) = service.beacon_chain.next_enr_fork_id() {
+                service
+                    .libp2p
+                    .swarm
+                    .begin_fork_transition(enr_fork_id.fork_digest);
+            }
+        }
+        ForkTransitionStep::ActivateFork => {
+            info!(service.log, "Activating the next fork");
+            service
+                .libp2p
+                .swarm
+                .update_fork_version(service.beacon_chain.enr_fork_id());
+        }
+        ForkTransitionStep::UnsubscribePreviousFork => {
+            service.libp2p.swarm.end_fork_transition();
+        }
+    }
+
+    service.next_fork_transition = next_fork_transition(&service.beacon_chain, Some(step));
+}
+
+/// Returns the step in the transition of our gossip topics to the next fork that follows
+/// `previous_step`, along with a `Delay` that triggers when it should be taken. If there is no
+/// scheduled fork, `None` is returned.
+fn next_fork_transition<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
-) -> Option<tokio::time::Delay> {
-    beacon_chain.duration_to_next_fork().map(|until_fork| {
-        // Add a short time-out to start within the new fork period.
-        let delay = Duration::from_millis(200);
-        tokio::time::delay_until(tokio::time::Instant::now() + until_fork + delay)
+    previous_step: Option<ForkTransitionStep>,
+) -> Option<(ForkTransitionStep, Delay)> {
+    let transition_window = beacon_chain.slot_clock.slot_duration()
+        * T::EthSpec::slots_per_epoch() as u32
+        * FORK_TRANSITION_EPOCHS;
+    next_fork_transition_step(
+        previous_step,
+        beacon_chain.duration_to_next_fork(),
+        transition_window,
+    )
+    .map(|(step, until_step)| {
+        (
+            step,
+            tokio::time::delay_until(tokio::time::Instant::now() + until_step),
+        )
     })
 }
 
@@ -416,6 +574,214 @@ pub enum NetworkMessage<T: EthSpec> {
     },
     /// Disconnect and bans a peer id.
     Disconnect { peer_id: PeerId },
+    /// A gossip message on `topic` from the peer failed validation.
+    GossipRejected { peer_id: PeerId, topic: GossipKind },
+    /// Write a diagnostic snapshot of the network to the network directory.
+    Snapshot,
+    /// Tear down the libp2p service and build a new one, keeping the `PeerDB`. The listening
+    /// ports are replaced by those given, if any.
+    Restart {
+        libp2p_port: Option<u16>,
+        discovery_port: Option<u16>,
+    },
+}
+
+/// Replaces the libp2p service of the network service with a new one listening on the given
+/// ports, if any. The peers of the previous service are disconnected, but the `NetworkGlobals`
+/// are kept, and with them the `PeerDB`. The gossip topics and long-lived subnets of the
+/// previous service are subscribed to by the new one.
+///
+/// The ports of the previous service are released asynchronously, so they are polled until they
+/// are free before the new service is built. If it cannot be built, the previous config is
+/// restored and the restart is retried until it succeeds, as the network service cannot continue
+/// without a libp2p service.
+async fn restart_libp2p<T: BeaconChainTypes>(
+    mut service: NetworkService<T>,
+    libp2p_port: Option<u16>,
+    discovery_port: Option<u16>,
+) -> NetworkService<T> {
+    let mut config = service.config.clone();
+    config.libp2p_port = libp2p_port.unwrap_or(config.libp2p_port);
+    config.discovery_port = discovery_port.unwrap_or(config.discovery_port);
+    info!(service.log, "Restarting the network stack"; "tcp_port" => config.libp2p_port, "udp_port" => config.discovery_port);
+
+    // keep the routing table, which is lost with the discovery service
+    let enrs = service.libp2p.swarm.enr_entries();
+    let connected_peers = service
+        .network_globals
+        .peers
+        .read()
+        .connected_peer_ids()
+        .cloned()
+        .collect::<Vec<_>>();
+    // the subscriptions are reset with the `NetworkGlobals` when the new service is built
+    let subscribed_kinds = service
+        .network_globals
+        .gossipsub_subscriptions
+        .read()
+        .iter()
+        .map(|topic| topic.kind().clone())
+        .collect::<HashSet<_>>();
+    let long_lived_subnets = service
+        .network_globals
+        .local_enr()
+        .bitfield::<T::EthSpec>()
+        .map(|bitfield| {
+            bitfield
+                .iter()
+                .enumerate()
+                .filter(|(_, subscribed)| *subscribed)
+                .map(|(subnet, _)| SubnetId::new(subnet as u64))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    // the previous service must release its ports before the new one listens on them
+    drop(service.libp2p);
+
+    let enr_fork_id = service.beacon_chain.enr_fork_id();
+    let executor = service.executor.clone();
+    let network_globals = service.network_globals.clone();
+    let libp2p = loop {
+        wait_for_ports(&config, &service.log).await;
+        match LibP2PService::restart(
+            executor.clone(),
+            &config,
+            enr_fork_id.clone(),
+            network_globals.clone(),
+            executor.log(),
+        ) {
+            Ok(libp2p) => break libp2p,
+            Err(e) => {
+                error!(service.log, "Failed to restart the network stack, restoring the previous config"; "error" => format!("{:?}", e));
+                config = service.config.clone();
+            }
+        }
+        tokio::time::delay_for(RESTART_RETRY_DELAY).await;
+    };
+    service.libp2p = libp2p;
+    service.config = config;
+
+    for enr in enrs {
+        service.libp2p.swarm.add_enr(enr);
+    }
+    for subnet_id in long_lived_subnets {
+        service.libp2p.swarm.update_enr_subnet(subnet_id, true);
+    }
+    for kind in subscribed_kinds {
+        service.libp2p.swarm.subscribe_kind(kind);
+    }
+
+    // the router does not see the peers of the previous service disconnect
+    for peer_id in connected_peers {
+        let _ = service
+            .router_send
+            .send(RouterMessage::PeerDisconnected(peer_id))
+            .map_err(|_| {
+                debug!(service.log, "Failed to send peer disconnect to router");
+            });
+    }
+
+    service
+}
+
+/// Waits until the TCP and UDP ports of `config` can be bound, or until `PORT_RELEASE_TIMEOUT`
+/// has elapsed.
+async fn wait_for_ports(config: &NetworkConfig, log: &slog::Logger) {
+    let ports_free = || {
+        config
+            .libp2p_listen_addresses()
+            .all(|addr| TcpListener::bind((addr, config.libp2p_port)).is_ok())
+            && UdpSocket::bind((config.discovery_listen_address(), config.discovery_port)).is_ok()
+    };
+
+    let deadline = Instant::now() + PORT_RELEASE_TIMEOUT;
+    while !ports_free() {
+        if Instant::now() >= deadline {
+            warn!(log, "Network ports have not been released"; "tcp_port" => config.libp2p_port, "udp_port" => config.discovery_port);
+            return;
+        }
+        tokio::time::delay_for(PORT_RELEASE_POLL_INTERVAL).await;
+    }
+}
+
+/// Returns a stream of the `SIGUSR1` signals received by the process, each of which requests a
+/// network snapshot. The stream never yields if the signal cannot be listened for.
+fn snapshot_signals(log: &slog::Logger) -> Pin<Box<dyn Stream<Item = ()> + Send>> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::user_defined1()) {
+            Ok(signals) => return Box::pin(signals),
+            Err(e) => warn!(
+                log,
+                "Unable to listen for network snapshot signals";
+                "error" => format!("{:?}", e)
+            ),
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = log;
+
+    Box::pin(futures::stream::pending())
+}
+
+/// Takes a snapshot of the network and writes it to the snapshot directory as JSON.
+///
+/// The snapshot is taken immediately, but written on the blocking thread pool so that the network
+/// is not stalled by the file system.
+fn save_snapshot<T: BeaconChainTypes>(service: &mut NetworkService<T>) {
+    let snapshot = service.libp2p.swarm.network_snapshot();
+    let dir = service.snapshot_dir.clone();
+    let log = service.log.clone();
+
+    service.executor.spawn_blocking(
+        move || match write_snapshot(&dir, &snapshot) {
+            Ok(path) => info!(
+                log,
+                "Wrote network snapshot";
+                "path" => format!("{}", path.display()),
+                "peers" => snapshot.peers.len(),
+            ),
+            Err(e) => error!(
+                log,
+                "Failed to write network snapshot";
+                "dir" => format!("{}", dir.display()),
+                "error" => e,
+            ),
+        },
+        "network_snapshot",
+    );
+}
+
+/// Writes the `snapshot` to a new file in `dir`, returning its path.
+///
+/// Files are named by the timestamp in milliseconds, with a numeric suffix if a snapshot with the
+/// same timestamp has already been written.
+fn write_snapshot<E: EthSpec>(
+    dir: &Path,
+    snapshot: &NetworkSnapshot<E>,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Unable to create snapshot directory: {:?}", e))?;
+
+    let mut suffix = 0;
+    let (path, file) = loop {
+        let name = if suffix == 0 {
+            format!("network_snapshot_{}.json", snapshot.timestamp)
+        } else {
+            format!("network_snapshot_{}_{}.json", snapshot.timestamp, suffix)
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => break (path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(e) => return Err(format!("Unable to create file: {:?}", e)),
+        }
+    };
+
+    serde_json::to_writer_pretty(file, snapshot)
+        .map_err(|e| format!("Unable to serialize snapshot: {:?}", e))?;
+    Ok(path)
 }
 
 /// Inspects the `messages` that were being sent to the network and updates Prometheus metrics.
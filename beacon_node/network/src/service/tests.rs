@@ -2,13 +2,15 @@
 #[cfg(test)]
 mod tests {
     use crate::persisted_dht::load_dht;
-    use crate::{NetworkConfig, NetworkService};
+    use crate::{NetworkConfig, NetworkMessage, NetworkService};
     use beacon_chain::test_utils::BeaconChainHarness;
-    use eth2_libp2p::Enr;
+    use eth2_libp2p::{Enr, NetworkGlobals};
     use slog::Logger;
     use sloggers::{null::NullLoggerBuilder, Build};
+    use std::collections::HashSet;
     use std::str::FromStr;
     use std::sync::Arc;
+    use std::time::{Duration, Instant};
     use store::config::StoreConfig;
     use tokio::runtime::Runtime;
     use types::{test_utils::generate_deterministic_keypairs, MinimalEthSpec};
@@ -66,4 +68,166 @@ mod tests {
             "should have persisted the second ENR to store"
         );
     }
+
+    fn subscribed_kinds(
+        network_globals: &NetworkGlobals<MinimalEthSpec>,
+    ) -> HashSet<eth2_libp2p::types::GossipKind> {
+        network_globals
+            .gossipsub_subscriptions
+            .read()
+            .iter()
+            .map(|topic| topic.kind().clone())
+            .collect()
+    }
+
+    /// Restarts a network service listening on `port` with the given ports and waits
+    /// until the new libp2p service listens on them with the subscriptions of the previous one.
+    ///
+    /// One of the ports should be kept, so that the new service must wait for the previous one to
+    /// release it.
+    fn restart_and_check(port: u16, libp2p_port: Option<u16>, discovery_port: Option<u16>) {
+        let log = get_logger();
+
+        let beacon_chain = Arc::new(
+            BeaconChainHarness::new(
+                MinimalEthSpec,
+                generate_deterministic_keypairs(8),
+                StoreConfig::default(),
+            )
+            .chain,
+        );
+
+        let runtime = Runtime::new().unwrap();
+
+        let (signal, exit) = exit_future::signal();
+        let executor = environment::TaskExecutor::new(runtime.handle().clone(), exit, log.clone());
+
+        let network_dir = tempfile::tempdir().unwrap();
+        let mut config = NetworkConfig::default();
+        config.network_dir = network_dir.path().to_path_buf();
+        config.libp2p_port = port;
+        config.discovery_port = port;
+
+        runtime.block_on(async move {
+            let (network_globals, network_send) =
+                NetworkService::start(beacon_chain, &config, executor).unwrap();
+            let subscriptions = subscribed_kinds(&network_globals);
+            assert!(!subscriptions.is_empty());
+
+            network_send
+                .send(NetworkMessage::Restart {
+                    libp2p_port,
+                    discovery_port,
+                })
+                .unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while network_globals.listen_port_tcp() != libp2p_port.unwrap_or(port)
+                || network_globals.listen_port_udp() != discovery_port.unwrap_or(port)
+                || subscribed_kinds(&network_globals) != subscriptions
+            {
+                assert!(
+                    Instant::now() < deadline,
+                    "should restart with the previous subscriptions"
+                );
+                tokio::time::delay_for(Duration::from_millis(100)).await;
+            }
+        });
+        drop(signal);
+    }
+
+    #[test]
+    fn test_restart_on_new_libp2p_port() {
+        restart_and_check(21213, Some(21214), None);
+    }
+
+    #[test]
+    fn test_restart_on_new_discovery_port() {
+        restart_and_check(21215, None, Some(21216));
+    }
+
+    #[test]
+    fn test_snapshot_on_request() {
+        let log = get_logger();
+
+        let beacon_chain = Arc::new(
+            BeaconChainHarness::new(
+                MinimalEthSpec,
+                generate_deterministic_keypairs(8),
+                StoreConfig::default(),
+            )
+            .chain,
+        );
+
+        let runtime = Runtime::new().unwrap();
+
+        let (signal, exit) = exit_future::signal();
+        let executor = environment::TaskExecutor::new(runtime.handle().clone(), exit, log.clone());
+
+        let network_dir = tempfile::tempdir().unwrap();
+        let snapshot_dir = network_dir.path().join(super::SNAPSHOT_DIR);
+        let mut config = NetworkConfig::default();
+        config.network_dir = network_dir.path().to_path_buf();
+        config.libp2p_port = 21217;
+        config.discovery_port = 21217;
+
+        runtime.block_on(async move {
+            let (network_globals, network_send) =
+                NetworkService::start(beacon_chain, &config, executor).unwrap();
+            network_send.send(NetworkMessage::Snapshot).unwrap();
+
+            // the snapshot is written in the background, so wait until it can be parsed
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let (path, snapshot) = loop {
+                let written = std::fs::read_dir(&snapshot_dir)
+                    .ok()
+                    .and_then(|mut entries| entries.next())
+                    .and_then(|entry| entry.ok())
+                    .and_then(|entry| {
+                        let path = entry.path();
+                        let contents = std::fs::read(&path).ok()?;
+                        let snapshot =
+                            serde_json::from_slice::<serde_json::Value>(&contents).ok()?;
+                        Some((path, snapshot))
+                    });
+                if let Some(written) = written {
+                    break written;
+                }
+                assert!(Instant::now() < deadline, "should write a snapshot");
+                tokio::time::delay_for(Duration::from_millis(100)).await;
+            };
+
+            let timestamp = snapshot["timestamp"].as_u64().unwrap();
+            assert_eq!(
+                path.file_name().unwrap().to_str().unwrap(),
+                format!("network_snapshot_{}.json", timestamp)
+            );
+
+            let subscriptions = snapshot["subscriptions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|topic| topic.as_str().unwrap().to_string())
+                .collect::<Vec<_>>();
+            assert!(!subscriptions.is_empty());
+            assert!(subscriptions.iter().all(|topic| network_globals
+                .gossipsub_subscriptions
+                .read()
+                .iter()
+                .any(|subscribed| String::from(subscribed.clone()) == *topic)));
+
+            // without any peers, each subscribed topic has an empty mesh and no RPC is in flight
+            let mesh_peers = snapshot["mesh_peers"].as_object().unwrap();
+            assert_eq!(
+                mesh_peers.keys().cloned().collect::<Vec<_>>(),
+                subscriptions
+            );
+            assert!(mesh_peers
+                .values()
+                .all(|peers| peers.as_array().unwrap().is_empty()));
+            assert_eq!(snapshot["rpc_streams"], serde_json::json!([]));
+            assert_eq!(snapshot["peers"], serde_json::json!([]));
+        });
+        drop(signal);
+    }
 }
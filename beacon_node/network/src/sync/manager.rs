@@ -32,6 +32,11 @@
 //! To keep the logic maintained to the syncing thread (and manage the request_ids), when a block needs to be searched for (i.e
 //! if an attestation references an unknown block) this manager can search for the block and
 //! subsequently search for parents if needed.
+//!
+//! Blocks searched for while handling the messages already waiting for the manager are requested
+//! together, with a single `BlocksByRoot` request per peer of up to `MAX_REQUEST_BLOCKS` roots.
+//! A block which is not returned is requested again from another synced peer, up to
+//! `SINGLE_BLOCK_LOOKUP_ATTEMPTS` times.
 
 use super::block_processor::{spawn_block_processor, BatchProcessResult, ProcessId};
 use super::network_context::SyncNetworkContext;
@@ -40,15 +45,17 @@ use super::range_sync::{BatchId, ChainId, RangeSync};
 use super::RequestId;
 use crate::service::NetworkMessage;
 use beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
-use eth2_libp2p::rpc::BlocksByRootRequest;
-use eth2_libp2p::types::NetworkGlobals;
+use eth2_libp2p::rpc::{BlocksByRootRequest, MAX_REQUEST_BLOCKS};
+use eth2_libp2p::types::{NetworkGlobals, SyncMode, SyncStatus};
 use eth2_libp2p::PeerId;
 use fnv::FnvHashMap;
 use slog::{crit, debug, error, info, trace, warn, Logger};
 use smallvec::SmallVec;
 use std::boxed::Box;
+use std::collections::HashSet;
 use std::ops::Sub;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use types::{EthSpec, Hash256, SignedBeaconBlock, Slot};
 
@@ -62,6 +69,13 @@ const PARENT_FAIL_TOLERANCE: usize = 5;
 /// canonical chain to its head once the peer connects. A chain should not appear where it's depth
 /// is further back than the most recent head slot.
 const PARENT_DEPTH_TOLERANCE: usize = SLOT_IMPORT_TOLERANCE * 2;
+/// How many times we request a block searched for by its root before we give up.
+const SINGLE_BLOCK_LOOKUP_ATTEMPTS: usize = 3;
+/// The minimum time between publishing snapshots of the sync status to the network globals.
+const STATUS_PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+/// The maximum number of messages handled before the queued block lookups are sent, so that a
+/// flood of messages cannot delay them indefinitely.
+const MAX_MESSAGES_PER_POLL: usize = 64;
 
 #[derive(Debug)]
 /// A message than can be sent to the sync manager thread.
@@ -148,10 +162,10 @@ pub struct SyncManager<T: BeaconChainTypes> {
     /// A collection of parent block lookups.
     parent_queue: SmallVec<[ParentRequests<T::EthSpec>; 3]>,
 
-    /// A collection of block hashes being searched for and a flag indicating if a result has been
-    /// received or not.
-    ///
-    /// The flag allows us to determine if the peer returned data or sent us nothing.
+    /// Blocks being searched for by their root which are yet to be requested.
+    queued_block_lookups: Vec<BlockLookup>,
+
+    /// The `BlocksByRoot` requests for blocks being searched for by their root.
     single_block_lookups: FnvHashMap<RequestId, SingleBlockRequest>,
 
     /// The logger for the import manager.
@@ -159,23 +173,75 @@ pub struct SyncManager<T: BeaconChainTypes> {
 
     /// The sending part of input_channel
     sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+
+    /// The time at which the sync status was last published to the network globals.
+    status_published: Option<Instant>,
+
+    /// The time and head slot at which the current range sync started, used to estimate the time
+    /// remaining until the head is reached.
+    range_sync_started: Option<(Instant, Slot)>,
+}
+
+/// A block being searched for by its root.
+struct BlockLookup {
+    /// The hash of the block.
+    hash: Hash256,
+    /// The peer the block is requested from.
+    peer_id: PeerId,
+    /// The number of requests for the block which have failed.
+    failed_attempts: usize,
 }
 
-/// Object representing a single block lookup request.
+/// Object representing a `BlocksByRoot` request for the blocks of one or more block lookups.
 struct SingleBlockRequest {
-    /// The hash of the requested block.
-    pub hash: Hash256,
+    /// The lookups whose blocks have not yet been received.
+    pub lookups: Vec<BlockLookup>,
+    /// The hashes of the blocks which have been received.
+    pub received: Vec<Hash256>,
     /// Whether a block was received from this request, or the peer returned an empty response.
     pub block_returned: bool,
 }
 
+/// How a block received for a `SingleBlockRequest` relates to the blocks requested.
+#[derive(Debug, PartialEq)]
+enum ReceivedBlock {
+    /// The block was requested and is the first copy received.
+    Requested,
+    /// The block has already been received from this request.
+    Duplicate,
+    /// The block was not requested.
+    Unrequested,
+}
+
 impl SingleBlockRequest {
-    pub fn new(hash: Hash256) -> Self {
+    pub fn new(lookups: Vec<BlockLookup>) -> Self {
         Self {
-            hash,
+            lookups,
+            received: Vec::new(),
             block_returned: false,
         }
     }
+
+    /// Records the receipt of the block with `block_hash`, ending its lookup if it was requested.
+    pub fn receive_block(&mut self, block_hash: Hash256) -> ReceivedBlock {
+        self.block_returned = true;
+        if self.received.contains(&block_hash) {
+            return ReceivedBlock::Duplicate;
+        }
+
+        match self
+            .lookups
+            .iter()
+            .position(|lookup| lookup.hash == block_hash)
+        {
+            Some(pos) => {
+                self.lookups.swap_remove(pos);
+                self.received.push(block_hash);
+                ReceivedBlock::Requested
+            }
+            None => ReceivedBlock::Unrequested,
+        }
+    }
 }
 
 /// Spawns a new `SyncManager` thread which has a weak reference to underlying beacon
@@ -204,9 +270,12 @@ pub fn spawn<T: BeaconChainTypes>(
         network_globals,
         input_channel: sync_recv,
         parent_queue: SmallVec::new(),
+        queued_block_lookups: Vec::new(),
         single_block_lookups: FnvHashMap::default(),
         log: log.clone(),
         sync_send: sync_send.clone(),
+        status_published: None,
+        range_sync_started: None,
     };
 
     // spawn the sync manager thread
@@ -290,11 +359,12 @@ impl<T: BeaconChainTypes> SyncManager<T> {
     }
 
     /// The response to a `BlocksByRoot` request.
-    /// The current implementation takes one block at a time. As blocks are streamed, any
-    /// subsequent blocks will simply be ignored.
     /// There are two reasons we could have received a BlocksByRoot response
-    /// - We requested a single hash and have received a response for the single_block_lookup
-    /// - We are looking up parent blocks in parent lookup search
+    /// - We requested the hashes of block lookups and have received a response for the
+    /// single_block_lookup
+    /// - We are looking up parent blocks in parent lookup search. The current implementation
+    /// takes one block at a time. As blocks are streamed, any subsequent blocks will simply be
+    /// ignored.
     fn blocks_by_root_response(
         &mut self,
         peer_id: PeerId,
@@ -305,15 +375,24 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             Some(block) => {
                 // data was returned, not just a stream termination
 
-                // check if this is a single block lookup - i.e we were searching for a specific hash
-                let mut single_block_hash = None;
+                // check if this is a single block lookup - i.e we were searching for specific hashes
                 if let Some(block_request) = self.single_block_lookups.get_mut(&request_id) {
                     // update the state of the lookup indicating a block was received from the peer
-                    block_request.block_returned = true;
-                    single_block_hash = Some(block_request.hash.clone());
-                }
-                if let Some(block_hash) = single_block_hash {
-                    self.single_block_lookup_response(peer_id, block, block_hash);
+                    let block_hash = block.canonical_root();
+                    match block_request.receive_block(block_hash) {
+                        ReceivedBlock::Requested => {
+                            self.single_block_lookup_response(peer_id, block);
+                        }
+                        ReceivedBlock::Duplicate => {
+                            // the block has already been processed, ignore the copy
+                            trace!(self.log, "Peer sent a block for single block lookup twice"; "peer_id" => format!("{}", peer_id), "block_hash" => format!("{}", block_hash));
+                        }
+                        ReceivedBlock::Unrequested => {
+                            // the peer that sent this, sent us a block we did not request
+                            warn!(self.log, "Peer sent incorrect block for single block lookup"; "peer_id" => format!("{}", peer_id));
+                            self.network.downvote_peer(peer_id);
+                        }
+                    }
                     return;
                 }
 
@@ -342,10 +421,13 @@ impl<T: BeaconChainTypes> SyncManager<T> {
 
                 // stream termination for a single block lookup, remove the key
                 if let Some(single_block_request) = self.single_block_lookups.remove(&request_id) {
-                    // the peer didn't respond with a block that it referenced
-                    if !single_block_request.block_returned {
-                        warn!(self.log, "Peer didn't respond with a block it referenced"; "referenced_block_hash" => format!("{}", single_block_request.hash), "peer_id" =>  format!("{}", peer_id));
-                        self.network.downvote_peer(peer_id);
+                    // the peer didn't respond with the blocks that it referenced
+                    if !single_block_request.lookups.is_empty() {
+                        if !single_block_request.block_returned {
+                            warn!(self.log, "Peer didn't respond with a block it referenced"; "referenced_block_hashes" => format!("{:?}", single_block_request.lookups.iter().map(|lookup| lookup.hash).collect::<Vec<_>>()), "peer_id" =>  format!("{}", peer_id));
+                            self.network.downvote_peer(peer_id.clone());
+                        }
+                        self.retry_block_lookups(&peer_id, single_block_request.lookups);
                     }
                     return;
                 }
@@ -373,23 +455,14 @@ impl<T: BeaconChainTypes> SyncManager<T> {
         }
     }
 
-    /// Processes the response obtained from a single block lookup search. If the block is
-    /// processed or errors, the search ends. If the blocks parent is unknown, a block parent
-    /// lookup search is started.
+    /// Processes a block obtained from a single block lookup search, whose hash has been
+    /// verified. If the block is processed or errors, the search ends. If the blocks parent is
+    /// unknown, a block parent lookup search is started.
     fn single_block_lookup_response(
         &mut self,
         peer_id: PeerId,
         block: SignedBeaconBlock<T::EthSpec>,
-        expected_block_hash: Hash256,
     ) {
-        // verify the hash is correct and try and process the block
-        if expected_block_hash != block.canonical_root() {
-            // the peer that sent this, sent us the wrong block
-            warn!(self.log, "Peer sent incorrect block for single block lookup"; "peer_id" => format!("{}", peer_id));
-            self.network.downvote_peer(peer_id);
-            return;
-        }
-
         // we have the correct block, try and process it
         match BlockProcessingOutcome::shim(self.chain.process_block(block.clone())) {
             Ok(outcome) => {
@@ -478,8 +551,9 @@ impl<T: BeaconChainTypes> SyncManager<T> {
         self.request_parent(parent_request)
     }
 
-    /// A request to search for a block hash has been received. This function begins a BlocksByRoot
-    /// request to find the requested block.
+    /// A request to search for a block hash has been received. This function queues the block to
+    /// be requested from the peer by `send_block_lookups`, along with any other blocks being
+    /// searched for.
     fn search_for_block(&mut self, peer_id: PeerId, block_hash: Hash256) {
         // If we are not synced, ignore this block
         if !self.network_globals.sync_state.read().is_synced() {
@@ -488,29 +562,72 @@ impl<T: BeaconChainTypes> SyncManager<T> {
 
         // Do not re-request a block that is already being requested
         if self
-            .single_block_lookups
-            .values()
-            .find(|single_block_request| single_block_request.hash == block_hash)
-            .is_some()
+            .queued_block_lookups
+            .iter()
+            .chain(
+                self.single_block_lookups
+                    .values()
+                    .flat_map(|single_block_request| single_block_request.lookups.iter()),
+            )
+            .any(|lookup| lookup.hash == block_hash)
         {
             return;
         }
 
-        let request = BlocksByRootRequest {
-            block_roots: vec![block_hash],
-        };
+        self.queued_block_lookups.push(BlockLookup {
+            hash: block_hash,
+            peer_id,
+            failed_attempts: 0,
+        });
+    }
 
-        if let Ok(request_id) = self.network.blocks_by_root_request(peer_id, request) {
-            self.single_block_lookups
-                .insert(request_id, SingleBlockRequest::new(block_hash));
+    /// Queues the `lookups` whose blocks were not returned by `peer_id` to be requested from
+    /// another synced peer, unless they have failed too many times.
+    fn retry_block_lookups(&mut self, peer_id: &PeerId, lookups: Vec<BlockLookup>) {
+        let retry_peer = self
+            .network_globals
+            .peers
+            .read()
+            .synced_peers()
+            .find(|synced_peer| *synced_peer != peer_id)
+            .cloned();
+
+        for mut lookup in lookups {
+            lookup.failed_attempts += 1;
+            match &retry_peer {
+                Some(retry_peer) if lookup.failed_attempts < SINGLE_BLOCK_LOOKUP_ATTEMPTS => {
+                    lookup.peer_id = retry_peer.clone();
+                    self.queued_block_lookups.push(lookup);
+                }
+                _ => {
+                    debug!(self.log, "Single block lookup failed"; "block_hash" => format!("{}", lookup.hash), "attempts" => lookup.failed_attempts);
+                }
+            }
+        }
+    }
+
+    /// Sends the queued block lookups, with a single `BlocksByRoot` request per peer for up to
+    /// `MAX_REQUEST_BLOCKS` blocks.
+    fn send_block_lookups(&mut self) {
+        let lookups = std::mem::replace(&mut self.queued_block_lookups, Vec::new());
+        for (peer_id, lookups) in group_block_lookups(lookups) {
+            let request = BlocksByRootRequest {
+                block_roots: lookups.iter().map(|lookup| lookup.hash).collect(),
+            };
+
+            if let Ok(request_id) = self.network.blocks_by_root_request(peer_id, request) {
+                self.single_block_lookups
+                    .insert(request_id, SingleBlockRequest::new(lookups));
+            }
         }
     }
 
     fn inject_error(&mut self, peer_id: PeerId, request_id: RequestId) {
         trace!(self.log, "Sync manager received a failed RPC");
-        // remove any single block lookups
-        if self.single_block_lookups.remove(&request_id).is_some() {
+        // retry any single block lookups
+        if let Some(single_block_request) = self.single_block_lookups.remove(&request_id) {
             // this was a single block request lookup, look no further
+            self.retry_block_lookups(&peer_id, single_block_request.lookups);
             return;
         }
 
@@ -586,6 +703,68 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             info!(self.log, "Sync state updated"; "old_state" => format!("{}", old_state), "new_state" => format!("{}",new_state));
         }
     }
+
+    /// Publishes a snapshot of the internals of the sync manager to the network globals, at most
+    /// once per `STATUS_PUBLISH_INTERVAL`.
+    fn publish_status(&mut self) {
+        let now = Instant::now();
+        if self
+            .status_published
+            .map_or(false, |published| now - published < STATUS_PUBLISH_INTERVAL)
+        {
+            return;
+        }
+        self.status_published = Some(now);
+
+        let range_chains = self.range_sync.status();
+        let head_slot = self.chain.best_slot().ok();
+
+        let mode = if self.network_globals.is_syncing() {
+            SyncMode::Range
+        } else if !self.parent_queue.is_empty() {
+            SyncMode::ParentLookup
+        } else {
+            SyncMode::Idle
+        };
+
+        // Estimate the time to reach the target from the import rate since range sync started.
+        let estimated_seconds_to_head = if mode == SyncMode::Range {
+            let (started, start_slot) = *self
+                .range_sync_started
+                .get_or_insert((now, head_slot.unwrap_or_else(|| Slot::new(0))));
+            let target_slot = range_chains
+                .iter()
+                .filter(|chain| chain.syncing)
+                .map(|chain| chain.target_head_slot)
+                .max();
+
+            match (head_slot, target_slot) {
+                (Some(head_slot), Some(target_slot)) if head_slot > start_slot => {
+                    let imported = (head_slot - start_slot).as_u64();
+                    let remaining = target_slot.saturating_sub(head_slot).as_u64();
+                    Some((now - started).as_secs() * remaining / imported)
+                }
+                _ => None,
+            }
+        } else {
+            self.range_sync_started = None;
+            None
+        };
+
+        *self.network_globals.sync_status.write() = SyncStatus {
+            mode,
+            range_chains,
+            parent_lookups: self.parent_queue.len(),
+            single_block_lookups: self.queued_block_lookups.len()
+                + self
+                    .single_block_lookups
+                    .values()
+                    .map(|single_block_request| single_block_request.lookups.len())
+                    .sum::<usize>(),
+            estimated_seconds_to_head,
+        };
+    }
+
     /* Processing State Functions */
     // These functions are called in the main poll function to transition the state of the sync
     // manager
@@ -729,65 +908,181 @@ impl<T: BeaconChainTypes> SyncManager<T> {
         }
     }
 
+    /// Handles a message sent to the sync manager.
+    fn handle_message(&mut self, sync_message: SyncMessage<T::EthSpec>) {
+        match sync_message {
+            SyncMessage::AddPeer(peer_id, info) => {
+                self.add_peer(peer_id, info);
+            }
+            SyncMessage::BlocksByRangeResponse {
+                peer_id,
+                request_id,
+                beacon_block,
+            } => {
+                self.range_sync.blocks_by_range_response(
+                    &mut self.network,
+                    peer_id,
+                    request_id,
+                    beacon_block.map(|b| *b),
+                );
+            }
+            SyncMessage::BlocksByRootResponse {
+                peer_id,
+                request_id,
+                beacon_block,
+            } => {
+                self.blocks_by_root_response(peer_id, request_id, beacon_block.map(|b| *b));
+            }
+            SyncMessage::UnknownBlock(peer_id, block) => {
+                self.add_unknown_block(peer_id, *block);
+            }
+            SyncMessage::UnknownBlockHash(peer_id, block_hash) => {
+                self.search_for_block(peer_id, block_hash);
+            }
+            SyncMessage::Disconnect(peer_id) => {
+                self.peer_disconnect(&peer_id);
+            }
+            SyncMessage::RPCError(peer_id, request_id) => {
+                self.inject_error(peer_id, request_id);
+            }
+            SyncMessage::BatchProcessed {
+                chain_id,
+                batch_id,
+                downloaded_blocks,
+                result,
+            } => {
+                self.range_sync.handle_block_process_result(
+                    &mut self.network,
+                    chain_id,
+                    batch_id,
+                    downloaded_blocks,
+                    result,
+                );
+            }
+            SyncMessage::ParentLookupFailed(peer_id) => {
+                self.network.downvote_peer(peer_id);
+            }
+        }
+    }
+
     /// The main driving future for the sync manager.
     async fn main(&mut self) {
         // process any inbound messages
         loop {
             if let Some(sync_message) = self.input_channel.recv().await {
-                match sync_message {
-                    SyncMessage::AddPeer(peer_id, info) => {
-                        self.add_peer(peer_id, info);
-                    }
-                    SyncMessage::BlocksByRangeResponse {
-                        peer_id,
-                        request_id,
-                        beacon_block,
-                    } => {
-                        self.range_sync.blocks_by_range_response(
-                            &mut self.network,
-                            peer_id,
-                            request_id,
-                            beacon_block.map(|b| *b),
-                        );
-                    }
-                    SyncMessage::BlocksByRootResponse {
-                        peer_id,
-                        request_id,
-                        beacon_block,
-                    } => {
-                        self.blocks_by_root_response(peer_id, request_id, beacon_block.map(|b| *b));
-                    }
-                    SyncMessage::UnknownBlock(peer_id, block) => {
-                        self.add_unknown_block(peer_id, *block);
-                    }
-                    SyncMessage::UnknownBlockHash(peer_id, block_hash) => {
-                        self.search_for_block(peer_id, block_hash);
-                    }
-                    SyncMessage::Disconnect(peer_id) => {
-                        self.peer_disconnect(&peer_id);
-                    }
-                    SyncMessage::RPCError(peer_id, request_id) => {
-                        self.inject_error(peer_id, request_id);
-                    }
-                    SyncMessage::BatchProcessed {
-                        chain_id,
-                        batch_id,
-                        downloaded_blocks,
-                        result,
-                    } => {
-                        self.range_sync.handle_block_process_result(
-                            &mut self.network,
-                            chain_id,
-                            batch_id,
-                            downloaded_blocks,
-                            result,
-                        );
-                    }
-                    SyncMessage::ParentLookupFailed(peer_id) => {
-                        self.network.downvote_peer(peer_id);
-                    }
+                self.handle_message(sync_message);
+                // handle some of the messages which are already waiting, so that the blocks they
+                // search for are requested together
+                for sync_message in
+                    try_recv_waiting(&mut self.input_channel, MAX_MESSAGES_PER_POLL - 1)
+                {
+                    self.handle_message(sync_message);
                 }
+                self.send_block_lookups();
+
+                self.publish_status();
             }
         }
     }
 }
+
+/// Groups the `lookups` into `BlocksByRoot` requests of up to `MAX_REQUEST_BLOCKS` blocks, with as
+/// few requests to each peer as possible. Repeated lookups of the same block are dropped.
+fn group_block_lookups(lookups: Vec<BlockLookup>) -> Vec<(PeerId, Vec<BlockLookup>)> {
+    let mut hashes = HashSet::new();
+    let mut lookups_by_peer: FnvHashMap<PeerId, Vec<BlockLookup>> = FnvHashMap::default();
+    for lookup in lookups {
+        if hashes.insert(lookup.hash) {
+            lookups_by_peer
+                .entry(lookup.peer_id.clone())
+                .or_default()
+                .push(lookup);
+        }
+    }
+
+    let mut requests = vec![];
+    for (peer_id, mut lookups) in lookups_by_peer {
+        while !lookups.is_empty() {
+            let remaining = lookups.split_off(std::cmp::min(lookups.len(), MAX_REQUEST_BLOCKS));
+            requests.push((peer_id.clone(), lookups));
+            lookups = remaining;
+        }
+    }
+    requests
+}
+
+/// Returns up to `limit` of the messages already waiting in `receiver`, without waiting for more.
+fn try_recv_waiting<M>(receiver: &mut mpsc::UnboundedReceiver<M>, limit: usize) -> Vec<M> {
+    let mut messages = vec![];
+    while messages.len() < limit {
+        match receiver.try_recv() {
+            Ok(message) => messages.push(message),
+            Err(_) => break,
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(hash: u64, peer_id: &PeerId) -> BlockLookup {
+        BlockLookup {
+            hash: Hash256::from_low_u64_be(hash),
+            peer_id: peer_id.clone(),
+            failed_attempts: 0,
+        }
+    }
+
+    #[test]
+    fn duplicate_blocks_are_ignored() {
+        let peer_id = PeerId::random();
+        let mut request = SingleBlockRequest::new(vec![lookup(1, &peer_id), lookup(2, &peer_id)]);
+
+        let hash = Hash256::from_low_u64_be(1);
+        assert_eq!(request.receive_block(hash), ReceivedBlock::Requested);
+        assert_eq!(request.receive_block(hash), ReceivedBlock::Duplicate);
+        assert_eq!(
+            request.receive_block(Hash256::from_low_u64_be(3)),
+            ReceivedBlock::Unrequested
+        );
+        assert!(request.block_returned);
+        assert_eq!(request.lookups.len(), 1);
+    }
+
+    #[test]
+    fn block_lookups_are_grouped_by_peer() {
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+        let mut lookups = (0..MAX_REQUEST_BLOCKS as u64 + 1)
+            .map(|hash| lookup(hash, &peer_a))
+            .collect::<Vec<_>>();
+        lookups.push(lookup(0, &peer_b));
+        lookups.push(lookup(MAX_REQUEST_BLOCKS as u64 + 2, &peer_b));
+
+        let mut requests = group_block_lookups(lookups)
+            .into_iter()
+            .map(|(peer_id, lookups)| (peer_id == peer_a, lookups.len()))
+            .collect::<Vec<_>>();
+        requests.sort();
+
+        // the repeated lookup of block 0 is dropped
+        assert_eq!(
+            requests,
+            vec![(false, 1), (true, 1), (true, MAX_REQUEST_BLOCKS)]
+        );
+    }
+
+    #[test]
+    fn waiting_messages_are_limited() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        for i in 0..5 {
+            sender.send(i).unwrap();
+        }
+
+        assert_eq!(try_recv_waiting(&mut receiver, 3), vec![0, 1, 2]);
+        assert_eq!(try_recv_waiting(&mut receiver, 3), vec![3, 4]);
+        assert_eq!(try_recv_waiting(&mut receiver, 3), Vec::<i32>::new());
+    }
+}
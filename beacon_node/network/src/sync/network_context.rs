@@ -50,6 +50,15 @@ impl<T: EthSpec> SyncNetworkContext<T> {
             .unwrap_or_default()
     }
 
+    /// Returns true if the peer has been slow to serve our `BlocksByRange` requests.
+    pub fn is_slow_peer(&self, peer_id: &PeerId) -> bool {
+        self.network_globals
+            .peers
+            .read()
+            .peer_info(peer_id)
+            .map_or(false, |info| info.range_performance.is_slow())
+    }
+
     pub fn status_peer<U: BeaconChainTypes>(
         &mut self,
         chain: Arc<BeaconChain<U>>,
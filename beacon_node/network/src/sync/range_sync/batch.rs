@@ -1,5 +1,6 @@
 use super::chain::EPOCHS_PER_BATCH;
 use eth2_libp2p::rpc::methods::*;
+use eth2_libp2p::types::{BatchState, BatchStatus};
 use eth2_libp2p::PeerId;
 use fnv::FnvHashMap;
 use ssz::Encode;
@@ -83,6 +84,19 @@ impl<T: EthSpec> Batch<T> {
         }
     }
 
+    /// Returns the status of the batch, for monitoring.
+    pub fn status(&self, state: BatchState) -> BatchStatus {
+        BatchStatus {
+            id: *self.id,
+            start_slot: self.start_slot,
+            end_slot: self.end_slot,
+            peer_id: self.current_peer.to_string(),
+            state,
+            retries: self.retries,
+            reprocess_retries: self.reprocess_retries,
+        }
+    }
+
     /// This gets a hash that represents the blocks currently downloaded. This allows comparing a
     /// previously downloaded batch of blocks with a new downloaded batch of blocks.
     pub fn hash(&self) -> u64 {
@@ -154,6 +168,11 @@ impl<T: EthSpec> PendingBatches<T> {
         self.batches.len()
     }
 
+    /// Returns an iterator over the pending batches.
+    pub fn iter(&self) -> impl Iterator<Item = &Batch<T>> {
+        self.batches.values()
+    }
+
     /// Adds a block to the batches if the request id exists. Returns None if there is no batch
     /// matching the request id.
     pub fn add_block(&mut self, request_id: usize, block: SignedBeaconBlock<T>) -> Option<()> {
@@ -3,6 +3,7 @@ use crate::sync::block_processor::{spawn_block_processor, BatchProcessResult, Pr
 use crate::sync::network_context::SyncNetworkContext;
 use crate::sync::{RequestId, SyncMessage};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::types::{BatchState, ChainStatus, ChainType};
 use eth2_libp2p::PeerId;
 use rand::prelude::*;
 use slog::{crit, debug, warn};
@@ -136,6 +137,42 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
         }
     }
 
+    /// Returns the status of the chain and its batches, for monitoring.
+    pub fn status(&self, chain_type: ChainType) -> ChainStatus {
+        let mut batches = self
+            .pending_batches
+            .iter()
+            .map(|batch| batch.status(BatchState::Downloading))
+            .chain(
+                self.completed_batches
+                    .iter()
+                    .map(|batch| batch.status(BatchState::AwaitingProcessing)),
+            )
+            .chain(
+                self.current_processing_batch
+                    .iter()
+                    .map(|batch| batch.status(BatchState::Processing)),
+            )
+            .chain(
+                self.processed_batches
+                    .iter()
+                    .map(|batch| batch.status(BatchState::AwaitingValidation)),
+            )
+            .collect::<Vec<_>>();
+        batches.sort_by_key(|batch| batch.id);
+
+        ChainStatus {
+            id: self.id,
+            chain_type,
+            syncing: self.state == ChainSyncingState::Syncing,
+            start_slot: self.start_epoch.start_slot(T::EthSpec::slots_per_epoch()),
+            target_head_slot: self.target_head_slot,
+            target_head_root: self.target_head_root,
+            peers: self.peer_pool.len(),
+            batches,
+        }
+    }
+
     /// Returns the latest slot number that has been processed.
     fn current_processed_slot(&self) -> Slot {
         self.start_epoch
@@ -649,12 +686,14 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
             // chain is unrecoverable, remove it
             ProcessingResult::RemoveChain
         } else {
-            // try to re-process the request using a different peer, if possible
+            // try to re-process the request using a different peer, if possible, preferring
+            // peers which are not slow
             let current_peer = &batch.current_peer;
-            let new_peer = self
-                .peer_pool
-                .iter()
-                .find(|peer| *peer != current_peer)
+            let other_peers = self.peer_pool.iter().filter(|peer| *peer != current_peer);
+            let new_peer = other_peers
+                .clone()
+                .find(|peer| !network.is_slow_peer(peer))
+                .or_else(|| other_peers.clone().next())
                 .unwrap_or_else(|| current_peer);
 
             batch.current_peer = new_peer.clone();
@@ -681,7 +720,7 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
     /// to send a request and there are batches to request, false otherwise.
     fn send_range_request(&mut self, network: &mut SyncNetworkContext<T::EthSpec>) -> bool {
         // find the next pending batch and request it from the peer
-        if let Some(peer_id) = self.get_next_peer() {
+        if let Some(peer_id) = self.get_next_peer(network) {
             if let Some(batch) = self.get_next_batch(peer_id) {
                 debug!(self.log, "Requesting batch";
                     "chain_id" => self.id,
@@ -698,14 +737,18 @@ impl<T: BeaconChainTypes> SyncingChain<T> {
     }
 
     /// Returns a peer if there exists a peer which does not currently have a pending request.
+    /// Peers which have been slow to serve previous requests are only chosen if no other peer is
+    /// idle.
     ///
     /// This is used to create the next request.
-    fn get_next_peer(&self) -> Option<PeerId> {
+    fn get_next_peer(&self, network: &SyncNetworkContext<T::EthSpec>) -> Option<PeerId> {
         // TODO: Optimize this by combining with above two functions.
         // randomize the peers for load balancing
         let mut rng = rand::thread_rng();
         let mut peers = self.peer_pool.iter().collect::<Vec<_>>();
         peers.shuffle(&mut rng);
+        // the sort is stable, so the order within fast and slow peers remains random
+        peers.sort_by_key(|peer| network.is_slow_peer(peer));
         for peer in peers {
             if self.pending_batches.peer_is_idle(peer) {
                 return Some(peer.clone());
@@ -8,7 +8,10 @@ use crate::sync::manager::SyncMessage;
 use crate::sync::network_context::SyncNetworkContext;
 use crate::sync::PeerSyncInfo;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
-use eth2_libp2p::{types::SyncState, NetworkGlobals, PeerId};
+use eth2_libp2p::{
+    types::{ChainStatus, ChainType, SyncState},
+    NetworkGlobals, PeerId,
+};
 use slog::{debug, error, info};
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -105,6 +108,19 @@ impl<T: BeaconChainTypes> ChainCollection<T> {
         }
     }
 
+    /// Returns the status of all finalized and head chains, for monitoring.
+    pub fn status(&self) -> Vec<ChainStatus> {
+        self.finalized_chains
+            .iter()
+            .map(|chain| chain.status(ChainType::Finalized))
+            .chain(
+                self.head_chains
+                    .iter()
+                    .map(|chain| chain.status(ChainType::Head)),
+            )
+            .collect()
+    }
+
     pub fn state(&self) -> &RangeSyncState {
         &self.state
     }
@@ -49,7 +49,7 @@ use crate::sync::network_context::SyncNetworkContext;
 use crate::sync::PeerSyncInfo;
 use crate::sync::RequestId;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
-use eth2_libp2p::{NetworkGlobals, PeerId};
+use eth2_libp2p::{types::ChainStatus, NetworkGlobals, PeerId};
 use slog::{debug, error, trace};
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -104,6 +104,11 @@ impl<T: BeaconChainTypes> RangeSync<T> {
         self.chains.fully_synced_peer_found()
     }
 
+    /// Returns the status of all chains being synced, for monitoring.
+    pub fn status(&self) -> Vec<ChainStatus> {
+        self.chains.status()
+    }
+
     /// A useful peer has been added. The SyncManager has identified this peer as needing either
     /// a finalized or head chain sync. This processes the peer and starts/resumes any chain that
     /// may need to be synced as a result. A new peer, may increase the peer pool of a finalized
@@ -7,7 +7,7 @@ pub use persistence::PersistedOperationPool;
 
 use attestation::AttMaxCover;
 use attestation_id::AttestationId;
-use max_cover::maximum_cover;
+use max_cover::{maximum_cover, MaxCover};
 use parking_lot::RwLock;
 use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, ExitValidationError,
@@ -39,6 +39,19 @@ pub struct OperationPool<T: EthSpec + Default> {
     _phantom: PhantomData<T>,
 }
 
+/// A summary of how well the attestations selected for a block cover those available in the pool.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AttestationPacking {
+    /// The number of attestations in the pool which were valid for inclusion.
+    pub available: usize,
+    /// The number of attestations selected for inclusion.
+    pub included: usize,
+    /// The proposer reward for including all of the available attestations, in Gwei.
+    pub available_reward: u64,
+    /// The proposer reward for including the selected attestations, in Gwei.
+    pub included_reward: u64,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum OpPoolError {
     GetAttestationsTotalBalanceError(BeaconStateError),
@@ -109,6 +122,18 @@ impl<T: EthSpec> OperationPool<T> {
         validity_filter: impl FnMut(&&Attestation<T>) -> bool,
         spec: &ChainSpec,
     ) -> Result<Vec<Attestation<T>>, OpPoolError> {
+        self.get_attestations_with_packing(state, validity_filter, spec)
+            .map(|(attestations, _)| attestations)
+    }
+
+    /// Get a list of attestations for inclusion in a block, along with a summary of how much of
+    /// the available proposer reward they capture.
+    pub fn get_attestations_with_packing(
+        &self,
+        state: &BeaconState<T>,
+        validity_filter: impl FnMut(&&Attestation<T>) -> bool,
+        spec: &ChainSpec,
+    ) -> Result<(Vec<Attestation<T>>, AttestationPacking), OpPoolError> {
         // Attestations for the current fork, which may be from the current or previous epoch.
         let prev_epoch = state.previous_epoch();
         let current_epoch = state.current_epoch();
@@ -149,12 +174,30 @@ impl<T: EthSpec> OperationPool<T> {
                 .is_ok()
             })
             .filter(validity_filter)
-            .flat_map(|att| AttMaxCover::new(att, state, total_active_balance, spec));
+            .flat_map(|att| AttMaxCover::new(att, state, total_active_balance, spec))
+            .collect::<Vec<_>>();
+
+        let available = valid_attestations.len();
+        let available_reward = total_reward(valid_attestations.iter());
 
-        Ok(maximum_cover(
-            valid_attestations,
-            T::MaxAttestations::to_usize(),
-        ))
+        let attestations = maximum_cover(valid_attestations, T::MaxAttestations::to_usize());
+
+        let included_reward = total_reward(
+            attestations
+                .iter()
+                .flat_map(|att| AttMaxCover::new(att, state, total_active_balance, spec))
+                .collect::<Vec<_>>()
+                .iter(),
+        );
+
+        let packing = AttestationPacking {
+            available,
+            included: attestations.len(),
+            available_reward,
+            included_reward,
+        };
+
+        Ok((attestations, packing))
     }
 
     /// Remove attestations which are too old to be included in a block.
@@ -371,6 +414,19 @@ impl<T: EthSpec> OperationPool<T> {
     }
 }
 
+/// The proposer reward for including all of the given attestations, counting each fresh validator
+/// only once.
+fn total_reward<'a, 'b: 'a, T: EthSpec>(
+    attestations: impl Iterator<Item = &'a AttMaxCover<'b, T>>,
+) -> u64 {
+    attestations
+        .flat_map(|att| att.covering_set())
+        .map(|(validator_index, reward)| (*validator_index, *reward))
+        .collect::<HashMap<_, _>>()
+        .values()
+        .sum()
+}
+
 /// Filter up to a maximum number of operations out of an iterator.
 fn filter_limit_operations<'a, T: 'a, I, F>(operations: I, filter: F, limit: usize) -> Vec<T>
 where
@@ -861,6 +917,8 @@ mod release_tests {
         let mut seen_indices = BTreeSet::new();
         // Used for asserting that rewards are in decreasing order.
         let mut prev_reward = u64::max_value();
+        // The total reward of `best_attestations`.
+        let mut total_reward = 0;
 
         for att in &best_attestations {
             let fresh_validators_bitlist = earliest_attestation_validators(att, state);
@@ -891,8 +949,18 @@ mod release_tests {
             assert!(prev_reward >= rewards);
 
             prev_reward = rewards;
+            total_reward += rewards;
             seen_indices.extend(fresh_indices);
         }
+
+        let (packed_attestations, packing) = op_pool
+            .get_attestations_with_packing(state, |_| true, spec)
+            .expect("should have valid best attestations");
+        assert_eq!(packed_attestations, best_attestations);
+        assert_eq!(packing.available, op_pool.num_attestations());
+        assert_eq!(packing.included, max_attestations);
+        assert_eq!(packing.included_reward, total_reward);
+        assert!(packing.available_reward >= packing.included_reward);
     }
 
     /// Insert two slashings for the same proposer and ensure only one is returned.
@@ -0,0 +1,8 @@
+//! Generates the gRPC server and client from the protobuf definitions in `proto/`.
+
+fn main() {
+    println!("cargo:rerun-if-changed=proto/validator.proto");
+
+    tonic_build::compile_protos("proto/validator.proto")
+        .expect("should compile gRPC protobuf definitions");
+}
@@ -2,24 +2,42 @@ use crate::helpers::*;
 use crate::response_builder::ResponseBuilder;
 use crate::validator::get_state_for_epoch;
 use crate::{ApiError, ApiResult, UrlQuery};
-use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use beacon_chain::{events::Reorg, BeaconChain, BeaconChainTypes, StateSkipConfig};
 use bus::BusReader;
+use environment::TaskExecutor;
 use futures::executor::block_on;
 use hyper::body::Bytes;
 use hyper::{Body, Request, Response};
 use rest_types::{
-    BlockResponse, CanonicalHeadResponse, Committee, HeadBeaconBlock, StateResponse,
-    ValidatorRequest, ValidatorResponse,
+    BlockResponse, CanonicalHeadResponse, Committee, HeadBeaconBlock, StateProofResponse,
+    StateResponse, ValidatorQuery, ValidatorQueryResponse, ValidatorRequest, ValidatorResponse,
+    ValidatorStatus, ValidatorStatusResponse,
 };
+use ssz::Encode;
+use std::collections::BTreeSet;
 use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use slog::{error, Logger};
 use types::{
-    AttesterSlashing, BeaconState, EthSpec, Hash256, ProposerSlashing, PublicKeyBytes,
-    RelativeEpoch, SignedBeaconBlockHash, Slot,
+    AttesterSlashing, BeaconState, BeaconStateError, EthSpec, Hash256, ProposerSlashing,
+    PublicKeyBytes, RelativeEpoch, SignedBeaconBlock, SignedBeaconBlockHash, Slot,
 };
 
+/// The size of each chunk of a streamed SSZ response.
+const SSZ_CHUNK_SIZE: usize = 1 << 20;
+
+/// The maximum number of requests for the finalized state which are served at once, as each one
+/// holds a full `BeaconState` in memory.
+const MAX_CONCURRENT_FINALIZED_STATE_REQUESTS: usize = 2;
+
+/// The number of requests for the finalized state in progress.
+static FINALIZED_STATE_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum number of validators returned in a single page of a `ValidatorQuery`.
+pub const MAX_VALIDATOR_QUERY_LIMIT: u64 = 1_024;
+
 /// HTTP handler to return a `BeaconBlock` at a given `root` or `slot`.
 pub fn get_head<T: BeaconChainTypes>(
     req: Request<Body>,
@@ -79,18 +97,20 @@ pub fn get_block<T: BeaconChainTypes>(
     let query_params = ["root", "slot"];
     let (key, value) = UrlQuery::from_request(&req)?.first_of(&query_params)?;
 
-    let block_root = match (key.as_ref(), value) {
+    // Blocks requested by slot are resolved via the canonical chain.
+    let (block_root, resolved_by_slot) = match (key.as_ref(), value) {
         ("slot", value) => {
             let target = parse_slot(&value)?;
 
-            block_root_at_slot(&beacon_chain, target)?.ok_or_else(|| {
+            let root = block_root_at_slot(&beacon_chain, target)?.ok_or_else(|| {
                 ApiError::NotFound(format!(
                     "Unable to find SignedBeaconBlock for slot {:?}",
                     target
                 ))
-            })?
+            })?;
+            (root, true)
         }
-        ("root", value) => parse_root(&value)?,
+        ("root", value) => (parse_root(&value)?, false),
         _ => return Err(ApiError::ServerError("Unexpected query parameter".into())),
     };
 
@@ -101,8 +121,14 @@ pub fn get_block<T: BeaconChainTypes>(
         ))
     })?;
 
+    let slot = block.message.slot;
+    let canonical =
+        resolved_by_slot || block_root_at_slot(&beacon_chain, slot)? == Some(block_root);
+
     let response = BlockResponse {
         root: block_root,
+        canonical,
+        finalized: canonical && is_finalized_slot(&beacon_chain, slot)?,
         beacon_block: block,
     };
 
@@ -138,14 +164,37 @@ fn make_sse_response_chunk(new_head_hash: SignedBeaconBlockHash) -> std::io::Res
     Ok(bytes)
 }
 
+fn make_reorg_sse_response_chunk(reorg: Reorg) -> std::io::Result<Bytes> {
+    let mut buffer = Vec::new();
+    {
+        let mut sse_message = uhttp_sse::SseMessage::new(&mut buffer);
+        serde_json::to_writer(sse_message.data()?, &reorg)?;
+    }
+    let bytes: Bytes = buffer.into();
+    Ok(bytes)
+}
+
 pub fn stream_forks<T: BeaconChainTypes>(
     log: Logger,
-    mut events: BusReader<SignedBeaconBlockHash>,
+    events: BusReader<SignedBeaconBlockHash>,
+) -> ApiResult {
+    stream_events(log, events, make_sse_response_chunk)
+}
+
+/// HTTP handler to stream a JSON object describing each re-org of the canonical chain.
+pub fn stream_reorgs<T: BeaconChainTypes>(log: Logger, events: BusReader<Reorg>) -> ApiResult {
+    stream_events(log, events, make_reorg_sse_response_chunk)
+}
+
+fn stream_events<E: Clone + Sync + Send + 'static>(
+    log: Logger,
+    mut events: BusReader<E>,
+    make_chunk: fn(E) -> std::io::Result<Bytes>,
 ) -> ApiResult {
     let (mut sender, body) = Body::channel();
     std::thread::spawn(move || {
-        while let Ok(new_head_hash) = events.recv() {
-            let chunk = match make_sse_response_chunk(new_head_hash) {
+        while let Ok(event) = events.recv() {
+            let chunk = match make_chunk(event) {
                 Ok(chunk) => chunk,
                 Err(e) => {
                     error!(log, "Failed to make SSE chunk"; "error" => e.to_string());
@@ -289,6 +338,156 @@ pub async fn post_validators<T: BeaconChainTypes>(
         .and_then(|validators| response_builder?.body(&validators))
 }
 
+/// HTTP handler to return a page of the validators matching the `ValidatorQuery` given in the
+/// query string, where `status`, `pubkey` and `index` may each be given more than once.
+pub fn get_validators_query<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let query = UrlQuery::from_request(&req)?;
+
+    let parse_u64 = |key: &str, value: &str| {
+        value
+            .parse::<u64>()
+            .map_err(|e| ApiError::BadRequest(format!("Invalid {} {}: {:?}", key, value, e)))
+    };
+
+    let validator_query = ValidatorQuery {
+        state_root: query
+            .first_of_opt(&["state_root"])
+            .map(|(_key, value)| parse_root(&value))
+            .transpose()?,
+        statuses: query
+            .all_of("status")?
+            .iter()
+            .map(|status| {
+                status
+                    .parse::<ValidatorStatus>()
+                    .map_err(ApiError::BadRequest)
+            })
+            .collect::<Result<_, _>>()?,
+        pubkeys: query
+            .all_of("pubkey")?
+            .iter()
+            .map(|pubkey| parse_pubkey_bytes(pubkey))
+            .collect::<Result<_, _>>()?,
+        indices: query
+            .all_of("index")?
+            .iter()
+            .map(|index| parse_u64("index", index))
+            .collect::<Result<_, _>>()?,
+        offset: query
+            .first_of_opt(&["offset"])
+            .map(|(key, value)| parse_u64(&key, &value))
+            .transpose()?
+            .unwrap_or(0),
+        limit: query
+            .first_of_opt(&["limit"])
+            .map(|(key, value)| parse_u64(&key, &value))
+            .transpose()?,
+    };
+
+    let response = validators_by_query(&beacon_chain, validator_query)?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&response)
+}
+
+/// HTTP handler which accepts a `ValidatorQuery` and returns the page of matching validators.
+///
+/// Unlike `get_validators_query`, this allows for a basically unbounded list of `pubkeys`.
+pub async fn post_validators_query<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let body = req.into_body();
+    let chunks = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+    serde_json::from_slice::<ValidatorQuery>(&chunks)
+        .map_err(|e| {
+            ApiError::BadRequest(format!("Unable to parse JSON into ValidatorQuery: {:?}", e))
+        })
+        .and_then(|validator_query| validators_by_query(&beacon_chain, validator_query))
+        .and_then(|response| response_builder?.body_no_ssz(&response))
+}
+
+/// Returns the page of validators matching `query`, in order of validator index.
+///
+/// When the query lists validators, only those validators are read from the state, with pubkeys
+/// resolved through the pubkey cache.
+fn validators_by_query<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    query: ValidatorQuery,
+) -> Result<ValidatorQueryResponse, ApiError> {
+    let mut state = get_state_from_root_opt(beacon_chain, query.state_root)?;
+    let epoch = state.current_epoch();
+    let validator_count = state.validators.len();
+
+    let indices: Box<dyn Iterator<Item = usize>> =
+        if query.pubkeys.is_empty() && query.indices.is_empty() {
+            Box::new(0..validator_count)
+        } else {
+            state.update_pubkey_cache()?;
+
+            let mut indices = query
+                .indices
+                .iter()
+                .map(|&index| index as usize)
+                .filter(|&index| index < validator_count)
+                .collect::<BTreeSet<_>>();
+            for pubkey in &query.pubkeys {
+                if let Some(index) = state.get_validator_index(pubkey).map_err(|e| {
+                    ApiError::ServerError(format!("Unable to read pubkey cache: {:?}", e))
+                })? {
+                    indices.insert(index);
+                }
+            }
+
+            Box::new(indices.into_iter())
+        };
+
+    let matching = indices.filter_map(|index| {
+        let validator = &state.validators[index];
+        let status = ValidatorStatus::at_epoch(validator, epoch);
+        if query.statuses.is_empty() || query.statuses.contains(&status) {
+            Some((index, status))
+        } else {
+            None
+        }
+    });
+
+    let limit = query
+        .limit
+        .unwrap_or(MAX_VALIDATOR_QUERY_LIMIT)
+        .min(MAX_VALIDATOR_QUERY_LIMIT);
+    let mut total = 0;
+    let mut validators = vec![];
+    for (index, status) in matching {
+        if total >= query.offset && (validators.len() as u64) < limit {
+            validators.push(ValidatorStatusResponse {
+                validator_index: index as u64,
+                status,
+                balance: state.balances[index],
+                validator: state.validators[index].clone(),
+            });
+        }
+        total += 1;
+    }
+
+    let next_offset = query.offset.saturating_add(validators.len() as u64);
+    Ok(ValidatorQueryResponse {
+        total,
+        next_offset: if next_offset < total {
+            Some(next_offset)
+        } else {
+            None
+        },
+        validators,
+    })
+}
+
 /// Returns either the state given by `state_root_opt`, or the canonical head state if it is
 /// `None`.
 fn get_state_from_root_opt<T: BeaconChainTypes>(
@@ -424,29 +623,89 @@ pub fn get_state<T: BeaconChainTypes>(
         }
     };
 
-    let (root, state): (Hash256, BeaconState<T::EthSpec>) = match (key.as_ref(), value) {
-        ("slot", value) => state_at_slot(&beacon_chain, parse_slot(&value)?)?,
+    // States requested by slot are resolved via the canonical chain.
+    let (root, state, resolved_by_slot) = match (key.as_ref(), value) {
+        ("slot", value) => {
+            let (root, state) = state_at_slot(&beacon_chain, parse_slot(&value)?)?;
+            (root, state, true)
+        }
         ("root", value) => {
             let root = &parse_root(&value)?;
 
-            let state = beacon_chain
+            let state: BeaconState<T::EthSpec> = beacon_chain
                 .store
                 .get_state(root, None)?
                 .ok_or_else(|| ApiError::NotFound(format!("No state for root: {:?}", root)))?;
 
-            (*root, state)
+            (*root, state, false)
         }
         _ => return Err(ApiError::ServerError("Unexpected query parameter".into())),
     };
 
+    // A state loaded by root can't be later than the head, so this doesn't skip slots.
+    let slot = state.slot;
+    let canonical = resolved_by_slot
+        || (slot <= head_state.slot
+            && state_root_at_slot(&beacon_chain, slot, StateSkipConfig::WithStateRoots)? == root);
+
     let response = StateResponse {
         root,
+        canonical,
+        finalized: canonical && is_finalized_slot(&beacon_chain, slot)?,
         beacon_state: state,
     };
 
     ResponseBuilder::new(&req)?.body(&response)
 }
 
+/// HTTP handler to return a Merkle multiproof of the leaves at the `path` parameters of the
+/// `BeaconState` at a given `root` or `slot`, or the head state if neither is given.
+pub fn get_state_proof<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let paths = UrlQuery::from_request(&req)?.all_of("path")?;
+    if paths.is_empty() {
+        return Err(ApiError::BadRequest(
+            "At least one path parameter is required".into(),
+        ));
+    }
+
+    let (state_root, state) = match UrlQuery::from_request(&req)?.first_of_opt(&["root", "slot"]) {
+        Some((key, value)) if key == "slot" => state_at_slot(&beacon_chain, parse_slot(&value)?)?,
+        Some((_, value)) => {
+            let root = parse_root(&value)?;
+            let state: BeaconState<T::EthSpec> = beacon_chain
+                .store
+                .get_state(&root, None)?
+                .ok_or_else(|| ApiError::NotFound(format!("No state for root: {:?}", root)))?;
+            (root, state)
+        }
+        None => {
+            let head = beacon_chain.head()?;
+            (head.beacon_state_root, head.beacon_state)
+        }
+    };
+
+    let proof = state.compute_multiproof(&paths).map_err(|e| match e {
+        BeaconStateError::UnknownProofPath(path) => {
+            ApiError::BadRequest(format!("Unknown state path: {}", path))
+        }
+        BeaconStateError::UnknownValidator(index) => {
+            ApiError::NotFound(format!("No validator at index {}", index))
+        }
+        e => e.into(),
+    })?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&StateProofResponse {
+        state_root,
+        paths,
+        gindices: proof.indices,
+        leaves: proof.leaves,
+        proof: proof.proof,
+    })
+}
+
 /// HTTP handler to return a `BeaconState` root at a given `slot`.
 ///
 /// Will not return a state if the request slot is in the future. Will return states higher than
@@ -476,6 +735,150 @@ pub fn get_genesis_state<T: BeaconChainTypes>(
     ResponseBuilder::new(&req)?.body(&state)
 }
 
+/// HTTP handler to return the finalized `SignedBeaconBlock` as SSZ bytes, so that other nodes can
+/// start syncing from it.
+pub fn get_finalized_block_ssz<T: BeaconChainTypes>(
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let block = finalized_block(&beacon_chain)?;
+
+    ssz_response(Body::from(block.as_ssz_bytes()))
+}
+
+/// HTTP handler to return the `BeaconState` at the start of the finalized epoch as SSZ bytes, so
+/// that other nodes can start syncing from it.
+///
+/// The state is loaded and encoded on a blocking thread of the `executor` and then sent in chunks,
+/// so that a large state does not hold up the server. At most
+/// `MAX_CONCURRENT_FINALIZED_STATE_REQUESTS` are served at once, further requests are refused.
+pub fn get_finalized_state_ssz<T: BeaconChainTypes>(
+    log: Logger,
+    beacon_chain: Arc<BeaconChain<T>>,
+    executor: TaskExecutor,
+) -> ApiResult {
+    let request_guard = FinalizedStateRequestGuard::new().ok_or_else(|| {
+        ApiError::ServiceUnavailable(
+            "Too many requests for the finalized state in progress".to_string(),
+        )
+    })?;
+
+    let finalized_epoch = beacon_chain.head_info()?.finalized_checkpoint.epoch;
+    let block = finalized_block(&beacon_chain)?;
+    let state_root = block.state_root();
+    let block_slot = block.slot();
+    let slot = finalized_epoch.start_slot(T::EthSpec::slots_per_epoch());
+
+    let (mut sender, body) = Body::channel();
+    executor.spawn_blocking(
+        move || {
+            let _request_guard = request_guard;
+
+            let bytes =
+                match finalized_checkpoint_state(&beacon_chain, state_root, block_slot, slot) {
+                    Ok(state) => Bytes::from(state.as_ssz_bytes()),
+                    Err(e) => {
+                        error!(
+                            log,
+                            "Unable to load finalized state";
+                            "error" => format!("{:?}", e),
+                            "state_root" => format!("{:?}", state_root),
+                            "slot" => slot,
+                        );
+                        sender.abort();
+                        return;
+                    }
+                };
+
+            let mut start = 0;
+            while start < bytes.len() {
+                let end = std::cmp::min(start + SSZ_CHUNK_SIZE, bytes.len());
+                // An error means the client has gone away.
+                if block_on(sender.send_data(bytes.slice(start..end))).is_err() {
+                    break;
+                }
+                start = end;
+            }
+        },
+        "finalized_state_ssz",
+    );
+
+    ssz_response(body)
+}
+
+/// Loads the state of the finalized block at `block_slot` and advances it through any skipped
+/// slots to `slot`, the start of the finalized epoch.
+fn finalized_checkpoint_state<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    state_root: Hash256,
+    block_slot: Slot,
+    slot: Slot,
+) -> Result<BeaconState<T::EthSpec>, ApiError> {
+    let mut state = beacon_chain
+        .get_state(&state_root, Some(block_slot))?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Finalized state {:?} missing from store",
+                state_root
+            ))
+        })?;
+
+    while state.slot < slot {
+        // Ensure the next epoch state caches are built in case of an epoch transition.
+        state.build_committee_cache(RelativeEpoch::Next, &beacon_chain.spec)?;
+        state_processing::per_slot_processing(&mut state, None, &beacon_chain.spec)?;
+    }
+    state.drop_all_caches();
+
+    Ok(state)
+}
+
+/// Counts a request for the finalized state as in progress for as long as it is held.
+struct FinalizedStateRequestGuard;
+
+impl FinalizedStateRequestGuard {
+    /// Returns `None` if `MAX_CONCURRENT_FINALIZED_STATE_REQUESTS` are already in progress.
+    fn new() -> Option<Self> {
+        let in_progress = FINALIZED_STATE_REQUESTS.fetch_add(1, Ordering::SeqCst);
+        if in_progress >= MAX_CONCURRENT_FINALIZED_STATE_REQUESTS {
+            FINALIZED_STATE_REQUESTS.fetch_sub(1, Ordering::SeqCst);
+            None
+        } else {
+            Some(Self)
+        }
+    }
+}
+
+impl Drop for FinalizedStateRequestGuard {
+    fn drop(&mut self) {
+        FINALIZED_STATE_REQUESTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Returns the finalized block of the canonical head, or the genesis block if there has not yet
+/// been a finalization.
+fn finalized_block<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+) -> Result<SignedBeaconBlock<T::EthSpec>, ApiError> {
+    let finalized_root = beacon_chain.head_info()?.finalized_checkpoint.root;
+    let block_root = if finalized_root == Hash256::zero() {
+        beacon_chain.genesis_block_root
+    } else {
+        finalized_root
+    };
+
+    beacon_chain.get_block(&block_root)?.ok_or_else(|| {
+        ApiError::NotFound(format!("Unable to find finalized block {:?}", block_root))
+    })
+}
+
+fn ssz_response(body: Body) -> ApiResult {
+    Response::builder()
+        .status(200)
+        .header("content-type", "application/octet-stream")
+        .body(body)
+        .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+}
+
 /// Read the genesis time from the current beacon chain state.
 pub fn get_genesis_time<T: BeaconChainTypes>(
     req: Request<Body>,
@@ -0,0 +1,191 @@
+use crate::{ApiError, ApiResult, UrlQuery};
+use beacon_chain::events::EventKind;
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::TaskExecutor;
+use futures::future::{select, Either};
+use futures::{SinkExt, StreamExt};
+use hyper::header::{
+    HeaderValue, CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION,
+    UPGRADE,
+};
+use hyper::upgrade::Upgraded;
+use hyper::{Body, Request, Response, StatusCode};
+use slog::{debug, warn, Logger};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast::{Receiver, RecvError};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use types::EthSpec;
+
+/// The kinds of events which may be streamed over a WebSocket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventTopic {
+    Head,
+    Block,
+    Attestation,
+    FinalizedCheckpoint,
+}
+
+impl EventTopic {
+    pub const ALL: [EventTopic; 4] = [
+        EventTopic::Head,
+        EventTopic::Block,
+        EventTopic::Attestation,
+        EventTopic::FinalizedCheckpoint,
+    ];
+
+    /// Returns the topic of `kind`, if it is one which may be streamed.
+    fn of<E: EthSpec>(kind: &EventKind<E>) -> Option<Self> {
+        match kind {
+            EventKind::BeaconHeadChanged { .. } => Some(EventTopic::Head),
+            EventKind::BeaconBlockImported { .. } => Some(EventTopic::Block),
+            EventKind::BeaconAttestationImported { .. } => Some(EventTopic::Attestation),
+            EventKind::BeaconFinalization { .. } => Some(EventTopic::FinalizedCheckpoint),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for EventTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "head" => Ok(EventTopic::Head),
+            "block" => Ok(EventTopic::Block),
+            "attestation" => Ok(EventTopic::Attestation),
+            "finalized_checkpoint" => Ok(EventTopic::FinalizedCheckpoint),
+            other => Err(format!("Unknown event topic: {}", other)),
+        }
+    }
+}
+
+/// HTTP handler to upgrade the connection to a WebSocket which streams a JSON message for each
+/// event of the requested `topics`, or of all topics if none are requested.
+///
+/// Each message has the same `{"event": ..., "data": ...}` form as those of the WebSocket server.
+/// There is no JSON-RPC framing: the topics are fixed by the request, so messages carry no
+/// subscription id and those sent by the client are ignored.
+///
+/// The stream is closed when the node shuts down.
+pub fn stream_events_websocket<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    log: Logger,
+    executor: TaskExecutor,
+) -> ApiResult {
+    let topics = match UrlQuery::from_request(&req)?.first_of_opt(&["topics"]) {
+        Some((_key, value)) => value
+            .split(',')
+            .map(|topic| topic.trim().parse())
+            .collect::<Result<HashSet<EventTopic>, _>>()
+            .map_err(ApiError::BadRequest)?,
+        None => EventTopic::ALL.iter().copied().collect(),
+    };
+
+    let headers = req.headers();
+    let is_websocket_upgrade = headers
+        .get(UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.eq_ignore_ascii_case("websocket"));
+    let is_version_13 = headers
+        .get(SEC_WEBSOCKET_VERSION)
+        .map_or(false, |value| value == "13");
+    if !is_websocket_upgrade || !is_version_13 {
+        return Err(ApiError::BadRequest(
+            "Request must be a version 13 WebSocket upgrade".into(),
+        ));
+    }
+    let accept_key = headers
+        .get(SEC_WEBSOCKET_KEY)
+        .map(|key| derive_accept_key(key.as_bytes()))
+        .ok_or_else(|| ApiError::BadRequest("Missing Sec-WebSocket-Key header".into()))?;
+
+    // Subscribe before responding, so that no events are missed after the handshake.
+    let events = beacon_chain.subscribe_events();
+
+    let stream_events = async move {
+        match req.into_body().on_upgrade().await {
+            Ok(upgraded) => {
+                let websocket =
+                    WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                forward_events(websocket, events, topics, log).await
+            }
+            Err(e) => debug!(
+                log,
+                "Failed to upgrade event stream";
+                "error" => format!("{:?}", e)
+            ),
+        }
+    };
+    executor.spawn(stream_events, "events_websocket");
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(UPGRADE, HeaderValue::from_static("websocket"))
+        .header(CONNECTION, HeaderValue::from_static("Upgrade"))
+        .header(SEC_WEBSOCKET_ACCEPT, accept_key)
+        .body(Body::empty())
+        .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+}
+
+/// Sends each event with one of the given `topics` to the `websocket` until the client
+/// disconnects.
+async fn forward_events<E: EthSpec>(
+    websocket: WebSocketStream<Upgraded>,
+    mut events: Receiver<EventKind<E>>,
+    topics: HashSet<EventTopic>,
+    log: Logger,
+) {
+    let (mut sink, mut incoming) = websocket.split();
+
+    loop {
+        // Incoming messages are read so that pings are answered and closes are noticed.
+        let event = match select(Box::pin(events.recv()), incoming.next()).await {
+            Either::Left((event, _)) => event,
+            Either::Right((Some(Ok(Message::Close(_))), _))
+            | Either::Right((Some(Err(_)), _))
+            | Either::Right((None, _)) => break,
+            Either::Right((Some(Ok(_)), _)) => continue,
+        };
+
+        let kind = match event {
+            Ok(kind) => kind,
+            Err(RecvError::Lagged(missed)) => {
+                warn!(
+                    log,
+                    "WebSocket event stream fell behind";
+                    "missed_events" => missed
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        if !EventTopic::of(&kind).map_or(false, |topic| topics.contains(&topic)) {
+            continue;
+        }
+
+        let message = match serde_json::to_string(&kind) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!(
+                    log,
+                    "Unable to serialize event";
+                    "error" => format!("{:?}", e)
+                );
+                continue;
+            }
+        };
+
+        if sink.send(Message::Text(message)).await.is_err() {
+            break;
+        }
+    }
+
+    debug!(log, "WebSocket event stream closed");
+}
@@ -0,0 +1,234 @@
+//! An optional gRPC server which exposes the validator endpoints of the HTTP API.
+//!
+//! The service is generated from `proto/validator.proto` and each method calls the same handler
+//! as the corresponding HTTP endpoint.
+
+use crate::validator::{
+    process_and_publish_beacon_block, produce_beacon_block, return_validator_duties,
+};
+use crate::{ApiError, NetworkChannel};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use bls::PublicKeyBytes;
+use rest_types::ValidatorDutyBytes;
+use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use ssz::{Decode, Encode};
+use std::convert::TryInto;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tonic::{transport::Server, Request, Response, Status};
+use types::{Epoch, Signature, SignedBeaconBlock, Slot};
+
+pub mod proto {
+    tonic::include_proto!("lighthouse.validator");
+}
+
+use proto::beacon_node_validator_server::{BeaconNodeValidator, BeaconNodeValidatorServer};
+use proto::{
+    DutiesRequest, DutiesResponse, ProduceBlockRequest, ProduceBlockResponse, PublishBlockRequest,
+    PublishBlockResponse, ValidatorDuty,
+};
+
+/// gRPC API Configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Enable the gRPC server.
+    pub enabled: bool,
+    /// The IPv4 address the gRPC server will listen on.
+    pub listen_address: Ipv4Addr,
+    /// The port the gRPC server will listen on.
+    pub port: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            port: 5054,
+        }
+    }
+}
+
+impl From<ApiError> for Status {
+    fn from(e: ApiError) -> Status {
+        match e {
+            ApiError::BadRequest(desc) | ApiError::ProcessingError(desc) => {
+                Status::invalid_argument(desc)
+            }
+            ApiError::NotFound(desc) => Status::not_found(desc),
+            ApiError::NotImplemented(desc) => Status::unimplemented(desc),
+            ApiError::ServiceUnavailable(desc) => Status::unavailable(desc),
+            e => Status::internal(e.status_code().1),
+        }
+    }
+}
+
+impl From<ValidatorDutyBytes> for ValidatorDuty {
+    fn from(duty: ValidatorDutyBytes) -> ValidatorDuty {
+        ValidatorDuty {
+            validator_pubkey: duty.validator_pubkey.as_ssz_bytes(),
+            validator_index: duty.validator_index,
+            attestation_slot: duty.attestation_slot.map(Slot::as_u64),
+            attestation_committee_index: duty.attestation_committee_index,
+            attestation_committee_position: duty
+                .attestation_committee_position
+                .map(|position| position as u64),
+            block_proposal_slots: duty
+                .block_proposal_slots
+                .into_iter()
+                .map(Slot::as_u64)
+                .collect(),
+            aggregator_modulo: duty.aggregator_modulo,
+        }
+    }
+}
+
+fn decode_field<T: Decode>(bytes: &[u8], name: &str) -> Result<T, Status> {
+    T::from_ssz_bytes(bytes)
+        .map_err(|e| Status::invalid_argument(format!("Invalid {}: {:?}", name, e)))
+}
+
+/// Implements the generated `BeaconNodeValidator` service.
+pub struct ValidatorService<T: BeaconChainTypes> {
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel<T::EthSpec>,
+    log: Logger,
+}
+
+#[tonic::async_trait]
+impl<T: BeaconChainTypes> BeaconNodeValidator for ValidatorService<T> {
+    async fn get_duties(
+        &self,
+        request: Request<DutiesRequest>,
+    ) -> Result<Response<DutiesResponse>, Status> {
+        let request = request.into_inner();
+        let pubkeys = request
+            .pubkeys
+            .iter()
+            .map(|bytes| decode_field::<PublicKeyBytes>(bytes, "pubkey"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let duties = return_validator_duties(
+            self.beacon_chain.clone(),
+            Epoch::new(request.epoch),
+            pubkeys,
+        )?;
+
+        Ok(Response::new(DutiesResponse {
+            duties: duties.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn produce_block(
+        &self,
+        request: Request<ProduceBlockRequest>,
+    ) -> Result<Response<ProduceBlockResponse>, Status> {
+        let request = request.into_inner();
+        let randao_reveal = decode_field::<Signature>(&request.randao_reveal, "randao_reveal")?;
+        let graffiti =
+            if request.graffiti.is_empty() {
+                None
+            } else {
+                Some(
+                    request.graffiti.as_slice().try_into().map_err(|_| {
+                        Status::invalid_argument("Graffiti must be empty or 32 bytes")
+                    })?,
+                )
+            };
+
+        let block = produce_beacon_block(
+            &self.beacon_chain,
+            Slot::new(request.slot),
+            randao_reveal,
+            graffiti,
+            &self.log,
+        )?;
+
+        Ok(Response::new(ProduceBlockResponse {
+            block: block.as_ssz_bytes(),
+        }))
+    }
+
+    async fn publish_block(
+        &self,
+        request: Request<PublishBlockRequest>,
+    ) -> Result<Response<PublishBlockResponse>, Status> {
+        let block = decode_field::<SignedBeaconBlock<T::EthSpec>>(
+            &request.into_inner().signed_block,
+            "signed_block",
+        )?;
+
+        process_and_publish_beacon_block(
+            &self.beacon_chain,
+            self.network_chan.clone(),
+            block,
+            &self.log,
+        )?;
+
+        Ok(Response::new(PublishBlockResponse {}))
+    }
+}
+
+/// Starts the gRPC server, returning the address it is listening on.
+pub fn start_server<T: BeaconChainTypes>(
+    executor: environment::TaskExecutor,
+    config: &Config,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel<T::EthSpec>,
+) -> Result<SocketAddr, String> {
+    let log = executor.log().clone();
+
+    // Bind synchronously so that the actual listen address is known before returning.
+    //
+    // This may be different to the configured address if the port was 0 (this allows the OS to
+    // choose a free port).
+    let std_listener = std::net::TcpListener::bind((config.listen_address, config.port))
+        .map_err(|e| format!("Unable to bind gRPC server: {:?}", e))?;
+    std_listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Unable to configure gRPC listener: {:?}", e))?;
+    let actual_listen_addr = std_listener
+        .local_addr()
+        .map_err(|e| format!("Unable to read gRPC listen address: {:?}", e))?;
+    let mut listener = TcpListener::from_std(std_listener)
+        .map_err(|e| format!("Unable to register gRPC listener: {:?}", e))?;
+
+    let service = BeaconNodeValidatorServer::new(ValidatorService {
+        beacon_chain,
+        network_chan,
+        log: log.clone(),
+    });
+
+    let exit = executor.exit();
+    let inner_log = log.clone();
+    let server_future = async move {
+        let result = Server::builder()
+            .add_service(service)
+            .serve_with_incoming_shutdown(listener.incoming(), async {
+                let _ = exit.await;
+            })
+            .await;
+
+        match result {
+            Ok(()) => info!(inner_log, "gRPC service shutdown"),
+            Err(e) => warn!(
+                inner_log,
+                "gRPC server failed";
+                "error" => format!("{:?}", e)
+            ),
+        }
+    };
+
+    info!(
+        log,
+        "gRPC API started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+    );
+
+    executor.spawn_without_exit(server_future, "grpc");
+
+    Ok(actual_listen_addr)
+}
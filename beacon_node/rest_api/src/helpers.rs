@@ -119,6 +119,12 @@ pub fn block_root_at_slot<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
     target: Slot,
 ) -> Result<Option<Hash256>, ApiError> {
+    // Finalized slots are read from the freezer's index of block roots, rather than by iterating
+    // back from the head.
+    if let Some(root) = beacon_chain.store.get_frozen_block_root(target)? {
+        return Ok(Some(root));
+    }
+
     Ok(process_results(
         beacon_chain.rev_iter_block_roots()?,
         |iter| {
@@ -192,8 +198,12 @@ pub fn state_root_at_slot<T: BeaconChainTypes>(
     } else if head_state.slot > slot {
         // 3. The request slot is prior to the head slot.
         //
-        // Iterate through the state roots on the head state to find the root for that
-        // slot. Once the root is found, load it from the database.
+        // Finalized slots are read from the freezer's index of state roots. Otherwise, iterate
+        // through the state roots on the head state to find the root for that slot.
+        if let Some(root) = beacon_chain.store.get_frozen_state_root(slot)? {
+            return Ok(root);
+        }
+
         process_results(
             head_state
                 .try_iter_ancestor_roots(beacon_chain.store.clone())
@@ -229,6 +239,22 @@ pub fn state_root_at_slot<T: BeaconChainTypes>(
     }
 }
 
+/// Returns true if `slot` is at or prior to the start of the finalized epoch.
+///
+/// Only meaningful for blocks and states known to be in the canonical chain.
+pub fn is_finalized_slot<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    slot: Slot,
+) -> Result<bool, ApiError> {
+    let finalized_slot = beacon_chain
+        .head_info()?
+        .finalized_checkpoint
+        .epoch
+        .start_slot(T::EthSpec::slots_per_epoch());
+
+    Ok(slot <= finalized_slot)
+}
+
 pub fn implementation_pending_response(_req: Request<Body>) -> ApiResult {
     Err(ApiError::NotImplemented(
         "API endpoint has not yet been implemented, but is planned to be soon.".to_owned(),
@@ -9,6 +9,8 @@ mod beacon;
 pub mod config;
 mod consensus;
 mod error;
+mod events;
+pub mod grpc;
 mod helpers;
 mod lighthouse;
 mod metrics;
@@ -19,9 +21,9 @@ mod router;
 mod spec;
 mod url_query;
 mod validator;
+mod validator_inclusion;
 
-use beacon_chain::{BeaconChain, BeaconChainTypes};
-use bus::Bus;
+use beacon_chain::{events::ServerSentEventQueues, BeaconChain, BeaconChainTypes};
 use client_network::NetworkMessage;
 pub use config::ApiEncodingFormat;
 use error::{ApiError, ApiResult};
@@ -31,13 +33,12 @@ use futures::future::TryFutureExt;
 use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Request, Server};
-use parking_lot::Mutex;
+use logging::LogLevels;
 use slog::{info, warn};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use types::SignedBeaconBlockHash;
 use url_query::UrlQuery;
 
 pub use crate::helpers::parse_pubkey_bytes;
@@ -60,7 +61,8 @@ pub fn start_server<T: BeaconChainTypes>(
     db_path: PathBuf,
     freezer_db_path: PathBuf,
     eth2_config: Eth2Config,
-    events: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    events: ServerSentEventQueues,
+    log_levels: LogLevels,
 ) -> Result<SocketAddr, hyper::Error> {
     let log = executor.log();
     let inner_log = log.clone();
@@ -76,6 +78,8 @@ pub fn start_server<T: BeaconChainTypes>(
         let db_path = db_path.clone();
         let freezer_db_path = freezer_db_path.clone();
         let events = events.clone();
+        let log_levels = log_levels.clone();
+        let executor = executor.clone();
 
         async move {
             Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
@@ -89,6 +93,8 @@ pub fn start_server<T: BeaconChainTypes>(
                     db_path.clone(),
                     freezer_db_path.clone(),
                     events.clone(),
+                    log_levels.clone(),
+                    executor.clone(),
                 )
             }))
         }
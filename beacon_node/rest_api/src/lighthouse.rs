@@ -1,12 +1,21 @@
 //! This contains a collection of lighthouse specific HTTP endpoints.
 
 use crate::response_builder::ResponseBuilder;
-use crate::ApiResult;
-use eth2_libp2p::{NetworkGlobals, PeerInfo};
+use crate::{ApiError, ApiResult, NetworkChannel};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::types::{SyncState, SyncStatus};
+use eth2_libp2p::{NetworkGlobals, PeerId, PeerInfo, PeerSyncStatus, ScoreChange};
 use hyper::{Body, Request};
+use logging::LogLevels;
+use network::NetworkMessage;
+use proto_array_fork_choice::ViableHead;
+use rest_types::{Health, LighthouseHealth, LogLevelConfig, NetworkRestartRequest};
 use serde::Serialize;
+use slog::info;
+use std::path::PathBuf;
 use std::sync::Arc;
-use types::EthSpec;
+use store::CompactionStats;
+use types::{EthSpec, Slot};
 
 /// The syncing state of the beacon node.
 pub fn syncing<T: EthSpec>(
@@ -16,6 +25,29 @@ pub fn syncing<T: EthSpec>(
     ResponseBuilder::new(&req)?.body_no_ssz(&network_globals.sync_state())
 }
 
+/// The syncing state of the beacon node, along with the progress of each sync chain and lookup.
+pub fn syncing_details<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let mut peers = PeersBySyncStatus::default();
+    for (_, peer_info) in network_globals.peers.read().connected_peers() {
+        match peer_info.sync_status {
+            PeerSyncStatus::Synced { .. } => peers.synced += 1,
+            PeerSyncStatus::Advanced { .. } => peers.advanced += 1,
+            PeerSyncStatus::Behind { .. } => peers.behind += 1,
+            PeerSyncStatus::Unknown => peers.unknown += 1,
+            PeerSyncStatus::Quarantined { .. } => peers.quarantined += 1,
+        }
+    }
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&SyncingDetails {
+        state: network_globals.sync_state(),
+        status: network_globals.sync_status(),
+        peers,
+    })
+}
+
 /// Returns all known peers and corresponding information
 pub fn peers<T: EthSpec>(req: Request<Body>, network_globals: Arc<NetworkGlobals<T>>) -> ApiResult {
     let peers: Vec<Peer<T>> = network_globals
@@ -47,6 +79,259 @@ pub fn connected_peers<T: EthSpec>(
     ResponseBuilder::new(&req)?.body_no_ssz(&peers)
 }
 
+/// Returns the entries of the ban list, in the format of the `--ban-list-file`.
+pub fn ban_list<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let entries = network_globals.peers.read().ban_list().entries();
+    ResponseBuilder::new(&req)?.body_no_ssz(&entries)
+}
+
+/// Returns the RPC errors and rejected gossip messages of peers, by the client they run.
+pub fn client_errors<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let summary = network_globals.client_errors.read().summary();
+    ResponseBuilder::new(&req)?.body_no_ssz(&summary)
+}
+
+/// Returns the most recent changes to the reputation of the peer in the `path`, which must be of
+/// the form `/lighthouse/peers/{peer_id}/score_history`.
+pub fn peer_score_history<T: EthSpec>(
+    req: Request<Body>,
+    network_globals: Arc<NetworkGlobals<T>>,
+) -> ApiResult {
+    let peer_id = req
+        .uri()
+        .path()
+        .trim_start_matches("/lighthouse/peers/")
+        .trim_end_matches("/score_history")
+        .parse::<PeerId>()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid peer id: {:?}", e)))?;
+
+    let history: Vec<ScoreChange> = network_globals
+        .peers
+        .read()
+        .peer_info(&peer_id)
+        .map(|peer_info| peer_info.score_history.iter().cloned().collect())
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown peer: {}", peer_id)))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&history)
+}
+
+/// Returns the heads of the block tree which are viable in fork choice, with their weights and the
+/// ancestors at which their chains fork, latest first.
+pub fn beacon_heads<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let canonical_root = beacon_chain.head_info()?.block_root;
+
+    let mut heads: Vec<ForkChoiceHead> = beacon_chain
+        .fork_choice
+        .viable_heads()
+        .into_iter()
+        .map(|head| ForkChoiceHead {
+            canonical: head.root == canonical_root,
+            head,
+        })
+        .collect();
+    heads.sort_by(|a, b| (b.head.slot, b.head.root).cmp(&(a.head.slot, a.head.root)));
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&heads)
+}
+
+/// Returns process and system statistics, along with the size of the databases.
+pub fn health(req: Request<Body>, db_path: PathBuf, freezer_db_path: PathBuf) -> ApiResult {
+    let health = Health::observe().map_err(ApiError::ServerError)?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&LighthouseHealth {
+        health,
+        db_size: store::size_of_dir(&db_path),
+        freezer_db_size: store::size_of_dir(&freezer_db_path),
+    })
+}
+
+/// The size of the hot and freezer databases, along with the outcome of the last compaction.
+pub fn database<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    db_path: PathBuf,
+    freezer_db_path: PathBuf,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&DatabaseInfo {
+        db_size: store::size_of_dir(&db_path),
+        freezer_db_size: store::size_of_dir(&freezer_db_path),
+        split_slot: beacon_chain.store.get_split_slot(),
+        last_compaction: beacon_chain.store.last_compaction(),
+    })
+}
+
+#[derive(Serialize)]
+struct DatabaseInfo {
+    db_size: u64,
+    freezer_db_size: u64,
+    /// The slot below which states are stored in the freezer database.
+    split_slot: Slot,
+    last_compaction: Option<CompactionStats>,
+}
+
+/// The health of each configured eth1 endpoint and which one is in use.
+pub fn eth1_endpoints<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let endpoints = beacon_chain
+        .eth1_chain
+        .as_ref()
+        .map(|eth1_chain| eth1_chain.endpoints())
+        .unwrap_or_default();
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&endpoints)
+}
+
+/// Returns the contents of the eth1 block and deposit caches, or `null` if the node is not
+/// syncing the eth1 chain.
+pub fn eth1_cache<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let status = beacon_chain
+        .eth1_chain
+        .as_ref()
+        .and_then(|eth1_chain| eth1_chain.cache_status());
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&status)
+}
+
+/// Returns a snapshot of the deposit tree at the most recent finalized eth1 data, which another
+/// node may start its deposit cache from.
+pub fn eth1_deposit_snapshot<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let snapshot = beacon_chain
+        .get_deposit_snapshot()?
+        .ok_or_else(|| ApiError::NotFound("No deposit snapshot is available".to_string()))?;
+
+    ResponseBuilder::new(&req)?.body(&snapshot)
+}
+
+/// Returns the default log level and all per-module overrides.
+pub fn get_log_levels(req: Request<Body>, log_levels: LogLevels) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&log_level_config(&log_levels))
+}
+
+/// Requests that the network service writes a diagnostic snapshot of the network to the network
+/// directory.
+pub fn network_snapshot<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network_chan: NetworkChannel<T::EthSpec>,
+) -> ApiResult {
+    network_chan.send(NetworkMessage::Snapshot).map_err(|e| {
+        ApiError::ServerError(format!(
+            "Unable to request a snapshot from the network: {:?}",
+            e
+        ))
+    })?;
+    ResponseBuilder::new(&req)?.body_no_ssz(&())
+}
+
+/// Requests that the network service tears down the libp2p stack and builds a new one, on the
+/// ports in the request body if any are given. The restart happens after the response is sent.
+pub async fn network_restart<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network_chan: NetworkChannel<T::EthSpec>,
+) -> ApiResult {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let body = req.into_body();
+    let chunks = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+
+    let request = if chunks.is_empty() {
+        NetworkRestartRequest::default()
+    } else {
+        serde_json::from_slice::<NetworkRestartRequest>(&chunks).map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Unable to parse JSON into NetworkRestartRequest: {:?}",
+                e
+            ))
+        })?
+    };
+
+    network_chan
+        .send(NetworkMessage::Restart {
+            libp2p_port: request.libp2p_port,
+            discovery_port: request.discovery_port,
+        })
+        .map_err(|e| {
+            ApiError::ServerError(format!(
+                "Unable to request a restart from the network: {:?}",
+                e
+            ))
+        })?;
+    response_builder?.body_no_ssz(&())
+}
+
+/// Replaces the default log level and all per-module overrides with those in the request body.
+pub async fn post_log_levels(
+    req: Request<Body>,
+    log_levels: LogLevels,
+    log: slog::Logger,
+) -> ApiResult {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let body = req.into_body();
+    let chunks = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+
+    let config = serde_json::from_slice::<LogLevelConfig>(&chunks).map_err(|e| {
+        ApiError::BadRequest(format!("Unable to parse JSON into LogLevelConfig: {:?}", e))
+    })?;
+
+    // Parse all levels before applying any, so an invalid request has no effect.
+    let default = logging::parse_level(&config.default).map_err(ApiError::BadRequest)?;
+    let modules = config
+        .modules
+        .into_iter()
+        .map(|(module, level)| Ok((module, logging::parse_level(&level)?)))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(ApiError::BadRequest)?;
+
+    log_levels.set_default_level(default);
+    log_levels.clear_module_levels();
+    for (module, level) in modules {
+        log_levels.set_module_level(module, level);
+    }
+
+    let new_config = log_level_config(&log_levels);
+
+    info!(
+        log,
+        "Updated log levels";
+        "default" => &new_config.default,
+        "modules" => format!("{:?}", new_config.modules),
+    );
+
+    response_builder?.body_no_ssz(&new_config)
+}
+
+fn log_level_config(log_levels: &LogLevels) -> LogLevelConfig {
+    LogLevelConfig {
+        default: logging::level_to_str(log_levels.default_level()).to_string(),
+        modules: log_levels
+            .module_levels()
+            .into_iter()
+            .map(|(module, level)| (module, logging::level_to_str(level).to_string()))
+            .collect(),
+    }
+}
+
 /// Information returned by `peers` and `connected_peers`.
 #[derive(Clone, Debug, Serialize)]
 #[serde(bound = "T: EthSpec")]
@@ -56,3 +341,33 @@ struct Peer<T: EthSpec> {
     /// The PeerInfo associated with the peer.
     peer_info: PeerInfo<T>,
 }
+
+/// Information returned by `beacon_heads`.
+#[derive(Clone, Debug, Serialize)]
+struct ForkChoiceHead {
+    #[serde(flatten)]
+    head: ViableHead,
+    /// True if the head is the canonical head of the chain.
+    canonical: bool,
+}
+
+/// Information returned by `syncing_details`.
+#[derive(Clone, Debug, Serialize)]
+struct SyncingDetails {
+    /// The coarse syncing state, as returned by `syncing`.
+    state: SyncState,
+    /// The progress of each sync chain and lookup.
+    status: SyncStatus,
+    /// The number of connected peers in each sync status.
+    peers: PeersBySyncStatus,
+}
+
+/// The number of connected peers in each `PeerSyncStatus`.
+#[derive(Clone, Debug, Default, Serialize)]
+struct PeersBySyncStatus {
+    synced: usize,
+    advanced: usize,
+    behind: usize,
+    unknown: usize,
+    quarantined: usize,
+}
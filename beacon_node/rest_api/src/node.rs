@@ -1,8 +1,9 @@
 use crate::response_builder::ResponseBuilder;
 use crate::{ApiError, ApiResult};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::{types::SyncState, NetworkGlobals};
-use hyper::{Body, Request};
-use rest_types::{Health, SyncingResponse, SyncingStatus};
+use hyper::{Body, Request, StatusCode};
+use rest_types::{DataResponse, EthSyncingData, Health, SyncingResponse, SyncingStatus};
 use std::sync::Arc;
 use types::{EthSpec, Slot};
 use version;
@@ -42,6 +43,53 @@ pub fn syncing<T: EthSpec>(
     })
 }
 
+/// The syncing status of the node, as defined by the standard `/eth/v1/node/syncing` API.
+pub fn get_eth_syncing<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network: Arc<NetworkGlobals<T::EthSpec>>,
+) -> ApiResult {
+    let head_slot = beacon_chain.head_info()?.slot;
+    let current_slot = beacon_chain
+        .slot()
+        .map_err(|e| ApiError::ServerError(format!("Unable to read slot clock: {:?}", e)))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&DataResponse {
+        data: EthSyncingData {
+            head_slot,
+            // Taking advantage of saturating subtraction on `Slot`.
+            sync_distance: current_slot - head_slot,
+            is_syncing: network.is_syncing(),
+        },
+    })
+}
+
+/// Returns an empty response whose status code reflects the health of the node, as defined by the
+/// standard `/eth/v1/node/health` API:
+///
+/// - 200: the node is synced.
+/// - 206: the node is syncing.
+/// - 503: the node has no peers or is unable to read its head.
+pub fn get_eth_health<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network: Arc<NetworkGlobals<T::EthSpec>>,
+) -> ApiResult {
+    let status = if beacon_chain.head_info().is_err() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        match network.sync_state() {
+            SyncState::Synced => StatusCode::OK,
+            SyncState::SyncingFinalized { .. } | SyncState::SyncingHead { .. } => {
+                StatusCode::PARTIAL_CONTENT
+            }
+            SyncState::Stalled => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    };
+
+    ResponseBuilder::new(&req)?.status_only(status)
+}
+
 pub fn get_health(req: Request<Body>) -> ApiResult {
     let health = Health::observe().map_err(|e| ApiError::ServerError(e))?;
 
@@ -73,6 +73,14 @@ impl ResponseBuilder {
             .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
     }
 
+    /// Returns an empty response with the given `status`.
+    pub fn status_only(self, status: StatusCode) -> ApiResult {
+        Response::builder()
+            .status(status)
+            .body(Body::empty())
+            .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+    }
+
     pub fn body_text(self, text: String) -> ApiResult {
         Response::builder()
             .status(StatusCode::OK)
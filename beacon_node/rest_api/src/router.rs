@@ -1,18 +1,18 @@
 use crate::{
-    advanced, beacon, consensus, error::ApiError, helpers, lighthouse, metrics, network, node,
-    spec, validator, NetworkChannel,
+    advanced, beacon, consensus, error::ApiError, events, helpers, lighthouse, metrics, network,
+    node, spec, validator, validator_inclusion, NetworkChannel,
 };
-use beacon_chain::{BeaconChain, BeaconChainTypes};
-use bus::Bus;
+use beacon_chain::{events::ServerSentEventQueues, BeaconChain, BeaconChainTypes};
+use environment::TaskExecutor;
 use eth2_config::Eth2Config;
 use eth2_libp2p::NetworkGlobals;
 use hyper::{Body, Error, Method, Request, Response};
-use parking_lot::Mutex;
+use logging::LogLevels;
 use slog::debug;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use types::{SignedBeaconBlockHash, Slot};
+use types::Slot;
 
 // Allowing more than 7 arguments.
 #[allow(clippy::too_many_arguments)]
@@ -25,7 +25,9 @@ pub async fn route<T: BeaconChainTypes>(
     local_log: slog::Logger,
     db_path: PathBuf,
     freezer_db_path: PathBuf,
-    events: Arc<Mutex<Bus<SignedBeaconBlockHash>>>,
+    events: ServerSentEventQueues,
+    log_levels: LogLevels,
+    executor: TaskExecutor,
 ) -> Result<Response<Body>, Error> {
     metrics::inc_counter(&metrics::REQUEST_COUNT);
     let timer = metrics::start_timer(&metrics::REQUEST_RESPONSE_TIME);
@@ -48,6 +50,14 @@ pub async fn route<T: BeaconChainTypes>(
             node::syncing::<T::EthSpec>(req, network_globals, current_slot)
         }
 
+        // Standard methods for Client
+        (&Method::GET, "/eth/v1/node/health") => {
+            node::get_eth_health::<T>(req, beacon_chain, network_globals)
+        }
+        (&Method::GET, "/eth/v1/node/syncing") => {
+            node::get_eth_syncing::<T>(req, beacon_chain, network_globals)
+        }
+
         // Methods for Network
         (&Method::GET, "/network/enr") => network::get_enr::<T>(req, network_globals),
         (&Method::GET, "/network/peer_count") => network::get_peer_count::<T>(req, network_globals),
@@ -67,9 +77,16 @@ pub async fn route<T: BeaconChainTypes>(
         (&Method::GET, "/beacon/block_root") => beacon::get_block_root::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/fork") => beacon::get_fork::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/fork/stream") => {
-            let reader = events.lock().add_rx();
+            let reader = events.head_changed.lock().add_rx();
             beacon::stream_forks::<T>(log, reader)
         }
+        (&Method::GET, "/beacon/reorg/stream") => {
+            let reader = events.reorg.lock().add_rx();
+            beacon::stream_reorgs::<T>(log, reader)
+        }
+        (&Method::GET, "/beacon/events/ws") => {
+            events::stream_events_websocket::<T>(req, beacon_chain, log, executor)
+        }
         (&Method::GET, "/beacon/genesis_time") => beacon::get_genesis_time::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/genesis_validators_root") => {
             beacon::get_genesis_validators_root::<T>(req, beacon_chain)
@@ -84,11 +101,24 @@ pub async fn route<T: BeaconChainTypes>(
         (&Method::GET, "/beacon/validators/active") => {
             beacon::get_active_validators::<T>(req, beacon_chain)
         }
+        (&Method::GET, "/beacon/validators/query") => {
+            beacon::get_validators_query::<T>(req, beacon_chain)
+        }
+        (&Method::POST, "/beacon/validators/query") => {
+            beacon::post_validators_query::<T>(req, beacon_chain).await
+        }
         (&Method::GET, "/beacon/state") => beacon::get_state::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/state_root") => beacon::get_state_root::<T>(req, beacon_chain),
+        (&Method::GET, "/beacon/state/proof") => beacon::get_state_proof::<T>(req, beacon_chain),
         (&Method::GET, "/beacon/state/genesis") => {
             beacon::get_genesis_state::<T>(req, beacon_chain)
         }
+        (&Method::GET, "/eth/v2/beacon/blocks/finalized") => {
+            beacon::get_finalized_block_ssz::<T>(beacon_chain)
+        }
+        (&Method::GET, "/eth/v2/debug/beacon/states/finalized") => {
+            beacon::get_finalized_state_ssz::<T>(log, beacon_chain, executor)
+        }
         (&Method::GET, "/beacon/committees") => beacon::get_committees::<T>(req, beacon_chain),
         (&Method::POST, "/beacon/proposer_slashing") => {
             beacon::proposer_slashing::<T>(req, beacon_chain).await
@@ -171,6 +201,10 @@ pub async fn route<T: BeaconChainTypes>(
             lighthouse::syncing::<T::EthSpec>(req, network_globals)
         }
 
+        (&Method::GET, "/lighthouse/syncing/details") => {
+            lighthouse::syncing_details::<T::EthSpec>(req, network_globals)
+        }
+
         (&Method::GET, "/lighthouse/peers") => {
             lighthouse::peers::<T::EthSpec>(req, network_globals)
         }
@@ -178,6 +212,57 @@ pub async fn route<T: BeaconChainTypes>(
         (&Method::GET, "/lighthouse/connected_peers") => {
             lighthouse::connected_peers::<T::EthSpec>(req, network_globals)
         }
+
+        (&Method::GET, "/lighthouse/ban_list") => {
+            lighthouse::ban_list::<T::EthSpec>(req, network_globals)
+        }
+        (&Method::GET, "/lighthouse/client_errors") => {
+            lighthouse::client_errors::<T::EthSpec>(req, network_globals)
+        }
+
+        (&Method::POST, "/lighthouse/network/snapshot") => {
+            lighthouse::network_snapshot::<T>(req, network_channel)
+        }
+        (&Method::POST, "/lighthouse/network/restart") => {
+            lighthouse::network_restart::<T>(req, network_channel).await
+        }
+
+        (&Method::GET, path)
+            if path.starts_with("/lighthouse/peers/") && path.ends_with("/score_history") =>
+        {
+            lighthouse::peer_score_history::<T::EthSpec>(req, network_globals)
+        }
+
+        (&Method::GET, "/lighthouse/beacon/heads") => {
+            lighthouse::beacon_heads::<T>(req, beacon_chain)
+        }
+
+        (&Method::GET, path)
+            if path.starts_with("/lighthouse/validator_inclusion/")
+                && path.ends_with("/global") =>
+        {
+            validator_inclusion::get_global_inclusion::<T>(req, beacon_chain)
+        }
+        (&Method::POST, "/lighthouse/validator_inclusion") => {
+            validator_inclusion::post_validator_inclusion::<T>(req, beacon_chain).await
+        }
+
+        (&Method::GET, "/lighthouse/health") => lighthouse::health(req, db_path, freezer_db_path),
+        (&Method::GET, "/lighthouse/database") => {
+            lighthouse::database::<T>(req, beacon_chain, db_path, freezer_db_path)
+        }
+        (&Method::GET, "/lighthouse/eth1/endpoints") => {
+            lighthouse::eth1_endpoints::<T>(req, beacon_chain)
+        }
+        (&Method::GET, "/lighthouse/eth1/cache") => lighthouse::eth1_cache::<T>(req, beacon_chain),
+        (&Method::GET, "/lighthouse/eth1/deposit_snapshot") => {
+            lighthouse::eth1_deposit_snapshot::<T>(req, beacon_chain)
+        }
+
+        (&Method::GET, "/lighthouse/log_levels") => lighthouse::get_log_levels(req, log_levels),
+        (&Method::POST, "/lighthouse/log_levels") => {
+            lighthouse::post_log_levels(req, log_levels, log).await
+        }
         _ => Err(ApiError::NotFound(
             "Request path and/or method not found.".to_owned(),
         )),
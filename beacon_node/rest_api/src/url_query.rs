@@ -1,7 +1,7 @@
 use crate::helpers::{parse_committee_index, parse_epoch, parse_hex_ssz_bytes, parse_slot};
 use crate::ApiError;
 use hyper::Request;
-use types::{AttestationData, CommitteeIndex, Epoch, Signature, Slot};
+use types::{utils::GRAFFITI_BYTES_LEN, AttestationData, CommitteeIndex, Epoch, Signature, Slot};
 
 /// Provides handy functions for parsing the query parameters of a URL.
 
@@ -109,6 +109,13 @@ impl<'a> UrlQuery<'a> {
             .and_then(|(_key, value)| parse_hex_ssz_bytes(&value))
     }
 
+    /// Returns the value of the first occurrence of the `graffiti` key, if any.
+    pub fn graffiti(self) -> Result<Option<[u8; GRAFFITI_BYTES_LEN]>, ApiError> {
+        self.first_of_opt(&["graffiti"])
+            .map(|(_key, value)| parse_hex_ssz_bytes(&value))
+            .transpose()
+    }
+
     /// Returns the value of the first occurrence of the `attestation_data` key.
     pub fn attestation_data(self) -> Result<AttestationData, ApiError> {
         self.first_of(&["attestation_data"])
@@ -14,9 +14,10 @@ use rest_types::{ValidatorDutiesRequest, ValidatorDutyBytes, ValidatorSubscripti
 use slog::{error, info, trace, warn, Logger};
 use std::sync::Arc;
 use types::beacon_state::EthSpec;
+use types::utils::GRAFFITI_BYTES_LEN;
 use types::{
-    Attestation, AttestationData, BeaconState, Epoch, RelativeEpoch, SelectionProof,
-    SignedAggregateAndProof, SignedBeaconBlock, Slot,
+    Attestation, AttestationData, BeaconBlock, BeaconState, Epoch, RelativeEpoch, SelectionProof,
+    Signature, SignedAggregateAndProof, SignedBeaconBlock, Slot,
 };
 
 /// HTTP Handler to retrieve the duties for a set of validators during a particular epoch. This
@@ -161,7 +162,9 @@ pub fn get_state_for_epoch<T: BeaconChainTypes>(
 }
 
 /// Helper function to get the duties for some `validator_pubkeys` in some `epoch`.
-fn return_validator_duties<T: BeaconChainTypes>(
+///
+/// Shared by the HTTP and gRPC APIs.
+pub(crate) fn return_validator_duties<T: BeaconChainTypes>(
     beacon_chain: Arc<BeaconChain<T>>,
     epoch: Epoch,
     validator_pubkeys: Vec<PublicKeyBytes>,
@@ -267,9 +270,25 @@ pub fn get_new_beacon_block<T: BeaconChainTypes>(
 
     let slot = query.slot()?;
     let randao_reveal = query.randao_reveal()?;
+    let graffiti = query.graffiti()?;
 
+    let new_block = produce_beacon_block(&beacon_chain, slot, randao_reveal, graffiti, &log)?;
+
+    ResponseBuilder::new(&req)?.body(&new_block)
+}
+
+/// Produce a new BeaconBlock at `slot` from the current state, ready to be signed by a validator.
+///
+/// Shared by the HTTP and gRPC APIs.
+pub(crate) fn produce_beacon_block<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    slot: Slot,
+    randao_reveal: Signature,
+    graffiti: Option<[u8; GRAFFITI_BYTES_LEN]>,
+    log: &Logger,
+) -> Result<BeaconBlock<T::EthSpec>, ApiError> {
     let (new_block, _state) = beacon_chain
-        .produce_block(randao_reveal, slot)
+        .produce_block(randao_reveal, slot, graffiti)
         .map_err(|e| {
             error!(
                 log,
@@ -283,7 +302,7 @@ pub fn get_new_beacon_block<T: BeaconChainTypes>(
             ))
         })?;
 
-    ResponseBuilder::new(&req)?.body(&new_block)
+    Ok(new_block)
 }
 
 /// HTTP Handler to publish a SignedBeaconBlock, which has been signed by a validator.
@@ -301,84 +320,100 @@ pub async fn publish_beacon_block<T: BeaconChainTypes>(
         .await
         .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
 
-    serde_json::from_slice(&chunks).map_err(|e| {
-                    ApiError::BadRequest(format!("Unable to parse JSON into SignedBeaconBlock: {:?}", e))
-                })
-            .and_then(move |block: SignedBeaconBlock<T::EthSpec>| {
-                let slot = block.slot();
-                match beacon_chain.process_block(block.clone()) {
-                    Ok(block_root) => {
-                        // Block was processed, publish via gossipsub
-                        info!(
-                            log,
-                            "Block from local validator";
-                            "block_root" => format!("{}", block_root),
-                            "block_slot" => slot,
-                        );
-
-                        publish_beacon_block_to_network::<T>(network_chan, block)?;
-
-                        // Run the fork choice algorithm and enshrine a new canonical head, if
-                        // found.
-                        //
-                        // The new head may or may not be the block we just received.
-                        if let Err(e) = beacon_chain.fork_choice() {
-                            error!(
-                                log,
-                                "Failed to find beacon chain head";
-                                "error" => format!("{:?}", e)
-                            );
-                        } else {
-                            // In the best case, validators should produce blocks that become the
-                            // head.
-                            //
-                            // Potential reasons this may not be the case:
-                            //
-                            // - A quick re-org between block produce and publish.
-                            // - Excessive time between block produce and publish.
-                            // - A validator is using another beacon node to produce blocks and
-                            // submitting them here.
-                            if beacon_chain.head()?.beacon_block_root != block_root {
-                                warn!(
-                                    log,
-                                    "Block from validator is not head";
-                                    "desc" => "potential re-org",
-                                );
-
-                            }
-                        }
-
-                        Ok(())
-                    }
-                    Err(BlockError::BeaconChainError(e)) => {
-                        error!(
-                            log,
-                            "Error whilst processing block";
-                            "error" => format!("{:?}", e)
-                        );
-
-                        Err(ApiError::ServerError(format!(
-                            "Error while processing block: {:?}",
-                            e
-                        )))
-                    }
-                    Err(other) => {
-                        warn!(
-                            log,
-                            "Invalid block from local validator";
-                            "outcome" => format!("{:?}", other)
-                        );
-
-                        Err(ApiError::ProcessingError(format!(
-                            "The SignedBeaconBlock could not be processed and has not been published: {:?}",
-                            other
-                        )))
-                    }
-                }
+    serde_json::from_slice(&chunks)
+        .map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Unable to parse JSON into SignedBeaconBlock: {:?}",
+                e
+            ))
+        })
+        .and_then(move |block: SignedBeaconBlock<T::EthSpec>| {
+            process_and_publish_beacon_block(&beacon_chain, network_chan, block, &log)
         })
         .and_then(|_| response_builder?.body_no_ssz(&()))
 }
 
+/// Process a SignedBeaconBlock from a local validator and publish it to the network if it is
+/// valid.
+///
+/// Shared by the HTTP and gRPC APIs.
+pub(crate) fn process_and_publish_beacon_block<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    network_chan: NetworkChannel<T::EthSpec>,
+    block: SignedBeaconBlock<T::EthSpec>,
+    log: &Logger,
+) -> Result<(), ApiError> {
+    let slot = block.slot();
+    match beacon_chain.process_block(block.clone()) {
+        Ok(block_root) => {
+            // Block was processed, publish via gossipsub
+            info!(
+                log,
+                "Block from local validator";
+                "block_root" => format!("{}", block_root),
+                "block_slot" => slot,
+            );
+
+            publish_beacon_block_to_network::<T>(network_chan, block)?;
+
+            // Run the fork choice algorithm and enshrine a new canonical head, if
+            // found.
+            //
+            // The new head may or may not be the block we just received.
+            if let Err(e) = beacon_chain.fork_choice() {
+                error!(
+                    log,
+                    "Failed to find beacon chain head";
+                    "error" => format!("{:?}", e)
+                );
+            } else {
+                // In the best case, validators should produce blocks that become the
+                // head.
+                //
+                // Potential reasons this may not be the case:
+                //
+                // - A quick re-org between block produce and publish.
+                // - Excessive time between block produce and publish.
+                // - A validator is using another beacon node to produce blocks and
+                // submitting them here.
+                if beacon_chain.head()?.beacon_block_root != block_root {
+                    warn!(
+                        log,
+                        "Block from validator is not head";
+                        "desc" => "potential re-org",
+                    );
+                }
+            }
+
+            Ok(())
+        }
+        Err(BlockError::BeaconChainError(e)) => {
+            error!(
+                log,
+                "Error whilst processing block";
+                "error" => format!("{:?}", e)
+            );
+
+            Err(ApiError::ServerError(format!(
+                "Error while processing block: {:?}",
+                e
+            )))
+        }
+        Err(other) => {
+            warn!(
+                log,
+                "Invalid block from local validator";
+                "outcome" => format!("{:?}", other)
+            );
+
+            Err(ApiError::ProcessingError(format!(
+                "The SignedBeaconBlock could not be processed and has not been published: {:?}",
+                other
+            )))
+        }
+    }
+}
+
 /// HTTP Handler to produce a new Attestation from the current state, ready to be signed by a validator.
 pub fn get_new_attestation<T: BeaconChainTypes>(
     req: Request<Body>,
@@ -0,0 +1,164 @@
+//! Reports the inclusion of the attestations of validators on chain, so that the performance of a
+//! set of validators can be computed without replaying states.
+//!
+//! Epochs are only reported from the `EpochSummary` persisted when the following epoch was
+//! processed, which remains available after the states themselves have been pruned. States are
+//! never loaded or replayed to serve a request.
+
+use crate::helpers::*;
+use crate::response_builder::ResponseBuilder;
+use crate::{ApiError, ApiResult};
+use beacon_chain::epoch_summary::{
+    EpochSummary, PARTICIPATION_ACTIVE, PARTICIPATION_ATTESTER, PARTICIPATION_HEAD_ATTESTER,
+    PARTICIPATION_SLASHED, PARTICIPATION_TARGET_ATTESTER,
+};
+use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use hyper::{Body, Request};
+use rest_types::{EpochInclusion, GlobalInclusion, ValidatorInclusion, ValidatorInclusionRequest};
+use std::sync::Arc;
+use types::{Epoch, EthSpec};
+
+/// The maximum number of epochs which may be requested at once, as each epoch requires a summary
+/// to be read from the database.
+pub const MAX_INCLUSION_EPOCHS: u64 = 64;
+
+/// HTTP handler to return the `GlobalInclusion` of the epoch in the `path`, which must be of the
+/// form `/lighthouse/validator_inclusion/{epoch}/global`.
+pub fn get_global_inclusion<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let epoch = parse_epoch(
+        req.uri()
+            .path()
+            .trim_start_matches("/lighthouse/validator_inclusion/")
+            .trim_end_matches("/global"),
+    )?;
+
+    let summary = epoch_summary(&beacon_chain, epoch)?;
+
+    ResponseBuilder::new(&req)?.body(&global_inclusion(epoch, &summary))
+}
+
+/// HTTP handler which accepts a `ValidatorInclusionRequest` and returns an `EpochInclusion` for
+/// each epoch in the requested range.
+pub async fn post_validator_inclusion<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let body = req.into_body();
+    let chunks = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))?;
+
+    serde_json::from_slice::<ValidatorInclusionRequest>(&chunks)
+        .map_err(|e| {
+            ApiError::BadRequest(format!(
+                "Unable to parse JSON into ValidatorInclusionRequest: {:?}",
+                e
+            ))
+        })
+        .and_then(|request| {
+            if request.end_epoch < request.start_epoch {
+                return Err(ApiError::BadRequest(
+                    "The end epoch must not be prior to the start epoch".to_string(),
+                ));
+            }
+            if request.end_epoch.as_u64() - request.start_epoch.as_u64() >= MAX_INCLUSION_EPOCHS {
+                return Err(ApiError::BadRequest(format!(
+                    "At most {} epochs may be requested at once",
+                    MAX_INCLUSION_EPOCHS
+                )));
+            }
+
+            (request.start_epoch.as_u64()..=request.end_epoch.as_u64())
+                .map(Epoch::new)
+                .map(|epoch| {
+                    let summary = epoch_summary(&beacon_chain, epoch)?;
+                    let validators = request
+                        .validator_indices
+                        .iter()
+                        .filter_map(|&index| validator_inclusion(index, &summary))
+                        .collect();
+
+                    Ok(EpochInclusion {
+                        global: global_inclusion(epoch, &summary),
+                        validators,
+                    })
+                })
+                .collect::<Result<Vec<_>, ApiError>>()
+        })
+        .and_then(|inclusions| response_builder?.body_no_ssz(&inclusions))
+}
+
+/// Returns the stored summary of the epoch following `epoch`, which reports the participation in
+/// `epoch` once all of its attestations must have been included.
+///
+/// The summary is only stored once a block after the following epoch has been imported, and is
+/// not stored for epochs processed before summaries were introduced.
+fn epoch_summary<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    epoch: Epoch,
+) -> Result<EpochSummary, ApiError> {
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    let current_slot = beacon_chain.slot()?;
+    if epoch >= current_slot.epoch(slots_per_epoch) {
+        return Err(ApiError::BadRequest(format!(
+            "Epoch {} has not yet ended",
+            epoch
+        )));
+    }
+
+    // This is the last slot of the epoch following the given epoch, the state at which is
+    // summarized when the following epoch is processed.
+    let last_slot = (epoch + 2).start_slot(slots_per_epoch) - 1;
+    // The following epoch has only been processed on the canonical chain once there is a later
+    // block. This also ensures the state root is found without advancing the head state.
+    if last_slot >= beacon_chain.head_info()?.slot {
+        return Err(ApiError::NotFound(format!(
+            "The summary of epoch {} is not available until a block is imported after slot {}",
+            epoch, last_slot
+        )));
+    }
+
+    let state_root = state_root_at_slot(beacon_chain, last_slot, StateSkipConfig::WithStateRoots)?;
+    beacon_chain
+        .store
+        .get_item::<EpochSummary>(&state_root)?
+        .ok_or_else(|| ApiError::NotFound(format!("No summary of epoch {} is stored", epoch)))
+}
+
+/// Reports the participation in the attestations of `epoch`, from the `summary` of the following
+/// epoch.
+fn global_inclusion(epoch: Epoch, summary: &EpochSummary) -> GlobalInclusion {
+    GlobalInclusion {
+        epoch,
+        active_validators: summary.count_participants(PARTICIPATION_ACTIVE),
+        active_gwei: summary.previous_epoch_active_gwei,
+        attesting_validators: summary.count_participants(PARTICIPATION_ATTESTER),
+        attesting_gwei: summary.previous_epoch_attesting_gwei,
+        target_attesting_validators: summary.count_participants(PARTICIPATION_TARGET_ATTESTER),
+        target_attesting_gwei: summary.previous_epoch_target_attesting_gwei,
+        head_attesting_validators: summary.count_participants(PARTICIPATION_HEAD_ATTESTER),
+        head_attesting_gwei: summary.previous_epoch_head_attesting_gwei,
+    }
+}
+
+/// Reports the inclusion of the validator with `validator_index` from the `summary` of the
+/// following epoch, if the validator existed.
+fn validator_inclusion(validator_index: u64, summary: &EpochSummary) -> Option<ValidatorInclusion> {
+    let participation = summary.participation(validator_index as usize)?;
+    let is_set = |flag| participation & flag != 0;
+
+    Some(ValidatorInclusion {
+        validator_index,
+        is_slashed: is_set(PARTICIPATION_SLASHED),
+        is_active: is_set(PARTICIPATION_ACTIVE),
+        is_attester: is_set(PARTICIPATION_ATTESTER),
+        is_target_attester: is_set(PARTICIPATION_TARGET_ATTESTER),
+        is_head_attester: is_set(PARTICIPATION_HEAD_ATTESTER),
+        inclusion_delay: summary.inclusion_delay(validator_index as usize),
+    })
+}
@@ -4,16 +4,23 @@
 extern crate assert_matches;
 
 use beacon_chain::{BeaconChain, BeaconChainTypes, StateSkipConfig};
+use futures::StreamExt;
 use node_test_rig::{
     environment::{Environment, EnvironmentBuilder},
     testing_client_config, ClientConfig, ClientGenesis, LocalBeaconNode,
 };
 use remote_beacon_node::{
-    Committee, HeadBeaconBlock, PersistedOperationPool, PublishStatus, ValidatorResponse,
+    Committee, HeadBeaconBlock, PersistedOperationPool, PublishStatus, ValidatorQuery,
+    ValidatorQueryResponse, ValidatorResponse, ValidatorStatus,
+};
+use rest_api::grpc::proto::{
+    self as grpc, beacon_node_validator_client::BeaconNodeValidatorClient,
 };
 use rest_types::ValidatorDutyBytes;
+use ssz::{Decode, Encode};
 use std::convert::TryInto;
 use std::sync::Arc;
+use tokio_tungstenite::connect_async;
 use types::{
     test_utils::{
         build_double_vote_attester_slashing, build_proposer_slashing,
@@ -433,7 +440,7 @@ fn validator_block_post() {
             remote_node
                 .http
                 .validator()
-                .produce_block(slot, randao_reveal),
+                .produce_block(slot, randao_reveal, None),
         )
         .expect("should fetch block from http api");
 
@@ -497,6 +504,214 @@ fn validator_block_post() {
     );
 }
 
+#[test]
+fn grpc_validator_endpoints() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: 13_371_337,
+    };
+
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let grpc_addr = node
+        .client
+        .grpc_listen_addr()
+        .expect("should have grpc server");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let mut client = env
+        .runtime()
+        .block_on(BeaconNodeValidatorClient::connect(format!(
+            "http://{}",
+            grpc_addr
+        )))
+        .expect("should connect to grpc server");
+
+    // Duties should match those served by the HTTP API.
+    let epoch = Epoch::new(0);
+    let validators = beacon_chain
+        .head()
+        .expect("should get head")
+        .beacon_state
+        .validators
+        .iter()
+        .map(|v| (&v.pubkey).try_into().expect("pubkey should be valid"))
+        .collect::<Vec<PublicKey>>();
+
+    let http_duties = env
+        .runtime()
+        .block_on(remote_node.http.validator().get_duties(epoch, &validators))
+        .expect("should fetch duties from http api");
+    let grpc_duties = env
+        .runtime()
+        .block_on(
+            client.get_duties(grpc::DutiesRequest {
+                epoch: epoch.as_u64(),
+                pubkeys: validators
+                    .iter()
+                    .map(|pubkey| pubkey.as_ssz_bytes())
+                    .collect(),
+            }),
+        )
+        .expect("should fetch duties from grpc api")
+        .into_inner()
+        .duties;
+
+    assert_eq!(
+        grpc_duties,
+        http_duties
+            .into_iter()
+            .map(grpc::ValidatorDuty::from)
+            .collect::<Vec<_>>(),
+        "grpc duties should match http duties"
+    );
+
+    // A block produced over gRPC should become the head once signed and published.
+    let slot = Slot::new(1);
+    let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+
+    let block_bytes = env
+        .runtime()
+        .block_on(client.produce_block(grpc::ProduceBlockRequest {
+            slot: slot.as_u64(),
+            randao_reveal: randao_reveal.as_ssz_bytes(),
+            graffiti: vec![],
+        }))
+        .expect("should fetch block from grpc api")
+        .into_inner()
+        .block;
+    let block = BeaconBlock::from_ssz_bytes(&block_bytes).expect("block should decode");
+    assert_eq!(block.slot, slot, "block should be at the requested slot");
+
+    let invalid_graffiti =
+        env.runtime()
+            .block_on(client.produce_block(grpc::ProduceBlockRequest {
+                slot: slot.as_u64(),
+                randao_reveal: randao_reveal.as_ssz_bytes(),
+                graffiti: vec![42; 31],
+            }));
+    assert!(
+        invalid_graffiti.is_err(),
+        "graffiti that is not 32 bytes should be rejected"
+    );
+
+    let signed_block = sign_block(beacon_chain.clone(), block, spec);
+    let block_root = signed_block.canonical_root();
+
+    env.runtime()
+        .block_on(client.publish_block(grpc::PublishBlockRequest {
+            signed_block: signed_block.as_ssz_bytes(),
+        }))
+        .expect("should publish block over grpc");
+
+    assert_eq!(
+        beacon_chain
+            .head()
+            .expect("should get head")
+            .beacon_block_root,
+        block_root,
+        "the published block should become the head block"
+    );
+}
+
+#[test]
+fn websocket_event_stream() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: 13_371_337,
+    };
+
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let http_addr = node
+        .client
+        .http_listen_addr()
+        .expect("should have http server");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let invalid_topic = env.runtime().block_on(connect_async(format!(
+        "ws://{}/beacon/events/ws?topics=head,unknown",
+        http_addr
+    )));
+    assert!(invalid_topic.is_err(), "unknown topics should be rejected");
+
+    let (mut websocket, _) = env
+        .runtime()
+        .block_on(connect_async(format!(
+            "ws://{}/beacon/events/ws?topics=head,block",
+            http_addr
+        )))
+        .expect("should connect to event stream");
+
+    let slot = Slot::new(1);
+    let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+    let block = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .produce_block(slot, randao_reveal, None),
+        )
+        .expect("should fetch block from http api");
+    let signed_block = sign_block(beacon_chain.clone(), block, spec);
+    let block_root = signed_block.canonical_root();
+
+    env.runtime()
+        .block_on(remote_node.http.validator().publish_block(signed_block))
+        .expect("should publish block");
+
+    let mut events = (0..2)
+        .map(|_| {
+            let message = env
+                .runtime()
+                .block_on(websocket.next())
+                .expect("stream should not end")
+                .expect("should receive message");
+            let event: serde_json::Value =
+                serde_json::from_str(message.to_text().expect("message should be text"))
+                    .expect("message should be json");
+            event["event"]
+                .as_str()
+                .expect("event should have a name")
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    events.sort();
+
+    assert_eq!(
+        events,
+        vec!["beacon_block_imported", "beacon_head_changed"],
+        "should stream the imported block and the new head"
+    );
+    assert_eq!(
+        beacon_chain
+            .head()
+            .expect("should get head")
+            .beacon_block_root,
+        block_root,
+        "the published block should become the head block"
+    );
+}
+
 #[test]
 fn validator_block_get() {
     let mut env = build_env();
@@ -520,7 +735,7 @@ fn validator_block_get() {
             remote_node
                 .http
                 .validator()
-                .produce_block(slot, randao_reveal.clone()),
+                .produce_block(slot, randao_reveal.clone(), None),
         )
         .expect("should fetch block from http api");
 
@@ -528,7 +743,7 @@ fn validator_block_get() {
         .client
         .beacon_chain()
         .expect("client should have beacon chain")
-        .produce_block(randao_reveal, slot)
+        .produce_block(randao_reveal, slot, None)
         .expect("should produce block");
 
     assert_eq!(
@@ -625,6 +840,44 @@ fn beacon_block() {
     );
 }
 
+#[test]
+fn finalized_block_and_state_ssz() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let block = env
+        .runtime()
+        .block_on(remote_node.http.beacon().get_finalized_block_ssz())
+        .expect("should fetch finalized block from http api");
+
+    let state = env
+        .runtime()
+        .block_on(remote_node.http.beacon().get_finalized_state_ssz())
+        .expect("should fetch finalized state from http api");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let mut db_state = beacon_chain
+        .state_at_slot(Slot::new(0), StateSkipConfig::WithStateRoots)
+        .expect("should find state");
+    db_state.drop_all_caches();
+
+    assert_eq!(
+        block.canonical_root(),
+        beacon_chain.genesis_block_root,
+        "the finalized block prior to finalization should be the genesis block"
+    );
+    assert_eq!(
+        state, db_state,
+        "the finalized state prior to finalization should be the genesis state"
+    );
+}
+
 #[test]
 fn genesis_time() {
     let mut env = build_env();
@@ -883,6 +1136,141 @@ fn get_active_validators() {
         .for_each(|(response, validator)| compare_validator_response(state, response, validator));
 }
 
+#[test]
+fn query_validators() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+    let state = &chain.head().expect("should get head").beacon_state;
+
+    // Validators may be given by index or pubkey, and are returned in order of index, once each.
+    let query = ValidatorQuery {
+        statuses: vec![ValidatorStatus::Active],
+        pubkeys: vec![
+            state.validators[3].pubkey.clone(),
+            state.validators[0].pubkey.clone(),
+        ],
+        indices: vec![5, 2, 0, u64::max_value()],
+        limit: Some(2),
+        ..ValidatorQuery::default()
+    };
+
+    let first_page = env
+        .runtime()
+        .block_on(remote_node.http.beacon().query_validators(query.clone()))
+        .expect("should fetch from http api");
+
+    assert_eq!(first_page.total, 4, "total");
+    assert_eq!(first_page.next_offset, Some(2), "next offset");
+    for (response, &index) in first_page.validators.iter().zip(&[0, 2]) {
+        assert_eq!(response.validator_index, index as u64, "validator index");
+        assert_eq!(response.status, ValidatorStatus::Active, "status");
+        assert_eq!(response.validator, state.validators[index], "validator");
+        assert_eq!(response.balance, state.balances[index], "balance");
+    }
+
+    let second_page = env
+        .runtime()
+        .block_on(remote_node.http.beacon().query_validators(ValidatorQuery {
+            offset: 2,
+            ..query.clone()
+        }))
+        .expect("should fetch from http api");
+
+    assert_eq!(second_page.total, 4, "total");
+    assert_eq!(second_page.next_offset, None, "next offset");
+    assert_eq!(
+        second_page
+            .validators
+            .iter()
+            .map(|response| response.validator_index)
+            .collect::<Vec<_>>(),
+        vec![3, 5],
+        "validator indices"
+    );
+
+    // All validators in the test state are active.
+    let exited = env
+        .runtime()
+        .block_on(remote_node.http.beacon().query_validators(ValidatorQuery {
+            statuses: vec![ValidatorStatus::Exited, ValidatorStatus::Slashed],
+            ..ValidatorQuery::default()
+        }))
+        .expect("should fetch from http api");
+
+    assert_eq!(exited.total, 0, "total");
+    assert!(exited.validators.is_empty(), "validators");
+}
+
+#[test]
+fn query_validators_by_get() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let socket_addr = node
+        .client
+        .http_listen_addr()
+        .expect("A remote beacon node must have a http server");
+    let url = url::Url::parse(&format!(
+        "http://{}:{}/beacon/validators/query",
+        socket_addr.ip(),
+        socket_addr.port()
+    ))
+    .expect("should be valid endpoint");
+
+    let params = |offset: u64| {
+        vec![
+            ("status".to_string(), "active".to_string()),
+            ("index".to_string(), "5".to_string()),
+            ("index".to_string(), "2".to_string()),
+            ("index".to_string(), "0".to_string()),
+            ("offset".to_string(), offset.to_string()),
+            ("limit".to_string(), "2".to_string()),
+        ]
+    };
+
+    let first_page = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .json_get::<ValidatorQueryResponse>(url.clone(), params(0)),
+        )
+        .expect("should fetch from http api");
+
+    assert_eq!(first_page.total, 3, "total");
+    assert_eq!(first_page.next_offset, Some(2), "next offset");
+    assert_eq!(
+        first_page
+            .validators
+            .iter()
+            .map(|response| response.validator_index)
+            .collect::<Vec<_>>(),
+        vec![0, 2],
+        "validator indices"
+    );
+
+    // An offset past every matching validator gives an empty last page, however large.
+    let past_the_end = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .json_get::<ValidatorQueryResponse>(url, params(u64::max_value())),
+        )
+        .expect("should fetch from http api");
+
+    assert_eq!(past_the_end.total, 3, "total");
+    assert_eq!(past_the_end.next_offset, None, "next offset");
+    assert!(past_the_end.validators.is_empty(), "validators");
+}
+
 #[test]
 fn get_committees() {
     let mut env = build_env();
@@ -965,6 +1353,105 @@ fn get_operation_pool() {
     assert_eq!(result, expected, "result should be as expected");
 }
 
+#[test]
+fn validator_inclusion() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: 13_371_337,
+    };
+
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    // Importing the first block of an epoch stores the summary of the epoch two prior, so this
+    // stores the summaries of epochs 0 and 1.
+    for slot in 1..=E::slots_per_epoch() * 3 {
+        let slot = Slot::new(slot);
+        let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+        let (block, _state) = beacon_chain
+            .produce_block(randao_reveal, slot, None)
+            .expect("should produce block");
+        let signed_block = sign_block(beacon_chain.clone(), block, spec);
+        beacon_chain
+            .process_block(signed_block)
+            .expect("should import block");
+        beacon_chain.fork_choice().expect("should run fork choice");
+    }
+
+    let global = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .lighthouse()
+                .get_global_inclusion(Epoch::new(1)),
+        )
+        .expect("should get global inclusion");
+    assert_eq!(global.epoch, Epoch::new(1));
+    assert_eq!(global.active_validators, 8, "all validators are active");
+    assert_eq!(
+        global.attesting_validators, 0,
+        "no attestations were included"
+    );
+
+    let inclusions = env
+        .runtime()
+        .block_on(remote_node.http.lighthouse().get_validator_inclusion(
+            Epoch::new(0),
+            Epoch::new(1),
+            vec![0, 8],
+        ))
+        .expect("should get validator inclusion");
+    assert_eq!(inclusions.len(), 2, "should report each epoch");
+    assert_eq!(inclusions[1].global, global);
+    for inclusion in &inclusions {
+        assert_eq!(
+            inclusion.validators.len(),
+            1,
+            "should only report existing validators"
+        );
+        let validator = &inclusion.validators[0];
+        assert_eq!(validator.validator_index, 0);
+        assert!(validator.is_active);
+        assert!(!validator.is_attester);
+        assert_eq!(validator.inclusion_delay, None);
+    }
+
+    // Epoch 2 is only summarized once a block is imported after epoch 3 starts.
+    let result = env.runtime().block_on(
+        remote_node
+            .http
+            .lighthouse()
+            .get_global_inclusion(Epoch::new(2)),
+    );
+    assert_matches!(
+        result,
+        Err(remote_beacon_node::Error::DidNotSucceed { status, .. }) if status.as_u16() == 404
+    );
+
+    let result = env
+        .runtime()
+        .block_on(remote_node.http.lighthouse().get_validator_inclusion(
+            Epoch::new(1),
+            Epoch::new(0),
+            vec![],
+        ));
+    assert_matches!(
+        result,
+        Err(remote_beacon_node::Error::DidNotSucceed { status, .. }) if status.as_u16() == 400
+    );
+}
+
 fn compare_validator_response<T: EthSpec>(
     state: &BeaconState<T>,
     response: &ValidatorResponse,
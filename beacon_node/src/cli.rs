@@ -41,10 +41,20 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
             Arg::with_name("listen-address")
                 .long("listen-address")
                 .value_name("ADDRESS")
-                .help("The address lighthouse will listen for UDP and TCP connections.")
+                .help("The address lighthouse will listen for UDP and TCP connections. To listen \
+                on multiple interfaces, provide a comma-separated list of addresses; discovery \
+                listens on the first of these unless --discovery-address is set.")
                 .default_value("0.0.0.0")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("discovery-address")
+                .long("discovery-address")
+                .value_name("ADDRESS")
+                .help("The address that discovery will listen for UDP connections on. Defaults to \
+                the first --listen-address.")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("port")
                 .long("port")
@@ -61,10 +71,102 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true),
         )
         .arg(
-            Arg::with_name("max_peers")
+            Arg::with_name("max-peers")
                 .long("max-peers")
-                .help("The maximum number of peers.")
-                .default_value("50")
+                .help("The maximum number of peers. Defaults to the target of the \
+                       --network-load profile.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("network-load")
+                .long("network-load")
+                .value_name("PROFILE")
+                .help("The amount of bandwidth the node may use. Sets the target number of peers, \
+                       the gossipsub mesh size and how many blocks are served to syncing peers. \
+                       Use \"low\" for nodes with a constrained uplink.")
+                .possible_values(&["low", "normal", "high"])
+                .default_value("normal")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("proposer-only")
+                .long("proposer-only")
+                .help("Runs a lightweight node which keeps a small set of peers in order to \
+                       publish blocks and follow the head. Attestation subnets and aggregate \
+                       attestations are not subscribed to, so this node should not be used by \
+                       validators to produce attestations. Overrides --network-load.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("disable-inbound")
+                .long("disable-inbound")
+                .help("Runs a node which only makes outbound connections, e.g. behind a strict \
+                       firewall. The TCP port is not advertised in the ENR, peers which connect \
+                       to this node are disconnected and the target number of peers is raised \
+                       to compensate.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("disable-topics")
+                .long("disable-topics")
+                .value_name("TOPICS")
+                .help("A comma-separated list of gossip topics which are never subscribed to, \
+                       e.g. \"voluntary_exit,proposer_slashing\". Attestation subnets are \
+                       named beacon_attestation_N.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enable-topics")
+                .long("enable-topics")
+                .value_name("TOPICS")
+                .help("A comma-separated list of gossip topics which are always subscribed to, \
+                       across forks, e.g. \"beacon_attestation_0\".")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("block-serving-rate-limit")
+                .long("block-serving-rate-limit")
+                .value_name("MB_PER_SEC")
+                .help("The maximum rate at which blocks are served to syncing peers, in MB/s. \
+                       Requests beyond this rate receive a rate limited error, so that syncing \
+                       peers cannot starve the publishing of our own messages. Not limited by \
+                       default.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("slot-aware-scheduling")
+                .long("slot-aware-scheduling")
+                .help("Defers serving blocks to syncing peers and searching for new peers until \
+                       after the first third of each slot, so that block import and attestation \
+                       production meet their deadlines on machines with few cores.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("min-connection-age")
+                .long("min-connection-age")
+                .value_name("SECONDS")
+                .help("The minimum number of seconds a peer must be connected before it may be \
+                       disconnected to reduce the number of peers to --max-peers.")
+                .default_value("120")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gossip-reject-limit")
+                .long("gossip-reject-limit")
+                .value_name("COUNT")
+                .help("The number of gossip messages from a peer which may fail validation within \
+                       a minute before the peer is penalized. A peer which keeps exceeding the \
+                       limit is banned.")
+                .default_value("5")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("maximum-gossip-clock-disparity")
+                .long("maximum-gossip-clock-disparity")
+                .value_name("MILLISECONDS")
+                .help("The number of milliseconds by which the slot of a gossiped block or \
+                       attestation may disagree with our clock whilst still being accepted. \
+                       Overrides MAXIMUM_GOSSIP_CLOCK_DISPARITY from the specification.")
                 .takes_value(true),
         )
         .arg(
@@ -75,6 +177,16 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("One or more comma-delimited base64-encoded ENR's to bootstrap the p2p network.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("dns-discovery")
+                .long("dns-discovery")
+                .value_name("TREE-URL-LIST")
+                .help("One or more comma-delimited URLs of node lists published over DNS \
+                       (EIP-1459), of the form enrtree://<public key>@<domain>. The nodes are \
+                       used alongside the boot nodes, which helps when the boot nodes are \
+                       unreachable.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("enr-udp-port")
                 .long("enr-udp-port")
@@ -90,6 +202,16 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                     The --port flag is used if this is not set.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("enr-custom-fields")
+                .long("enr-custom-fields")
+                .value_name("KEY=HEXVALUE,...")
+                .help("A comma-separated list of custom fields to publish in the local ENR, e.g. \
+                       \"operator=0x6c68,region=0x6575\". Keys are at most 16 characters and \
+                       values at most 64 bytes, and the fields set by Lighthouse cannot be \
+                       overridden.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("enr-address")
                 .long("enr-address")
@@ -107,7 +229,8 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .short("e")
                 .long("enr-match")
                 .help("Sets the local ENR IP address and port to match those set for lighthouse. \
-                Specifically, the IP address will be the value of --listen-address and the UDP port will be --discovery-port.")
+                Specifically, the IP address will be the value of --discovery-address (or --listen-address) and the UDP port will be --discovery-port. \
+                If lighthouse also listens on an address of the other IP version, it is advertised as well.")
         )
         .arg(
             Arg::with_name("disable-enr-auto-update")
@@ -125,7 +248,64 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                        without an ENR.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("ban-list-file")
+                .long("ban-list-file")
+                .value_name("FILE")
+                .help("A file of banned PeerIds, IP addresses and CIDR subnets, one per line. The \
+                       file is loaded on startup and updated as peers are banned, along with the \
+                       time their ban expires. It is created if it does not exist.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("private-network")
+                .long("private-network")
+                .help("Only connect to peers in the --private-network-allowlist file, or which \
+                       know the secret in the --private-network-secret-file. At least one of \
+                       these must be given. This allows a private network to be run on the \
+                       public internet.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("private-network-allowlist")
+                .long("private-network-allowlist")
+                .value_name("FILE")
+                .help("A file of the hex-encoded node ids which may join the private network, one \
+                       per line.")
+                .requires("private-network")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("private-network-secret-file")
+                .long("private-network-secret-file")
+                .value_name("FILE")
+                .help("A file containing the shared secret of the private network. A proof that \
+                       this node knows the secret is added to its ENR, and peers whose ENR has a \
+                       valid proof are accepted.")
+                .requires("private-network")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rpc-faults")
+                .long("rpc-faults")
+                .value_name("FAULTS")
+                .help("Injects faults into the RPC responses sent to peers, for testing their \
+                       sync and peer scoring. A comma-separated list of drop=PROBABILITY, \
+                       latency_ms=MILLIS and corrupt=PROBABILITY. Requires Lighthouse to be \
+                       built with the network-testing feature.")
+                .hidden(true)
+                .takes_value(true),
+        )
         /* REST API related arguments */
+        .arg(
+            Arg::with_name("staking")
+                .long("staking")
+                .help("Standard option for a staking beacon node. Enables the HTTP API on \
+                       localhost and the eth1 service, using the standard ports. Equivalent to \
+                       `lighthouse bn --http --eth1`.")
+                .conflicts_with_all(&["dummy-eth1", "zero-ports"])
+                .takes_value(false),
+        )
         .arg(
             Arg::with_name("http")
                 .long("http")
@@ -148,6 +328,29 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("5052")
                 .takes_value(true),
         )
+        /* gRPC related arguments */
+        .arg(
+            Arg::with_name("grpc")
+                .long("grpc")
+                .help("Enable the gRPC server for the validator endpoints of the API. Disabled by default.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("grpc-address")
+                .long("grpc-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the gRPC server.")
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("grpc-port")
+                .long("grpc-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the gRPC server.")
+                .default_value("5054")
+                .takes_value(true),
+        )
         /* Websocket related arguments */
         .arg(
             Arg::with_name("ws")
@@ -196,12 +399,68 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Specifies the server for a web3 connection to the Eth1 chain. Also enables the --eth1 flag. Defaults to http://127.0.0.1:8545.")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("eth1-fallback-endpoints")
+                .long("eth1-fallback-endpoints")
+                .value_name("HTTP-ENDPOINTS")
+                .help("One or more comma-delimited servers to use, in order of preference, when \
+                       the --eth1-endpoint is unhealthy. Also enables the --eth1 flag.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-chain-id")
+                .long("eth1-chain-id")
+                .value_name("CHAIN_ID")
+                .help("The chain id of the eth1 network (e.g., 5 for Goerli). Eth1 endpoints \
+                       reporting a different chain id are never used.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("wss-checkpoint")
+                .long("wss-checkpoint")
+                .value_name("BLOCK_ROOT:EPOCH")
+                .help("A weak subjectivity checkpoint, e.g. from a trusted node or block \
+                       explorer. If the node finalizes a chain which does not include the \
+                       checkpoint, it halts block production and attestation until it is \
+                       restarted.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("trusted-peers")
+                .long("trusted-peers")
+                .value_name("PEER_IDS")
+                .help("One or more comma-delimited peer ids of trusted nodes. If more than half \
+                       of them report a finalized checkpoint which conflicts with our chain, the \
+                       node halts block production and attestation until it is restarted.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-deposit-snapshot")
+                .long("eth1-deposit-snapshot")
+                .value_name("FILE")
+                .help("Path to a JSON deposit tree snapshot (e.g., from the \
+                       /lighthouse/eth1/deposit_snapshot endpoint of a trusted node). The deposit \
+                       cache starts from the snapshot instead of downloading every deposit log. \
+                       Ignored if the database already contains an eth1 cache.")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("slots-per-restore-point")
                 .long("slots-per-restore-point")
                 .value_name("SLOT_COUNT")
                 .help("Specifies how often a freezer DB restore point should be stored. \
-                       DO NOT DECREASE AFTER INITIALIZATION. [default: 2048 (mainnet) or 64 (minimal)]")
+                       Cannot be increased after initialization. If decreased, the missing \
+                       restore points are reconstructed in the background. \
+                       [default: 2048 (mainnet) or 64 (minimal)]")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("state-reconstruction-rate")
+                .long("state-reconstruction-rate")
+                .value_name("RESTORE_POINTS")
+                .help("The maximum number of historic restore points to reconstruct each slot \
+                       after --slots-per-restore-point has been decreased. Each restore point \
+                       requires replaying the blocks since the previous one. [default: 2]")
                 .takes_value(true)
         )
         .arg(
@@ -220,4 +479,5 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .long("purge-db")
                 .help("If present, the chain database will be deleted. Use with caution.")
         )
+        .subcommand(crate::status::cli_app())
 }
@@ -2,7 +2,11 @@ use beacon_chain::builder::PUBKEY_CACHE_FILENAME;
 use clap::ArgMatches;
 use clap_utils::BAD_TESTNET_DIR_MESSAGE;
 use client::{config::DEFAULT_DATADIR, ClientConfig, ClientGenesis};
-use eth2_libp2p::{Enr, Multiaddr};
+use eth2_libp2p::{
+    discovery::{dns::EnrTreeUrl, parse_custom_enr_field},
+    types::GossipKind,
+    Enr, Multiaddr, NetworkConstants, PeerId,
+};
 use eth2_testnet_config::Eth2TestnetConfig;
 use slog::{crit, info, Logger};
 use ssz::Encode;
@@ -12,7 +16,7 @@ use std::io::prelude::*;
 use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
 use std::net::{TcpListener, UdpSocket};
 use std::path::PathBuf;
-use types::{ChainSpec, EthSpec};
+use types::{ChainSpec, Checkpoint, DepositTreeSnapshot, EthSpec, Hash256};
 
 pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const BEACON_NODE_DIR: &str = "beacon";
@@ -95,19 +99,102 @@ pub fn get_config<E: EthSpec>(
         client_config.network.network_dir = client_config.data_dir.join(NETWORK_DIR);
     };
 
+    // Use the networking constants of the spec, which may differ between testnets.
+    client_config
+        .network
+        .set_constants(NetworkConstants::from_spec(spec));
+
     if let Some(listen_address_str) = cli_args.value_of("listen-address") {
-        let listen_address = listen_address_str
+        let mut listen_addresses = listen_address_str
+            .split(',')
+            .map(|address| {
+                address
+                    .trim()
+                    .parse::<IpAddr>()
+                    .map_err(|_| format!("Invalid listen address: {:?}", address))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        if let Some(listen_address) = listen_addresses.next() {
+            client_config.network.listen_address = listen_address;
+        }
+        client_config.network.additional_listen_addresses = listen_addresses.collect();
+    }
+
+    if let Some(discovery_address_str) = cli_args.value_of("discovery-address") {
+        let discovery_address = discovery_address_str
             .parse()
-            .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
-        client_config.network.listen_address = listen_address;
+            .map_err(|_| format!("Invalid discovery address: {:?}", discovery_address_str))?;
+        client_config.network.discovery_address = Some(discovery_address);
+    }
+
+    if let Some(network_load_str) = cli_args.value_of("network-load") {
+        client_config
+            .network
+            .set_network_load(network_load_str.parse()?);
+    }
+
+    if let Some(topics_str) = cli_args.value_of("disable-topics") {
+        client_config.network.disabled_topics = parse_topics(topics_str)?;
+    }
+
+    if let Some(topics_str) = cli_args.value_of("enable-topics") {
+        client_config.network.forced_topics = parse_topics(topics_str)?;
+    }
+
+    if let Some(topic) = client_config
+        .network
+        .forced_topics
+        .iter()
+        .find(|topic| client_config.network.disabled_topics.contains(topic))
+    {
+        return Err(format!(
+            "Topic {} cannot be both enabled and disabled",
+            topic
+        ));
+    }
+
+    if let Some(rate_limit_str) = cli_args.value_of("block-serving-rate-limit") {
+        let rate_limit = rate_limit_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid block serving rate limit: {}", rate_limit_str))?;
+        if rate_limit == 0 {
+            return Err("The block serving rate limit must be at least 1 MB/s".to_string());
+        }
+        client_config.network.block_serving_rate_limit = Some(rate_limit);
     }
 
+    client_config.network.slot_aware_scheduling = cli_args.is_present("slot-aware-scheduling");
+
+    // Overrides the target set by the `--network-load` profile, if given.
     if let Some(max_peers_str) = cli_args.value_of("max-peers") {
         client_config.network.max_peers = max_peers_str
             .parse::<usize>()
             .map_err(|_| format!("Invalid number of max peers: {}", max_peers_str))?;
     }
 
+    // Must be applied after `--max-peers`, which would otherwise replace the smaller target.
+    if cli_args.is_present("proposer-only") {
+        client_config.network.set_proposer_only();
+    }
+
+    // Raises the target number of peers, so must be applied once the target is known.
+    if cli_args.is_present("disable-inbound") {
+        client_config.network.set_disable_inbound();
+    }
+
+    if let Some(min_age_str) = cli_args.value_of("min-connection-age") {
+        client_config.network.min_connection_age_secs = min_age_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid minimum connection age: {}", min_age_str))?;
+    }
+
+    if let Some(limit_str) = cli_args.value_of("gossip-reject-limit") {
+        client_config.network.gossip_reject_limit = limit_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid gossip reject limit: {}", limit_str))?;
+    }
+
     if let Some(port_str) = cli_args.value_of("port") {
         let port = port_str
             .parse::<u16>()
@@ -130,6 +217,17 @@ pub fn get_config<E: EthSpec>(
             .collect::<Result<Vec<Enr>, _>>()?;
     }
 
+    if let Some(dns_discovery_str) = cli_args.value_of("dns-discovery") {
+        client_config.network.dns_discovery = dns_discovery_str
+            .split(',')
+            .map(|url| {
+                url.parse::<EnrTreeUrl>()
+                    .map(|_| url.to_string())
+                    .map_err(|e| format!("Invalid ENR tree URL {}: {}", url, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
     if let Some(libp2p_addresses_str) = cli_args.value_of("libp2p-addresses") {
         client_config.network.libp2p_nodes = libp2p_addresses_str
             .split(',')
@@ -141,6 +239,35 @@ pub fn get_config<E: EthSpec>(
             .collect::<Result<Vec<Multiaddr>, _>>()?;
     }
 
+    if let Some(ban_list_file) = cli_args.value_of("ban-list-file") {
+        client_config.network.ban_list_file = Some(PathBuf::from(ban_list_file));
+    }
+
+    if cli_args.is_present("private-network") {
+        client_config.network.private_network = true;
+        client_config.network.private_network_allowlist = cli_args
+            .value_of("private-network-allowlist")
+            .map(PathBuf::from);
+        client_config.network.private_network_secret = cli_args
+            .value_of("private-network-secret-file")
+            .map(|file| {
+                fs::read_to_string(file)
+                    .map(|secret| secret.trim().to_string())
+                    .map_err(|e| format!("Unable to read private network secret file: {:?}", e))
+            })
+            .transpose()?;
+
+        if client_config.network.private_network_allowlist.is_none()
+            && client_config.network.private_network_secret.is_none()
+        {
+            return Err(
+                "--private-network requires --private-network-allowlist and/or \
+                        --private-network-secret-file"
+                    .to_string(),
+            );
+        }
+    }
+
     if let Some(enr_udp_port_str) = cli_args.value_of("enr-udp-port") {
         client_config.network.enr_udp_port = Some(
             enr_udp_port_str
@@ -157,15 +284,21 @@ pub fn get_config<E: EthSpec>(
         );
     }
 
+    if let Some(enr_custom_fields_str) = cli_args.value_of("enr-custom-fields") {
+        client_config.network.enr_custom_fields = enr_custom_fields_str
+            .split(',')
+            .map(parse_custom_enr_field)
+            .collect::<Result<_, _>>()?;
+    }
+
     if cli_args.is_present("enr-match") {
-        // set the enr address to localhost if the address is 0.0.0.0
-        if client_config.network.listen_address
-            == "0.0.0.0".parse::<IpAddr>().expect("valid ip addr")
-        {
+        // set the enr address to localhost if discovery listens on all interfaces
+        let discovery_address = client_config.network.discovery_listen_address();
+        if discovery_address.is_unspecified() {
             client_config.network.enr_address =
                 Some("127.0.0.1".parse::<IpAddr>().expect("valid ip addr"));
         } else {
-            client_config.network.enr_address = Some(client_config.network.listen_address);
+            client_config.network.enr_address = Some(discovery_address);
         }
         client_config.network.enr_udp_port = Some(client_config.network.discovery_port);
     }
@@ -208,11 +341,25 @@ pub fn get_config<E: EthSpec>(
         client_config.network.discv5_config.enr_update = false;
     }
 
+    if let Some(faults) = cli_args.value_of("rpc-faults") {
+        #[cfg(feature = "network-testing")]
+        {
+            client_config.network.rpc_faults = faults.parse()?;
+        }
+        #[cfg(not(feature = "network-testing"))]
+        {
+            return Err(format!(
+                "--rpc-faults {} requires Lighthouse to be built with the network-testing feature",
+                faults
+            ));
+        }
+    }
+
     /*
      * Http server
      */
 
-    if cli_args.is_present("http") {
+    if cli_args.is_present("http") || cli_args.is_present("staking") {
         client_config.rest_api.enabled = true;
     }
 
@@ -222,12 +369,41 @@ pub fn get_config<E: EthSpec>(
             .map_err(|_| "http-address is not a valid IPv4 address.")?;
     }
 
+    // The validator client expects the API on localhost, and it should not be exposed publicly
+    // by accident.
+    if cli_args.is_present("staking") && !client_config.rest_api.listen_address.is_loopback() {
+        return Err(format!(
+            "--staking serves the HTTP API on localhost, but --http-address is {}",
+            client_config.rest_api.listen_address
+        ));
+    }
+
     if let Some(port) = cli_args.value_of("http-port") {
         client_config.rest_api.port = port
             .parse::<u16>()
             .map_err(|_| "http-port is not a valid u16.")?;
     }
 
+    /*
+     * gRPC server
+     */
+
+    if cli_args.is_present("grpc") {
+        client_config.grpc.enabled = true;
+    }
+
+    if let Some(address) = cli_args.value_of("grpc-address") {
+        client_config.grpc.listen_address = address
+            .parse::<Ipv4Addr>()
+            .map_err(|_| "grpc-address is not a valid IPv4 address.")?;
+    }
+
+    if let Some(port) = cli_args.value_of("grpc-port") {
+        client_config.grpc.port = port
+            .parse::<u16>()
+            .map_err(|_| "grpc-port is not a valid u16.")?;
+    }
+
     /*
      * Websocket server
      */
@@ -262,7 +438,7 @@ pub fn get_config<E: EthSpec>(
     // When present, attempt to sync to an eth1 node.
     //
     // Required for block production.
-    if cli_args.is_present("eth1") {
+    if cli_args.is_present("eth1") || cli_args.is_present("staking") {
         client_config.sync_eth1_chain = true;
     }
 
@@ -272,6 +448,48 @@ pub fn get_config<E: EthSpec>(
         client_config.eth1.endpoint = val.to_string();
     }
 
+    if let Some(val) = cli_args.value_of("eth1-fallback-endpoints") {
+        client_config.sync_eth1_chain = true;
+        client_config.eth1.fallback_endpoints = val
+            .split(',')
+            .map(|endpoint| endpoint.trim().to_string())
+            .filter(|endpoint| !endpoint.is_empty())
+            .collect();
+    }
+
+    client_config.eth1.chain_id = clap_utils::parse_optional(cli_args, "eth1-chain-id")?;
+
+    if let Some(path) = cli_args.value_of("eth1-deposit-snapshot") {
+        let file = File::open(path)
+            .map_err(|e| format!("Unable to open deposit snapshot {}: {:?}", path, e))?;
+        let snapshot: DepositTreeSnapshot = serde_json::from_reader(file)
+            .map_err(|e| format!("Unable to parse deposit snapshot {}: {:?}", path, e))?;
+
+        if !snapshot.is_valid() {
+            return Err(format!(
+                "Deposit snapshot {} does not match its deposit root",
+                path
+            ));
+        }
+        client_config.eth1.deposit_snapshot = Some(snapshot);
+    }
+
+    if let Some(checkpoint) = cli_args.value_of("wss-checkpoint") {
+        client_config.weak_subjectivity_checkpoint = Some(parse_checkpoint(checkpoint)?);
+    }
+
+    if let Some(trusted_peers_str) = cli_args.value_of("trusted-peers") {
+        client_config.trusted_peers = trusted_peers_str
+            .split(',')
+            .map(|peer_id| {
+                peer_id
+                    .parse::<PeerId>()
+                    .map(|peer_id| peer_id.to_string())
+                    .map_err(|_| format!("Invalid peer id: {}", peer_id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
     if let Some(freezer_dir) = cli_args.value_of("freezer-dir") {
         client_config.freezer_db_path = Some(PathBuf::from(freezer_dir));
     }
@@ -287,6 +505,12 @@ pub fn get_config<E: EthSpec>(
         );
     }
 
+    if let Some(rate) = cli_args.value_of("state-reconstruction-rate") {
+        client_config.store.state_reconstruction_rate = rate
+            .parse()
+            .map_err(|_| "state-reconstruction-rate is not a valid integer".to_string())?;
+    }
+
     if let Some(block_cache_size) = cli_args.value_of("block-cache-size") {
         client_config.store.block_cache_size = block_cache_size
             .parse()
@@ -319,6 +543,7 @@ pub fn get_config<E: EthSpec>(
         client_config.network.discovery_port =
             unused_port("udp").map_err(|e| format!("Failed to get port for discovery: {}", e))?;
         client_config.rest_api.port = 0;
+        client_config.grpc.port = 0;
         client_config.websocket_server.port = 0;
     }
 
@@ -436,6 +661,35 @@ pub fn unused_port(transport: &str) -> Result<u16, String> {
     Ok(local_addr.port())
 }
 
+/// Parses a comma-separated list of gossip topic names, e.g. `beacon_block,voluntary_exit`.
+fn parse_topics(topics: &str) -> Result<Vec<GossipKind>, String> {
+    topics
+        .split(',')
+        .map(|topic| topic.trim().parse::<GossipKind>())
+        .collect()
+}
+
+/// Parses a checkpoint of the form `BLOCK_ROOT:EPOCH`, e.g. `0x1234..abcd:100`.
+fn parse_checkpoint(checkpoint: &str) -> Result<Checkpoint, String> {
+    let invalid = || {
+        format!(
+            "Invalid checkpoint {}, expected BLOCK_ROOT:EPOCH",
+            checkpoint
+        )
+    };
+    let mut parts = checkpoint.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(root), Some(epoch), None) => Ok(Checkpoint {
+            root: root
+                .trim_start_matches("0x")
+                .parse::<Hash256>()
+                .map_err(|_| invalid())?,
+            epoch: epoch.parse::<u64>().map_err(|_| invalid())?.into(),
+        }),
+        _ => Err(invalid()),
+    }
+}
+
 /// Write a configuration to file.
 pub fn write_to_file<T>(path: PathBuf, config: &T) -> Result<(), String>
 where
@@ -0,0 +1,343 @@
+//! Reads and writes "era" style archive files of signed beacon blocks and states.
+//!
+//! An archive file is a sequence of records, each of which is an 8-byte header followed by
+//! `length` bytes of data:
+//!
+//! ```ignore
+//! | type: [u8; 2] | length: u32 (little-endian) | reserved: [u8; 2] | data: [u8; length] |
+//! ```
+//!
+//! The first record must be a `VERSION` record with no data. Blocks and states are stored as
+//! `COMPRESSED_SIGNED_BEACON_BLOCK` and `COMPRESSED_BEACON_STATE` records holding snappy-framed
+//! SSZ. Each group of blocks or states is followed by a `SLOT_INDEX` record:
+//!
+//! ```ignore
+//! | start_slot: u64 | offsets: [i64; count] | count: u64 |
+//! ```
+//!
+//! The offset of each slot is the position of its record relative to the start of the index
+//! record, or zero if the slot has no record. All integers are little-endian.
+//!
+//! Only blocks are imported, all other record types are skipped.
+
+use snap::read::FrameDecoder;
+use snap::write::FrameEncoder;
+use ssz::{Decode, Encode};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use types::{BeaconState, EthSpec, SignedBeaconBlock, Slot};
+
+/// The file extension of archive files.
+pub const ARCHIVE_EXTENSION: &str = "era";
+
+pub const VERSION: [u8; 2] = [0x65, 0x32];
+pub const COMPRESSED_SIGNED_BEACON_BLOCK: [u8; 2] = [0x01, 0x00];
+pub const COMPRESSED_BEACON_STATE: [u8; 2] = [0x02, 0x00];
+pub const SLOT_INDEX: [u8; 2] = [0x69, 0x32];
+
+const HEADER_LEN: usize = 8;
+
+/// Returns the paths of all archive files in `dir`, ordered by file name.
+///
+/// Archive files are expected to be named such that ordering them by name orders them by slot.
+pub fn archive_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = fs::read_dir(dir)
+        .map_err(|e| format!("Unable to read archive dir {:?}: {}", dir, e))?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .map_err(|e| format!("Unable to read archive dir {:?}: {}", dir, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .map_or(false, |ext| ext == ARCHIVE_EXTENSION)
+        })
+        .collect::<Vec<_>>();
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Returns the name of the archive file whose first slot is `start_slot`.
+///
+/// The slot is zero-padded so that ordering files by name orders them by slot.
+pub fn archive_file_name(start_slot: Slot) -> String {
+    format!("{:020}.{}", start_slot.as_u64(), ARCHIVE_EXTENSION)
+}
+
+/// Encodes an archive of the `slot_count` slots from `start_slot`, containing the given `blocks`
+/// and an optional `state`.
+///
+/// The `blocks` must be within the slot range and ordered by slot.
+pub fn encode_archive<E: EthSpec>(
+    start_slot: Slot,
+    slot_count: u64,
+    blocks: &[SignedBeaconBlock<E>],
+    state: Option<&BeaconState<E>>,
+) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    write_record(&mut bytes, VERSION, &[]);
+
+    let mut block_offsets = vec![None; slot_count as usize];
+    for block in blocks {
+        let index = block
+            .slot()
+            .as_u64()
+            .checked_sub(start_slot.as_u64())
+            .filter(|index| *index < slot_count)
+            .ok_or_else(|| format!("Block at slot {} is outside the archive", block.slot()))?;
+        block_offsets[index as usize] = Some(bytes.len());
+        write_record(
+            &mut bytes,
+            COMPRESSED_SIGNED_BEACON_BLOCK,
+            &compress(&block.as_ssz_bytes())?,
+        );
+    }
+    write_slot_index(&mut bytes, start_slot, &block_offsets);
+
+    if let Some(state) = state {
+        let offset = bytes.len();
+        write_record(
+            &mut bytes,
+            COMPRESSED_BEACON_STATE,
+            &compress(&state.as_ssz_bytes())?,
+        );
+        write_slot_index(&mut bytes, state.slot, &[Some(offset)]);
+    }
+
+    Ok(bytes)
+}
+
+fn write_record(bytes: &mut Vec<u8>, record_type: [u8; 2], data: &[u8]) {
+    bytes.extend_from_slice(&record_type);
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0, 0]);
+    bytes.extend_from_slice(data);
+}
+
+/// Writes a `SLOT_INDEX` record, where `positions` are the positions of the records of
+/// consecutive slots from `start_slot` in `bytes`.
+fn write_slot_index(bytes: &mut Vec<u8>, start_slot: Slot, positions: &[Option<usize>]) {
+    let index_position = bytes.len() as i64;
+
+    let mut data = start_slot.as_u64().to_le_bytes().to_vec();
+    for position in positions {
+        let offset = position.map_or(0, |position| position as i64 - index_position);
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(&(positions.len() as u64).to_le_bytes());
+
+    write_record(bytes, SLOT_INDEX, &data);
+}
+
+fn compress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = FrameEncoder::new(vec![]);
+    encoder
+        .write_all(bytes)
+        .map_err(|e| format!("Unable to compress: {}", e))?;
+    encoder
+        .into_inner()
+        .map_err(|e| format!("Unable to compress: {}", e))
+}
+
+/// Reads all the blocks in the archive file at `path`.
+pub fn read_archive_file<E: EthSpec>(path: &Path) -> Result<Vec<SignedBeaconBlock<E>>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+    decode_blocks(&bytes).map_err(|e| format!("Invalid archive file {:?}: {}", path, e))
+}
+
+/// Decodes the blocks in the given archive, ensuring that their slots are strictly increasing.
+pub fn decode_blocks<E: EthSpec>(bytes: &[u8]) -> Result<Vec<SignedBeaconBlock<E>>, String> {
+    let mut records = Records { bytes };
+
+    match records.next().transpose()? {
+        Some((VERSION, _)) => (),
+        Some((record_type, _)) => {
+            return Err(format!(
+                "first record must be a version record, not {:?}",
+                record_type
+            ))
+        }
+        None => return Err("empty archive".into()),
+    }
+
+    let mut blocks: Vec<SignedBeaconBlock<E>> = vec![];
+
+    for record in records {
+        let (record_type, data) = record?;
+
+        if record_type != COMPRESSED_SIGNED_BEACON_BLOCK {
+            continue;
+        }
+
+        let mut ssz_bytes = vec![];
+        FrameDecoder::new(data)
+            .read_to_end(&mut ssz_bytes)
+            .map_err(|e| format!("unable to decompress block: {}", e))?;
+        let block = SignedBeaconBlock::from_ssz_bytes(&ssz_bytes)
+            .map_err(|e| format!("unable to decode block: {:?}", e))?;
+
+        if let Some(previous) = blocks.last() {
+            if block.slot() <= previous.slot() {
+                return Err(format!(
+                    "block at slot {} follows block at slot {}",
+                    block.slot(),
+                    previous.slot()
+                ));
+            }
+        }
+
+        blocks.push(block);
+    }
+
+    Ok(blocks)
+}
+
+/// Iterates the `(type, data)` records of an archive.
+struct Records<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<([u8; 2], &'a [u8]), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+
+        if self.bytes.len() < HEADER_LEN {
+            self.bytes = &[];
+            return Some(Err("truncated record header".into()));
+        }
+
+        let record_type = [self.bytes[0], self.bytes[1]];
+        let mut length = [0; 4];
+        length.copy_from_slice(&self.bytes[2..6]);
+        let length = u32::from_le_bytes(length) as usize;
+
+        let rest = &self.bytes[HEADER_LEN..];
+        if rest.len() < length {
+            self.bytes = &[];
+            return Some(Err(format!(
+                "record of length {} exceeds the remaining {} bytes",
+                length,
+                rest.len()
+            )));
+        }
+
+        self.bytes = &rest[length..];
+        Some(Ok((record_type, &rest[..length])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{BeaconBlock, MinimalEthSpec, Signature};
+
+    type E = MinimalEthSpec;
+
+    fn record(record_type: [u8; 2], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_record(&mut bytes, record_type, data);
+        bytes
+    }
+
+    fn block(slot: u64) -> SignedBeaconBlock<E> {
+        let mut block = BeaconBlock::empty(&E::default_spec());
+        block.slot = Slot::new(slot);
+        SignedBeaconBlock {
+            message: block,
+            signature: Signature::empty_signature(),
+        }
+    }
+
+    fn block_record(slot: u64) -> Vec<u8> {
+        record(
+            COMPRESSED_SIGNED_BEACON_BLOCK,
+            &compress(&block(slot).as_ssz_bytes()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn decodes_blocks_and_skips_other_records() {
+        let mut bytes = record(VERSION, &[]);
+        bytes.extend(block_record(1));
+        bytes.extend(record([0x02, 0x00], &[42; 10]));
+        bytes.extend(block_record(3));
+
+        let slots = decode_blocks::<E>(&bytes)
+            .unwrap()
+            .iter()
+            .map(|block| block.slot())
+            .collect::<Vec<_>>();
+        assert_eq!(slots, vec![Slot::new(1), Slot::new(3)]);
+    }
+
+    #[test]
+    fn rejects_invalid_archives() {
+        assert!(decode_blocks::<E>(&[]).is_err());
+        assert!(decode_blocks::<E>(&block_record(1)).is_err());
+
+        let mut unordered = record(VERSION, &[]);
+        unordered.extend(block_record(2));
+        unordered.extend(block_record(2));
+        assert!(decode_blocks::<E>(&unordered).is_err());
+
+        let mut truncated = record(VERSION, &[]);
+        truncated.extend(block_record(1));
+        truncated.pop();
+        assert!(decode_blocks::<E>(&truncated).is_err());
+    }
+
+    #[test]
+    fn encoded_archives_are_indexed_and_decodable() {
+        let blocks = vec![block(9), block(10), block(12)];
+        let bytes = encode_archive::<E>(Slot::new(8), 8, &blocks, None).unwrap();
+
+        assert_eq!(decode_blocks::<E>(&bytes).unwrap(), blocks);
+
+        let index_len = HEADER_LEN + 8 * (8 + 2);
+        let index_position = bytes.len() - index_len;
+        let (record_type, index) = Records {
+            bytes: &bytes[index_position..],
+        }
+        .next()
+        .unwrap()
+        .unwrap();
+        assert_eq!(record_type, SLOT_INDEX);
+
+        let read_u64 = |i: usize| {
+            let mut int = [0; 8];
+            int.copy_from_slice(&index[i * 8..(i + 1) * 8]);
+            u64::from_le_bytes(int)
+        };
+        assert_eq!(read_u64(0), 8);
+        assert_eq!(read_u64(9), 8);
+
+        let block_slots = (0..8)
+            .filter(|i| read_u64(1 + i) != 0)
+            .map(|i| {
+                let position = (index_position as i64 + read_u64(1 + i) as i64) as usize;
+                let (record_type, _) = Records {
+                    bytes: &bytes[position..],
+                }
+                .next()
+                .unwrap()
+                .unwrap();
+                assert_eq!(record_type, COMPRESSED_SIGNED_BEACON_BLOCK);
+                8 + i
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(block_slots, vec![9, 10, 12]);
+
+        assert!(encode_archive::<E>(Slot::new(10), 8, &blocks, None).is_err());
+    }
+}
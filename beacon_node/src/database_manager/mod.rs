@@ -0,0 +1,675 @@
+//! Offline operations on the beacon node database.
+
+mod archive;
+
+use crate::config::get_config;
+use crate::{ClientBuilder, ClientConfig};
+use beacon_chain::{
+    builder::Witness, eth1_chain::CachingEth1Backend, migrate::BlockingMigrator,
+    slot_clock::SystemTimeSlotClock, store::LevelDB, BeaconChain, BeaconChainTypes,
+    ChainSegmentResult, StateSkipConfig,
+};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use environment::{Environment, RuntimeContext};
+use slog::{info, Logger};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use types::{EthSpec, Hash256, SignedBeaconBlock, Slot};
+use websocket_server::{Config as WebSocketConfig, WebSocketSender};
+
+pub const CMD: &str = "db";
+pub const IMPORT_ARCHIVE_CMD: &str = "import-archive";
+pub const EXPORT_ARCHIVE_CMD: &str = "export-archive";
+pub const DIR_ARG: &str = "dir";
+pub const START_SLOT_FLAG: &str = "start-slot";
+pub const END_SLOT_FLAG: &str = "end-slot";
+
+/// The number of slots in each exported archive file, which is also the distance between the
+/// states written to the archive.
+///
+/// Files are aligned to multiples of this value so that complete files from separate exports are
+/// identical.
+pub const SLOTS_PER_ARCHIVE_FILE: u64 = 8192;
+
+/// The `BeaconChainTypes` of the chain that is built to import and export archives.
+///
+/// Finalized states are migrated to the freezer DB as part of fork choice so that the freezer DB
+/// is complete when the import finishes.
+type ImportWitness<E> = Witness<
+    BlockingMigrator<E, LevelDB<E>, LevelDB<E>>,
+    SystemTimeSlotClock,
+    CachingEth1Backend<E>,
+    E,
+    WebSocketSender<E>,
+    LevelDB<E>,
+    LevelDB<E>,
+>;
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about("Offline operations on the beacon node database. The beacon node must be stopped.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name(IMPORT_ARCHIVE_CMD)
+                .about(
+                    "Imports historical blocks from the era archive files in a directory instead \
+                    of syncing them from the network. Each block is verified against the chain \
+                    (blocks which are already known must be canonical) and finalized states are \
+                    stored in the freezer DB.",
+                )
+                .arg(
+                    Arg::with_name(DIR_ARG)
+                        .value_name("DIR")
+                        .help(
+                            "The directory containing the .era archive files. Files are imported \
+                            in the order of their names.",
+                        )
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(EXPORT_ARCHIVE_CMD)
+                .about(
+                    "Exports the canonical blocks in a range of slots to era archive files in a \
+                    directory. Each file covers an aligned range of slots and includes the state \
+                    at its last slot. The files can be imported with the import-archive command.",
+                )
+                .arg(
+                    Arg::with_name(DIR_ARG)
+                        .value_name("DIR")
+                        .help("The directory in which to write the .era archive files.")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name(START_SLOT_FLAG)
+                        .long(START_SLOT_FLAG)
+                        .value_name("SLOT")
+                        .help("The first slot to export.")
+                        .default_value("0")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name(END_SLOT_FLAG)
+                        .long(END_SLOT_FLAG)
+                        .value_name("SLOT")
+                        .help("The last slot to export. Defaults to the slot of the head block.")
+                        .takes_value(true),
+                ),
+        )
+}
+
+pub fn run<T: EthSpec>(matches: &ArgMatches, env: Environment<T>) -> Result<(), String> {
+    match matches.subcommand() {
+        (IMPORT_ARCHIVE_CMD, Some(matches)) => import_archive(matches, env),
+        (EXPORT_ARCHIVE_CMD, Some(matches)) => export_archive(matches, env),
+        (unknown, _) => Err(format!(
+            "{} is not a valid {} command. See --help.",
+            unknown, CMD
+        )),
+    }
+}
+
+fn import_archive<T: EthSpec>(matches: &ArgMatches, mut env: Environment<T>) -> Result<(), String> {
+    let dir: PathBuf = clap_utils::parse_required(matches, DIR_ARG)?;
+    let files = archive::archive_files(&dir)?;
+    if files.is_empty() {
+        return Err(format!(
+            "No .{} files in {:?}",
+            archive::ARCHIVE_EXTENSION,
+            dir
+        ));
+    }
+
+    let context = env.core_context();
+    let log = context.log().clone();
+    let client_config = get_config::<T>(
+        matches,
+        &context.eth2_config.spec_constants,
+        &context.eth2_config().spec,
+        log.clone(),
+    )?;
+
+    let client = env
+        .runtime()
+        .block_on(build_client(context, client_config))?;
+    let chain = client
+        .beacon_chain()
+        .ok_or_else(|| "Client has no beacon chain".to_string())?;
+
+    let imported_blocks = import_archive_files(&chain, &files, &log)?;
+
+    info!(
+        log,
+        "Archive import complete";
+        "imported_blocks" => imported_blocks,
+    );
+
+    Ok(())
+}
+
+/// Imports the blocks in the archive `files`, returning the number of blocks imported.
+///
+/// Fails if a block conflicts with the chain or if finalized states could not be migrated to the
+/// freezer DB.
+fn import_archive_files<T>(
+    chain: &BeaconChain<T>,
+    files: &[PathBuf],
+    log: &Logger,
+) -> Result<usize, String>
+where
+    T: BeaconChainTypes<StoreMigrator = BlockingMigrator<T::EthSpec, T::HotStore, T::ColdStore>>,
+{
+    let initial_head = chain
+        .head_info()
+        .map_err(|e| format!("Unable to read head: {:?}", e))?;
+    let canonical_roots = canonical_block_roots(chain)?;
+    let mut imported_blocks = 0;
+
+    for path in files {
+        let blocks = archive::read_archive_file::<T::EthSpec>(path)?;
+
+        // Blocks that are not newer than the initial head must already be in the chain.
+        let split = blocks
+            .iter()
+            .position(|block| block.slot() > initial_head.slot)
+            .unwrap_or(blocks.len());
+
+        for block in &blocks[..split] {
+            let block_root = block.canonical_root();
+            if canonical_roots.get(&block.slot()) != Some(&block_root) {
+                return Err(format!(
+                    "Block {:?} at slot {} in {:?} conflicts with the chain",
+                    block_root,
+                    block.slot(),
+                    path
+                ));
+            }
+        }
+
+        let blocks = blocks.into_iter().skip(split).collect::<Vec<_>>();
+        let first_block = match blocks.first() {
+            Some(block) => block,
+            None => continue,
+        };
+
+        let head = chain
+            .head_info()
+            .map_err(|e| format!("Unable to read head: {:?}", e))?;
+        if first_block.parent_root() != head.block_root {
+            return Err(format!(
+                "Block at slot {} in {:?} does not extend the head at slot {}. Are archive \
+                 files missing?",
+                first_block.slot(),
+                path,
+                head.slot
+            ));
+        }
+
+        let file_blocks = blocks.len();
+        let result = chain.process_chain_segment(blocks);
+
+        // Run fork choice even if a block was invalid so that the blocks before it are
+        // finalized and migrated.
+        chain
+            .fork_choice()
+            .map_err(|e| format!("Unable to run fork choice: {:?}", e))?;
+        if let Some(e) = chain.store_migrator.take_error() {
+            return Err(format!("Unable to migrate finalized states: {:?}", e));
+        }
+
+        match result {
+            ChainSegmentResult::Successful {
+                imported_blocks: file_imported,
+            } => imported_blocks += file_imported,
+            ChainSegmentResult::Failed {
+                imported_blocks: file_imported,
+                error,
+            } => {
+                return Err(format!(
+                    "Invalid block in {:?} after importing {} of {} blocks: {:?}",
+                    path, file_imported, file_blocks, error
+                ))
+            }
+        }
+
+        log_progress(chain, path, imported_blocks, log)?;
+    }
+
+    Ok(imported_blocks)
+}
+
+fn export_archive<T: EthSpec>(matches: &ArgMatches, mut env: Environment<T>) -> Result<(), String> {
+    let dir: PathBuf = clap_utils::parse_required(matches, DIR_ARG)?;
+    let start_slot = Slot::new(clap_utils::parse_required(matches, START_SLOT_FLAG)?);
+    let end_slot: Option<u64> = clap_utils::parse_optional(matches, END_SLOT_FLAG)?;
+
+    let context = env.core_context();
+    let log = context.log().clone();
+    let client_config = get_config::<T>(
+        matches,
+        &context.eth2_config.spec_constants,
+        &context.eth2_config().spec,
+        log.clone(),
+    )?;
+
+    let client = env
+        .runtime()
+        .block_on(build_client(context, client_config))?;
+    let chain = client
+        .beacon_chain()
+        .ok_or_else(|| "Client has no beacon chain".to_string())?;
+
+    let exported_blocks =
+        export_archive_files(&chain, &dir, start_slot, end_slot.map(Slot::new), &log)?;
+
+    info!(
+        log,
+        "Archive export complete";
+        "exported_blocks" => exported_blocks,
+        "dir" => format!("{:?}", dir),
+    );
+
+    Ok(())
+}
+
+/// Exports the canonical blocks from `start_slot` to `end_slot` (inclusive, defaulting to the
+/// head slot) to archive files in `dir`, returning the number of blocks exported.
+///
+/// Files are aligned to multiples of `SLOTS_PER_ARCHIVE_FILE`, except that the first starts at
+/// `start_slot` and the last ends at `end_slot`. A file is written for every slot range, even if
+/// all of its slots were skipped.
+fn export_archive_files<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    dir: &Path,
+    start_slot: Slot,
+    end_slot: Option<Slot>,
+    log: &Logger,
+) -> Result<usize, String> {
+    let head = chain
+        .head_info()
+        .map_err(|e| format!("Unable to read head: {:?}", e))?;
+    let end_slot = end_slot.unwrap_or(head.slot);
+    if end_slot > head.slot {
+        return Err(format!(
+            "The end slot {} is later than the head slot {}",
+            end_slot, head.slot
+        ));
+    }
+    if start_slot > end_slot {
+        return Err(format!(
+            "The start slot {} is later than the end slot {}",
+            start_slot, end_slot
+        ));
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("Unable to create {:?}: {}", dir, e))?;
+
+    let mut file_start = start_slot;
+    let mut blocks = vec![];
+    let mut previous_root = None;
+    let mut exported_blocks = 0;
+
+    let block_roots = chain
+        .forwards_iter_block_roots(start_slot)
+        .map_err(|e| format!("Unable to read block roots: {:?}", e))?;
+
+    for result in block_roots {
+        let (block_root, slot) =
+            result.map_err(|e| format!("Unable to read block roots: {:?}", e))?;
+        if slot > end_slot {
+            break;
+        }
+
+        // Skipped slots repeat the root of the previous block.
+        if previous_root == Some(block_root) {
+            continue;
+        }
+        previous_root = Some(block_root);
+
+        let block = chain
+            .get_block(&block_root)
+            .map_err(|e| format!("Unable to read block {:?}: {:?}", block_root, e))?
+            .ok_or_else(|| format!("Missing block {:?}", block_root))?;
+
+        // The first slot may be skipped, in which case its root is that of an earlier block.
+        if block.slot() != slot {
+            continue;
+        }
+
+        // Write the preceding files, including any with only skipped slots.
+        while slot > archive_file_end(file_start, end_slot) {
+            let file_end = archive_file_end(file_start, end_slot);
+            exported_blocks += blocks.len();
+            write_archive_file(chain, dir, file_start, file_end, &blocks, log)?;
+            blocks.clear();
+            file_start = file_end + 1;
+        }
+
+        blocks.push(block);
+    }
+
+    // Write the file containing the end slot and any files of skipped slots before it.
+    while file_start <= end_slot {
+        let file_end = archive_file_end(file_start, end_slot);
+        exported_blocks += blocks.len();
+        write_archive_file(chain, dir, file_start, file_end, &blocks, log)?;
+        blocks.clear();
+        file_start = file_end + 1;
+    }
+
+    Ok(exported_blocks)
+}
+
+/// Returns the first slot of the archive file containing `slot`.
+fn archive_file_start(slot: Slot) -> Slot {
+    slot - slot % SLOTS_PER_ARCHIVE_FILE
+}
+
+/// Returns the last slot of the archive file which starts at `file_start`, which is never later
+/// than `end_slot`.
+fn archive_file_end(file_start: Slot, end_slot: Slot) -> Slot {
+    std::cmp::min(
+        archive_file_start(file_start) + SLOTS_PER_ARCHIVE_FILE - 1,
+        end_slot,
+    )
+}
+
+/// Writes the `blocks` from `file_start` to `file_end` (inclusive) along with the state at
+/// `file_end`.
+fn write_archive_file<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    dir: &Path,
+    file_start: Slot,
+    file_end: Slot,
+    blocks: &[SignedBeaconBlock<T::EthSpec>],
+    log: &Logger,
+) -> Result<(), String> {
+    let state = chain
+        .state_at_slot(file_end, StateSkipConfig::WithStateRoots)
+        .map_err(|e| format!("Unable to read state at slot {}: {:?}", file_end, e))?;
+
+    let bytes = archive::encode_archive(
+        file_start,
+        (file_end - file_start).as_u64() + 1,
+        blocks,
+        Some(&state),
+    )?;
+
+    let path = dir.join(archive::archive_file_name(file_start));
+    fs::write(&path, bytes).map_err(|e| format!("Unable to write {:?}: {}", path, e))?;
+
+    info!(
+        log,
+        "Exported archive file";
+        "file" => format!("{:?}", path),
+        "blocks" => blocks.len(),
+        "start_slot" => file_start,
+        "end_slot" => file_end,
+    );
+
+    Ok(())
+}
+
+/// Builds a client with only a beacon chain, backed by the on-disk database.
+async fn build_client<T: EthSpec>(
+    context: RuntimeContext<T>,
+    client_config: ClientConfig,
+) -> Result<crate::Client<ImportWitness<T>>, String> {
+    let spec = context.eth2_config().spec.clone();
+    let db_path = client_config.create_db_path()?;
+    let freezer_db_path = client_config.create_freezer_db_path()?;
+
+    let builder = ClientBuilder::new(context.eth_spec_instance.clone())
+        .runtime_context(context)
+        .chain_spec(spec)
+        .disk_store(&db_path, &freezer_db_path, client_config.store.clone())?
+        .blocking_migrator()?
+        .beacon_chain_builder(client_config.genesis.clone(), client_config)
+        .await?;
+
+    Ok(builder
+        .no_eth1_backend()?
+        .system_time_slot_clock()?
+        .websocket_event_handler(WebSocketConfig::default())?
+        .build_beacon_chain()?
+        .build())
+}
+
+/// Returns the root of the canonical block at each slot up to the head.
+///
+/// Skipped slots map to the root of the most recent block before them.
+fn canonical_block_roots<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+) -> Result<HashMap<Slot, Hash256>, String> {
+    chain
+        .rev_iter_block_roots()
+        .and_then(|iter| {
+            iter.map(|res| res.map(|(root, slot)| (slot, root)))
+                .collect()
+        })
+        .map_err(|e| format!("Unable to read canonical block roots: {:?}", e))
+}
+
+fn log_progress<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    path: &Path,
+    imported_blocks: usize,
+    log: &Logger,
+) -> Result<(), String> {
+    let head = chain
+        .head_info()
+        .map_err(|e| format!("Unable to read head: {:?}", e))?;
+
+    info!(
+        log,
+        "Imported archive file";
+        "file" => format!("{:?}", path),
+        "imported_blocks" => imported_blocks,
+        "head_slot" => head.slot,
+        "finalized_epoch" => head.finalized_checkpoint.epoch,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beacon_chain::store::{HotColdDB, StoreConfig};
+    use beacon_chain::test_utils::{
+        AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
+    };
+    use std::sync::Arc;
+    use types::{test_utils::generate_deterministic_keypairs, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    const VALIDATOR_COUNT: usize = 24;
+
+    fn null_logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    fn get_harness() -> BeaconChainHarness<EphemeralHarnessType<E>> {
+        let store =
+            HotColdDB::open_ephemeral(StoreConfig::default(), E::default_spec(), null_logger())
+                .unwrap();
+        BeaconChainHarness::new_with_store(
+            MinimalEthSpec,
+            Arc::new(store),
+            generate_deterministic_keypairs(VALIDATOR_COUNT),
+        )
+    }
+
+    #[test]
+    fn import_archive_files_from_another_chain() {
+        let source = get_harness();
+        let num_blocks = E::slots_per_epoch() as usize * 5;
+        source.extend_chain(
+            num_blocks,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        );
+        let head = source.chain.head_info().unwrap();
+        assert!(
+            head.finalized_checkpoint.epoch > 0,
+            "source should finalize"
+        );
+
+        let mut blocks = source
+            .chain
+            .chain_dump()
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.beacon_block)
+            .filter(|block| block.slot() > 0)
+            .collect::<Vec<_>>();
+        let path = source
+            .data_dir
+            .path()
+            .join(archive::archive_file_name(Slot::new(0)));
+        let slot_count = head.slot.as_u64() + 1;
+        let bytes = archive::encode_archive(Slot::new(0), slot_count, &blocks, None).unwrap();
+        fs::write(&path, bytes).unwrap();
+        let files = vec![path.clone()];
+
+        let target = get_harness();
+        target.chain.slot_clock.set_slot(head.slot.as_u64());
+        assert_eq!(
+            import_archive_files(&target.chain, &files, &null_logger()),
+            Ok(num_blocks)
+        );
+
+        let target_head = target.chain.head_info().unwrap();
+        assert_eq!(target_head.block_root, head.block_root);
+        assert_eq!(target_head.finalized_checkpoint, head.finalized_checkpoint);
+        assert!(
+            target.chain.store.get_split_slot() > 0,
+            "finalized states should be migrated to the freezer DB"
+        );
+
+        // Known blocks are checked against the chain rather than imported again.
+        assert_eq!(
+            import_archive_files(&target.chain, &files, &null_logger()),
+            Ok(0)
+        );
+
+        blocks[0].message.body.graffiti = [1; 32];
+        let bytes = archive::encode_archive(Slot::new(0), slot_count, &blocks, None).unwrap();
+        fs::write(&path, bytes).unwrap();
+        assert!(
+            import_archive_files(&target.chain, &files, &null_logger()).is_err(),
+            "a block which conflicts with the chain should not be accepted"
+        );
+    }
+
+    fn archive_paths(dir: &Path, start_slots: &[u64]) -> Vec<PathBuf> {
+        start_slots
+            .iter()
+            .map(|&slot| dir.join(archive::archive_file_name(Slot::new(slot))))
+            .collect()
+    }
+
+    fn block_slots(path: &Path) -> Vec<u64> {
+        archive::read_archive_file::<E>(path)
+            .unwrap()
+            .iter()
+            .map(|block| block.slot().as_u64())
+            .collect()
+    }
+
+    // Skipping a whole archive file of slots is slow without optimizations.
+    #[cfg(not(debug_assertions))]
+    #[test]
+    fn export_archive_files_across_file_boundaries() {
+        let source = get_harness();
+        source.extend_chain(
+            8,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        );
+        // Skip the rest of the first file and all of the second.
+        let resumed_slot = 2 * SLOTS_PER_ARCHIVE_FILE + 3;
+        source.extend_chain(
+            4,
+            BlockStrategy::ForkCanonicalChainAt {
+                previous_slot: Slot::new(8),
+                first_slot: Slot::new(resumed_slot),
+            },
+            AttestationStrategy::AllValidators,
+        );
+        let head = source.chain.head_info().unwrap();
+        assert_eq!(head.slot, resumed_slot + 3);
+
+        let dir = source.data_dir.path().join("archive");
+        assert_eq!(
+            export_archive_files(&source.chain, &dir, Slot::new(0), None, &null_logger()),
+            Ok(13)
+        );
+        let files = archive::archive_files(&dir).unwrap();
+        assert_eq!(
+            files,
+            archive_paths(
+                &dir,
+                &[0, SLOTS_PER_ARCHIVE_FILE, 2 * SLOTS_PER_ARCHIVE_FILE]
+            )
+        );
+        assert_eq!(block_slots(&files[0]), (0..=8).collect::<Vec<_>>());
+        assert!(block_slots(&files[1]).is_empty());
+        assert_eq!(
+            block_slots(&files[2]),
+            (resumed_slot..=resumed_slot + 3).collect::<Vec<_>>()
+        );
+
+        // The export can be imported into a chain with only the genesis block.
+        let target = get_harness();
+        target.chain.slot_clock.set_slot(head.slot.as_u64());
+        assert_eq!(
+            import_archive_files(&target.chain, &files, &null_logger()),
+            Ok(12)
+        );
+        assert_eq!(
+            target.chain.head_info().unwrap().block_root,
+            head.block_root
+        );
+
+        // The first slot is skipped and the end slot is before the head.
+        let dir = source.data_dir.path().join("partial_archive");
+        assert_eq!(
+            export_archive_files(
+                &source.chain,
+                &dir,
+                Slot::new(10),
+                Some(Slot::new(resumed_slot + 1)),
+                &null_logger()
+            ),
+            Ok(2)
+        );
+        let files = archive::archive_files(&dir).unwrap();
+        assert_eq!(
+            files,
+            archive_paths(
+                &dir,
+                &[10, SLOTS_PER_ARCHIVE_FILE, 2 * SLOTS_PER_ARCHIVE_FILE]
+            )
+        );
+        assert!(block_slots(&files[0]).is_empty());
+        assert!(block_slots(&files[1]).is_empty());
+        assert_eq!(block_slots(&files[2]), vec![resumed_slot, resumed_slot + 1]);
+
+        assert!(
+            export_archive_files(
+                &source.chain,
+                &dir,
+                Slot::new(0),
+                Some(head.slot + 1),
+                &null_logger()
+            )
+            .is_err(),
+            "the end slot should not be later than the head"
+        );
+    }
+}
@@ -3,6 +3,8 @@ extern crate clap;
 
 mod cli;
 mod config;
+pub mod database_manager;
+pub mod status;
 
 pub use beacon_chain;
 pub use cli::cli_app;
@@ -53,9 +55,19 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
     /// given `matches` and potentially configuration files on the local filesystem or other
     /// configurations hosted remotely.
     pub async fn new_from_cli<'a, 'b>(
-        context: RuntimeContext<E>,
+        mut context: RuntimeContext<E>,
         matches: &ArgMatches<'b>,
     ) -> Result<Self, String> {
+        // Apply the override to the spec, rather than the client config, so that the network and
+        // the beacon chain use the same tolerance.
+        if let Some(millis) = clap_utils::parse_optional(matches, "maximum-gossip-clock-disparity")?
+        {
+            context
+                .eth2_config
+                .spec
+                .maximum_gossip_clock_disparity_millis = millis;
+        }
+
         let client_config = get_config::<E>(
             &matches,
             &context.eth2_config.spec_constants,
@@ -127,7 +139,8 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
         let builder = builder
             .build_beacon_chain()?
             .network(&mut client_config.network)?
-            .notifier()?;
+            .notifier()?
+            .state_reconstruction(client_config.store.state_reconstruction_rate)?;
 
         let builder = if client_config.rest_api.enabled {
             builder.http_server(&client_config, &http_eth2_config, events)?
@@ -135,6 +148,12 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
             builder
         };
 
+        let builder = if client_config.grpc.enabled {
+            builder.grpc_server(&client_config)?
+        } else {
+            builder
+        };
+
         Ok(Self(builder.build()))
     }
 
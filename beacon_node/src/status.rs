@@ -0,0 +1,299 @@
+//! Prints a summary of the state of a running beacon node, as reported by its HTTP API.
+
+use clap::{App, Arg, ArgMatches};
+use environment::Environment;
+use remote_beacon_node::{CanonicalHeadResponse, RemoteBeaconNode, SyncingResponse};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use types::EthSpec;
+
+pub const CMD: &str = "status";
+pub const BEACON_SERVER_FLAG: &str = "beacon-node";
+pub const JSON_FLAG: &str = "json";
+
+pub const DEFAULT_BEACON_SERVER: &str = "http://localhost:5052/";
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about(
+            "Queries the HTTP API of a running beacon node and prints a summary of its sync \
+            status, head and finalized slots, connected peers and eth1 cache. Exits with an \
+            error if the beacon node cannot be reached.",
+        )
+        .arg(
+            Arg::with_name(BEACON_SERVER_FLAG)
+                .long(BEACON_SERVER_FLAG)
+                .value_name("NETWORK_ADDRESS")
+                .help("The HTTP API of the beacon node to query.")
+                .default_value(DEFAULT_BEACON_SERVER)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(JSON_FLAG)
+                .long(JSON_FLAG)
+                .help("If present, the summary is printed as a single JSON object."),
+        )
+}
+
+/// The summary of a beacon node which is printed.
+#[derive(Serialize)]
+struct NodeStatus {
+    version: String,
+    /// The name of the `SyncState` of the node, e.g. `Synced`.
+    sync_state: String,
+    syncing: SyncingResponse,
+    head: CanonicalHeadResponse,
+    peers: PeerCounts,
+    /// `None` if the node is not syncing the eth1 chain.
+    eth1: Option<Eth1Status>,
+}
+
+/// The number of connected peers, by the direction of their connections and by client.
+#[derive(Serialize, Default)]
+struct PeerCounts {
+    total: usize,
+    /// Peers with at least one connection which we dialed.
+    outbound: usize,
+    /// Peers which have only dialed us.
+    inbound: usize,
+    clients: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+struct Eth1Status {
+    endpoint_in_use: Option<String>,
+    endpoints: usize,
+    usable_endpoints: usize,
+    /// The response of `/lighthouse/eth1/cache`.
+    cache: Value,
+}
+
+pub fn run<T: EthSpec>(matches: &ArgMatches, mut env: Environment<T>) -> Result<(), String> {
+    let beacon_server: String = clap_utils::parse_required(matches, BEACON_SERVER_FLAG)?;
+    let beacon_node = RemoteBeaconNode::<T>::new(beacon_server)?;
+
+    let status = env.runtime().block_on(query_status(&beacon_node))?;
+
+    if matches.is_present(JSON_FLAG) {
+        println!(
+            "{}",
+            serde_json::to_string(&status)
+                .map_err(|e| format!("Unable to encode status: {:?}", e))?
+        );
+    } else {
+        print_status(&status);
+    }
+
+    Ok(())
+}
+
+async fn query_status<T: EthSpec>(beacon_node: &RemoteBeaconNode<T>) -> Result<NodeStatus, String> {
+    let http = &beacon_node.http;
+    let query_error = |e| {
+        format!(
+            "Unable to query the beacon node at {}: {:?}",
+            http.server_url(),
+            e
+        )
+    };
+
+    let version = http.node().get_version().await.map_err(query_error)?;
+    let syncing = http.node().syncing_status().await.map_err(query_error)?;
+    let head = http.beacon().get_head().await.map_err(query_error)?;
+    let sync_state: Value = get_lighthouse(beacon_node, "syncing").await?;
+    let peers: Vec<Value> = get_lighthouse(beacon_node, "connected_peers").await?;
+    let endpoints: Vec<Value> = get_lighthouse(beacon_node, "eth1/endpoints").await?;
+    let cache: Value = get_lighthouse(beacon_node, "eth1/cache").await?;
+
+    let eth1 = if cache.is_null() {
+        None
+    } else {
+        Some(Eth1Status {
+            endpoint_in_use: endpoints
+                .iter()
+                .find(|endpoint| endpoint["in_use"].as_bool() == Some(true))
+                .and_then(|endpoint| endpoint["endpoint"].as_str())
+                .map(String::from),
+            endpoints: endpoints.len(),
+            usable_endpoints: endpoints
+                .iter()
+                .filter(|endpoint| endpoint["usable"].as_bool() == Some(true))
+                .count(),
+            cache,
+        })
+    };
+
+    Ok(NodeStatus {
+        version,
+        sync_state: variant_name(&sync_state),
+        syncing,
+        head,
+        peers: count_peers(&peers),
+        eth1,
+    })
+}
+
+/// Requests `/lighthouse/{path}` from the beacon node.
+async fn get_lighthouse<T: EthSpec, U: DeserializeOwned>(
+    beacon_node: &RemoteBeaconNode<T>,
+    path: &str,
+) -> Result<U, String> {
+    let http = &beacon_node.http;
+    let url = http
+        .server_url()
+        .join("lighthouse/")
+        .and_then(|url| url.join(path))
+        .map_err(|e| format!("Invalid beacon node URL: {:?}", e))?;
+
+    http.json_get(url, vec![])
+        .await
+        .map_err(|e| format!("Unable to query /lighthouse/{}: {:?}", path, e))
+}
+
+/// Counts the peers in a response of `/lighthouse/connected_peers`.
+fn count_peers(peers: &[Value]) -> PeerCounts {
+    let mut counts = PeerCounts::default();
+    for peer in peers {
+        let peer_info = &peer["peer_info"];
+        counts.total += 1;
+        if peer_info["connection_status"]["Connected"]["out"].as_u64() > Some(0) {
+            counts.outbound += 1;
+        } else {
+            counts.inbound += 1;
+        }
+        let client = peer_info["client"]["kind"]
+            .as_str()
+            .unwrap_or("Unknown")
+            .to_string();
+        *counts.clients.entry(client).or_default() += 1;
+    }
+    counts
+}
+
+/// Returns the name of the variant of a serialized enum, e.g. `Synced` for both `"Synced"` and
+/// `{"SyncingHead": {..}}`.
+fn variant_name(value: &Value) -> String {
+    match value {
+        Value::String(name) => name.clone(),
+        Value::Object(map) => map.keys().next().cloned().unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+fn print_status(status: &NodeStatus) {
+    let sync = &status.syncing.sync_status;
+    let head = &status.head;
+    let peers = &status.peers;
+
+    println!("Version:    {}", status.version);
+    println!(
+        "Sync:       {} (current slot {}, highest known slot {})",
+        status.sync_state, sync.current_slot, sync.highest_slot
+    );
+    println!("Head:       slot {}, root {:?}", head.slot, head.block_root);
+    println!(
+        "Finalized:  slot {}, root {:?}",
+        head.finalized_slot, head.finalized_block_root
+    );
+    println!(
+        "Peers:      {} ({} outbound, {} inbound)",
+        peers.total, peers.outbound, peers.inbound
+    );
+    if !peers.clients.is_empty() {
+        let clients = peers
+            .clients
+            .iter()
+            .map(|(client, count)| format!("{} {}", client, count))
+            .collect::<Vec<_>>();
+        println!("Clients:    {}", clients.join(", "));
+    }
+
+    match &status.eth1 {
+        Some(eth1) => {
+            println!(
+                "Eth1:       {} ({} of {} endpoints usable)",
+                eth1.endpoint_in_use
+                    .as_deref()
+                    .unwrap_or("no usable endpoint"),
+                eth1.usable_endpoints,
+                eth1.endpoints
+            );
+            let cache = &eth1.cache;
+            let highest_block = cache["highest_block_number"]
+                .as_u64()
+                .map_or_else(|| "none".to_string(), |number| format!("#{}", number));
+            println!(
+                "Eth1 cache: {} blocks (highest {}), {} deposits",
+                cache["block_cache_len"], highest_block, cache["deposit_cache_len"]
+            );
+        }
+        None => println!("Eth1:       not syncing the eth1 chain"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn peer(out: u64, client: Option<&str>) -> Value {
+        let mut peer = json!({
+            "peer_id": "16Uiu2HAm",
+            "peer_info": {
+                "connection_status": {
+                    "Connected": { "in": 1, "out": out }
+                },
+                "client": {}
+            }
+        });
+        if let Some(kind) = client {
+            peer["peer_info"]["client"]["kind"] = json!(kind);
+        }
+        peer
+    }
+
+    #[test]
+    fn counts_peers_by_direction_and_client() {
+        let peers = vec![
+            peer(1, Some("Lighthouse")),
+            peer(0, Some("Lighthouse")),
+            peer(2, Some("Prysm")),
+            peer(0, None),
+        ];
+        let counts = count_peers(&peers);
+
+        assert_eq!(counts.total, 4);
+        assert_eq!(counts.outbound, 2);
+        assert_eq!(counts.inbound, 2);
+        assert_eq!(
+            counts.clients.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("Lighthouse".to_string(), 2),
+                ("Prysm".to_string(), 1),
+                ("Unknown".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_no_peers() {
+        let counts = count_peers(&[]);
+
+        assert_eq!(counts.total, 0);
+        assert_eq!(counts.outbound, 0);
+        assert_eq!(counts.inbound, 0);
+        assert!(counts.clients.is_empty());
+    }
+
+    #[test]
+    fn names_unit_and_struct_variants() {
+        assert_eq!(variant_name(&json!("Synced")), "Synced");
+        assert_eq!(
+            variant_name(&json!({ "SyncingHead": { "start_slot": 1, "target_slot": 2 } })),
+            "SyncingHead"
+        );
+        assert_eq!(variant_name(&json!({})), "");
+        assert_eq!(variant_name(&json!(null)), "null");
+    }
+}
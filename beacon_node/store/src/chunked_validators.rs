@@ -0,0 +1,135 @@
+//! Deduplicated storage for the validator registry of frozen `BeaconState`s.
+//!
+//! The validator registry is by far the largest component of a `BeaconState`, yet very few
+//! validator records change between restore points. Rather than storing the whole registry with
+//! every restore point, we split it into fixed-size chunks and store each chunk under the hash of
+//! its SSZ bytes. A restore point only stores the list of its chunk hashes, so chunks which are
+//! identical between restore points are stored once.
+//!
+//! Chunks are content-addressed and never deleted, which is sound because the freezer database
+//! is append-only.
+use crate::chunked_vector::ChunkError;
+use crate::*;
+use eth2_hashing::hash;
+use ssz::{Decode, Encode};
+
+/// The number of validator records stored per chunk.
+///
+/// Smaller chunks improve deduplication at the cost of more database reads and a longer list of
+/// chunk roots in each restore point.
+pub const VALIDATORS_PER_CHUNK: usize = 128;
+
+/// Returns the key under which `chunk_bytes` are stored in `DBColumn::BeaconValidatorChunks`.
+fn chunk_root(chunk_bytes: &[u8]) -> Hash256 {
+    Hash256::from_slice(&hash(chunk_bytes))
+}
+
+/// Store the chunks of `validators` which are not already in `store`, returning the roots of all
+/// chunks in order.
+pub fn store_validator_chunks<E: EthSpec, S: KeyValueStore<E>>(
+    store: &S,
+    validators: &[Validator],
+) -> Result<Vec<Hash256>, Error> {
+    let column: &str = DBColumn::BeaconValidatorChunks.into();
+
+    validators
+        .chunks(VALIDATORS_PER_CHUNK)
+        .map(|chunk| {
+            let bytes = chunk.to_vec().as_ssz_bytes();
+            let root = chunk_root(&bytes);
+
+            if !store.key_exists(column, root.as_bytes())? {
+                store.put_bytes(column, root.as_bytes(), &bytes)?;
+                metrics::inc_counter(&metrics::BEACON_VALIDATOR_CHUNK_WRITE_COUNT);
+            }
+
+            Ok(root)
+        })
+        .collect()
+}
+
+/// Load the validator registry described by `chunk_roots` from `store`.
+pub fn load_validators_from_chunks<E: EthSpec, S: KeyValueStore<E>>(
+    store: &S,
+    chunk_roots: &[Hash256],
+) -> Result<VariableList<Validator, E::ValidatorRegistryLimit>, Error> {
+    let column: &str = DBColumn::BeaconValidatorChunks.into();
+    let mut validators = Vec::with_capacity(chunk_roots.len() * VALIDATORS_PER_CHUNK);
+
+    for (chunk_index, root) in chunk_roots.iter().enumerate() {
+        let bytes = store
+            .get_bytes(column, root.as_bytes())?
+            .ok_or_else(|| ChunkError::Missing { chunk_index })?;
+        validators.extend(Vec::<Validator>::from_ssz_bytes(&bytes)?);
+    }
+
+    Ok(validators.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemoryStore;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::MinimalEthSpec as TestSpec;
+
+    fn validators(n: usize) -> Vec<Validator> {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        (0..n)
+            .map(|_| Validator::random_for_test(&mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let store = MemoryStore::<TestSpec>::open();
+
+        for &n in &[0, 1, VALIDATORS_PER_CHUNK, VALIDATORS_PER_CHUNK * 3 + 7] {
+            let validators = validators(n);
+            let roots = store_validator_chunks(&store, &validators).unwrap();
+            assert_eq!(
+                roots.len(),
+                (n + VALIDATORS_PER_CHUNK - 1) / VALIDATORS_PER_CHUNK
+            );
+
+            let loaded = load_validators_from_chunks::<TestSpec, _>(&store, &roots).unwrap();
+            assert_eq!(loaded.to_vec(), validators, "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn unchanged_chunks_are_shared() {
+        let store = MemoryStore::<TestSpec>::open();
+
+        let mut validators = validators(VALIDATORS_PER_CHUNK * 4);
+        let roots_1 = store_validator_chunks(&store, &validators).unwrap();
+
+        validators[VALIDATORS_PER_CHUNK + 1].effective_balance += 1;
+        let roots_2 = store_validator_chunks(&store, &validators).unwrap();
+
+        let changed = roots_1
+            .iter()
+            .zip(roots_2.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert_eq!(changed, 1);
+        assert_ne!(roots_1[1], roots_2[1]);
+
+        assert_eq!(
+            load_validators_from_chunks::<TestSpec, _>(&store, &roots_2)
+                .unwrap()
+                .to_vec(),
+            validators
+        );
+    }
+
+    #[test]
+    fn missing_chunk() {
+        let store = MemoryStore::<TestSpec>::open();
+
+        match load_validators_from_chunks::<TestSpec, _>(&store, &[Hash256::zero()]) {
+            Err(Error::VectorChunkError(ChunkError::Missing { chunk_index: 0 })) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
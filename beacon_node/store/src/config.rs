@@ -3,6 +3,7 @@ use types::{EthSpec, MinimalEthSpec};
 
 pub const DEFAULT_SLOTS_PER_RESTORE_POINT: u64 = 2048;
 pub const DEFAULT_BLOCK_CACHE_SIZE: usize = 5;
+pub const DEFAULT_STATE_RECONSTRUCTION_RATE: usize = 2;
 
 /// Database configuration parameters.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -11,6 +12,9 @@ pub struct StoreConfig {
     pub slots_per_restore_point: u64,
     /// Maximum number of blocks to store in the in-memory block cache.
     pub block_cache_size: usize,
+    /// Maximum number of restore points to reconstruct each slot after `slots_per_restore_point`
+    /// has been decreased.
+    pub state_reconstruction_rate: usize,
 }
 
 impl Default for StoreConfig {
@@ -19,6 +23,7 @@ impl Default for StoreConfig {
             // Safe default for tests, shouldn't ever be read by a CLI node.
             slots_per_restore_point: MinimalEthSpec::slots_per_historical_root() as u64,
             block_cache_size: DEFAULT_BLOCK_CACHE_SIZE,
+            state_reconstruction_rate: DEFAULT_STATE_RECONSTRUCTION_RATE,
         }
     }
 }
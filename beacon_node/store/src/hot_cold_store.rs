@@ -1,5 +1,7 @@
+use crate::chunked_validators::store_validator_chunks;
 use crate::chunked_vector::{
-    store_updated_vector, BlockRoots, HistoricalRoots, RandaoMixes, StateRoots,
+    chunk_key, store_updated_vector, BlockRoots, Chunk, Field, HistoricalRoots, RandaoMixes,
+    StateRoots,
 };
 use crate::config::StoreConfig;
 use crate::forwards_iter::HybridForwardsBlockRootsIterator;
@@ -9,37 +11,63 @@ use crate::leveldb_store::LevelDB;
 use crate::memory_store::MemoryStore;
 use crate::metrics;
 use crate::{
-    get_key_for_col, DBColumn, Error, ItemStore, KeyValueStoreOp, PartialBeaconState, StoreItem,
-    StoreOp,
+    get_key_for_col, ColumnStats, DBColumn, Error, ItemStore, KeyValueStoreOp, PartialBeaconState,
+    PartialBeaconStateV1, StoreItem, StoreOp,
 };
 use lru::LruCache;
 use parking_lot::{Mutex, RwLock};
-use slog::{debug, trace, warn, Logger};
+use serde_derive::Serialize;
+use slog::{debug, info, trace, warn, Logger};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use state_processing::{
     per_block_processing, per_slot_processing, BlockProcessingError, BlockSignatureStrategy,
     SlotProcessingError,
 };
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::marker::PhantomData;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use types::*;
 
+/// The number of blocks and states which may be deleted from the hot database before it is
+/// compacted to reclaim the space they used.
+pub const COMPACTION_DELETION_THRESHOLD: usize = 4096;
+
 /// 32-byte key for accessing the `split` of the freezer DB.
 pub const SPLIT_DB_KEY: &str = "FREEZERDBSPLITFREEZERDBSPLITFREE";
 
+/// 32-byte key for accessing the `RestorePointInfo` of the freezer DB.
+pub const RESTORE_POINT_INFO_DB_KEY: &str = "FREEZERDBRPINFOFREEZERDBRPINFOFR";
+
+/// 32-byte key for accessing the `SchemaVersion` of the freezer DB.
+pub const SCHEMA_VERSION_DB_KEY: &str = "FREEZERDBSCHEMAFREEZERDBSCHEMAFR";
+
+/// The version of the freezer DB's encoding written by this version of the store.
+///
+/// Version 2 stores the validator registry of restore points in `DBColumn::BeaconValidatorChunks`.
+pub const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion(2);
+
 /// On-disk database that stores finalized states efficiently.
 ///
-/// Stores vector fields like the `block_roots` and `state_roots` separately, and only stores
-/// intermittent "restore point" states pre-finalization.
+/// Stores vector fields like the `block_roots` and `state_roots` separately, deduplicates the
+/// validator registry between restore points, and only stores intermittent "restore point" states
+/// pre-finalization.
 pub struct HotColdDB<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> {
     /// The slot and state root at the point where the database is split between hot and cold.
     ///
     /// States with slots less than `split.slot` are in the cold DB, while states with slots
     /// greater than or equal are in the hot DB.
     split: RwLock<Split>,
+    /// The frequency with which restore points are indexed, and the progress of reconstructing
+    /// the restore points missing since the frequency was increased.
+    restore_point_info: RwLock<RestorePointInfo>,
+    /// Prevents finalization and reconstruction from concurrently updating the vectors of roots in
+    /// the freezer DB.
+    freezer_write_lock: Mutex<()>,
     config: StoreConfig,
     /// Cold database containing compact historical data.
     pub(crate) cold_db: Cold,
@@ -49,6 +77,10 @@ pub struct HotColdDB<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> {
     pub(crate) hot_db: Hot,
     /// LRU cache of deserialized blocks. Updated whenever a block is loaded.
     block_cache: Mutex<LruCache<Hash256, SignedBeaconBlock<E>>>,
+    /// The number of blocks and states deleted from the hot database since the last compaction.
+    deletions_since_compaction: AtomicUsize,
+    /// The statistics gathered by the last compaction, if any.
+    last_compaction: RwLock<Option<CompactionStats>>,
     /// Chain spec.
     spec: ChainSpec,
     /// Logger.
@@ -83,6 +115,16 @@ pub enum HotColdDBError {
         slots_per_historical_root: u64,
         slots_per_epoch: u64,
     },
+    /// The freezer DB was written by a newer version of the store.
+    UnsupportedSchemaVersion {
+        stored: SchemaVersion,
+        current: SchemaVersion,
+    },
+    /// The restore points of an existing freezer DB can only be made more frequent.
+    IncreasedSlotsPerRestorePoint {
+        stored: u64,
+        configured: u64,
+    },
     RestorePointBlockHashError(BeaconStateError),
 }
 
@@ -93,13 +135,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         block_root: &Hash256,
         block: SignedBeaconBlock<E>,
     ) -> Result<(), Error> {
-        // Store on disk.
-        self.hot_db.put(block_root, &block)?;
-
-        // Update cache.
-        self.block_cache.lock().put(*block_root, block);
-
-        Ok(())
+        self.do_atomically(&[StoreOp::PutBlock((*block_root).into(), block)])
     }
 
     /// Fetch a block from the store.
@@ -125,6 +161,8 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
 
     /// Delete a block from the store and the block cache.
     pub fn delete_block(&self, block_root: &Hash256) -> Result<(), Error> {
+        self.deletions_since_compaction
+            .fetch_add(1, Ordering::Relaxed);
         self.block_cache.lock().pop(block_root);
         self.hot_db.delete::<SignedBeaconBlock<E>>(block_root)
     }
@@ -142,7 +180,9 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         if state.slot < self.get_split_slot() {
             self.store_cold_state(state_root, &state)
         } else {
-            self.store_hot_state(state_root, state)
+            let mut ops = Vec::with_capacity(2);
+            self.store_hot_state(state_root, state, &mut ops)?;
+            self.hot_db.do_atomically(&ops)
         }
     }
 
@@ -175,6 +215,9 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     /// (which are frozen, and won't be deleted), or valid descendents of the finalized checkpoint
     /// (which will be deleted by this function but shouldn't be).
     pub fn delete_state(&self, state_root: &Hash256, slot: Slot) -> Result<(), Error> {
+        self.deletions_since_compaction
+            .fetch_add(1, Ordering::Relaxed);
+
         // Delete the state summary.
         self.hot_db
             .key_delete(DBColumn::BeaconStateSummary.into(), state_root.as_bytes())?;
@@ -242,13 +285,32 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         self.hot_db.exists::<I>(key)
     }
 
-    pub fn do_atomically(&self, batch: &[StoreOp]) -> Result<(), Error> {
+    /// Applies all of the operations in `batch` to the hot database in a single write, so that
+    /// either all or none of them are applied, then updates the block cache.
+    pub fn do_atomically(&self, batch: &[StoreOp<E>]) -> Result<(), Error> {
         let mut guard = self.block_cache.lock();
 
         let mut key_value_batch: Vec<KeyValueStoreOp> = Vec::with_capacity(batch.len());
         for op in batch {
             match op {
+                StoreOp::PutBlock(block_hash, block) => {
+                    let untyped_hash: Hash256 = (*block_hash).into();
+                    key_value_batch.push(block.as_kv_store_op(untyped_hash));
+                }
+
+                StoreOp::PutState(state_hash, state) => {
+                    let untyped_hash: Hash256 = (*state_hash).into();
+                    self.store_hot_state(&untyped_hash, state, &mut key_value_batch)?;
+                }
+
+                StoreOp::PutStateSummary(state_hash, summary) => {
+                    let untyped_hash: Hash256 = (*state_hash).into();
+                    key_value_batch.push(summary.as_kv_store_op(untyped_hash));
+                }
+
                 StoreOp::DeleteBlock(block_hash) => {
+                    self.deletions_since_compaction
+                        .fetch_add(1, Ordering::Relaxed);
                     let untyped_hash: Hash256 = (*block_hash).into();
                     let key =
                         get_key_for_col(DBColumn::BeaconBlock.into(), untyped_hash.as_bytes());
@@ -256,6 +318,8 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
                 }
 
                 StoreOp::DeleteState(state_hash, slot) => {
+                    self.deletions_since_compaction
+                        .fetch_add(1, Ordering::Relaxed);
                     let untyped_hash: Hash256 = (*state_hash).into();
                     let state_summary_key = get_key_for_col(
                         DBColumn::BeaconStateSummary.into(),
@@ -269,23 +333,102 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
                         key_value_batch.push(KeyValueStoreOp::DeleteKey(state_key));
                     }
                 }
+
+                StoreOp::KeyValueOp(kv_op) => key_value_batch.push(kv_op.clone()),
             }
         }
         self.hot_db.do_atomically(&key_value_batch)?;
 
         for op in batch {
             match op {
+                StoreOp::PutBlock(block_hash, block) => {
+                    let untyped_hash: Hash256 = (*block_hash).into();
+                    guard.put(untyped_hash, block.clone());
+                }
                 StoreOp::DeleteBlock(block_hash) => {
                     let untyped_hash: Hash256 = (*block_hash).into();
                     guard.pop(&untyped_hash);
                 }
-                StoreOp::DeleteState(_, _) => (),
+                StoreOp::PutState(_, _)
+                | StoreOp::PutStateSummary(_, _)
+                | StoreOp::DeleteState(_, _)
+                | StoreOp::KeyValueOp(_) => (),
             }
         }
         Ok(())
     }
 }
 
+impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold> {
+    /// Compact the databases if at least `COMPACTION_DELETION_THRESHOLD` blocks and states have
+    /// been deleted since the last compaction, e.g. after a finalization migration or pruning.
+    ///
+    /// Returns `true` if the databases were compacted.
+    pub fn compact_if_needed(&self) -> Result<bool, Error> {
+        if self.deletions_since_compaction.load(Ordering::Relaxed) < COMPACTION_DELETION_THRESHOLD {
+            return Ok(false);
+        }
+
+        self.compact()?;
+        Ok(true)
+    }
+
+    /// Compact the hot and freezer databases, then record the number of keys and bytes in each
+    /// of their columns.
+    pub fn compact(&self) -> Result<CompactionStats, Error> {
+        let deletions = self.deletions_since_compaction.swap(0, Ordering::Relaxed);
+
+        let timer = metrics::start_timer(&metrics::DISK_DB_COMPACTION_TIMES);
+        let started = SystemTime::now();
+        self.hot_db.compact()?;
+        self.cold_db.compact()?;
+        let duration = started.elapsed().unwrap_or_default();
+        metrics::stop_timer(timer);
+        metrics::inc_counter(&metrics::DISK_DB_COMPACTION_COUNT);
+
+        let stats = CompactionStats {
+            completed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0),
+            duration_millis: duration.as_millis() as u64,
+            hot_columns: self.hot_db.column_stats()?,
+            cold_columns: self.cold_db.column_stats()?,
+        };
+
+        for (db, columns) in &[("hot", &stats.hot_columns), ("cold", &stats.cold_columns)] {
+            for (column, column_stats) in columns.iter() {
+                if let Some(gauge) =
+                    metrics::get_int_gauge(&metrics::DISK_DB_COLUMN_KEYS, &[db, column])
+                {
+                    gauge.set(column_stats.keys as i64);
+                }
+                if let Some(gauge) =
+                    metrics::get_int_gauge(&metrics::DISK_DB_COLUMN_BYTES, &[db, column])
+                {
+                    gauge.set(column_stats.bytes as i64);
+                }
+            }
+        }
+
+        info!(
+            self.log,
+            "Database compaction complete";
+            "deletions" => deletions,
+            "duration_ms" => stats.duration_millis,
+        );
+
+        *self.last_compaction.write() = Some(stats.clone());
+
+        Ok(stats)
+    }
+
+    /// Returns the statistics gathered by the last compaction, if any.
+    pub fn last_compaction(&self) -> Option<CompactionStats> {
+        self.last_compaction.read().clone()
+    }
+}
+
 impl<E: EthSpec> HotColdDB<E, MemoryStore<E>, MemoryStore<E>> {
     pub fn open_ephemeral(
         config: StoreConfig,
@@ -296,9 +439,13 @@ impl<E: EthSpec> HotColdDB<E, MemoryStore<E>, MemoryStore<E>> {
 
         let db = HotColdDB {
             split: RwLock::new(Split::default()),
+            restore_point_info: RwLock::new(RestorePointInfo::new(config.slots_per_restore_point)),
+            freezer_write_lock: Mutex::new(()),
             cold_db: MemoryStore::open(),
             hot_db: MemoryStore::open(),
             block_cache: Mutex::new(LruCache::new(config.block_cache_size)),
+            deletions_since_compaction: AtomicUsize::new(0),
+            last_compaction: RwLock::new(None),
             config,
             spec,
             log,
@@ -324,9 +471,13 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
 
         let db = HotColdDB {
             split: RwLock::new(Split::default()),
+            restore_point_info: RwLock::new(RestorePointInfo::new(config.slots_per_restore_point)),
+            freezer_write_lock: Mutex::new(()),
             cold_db: LevelDB::open(cold_path)?,
             hot_db: LevelDB::open(hot_path)?,
             block_cache: Mutex::new(LruCache::new(config.block_cache_size)),
+            deletions_since_compaction: AtomicUsize::new(0),
+            last_compaction: RwLock::new(None),
             config,
             spec,
             log,
@@ -335,15 +486,19 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
 
         // Load the previous split slot from the database (if any). This ensures we can
         // stop and restart correctly.
-        if let Some(split) = db.load_split()? {
+        let split = db.load_split()?;
+        if let Some(split) = split {
             *db.split.write() = split;
         }
+        db.init_schema_version(split.is_some())?;
+        db.init_restore_point_info()?;
         Ok(db)
     }
 }
 
 impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold> {
-    /// Store a post-finalization state efficiently in the hot database.
+    /// Prepare the operations which store a post-finalization state efficiently in the hot
+    /// database, adding them to `ops`.
     ///
     /// On an epoch boundary, store a full state. On an intermediate slot, store
     /// just a backpointer to the nearest epoch boundary.
@@ -351,6 +506,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         &self,
         state_root: &Hash256,
         state: &BeaconState<E>,
+        ops: &mut Vec<KeyValueStoreOp>,
     ) -> Result<(), Error> {
         // On the epoch boundary, store the full state.
         if state.slot % E::slots_per_epoch() == 0 {
@@ -360,13 +516,14 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
                 "slot" => state.slot.as_u64(),
                 "state_root" => format!("{:?}", state_root)
             );
-            store_full_state(&self.hot_db, state_root, &state)?;
+            store_full_state(state_root, &state, ops);
         }
 
         // Store a summary of the state.
         // We store one even for the epoch boundary states, as we may need their slots
         // when doing a look up by state root.
-        self.put_state_summary(state_root, HotStateSummary::new(state_root, state)?)?;
+        let summary = HotStateSummary::new(state_root, state)?;
+        ops.push(summary.as_kv_store_op(*state_root));
 
         Ok(())
     }
@@ -430,18 +587,21 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
             "state_root" => format!("{:?}", state_root)
         );
 
-        // 1. Convert to PartialBeaconState and store that in the DB.
-        let partial_state = PartialBeaconState::from_state_forgetful(state);
-        self.cold_db.put(state_root, &partial_state)?;
-
-        // 2. Store updated vector entries.
+        // 1. Store the chunks of the validator registry which aren't already in the DB.
         let db = &self.cold_db;
+        let validator_chunk_roots = store_validator_chunks(db, &state.validators)?;
+
+        // 2. Convert to PartialBeaconState and store that in the DB.
+        let partial_state = PartialBeaconState::from_state_forgetful(state, validator_chunk_roots);
+        db.put(state_root, &partial_state)?;
+
+        // 3. Store updated vector entries.
         store_updated_vector(BlockRoots, db, state, &self.spec)?;
         store_updated_vector(StateRoots, db, state, &self.spec)?;
         store_updated_vector(HistoricalRoots, db, state, &self.spec)?;
         store_updated_vector(RandaoMixes, db, state, &self.spec)?;
 
-        // 3. Store restore point.
+        // 4. Store restore point.
         let restore_point_index = state.slot.as_u64() / self.config.slots_per_restore_point;
         self.store_restore_point_hash(restore_point_index, *state_root)?;
 
@@ -464,10 +624,12 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     pub fn load_cold_state_by_slot(&self, slot: Slot) -> Result<BeaconState<E>, Error> {
         if slot % self.config.slots_per_restore_point == 0 {
             let restore_point_idx = slot.as_u64() / self.config.slots_per_restore_point;
-            self.load_restore_point_by_index(restore_point_idx)
-        } else {
-            self.load_cold_intermediate_state(slot)
+            // The restore point may not have been reconstructed yet.
+            if let Some(state_root) = self.get_restore_point_hash(restore_point_idx)? {
+                return self.load_restore_point(&state_root);
+            }
         }
+        self.load_cold_intermediate_state(slot)
     }
 
     /// Load a restore point state by its `state_root`.
@@ -482,38 +644,28 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         partial_state.load_state_roots(&self.cold_db, &self.spec)?;
         partial_state.load_historical_roots(&self.cold_db, &self.spec)?;
         partial_state.load_randao_mixes(&self.cold_db, &self.spec)?;
+        partial_state.load_validators(&self.cold_db)?;
 
         Ok(partial_state.try_into()?)
     }
 
-    /// Load a restore point state by its `restore_point_index`.
-    fn load_restore_point_by_index(
-        &self,
-        restore_point_index: u64,
-    ) -> Result<BeaconState<E>, Error> {
-        let state_root = self.load_restore_point_hash(restore_point_index)?;
-        self.load_restore_point(&state_root)
-    }
-
     /// Load a frozen state that lies between restore points.
+    ///
+    /// Restore points which have not been reconstructed yet are skipped over, using the nearest
+    /// stored restore points either side of the state instead.
     fn load_cold_intermediate_state(&self, slot: Slot) -> Result<BeaconState<E>, Error> {
-        // 1. Load the restore points either side of the intermediate state.
-        let low_restore_point_idx = slot.as_u64() / self.config.slots_per_restore_point;
-        let high_restore_point_idx = low_restore_point_idx + 1;
-
         // Acquire the read lock, so that the split can't change while this is happening.
         let split = self.split.read();
 
-        let low_restore_point = self.load_restore_point_by_index(low_restore_point_idx)?;
-        // If the slot of the high point lies outside the freezer, use the split state
-        // as the upper restore point.
-        let high_restore_point = if high_restore_point_idx * self.config.slots_per_restore_point
-            >= split.slot.as_u64()
-        {
-            self.get_state(&split.state_root, Some(split.slot))?
-                .ok_or_else(|| HotColdDBError::MissingSplitState(split.state_root, split.slot))?
-        } else {
-            self.load_restore_point_by_index(high_restore_point_idx)?
+        // 1. Load the restore points either side of the intermediate state.
+        let low_restore_point = self.load_restore_point(&self.lower_restore_point_hash(slot)?)?;
+        // If there is no higher restore point in the freezer, use the split state as the upper
+        // restore point.
+        let high_restore_point = match self.higher_restore_point_hash(slot, split.slot)? {
+            Some(state_root) => self.load_restore_point(&state_root)?,
+            None => self
+                .get_state(&split.state_root, Some(split.slot))?
+                .ok_or_else(|| HotColdDBError::MissingSplitState(split.state_root, split.slot))?,
         };
 
         // 2. Load the blocks from the high restore point back to the low restore point.
@@ -631,6 +783,46 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         self.split.read().slot
     }
 
+    /// Returns the root of the canonical block at `slot`, if it lies prior to the latest restore
+    /// point in the freezer DB.
+    ///
+    /// As in `BeaconState::block_roots`, the root for a skipped slot is that of the prior block.
+    pub fn get_frozen_block_root(&self, slot: Slot) -> Result<Option<Hash256>, Error> {
+        self.get_frozen_root(BlockRoots, slot)
+    }
+
+    /// Returns the root of the canonical state at `slot`, if it lies prior to the latest restore
+    /// point in the freezer DB.
+    pub fn get_frozen_state_root(&self, slot: Slot) -> Result<Option<Hash256>, Error> {
+        self.get_frozen_root(StateRoots, slot)
+    }
+
+    /// Reads the value at `slot` of a per-slot vector of roots from the freezer DB, without
+    /// iterating from the head.
+    fn get_frozen_root<F: Field<E, Value = Hash256>>(
+        &self,
+        _field: F,
+        slot: Slot,
+    ) -> Result<Option<Hash256>, Error> {
+        let (_, end_vindex) =
+            F::start_and_end_vindex(self.get_latest_restore_point_slot(), &self.spec);
+        let vindex = slot.as_usize();
+        if vindex >= end_vindex {
+            return Ok(None);
+        }
+
+        let chunk_size = F::chunk_size();
+        let root = Chunk::<Hash256>::load(
+            &self.cold_db,
+            F::column(),
+            &chunk_key((vindex / chunk_size) as u64),
+        )?
+        .and_then(|chunk| chunk.values.get(vindex % chunk_size).copied())
+        .filter(|root| !root.is_zero());
+
+        Ok(root)
+    }
+
     /// Fetch the slot of the most recently stored restore point.
     pub fn get_latest_restore_point_slot(&self) -> Slot {
         (self.get_split_slot() - 1) / self.config.slots_per_restore_point
@@ -651,13 +843,44 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         Ok(())
     }
 
-    /// Load the state root of a restore point.
-    fn load_restore_point_hash(&self, restore_point_index: u64) -> Result<Hash256, Error> {
+    /// Load the state root of a restore point, if it is stored.
+    fn get_restore_point_hash(&self, restore_point_index: u64) -> Result<Option<Hash256>, Error> {
         let key = Self::restore_point_key(restore_point_index);
-        self.cold_db
+        Ok(self
+            .cold_db
             .get(&key)?
-            .map(|r: RestorePointHash| r.state_root)
-            .ok_or_else(|| HotColdDBError::MissingRestorePointHash(restore_point_index).into())
+            .map(|r: RestorePointHash| r.state_root))
+    }
+
+    /// Load the state root of the latest stored restore point at or prior to `slot`.
+    fn lower_restore_point_hash(&self, slot: Slot) -> Result<Hash256, Error> {
+        let mut restore_point_index = slot.as_u64() / self.config.slots_per_restore_point;
+        loop {
+            match self.get_restore_point_hash(restore_point_index)? {
+                Some(state_root) => return Ok(state_root),
+                None if restore_point_index > 0 => restore_point_index -= 1,
+                None => {
+                    return Err(HotColdDBError::MissingRestorePointHash(restore_point_index).into())
+                }
+            }
+        }
+    }
+
+    /// Load the state root of the earliest stored restore point after `slot` and prior to
+    /// `split_slot`, if any.
+    fn higher_restore_point_hash(
+        &self,
+        slot: Slot,
+        split_slot: Slot,
+    ) -> Result<Option<Hash256>, Error> {
+        let mut restore_point_index = slot.as_u64() / self.config.slots_per_restore_point + 1;
+        while restore_point_index * self.config.slots_per_restore_point < split_slot.as_u64() {
+            if let Some(state_root) = self.get_restore_point_hash(restore_point_index)? {
+                return Ok(Some(state_root));
+            }
+            restore_point_index += 1;
+        }
+        Ok(None)
     }
 
     /// Store the state root of a restore point.
@@ -672,6 +895,234 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
             .map_err(Into::into)
     }
 
+    /// Returns the restore point frequency of the freezer DB and the progress of reconstructing
+    /// the restore points missing since it was increased.
+    pub fn restore_point_info(&self) -> RestorePointInfo {
+        *self.restore_point_info.read()
+    }
+
+    /// Check the `SchemaVersion` of the freezer DB, migrating it from earlier versions.
+    ///
+    /// Freezer DBs created before the `SchemaVersion` was stored are at version 1 if the database
+    /// has been split, and are otherwise empty.
+    fn init_schema_version(&self, split_stored: bool) -> Result<(), Error> {
+        let key = Hash256::from_slice(SCHEMA_VERSION_DB_KEY.as_bytes());
+
+        let stored = match self.cold_db.get::<SchemaVersion>(&key)? {
+            Some(version) => version,
+            None if split_stored => SchemaVersion(1),
+            None => CURRENT_SCHEMA_VERSION,
+        };
+
+        if stored == CURRENT_SCHEMA_VERSION {
+            if !split_stored {
+                self.cold_db.put(&key, &CURRENT_SCHEMA_VERSION)?;
+            }
+            return Ok(());
+        }
+        if stored != SchemaVersion(1) {
+            return Err(HotColdDBError::UnsupportedSchemaVersion {
+                stored,
+                current: CURRENT_SCHEMA_VERSION,
+            }
+            .into());
+        }
+
+        info!(
+            self.log,
+            "Migrating freezer database";
+            "info" => "this may take a few minutes",
+            "from_version" => stored.0,
+            "to_version" => CURRENT_SCHEMA_VERSION.0,
+        );
+
+        // Version 1 restore points are stored at every index up to the latest one. The validator
+        // chunks are content-addressed and may be written more than once, so the restore points
+        // and the new version are written atomically, and an interrupted migration is re-run.
+        let mut batch = vec![];
+        let mut restore_point_index = 0;
+        while let Some(state_root) = self.get_restore_point_hash(restore_point_index)? {
+            let legacy_state = self
+                .cold_db
+                .get::<PartialBeaconStateV1<E>>(&state_root)?
+                .ok_or_else(|| HotColdDBError::MissingRestorePoint(state_root))?;
+            batch.push(
+                legacy_state
+                    .upgrade(&self.cold_db)?
+                    .as_kv_store_op(state_root),
+            );
+            restore_point_index += 1;
+        }
+        batch.push(CURRENT_SCHEMA_VERSION.as_kv_store_op(key));
+        self.cold_db.do_atomically(&batch)?;
+
+        info!(
+            self.log,
+            "Freezer database migrated";
+            "restore_points" => restore_point_index,
+        );
+        Ok(())
+    }
+
+    /// Load the `RestorePointInfo` of the freezer DB, re-indexing its restore points if
+    /// `slots_per_restore_point` has been decreased since they were stored.
+    ///
+    /// The `slots_per_restore_point` of freezer DBs created before the `RestorePointInfo` was
+    /// stored is read from the slot of their second restore point. Freezer DBs without one have
+    /// no restore points to re-index, so they take the configured `slots_per_restore_point`.
+    fn init_restore_point_info(&self) -> Result<(), Error> {
+        let key = Hash256::from_slice(RESTORE_POINT_INFO_DB_KEY.as_bytes());
+        let slots_per_restore_point = self.config.slots_per_restore_point;
+
+        let (stored_info, legacy) = match self.cold_db.get::<RestorePointInfo>(&key)? {
+            Some(info) => (Some(info), false),
+            None => (
+                self.legacy_slots_per_restore_point()?
+                    .map(RestorePointInfo::new),
+                true,
+            ),
+        };
+
+        let info = match stored_info {
+            None => {
+                let info = RestorePointInfo::new(slots_per_restore_point);
+                self.cold_db.put(&key, &info)?;
+                info
+            }
+            Some(info) if info.slots_per_restore_point == slots_per_restore_point => {
+                if legacy {
+                    self.cold_db.put(&key, &info)?;
+                }
+                info
+            }
+            Some(info) if info.slots_per_restore_point % slots_per_restore_point == 0 => {
+                let split_slot = self.get_split_slot();
+                let new_info = RestorePointInfo {
+                    slots_per_restore_point,
+                    reconstructed_slot: Slot::new(0),
+                    reconstruction_target: split_slot,
+                };
+                self.reindex_restore_points(info.slots_per_restore_point, split_slot, &new_info)?;
+
+                info!(
+                    self.log,
+                    "Restore point frequency increased";
+                    "info" => "historic states will be reconstructed in the background",
+                    "previous_slots_per_restore_point" => info.slots_per_restore_point,
+                    "slots_per_restore_point" => slots_per_restore_point,
+                    "reconstruct_until_slot" => split_slot,
+                );
+                new_info
+            }
+            Some(info) => {
+                return Err(HotColdDBError::IncreasedSlotsPerRestorePoint {
+                    stored: info.slots_per_restore_point,
+                    configured: slots_per_restore_point,
+                }
+                .into())
+            }
+        };
+
+        *self.restore_point_info.write() = info;
+        Ok(())
+    }
+
+    /// Determine the `slots_per_restore_point` of a freezer DB which predates the
+    /// `RestorePointInfo` from the slot of the restore point at index 1, if it has been stored.
+    fn legacy_slots_per_restore_point(&self) -> Result<Option<u64>, Error> {
+        let state_root = match self.get_restore_point_hash(1)? {
+            Some(state_root) => state_root,
+            None => return Ok(None),
+        };
+        let slot = self
+            .load_cold_state_slot(&state_root)?
+            .ok_or_else(|| HotColdDBError::MissingColdStateSummary(state_root))?;
+        Ok(Some(slot.as_u64()))
+    }
+
+    /// Move each restore point stored prior to `split_slot` from its index under
+    /// `old_slots_per_restore_point` to its index under the configured `slots_per_restore_point`,
+    /// storing `new_info` in the same atomic batch.
+    fn reindex_restore_points(
+        &self,
+        old_slots_per_restore_point: u64,
+        split_slot: Slot,
+        new_info: &RestorePointInfo,
+    ) -> Result<(), Error> {
+        let ratio = old_slots_per_restore_point / self.config.slots_per_restore_point;
+        let restore_point_count =
+            (split_slot.as_u64() + old_slots_per_restore_point - 1) / old_slots_per_restore_point;
+
+        // Delete every old key before writing any new key, as the indices overlap.
+        let mut batch = vec![];
+        let mut moved = vec![];
+        for old_index in 0..restore_point_count {
+            if let Some(state_root) = self.get_restore_point_hash(old_index)? {
+                batch.push(KeyValueStoreOp::DeleteKey(get_key_for_col(
+                    DBColumn::BeaconRestorePoint.into(),
+                    Self::restore_point_key(old_index).as_bytes(),
+                )));
+                moved.push((old_index * ratio, state_root));
+            }
+        }
+        for (new_index, state_root) in moved {
+            batch.push(
+                RestorePointHash { state_root }.as_kv_store_op(Self::restore_point_key(new_index)),
+            );
+        }
+        batch.push(
+            new_info.as_kv_store_op(Hash256::from_slice(RESTORE_POINT_INFO_DB_KEY.as_bytes())),
+        );
+
+        self.cold_db.do_atomically(&batch)
+    }
+
+    /// Reconstruct up to `max_restore_points` of the restore points which are missing because
+    /// `slots_per_restore_point` was decreased, by replaying blocks on the previous restore point.
+    ///
+    /// Progress is stored after each restore point, so reconstruction resumes where it left off
+    /// after a restart. Returns `true` once all restore points have been reconstructed.
+    pub fn reconstruct_restore_points(&self, max_restore_points: usize) -> Result<bool, Error> {
+        let key = Hash256::from_slice(RESTORE_POINT_INFO_DB_KEY.as_bytes());
+        let slots_per_restore_point = self.config.slots_per_restore_point;
+
+        for _ in 0..max_restore_points {
+            let mut info = self.restore_point_info();
+            if info.reconstruction_complete() {
+                break;
+            }
+
+            let slot = std::cmp::min(
+                info.reconstructed_slot + slots_per_restore_point,
+                info.reconstruction_target,
+            );
+            let restore_point_index = slot.as_u64() / slots_per_restore_point;
+
+            if slot < info.reconstruction_target
+                && self.get_restore_point_hash(restore_point_index)?.is_none()
+            {
+                let state = self.load_cold_intermediate_state(slot)?;
+                let state_root = state.canonical_root();
+
+                let _freezer_write_lock = self.freezer_write_lock.lock();
+                self.store_cold_state(&state_root, &state)?;
+            }
+
+            info.reconstructed_slot = slot;
+            self.cold_db.put(&key, &info)?;
+            *self.restore_point_info.write() = info;
+
+            debug!(
+                self.log,
+                "Reconstructed restore point";
+                "slot" => slot,
+                "target_slot" => info.reconstruction_target,
+            );
+        }
+
+        Ok(self.restore_point_info().reconstruction_complete())
+    }
+
     /// Convert a `restore_point_index` into a database key.
     fn restore_point_key(restore_point_index: u64) -> Hash256 {
         Hash256::from_low_u64_be(restore_point_index)
@@ -743,6 +1194,10 @@ pub fn process_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
         "slot" => frozen_head.slot
     );
 
+    // Hold the lock for the entire migration so that restore point reconstruction cannot update
+    // the vectors of roots at the same time.
+    let _freezer_write_lock = store.freezer_write_lock.lock();
+
     // 0. Check that the migration is sensible.
     // The new frozen head must increase the current split slot, and lie on an epoch
     // boundary (in order for the hot state summary scheme to work).
@@ -806,6 +1261,18 @@ pub fn process_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
     Ok(())
 }
 
+/// The outcome of compacting the databases.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompactionStats {
+    /// The time at which the compaction completed, in seconds since the UNIX epoch.
+    pub completed_at: u64,
+    pub duration_millis: u64,
+    /// The number of keys and bytes in each column of the hot database, keyed by column prefix.
+    pub hot_columns: BTreeMap<String, ColumnStats>,
+    /// The number of keys and bytes in each column of the freezer database.
+    pub cold_columns: BTreeMap<String, ColumnStats>,
+}
+
 /// Struct for storing the split slot and state root in the database.
 #[derive(Debug, Clone, Copy, Default, Encode, Decode)]
 struct Split {
@@ -827,6 +1294,67 @@ impl StoreItem for Split {
     }
 }
 
+/// The version of the encoding of the freezer DB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchemaVersion(pub u64);
+
+impl StoreItem for SchemaVersion {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.0.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self(u64::from_ssz_bytes(bytes)?))
+    }
+}
+
+/// The frequency with which restore points are indexed in the freezer DB, and the progress of
+/// reconstructing the restore points which are missing since the frequency was increased.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct RestorePointInfo {
+    /// The `slots_per_restore_point` by which restore points are indexed.
+    pub slots_per_restore_point: u64,
+    /// A restore point is stored at every multiple of `slots_per_restore_point` prior to this
+    /// slot, and at or after `reconstruction_target`.
+    pub reconstructed_slot: Slot,
+    /// The split slot at the time `slots_per_restore_point` was decreased. Restore points prior
+    /// to this slot were stored at the previous frequency.
+    pub reconstruction_target: Slot,
+}
+
+impl RestorePointInfo {
+    fn new(slots_per_restore_point: u64) -> Self {
+        Self {
+            slots_per_restore_point,
+            reconstructed_slot: Slot::new(0),
+            reconstruction_target: Slot::new(0),
+        }
+    }
+
+    /// Returns `true` if no restore points are missing.
+    pub fn reconstruction_complete(&self) -> bool {
+        self.reconstructed_slot >= self.reconstruction_target
+    }
+}
+
+impl StoreItem for RestorePointInfo {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
+
 /// Struct for summarising a state in the hot database.
 ///
 /// Allows full reconstruction by replaying blocks.
@@ -33,3 +33,17 @@ impl<T: EthSpec> StoreItem for SignedBeaconBlock<T> {
         result
     }
 }
+
+impl StoreItem for DepositTreeSnapshot {
+    fn db_column() -> DBColumn {
+        DBColumn::DepositSnapshot
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
@@ -2,13 +2,20 @@ use super::*;
 use crate::metrics;
 use db_key::Key;
 use leveldb::database::batch::{Batch, Writebatch};
+use leveldb::database::compaction::Compaction;
+use leveldb::database::iterator::Iterable;
 use leveldb::database::kv::KV;
 use leveldb::database::Database;
 use leveldb::error::Error as LevelDBError;
 use leveldb::options::{Options, ReadOptions, WriteOptions};
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 use std::path::Path;
 
+/// The maximum length of a key, used as the end of the range to compact. All keys are a column
+/// prefix followed by (at most) a 32 byte hash.
+const MAX_KEY_LEN: usize = 64;
+
 /// A wrapped leveldb database.
 pub struct LevelDB<E: EthSpec> {
     db: Database<BytesKey>,
@@ -102,6 +109,10 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
         let mut leveldb_batch = Writebatch::new();
         for op in ops_batch.into_iter() {
             match op {
+                KeyValueStoreOp::PutKeyValue(key, value) => {
+                    leveldb_batch.put(BytesKey::from_vec(key.to_vec()), value);
+                }
+
                 KeyValueStoreOp::DeleteKey(key) => {
                     leveldb_batch.delete(BytesKey::from_vec(key.to_vec()));
                 }
@@ -110,6 +121,22 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
         self.db.write(self.write_options(), &leveldb_batch)?;
         Ok(())
     }
+
+    /// Compact the entire key space.
+    fn compact(&self) -> Result<(), Error> {
+        let start_key = BytesKey::from_vec(vec![]);
+        let end_key = BytesKey::from_vec(vec![0xff; MAX_KEY_LEN]);
+        self.db.compact(&start_key, &end_key);
+        Ok(())
+    }
+
+    fn column_stats(&self) -> Result<BTreeMap<String, ColumnStats>, Error> {
+        let mut stats = BTreeMap::new();
+        for (key, value) in self.db.iter(self.read_options()) {
+            add_to_column_stats(&mut stats, &key.key, value.len());
+        }
+        Ok(stats)
+    }
 }
 
 impl<E: EthSpec> ItemStore<E> for LevelDB<E> {}
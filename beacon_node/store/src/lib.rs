@@ -2,8 +2,13 @@
 //!
 //! Provides the following stores:
 //!
-//! - `HotColdDB`: an on-disk store backed by leveldb. Used in production.
-//! - `MemoryStore`: an in-memory store backed by a hash-map. Used for testing.
+//! - `HotColdDB`: a store split into a hot database for unfinalized data and a freezer database
+//!   for finalized data, each of which may use any `KeyValueStore` backend.
+//! - `LevelDB`: an on-disk backend backed by leveldb. Used in production.
+//! - `MemoryStore`: an in-memory backend backed by a hash-map. Used for testing.
+//!
+//! A backend stores bytes under a key within a column (see `DBColumn`). Alternative backends may
+//! be added by implementing `KeyValueStore` and `ItemStore`.
 //!
 //! Provides a simple API for storing/retrieving all types that sometimes needs type-hints. See
 //! tests for implementation examples.
@@ -11,6 +16,7 @@
 extern crate lazy_static;
 
 pub mod chunked_iter;
+pub mod chunked_validators;
 pub mod chunked_vector;
 pub mod config;
 pub mod errors;
@@ -26,16 +32,24 @@ mod state_batch;
 pub mod iter;
 
 pub use self::config::StoreConfig;
-pub use self::hot_cold_store::{HotColdDB, HotStateSummary};
+pub use self::hot_cold_store::{
+    CompactionStats, HotColdDB, HotStateSummary, RestorePointInfo, SchemaVersion,
+};
 pub use self::leveldb_store::LevelDB;
 pub use self::memory_store::MemoryStore;
-pub use self::partial_beacon_state::PartialBeaconState;
+pub use self::partial_beacon_state::{PartialBeaconState, PartialBeaconStateV1};
 pub use errors::Error;
 pub use impls::beacon_state::StorageContainer as BeaconStateStorageContainer;
-pub use metrics::scrape_for_metrics;
+pub use metrics::{scrape_for_metrics, size_of_dir};
 pub use state_batch::StateBatch;
 pub use types::*;
 
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+
+/// The length of the prefix which identifies the column of each key, see `DBColumn`.
+const COLUMN_PREFIX_LEN: usize = 3;
+
 pub trait KeyValueStore<E: EthSpec>: Sync + Send + Sized + 'static {
     /// Retrieve some bytes in `column` with `key`.
     fn get_bytes(&self, column: &str, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
@@ -51,6 +65,31 @@ pub trait KeyValueStore<E: EthSpec>: Sync + Send + Sized + 'static {
 
     /// Execute either all of the operations in `batch` or none at all, returning an error.
     fn do_atomically(&self, batch: &[KeyValueStoreOp]) -> Result<(), Error>;
+
+    /// Reclaim the space used by deleted and overwritten values.
+    fn compact(&self) -> Result<(), Error>;
+
+    /// Returns the number of keys and bytes stored in each column, keyed by column prefix.
+    ///
+    /// This reads the entire database, so should not be called frequently.
+    fn column_stats(&self) -> Result<BTreeMap<String, ColumnStats>, Error>;
+}
+
+/// The number of keys and bytes of values stored in a column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ColumnStats {
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+/// Adds a key and its value to the stats of the column the key belongs to.
+fn add_to_column_stats(stats: &mut BTreeMap<String, ColumnStats>, key: &[u8], value_len: usize) {
+    let prefix = &key[..std::cmp::min(key.len(), COLUMN_PREFIX_LEN)];
+    let column_stats = stats
+        .entry(String::from_utf8_lossy(prefix).into_owned())
+        .or_default();
+    column_stats.keys += 1;
+    column_stats.bytes += value_len as u64;
 }
 
 pub fn get_key_for_col(column: &str, key: &[u8]) -> Vec<u8> {
@@ -59,7 +98,9 @@ pub fn get_key_for_col(column: &str, key: &[u8]) -> Vec<u8> {
     result
 }
 
+#[derive(Clone)]
 pub enum KeyValueStoreOp {
+    PutKeyValue(Vec<u8>, Vec<u8>),
     DeleteKey(Vec<u8>),
 }
 
@@ -103,9 +144,15 @@ pub trait ItemStore<E: EthSpec>: KeyValueStore<E> + Sync + Send + Sized + 'stati
 
 /// Reified key-value storage operation.  Helps in modifying the storage atomically.
 /// See also https://github.com/sigp/lighthouse/issues/692
-pub enum StoreOp {
+pub enum StoreOp<'a, E: EthSpec> {
+    PutBlock(SignedBeaconBlockHash, SignedBeaconBlock<E>),
+    /// Stores a state in the hot database, which must not be prior to the split slot.
+    PutState(BeaconStateHash, &'a BeaconState<E>),
+    PutStateSummary(BeaconStateHash, HotStateSummary),
     DeleteBlock(SignedBeaconBlockHash),
     DeleteState(BeaconStateHash, Slot),
+    /// Any other write to the hot database, e.g. an item from `StoreItem::as_kv_store_op`.
+    KeyValueOp(KeyValueStoreOp),
 }
 
 /// A unique column identifier.
@@ -128,7 +175,18 @@ pub enum DBColumn {
     BeaconStateRoots,
     BeaconHistoricalRoots,
     BeaconRandaoMixes,
+    /// For the content-addressed chunks of frozen validator registries.
+    BeaconValidatorChunks,
     DhtEnrs,
+    /// For persisting the committee caches of the shuffling cache.
+    ShufflingCache,
+    /// For the snapshot of the finalized deposit tree.
+    DepositSnapshot,
+    /// For persisting the unaggregated attestations of the naive aggregation pool.
+    NaiveAggregationPool,
+    /// For the summaries of epoch transitions, keyed by the root of the state prior to the
+    /// transition.
+    EpochSummary,
 }
 
 impl Into<&'static str> for DBColumn {
@@ -148,7 +206,12 @@ impl Into<&'static str> for DBColumn {
             DBColumn::BeaconStateRoots => "bsr",
             DBColumn::BeaconHistoricalRoots => "bhr",
             DBColumn::BeaconRandaoMixes => "brm",
+            DBColumn::BeaconValidatorChunks => "bvc",
             DBColumn::DhtEnrs => "dht",
+            DBColumn::ShufflingCache => "shu",
+            DBColumn::DepositSnapshot => "dsn",
+            DBColumn::NaiveAggregationPool => "nap",
+            DBColumn::EpochSummary => "eps",
         }
     }
 }
@@ -165,6 +228,12 @@ pub trait StoreItem: Sized {
     ///
     /// Return an instance of the type and the number of bytes that were read.
     fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error>;
+
+    /// Returns the operation which stores `self` under `key`, for use in an atomic batch.
+    fn as_kv_store_op(&self, key: Hash256) -> KeyValueStoreOp {
+        let db_key = get_key_for_col(Self::db_column().into(), key.as_bytes());
+        KeyValueStoreOp::PutKeyValue(db_key, self.as_store_bytes())
+    }
 }
 
 #[cfg(test)]
@@ -230,6 +299,49 @@ mod tests {
         test_impl(store);
     }
 
+    #[test]
+    fn do_atomically() {
+        let store = MemoryStore::<MinimalEthSpec>::open();
+        let existing_key = Hash256::random();
+        let new_key = Hash256::random();
+        let item = StorableThing { a: 1, b: 42 };
+
+        store.put(&existing_key, &item).unwrap();
+
+        store
+            .do_atomically(&[
+                KeyValueStoreOp::DeleteKey(get_key_for_col(
+                    StorableThing::db_column().into(),
+                    existing_key.as_bytes(),
+                )),
+                item.as_kv_store_op(new_key),
+            ])
+            .unwrap();
+
+        assert_eq!(store.exists::<StorableThing>(&existing_key).unwrap(), false);
+        assert_eq!(store.get(&new_key).unwrap(), Some(item));
+    }
+
+    #[test]
+    fn column_stats() {
+        let store = MemoryStore::<MinimalEthSpec>::open();
+        let item = StorableThing { a: 1, b: 42 };
+
+        store.put(&Hash256::random(), &item).unwrap();
+        store.put(&Hash256::random(), &item).unwrap();
+
+        let stats = store.column_stats().unwrap();
+        let column: &str = StorableThing::db_column().into();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(
+            stats[column],
+            ColumnStats {
+                keys: 2,
+                bytes: 2 * item.as_ssz_bytes().len() as u64,
+            }
+        );
+    }
+
     #[test]
     fn exists() {
         let store = MemoryStore::<MinimalEthSpec>::open();
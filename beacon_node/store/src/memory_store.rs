@@ -1,6 +1,6 @@
-use super::{Error, ItemStore, KeyValueStore, KeyValueStoreOp};
+use super::{add_to_column_stats, ColumnStats, Error, ItemStore, KeyValueStore, KeyValueStoreOp};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::marker::PhantomData;
 use types::*;
 
@@ -65,15 +65,34 @@ impl<E: EthSpec> KeyValueStore<E> for MemoryStore<E> {
     }
 
     fn do_atomically(&self, batch: &[KeyValueStoreOp]) -> Result<(), Error> {
+        // Hold the lock for the whole batch so that readers never observe a partial write.
+        let mut db = self.db.write();
         for op in batch {
             match op {
+                KeyValueStoreOp::PutKeyValue(key, value) => {
+                    db.insert(key.clone(), value.clone());
+                }
+
                 KeyValueStoreOp::DeleteKey(hash) => {
-                    self.db.write().remove(hash);
+                    db.remove(hash);
                 }
             }
         }
         Ok(())
     }
+
+    /// Deleted values are freed immediately, so there is nothing to compact.
+    fn compact(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn column_stats(&self) -> Result<BTreeMap<String, ColumnStats>, Error> {
+        let mut stats = BTreeMap::new();
+        for (key, value) in self.db.read().iter() {
+            add_to_column_stats(&mut stats, key, value.len());
+        }
+        Ok(stats)
+    }
 }
 
 impl<E: EthSpec> ItemStore<E> for MemoryStore<E> {}
@@ -43,6 +43,24 @@ lazy_static! {
         "store_disk_db_delete_count_total",
         "Total number of deletions from the hot on-disk DB"
     );
+    pub static ref DISK_DB_COMPACTION_COUNT: Result<IntCounter> = try_create_int_counter(
+        "store_disk_db_compaction_total",
+        "Total number of compactions of the hot and freezer DBs"
+    );
+    pub static ref DISK_DB_COMPACTION_TIMES: Result<Histogram> = try_create_histogram(
+        "store_disk_db_compaction_seconds",
+        "Time taken to compact the hot and freezer DBs"
+    );
+    pub static ref DISK_DB_COLUMN_KEYS: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "store_disk_db_column_keys",
+        "Number of keys in each column, as of the last compaction",
+        &["db", "col"]
+    );
+    pub static ref DISK_DB_COLUMN_BYTES: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "store_disk_db_column_bytes",
+        "Number of bytes of values in each column, as of the last compaction",
+        &["db", "col"]
+    );
     /*
      * Beacon State
      */
@@ -94,6 +112,10 @@ lazy_static! {
         "store_beacon_state_write_bytes_total",
         "Total number of beacon state bytes written to the DB"
     );
+    pub static ref BEACON_VALIDATOR_CHUNK_WRITE_COUNT: Result<IntCounter> = try_create_int_counter(
+        "store_beacon_validator_chunk_write_total",
+        "Total number of new validator registry chunks written to the freezer DB"
+    );
     /*
      * Beacon Block
      */
@@ -139,7 +161,8 @@ pub fn scrape_for_metrics(db_path: &Path, freezer_db_path: &Path) {
     set_gauge(&FREEZER_DB_SIZE, freezer_db_size as i64);
 }
 
-fn size_of_dir(path: &Path) -> u64 {
+/// Returns the sum of the sizes of all files in the directory at `path`.
+pub fn size_of_dir(path: &Path) -> u64 {
     if let Ok(iter) = fs::read_dir(path) {
         iter.filter_map(std::result::Result::ok)
             .map(size_of_dir_entry)
@@ -1,3 +1,4 @@
+use crate::chunked_validators::{load_validators_from_chunks, store_validator_chunks};
 use crate::chunked_vector::{
     load_variable_list_from_db, load_vector_from_db, BlockRoots, HistoricalRoots, RandaoMixes,
     StateRoots,
@@ -9,7 +10,7 @@ use types::*;
 
 /// Lightweight variant of the `BeaconState` that is stored in the database.
 ///
-/// Utilises lazy-loading from separate storage for its vector fields.
+/// Utilises lazy-loading from separate storage for its vector fields and validator registry.
 ///
 /// Spec v0.11.1
 #[derive(Debug, PartialEq, Clone, Encode, Decode)]
@@ -43,7 +44,11 @@ where
     pub eth1_deposit_index: u64,
 
     // Registry
-    pub validators: VariableList<Validator, T::ValidatorRegistryLimit>,
+    /// Roots of the chunks which make up the `validators` list, see `chunked_validators`.
+    pub validator_chunk_roots: Vec<Hash256>,
+    #[ssz(skip_serializing)]
+    #[ssz(skip_deserializing)]
+    pub validators: Option<VariableList<Validator, T::ValidatorRegistryLimit>>,
     pub balances: VariableList<u64, T::ValidatorRegistryLimit>,
 
     // Shuffling
@@ -69,7 +74,10 @@ where
 
 impl<T: EthSpec> PartialBeaconState<T> {
     /// Convert a `BeaconState` to a `PartialBeaconState`, while dropping the optional fields.
-    pub fn from_state_forgetful(s: &BeaconState<T>) -> Self {
+    ///
+    /// The `validator_chunk_roots` should be the result of storing `s.validators` with
+    /// `store_validator_chunks`.
+    pub fn from_state_forgetful(s: &BeaconState<T>, validator_chunk_roots: Vec<Hash256>) -> Self {
         // TODO: could use references/Cow for fields to avoid cloning
         PartialBeaconState {
             genesis_time: s.genesis_time,
@@ -89,7 +97,8 @@ impl<T: EthSpec> PartialBeaconState<T> {
             eth1_deposit_index: s.eth1_deposit_index,
 
             // Validator registry
-            validators: s.validators.clone(),
+            validator_chunk_roots,
+            validators: None,
             balances: s.balances.clone(),
 
             // Shuffling
@@ -152,6 +161,16 @@ impl<T: EthSpec> PartialBeaconState<T> {
         Ok(())
     }
 
+    pub fn load_validators<S: KeyValueStore<T>>(&mut self, store: &S) -> Result<(), Error> {
+        if self.validators.is_none() {
+            self.validators = Some(load_validators_from_chunks::<T, _>(
+                store,
+                &self.validator_chunk_roots,
+            )?);
+        }
+        Ok(())
+    }
+
     pub fn load_randao_mixes<S: KeyValueStore<T>>(
         &mut self,
         store: &S,
@@ -199,7 +218,7 @@ impl<E: EthSpec> TryInto<BeaconState<E>> for PartialBeaconState<E> {
             eth1_deposit_index: self.eth1_deposit_index,
 
             // Validator registry
-            validators: self.validators,
+            validators: unpack(self.validators)?,
             balances: self.balances,
 
             // Shuffling
@@ -226,3 +245,120 @@ impl<E: EthSpec> TryInto<BeaconState<E>> for PartialBeaconState<E> {
         })
     }
 }
+
+/// The encoding of `PartialBeaconState` in schema version 1 of the freezer DB, in which every
+/// restore point stored a full copy of the validator registry.
+///
+/// Only used to migrate restore points to the current schema, see `HotColdDB::open`.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct PartialBeaconStateV1<T>
+where
+    T: EthSpec,
+{
+    // Versioning
+    pub genesis_time: u64,
+    pub genesis_validators_root: Hash256,
+    pub slot: Slot,
+    pub fork: Fork,
+
+    // History
+    pub latest_block_header: BeaconBlockHeader,
+
+    // Ethereum 1.0 chain data
+    pub eth1_data: Eth1Data,
+    pub eth1_data_votes: VariableList<Eth1Data, T::SlotsPerEth1VotingPeriod>,
+    pub eth1_deposit_index: u64,
+
+    // Registry
+    pub validators: VariableList<Validator, T::ValidatorRegistryLimit>,
+    pub balances: VariableList<u64, T::ValidatorRegistryLimit>,
+
+    // Shuffling
+    pub latest_randao_value: Hash256,
+
+    // Slashings
+    pub slashings: FixedVector<u64, T::EpochsPerSlashingsVector>,
+
+    // Attestations
+    pub previous_epoch_attestations: VariableList<PendingAttestation<T>, T::MaxPendingAttestations>,
+    pub current_epoch_attestations: VariableList<PendingAttestation<T>, T::MaxPendingAttestations>,
+
+    // Finality
+    pub justification_bits: BitVector<T::JustificationBitsLength>,
+    pub previous_justified_checkpoint: Checkpoint,
+    pub current_justified_checkpoint: Checkpoint,
+    pub finalized_checkpoint: Checkpoint,
+}
+
+impl<T: EthSpec> PartialBeaconStateV1<T> {
+    /// Convert a `BeaconState` to the schema version 1 encoding of a restore point.
+    pub fn from_state_forgetful(s: &BeaconState<T>) -> Self {
+        PartialBeaconStateV1 {
+            genesis_time: s.genesis_time,
+            genesis_validators_root: s.genesis_validators_root,
+            slot: s.slot,
+            fork: s.fork.clone(),
+            latest_block_header: s.latest_block_header.clone(),
+            eth1_data: s.eth1_data.clone(),
+            eth1_data_votes: s.eth1_data_votes.clone(),
+            eth1_deposit_index: s.eth1_deposit_index,
+            validators: s.validators.clone(),
+            balances: s.balances.clone(),
+            latest_randao_value: *s
+                .get_randao_mix(s.current_epoch())
+                .expect("randao at current epoch is OK"),
+            slashings: s.get_all_slashings().to_vec().into(),
+            previous_epoch_attestations: s.previous_epoch_attestations.clone(),
+            current_epoch_attestations: s.current_epoch_attestations.clone(),
+            justification_bits: s.justification_bits.clone(),
+            previous_justified_checkpoint: s.previous_justified_checkpoint.clone(),
+            current_justified_checkpoint: s.current_justified_checkpoint.clone(),
+            finalized_checkpoint: s.finalized_checkpoint.clone(),
+        }
+    }
+
+    /// Convert to the current encoding, storing the chunks of the validator registry in `store`.
+    pub fn upgrade<S: KeyValueStore<T>>(self, store: &S) -> Result<PartialBeaconState<T>, Error> {
+        let validator_chunk_roots = store_validator_chunks(store, &self.validators)?;
+
+        Ok(PartialBeaconState {
+            genesis_time: self.genesis_time,
+            genesis_validators_root: self.genesis_validators_root,
+            slot: self.slot,
+            fork: self.fork,
+
+            // History
+            latest_block_header: self.latest_block_header,
+            block_roots: None,
+            state_roots: None,
+            historical_roots: None,
+
+            // Eth1
+            eth1_data: self.eth1_data,
+            eth1_data_votes: self.eth1_data_votes,
+            eth1_deposit_index: self.eth1_deposit_index,
+
+            // Validator registry
+            validator_chunk_roots,
+            validators: None,
+            balances: self.balances,
+
+            // Shuffling
+            latest_randao_value: self.latest_randao_value,
+            randao_mixes: None,
+
+            // Slashings
+            slashings: self.slashings,
+
+            // Attestations
+            previous_epoch_attestations: self.previous_epoch_attestations,
+            current_epoch_attestations: self.current_epoch_attestations,
+
+            // Finality
+            justification_bits: self.justification_bits,
+            previous_justified_checkpoint: self.previous_justified_checkpoint,
+            current_justified_checkpoint: self.current_justified_checkpoint,
+            finalized_checkpoint: self.finalized_checkpoint,
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Error, HotColdDB, HotStateSummary, ItemStore};
+use crate::{Error, HotStateSummary, StoreOp};
 use types::{BeaconState, EthSpec, Hash256};
 
 /// A collection of states to be stored in the database.
@@ -33,18 +33,19 @@ impl<E: EthSpec> StateBatch<E> {
         Ok(())
     }
 
-    /// Write the batch to the database.
-    ///
-    /// May fail to write the full batch if any of the items error (i.e. not atomic!)
-    pub fn commit<Hot: ItemStore<E>, Cold: ItemStore<E>>(
-        self,
-        store: &HotColdDB<E, Hot, Cold>,
-    ) -> Result<(), Error> {
-        self.items.into_iter().try_for_each(|item| match item {
-            BatchItem::Full(state_root, state) => store.put_state(&state_root, &state),
-            BatchItem::Summary(state_root, summary) => {
-                store.put_state_summary(&state_root, summary)
-            }
-        })
+    /// Returns the operations which store the batch, to be applied with
+    /// `HotColdDB::do_atomically`.
+    pub fn as_store_ops(&self) -> Vec<StoreOp<E>> {
+        self.items
+            .iter()
+            .map(|item| match item {
+                BatchItem::Full(state_root, state) => {
+                    StoreOp::PutState((*state_root).into(), state)
+                }
+                BatchItem::Summary(state_root, summary) => {
+                    StoreOp::PutStateSummary((*state_root).into(), *summary)
+                }
+            })
+            .collect()
     }
 }
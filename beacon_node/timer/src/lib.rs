@@ -4,7 +4,7 @@
 
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use futures::stream::StreamExt;
-use slog::info;
+use slog::{debug, info};
 use slot_clock::SlotClock;
 use std::sync::Arc;
 use std::time::Duration;
@@ -25,9 +25,27 @@ pub fn spawn_timer<T: BeaconChainTypes>(
 
     // Warning: `interval_at` panics if `milliseconds_per_slot` = 0.
     let mut interval = interval_at(start_instant, Duration::from_millis(milliseconds_per_slot));
+    let inner_executor = executor.clone();
     let timer_future = async move {
         while interval.next().await.is_some() {
             beacon_chain.per_slot_task();
+
+            // Advancing the head state may involve epoch processing, so it is done on a blocking
+            // thread to avoid stalling the runtime.
+            let chain = beacon_chain.clone();
+            let log = inner_executor.log().clone();
+            inner_executor.spawn_blocking(
+                move || {
+                    if let Err(e) = chain.advance_head_state() {
+                        debug!(
+                            log,
+                            "Unable to advance head state";
+                            "error" => format!("{:?}", e)
+                        );
+                    }
+                },
+                "advance_head_state",
+            );
         }
     };
 
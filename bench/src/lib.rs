@@ -0,0 +1,123 @@
+//! Provides the `lighthouse bench` subcommand, which runs a standard set of workloads and prints
+//! their timings.
+//!
+//! The workloads are deterministic (except for BLS key generation where noted), so the results
+//! may be compared between machines or between Lighthouse versions.
+
+mod report;
+mod workloads;
+
+use clap::{App, Arg, ArgMatches};
+use environment::Environment;
+use report::Report;
+use slog::info;
+use types::EthSpec;
+
+pub const CMD: &str = "bench";
+pub const WORKLOAD_FLAG: &str = "workload";
+pub const VALIDATOR_COUNT_FLAG: &str = "validator-count";
+pub const KEYPAIR_COUNT_FLAG: &str = "keypair-count";
+pub const RUNS_FLAG: &str = "runs";
+
+pub const EPOCH_PROCESSING: &str = "epoch_processing";
+pub const BLOCK_PROCESSING: &str = "block_processing";
+pub const ATTESTATION_VERIFICATION: &str = "attestation_verification";
+pub const GOSSIP_DECODE: &str = "gossip_decode";
+
+pub const WORKLOADS: &[&str] = &[
+    EPOCH_PROCESSING,
+    BLOCK_PROCESSING,
+    ATTESTATION_VERIFICATION,
+    GOSSIP_DECODE,
+];
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD)
+        .about(
+            "Runs standardized state transition and networking workloads and prints a table of \
+            their timings. Useful for comparing hardware and detecting performance regressions.",
+        )
+        .arg(
+            Arg::with_name(WORKLOAD_FLAG)
+                .long(WORKLOAD_FLAG)
+                .value_name("NAME")
+                .help("Only run the given workloads. Runs all workloads if not supplied.")
+                .possible_values(WORKLOADS)
+                .multiple(true)
+                .use_delimiter(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(VALIDATOR_COUNT_FLAG)
+                .long(VALIDATOR_COUNT_FLAG)
+                .value_name("INTEGER")
+                .help(
+                    "The number of validators in the state used for epoch processing. The \
+                    default is similar in size to the mainnet validator registry.",
+                )
+                .default_value("300032")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(KEYPAIR_COUNT_FLAG)
+                .long(KEYPAIR_COUNT_FLAG)
+                .value_name("INTEGER")
+                .help(
+                    "The number of validators in the state used for workloads which require \
+                    valid signatures. A keypair is generated for each validator, so large values \
+                    are slow to set up.",
+                )
+                .default_value("16384")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(RUNS_FLAG)
+                .long(RUNS_FLAG)
+                .value_name("INTEGER")
+                .help("The number of times each workload is measured.")
+                .default_value("10")
+                .takes_value(true),
+        )
+}
+
+/// Run the benchmarks, printing the results to stdout.
+pub fn run<T: EthSpec>(matches: &ArgMatches<'_>, mut env: Environment<T>) -> Result<(), String> {
+    let validator_count: usize = clap_utils::parse_required(matches, VALIDATOR_COUNT_FLAG)?;
+    let keypair_count: usize = clap_utils::parse_required(matches, KEYPAIR_COUNT_FLAG)?;
+    let runs: usize = clap_utils::parse_required(matches, RUNS_FLAG)?;
+    let selected = matches
+        .values_of(WORKLOAD_FLAG)
+        .map(|values| values.collect::<Vec<_>>())
+        .unwrap_or_else(|| WORKLOADS.to_vec());
+
+    if runs == 0 {
+        return Err(format!("--{} must be greater than zero", RUNS_FLAG));
+    }
+
+    let log = env.core_context().log().clone();
+    let spec = env.eth2_config.spec.clone();
+    let mut report = Report::default();
+
+    for workload in WORKLOADS.iter().filter(|name| selected.contains(*name)) {
+        info!(log, "Running workload"; "name" => workload, "runs" => runs);
+
+        match *workload {
+            EPOCH_PROCESSING => {
+                workloads::epoch_processing::<T>(&mut report, validator_count, runs, &spec)?
+            }
+            BLOCK_PROCESSING => {
+                workloads::block_processing::<T>(&mut report, keypair_count, runs, &spec)?
+            }
+            ATTESTATION_VERIFICATION => workloads::attestation_verification(&mut report, runs)?,
+            GOSSIP_DECODE => {
+                workloads::gossip_decode::<T>(&mut report, keypair_count, runs, &spec)?
+            }
+            unknown => return Err(format!("Unknown workload: {}", unknown)),
+        }
+    }
+
+    println!("BLS backend: {}", bls::BACKEND_NAME);
+    println!("{}", report);
+
+    Ok(())
+}
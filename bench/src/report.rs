@@ -0,0 +1,109 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The timings of a single workload.
+struct Measurement {
+    name: String,
+    durations: Vec<Duration>,
+}
+
+impl Measurement {
+    fn min(&self) -> Duration {
+        self.durations.iter().min().copied().unwrap_or_default()
+    }
+
+    fn max(&self) -> Duration {
+        self.durations.iter().max().copied().unwrap_or_default()
+    }
+
+    fn mean(&self) -> Duration {
+        if self.durations.is_empty() {
+            Duration::default()
+        } else {
+            self.durations.iter().sum::<Duration>() / self.durations.len() as u32
+        }
+    }
+}
+
+/// Collects the timings of each workload and displays them as a table.
+#[derive(Default)]
+pub struct Report {
+    measurements: Vec<Measurement>,
+}
+
+impl Report {
+    /// Runs `setup` followed by `routine` for `runs` times, timing only `routine`.
+    ///
+    /// Returns early with an error if `routine` returns an error.
+    pub fn measure<I, O, S, R>(
+        &mut self,
+        name: String,
+        runs: usize,
+        mut setup: S,
+        mut routine: R,
+    ) -> Result<(), String>
+    where
+        S: FnMut() -> I,
+        R: FnMut(&mut I) -> Result<O, String>,
+    {
+        let mut durations = Vec::with_capacity(runs);
+
+        for _ in 0..runs {
+            let mut input = setup();
+
+            let start = Instant::now();
+            let output =
+                routine(&mut input).map_err(|e| format!("Workload {} failed: {}", name, e))?;
+            durations.push(start.elapsed());
+
+            // Ensure the output is not dropped whilst the timer is running.
+            drop(output);
+        }
+
+        self.measurements.push(Measurement { name, durations });
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self
+            .measurements
+            .iter()
+            .map(|m| m.name.len())
+            .chain(std::iter::once("workload".len()))
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:<width$}  {:>5}  {:>12}  {:>12}  {:>12}",
+            "workload",
+            "runs",
+            "min (ms)",
+            "mean (ms)",
+            "max (ms)",
+            width = name_width
+        )?;
+
+        for m in &self.measurements {
+            writeln!(
+                f,
+                "{:<width$}  {:>5}  {:>12.3}  {:>12.3}  {:>12.3}",
+                m.name,
+                m.durations.len(),
+                as_millis(m.min()),
+                as_millis(m.mean()),
+                as_millis(m.max()),
+                width = name_width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000.0
+}
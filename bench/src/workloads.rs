@@ -0,0 +1,203 @@
+use crate::report::Report;
+use bls::{AggregateSignature, Keypair, Signature, SignatureSet};
+use eth2_libp2p::types::GossipEncoding;
+use eth2_libp2p::{NetworkConstants, PubsubMessage, Topic, TopicHash};
+use state_processing::test_utils::BlockBuilder;
+use state_processing::{per_block_processing, per_epoch_processing, BlockSignatureStrategy};
+use std::borrow::Cow;
+use types::test_utils::{generate_deterministic_keypairs, TestingBeaconStateBuilder};
+use types::{BeaconState, ChainSpec, EthSpec, Hash256, SignedBeaconBlock, Slot, SubnetId};
+
+/// The number of signatures in each aggregate for `attestation_verification`.
+const ATTESTATION_COMMITTEE_SIZE: usize = 128;
+/// The number of aggregates verified at once by `attestation_verification`.
+const ATTESTATION_BATCH_SIZE: usize = 64;
+/// The number of attestations included in the block used for `block_processing`.
+const BLOCK_ATTESTATIONS: usize = 16;
+
+/// Times `per_epoch_processing` on a state with `validator_count` validators and full
+/// participation in the previous and current epochs.
+///
+/// All validators share a single keypair, since signatures are not verified during epoch
+/// processing.
+pub fn epoch_processing<T: EthSpec>(
+    report: &mut Report,
+    validator_count: usize,
+    runs: usize,
+    spec: &ChainSpec,
+) -> Result<(), String> {
+    let mut builder = TestingBeaconStateBuilder::<T>::from_single_keypair(
+        validator_count,
+        &Keypair::random(),
+        spec,
+    );
+    // Use the last slot of an epoch late enough for justification and finalization to occur.
+    builder.teleport_to_slot(Slot::from(T::slots_per_epoch() * 4 - 1));
+    builder.insert_attestations(spec);
+    builder
+        .build_caches(spec)
+        .map_err(|e| format!("Unable to build caches: {:?}", e))?;
+    let (state, _) = builder.build();
+
+    report.measure(
+        format!("epoch_processing/{}_validators", validator_count),
+        runs,
+        || state.clone(),
+        |state| per_epoch_processing(state, spec).map_err(|e| format!("{:?}", e)),
+    )
+}
+
+/// Times `per_block_processing` on a block with a typical number of attestations, using each of
+/// the signature verification strategies.
+pub fn block_processing<T: EthSpec>(
+    report: &mut Report,
+    keypair_count: usize,
+    runs: usize,
+    spec: &ChainSpec,
+) -> Result<(), String> {
+    let mut builder = BlockBuilder::<T>::new(keypair_count, spec);
+    builder.num_attestations = BLOCK_ATTESTATIONS;
+    let (block, state) = build_block(builder, spec);
+
+    let strategies = &[
+        (
+            "no_signature_verification",
+            BlockSignatureStrategy::NoVerification,
+        ),
+        (
+            "individual_signature_verification",
+            BlockSignatureStrategy::VerifyIndividual,
+        ),
+        (
+            "bulk_signature_verification",
+            BlockSignatureStrategy::VerifyBulk,
+        ),
+    ];
+
+    for (desc, strategy) in strategies {
+        report.measure(
+            format!("block_processing/{}_validators/{}", keypair_count, desc),
+            runs,
+            || state.clone(),
+            |state| {
+                per_block_processing(state, &block, None, *strategy, spec)
+                    .map_err(|e| format!("{:?}", e))
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Times the verification of a batch of aggregate signatures, each of which has been signed by
+/// an entire committee, both one-by-one and as a single batch.
+pub fn attestation_verification(report: &mut Report, runs: usize) -> Result<(), String> {
+    let keypairs = generate_deterministic_keypairs(ATTESTATION_COMMITTEE_SIZE);
+
+    let signature_sets = (0..ATTESTATION_BATCH_SIZE)
+        .map(|i| {
+            let message = Hash256::from_low_u64_be(i as u64).as_bytes().to_vec();
+
+            let mut signature = AggregateSignature::new();
+            for keypair in &keypairs {
+                signature.add(&Signature::new(&message, &keypair.sk));
+            }
+
+            let signing_keys = keypairs.iter().map(|kp| Cow::Borrowed(&kp.pk)).collect();
+
+            SignatureSet::new(&signature, signing_keys, message)
+        })
+        .collect::<Vec<_>>();
+
+    let desc = format!(
+        "{}_aggregates_of_{}",
+        ATTESTATION_BATCH_SIZE, ATTESTATION_COMMITTEE_SIZE
+    );
+
+    report.measure(
+        format!("attestation_verification/{}/individual", desc),
+        runs,
+        || (),
+        |_| verify(signature_sets.iter().all(SignatureSet::is_valid)),
+    )?;
+
+    report.measure(
+        format!("attestation_verification/{}/batch", desc),
+        runs,
+        || signature_sets.clone(),
+        |sets| verify(bls::verify_signature_sets(std::mem::replace(sets, vec![]))),
+    )
+}
+
+/// Times the decoding of gossip messages, as they are received from the network (i.e.,
+/// snappy-compressed SSZ).
+pub fn gossip_decode<T: EthSpec>(
+    report: &mut Report,
+    keypair_count: usize,
+    runs: usize,
+    spec: &ChainSpec,
+) -> Result<(), String> {
+    let gossip_max_size = NetworkConstants::from_spec(spec).gossip_max_size;
+
+    let mut builder = BlockBuilder::<T>::new(keypair_count, spec);
+    builder.maximize_block_operations();
+    // Deposits cannot be generated with valid proofs.
+    builder.num_deposits = 0;
+    let (block, _) = build_block(builder, spec);
+
+    let attestation = block
+        .message
+        .body
+        .attestations
+        .first()
+        .cloned()
+        .ok_or_else(|| "Block has no attestations".to_string())?;
+
+    let messages = vec![
+        ("full_block", PubsubMessage::BeaconBlock(Box::new(block))),
+        (
+            "attestation",
+            PubsubMessage::Attestation(Box::new((SubnetId::new(0), attestation))),
+        ),
+    ];
+
+    for (desc, message) in messages {
+        let topics = message
+            .topics(GossipEncoding::SSZSnappy, [0; 4])
+            .into_iter()
+            .map(|topic| {
+                let topic: Topic = topic.into();
+                topic.no_hash()
+            })
+            .collect::<Vec<TopicHash>>();
+        let data = message.encode(GossipEncoding::SSZSnappy, gossip_max_size)?;
+
+        report.measure(
+            format!("gossip_decode/{}", desc),
+            runs,
+            || (),
+            |_| PubsubMessage::<T>::decode(&topics, &data, gossip_max_size),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Builds a block and the state it should be applied to, at a slot where there are attestations
+/// available for inclusion.
+fn build_block<T: EthSpec>(
+    mut builder: BlockBuilder<T>,
+    spec: &ChainSpec,
+) -> (SignedBeaconBlock<T>, BeaconState<T>) {
+    builder.set_slot(Slot::from(T::slots_per_epoch() * 3 - 2));
+    builder.build_caches(spec);
+    builder.build(spec)
+}
+
+fn verify(is_valid: bool) -> Result<(), String> {
+    if is_valid {
+        Ok(())
+    } else {
+        Err("Invalid signature".to_string())
+    }
+}
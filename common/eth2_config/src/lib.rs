@@ -1,3 +1,4 @@
+use eth2_hashing::hash;
 use serde_derive::{Deserialize, Serialize};
 use types::ChainSpec;
 
@@ -41,6 +42,61 @@ impl Eth2Config {
     }
 }
 
+/// Overrides of selected `ChainSpec` parameters, for ephemeral development networks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainSpecOverrides {
+    pub seconds_per_slot: Option<u64>,
+    pub genesis_delay: Option<u64>,
+}
+
+impl ChainSpecOverrides {
+    /// Returns true if no parameter is overridden.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Applies the overrides to `spec`, returning an error if the result is inconsistent.
+    ///
+    /// Nodes with different overrides can't follow the same chain, so the genesis fork version is
+    /// replaced by one derived from the overridden parameters. This gives the network a distinct
+    /// fork digest, which keeps it apart from nodes using other parameters on gossip and in
+    /// discovery. Deposits must be signed with the same overrides.
+    pub fn apply(&self, spec: &mut ChainSpec) -> Result<(), String> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(seconds_per_slot) = self.seconds_per_slot {
+            let milliseconds_per_slot = seconds_per_slot
+                .checked_mul(1_000)
+                .ok_or_else(|| "Seconds per slot is too large".to_string())?;
+            if milliseconds_per_slot <= spec.maximum_gossip_clock_disparity_millis {
+                return Err(format!(
+                    "Seconds per slot must be greater than the maximum gossip clock disparity \
+                     of {}ms",
+                    spec.maximum_gossip_clock_disparity_millis
+                ));
+            }
+            spec.milliseconds_per_slot = milliseconds_per_slot;
+        }
+
+        if let Some(genesis_delay) = self.genesis_delay {
+            if genesis_delay == 0 {
+                return Err("Genesis delay must be greater than zero".to_string());
+            }
+            spec.min_genesis_delay = genesis_delay;
+        }
+
+        let mut preimage = spec.genesis_fork_version.to_vec();
+        preimage.extend_from_slice(&spec.milliseconds_per_slot.to_le_bytes());
+        preimage.extend_from_slice(&spec.min_genesis_delay.to_le_bytes());
+        spec.genesis_fork_version
+            .copy_from_slice(&hash(&preimage)[0..4]);
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +107,56 @@ mod tests {
         let _ =
             toml::to_string(&Eth2Config::default()).expect("Should serde encode default config");
     }
+
+    #[test]
+    fn chain_spec_overrides() {
+        let mut spec = ChainSpec::minimal();
+        ChainSpecOverrides::default()
+            .apply(&mut spec)
+            .expect("should apply no overrides");
+        assert_eq!(spec, ChainSpec::minimal());
+
+        let overrides = ChainSpecOverrides {
+            seconds_per_slot: Some(2),
+            genesis_delay: Some(30),
+        };
+        overrides.apply(&mut spec).expect("should apply overrides");
+        assert_eq!(spec.milliseconds_per_slot, 2_000);
+        assert_eq!(spec.min_genesis_delay, 30);
+        assert_ne!(
+            spec.genesis_fork_version,
+            ChainSpec::minimal().genesis_fork_version
+        );
+
+        // The same overrides give the same fork version, different overrides do not.
+        let mut same = ChainSpec::minimal();
+        overrides.apply(&mut same).expect("should apply overrides");
+        assert_eq!(same.genesis_fork_version, spec.genesis_fork_version);
+
+        let mut different = ChainSpec::minimal();
+        ChainSpecOverrides {
+            seconds_per_slot: Some(3),
+            ..overrides
+        }
+        .apply(&mut different)
+        .expect("should apply overrides");
+        assert_ne!(different.genesis_fork_version, spec.genesis_fork_version);
+    }
+
+    #[test]
+    fn invalid_chain_spec_overrides() {
+        let mut spec = ChainSpec::minimal();
+        assert!(ChainSpecOverrides {
+            seconds_per_slot: Some(0),
+            ..ChainSpecOverrides::default()
+        }
+        .apply(&mut spec)
+        .is_err());
+        assert!(ChainSpecOverrides {
+            genesis_delay: Some(0),
+            ..ChainSpecOverrides::default()
+        }
+        .apply(&mut spec)
+        .is_err());
+    }
 }
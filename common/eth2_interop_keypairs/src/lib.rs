@@ -19,8 +19,8 @@
 #[macro_use]
 extern crate lazy_static;
 
+use bls::{Keypair, PublicKey, SecretKey};
 use eth2_hashing::hash;
-use milagro_bls::{Keypair, PublicKey, SecretKey};
 use num_bigint::BigUint;
 use serde_derive::{Deserialize, Serialize};
 use std::convert::TryInto;
@@ -57,8 +57,8 @@
 use prometheus::{HistogramOpts, HistogramTimer, Opts};
 
 pub use prometheus::{
-    Encoder, Gauge, GaugeVec, Histogram, HistogramVec, IntCounter, IntGauge, IntGaugeVec, Result,
-    TextEncoder,
+    Encoder, Gauge, GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Result, TextEncoder,
 };
 
 /// Collect all the metrics for reporting.
@@ -115,6 +115,19 @@ pub fn try_create_histogram_vec(
     Ok(histogram_vec)
 }
 
+/// Attempts to crate a `IntCounterVec`, returning `Err` if the registry does not accept the counter
+/// (potentially due to naming conflict).
+pub fn try_create_int_counter_vec(
+    name: &str,
+    help: &str,
+    label_names: &[&str],
+) -> Result<IntCounterVec> {
+    let opts = Opts::new(name, help);
+    let counter_vec = IntCounterVec::new(opts, label_names)?;
+    prometheus::register(Box::new(counter_vec.clone()))?;
+    Ok(counter_vec)
+}
+
 /// Attempts to crate a `IntGaugeVec`, returning `Err` if the registry does not accept the gauge
 /// (potentially due to naming conflict).
 pub fn try_create_int_gauge_vec(
@@ -166,6 +179,15 @@ pub fn start_timer(histogram: &Result<Histogram>) -> Option<HistogramTimer> {
     }
 }
 
+/// Starts a timer for the `Histogram` in `histogram_vec` with the given label values, stopping
+/// when it gets dropped or given to `stop_timer(..)`.
+pub fn start_timer_vec(
+    histogram_vec: &Result<HistogramVec>,
+    name: &[&str],
+) -> Option<HistogramTimer> {
+    get_histogram(histogram_vec, name).map(|histogram| histogram.start_timer())
+}
+
 /// Stops a timer created with `start_timer(..)`.
 pub fn stop_timer(timer: Option<HistogramTimer>) {
     if let Some(t) = timer {
@@ -179,6 +201,14 @@ pub fn inc_counter(counter: &Result<IntCounter>) {
     }
 }
 
+pub fn inc_counter_vec(int_counter_vec: &Result<IntCounterVec>, name: &[&str]) {
+    if let Ok(int_counter_vec) = int_counter_vec {
+        if let Ok(counter) = int_counter_vec.get_metric_with_label_values(name) {
+            counter.inc();
+        }
+    }
+}
+
 pub fn inc_counter_by(counter: &Result<IntCounter>, value: i64) {
     if let Ok(counter) = counter {
         counter.inc_by(value);
@@ -4,8 +4,13 @@ extern crate lazy_static;
 use lighthouse_metrics::{
     inc_counter, try_create_int_counter, IntCounter, Result as MetricsResult,
 };
+use slog::{o, FnValue, Record};
 use std::io::{Result, Write};
 
+mod module_filter;
+
+pub use module_filter::{level_to_str, parse_level, LogLevels, ModuleLevelFilter};
+
 pub const MAX_MESSAGE_WIDTH: usize = 40;
 
 lazy_static! {
@@ -19,6 +24,17 @@ lazy_static! {
         try_create_int_counter("crit_total", "Count of crits logged");
 }
 
+/// Returns a JSON drain which writes one object per line to `io`.
+///
+/// Each object always contains the `ts`, `level`, `msg` and `module` keys, followed by the
+/// key-values of the logger and the record.
+pub fn json_drain<W: Write>(io: W) -> slog_json::Json<W> {
+    slog_json::Json::new(io)
+        .add_default_keys()
+        .add_key_value(o!("module" => FnValue(|record: &Record| record.module())))
+        .build()
+}
+
 pub struct AlignedTermDecorator {
     wrapped: slog_term::TermDecorator,
     message_width: usize,
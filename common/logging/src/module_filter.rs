@@ -0,0 +1,220 @@
+//! Provides a `slog::Drain` which filters records using a default level and a set of
+//! per-module overrides which can be modified at runtime (e.g., via the HTTP API).
+
+use parking_lot::RwLock;
+use slog::{Drain, Level, OwnedKVList, Record};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Parse one of the level strings accepted by the `--debug-level` flag.
+pub fn parse_level(level: &str) -> Result<Level, String> {
+    match level {
+        "info" => Ok(Level::Info),
+        "debug" => Ok(Level::Debug),
+        "trace" => Ok(Level::Trace),
+        "warn" => Ok(Level::Warning),
+        "error" => Ok(Level::Error),
+        "crit" => Ok(Level::Critical),
+        unknown => Err(format!("Unknown debug-level: {}", unknown)),
+    }
+}
+
+/// The inverse of `parse_level`.
+pub fn level_to_str(level: Level) -> &'static str {
+    match level {
+        Level::Info => "info",
+        Level::Debug => "debug",
+        Level::Trace => "trace",
+        Level::Warning => "warn",
+        Level::Error => "error",
+        Level::Critical => "crit",
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    default: Level,
+    modules: HashMap<String, Level>,
+}
+
+/// A shared, runtime-modifiable set of log levels.
+///
+/// A module override applies to a record if the override key is equal to the full module path of
+/// the record (e.g., `eth2_libp2p::peer_manager`), a prefix of it (e.g., `eth2_libp2p`) or any
+/// single component of it (e.g., `peer_manager`). When several overrides apply, the longest key
+/// wins.
+#[derive(Debug, Clone)]
+pub struct LogLevels {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl LogLevels {
+    /// Create a new set of levels where all modules log at `default`.
+    pub fn new(default: Level) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner {
+                default,
+                modules: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Parse a comma-separated list of `module=level` pairs (e.g., `peer_manager=debug,sync=trace`)
+    /// and apply each of them as an override.
+    pub fn set_overrides_from_str(&self, overrides: &str) -> Result<(), String> {
+        let parsed = overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|pair| {
+                let mut split = pair.splitn(2, '=');
+                match (split.next(), split.next()) {
+                    (Some(module), Some(level)) if !module.is_empty() => {
+                        Ok((module.to_string(), parse_level(level)?))
+                    }
+                    _ => Err(format!("Invalid module log level: {}", pair)),
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut inner = self.inner.write();
+        for (module, level) in parsed {
+            inner.modules.insert(module, level);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the level applied to modules without an override.
+    pub fn default_level(&self) -> Level {
+        self.inner.read().default
+    }
+
+    /// Sets the level applied to modules without an override.
+    pub fn set_default_level(&self, level: Level) {
+        self.inner.write().default = level;
+    }
+
+    /// Overrides the level for all records originating from `module`.
+    pub fn set_module_level(&self, module: String, level: Level) {
+        self.inner.write().modules.insert(module, level);
+    }
+
+    /// Removes all module overrides, returning all modules to the default level.
+    pub fn clear_module_levels(&self) {
+        self.inner.write().modules.clear();
+    }
+
+    /// Returns all module overrides, sorted by module.
+    pub fn module_levels(&self) -> Vec<(String, Level)> {
+        let mut modules = self
+            .inner
+            .read()
+            .modules
+            .iter()
+            .map(|(module, level)| (module.clone(), *level))
+            .collect::<Vec<_>>();
+        modules.sort_by(|a, b| a.0.cmp(&b.0));
+        modules
+    }
+
+    /// Returns the level which applies to records from the given `module_path`.
+    pub fn level_for(&self, module_path: &str) -> Level {
+        let inner = self.inner.read();
+
+        inner
+            .modules
+            .iter()
+            .filter(|(key, _)| module_matches(module_path, key))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(inner.default)
+    }
+
+    /// Returns the most verbose level of the default and all overrides.
+    fn most_verbose(&self) -> Level {
+        let inner = self.inner.read();
+
+        inner.modules.values().fold(inner.default, |most, level| {
+            if most.is_at_least(*level) {
+                *level
+            } else {
+                most
+            }
+        })
+    }
+}
+
+/// Returns `true` if the override `key` applies to `module_path`.
+fn module_matches(module_path: &str, key: &str) -> bool {
+    module_path == key
+        || (module_path.starts_with(key) && module_path[key.len()..].starts_with("::"))
+        || module_path.split("::").any(|component| component == key)
+}
+
+/// A `slog::Drain` which only passes records which are enabled by some `LogLevels`.
+pub struct ModuleLevelFilter<D: Drain> {
+    drain: D,
+    levels: LogLevels,
+}
+
+impl<D: Drain> ModuleLevelFilter<D> {
+    pub fn new(drain: D, levels: LogLevels) -> Self {
+        Self { drain, levels }
+    }
+}
+
+impl<D: Drain> Drain for ModuleLevelFilter<D> {
+    type Ok = Option<D::Ok>;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        if record
+            .level()
+            .is_at_least(self.levels.level_for(record.module()))
+        {
+            self.drain.log(record, logger_values).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn is_enabled(&self, level: Level) -> bool {
+        level.is_at_least(self.levels.most_verbose()) && self.drain.is_enabled(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_overrides() {
+        let levels = LogLevels::new(Level::Info);
+        levels
+            .set_overrides_from_str("peer_manager=debug, eth2_libp2p=warn")
+            .expect("should parse overrides");
+
+        assert_eq!(levels.level_for("beacon_chain::beacon_chain"), Level::Info);
+        assert_eq!(levels.level_for("eth2_libp2p::rpc"), Level::Warning);
+        assert_eq!(
+            levels.level_for("eth2_libp2p::peer_manager::peerdb"),
+            Level::Debug
+        );
+        assert_eq!(levels.level_for("eth2_libp2p_other"), Level::Info);
+        assert_eq!(levels.most_verbose(), Level::Debug);
+
+        levels.clear_module_levels();
+        assert_eq!(levels.level_for("eth2_libp2p::rpc"), Level::Info);
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        let levels = LogLevels::new(Level::Info);
+
+        assert!(levels.set_overrides_from_str("peer_manager").is_err());
+        assert!(levels.set_overrides_from_str("=debug").is_err());
+        assert!(levels.set_overrides_from_str("sync=loud").is_err());
+        assert!(levels.module_levels().is_empty());
+    }
+}
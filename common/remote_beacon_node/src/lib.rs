@@ -6,7 +6,7 @@
 use eth2_config::Eth2Config;
 use reqwest::{Client, ClientBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use ssz::Encode;
+use ssz::{Decode, Encode};
 use std::marker::PhantomData;
 use std::time::Duration;
 use types::{
@@ -19,9 +19,11 @@ use url::Url;
 pub use operation_pool::PersistedOperationPool;
 pub use proto_array_fork_choice::core::ProtoArray;
 pub use rest_types::{
-    CanonicalHeadResponse, Committee, HeadBeaconBlock, Health, IndividualVotesRequest,
-    IndividualVotesResponse, SyncingResponse, ValidatorDutiesRequest, ValidatorDutyBytes,
-    ValidatorRequest, ValidatorResponse, ValidatorSubscription,
+    CanonicalHeadResponse, Committee, EpochInclusion, GlobalInclusion, HeadBeaconBlock, Health,
+    IndividualVotesRequest, IndividualVotesResponse, SyncingResponse, ValidatorDutiesRequest,
+    ValidatorDutyBytes, ValidatorInclusion, ValidatorInclusionRequest, ValidatorQuery,
+    ValidatorQueryResponse, ValidatorRequest, ValidatorResponse, ValidatorStatus,
+    ValidatorStatusResponse, ValidatorSubscription,
 };
 
 // Setting a long timeout for debug ensures that crypto-heavy operations can still succeed.
@@ -59,6 +61,8 @@ pub enum Error {
     ReqwestError(reqwest::Error),
     /// There was an error when encoding/decoding an object using serde.
     SerdeJsonError(serde_json::Error),
+    /// There was an error when decoding an object using SSZ.
+    SszDecodeError(ssz::DecodeError),
     /// The server responded to the request, however it did not return a 200-type success code.
     DidNotSucceed { status: StatusCode, body: String },
     /// The request input was invalid.
@@ -111,6 +115,15 @@ impl<E: EthSpec> HttpClient<E> {
         Consensus(self.clone())
     }
 
+    pub fn lighthouse(&self) -> Lighthouse<E> {
+        Lighthouse(self.clone())
+    }
+
+    /// Returns the URL of the server, as supplied to `Self::new`.
+    pub fn server_url(&self) -> &Url {
+        &self.url
+    }
+
     fn url(&self, path: &str) -> Result<Url, Error> {
         self.url.join(path).map_err(|e| e.into())
     }
@@ -143,6 +156,19 @@ impl<E: EthSpec> HttpClient<E> {
         let success = error_for_status(response).await.map_err(Error::from)?;
         success.json::<T>().await.map_err(Error::from)
     }
+
+    pub async fn ssz_get<T: Decode>(&self, url: Url) -> Result<T, Error> {
+        let response = self
+            .client
+            .get(&url.to_string())
+            .send()
+            .await
+            .map_err(Error::from)?;
+
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        let bytes = success.bytes().await.map_err(Error::from)?;
+        T::from_ssz_bytes(&bytes).map_err(Error::SszDecodeError)
+    }
 }
 
 /// Returns an `Error` (with a description) if the `response` was not a 200-type success response.
@@ -313,18 +339,18 @@ impl<E: EthSpec> Validator<E> {
         &self,
         slot: Slot,
         randao_reveal: Signature,
+        graffiti: Option<[u8; 32]>,
     ) -> Result<BeaconBlock<E>, Error> {
         let client = self.0.clone();
         let url = self.url("block")?;
-        client
-            .json_get::<BeaconBlock<E>>(
-                url,
-                vec![
-                    ("slot".into(), format!("{}", slot.as_u64())),
-                    ("randao_reveal".into(), as_ssz_hex_string(&randao_reveal)),
-                ],
-            )
-            .await
+        let mut query_params = vec![
+            ("slot".into(), format!("{}", slot.as_u64())),
+            ("randao_reveal".into(), as_ssz_hex_string(&randao_reveal)),
+        ];
+        if let Some(graffiti) = graffiti {
+            query_params.push(("graffiti".into(), as_ssz_hex_string(&graffiti)));
+        }
+        client.json_get::<BeaconBlock<E>>(url, query_params).await
     }
 
     /// Subscribes a list of validators to particular slots for attestation production/publication.
@@ -443,6 +469,21 @@ impl<E: EthSpec> Beacon<E> {
             .await
     }
 
+    /// Returns the finalized block, which is the genesis block prior to the first finalization.
+    pub async fn get_finalized_block_ssz(&self) -> Result<SignedBeaconBlock<E>, Error> {
+        let client = self.0.clone();
+        let url = self.0.url("eth/v2/beacon/blocks/finalized")?;
+        client.ssz_get(url).await
+    }
+
+    /// Returns the state of the finalized block, which is the genesis state prior to the first
+    /// finalization.
+    pub async fn get_finalized_state_ssz(&self) -> Result<BeaconState<E>, Error> {
+        let client = self.0.clone();
+        let url = self.0.url("eth/v2/debug/beacon/states/finalized")?;
+        client.ssz_get(url).await
+    }
+
     /// Returns the root of the state at the given slot.
     pub async fn get_state_root(&self, slot: Slot) -> Result<Hash256, Error> {
         let client = self.0.clone();
@@ -540,6 +581,19 @@ impl<E: EthSpec> Beacon<E> {
         client.json_get(url, query_params).await
     }
 
+    /// Returns a page of the validators which match `query`.
+    pub async fn query_validators(
+        &self,
+        query: ValidatorQuery,
+    ) -> Result<ValidatorQueryResponse, Error> {
+        let client = self.0.clone();
+
+        let url = self.url("validators/query")?;
+        let response = client.json_post::<_>(url, query).await?;
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        success.json().await.map_err(Error::from)
+    }
+
     /// Returns committees at the given epoch.
     pub async fn get_committees(&self, epoch: Epoch) -> Result<Vec<Committee>, Error> {
         let client = self.0.clone();
@@ -688,6 +742,47 @@ impl<E: EthSpec> Consensus<E> {
     }
 }
 
+/// Provides the functions on the `/lighthouse` endpoint of the node.
+#[derive(Clone)]
+pub struct Lighthouse<E>(HttpClient<E>);
+
+impl<E: EthSpec> Lighthouse<E> {
+    fn url(&self, path: &str) -> Result<Url, Error> {
+        self.0
+            .url("lighthouse/")
+            .and_then(move |url| url.join(path).map_err(Error::from))
+            .map_err(Into::into)
+    }
+
+    /// Gets the `GlobalInclusion` of the given `epoch`.
+    pub async fn get_global_inclusion(&self, epoch: Epoch) -> Result<GlobalInclusion, Error> {
+        let client = self.0.clone();
+        let url = self.url(&format!("validator_inclusion/{}/global", epoch.as_u64()))?;
+        client.json_get(url, vec![]).await
+    }
+
+    /// Gets an `EpochInclusion` for each epoch from `start_epoch` to `end_epoch` (inclusive),
+    /// reporting the given `validator_indices`.
+    pub async fn get_validator_inclusion(
+        &self,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+        validator_indices: Vec<u64>,
+    ) -> Result<Vec<EpochInclusion>, Error> {
+        let client = self.0.clone();
+        let req_body = ValidatorInclusionRequest {
+            start_epoch,
+            end_epoch,
+            validator_indices,
+        };
+
+        let url = self.url("validator_inclusion")?;
+        let response = client.json_post::<_>(url, req_body).await?;
+        let success = error_for_status(response).await.map_err(Error::from)?;
+        success.json().await.map_err(Error::from)
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(bound = "T: EthSpec")]
 pub struct BlockResponse<T: EthSpec> {
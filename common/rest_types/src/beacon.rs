@@ -3,8 +3,9 @@
 use bls::PublicKeyBytes;
 use serde::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
+use std::str::FromStr;
 use types::beacon_state::EthSpec;
-use types::{BeaconState, CommitteeIndex, Hash256, SignedBeaconBlock, Slot, Validator};
+use types::{BeaconState, CommitteeIndex, Epoch, Hash256, SignedBeaconBlock, Slot, Validator};
 
 /// Information about a block that is at the head of a chain. May or may not represent the
 /// canonical head.
@@ -18,6 +19,10 @@ pub struct HeadBeaconBlock {
 #[serde(bound = "T: EthSpec")]
 pub struct BlockResponse<T: EthSpec> {
     pub root: Hash256,
+    /// True if the block is in the canonical chain.
+    pub canonical: bool,
+    /// True if the block is in the canonical chain and has been finalized.
+    pub finalized: bool,
     pub beacon_block: SignedBeaconBlock<T>,
 }
 
@@ -50,6 +55,90 @@ pub struct ValidatorRequest {
     pub pubkeys: Vec<PublicKeyBytes>,
 }
 
+/// The status of a validator in a `BeaconState`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorStatus {
+    /// The validator has not yet been activated.
+    Pending,
+    Active,
+    /// The validator has exited without being slashed.
+    Exited,
+    /// The validator has been slashed, whether or not it has exited yet.
+    Slashed,
+}
+
+impl ValidatorStatus {
+    /// Returns the status of `validator` at `epoch`.
+    pub fn at_epoch(validator: &Validator, epoch: Epoch) -> Self {
+        if validator.slashed {
+            ValidatorStatus::Slashed
+        } else if epoch < validator.activation_epoch {
+            ValidatorStatus::Pending
+        } else if epoch < validator.exit_epoch {
+            ValidatorStatus::Active
+        } else {
+            ValidatorStatus::Exited
+        }
+    }
+}
+
+impl FromStr for ValidatorStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ValidatorStatus::Pending),
+            "active" => Ok(ValidatorStatus::Active),
+            "exited" => Ok(ValidatorStatus::Exited),
+            "slashed" => Ok(ValidatorStatus::Slashed),
+            other => Err(format!("Unknown validator status: {}", other)),
+        }
+    }
+}
+
+/// A query for a page of the validators in a `BeaconState` which match all of the given filters,
+/// in order of validator index.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorQuery {
+    /// If set to `None`, uses the canonical head state.
+    #[serde(default)]
+    pub state_root: Option<Hash256>,
+    /// If not empty, only validators with one of these statuses are returned.
+    #[serde(default)]
+    pub statuses: Vec<ValidatorStatus>,
+    /// If either `pubkeys` or `indices` is not empty, only the validators with one of these
+    /// pubkeys or indices are returned. Unknown validators are ignored.
+    #[serde(default)]
+    pub pubkeys: Vec<PublicKeyBytes>,
+    #[serde(default)]
+    pub indices: Vec<u64>,
+    /// The number of matching validators to skip.
+    #[serde(default)]
+    pub offset: u64,
+    /// The maximum number of validators to return. The server may return fewer.
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorStatusResponse {
+    pub validator_index: u64,
+    pub status: ValidatorStatus,
+    pub balance: u64,
+    pub validator: Validator,
+}
+
+/// A page of the validators which match a `ValidatorQuery`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorQueryResponse {
+    /// The number of validators which match the query, across all pages.
+    pub total: u64,
+    /// The `offset` of the next page, if there are more matching validators.
+    pub next_offset: Option<u64>,
+    pub validators: Vec<ValidatorStatusResponse>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Encode, Decode)]
 pub struct Committee {
     pub slot: Slot,
@@ -57,9 +146,29 @@ pub struct Committee {
     pub committee: Vec<usize>,
 }
 
+/// A Merkle multiproof of some leaves of a `BeaconState`, as per the SSZ specification.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StateProofResponse {
+    /// The tree hash root of the state.
+    pub state_root: Hash256,
+    /// The requested paths, e.g. `finalized_checkpoint.root`.
+    pub paths: Vec<String>,
+    /// The generalized index of the leaf at each path.
+    pub gindices: Vec<u64>,
+    /// The leaf at each path.
+    pub leaves: Vec<Hash256>,
+    /// The nodes needed to compute the state root from the leaves, in descending order of their
+    /// generalized index.
+    pub proof: Vec<Hash256>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Encode, Decode)]
 #[serde(bound = "T: EthSpec")]
 pub struct StateResponse<T: EthSpec> {
     pub root: Hash256,
+    /// True if the state is in the canonical chain.
+    pub canonical: bool,
+    /// True if the state is in the canonical chain and has been finalized.
+    pub finalized: bool,
     pub beacon_state: BeaconState<T>,
 }
@@ -64,3 +64,55 @@ pub struct IndividualVotesResponse {
     /// Voting statistics for the validator, if they voted in the given epoch.
     pub vote: Option<IndividualVote>,
 }
+
+/// The participation of the validators which were active during an epoch in the attestations of
+/// that epoch, as included on chain by the end of the following epoch.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct GlobalInclusion {
+    pub epoch: Epoch,
+    /// The number and total effective balance of the validators active during the epoch.
+    pub active_validators: u64,
+    pub active_gwei: u64,
+    /// The number and total effective balance of the validators with an attestation included.
+    pub attesting_validators: u64,
+    pub attesting_gwei: u64,
+    /// As for `attesting_validators`, but only counting attestations with the correct target.
+    pub target_attesting_validators: u64,
+    pub target_attesting_gwei: u64,
+    /// As for `attesting_validators`, but only counting attestations with the correct head.
+    pub head_attesting_validators: u64,
+    pub head_attesting_gwei: u64,
+}
+
+/// The inclusion of the attestations of a validator during an epoch.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct ValidatorInclusion {
+    pub validator_index: u64,
+    pub is_slashed: bool,
+    /// True if the validator was active during the epoch.
+    pub is_active: bool,
+    /// True if the validator had an attestation included.
+    pub is_attester: bool,
+    /// True if the validator had an attestation with the correct target included.
+    pub is_target_attester: bool,
+    /// True if the validator had an attestation with the correct head included.
+    pub is_head_attester: bool,
+    /// The number of slots between the slot of the earliest included attestation of the
+    /// validator and the slot of the block which included it.
+    pub inclusion_delay: Option<u64>,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct ValidatorInclusionRequest {
+    pub start_epoch: Epoch,
+    /// The last epoch to report, inclusive.
+    pub end_epoch: Epoch,
+    pub validator_indices: Vec<u64>,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct EpochInclusion {
+    pub global: GlobalInclusion,
+    /// The inclusion of each of the requested validators which exist in the epoch.
+    pub validators: Vec<ValidatorInclusion>,
+}
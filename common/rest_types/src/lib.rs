@@ -8,14 +8,21 @@ mod node;
 mod validator;
 
 pub use beacon::{
-    BlockResponse, CanonicalHeadResponse, Committee, HeadBeaconBlock, StateResponse,
-    ValidatorRequest, ValidatorResponse,
+    BlockResponse, CanonicalHeadResponse, Committee, HeadBeaconBlock, StateProofResponse,
+    StateResponse, ValidatorQuery, ValidatorQueryResponse, ValidatorRequest, ValidatorResponse,
+    ValidatorStatus, ValidatorStatusResponse,
 };
 
 pub use validator::{
     ValidatorDutiesRequest, ValidatorDuty, ValidatorDutyBytes, ValidatorSubscription,
 };
 
-pub use consensus::{IndividualVote, IndividualVotesRequest, IndividualVotesResponse};
+pub use consensus::{
+    EpochInclusion, GlobalInclusion, IndividualVote, IndividualVotesRequest,
+    IndividualVotesResponse, ValidatorInclusion, ValidatorInclusionRequest,
+};
 
-pub use node::{Health, SyncingResponse, SyncingStatus};
+pub use node::{
+    DataResponse, EthSyncingData, Health, LighthouseHealth, LogLevelConfig, NetworkRestartRequest,
+    SyncingResponse, SyncingStatus,
+};
@@ -1,6 +1,7 @@
 //! Collection of types for the /node HTTP
 use serde::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
+use std::collections::BTreeMap;
 use types::Slot;
 
 #[cfg(target_os = "linux")]
@@ -34,6 +35,44 @@ pub struct SyncingResponse {
     pub sync_status: SyncingStatus,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// Wraps the response of the standard `/eth/v1` endpoints.
+pub struct DataResponse<T> {
+    pub data: T,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// The response for the /eth/v1/node/syncing HTTP GET.
+pub struct EthSyncingData {
+    /// The slot of the head block.
+    pub head_slot: Slot,
+    /// The number of slots between the head slot and the current slot.
+    pub sync_distance: Slot,
+    /// Is the node syncing.
+    pub is_syncing: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// The response for the /lighthouse/log_levels HTTP GET and the request for the HTTP POST.
+pub struct LogLevelConfig {
+    /// The level used by all modules without an override (e.g., `"info"`).
+    pub default: String,
+    /// Per-module level overrides (e.g., `{"peer_manager": "debug"}`).
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// The request for the /lighthouse/network/restart HTTP POST.
+pub struct NetworkRestartRequest {
+    /// The new TCP port of libp2p, if it should change.
+    #[serde(default)]
+    pub libp2p_port: Option<u16>,
+    /// The new UDP port of discovery, if it should change.
+    #[serde(default)]
+    pub discovery_port: Option<u16>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 /// Reports on the health of the Lighthouse instance.
 pub struct Health {
@@ -45,6 +84,8 @@ pub struct Health {
     pub pid_mem_resident_set_size: u64,
     /// The total virtual memory used by this pid.
     pub pid_mem_virtual_memory_size: u64,
+    /// The number of file descriptors opened by this pid.
+    pub pid_num_fds: u64,
     /// Total virtual memory on the system
     pub sys_virt_mem_total: u64,
     /// Total virtual memory available for new processes.
@@ -80,6 +121,10 @@ impl Health {
 
         let stat = pid::stat_self().map_err(|e| format!("Unable to get stat: {:?}", e))?;
 
+        let num_fds = std::fs::read_dir("/proc/self/fd")
+            .map_err(|e| format!("Unable to read file descriptors: {:?}", e))?
+            .count();
+
         let vm = psutil::memory::virtual_memory()
             .map_err(|e| format!("Unable to get virtual memory: {:?}", e))?;
         let loadavg =
@@ -90,6 +135,7 @@ impl Health {
             pid_num_threads: stat.num_threads,
             pid_mem_resident_set_size: process_mem.rss().into(),
             pid_mem_virtual_memory_size: process_mem.vms().into(),
+            pid_num_fds: num_fds as u64,
             sys_virt_mem_total: vm.total().into(),
             sys_virt_mem_available: vm.available().into(),
             sys_virt_mem_used: vm.used().into(),
@@ -101,3 +147,15 @@ impl Health {
         })
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// The response for the /lighthouse/health HTTP GET.
+pub struct LighthouseHealth {
+    /// Information about the process and the system.
+    #[serde(flatten)]
+    pub health: Health,
+    /// The size of the hot database, in bytes.
+    pub db_size: u64,
+    /// The size of the freezer database, in bytes.
+    pub freezer_db_size: u64,
+}
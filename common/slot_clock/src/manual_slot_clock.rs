@@ -139,6 +139,10 @@ impl SlotClock for ManualSlotClock {
         self.duration_to_slot(slot, *self.current_time.read())
     }
 
+    fn start_of(&self, slot: Slot) -> Option<Duration> {
+        self.start_of(slot)
+    }
+
     fn genesis_slot(&self) -> Slot {
         self.genesis_slot
     }
@@ -7,6 +7,7 @@ use std::fs::read_dir;
 use std::io;
 use std::iter::FromIterator;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug)]
 pub enum Error {
@@ -83,7 +84,8 @@ impl Manager {
 
     /// Opens all the validator directories in `self` and decrypts the validator keypairs.
     ///
-    /// If `log.is_some()`, an `info` log will be generated for each decrypted validator.
+    /// The keystores are decrypted in parallel. If `log.is_some()`, an `info` log will be
+    /// generated for each decrypted validator, including the number decrypted so far.
     ///
     /// ## Errors
     ///
@@ -93,17 +95,37 @@ impl Manager {
         secrets_dir: PathBuf,
         log_opt: Option<&Logger>,
     ) -> Result<Vec<(Keypair, ValidatorDir)>, Error> {
-        self.iter_dir()?
+        self.decrypt_all_validators_with_cache(secrets_dir, &HashMap::new(), log_opt)
+    }
+
+    /// As `decrypt_all_validators`, except that the keypairs in `cache` (keyed by public key, see
+    /// `ValidatorDir::voting_keypair_cached`) are used rather than decrypting their keystores.
+    pub fn decrypt_all_validators_with_cache(
+        &self,
+        secrets_dir: PathBuf,
+        cache: &HashMap<String, Keypair>,
+        log_opt: Option<&Logger>,
+    ) -> Result<Vec<(Keypair, ValidatorDir)>, Error> {
+        let paths = self.iter_dir()?;
+        let total = paths.len();
+        let decrypted = AtomicUsize::new(0);
+
+        paths
             .into_par_iter()
             .map(|path| {
                 ValidatorDir::open(path)
-                    .and_then(|v| v.voting_keypair(&secrets_dir).map(|kp| (kp, v)))
+                    .and_then(|v| {
+                        v.voting_keypair_cached(&secrets_dir, cache)
+                            .map(|kp| (kp, v))
+                    })
                     .map(|(kp, v)| {
+                        let count = decrypted.fetch_add(1, Ordering::Relaxed) + 1;
                         if let Some(log) = log_opt {
                             info!(
                                 log,
                                 "Decrypted validator keystore";
-                                "voting_pubkey" => kp.pk.as_hex_string()
+                                "voting_pubkey" => kp.pk.as_hex_string(),
+                                "progress" => format!("{}/{}", count, total),
                             )
                         }
                         (kp, v)
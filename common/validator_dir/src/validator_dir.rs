@@ -4,11 +4,12 @@ use crate::builder::{
 };
 use deposit_contract::decode_eth1_tx_data;
 use eth2_keystore::{Error as KeystoreError, Keystore, PlainText};
+use std::collections::HashMap;
 use std::fs::{read, remove_file, write, OpenOptions};
 use std::io;
 use std::path::{Path, PathBuf};
 use tree_hash::TreeHash;
-use types::{DepositData, Hash256, Keypair};
+use types::{DepositData, Hash256, Keypair, PublicKey};
 
 /// The file used for indicating if a directory is in-use by another process.
 const LOCK_FILE: &str = ".lock";
@@ -16,6 +17,9 @@ const LOCK_FILE: &str = ".lock";
 /// The file used to save the Eth1 transaction hash from a deposit.
 pub const ETH1_DEPOSIT_TX_HASH_FILE: &str = "eth1-deposit-tx-hash.txt";
 
+/// The file used to save the graffiti to include in blocks proposed by the validator.
+pub const GRAFFITI_FILE: &str = "graffiti.txt";
+
 #[derive(Debug)]
 pub enum Error {
     DirectoryDoesNotExist(PathBuf),
@@ -23,6 +27,7 @@ pub enum Error {
     UnableToCreateLockfile(io::Error),
     UnableToOpenKeystore(io::Error),
     UnableToReadKeystore(KeystoreError),
+    InvalidKeystorePubkey(String),
     UnableToOpenPassword(io::Error),
     UnableToReadPassword(PathBuf),
     UnableToDecryptKeypair(KeystoreError),
@@ -37,6 +42,9 @@ pub enum Error {
     UnableToParseDepositData(deposit_contract::DecodeError),
     Eth1TxHashExists(PathBuf),
     UnableToWriteEth1TxHash(io::Error),
+    UnableToReadGraffiti(io::Error),
+    GraffitiIsNotUtf8(std::string::FromUtf8Error),
+    UnableToWriteGraffiti(io::Error),
     /// The deposit root in the deposit data file does not match the one generated locally. This is
     /// generally caused by supplying an `amount` at deposit-time that is different to the one used
     /// at generation-time.
@@ -110,6 +118,50 @@ impl ValidatorDir {
         unlock_keypair(&self.dir.clone(), VOTING_KEYSTORE_FILE, password_dir)
     }
 
+    /// Reads the voting keystore in `self.dir`, without decrypting it.
+    ///
+    /// ## Errors
+    ///
+    /// If there is a filesystem error or the keystore is invalid.
+    pub fn voting_keystore(&self) -> Result<Keystore, Error> {
+        read_keystore(&self.dir, VOTING_KEYSTORE_FILE)
+    }
+
+    /// Returns the public key of the voting keystore in `self.dir`, without decrypting it.
+    ///
+    /// ## Errors
+    ///
+    /// If there is a filesystem error or the keystore has an invalid `pubkey` field.
+    pub fn voting_pubkey(&self) -> Result<PublicKey, Error> {
+        let keystore = read_keystore(&self.dir, VOTING_KEYSTORE_FILE)?;
+
+        hex::decode(keystore.pubkey())
+            .ok()
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| Error::InvalidKeystorePubkey(keystore.pubkey().to_string()))
+    }
+
+    /// As `voting_keypair`, except that if `cache` contains the public key of the keystore (as
+    /// hex without a `0x` prefix, like the `pubkey` field of a keystore), the cached keypair is
+    /// returned without decrypting the keystore.
+    ///
+    /// ## Errors
+    ///
+    /// If there is a filesystem error or the keypair is not cached and a password is missing or
+    /// incorrect.
+    pub fn voting_keypair_cached<P: AsRef<Path>>(
+        &self,
+        password_dir: P,
+        cache: &HashMap<String, Keypair>,
+    ) -> Result<Keypair, Error> {
+        let keystore = read_keystore(&self.dir, VOTING_KEYSTORE_FILE)?;
+
+        match cache.get(keystore.pubkey()) {
+            Some(keypair) => Ok(keypair.clone()),
+            None => unlock_keystore(&keystore, password_dir),
+        }
+    }
+
     /// Attempts to read the keystore in `self.dir` and decrypt the keypair using a password file
     /// in `password_dir`.
     ///
@@ -150,6 +202,41 @@ impl ValidatorDir {
         write(path, tx_hash.as_bytes()).map_err(Error::UnableToWriteEth1TxHash)
     }
 
+    /// Returns the graffiti saved in `self.dir`, if any.
+    ///
+    /// ## Errors
+    ///
+    /// If there is a file-system error or the graffiti is not UTF-8.
+    pub fn graffiti(&self) -> Result<Option<String>, Error> {
+        let path = self.dir.join(GRAFFITI_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let graffiti = read(path).map_err(Error::UnableToReadGraffiti)?;
+        String::from_utf8(graffiti)
+            .map(Some)
+            .map_err(Error::GraffitiIsNotUtf8)
+    }
+
+    /// Saves `graffiti` to a file in `self.dir`, or removes the file if `graffiti` is `None`.
+    /// Artificially requires `mut self` to prevent concurrent calls.
+    ///
+    /// ## Errors
+    ///
+    /// If there is a file-system error.
+    pub fn set_graffiti(&mut self, graffiti: Option<&str>) -> Result<(), Error> {
+        let path = self.dir.join(GRAFFITI_FILE);
+
+        match graffiti {
+            Some(graffiti) => {
+                write(path, graffiti.as_bytes()).map_err(Error::UnableToWriteGraffiti)
+            }
+            None if path.exists() => remove_file(path).map_err(Error::UnableToWriteGraffiti),
+            None => Ok(()),
+        }
+    }
+
     /// Attempts to read files in `self.dir` and return an `Eth1DepositData` that can be used for
     /// submitting an Eth1 deposit.
     ///
@@ -220,15 +307,27 @@ fn unlock_keypair<P: AsRef<Path>>(
     filename: &str,
     password_dir: P,
 ) -> Result<Keypair, Error> {
-    let keystore = Keystore::from_json_reader(
+    let keystore = read_keystore(keystore_dir, filename)?;
+    unlock_keystore(&keystore, password_dir)
+}
+
+/// Attempts to load a keystore.
+fn read_keystore(keystore_dir: &PathBuf, filename: &str) -> Result<Keystore, Error> {
+    Keystore::from_json_reader(
         &mut OpenOptions::new()
             .read(true)
             .create(false)
             .open(keystore_dir.clone().join(filename))
             .map_err(Error::UnableToOpenKeystore)?,
     )
-    .map_err(Error::UnableToReadKeystore)?;
+    .map_err(Error::UnableToReadKeystore)
+}
 
+/// Attempts to decrypt a keystore using a password file in `password_dir`.
+pub fn unlock_keystore<P: AsRef<Path>>(
+    keystore: &Keystore,
+    password_dir: P,
+) -> Result<Keypair, Error> {
     let password_path = password_dir
         .as_ref()
         .join(format!("0x{}", keystore.pubkey()));
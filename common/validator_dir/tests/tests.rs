@@ -1,13 +1,14 @@
 #![cfg(not(debug_assertions))]
 
 use eth2_keystore::{Keystore, KeystoreBuilder, PlainText};
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::path::Path;
 use tempfile::{tempdir, TempDir};
 use types::{test_utils::generate_deterministic_keypair, EthSpec, Keypair, MainnetEthSpec};
 use validator_dir::{
     Builder, BuilderError, ValidatorDir, ETH1_DEPOSIT_DATA_FILE, ETH1_DEPOSIT_TX_HASH_FILE,
-    VOTING_KEYSTORE_FILE, WITHDRAWAL_KEYSTORE_FILE,
+    GRAFFITI_FILE, VOTING_KEYSTORE_FILE, WITHDRAWAL_KEYSTORE_FILE,
 };
 
 /// A very weak password with which to encrypt the keystores.
@@ -203,6 +204,65 @@ fn concurrency() {
     ValidatorDir::open(&path).unwrap();
 }
 
+#[test]
+fn graffiti() {
+    let harness = Harness::new();
+
+    let mut val_dir = harness.create_and_test(&BuildConfig::default());
+    assert_eq!(val_dir.graffiti().unwrap(), None);
+
+    val_dir.set_graffiti(Some("lighthouse")).unwrap();
+    assert_eq!(val_dir.graffiti().unwrap(), Some("lighthouse".to_string()));
+
+    // Should persist across re-opening.
+    let path = val_dir.dir().clone();
+    drop(val_dir);
+    let mut val_dir = ValidatorDir::open(&path).unwrap();
+    assert_eq!(val_dir.graffiti().unwrap(), Some("lighthouse".to_string()));
+
+    val_dir.set_graffiti(None).unwrap();
+    assert!(!path.join(GRAFFITI_FILE).exists());
+    assert_eq!(val_dir.graffiti().unwrap(), None);
+
+    // Removing twice is fine.
+    val_dir.set_graffiti(None).unwrap();
+}
+
+#[test]
+fn cached_voting_keypair() {
+    let harness = Harness::new();
+
+    let val_dir = harness.create_and_test(&BuildConfig::default());
+    let keypair = val_dir.voting_keypair(harness.password_dir.path()).unwrap();
+    let pubkey = keypair.pk.as_hex_string();
+
+    // Without a password, the keypair is only available from the cache.
+    fs::remove_file(harness.password_dir.path().join(&pubkey)).unwrap();
+    assert!(val_dir.voting_keypair(harness.password_dir.path()).is_err());
+    assert!(val_dir
+        .voting_keypair_cached(harness.password_dir.path(), &HashMap::new())
+        .is_err());
+
+    let mut cache = HashMap::new();
+    cache.insert(pubkey[2..].to_string(), keypair.clone());
+    let cached = val_dir
+        .voting_keypair_cached(harness.password_dir.path(), &cache)
+        .unwrap();
+    assert_eq!(cached.pk, keypair.pk);
+}
+
+#[test]
+fn voting_pubkey_without_password() {
+    let harness = Harness::new();
+
+    let val_dir = harness.create_and_test(&BuildConfig::default());
+    let keypair = val_dir.voting_keypair(harness.password_dir.path()).unwrap();
+
+    // The public key is read from the keystore without decrypting it.
+    fs::remove_file(harness.password_dir.path().join(keypair.pk.as_hex_string())).unwrap();
+    assert_eq!(val_dir.voting_pubkey().unwrap(), keypair.pk);
+}
+
 #[test]
 fn without_voting_keystore() {
     let harness = Harness::new();
@@ -3,6 +3,8 @@ use ethereum_types::H256;
 use lazy_static::lazy_static;
 use safe_arith::ArithError;
 
+pub mod multiproof;
+
 const MAX_TREE_DEPTH: usize = 32;
 const EMPTY_SLICE: &[H256] = &[];
 
@@ -27,6 +29,10 @@ pub enum MerkleTree {
     ///
     /// It represents a Merkle tree of 2^depth zero leaves.
     Zero(usize),
+    /// Full subtree whose leaves are unknown, only its hash.
+    ///
+    /// Proofs cannot be generated for the leaves of a finalized subtree.
+    Finalized(H256),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -41,6 +47,10 @@ pub enum MerkleTreeError {
     DepthTooSmall,
     // Overflow occurred
     ArithError,
+    // Trying to generate a proof for a leaf within a finalized subtree
+    ProofEncounteredFinalizedNode,
+    // The finalized hashes do not match the number of leaves
+    InvalidSnapshot,
 }
 
 impl MerkleTree {
@@ -78,6 +88,110 @@ impl MerkleTree {
         }
     }
 
+    /// Create a Merkle tree of `leaf_count` leaves from the hashes of its finalized subtrees.
+    ///
+    /// `finalized` contains the root of each full subtree, from left to right, such that there is
+    /// one subtree of `2^i` leaves for each bit `i` set in `leaf_count`. This is the inverse of
+    /// `finalized_hashes`.
+    pub fn from_finalized_snapshot(
+        finalized: &[H256],
+        leaf_count: usize,
+        depth: usize,
+    ) -> Result<Self, MerkleTreeError> {
+        use MerkleTree::*;
+
+        if leaf_count == 0 {
+            return if finalized.is_empty() {
+                Ok(Zero(depth))
+            } else {
+                Err(MerkleTreeError::InvalidSnapshot)
+            };
+        }
+
+        let capacity = 1usize
+            .checked_shl(depth as u32)
+            .ok_or(MerkleTreeError::ArithError)?;
+        if leaf_count == capacity {
+            return match finalized {
+                [hash] => Ok(Finalized(*hash)),
+                _ => Err(MerkleTreeError::InvalidSnapshot),
+            };
+        }
+        if depth == 0 || leaf_count > capacity {
+            return Err(MerkleTreeError::InvalidSnapshot);
+        }
+
+        let subtree_capacity = capacity / 2;
+        let (left, right) = if leaf_count >= subtree_capacity {
+            let (first, rest) = finalized
+                .split_first()
+                .ok_or(MerkleTreeError::InvalidSnapshot)?;
+            (
+                Finalized(*first),
+                MerkleTree::from_finalized_snapshot(
+                    rest,
+                    leaf_count - subtree_capacity,
+                    depth - 1,
+                )?,
+            )
+        } else {
+            (
+                MerkleTree::from_finalized_snapshot(finalized, leaf_count, depth - 1)?,
+                Zero(depth - 1),
+            )
+        };
+        let hash = H256::from_slice(&hash32_concat(
+            left.hash().as_bytes(),
+            right.hash().as_bytes(),
+        ));
+
+        Ok(Node(hash, Box::new(left), Box::new(right)))
+    }
+
+    /// Returns the roots of the full subtrees which contain the first `leaf_count` leaves, from
+    /// left to right. There is one subtree of `2^i` leaves for each bit `i` set in `leaf_count`.
+    ///
+    /// The tree may contain more than `leaf_count` leaves.
+    pub fn finalized_hashes(
+        &self,
+        leaf_count: usize,
+        depth: usize,
+    ) -> Result<Vec<H256>, MerkleTreeError> {
+        let mut hashes = vec![];
+        self.append_finalized_hashes(leaf_count, depth, &mut hashes)?;
+        Ok(hashes)
+    }
+
+    fn append_finalized_hashes(
+        &self,
+        leaf_count: usize,
+        depth: usize,
+        hashes: &mut Vec<H256>,
+    ) -> Result<(), MerkleTreeError> {
+        if leaf_count == 0 {
+            return Ok(());
+        }
+        if Some(leaf_count) == 1usize.checked_shl(depth as u32) {
+            hashes.push(self.hash());
+            return Ok(());
+        }
+
+        match self {
+            MerkleTree::Node(_, left, right) => {
+                let subtree_capacity = 1usize << (depth - 1);
+                if leaf_count >= subtree_capacity {
+                    hashes.push(left.hash());
+                    right.append_finalized_hashes(leaf_count - subtree_capacity, depth - 1, hashes)
+                } else {
+                    left.append_finalized_hashes(leaf_count, depth - 1, hashes)
+                }
+            }
+            // The tree has fewer than `leaf_count` leaves, or only some of the leaves of a
+            // finalized subtree were requested.
+            _ => Err(MerkleTreeError::Invalid),
+        }
+    }
+
     /// Push an element in the MerkleTree.
     /// MerkleTree and depth must be correct, as the algorithm expects valid data.
     pub fn push_leaf(&mut self, elem: H256, depth: usize) -> Result<(), MerkleTreeError> {
@@ -90,6 +204,7 @@ impl MerkleTree {
 
         match self {
             Leaf(_) => return Err(MerkleTreeError::LeafReached),
+            Finalized(_) => return Err(MerkleTreeError::MerkleTreeFull),
             Zero(_) => {
                 mem::replace(self, MerkleTree::create(&[elem], depth));
             }
@@ -98,9 +213,11 @@ impl MerkleTree {
                 let right: &mut MerkleTree = &mut *right;
                 match (&*left, &*right) {
                     // Tree is full
-                    (Leaf(_), Leaf(_)) => return Err(MerkleTreeError::MerkleTreeFull),
+                    (Leaf(_), Leaf(_)) | (Finalized(_), Leaf(_)) | (Finalized(_), Finalized(_)) => {
+                        return Err(MerkleTreeError::MerkleTreeFull)
+                    }
                     // There is a right node so insert in right node
-                    (Node(_, _, _), Node(_, _, _)) => {
+                    (Node(_, _, _), Node(_, _, _)) | (Finalized(_), Node(_, _, _)) => {
                         if let Err(e) = right.push_leaf(elem, depth - 1) {
                             return Err(e);
                         }
@@ -109,8 +226,8 @@ impl MerkleTree {
                     (Zero(_), Zero(_)) => {
                         mem::replace(left, MerkleTree::create(&[elem], depth - 1));
                     }
-                    // Leaf on left branch and zero on right branch, insert on right side
-                    (Leaf(_), Zero(_)) => {
+                    // Full left branch and zero on right branch, insert on right side
+                    (Leaf(_), Zero(_)) | (Finalized(_), Zero(_)) => {
                         mem::replace(right, MerkleTree::create(&[elem], depth - 1));
                     }
                     // Try inserting on the left node -> if it fails because it is full, insert in right side.
@@ -142,6 +259,7 @@ impl MerkleTree {
         match *self {
             MerkleTree::Leaf(h) => h,
             MerkleTree::Node(h, _, _) => h,
+            MerkleTree::Finalized(h) => h,
             MerkleTree::Zero(depth) => H256::from_slice(&ZERO_HASHES[depth]),
         }
     }
@@ -149,7 +267,7 @@ impl MerkleTree {
     /// Get a reference to the left and right subtrees if they exist.
     pub fn left_and_right_branches(&self) -> Option<(&Self, &Self)> {
         match *self {
-            MerkleTree::Leaf(_) | MerkleTree::Zero(0) => None,
+            MerkleTree::Leaf(_) | MerkleTree::Finalized(_) | MerkleTree::Zero(0) => None,
             MerkleTree::Node(_, ref l, ref r) => Some((l, r)),
             MerkleTree::Zero(depth) => Some((&ZERO_NODES[depth - 1], &ZERO_NODES[depth - 1])),
         }
@@ -167,14 +285,27 @@ impl MerkleTree {
     ///
     /// The Merkle proof is in "bottom-up" order, starting with a leaf node
     /// and moving up the tree. Its length will be exactly equal to `depth`.
-    pub fn generate_proof(&self, index: usize, depth: usize) -> (H256, Vec<H256>) {
+    ///
+    /// Returns an error if the leaf is within a finalized subtree.
+    pub fn generate_proof(
+        &self,
+        index: usize,
+        depth: usize,
+    ) -> Result<(H256, Vec<H256>), MerkleTreeError> {
         let mut proof = vec![];
         let mut current_node = self;
         let mut current_depth = depth;
         while current_depth > 0 {
             let ith_bit = (index >> (current_depth - 1)) & 0x01;
-            // Note: unwrap is safe because leaves are only ever constructed at depth == 0.
-            let (left, right) = current_node.left_and_right_branches().unwrap();
+            let (left, right) = match current_node {
+                MerkleTree::Finalized(_) => {
+                    return Err(MerkleTreeError::ProofEncounteredFinalizedNode)
+                }
+                // Leaves are only ever constructed at depth == 0.
+                _ => current_node
+                    .left_and_right_branches()
+                    .ok_or(MerkleTreeError::Invalid)?,
+            };
 
             // Go right, include the left branch in the proof.
             if ith_bit == 1 {
@@ -193,7 +324,7 @@ impl MerkleTree {
         // Put proof in bottom-up order.
         proof.reverse();
 
-        (current_node.hash(), proof)
+        Ok((current_node.hash(), proof))
     }
 }
 
@@ -261,7 +392,9 @@ mod tests {
         let merkle_root = merkle_tree.hash();
 
         let proofs_ok = (0..leaves.len()).all(|i| {
-            let (leaf, branch) = merkle_tree.generate_proof(i, depth);
+            let (leaf, branch) = merkle_tree
+                .generate_proof(i, depth)
+                .expect("should generate proof");
             leaf == leaves[i] && verify_merkle_proof(leaf, &branch, depth, i, merkle_root)
         });
 
@@ -280,13 +413,73 @@ mod tests {
 
         let proofs_ok = leaves.into_iter().enumerate().all(|(i, leaf)| {
             assert_eq!(merkle_tree.push_leaf(leaf, depth), Ok(()));
-            let (stored_leaf, branch) = merkle_tree.generate_proof(i, depth);
+            let (stored_leaf, branch) = merkle_tree
+                .generate_proof(i, depth)
+                .expect("should generate proof");
             stored_leaf == leaf && verify_merkle_proof(leaf, &branch, depth, i, merkle_tree.hash())
         });
 
         TestResult::from_bool(proofs_ok)
     }
 
+    /// Check that a tree rebuilt from the finalized hashes of a tree has the same root, and that
+    /// both trees produce the same proofs for leaves pushed afterwards.
+    #[quickcheck]
+    fn quickcheck_finalized_snapshot(
+        int_leaves: Vec<u64>,
+        finalized_count: usize,
+        depth: usize,
+    ) -> TestResult {
+        if depth == 0 || depth > MAX_TREE_DEPTH || int_leaves.len() > 2usize.pow(depth as u32) {
+            return TestResult::discard();
+        }
+
+        let finalized_count = finalized_count % (int_leaves.len() + 1);
+        let leaves: Vec<_> = int_leaves.into_iter().map(H256::from_low_u64_be).collect();
+        let (finalized_leaves, new_leaves) = leaves.split_at(finalized_count);
+
+        let mut full_tree = MerkleTree::create(finalized_leaves, depth);
+        let finalized = full_tree
+            .finalized_hashes(finalized_count, depth)
+            .expect("should get finalized hashes");
+        if finalized.len() != finalized_count.count_ones() as usize {
+            return TestResult::failed();
+        }
+
+        let mut snapshot_tree =
+            MerkleTree::from_finalized_snapshot(&finalized, finalized_count, depth)
+                .expect("should create tree from snapshot");
+        if snapshot_tree.hash() != full_tree.hash() {
+            return TestResult::failed();
+        }
+
+        let proofs_ok = new_leaves.iter().enumerate().all(|(i, leaf)| {
+            let index = finalized_count + i;
+            assert_eq!(full_tree.push_leaf(*leaf, depth), Ok(()));
+            assert_eq!(snapshot_tree.push_leaf(*leaf, depth), Ok(()));
+            full_tree.generate_proof(index, depth) == snapshot_tree.generate_proof(index, depth)
+        });
+
+        TestResult::from_bool(proofs_ok && snapshot_tree.hash() == full_tree.hash())
+    }
+
+    #[test]
+    fn finalized_leaves_have_no_proof() {
+        let leaves = [H256::from([0xAA; 32]), H256::from([0xBB; 32])];
+        let tree = MerkleTree::create(&leaves, 2);
+        let finalized = tree.finalized_hashes(2, 2).unwrap();
+        let snapshot_tree = MerkleTree::from_finalized_snapshot(&finalized, 2, 2).unwrap();
+
+        assert_eq!(
+            snapshot_tree.generate_proof(1, 2),
+            Err(MerkleTreeError::ProofEncounteredFinalizedNode)
+        );
+        assert_eq!(
+            MerkleTree::from_finalized_snapshot(&finalized, 3, 2),
+            Err(MerkleTreeError::InvalidSnapshot)
+        );
+    }
+
     #[test]
     fn sparse_zero_correct() {
         let depth = 2;
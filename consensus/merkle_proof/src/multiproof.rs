@@ -0,0 +1,215 @@
+//! Generalized indices and Merkle multiproofs, as per the SSZ Merkle proof specification.
+//!
+//! A generalized index identifies a node of a binary Merkle tree: the root is at index `1` and
+//! the children of the node at index `i` are at `2i` and `2i + 1`. The depth of a node is the
+//! number of bits of its index, less one.
+
+use crate::MerkleTreeError;
+use eth2_hashing::hash32_concat;
+use ethereum_types::H256;
+use std::collections::{BTreeSet, HashMap};
+
+/// The index of a node in a binary Merkle tree.
+pub type GeneralizedIndex = u64;
+
+/// The depth of the node at `index`, which is the length of its Merkle branch.
+pub fn index_depth(index: GeneralizedIndex) -> usize {
+    63 - index.leading_zeros() as usize
+}
+
+/// Returns the index within a tree of the node at `indices[n]` within the subtree rooted at
+/// `indices[n - 1]`, which is in turn within the subtree rooted at `indices[n - 2]`, and so on.
+///
+/// Returns `None` if the index does not fit in a `GeneralizedIndex`.
+pub fn concat_generalized_indices(indices: &[GeneralizedIndex]) -> Option<GeneralizedIndex> {
+    indices
+        .iter()
+        .try_fold(1, |index: GeneralizedIndex, &subindex| {
+            if subindex == 0 {
+                return None;
+            }
+            let depth = index_depth(subindex);
+            if index_depth(index) + depth > 63 {
+                return None;
+            }
+            Some((index << depth) | (subindex ^ (1 << depth)))
+        })
+}
+
+/// The indices of the nodes needed to compute the root from the node at `index`, bottom-up.
+pub fn branch_indices(index: GeneralizedIndex) -> Vec<GeneralizedIndex> {
+    path_indices(index).into_iter().map(|i| i ^ 1).collect()
+}
+
+/// The indices of the node at `index` and its ancestors, excluding the root, bottom-up.
+pub fn path_indices(index: GeneralizedIndex) -> Vec<GeneralizedIndex> {
+    let mut indices = vec![];
+    let mut i = index;
+    while i > 1 {
+        indices.push(i);
+        i /= 2;
+    }
+    indices
+}
+
+/// The indices of the nodes needed to compute the root from the nodes at `indices`, in
+/// descending order. Nodes which can be computed from the given nodes are excluded.
+pub fn helper_indices(indices: &[GeneralizedIndex]) -> Vec<GeneralizedIndex> {
+    let mut helpers = BTreeSet::new();
+    let mut paths = BTreeSet::new();
+    for &index in indices {
+        helpers.extend(branch_indices(index));
+        paths.extend(path_indices(index));
+    }
+    helpers.difference(&paths).rev().copied().collect()
+}
+
+/// Returns every node which is known given the leaf at `index` and its Merkle `branch`, bottom-up:
+/// the leaf, the branch and the ancestors of the leaf, including the root.
+pub fn branch_nodes(
+    index: GeneralizedIndex,
+    leaf: H256,
+    branch: &[H256],
+) -> Result<Vec<(GeneralizedIndex, H256)>, MerkleTreeError> {
+    if branch.len() != index_depth(index) {
+        return Err(MerkleTreeError::Invalid);
+    }
+
+    let mut nodes = vec![(index, leaf)];
+    let mut i = index;
+    let mut node = leaf;
+    for sibling in branch {
+        nodes.push((i ^ 1, *sibling));
+        node = if i % 2 == 0 {
+            H256::from_slice(&hash32_concat(node.as_bytes(), sibling.as_bytes()))
+        } else {
+            H256::from_slice(&hash32_concat(sibling.as_bytes(), node.as_bytes()))
+        };
+        i /= 2;
+        nodes.push((i, node));
+    }
+    Ok(nodes)
+}
+
+/// A proof that several leaves are part of a Merkle tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multiproof {
+    /// The generalized index of each leaf.
+    pub indices: Vec<GeneralizedIndex>,
+    /// The leaves, in the order of `indices`.
+    pub leaves: Vec<H256>,
+    /// The nodes at `helper_indices(indices)`, in descending order of their index.
+    pub proof: Vec<H256>,
+}
+
+impl Multiproof {
+    /// Builds a proof of the leaves at `indices`, taking the leaves and the proof from `nodes`.
+    ///
+    /// Returns an error if `nodes` is missing a leaf or a node of the proof.
+    pub fn from_nodes(
+        indices: Vec<GeneralizedIndex>,
+        nodes: &HashMap<GeneralizedIndex, H256>,
+    ) -> Result<Self, MerkleTreeError> {
+        let get = |index| nodes.get(&index).copied().ok_or(MerkleTreeError::Invalid);
+        let leaves = indices
+            .iter()
+            .map(|&index| get(index))
+            .collect::<Result<_, _>>()?;
+        let proof = helper_indices(&indices)
+            .into_iter()
+            .map(get)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Multiproof {
+            indices,
+            leaves,
+            proof,
+        })
+    }
+
+    /// Computes the root of the tree from the leaves and the proof. The leaves are part of the
+    /// tree if this equals its known root.
+    pub fn root(&self) -> Result<H256, MerkleTreeError> {
+        let helpers = helper_indices(&self.indices);
+        if self.leaves.len() != self.indices.len() || self.proof.len() != helpers.len() {
+            return Err(MerkleTreeError::Invalid);
+        }
+
+        let mut nodes = HashMap::new();
+        nodes.extend(
+            self.indices
+                .iter()
+                .copied()
+                .zip(self.leaves.iter().copied()),
+        );
+        nodes.extend(helpers.into_iter().zip(self.proof.iter().copied()));
+
+        let mut keys = nodes.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut pos = 0;
+        while pos < keys.len() {
+            let k = keys[pos];
+            if k > 1 && nodes.contains_key(&(k ^ 1)) && !nodes.contains_key(&(k / 2)) {
+                let left = nodes[&(k & !1)];
+                let right = nodes[&(k | 1)];
+                nodes.insert(
+                    k / 2,
+                    H256::from_slice(&hash32_concat(left.as_bytes(), right.as_bytes())),
+                );
+                keys.push(k / 2);
+            }
+            pos += 1;
+        }
+
+        nodes.get(&1).copied().ok_or(MerkleTreeError::Invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    #[test]
+    fn concat_indices() {
+        assert_eq!(concat_generalized_indices(&[]), Some(1));
+        assert_eq!(concat_generalized_indices(&[5]), Some(5));
+        // the right child (3) of the left child (2) of the root
+        assert_eq!(concat_generalized_indices(&[2, 3]), Some(5));
+        assert_eq!(concat_generalized_indices(&[3, 2, 3]), Some(13));
+        assert_eq!(concat_generalized_indices(&[1 << 40, 1 << 40]), None);
+    }
+
+    #[test]
+    fn helpers_exclude_computable_nodes() {
+        // siblings 8 and 9 compute 4, which is the sibling of 5
+        assert_eq!(helper_indices(&[8, 9, 5]), vec![3]);
+        assert_eq!(helper_indices(&[8]), vec![9, 5, 3]);
+    }
+
+    #[test]
+    fn multiproof_round_trip() {
+        let depth = 4;
+        let leaves = (0..11).map(H256::from_low_u64_be).collect::<Vec<_>>();
+        let tree = MerkleTree::create(&leaves, depth);
+
+        let mut nodes = HashMap::new();
+        let mut indices = vec![];
+        for &i in &[0, 3, 4, 10] {
+            let (leaf, branch) = tree.generate_proof(i, depth).unwrap();
+            let index = (1 << depth) + i as u64;
+            nodes.extend(branch_nodes(index, leaf, &branch).unwrap());
+            indices.push(index);
+        }
+        assert_eq!(nodes[&1], tree.hash());
+
+        let mut proof = Multiproof::from_nodes(indices, &nodes).unwrap();
+        assert_eq!(proof.root(), Ok(tree.hash()));
+
+        proof.leaves[1] = H256::repeat_byte(1);
+        assert_ne!(proof.root(), Ok(tree.hash()));
+        proof.proof.pop();
+        assert_eq!(proof.root(), Err(MerkleTreeError::Invalid));
+    }
+}
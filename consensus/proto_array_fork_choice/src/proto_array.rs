@@ -21,6 +21,24 @@ pub struct ProtoNode {
     best_descendant: Option<usize>,
 }
 
+/// A leaf of the block tree which is viable to become the head.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ViableHead {
+    pub root: Hash256,
+    pub slot: Slot,
+    /// `None` if the parent has been pruned.
+    pub parent_root: Option<Hash256>,
+    /// The balance of the validators whose latest vote is for this block.
+    pub weight: u64,
+    /// The latest ancestor with more than one child, where the chain of this head forks from the
+    /// chain of another block. `None` if no known ancestor has more than one child.
+    pub fork_root: Option<Hash256>,
+    pub fork_slot: Option<Slot>,
+    /// The weight of the child of `fork_root` which leads to this head, which fork choice
+    /// compares against the other children of `fork_root`.
+    pub branch_weight: Option<u64>,
+}
+
 #[derive(PartialEq, Debug, Serialize, Deserialize)]
 pub struct ProtoArray {
     /// Do not attempt to prune the tree unless it has at least this many nodes. Small prunes
@@ -408,6 +426,54 @@ impl ProtoArray {
                 || self.finalized_epoch == Epoch::new(0))
     }
 
+    /// Returns the leaves of the block tree which are viable to become the head, along with the
+    /// points at which their chains fork.
+    ///
+    /// The weights are only up-to-date after a call to `Self::apply_score_changes`.
+    pub fn viable_heads(&self) -> Vec<ViableHead> {
+        let mut child_counts = vec![0_usize; self.nodes.len()];
+        for parent_index in self.nodes.iter().filter_map(|node| node.parent) {
+            if let Some(count) = child_counts.get_mut(parent_index) {
+                *count += 1;
+            }
+        }
+
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, node)| child_counts[*i] == 0 && self.node_is_viable_for_head(node))
+            .map(|(_, node)| {
+                // Walk back to the first ancestor with a sibling chain.
+                let mut branch = node;
+                let mut fork = None;
+                while let Some(parent_index) = branch.parent {
+                    let parent = match self.nodes.get(parent_index) {
+                        Some(parent) => parent,
+                        None => break,
+                    };
+                    if child_counts[parent_index] > 1 {
+                        fork = Some(parent);
+                        break;
+                    }
+                    branch = parent;
+                }
+
+                ViableHead {
+                    root: node.root,
+                    slot: node.slot,
+                    parent_root: node
+                        .parent
+                        .and_then(|parent_index| self.nodes.get(parent_index))
+                        .map(|parent| parent.root),
+                    weight: node.weight,
+                    fork_root: fork.map(|fork| fork.root),
+                    fork_slot: fork.map(|fork| fork.slot),
+                    branch_weight: fork.map(|_| branch.weight),
+                }
+            })
+            .collect()
+    }
+
     /// Return a reverse iterator over the nodes which comprise the chain ending at `block_root`.
     pub fn iter_nodes<'a>(&'a self, block_root: &Hash256) -> Iter<'a> {
         let next_node_index = self.indices.get(block_root).copied();
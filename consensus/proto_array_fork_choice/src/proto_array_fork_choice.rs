@@ -1,5 +1,5 @@
 use crate::error::Error;
-use crate::proto_array::ProtoArray;
+use crate::proto_array::{ProtoArray, ViableHead};
 use crate::ssz_container::SszContainer;
 use parking_lot::{RwLock, RwLockReadGuard};
 use ssz::{Decode, Encode};
@@ -203,6 +203,22 @@ impl ProtoArrayForkChoice {
         Some((block.slot, block.state_root))
     }
 
+    /// Returns the root of the latest block with a slot less than or equal to `slot` in the chain
+    /// ending at `block_root`, if that block is still known (i.e., it has not been pruned).
+    pub fn ancestor_at_slot(&self, block_root: &Hash256, slot: Slot) -> Option<Hash256> {
+        self.proto_array
+            .read()
+            .iter_block_roots(block_root)
+            .find(|(_, block_slot)| *block_slot <= slot)
+            .map(|(root, _)| root)
+    }
+
+    /// Returns the leaves of the block tree which are viable to become the head, with their
+    /// weights as of the last call to `Self::find_head`.
+    pub fn viable_heads(&self) -> Vec<ViableHead> {
+        self.proto_array.read().viable_heads()
+    }
+
     pub fn latest_message(&self, validator_index: usize) -> Option<(Hash256, Epoch)> {
         let votes = self.votes.read();
 
@@ -307,6 +323,76 @@ fn compute_deltas(
     Ok(deltas)
 }
 
+#[cfg(test)]
+mod test_viable_heads {
+    use super::*;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn forked_heads() {
+        let epoch = Epoch::new(1);
+        let fork_choice =
+            ProtoArrayForkChoice::new(Slot::new(0), Hash256::zero(), epoch, epoch, root(0))
+                .expect("should create fork choice");
+
+        // 0 <- 1 <- 2 <- 3
+        //       \
+        //        <- 4
+        for (slot, block, parent) in &[(1, 1, 0), (2, 2, 1), (3, 3, 2), (2, 4, 1)] {
+            fork_choice
+                .process_block(
+                    Slot::new(*slot),
+                    root(*block),
+                    root(*parent),
+                    Hash256::zero(),
+                    epoch,
+                    epoch,
+                )
+                .expect("should process block");
+        }
+
+        // Two validators vote for 3, one for 4.
+        for (validator_index, block) in &[(0, 3), (1, 3), (2, 4)] {
+            fork_choice
+                .process_attestation(*validator_index, root(*block), epoch)
+                .expect("should process attestation");
+        }
+        let head = fork_choice
+            .find_head(epoch, root(0), epoch, &[10, 10, 10])
+            .expect("should find head");
+        assert_eq!(head, root(3));
+
+        let mut heads = fork_choice.viable_heads();
+        heads.sort_by_key(|head| head.slot);
+        assert_eq!(
+            heads,
+            vec![
+                ViableHead {
+                    root: root(4),
+                    slot: Slot::new(2),
+                    parent_root: Some(root(1)),
+                    weight: 10,
+                    fork_root: Some(root(1)),
+                    fork_slot: Some(Slot::new(1)),
+                    branch_weight: Some(10),
+                },
+                ViableHead {
+                    root: root(3),
+                    slot: Slot::new(3),
+                    parent_root: Some(root(2)),
+                    weight: 20,
+                    fork_root: Some(root(1)),
+                    fork_slot: Some(Slot::new(1)),
+                    branch_weight: Some(20),
+                },
+            ]
+        );
+    }
+}
+
 #[cfg(test)]
 mod test_compute_deltas {
     use super::*;
@@ -4,6 +4,7 @@ use criterion::Criterion;
 use criterion::{black_box, criterion_group, criterion_main, Benchmark};
 use ssz::Encode;
 use state_processing::{test_utils::BlockBuilder, BlockSignatureStrategy, VerifySignatures};
+use tree_hash::TreeHash;
 use types::{
     BeaconState, ChainSpec, EthSpec, MainnetEthSpec, MinimalEthSpec, SignedBeaconBlock, Slot,
 };
@@ -244,6 +245,54 @@ fn bench_block<T: EthSpec>(
         .sample_size(10),
     );
 
+    let local_state = state.clone();
+    c.bench(
+        &title,
+        Benchmark::new("tree_hash_state/uncached", move |b| {
+            b.iter_batched_ref(
+                || local_state.clone(),
+                |state| black_box(state.tree_hash_root()),
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
+
+    let local_block = block.clone();
+    let mut local_state = state.clone();
+    let local_spec = spec.clone();
+    local_state
+        .build_tree_hash_cache()
+        .expect("should build tree hash cache");
+    c.bench(
+        &title,
+        Benchmark::new("tree_hash_state/cached_after_block", move |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut state = local_state.clone();
+                    state_processing::per_block_processing::<T>(
+                        &mut state,
+                        &local_block,
+                        None,
+                        BlockSignatureStrategy::NoVerification,
+                        &local_spec,
+                    )
+                    .expect("block processing should succeed");
+                    state
+                },
+                |state| {
+                    black_box(
+                        state
+                            .update_tree_hash_cache()
+                            .expect("should update tree hash cache"),
+                    )
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
+
     let local_block = block.clone();
     let local_state = state.clone();
     let local_spec = spec.clone();
@@ -2,7 +2,7 @@ use eth2_hashing::hash;
 use int_to_bytes::int_to_bytes32;
 use merkle_proof::{MerkleTree, MerkleTreeError};
 use safe_arith::SafeArith;
-use types::Hash256;
+use types::{DepositTreeSnapshot, Hash256};
 
 /// Emulates the eth1 deposit contract merkle tree.
 pub struct DepositDataTree {
@@ -21,6 +21,29 @@ impl DepositDataTree {
         }
     }
 
+    /// Create a Merkle tree of the deposits in `snapshot`, to which further deposits can be
+    /// pushed. Proofs cannot be generated for the deposits in the snapshot.
+    pub fn from_snapshot(
+        snapshot: &DepositTreeSnapshot,
+        depth: usize,
+    ) -> Result<Self, MerkleTreeError> {
+        Ok(Self {
+            tree: MerkleTree::from_finalized_snapshot(
+                &snapshot.finalized,
+                snapshot.deposit_count as usize,
+                depth,
+            )?,
+            mix_in_length: snapshot.deposit_count as usize,
+            depth,
+        })
+    }
+
+    /// Returns the roots of the full subtrees which contain the first `deposit_count` deposits,
+    /// as stored in a `DepositTreeSnapshot`.
+    pub fn finalized_hashes(&self, deposit_count: usize) -> Result<Vec<Hash256>, MerkleTreeError> {
+        self.tree.finalized_hashes(deposit_count, self.depth)
+    }
+
     /// Returns 32 bytes representing the "mix in length" for the merkle root of this tree.
     fn length_bytes(&self) -> Vec<u8> {
         int_to_bytes32(self.mix_in_length as u64)
@@ -38,10 +61,10 @@ impl DepositDataTree {
     ///
     /// The Merkle proof is in "bottom-up" order, starting with a leaf node
     /// and moving up the tree. Its length will be exactly equal to `depth + 1`.
-    pub fn generate_proof(&self, index: usize) -> (Hash256, Vec<Hash256>) {
-        let (root, mut proof) = self.tree.generate_proof(index, self.depth);
+    pub fn generate_proof(&self, index: usize) -> Result<(Hash256, Vec<Hash256>), MerkleTreeError> {
+        let (root, mut proof) = self.tree.generate_proof(index, self.depth)?;
         proof.push(Hash256::from_slice(&self.length_bytes()));
-        (root, proof)
+        Ok((root, proof))
     }
 
     /// Add a deposit to the merkle tree.
@@ -18,6 +18,8 @@ pub use validator_statuses::{TotalBalances, ValidatorStatus, ValidatorStatuses};
 /// Provides a summary of validator participation during the epoch.
 pub struct EpochProcessingSummary {
     pub total_balances: TotalBalances,
+    /// The status of each validator, as used to process the epoch.
+    pub statuses: Vec<ValidatorStatus>,
 }
 
 /// Performs per-epoch processing on some BeaconState.
@@ -65,6 +67,7 @@ pub fn per_epoch_processing<T: EthSpec>(
 
     Ok(EpochProcessingSummary {
         total_balances: validator_statuses.total_balances,
+        statuses: validator_statuses.statuses,
     })
 }
 
@@ -164,7 +167,7 @@ pub fn process_final_updates<T: EthSpec>(
 
     // Reset eth1 data votes.
     if (state.slot + 1) % T::SlotsPerEth1VotingPeriod::to_u64() == 0 {
-        state.eth1_data_votes = VariableList::empty();
+        state.clear_eth1_data_votes();
     }
 
     // Update effective balances with hysteresis (lag).
@@ -205,8 +208,7 @@ pub fn process_final_updates<T: EthSpec>(
     }
 
     // Rotate current/previous epoch attestations
-    state.previous_epoch_attestations =
-        std::mem::replace(&mut state.current_epoch_attestations, VariableList::empty());
+    state.rotate_epoch_attestations();
 
     Ok(())
 }
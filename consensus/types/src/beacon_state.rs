@@ -29,6 +29,7 @@ pub use tree_hash_cache::BeaconTreeHashCache;
 mod committee_cache;
 mod clone_config;
 mod exit_cache;
+mod merkle_proofs;
 mod pubkey_cache;
 mod tests;
 mod tree_hash_cache;
@@ -85,6 +86,9 @@ pub enum Error {
     ArithError(ArithError),
     MissingBeaconBlock(SignedBeaconBlockHash),
     MissingBeaconState(BeaconStateHash),
+    /// A Merkle proof was requested for a path which is not a leaf of the state.
+    UnknownProofPath(String),
+    MerkleTreeError(merkle_proof::MerkleTreeError),
 }
 
 /// Control whether an epoch-indexed field can be indexed at the next epoch or not.
@@ -513,7 +517,7 @@ impl<T: EthSpec> BeaconState<T> {
     /// Compute the seed to use for the beacon proposer selection at the given `slot`.
     ///
     /// Spec v0.11.1
-    fn get_beacon_proposer_seed(&self, slot: Slot, spec: &ChainSpec) -> Result<Vec<u8>, Error> {
+    pub fn get_beacon_proposer_seed(&self, slot: Slot, spec: &ChainSpec) -> Result<Vec<u8>, Error> {
         let epoch = slot.epoch(T::slots_per_epoch());
         let mut preimage = self
             .get_seed(epoch, Domain::BeaconProposer, spec)?
@@ -962,6 +966,42 @@ impl<T: EthSpec> BeaconState<T> {
         }
     }
 
+    /// Sets the cache for some `RelativeEpoch`, such as one built from another state with the same
+    /// shuffling. Returns an error if `cache` is not initialized at that epoch.
+    pub fn set_committee_cache(
+        &mut self,
+        relative_epoch: RelativeEpoch,
+        cache: CommitteeCache,
+    ) -> Result<(), Error> {
+        if cache.is_initialized_at(relative_epoch.into_epoch(self.current_epoch())) {
+            self.committee_caches[Self::committee_cache_index(relative_epoch)] = cache;
+            Ok(())
+        } else {
+            Err(Error::CommitteeCacheUninitialized(Some(relative_epoch)))
+        }
+    }
+
+    /// Returns the slot of the last block which influences the shuffling for `epoch`.
+    ///
+    /// Any two states which share the block at this slot will produce identical committees for
+    /// `epoch`, so their committee caches are interchangeable.
+    pub fn shuffling_decision_slot(epoch: Epoch) -> Slot {
+        epoch
+            .saturating_sub(1u64)
+            .start_slot(T::slots_per_epoch())
+            .saturating_sub(1u64)
+    }
+
+    /// Returns the root of the block at the `shuffling_decision_slot` of `relative_epoch`.
+    ///
+    /// Returns an error if the decision slot is not prior to `self.slot`, which is only the case
+    /// for states at the genesis slot.
+    pub fn shuffling_decision_root(&self, relative_epoch: RelativeEpoch) -> Result<Hash256, Error> {
+        let epoch = relative_epoch.into_epoch(self.current_epoch());
+        self.get_block_root(Self::shuffling_decision_slot(epoch))
+            .map(|root| *root)
+    }
+
     /// Drops the cache, leaving it in an uninitialized state.
     fn drop_committee_cache(&mut self, relative_epoch: RelativeEpoch) {
         self.committee_caches[Self::committee_cache_index(relative_epoch)] =
@@ -1054,6 +1094,32 @@ impl<T: EthSpec> BeaconState<T> {
         self.tree_hash_cache = None;
     }
 
+    /// Moves the current epoch attestations into the previous epoch attestations, leaving the
+    /// current epoch attestations empty.
+    ///
+    /// The tree hash cache is updated accordingly, this method should always be used instead of
+    /// modifying the lists directly.
+    pub fn rotate_epoch_attestations(&mut self) {
+        self.previous_epoch_attestations =
+            std::mem::replace(&mut self.current_epoch_attestations, VariableList::empty());
+
+        if let Some(cache) = self.tree_hash_cache.as_mut() {
+            cache.rotate_epoch_attestations();
+        }
+    }
+
+    /// Removes all eth1 data votes.
+    ///
+    /// The tree hash cache is updated accordingly, this method should always be used instead of
+    /// clearing the list directly.
+    pub fn clear_eth1_data_votes(&mut self) {
+        self.eth1_data_votes = VariableList::empty();
+
+        if let Some(cache) = self.tree_hash_cache.as_mut() {
+            cache.reset_eth1_data_votes();
+        }
+    }
+
     /// Clone the state whilst preserving only the selected caches.
     pub fn clone_with(&self, config: CloneConfig) -> Self {
         BeaconState {
@@ -1152,6 +1218,12 @@ impl From<tree_hash::Error> for Error {
     }
 }
 
+impl From<merkle_proof::MerkleTreeError> for Error {
+    fn from(e: merkle_proof::MerkleTreeError) -> Error {
+        Error::MerkleTreeError(e)
+    }
+}
+
 impl From<ArithError> for Error {
     fn from(e: ArithError) -> Error {
         Error::ArithError(e)
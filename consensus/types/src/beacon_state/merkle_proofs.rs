@@ -0,0 +1,221 @@
+//! Merkle proofs of the fields of a `BeaconState` against its tree hash root, e.g. so that a light
+//! client can verify a validator's balance or the finalized checkpoint without the whole state.
+//!
+//! Leaves are identified by a path, which is the name of a field, optionally followed by the name
+//! of a field of a checkpoint or the index of an item of the validators or balances lists:
+//!
+//! - `slot`
+//! - `finalized_checkpoint.root`
+//! - `balances.5` (the chunk holding the balances of validators 4 to 7)
+//! - `validators.5`
+
+use super::{BeaconState, Error};
+use crate::{EthSpec, Hash256};
+use merkle_proof::multiproof::{branch_nodes, concat_generalized_indices, Multiproof};
+use merkle_proof::MerkleTree;
+use ssz_types::typenum::Unsigned;
+use std::collections::HashMap;
+use tree_hash::TreeHash;
+
+/// The names of the fields of a `BeaconState`, in the order they are hashed.
+const FIELDS: [&str; 21] = [
+    "genesis_time",
+    "genesis_validators_root",
+    "slot",
+    "fork",
+    "latest_block_header",
+    "block_roots",
+    "state_roots",
+    "historical_roots",
+    "eth1_data",
+    "eth1_data_votes",
+    "eth1_deposit_index",
+    "validators",
+    "balances",
+    "randao_mixes",
+    "slashings",
+    "previous_epoch_attestations",
+    "current_epoch_attestations",
+    "justification_bits",
+    "previous_justified_checkpoint",
+    "current_justified_checkpoint",
+    "finalized_checkpoint",
+];
+
+/// The depth of the tree whose leaves are the roots of the fields of a `BeaconState`.
+const STATE_TREE_DEPTH: usize = 5;
+
+/// A leaf within the subtree of a field, along with its Merkle branch up to the root of the
+/// field.
+struct SubtreeLeaf {
+    index: u64,
+    leaf: Hash256,
+    branch: Vec<Hash256>,
+}
+
+impl<T: EthSpec> BeaconState<T> {
+    /// Builds a multiproof of the leaves at `paths` against the tree hash root of the state.
+    pub fn compute_multiproof(&self, paths: &[String]) -> Result<Multiproof, Error> {
+        let field_roots = self.field_roots();
+        let state_tree = MerkleTree::create(&field_roots, STATE_TREE_DEPTH);
+
+        let mut indices = Vec::with_capacity(paths.len());
+        let mut nodes = HashMap::new();
+        for path in paths {
+            let mut parts = path.splitn(2, '.');
+            let field_name = parts.next().unwrap_or_default();
+            let field = FIELDS
+                .iter()
+                .position(|name| *name == field_name)
+                .ok_or_else(|| Error::UnknownProofPath(path.clone()))?;
+
+            let subtree_leaf = match parts.next() {
+                None => SubtreeLeaf {
+                    index: 1,
+                    leaf: field_roots[field],
+                    branch: vec![],
+                },
+                Some(subpath) => self.subtree_leaf(field_name, subpath, path)?,
+            };
+
+            let (_, state_branch) = state_tree.generate_proof(field, STATE_TREE_DEPTH)?;
+            let index = concat_generalized_indices(&[
+                (1 << STATE_TREE_DEPTH) + field as u64,
+                subtree_leaf.index,
+            ])
+            .ok_or_else(|| Error::UnknownProofPath(path.clone()))?;
+            let mut branch = subtree_leaf.branch;
+            branch.extend(state_branch);
+
+            nodes.extend(branch_nodes(index, subtree_leaf.leaf, &branch)?);
+            indices.push(index);
+        }
+
+        Multiproof::from_nodes(indices, &nodes).map_err(Into::into)
+    }
+
+    /// The tree hash root of each field, in the order of `FIELDS`.
+    fn field_roots(&self) -> Vec<Hash256> {
+        vec![
+            self.genesis_time.tree_hash_root(),
+            self.genesis_validators_root.tree_hash_root(),
+            self.slot.tree_hash_root(),
+            self.fork.tree_hash_root(),
+            self.latest_block_header.tree_hash_root(),
+            self.block_roots.tree_hash_root(),
+            self.state_roots.tree_hash_root(),
+            self.historical_roots.tree_hash_root(),
+            self.eth1_data.tree_hash_root(),
+            self.eth1_data_votes.tree_hash_root(),
+            self.eth1_deposit_index.tree_hash_root(),
+            self.validators.tree_hash_root(),
+            self.balances.tree_hash_root(),
+            self.randao_mixes.tree_hash_root(),
+            self.slashings.tree_hash_root(),
+            self.previous_epoch_attestations.tree_hash_root(),
+            self.current_epoch_attestations.tree_hash_root(),
+            self.justification_bits.tree_hash_root(),
+            self.previous_justified_checkpoint.tree_hash_root(),
+            self.current_justified_checkpoint.tree_hash_root(),
+            self.finalized_checkpoint.tree_hash_root(),
+        ]
+    }
+
+    /// The leaf at `subpath` within the field `field_name`.
+    fn subtree_leaf(
+        &self,
+        field_name: &str,
+        subpath: &str,
+        path: &str,
+    ) -> Result<SubtreeLeaf, Error> {
+        let unknown_path = || Error::UnknownProofPath(path.to_string());
+
+        match field_name {
+            "previous_justified_checkpoint"
+            | "current_justified_checkpoint"
+            | "finalized_checkpoint" => {
+                let checkpoint = match field_name {
+                    "previous_justified_checkpoint" => &self.previous_justified_checkpoint,
+                    "current_justified_checkpoint" => &self.current_justified_checkpoint,
+                    _ => &self.finalized_checkpoint,
+                };
+                let leaf_index = match subpath {
+                    "epoch" => 0,
+                    "root" => 1,
+                    _ => return Err(unknown_path()),
+                };
+                let leaves = [
+                    checkpoint.epoch.tree_hash_root(),
+                    checkpoint.root.tree_hash_root(),
+                ];
+                let (leaf, branch) =
+                    MerkleTree::create(&leaves, 1).generate_proof(leaf_index, 1)?;
+                Ok(SubtreeLeaf {
+                    index: 2 + leaf_index as u64,
+                    leaf,
+                    branch,
+                })
+            }
+            "validators" => {
+                let index = subpath.parse::<usize>().map_err(|_| unknown_path())?;
+                let leaves = self
+                    .validators
+                    .iter()
+                    .map(TreeHash::tree_hash_root)
+                    .collect::<Vec<_>>();
+                list_leaf(&leaves, index, T::ValidatorRegistryLimit::to_usize())
+            }
+            "balances" => {
+                let index = subpath.parse::<usize>().map_err(|_| unknown_path())?;
+                // balances are packed four to a chunk
+                let leaves = self
+                    .balances
+                    .chunks(4)
+                    .map(|balances| {
+                        let mut chunk = [0; 32];
+                        for (i, balance) in balances.iter().enumerate() {
+                            chunk[i * 8..(i + 1) * 8].copy_from_slice(&balance.to_le_bytes());
+                        }
+                        Hash256::from(chunk)
+                    })
+                    .collect::<Vec<_>>();
+                if index >= self.balances.len() {
+                    return Err(Error::UnknownValidator(index as u64));
+                }
+                let chunk_limit = (T::ValidatorRegistryLimit::to_usize() + 3) / 4;
+                let mut leaf = list_leaf(&leaves, index / 4, chunk_limit)?;
+                // the length is that of the list, not the number of chunks
+                if let Some(length) = leaf.branch.last_mut() {
+                    *length = length_chunk(self.balances.len());
+                }
+                Ok(leaf)
+            }
+            _ => Err(unknown_path()),
+        }
+    }
+}
+
+/// The leaf at `index` of a list with at most `limit` leaves, whose root mixes in the length.
+fn list_leaf(leaves: &[Hash256], index: usize, limit: usize) -> Result<SubtreeLeaf, Error> {
+    if index >= leaves.len() {
+        return Err(Error::UnknownValidator(index as u64));
+    }
+
+    let depth = limit.next_power_of_two().trailing_zeros() as usize;
+    let (leaf, mut branch) = MerkleTree::create(leaves, depth).generate_proof(index, depth)?;
+    branch.push(length_chunk(leaves.len()));
+
+    Ok(SubtreeLeaf {
+        // the data root is the left child of the list root
+        index: (2 << depth) + index as u64,
+        leaf,
+        branch,
+    })
+}
+
+/// The chunk mixed into the root of a list of `length` items.
+fn length_chunk(length: usize) -> Hash256 {
+    let mut chunk = [0; 32];
+    chunk[..8].copy_from_slice(&(length as u64).to_le_bytes());
+    Hash256::from(chunk)
+}
@@ -125,6 +125,49 @@ fn cache_initialization() {
     test_cache_initialization(&mut state, RelativeEpoch::Next, &spec);
 }
 
+#[test]
+fn set_committee_cache() {
+    let spec = MinimalEthSpec::default_spec();
+
+    let builder: TestingBeaconStateBuilder<MinimalEthSpec> =
+        TestingBeaconStateBuilder::from_deterministic_keypairs(16, &spec);
+    let (mut state, _keypairs) = builder.build();
+
+    state.slot =
+        (MinimalEthSpec::genesis_epoch() + 2).start_slot(MinimalEthSpec::slots_per_epoch());
+    state
+        .build_committee_cache(RelativeEpoch::Current, &spec)
+        .unwrap();
+    let cache = state
+        .committee_cache(RelativeEpoch::Current)
+        .unwrap()
+        .clone();
+
+    let mut other = state.clone_with(CloneConfig::none());
+    assert_eq!(
+        other.set_committee_cache(RelativeEpoch::Previous, cache.clone()),
+        Err(BeaconStateError::CommitteeCacheUninitialized(Some(
+            RelativeEpoch::Previous
+        )))
+    );
+    other
+        .set_committee_cache(RelativeEpoch::Current, cache)
+        .unwrap();
+    assert_eq!(
+        other.get_beacon_committee(state.slot, 0).unwrap().committee,
+        state.get_beacon_committee(state.slot, 0).unwrap().committee
+    );
+
+    assert_eq!(
+        BeaconState::<MinimalEthSpec>::shuffling_decision_slot(Epoch::new(2)),
+        Slot::new(MinimalEthSpec::slots_per_epoch() - 1)
+    );
+    assert_eq!(
+        BeaconState::<MinimalEthSpec>::shuffling_decision_slot(Epoch::new(0)),
+        Slot::new(0)
+    );
+}
+
 fn test_clone_config<E: EthSpec>(base_state: &BeaconState<E>, clone_config: CloneConfig) {
     let state = base_state.clone_with(clone_config.clone());
     if clone_config.committee_caches {
@@ -213,6 +256,143 @@ fn tree_hash_cache() {
     assert_eq!(root.as_bytes(), &state.tree_hash_root()[..]);
 }
 
+#[test]
+fn merkle_multiproof() {
+    use tree_hash::TreeHash;
+
+    let spec = MinimalEthSpec::default_spec();
+    let builder: TestingBeaconStateBuilder<MinimalEthSpec> =
+        TestingBeaconStateBuilder::from_deterministic_keypairs(10, &spec);
+    let (mut state, _keypairs) = builder.build();
+    state.balances[5] = 42;
+    state.finalized_checkpoint.root = Hash256::repeat_byte(7);
+
+    let paths = vec![
+        "slot".to_string(),
+        "finalized_checkpoint.root".to_string(),
+        "balances.5".to_string(),
+        "validators.9".to_string(),
+    ];
+    let proof = state.compute_multiproof(&paths).unwrap();
+
+    assert_eq!(proof.root(), Ok(state.tree_hash_root()));
+    assert_eq!(proof.leaves[0], state.slot.tree_hash_root());
+    assert_eq!(proof.leaves[1], Hash256::repeat_byte(7));
+    assert_eq!(&proof.leaves[2].as_bytes()[8..16], &42u64.to_le_bytes()[..]);
+    assert_eq!(proof.leaves[3], state.validators[9].tree_hash_root());
+
+    assert_eq!(
+        state.compute_multiproof(&["balances.10".to_string()]),
+        Err(BeaconStateError::UnknownValidator(10))
+    );
+    assert_eq!(
+        state.compute_multiproof(&["finalized_checkpoint.slot".to_string()]),
+        Err(BeaconStateError::UnknownProofPath(
+            "finalized_checkpoint.slot".to_string()
+        ))
+    );
+}
+
+#[test]
+fn tree_hash_cache_append_only_lists() {
+    use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use tree_hash::TreeHash;
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let mut state: FoundationBeaconState = BeaconState::random_for_test(&mut rng);
+
+    let check_root = |state: &mut FoundationBeaconState| {
+        let root = state.update_tree_hash_cache().unwrap();
+        assert_eq!(root.as_bytes(), &state.tree_hash_root()[..]);
+    };
+
+    check_root(&mut state);
+
+    for _ in 0..3 {
+        state
+            .current_epoch_attestations
+            .push(PendingAttestation::random_for_test(&mut rng))
+            .unwrap();
+        state
+            .eth1_data_votes
+            .push(Eth1Data::random_for_test(&mut rng))
+            .unwrap();
+        check_root(&mut state);
+    }
+
+    state.rotate_epoch_attestations();
+    check_root(&mut state);
+
+    state.clear_eth1_data_votes();
+    check_root(&mut state);
+
+    state
+        .current_epoch_attestations
+        .push(PendingAttestation::random_for_test(&mut rng))
+        .unwrap();
+    state
+        .eth1_data_votes
+        .push(Eth1Data::random_for_test(&mut rng))
+        .unwrap();
+    check_root(&mut state);
+
+    // Shrinking a list without using the hooks must also be detected.
+    state.previous_epoch_attestations = VariableList::empty();
+    check_root(&mut state);
+}
+
+#[test]
+fn tree_hash_cache_replaced_append_only_lists() {
+    use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use tree_hash::TreeHash;
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let mut state: FoundationBeaconState = BeaconState::random_for_test(&mut rng);
+
+    let check_root = |state: &mut FoundationBeaconState| {
+        let root = state.update_tree_hash_cache().unwrap();
+        assert_eq!(root.as_bytes(), &state.tree_hash_root()[..]);
+    };
+
+    let random_votes = |rng: &mut XorShiftRng, n: usize| {
+        VariableList::new((0..n).map(|_| Eth1Data::random_for_test(rng)).collect()).unwrap()
+    };
+    let random_attestations = |rng: &mut XorShiftRng, n: usize| {
+        VariableList::new(
+            (0..n)
+                .map(|_| PendingAttestation::random_for_test(rng))
+                .collect(),
+        )
+        .unwrap()
+    };
+
+    state.eth1_data_votes = random_votes(&mut rng, 3);
+    state.current_epoch_attestations = random_attestations(&mut rng, 3);
+    check_root(&mut state);
+
+    // Replace the lists with different contents of the same length.
+    state.eth1_data_votes = random_votes(&mut rng, 3);
+    state.current_epoch_attestations = random_attestations(&mut rng, 3);
+    check_root(&mut state);
+
+    // Replace the lists with different, longer contents.
+    state.eth1_data_votes = random_votes(&mut rng, 5);
+    state.current_epoch_attestations = random_attestations(&mut rng, 5);
+    check_root(&mut state);
+
+    // Replace the lists in a later epoch, keeping the last item the same.
+    let last_vote = state.eth1_data_votes[4].clone();
+    let last_attestation = state.current_epoch_attestations[4].clone();
+    state.slot += <FoundationEthSpec as EthSpec>::SlotsPerEth1VotingPeriod::to_u64();
+    state.eth1_data_votes = random_votes(&mut rng, 5);
+    state.eth1_data_votes[4] = last_vote;
+    state.current_epoch_attestations = random_attestations(&mut rng, 5);
+    state.current_epoch_attestations[4] = last_attestation;
+    check_root(&mut state);
+}
+
 /// Tests committee-specific components
 #[cfg(test)]
 mod committees {
@@ -37,6 +37,9 @@ pub struct BeaconTreeHashCache {
     balances: TreeHashCache,
     randao_mixes: TreeHashCache,
     slashings: TreeHashCache,
+    eth1_data_votes: AppendOnlyListTreeHashCache,
+    previous_epoch_attestations: AppendOnlyListTreeHashCache,
+    current_epoch_attestations: AppendOnlyListTreeHashCache,
 }
 
 impl BeaconTreeHashCache {
@@ -70,9 +73,33 @@ impl BeaconTreeHashCache {
             balances,
             randao_mixes,
             slashings,
+            eth1_data_votes: AppendOnlyListTreeHashCache::new(eth1_data_votes_depth::<T>()),
+            previous_epoch_attestations: AppendOnlyListTreeHashCache::new(
+                pending_attestations_depth::<T>(),
+            ),
+            current_epoch_attestations: AppendOnlyListTreeHashCache::new(
+                pending_attestations_depth::<T>(),
+            ),
         }
     }
 
+    /// Must be called whenever `state.current_epoch_attestations` is moved into
+    /// `state.previous_epoch_attestations` (see `BeaconState::rotate_epoch_attestations`).
+    ///
+    /// The cache for the current epoch attestations is moved to the previous epoch attestations
+    /// so that the hashing already performed is not lost.
+    pub fn rotate_epoch_attestations(&mut self) {
+        let empty = AppendOnlyListTreeHashCache::new(self.current_epoch_attestations.depth);
+        self.previous_epoch_attestations =
+            std::mem::replace(&mut self.current_epoch_attestations, empty);
+    }
+
+    /// Must be called whenever `state.eth1_data_votes` is cleared (see
+    /// `BeaconState::clear_eth1_data_votes`).
+    pub fn reset_eth1_data_votes(&mut self) {
+        self.eth1_data_votes.reset();
+    }
+
     /// Updates the cache and returns the tree hash root for the given `state`.
     ///
     /// The provided `state` should be a descendant of the last `state` given to this function, or
@@ -107,7 +134,14 @@ impl BeaconTreeHashCache {
                 .as_bytes(),
         )?;
         hasher.write(state.eth1_data.tree_hash_root().as_bytes())?;
-        hasher.write(state.eth1_data_votes.tree_hash_root().as_bytes())?;
+        hasher.write(
+            self.eth1_data_votes
+                .recalculate_tree_hash_root(
+                    &state.eth1_data_votes[..],
+                    state.slot.as_u64() / T::SlotsPerEth1VotingPeriod::to_u64(),
+                )?
+                .as_bytes(),
+        )?;
         hasher.write(state.eth1_deposit_index.tree_hash_root().as_bytes())?;
         hasher.write(
             self.validators
@@ -133,12 +167,21 @@ impl BeaconTreeHashCache {
                 .as_bytes(),
         )?;
         hasher.write(
-            state
-                .previous_epoch_attestations
-                .tree_hash_root()
+            self.previous_epoch_attestations
+                .recalculate_tree_hash_root(
+                    &state.previous_epoch_attestations[..],
+                    state.previous_epoch().as_u64(),
+                )?
+                .as_bytes(),
+        )?;
+        hasher.write(
+            self.current_epoch_attestations
+                .recalculate_tree_hash_root(
+                    &state.current_epoch_attestations[..],
+                    state.current_epoch().as_u64(),
+                )?
                 .as_bytes(),
         )?;
-        hasher.write(state.current_epoch_attestations.tree_hash_root().as_bytes())?;
         hasher.write(state.justification_bits.tree_hash_root().as_bytes())?;
         hasher.write(
             state
@@ -166,6 +209,88 @@ impl BeaconTreeHashCache {
     }
 }
 
+/// Returns the depth of the Merkle tree for `state.eth1_data_votes`.
+fn eth1_data_votes_depth<T: EthSpec>() -> usize {
+    int_log(T::SlotsPerEth1VotingPeriod::to_usize())
+}
+
+/// Returns the depth of the Merkle tree for `state.{previous,current}_epoch_attestations`.
+fn pending_attestations_depth<T: EthSpec>() -> usize {
+    int_log(T::MaxPendingAttestations::to_usize())
+}
+
+/// A cache for computing the tree hash root of a list which is only ever appended to until it is
+/// cleared (e.g., `state.eth1_data_votes`).
+///
+/// The root of each item is only computed once, when it is first appended to the list. Clearing
+/// the list should be followed by a call to `Self::reset`, but the cache is also rebuilt if it
+/// detects that the list has been replaced: when the list has shrunk, when the generation (e.g.,
+/// the epoch of a list of attestations) has changed or when the last cached item no longer
+/// matches the list.
+#[derive(Debug, PartialEq, Clone, Default, Encode, Decode)]
+struct AppendOnlyListTreeHashCache {
+    depth: usize,
+    /// The generation of the list that the cached roots were computed for.
+    generation: u64,
+    arena: CacheArena,
+    cache: TreeHashCache,
+    /// The tree hash root of each item that has been hashed so far.
+    leaves: Vec<Hash256>,
+}
+
+impl AppendOnlyListTreeHashCache {
+    /// Instantiates a new, empty cache for a list with a maximum length of `2^depth`.
+    fn new(depth: usize) -> Self {
+        let mut arena = CacheArena::default();
+        Self {
+            depth,
+            generation: 0,
+            cache: TreeHashCache::new(&mut arena, depth, 0),
+            arena,
+            leaves: vec![],
+        }
+    }
+
+    /// Discards all cached roots.
+    fn reset(&mut self) {
+        *self = Self::new(self.depth);
+    }
+
+    /// Updates the cache and returns the tree hash root of `items`.
+    ///
+    /// Only the items which have been appended since the last call to this function are hashed,
+    /// unless `items` is detected to be a different list to the one that was cached, in which
+    /// case the cache is rebuilt. The list is considered replaced if `generation` differs from the
+    /// previous call.
+    fn recalculate_tree_hash_root<T: TreeHash>(
+        &mut self,
+        items: &[T],
+        generation: u64,
+    ) -> Result<Hash256, Error> {
+        let replaced = generation != self.generation
+            || items.len() < self.leaves.len()
+            || self.leaves.last().map_or(false, |last_leaf| {
+                *last_leaf != items[self.leaves.len() - 1].tree_hash_root()
+            });
+
+        if replaced {
+            self.reset();
+            self.generation = generation;
+        }
+
+        let num_hashed = self.leaves.len();
+        self.leaves
+            .extend(items[num_hashed..].iter().map(TreeHash::tree_hash_root));
+
+        let root = self.cache.recalculate_merkle_root(
+            &mut self.arena,
+            self.leaves.iter().map(|leaf| leaf.to_fixed_bytes()),
+        )?;
+
+        Ok(mix_in_length(&root, items.len()))
+    }
+}
+
 /// A specialized cache for computing the tree hash root of `state.validators`.
 #[derive(Debug, PartialEq, Clone, Default, Encode, Decode)]
 struct ValidatorsListTreeHashCache {
@@ -3,6 +3,7 @@ use int_to_bytes::int_to_bytes4;
 use serde_derive::{Deserialize, Serialize};
 use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 use tree_hash::TreeHash;
 use utils::{
     fork_from_hex_str, fork_to_hex_str, u32_from_hex_str, u32_to_hex_str, u8_from_hex_str,
@@ -122,6 +123,10 @@ pub struct ChainSpec {
     pub network_id: u8,
     pub attestation_propagation_slot_range: u64,
     pub maximum_gossip_clock_disparity_millis: u64,
+    pub gossip_max_size: u64,
+    pub max_chunk_size: u64,
+    pub ttfb_timeout: u64,
+    pub resp_timeout: u64,
     pub target_aggregators_per_committee: u64,
     pub attestation_subnet_count: u64,
     pub random_subnets_per_validator: u64,
@@ -144,6 +149,12 @@ impl ChainSpec {
         }
     }
 
+    /// Returns the maximum tolerated difference between our clock and the clock of a peer which
+    /// sent us a message on gossip.
+    pub fn maximum_gossip_clock_disparity(&self) -> Duration {
+        Duration::from_millis(self.maximum_gossip_clock_disparity_millis)
+    }
+
     /// Returns the epoch of the next scheduled change in the `fork.current_version`.
     ///
     /// There are no future forks scheduled so this function always returns `None`. This may not
@@ -334,6 +345,10 @@ impl ChainSpec {
             attestation_subnet_count: 64,
             random_subnets_per_validator: 1,
             maximum_gossip_clock_disparity_millis: 500,
+            gossip_max_size: 1_048_576,
+            max_chunk_size: 1_048_576,
+            ttfb_timeout: 5,
+            resp_timeout: 10,
             target_aggregators_per_committee: 16,
             epochs_per_random_subnet_subscription: 256,
         }
@@ -551,6 +566,14 @@ pub struct YamlConfig {
     random_subnets_per_validator: u64,
     epochs_per_random_subnet_subscription: u64,
     seconds_per_eth1_block: u64,
+
+    // Networking
+    gossip_max_size: u64,
+    max_chunk_size: u64,
+    ttfb_timeout: u64,
+    resp_timeout: u64,
+    attestation_propagation_slot_range: u64,
+    maximum_gossip_clock_disparity: u64,
 }
 
 impl Default for YamlConfig {
@@ -631,6 +654,14 @@ impl YamlConfig {
             random_subnets_per_validator: spec.random_subnets_per_validator,
             epochs_per_random_subnet_subscription: spec.epochs_per_random_subnet_subscription,
             seconds_per_eth1_block: spec.seconds_per_eth1_block,
+
+            // Networking
+            gossip_max_size: spec.gossip_max_size,
+            max_chunk_size: spec.max_chunk_size,
+            ttfb_timeout: spec.ttfb_timeout,
+            resp_timeout: spec.resp_timeout,
+            attestation_propagation_slot_range: spec.attestation_propagation_slot_range,
+            maximum_gossip_clock_disparity: spec.maximum_gossip_clock_disparity_millis,
         }
     }
 
@@ -705,6 +736,12 @@ impl YamlConfig {
             boot_nodes: chain_spec.boot_nodes.clone(),
             genesis_fork_version: self.genesis_fork_version,
             eth1_follow_distance: self.eth1_follow_distance,
+            gossip_max_size: self.gossip_max_size,
+            max_chunk_size: self.max_chunk_size,
+            ttfb_timeout: self.ttfb_timeout,
+            resp_timeout: self.resp_timeout,
+            attestation_propagation_slot_range: self.attestation_propagation_slot_range,
+            maximum_gossip_clock_disparity_millis: self.maximum_gossip_clock_disparity,
             ..*chain_spec
         })
     }
@@ -0,0 +1,112 @@
+use crate::*;
+use eth2_hashing::{hash32_concat, ZERO_HASHES};
+use int_to_bytes::int_to_bytes32;
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+
+/// The finalized portion of the eth1 deposit contract tree, from which the tree can be extended
+/// without the preceding deposits (EIP-4881).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct DepositTreeSnapshot {
+    /// The roots of the full subtrees which contain the first `deposit_count` deposits, from left
+    /// to right. There is one subtree of `2^i` deposits for each bit `i` set in `deposit_count`.
+    pub finalized: Vec<Hash256>,
+    pub deposit_root: Hash256,
+    pub deposit_count: u64,
+    /// The eth1 block at which the deposit contract had `deposit_count` deposits.
+    pub execution_block_hash: Hash256,
+    pub execution_block_height: u64,
+}
+
+impl DepositTreeSnapshot {
+    /// Computes the deposit root from the `finalized` hashes.
+    ///
+    /// Returns `None` if there is not exactly one hash for each bit set in `deposit_count`.
+    pub fn calculate_root(&self) -> Option<Hash256> {
+        let mut size = self.deposit_count;
+        let mut index = self.finalized.len();
+        let mut root = Hash256::from_slice(&ZERO_HASHES[0]);
+
+        for height in 0..DEPOSIT_TREE_DEPTH {
+            root = if size & 1 == 1 {
+                index = index.checked_sub(1)?;
+                Hash256::from_slice(&hash32_concat(
+                    self.finalized[index].as_bytes(),
+                    root.as_bytes(),
+                ))
+            } else {
+                Hash256::from_slice(&hash32_concat(root.as_bytes(), &ZERO_HASHES[height]))
+            };
+            size >>= 1;
+        }
+
+        if index != 0 || size != 0 {
+            return None;
+        }
+
+        Some(Hash256::from_slice(&hash32_concat(
+            root.as_bytes(),
+            &int_to_bytes32(self.deposit_count),
+        )))
+    }
+
+    /// Returns true if the `finalized` hashes are consistent with the `deposit_root`.
+    pub fn is_valid(&self) -> bool {
+        self.calculate_root() == Some(self.deposit_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merkle_proof::MerkleTree;
+    use ssz::{Decode, Encode};
+
+    fn build_snapshot(deposit_count: u64) -> (DepositTreeSnapshot, Hash256) {
+        let leaves = (0..deposit_count)
+            .map(Hash256::from_low_u64_be)
+            .collect::<Vec<_>>();
+        let tree = MerkleTree::create(&leaves, DEPOSIT_TREE_DEPTH);
+        let deposit_root = Hash256::from_slice(&hash32_concat(
+            tree.hash().as_bytes(),
+            &int_to_bytes32(deposit_count),
+        ));
+        let snapshot = DepositTreeSnapshot {
+            finalized: tree
+                .finalized_hashes(deposit_count as usize, DEPOSIT_TREE_DEPTH)
+                .unwrap(),
+            deposit_root,
+            deposit_count,
+            execution_block_hash: Hash256::from_low_u64_be(42),
+            execution_block_height: 42,
+        };
+        (snapshot, deposit_root)
+    }
+
+    #[test]
+    fn calculate_root() {
+        for deposit_count in 0..20 {
+            let (snapshot, deposit_root) = build_snapshot(deposit_count);
+            assert_eq!(snapshot.calculate_root(), Some(deposit_root));
+            assert!(snapshot.is_valid());
+        }
+    }
+
+    #[test]
+    fn invalid_finalized_hashes() {
+        let (mut snapshot, _) = build_snapshot(5);
+        snapshot.finalized.pop();
+        assert_eq!(snapshot.calculate_root(), None);
+
+        let (mut snapshot, _) = build_snapshot(5);
+        snapshot.finalized[0] = Hash256::zero();
+        assert!(!snapshot.is_valid());
+    }
+
+    #[test]
+    fn ssz_round_trip() {
+        let (snapshot, _) = build_snapshot(7);
+        let bytes = snapshot.as_ssz_bytes();
+        assert_eq!(DepositTreeSnapshot::from_ssz_bytes(&bytes), Ok(snapshot));
+    }
+}
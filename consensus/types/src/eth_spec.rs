@@ -15,6 +15,7 @@ pub trait EthSpec: 'static + Default + Sync + Send + Clone + Debug + PartialEq {
     type GenesisEpoch: Unsigned + Clone + Sync + Send + Debug + PartialEq;
     type JustificationBitsLength: Unsigned + Clone + Sync + Send + Debug + PartialEq + Default;
     type SubnetBitfieldLength: Unsigned + Clone + Sync + Send + Debug + PartialEq + Default;
+    type SyncCommitteeSubnetCount: Unsigned + Clone + Sync + Send + Debug + PartialEq + Default;
     /*
      * Misc
      */
@@ -141,6 +142,7 @@ pub struct MainnetEthSpec;
 impl EthSpec for MainnetEthSpec {
     type JustificationBitsLength = U4;
     type SubnetBitfieldLength = U64;
+    type SyncCommitteeSubnetCount = U4;
     type MaxValidatorsPerCommittee = U2048;
     type GenesisEpoch = U0;
     type SlotsPerEpoch = U32;
@@ -188,6 +190,7 @@ impl EthSpec for MinimalEthSpec {
     params_from_eth_spec!(MainnetEthSpec {
         JustificationBitsLength,
         SubnetBitfieldLength,
+        SyncCommitteeSubnetCount,
         MaxValidatorsPerCommittee,
         GenesisEpoch,
         HistoricalRootsLimit,
@@ -227,6 +230,7 @@ impl EthSpec for InteropEthSpec {
     params_from_eth_spec!(MainnetEthSpec {
         JustificationBitsLength,
         SubnetBitfieldLength,
+        SyncCommitteeSubnetCount,
         MaxValidatorsPerCommittee,
         GenesisEpoch,
         HistoricalRootsLimit,
@@ -306,7 +306,9 @@ impl<T: EthSpec> TestingBeaconBlockBuilder<T> {
         // Building proofs
         let mut proofs = vec![];
         for i in 0..leaves.len() {
-            let (_, mut proof) = tree.generate_proof(i, spec.deposit_contract_tree_depth as usize);
+            let (_, mut proof) = tree
+                .generate_proof(i, spec.deposit_contract_tree_depth as usize)
+                .expect("should generate proof for a complete tree");
             proof.push(Hash256::from_slice(&int_to_bytes32(leaves.len() as u64)));
             proofs.push(proof);
         }
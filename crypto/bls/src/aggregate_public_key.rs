@@ -1,5 +1,6 @@
 use super::{PublicKey, BLS_PUBLIC_KEY_BYTE_SIZE};
-use milagro_bls::AggregatePublicKey as RawAggregatePublicKey;
+use crate::backend::AggregatePublicKey as RawAggregatePublicKey;
+use crate::impls::TAggregatePublicKey;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -9,46 +10,40 @@ use ssz::{Decode, DecodeError, Encode};
 ///
 /// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
 /// serialization).
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct AggregatePublicKey(RawAggregatePublicKey);
 
 impl AggregatePublicKey {
     pub fn new() -> Self {
-        AggregatePublicKey(RawAggregatePublicKey::new())
+        AggregatePublicKey(RawAggregatePublicKey::infinity())
+    }
+
+    /// Returns the sum of all the given `public_keys`.
+    pub fn aggregate(public_keys: &[&PublicKey]) -> Self {
+        let raw_keys: Vec<_> = public_keys.iter().map(|pk| pk.as_raw()).collect();
+        AggregatePublicKey(RawAggregatePublicKey::aggregate(&raw_keys))
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        let pubkey = RawAggregatePublicKey::from_bytes(&bytes).map_err(|_| {
+        let pubkey = RawAggregatePublicKey::deserialize(&bytes).map_err(|_| {
             DecodeError::BytesInvalid(format!("Invalid AggregatePublicKey bytes: {:?}", bytes))
         })?;
 
         Ok(AggregatePublicKey(pubkey))
     }
 
-    pub fn add_without_affine(&mut self, public_key: &PublicKey) {
-        self.0.point.add(&public_key.as_raw().point)
-    }
-
-    pub fn affine(&mut self) {
-        self.0.point.affine()
-    }
-
     pub fn add(&mut self, public_key: &PublicKey) {
-        self.0.add(public_key.as_raw())
+        self.0.add_assign(public_key.as_raw())
     }
 
     /// Returns the underlying public key.
-    pub fn as_raw(&self) -> &RawAggregatePublicKey {
+    pub(crate) fn as_raw(&self) -> &RawAggregatePublicKey {
         &self.0
     }
 
     /// Returns the underlying point as compressed bytes.
     pub fn as_bytes(&self) -> Vec<u8> {
-        self.as_raw().as_bytes()
-    }
-
-    pub fn into_raw(self) -> RawAggregatePublicKey {
-        self.0
+        self.as_raw().serialize()
     }
 
     /// Return a hex string representation of this key's bytes.
@@ -58,6 +53,12 @@ impl AggregatePublicKey {
     }
 }
 
+impl Default for AggregatePublicKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl_ssz!(
     AggregatePublicKey,
     BLS_PUBLIC_KEY_BYTE_SIZE,
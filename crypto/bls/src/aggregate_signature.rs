@@ -1,5 +1,6 @@
 use super::*;
-use milagro_bls::{AggregateSignature as RawAggregateSignature, G2Point};
+use crate::backend::AggregateSignature as RawAggregateSignature;
+use crate::impls::TAggregateSignature;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -9,7 +10,7 @@ use ssz::{Decode, DecodeError, Encode};
 ///
 /// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
 /// serialization).
-#[derive(Debug, PartialEq, Clone, Default, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq)]
 pub struct AggregateSignature {
     aggregate_signature: RawAggregateSignature,
     is_empty: bool,
@@ -22,7 +23,7 @@ impl AggregateSignature {
     /// AggregateSignature is point at infinity
     pub fn new() -> Self {
         Self {
-            aggregate_signature: RawAggregateSignature::new(),
+            aggregate_signature: RawAggregateSignature::infinity(),
             is_empty: false,
         }
     }
@@ -33,7 +34,7 @@ impl AggregateSignature {
         self.is_empty = self.is_empty && signature.is_empty();
 
         // Note: empty signatures will have point at infinity which is equivalent of adding 0.
-        self.aggregate_signature.add(signature.as_raw())
+        self.aggregate_signature.add_assign(signature.as_raw())
     }
 
     /// Add (aggregate) another `AggregateSignature`.
@@ -43,7 +44,7 @@ impl AggregateSignature {
 
         // Note: empty signatures will have point at infinity which is equivalent of adding 0.
         self.aggregate_signature
-            .add_aggregate(&agg_signature.aggregate_signature)
+            .add_assign_aggregate(&agg_signature.aggregate_signature)
     }
 
     /// Verify the `AggregateSignature` against an `AggregatePublicKey`.
@@ -88,14 +89,14 @@ impl AggregateSignature {
         if self.is_empty {
             return vec![0; BLS_AGG_SIG_BYTE_SIZE];
         }
-        self.aggregate_signature.as_bytes()
+        self.aggregate_signature.serialize()
     }
 
     /// Convert bytes to AggregateSignature
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         for byte in bytes {
             if *byte != 0 {
-                let sig = RawAggregateSignature::from_bytes(&bytes).map_err(|_| {
+                let sig = RawAggregateSignature::deserialize(&bytes).map_err(|_| {
                     DecodeError::BytesInvalid(format!(
                         "Invalid AggregateSignature bytes: {:?}",
                         bytes
@@ -112,18 +113,10 @@ impl AggregateSignature {
     }
 
     /// Returns the underlying signature.
-    pub fn as_raw(&self) -> &RawAggregateSignature {
+    pub(crate) fn as_raw(&self) -> &RawAggregateSignature {
         &self.aggregate_signature
     }
 
-    /// Returns the underlying signature.
-    pub fn from_point(point: G2Point) -> Self {
-        Self {
-            aggregate_signature: RawAggregateSignature { point },
-            is_empty: false,
-        }
-    }
-
     /// Returns if the AggregateSignature `is_empty`
     pub fn is_empty(&self) -> bool {
         self.is_empty
@@ -135,7 +128,7 @@ impl AggregateSignature {
     /// is_empty set to true
     pub fn empty_signature() -> Self {
         Self {
-            aggregate_signature: RawAggregateSignature::new(),
+            aggregate_signature: RawAggregateSignature::infinity(),
             is_empty: true,
         }
     }
@@ -147,6 +140,12 @@ impl AggregateSignature {
     }
 }
 
+impl Default for AggregateSignature {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl_ssz!(
     AggregateSignature,
     BLS_AGG_SIG_BYTE_SIZE,
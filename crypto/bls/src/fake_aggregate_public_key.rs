@@ -1,6 +1,5 @@
 use super::{PublicKey, BLS_PUBLIC_KEY_BYTE_SIZE};
 use hex::encode as hex_encode;
-use milagro_bls::G1Point;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::PrefixedHexVisitor;
@@ -13,8 +12,6 @@ use ssz::{ssz_encode, Decode, DecodeError, Encode};
 #[derive(Debug, Clone, Default)]
 pub struct FakeAggregatePublicKey {
     bytes: Vec<u8>,
-    /// Never used, only use for compatibility with "real" `AggregatePublicKey`.
-    pub point: G1Point,
 }
 
 impl FakeAggregatePublicKey {
@@ -25,7 +22,6 @@ impl FakeAggregatePublicKey {
     pub fn empty_signature() -> Self {
         Self {
             bytes: vec![0; BLS_PUBLIC_KEY_BYTE_SIZE],
-            point: G1Point::new(),
         }
     }
 
@@ -38,24 +34,14 @@ impl FakeAggregatePublicKey {
         } else {
             Ok(Self {
                 bytes: bytes.to_vec(),
-                point: G1Point::new(),
             })
         }
     }
 
-    pub fn add_without_affine(&mut self, _public_key: &PublicKey) {
-        // No nothing.
-    }
-
-    pub fn affine(&mut self) {
-        // No nothing.
-    }
-
     /// Creates a new all-zero's aggregate public key
     pub fn zero() -> Self {
         Self {
             bytes: vec![0; BLS_PUBLIC_KEY_BYTE_SIZE],
-            point: G1Point::new(),
         }
     }
 
@@ -63,10 +49,6 @@ impl FakeAggregatePublicKey {
         // No nothing.
     }
 
-    pub fn add_point(&mut self, _point: &G1Point) {
-        // No nothing.
-    }
-
     pub fn aggregate(_pks: &[&PublicKey]) -> Self {
         Self::new()
     }
@@ -74,7 +56,6 @@ impl FakeAggregatePublicKey {
     pub fn from_public_key(public_key: &PublicKey) -> Self {
         Self {
             bytes: public_key.as_bytes(),
-            point: public_key.point.clone(),
         }
     }
 
@@ -2,7 +2,6 @@ use super::{
     fake_aggregate_public_key::FakeAggregatePublicKey, fake_public_key::FakePublicKey,
     fake_signature::FakeSignature, BLS_AGG_SIG_BYTE_SIZE,
 };
-use milagro_bls::G2Point;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -15,8 +14,6 @@ use ssz::{ssz_encode, Decode, DecodeError, Encode};
 #[derive(Debug, PartialEq, Clone, Default, Eq)]
 pub struct FakeAggregateSignature {
     bytes: Vec<u8>,
-    /// Never used, only use for compatibility with "real" `AggregateSignature`.
-    pub point: G2Point,
 }
 
 impl FakeAggregateSignature {
@@ -29,7 +26,6 @@ impl FakeAggregateSignature {
     pub fn zero() -> Self {
         Self {
             bytes: vec![0; BLS_AGG_SIG_BYTE_SIZE],
-            point: G2Point::new(),
         }
     }
 
@@ -79,7 +75,6 @@ impl FakeAggregateSignature {
     pub fn from_signature(signature: &FakeSignature) -> Self {
         Self {
             bytes: signature.as_bytes(),
-            point: signature.point.clone(),
         }
     }
 
@@ -93,7 +88,6 @@ impl FakeAggregateSignature {
         } else {
             Ok(Self {
                 bytes: bytes.to_vec(),
-                point: G2Point::new(),
             })
         }
     }
@@ -1,6 +1,5 @@
 use super::{SecretKey, BLS_PUBLIC_KEY_BYTE_SIZE};
-use milagro_bls::G1Point;
-use milagro_bls::PublicKey as RawPublicKey;
+use crate::impls::{TPublicKey, TSecretKey};
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -16,19 +15,14 @@ use std::hash::{Hash, Hasher};
 #[derive(Clone, Eq)]
 pub struct FakePublicKey {
     bytes: Vec<u8>,
-    /// Never used, only use for compatibility with "real" `PublicKey`.
-    pub point: G1Point,
 }
 
 impl FakePublicKey {
-    pub fn from_secret_key(_secret_key: &SecretKey) -> Self {
-        Self::zero()
-    }
-
-    pub fn from_raw(raw: RawPublicKey) -> Self {
+    /// Returns the real public key bytes for `secret_key`, so that keys generated with fake crypto
+    /// still match those generated with real crypto (e.g., interop keypairs).
+    pub fn from_secret_key(secret_key: &SecretKey) -> Self {
         Self {
-            bytes: raw.clone().as_bytes(),
-            point: G1Point::new(),
+            bytes: secret_key.as_raw().public_key().serialize(),
         }
     }
 
@@ -36,7 +30,6 @@ impl FakePublicKey {
     pub fn zero() -> Self {
         Self {
             bytes: vec![0; BLS_PUBLIC_KEY_BYTE_SIZE],
-            point: G1Point::new(),
         }
     }
 
@@ -49,7 +42,6 @@ impl FakePublicKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         Ok(Self {
             bytes: bytes.to_vec(),
-            point: G1Point::new(),
         })
     }
 
@@ -83,14 +75,6 @@ impl FakePublicKey {
     pub fn as_raw(&self) -> &Self {
         self
     }
-
-    pub fn as_point(&self) -> &G1Point {
-        &self.point
-    }
-
-    pub fn into_point(self) -> G1Point {
-        self.point
-    }
 }
 
 impl fmt::Display for FakePublicKey {
@@ -0,0 +1,255 @@
+//! A backend using the `blst` crate by Supranational.
+//!
+//! This backend is significantly faster than `milagro` at signature verification, especially when
+//! verifying a batch of signatures with `verify_signature_sets`.
+
+use super::{TAggregatePublicKey, TAggregateSignature, TPublicKey, TSecretKey, TSignature};
+use ::blst::{blst_scalar, min_pk as blst_core, BLST_ERROR};
+use rand::RngCore;
+
+pub const NAME: &str = "blst";
+
+/// The domain separation tag for the Ethereum 2.0 ciphersuite.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The number of random bits used to blind each signature in `verify_signature_sets`.
+const RAND_BITS: usize = 64;
+
+const PUBLIC_KEY_BYTES: usize = 48;
+const SIGNATURE_BYTES: usize = 96;
+
+/// The flag bits which indicate a compressed point at infinity.
+const INFINITY_FLAGS: u8 = 0xc0;
+
+#[derive(Clone)]
+pub struct SecretKey(blst_core::SecretKey);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey(blst_core::PublicKey);
+
+#[derive(Debug, Clone)]
+pub struct AggregatePublicKey(blst_core::AggregatePublicKey);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(blst_core::Signature);
+
+#[derive(Debug, Clone)]
+pub struct AggregateSignature(blst_core::AggregateSignature);
+
+impl PartialEq for AggregateSignature {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_signature() == other.0.to_signature()
+    }
+}
+
+impl Eq for AggregateSignature {}
+
+/// Returns the compressed encoding of the point at infinity, for a point of `len` bytes.
+fn infinity_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    bytes[0] = INFINITY_FLAGS;
+    bytes
+}
+
+impl TSecretKey<PublicKey, Signature> for SecretKey {
+    fn random() -> Self {
+        let mut ikm = [0; 32];
+        rand::thread_rng().fill_bytes(&mut ikm);
+        Self(
+            blst_core::SecretKey::key_gen(&ikm, &[])
+                .expect("key_gen only fails with less than 32 bytes of ikm"),
+        )
+    }
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey(self.0.sk_to_pk())
+    }
+
+    fn sign(&self, msg: &[u8]) -> Signature {
+        Signature(self.0.sign(msg, DST, &[]))
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.serialize().to_vec()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        blst_core::SecretKey::deserialize(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TPublicKey for PublicKey {
+    fn serialize(&self) -> Vec<u8> {
+        self.0.compress().to_vec()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != PUBLIC_KEY_BYTES {
+            return Err(format!("Invalid public key length: {}", bytes.len()));
+        }
+
+        blst_core::PublicKey::key_validate(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    fn serialize_uncompressed(&self) -> Vec<u8> {
+        self.0.serialize().to_vec()
+    }
+
+    fn deserialize_uncompressed(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != PUBLIC_KEY_BYTES * 2 {
+            return Err(format!("Invalid public key length: {}", bytes.len()));
+        }
+
+        blst_core::PublicKey::key_validate(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TAggregatePublicKey<PublicKey> for AggregatePublicKey {
+    fn infinity() -> Self {
+        let infinity = blst_core::PublicKey::from_bytes(&infinity_bytes(PUBLIC_KEY_BYTES))
+            .expect("the point at infinity is a valid encoding");
+        Self(blst_core::AggregatePublicKey::from_public_key(&infinity))
+    }
+
+    fn add_assign(&mut self, pubkey: &PublicKey) {
+        // Public keys are validated during deserialization, there is no need to do it again.
+        let _ = self.0.add_public_key(&pubkey.0, false);
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.to_public_key().compress().to_vec()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != PUBLIC_KEY_BYTES {
+            return Err(format!("Invalid public key length: {}", bytes.len()));
+        }
+
+        blst_core::PublicKey::from_bytes(bytes)
+            .map(|pubkey| Self(blst_core::AggregatePublicKey::from_public_key(&pubkey)))
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TSignature<PublicKey> for Signature {
+    fn verify(&self, pubkey: &PublicKey, msg: &[u8]) -> bool {
+        self.0.verify(true, msg, DST, &[], &pubkey.0, false) == BLST_ERROR::BLST_SUCCESS
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.compress().to_vec()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != SIGNATURE_BYTES {
+            return Err(format!("Invalid signature length: {}", bytes.len()));
+        }
+
+        blst_core::Signature::from_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TAggregateSignature<PublicKey, AggregatePublicKey, Signature> for AggregateSignature {
+    fn infinity() -> Self {
+        let infinity = blst_core::Signature::from_bytes(&infinity_bytes(SIGNATURE_BYTES))
+            .expect("the point at infinity is a valid encoding");
+        Self(blst_core::AggregateSignature::from_signature(&infinity))
+    }
+
+    fn add_assign(&mut self, signature: &Signature) {
+        // Signatures are group-checked during verification.
+        let _ = self.0.add_signature(&signature.0, false);
+    }
+
+    fn add_assign_aggregate(&mut self, other: &Self) {
+        self.0.add_aggregate(&other.0)
+    }
+
+    fn fast_aggregate_verify_pre_aggregated(
+        &self,
+        msg: &[u8],
+        pubkey: &AggregatePublicKey,
+    ) -> bool {
+        self.0.to_signature().fast_aggregate_verify_pre_aggregated(
+            true,
+            msg,
+            DST,
+            &pubkey.0.to_public_key(),
+        ) == BLST_ERROR::BLST_SUCCESS
+    }
+
+    fn fast_aggregate_verify(&self, msg: &[u8], pubkeys: &[&PublicKey]) -> bool {
+        let pubkeys = pubkeys.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+        self.0
+            .to_signature()
+            .fast_aggregate_verify(true, msg, DST, &pubkeys)
+            == BLST_ERROR::BLST_SUCCESS
+    }
+
+    fn aggregate_verify(&self, msgs: &[&[u8]], pubkeys: &[&PublicKey]) -> bool {
+        let pubkeys = pubkeys.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+        self.0
+            .to_signature()
+            .aggregate_verify(true, msgs, DST, &pubkeys, false)
+            == BLST_ERROR::BLST_SUCCESS
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.to_signature().compress().to_vec()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        Signature::deserialize(bytes)
+            .map(|signature| Self(blst_core::AggregateSignature::from_signature(&signature.0)))
+    }
+}
+
+/// Verifies all the given `(signature, signing_keys, message)` sets in a single batch, returning
+/// `true` only if all are valid.
+///
+/// Each set is blinded with a random, non-zero `RAND_BITS`-bit scalar so that invalid sets cannot
+/// cancel each other out.
+pub fn verify_signature_sets<'a>(
+    sets: impl Iterator<Item = (&'a AggregateSignature, &'a AggregatePublicKey, &'a [u8])>,
+) -> bool {
+    let rng = &mut rand::thread_rng();
+
+    let mut signatures = vec![];
+    let mut pubkeys = vec![];
+    let mut msgs = vec![];
+    let mut rands = vec![];
+
+    for (signature, signing_keys, msg) in sets {
+        signatures.push(signature.0.to_signature());
+        pubkeys.push(signing_keys.0.to_public_key());
+        msgs.push(msg);
+
+        let mut rand = blst_scalar { b: [0; 32] };
+        while rand.b.iter().all(|byte| *byte == 0) {
+            rng.fill_bytes(&mut rand.b[0..RAND_BITS / 8]);
+        }
+        rands.push(rand);
+    }
+
+    let signature_refs = signatures.iter().collect::<Vec<_>>();
+    let pubkey_refs = pubkeys.iter().collect::<Vec<_>>();
+
+    blst_core::Signature::verify_multiple_aggregate_signatures(
+        &msgs,
+        DST,
+        &pubkey_refs,
+        false,
+        &signature_refs,
+        true,
+        &rands,
+        RAND_BITS,
+    ) == BLST_ERROR::BLST_SUCCESS
+}
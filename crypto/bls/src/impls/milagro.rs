@@ -0,0 +1,157 @@
+//! A backend using the pure-Rust `milagro_bls` crate.
+
+use super::{TAggregatePublicKey, TAggregateSignature, TPublicKey, TSecretKey, TSignature};
+use milagro_bls as milagro;
+
+pub const NAME: &str = "milagro";
+
+#[derive(Clone)]
+pub struct SecretKey(milagro::SecretKey);
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct PublicKey(milagro::PublicKey);
+
+#[derive(Debug, Clone)]
+pub struct AggregatePublicKey(milagro::AggregatePublicKey);
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct Signature(milagro::Signature);
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct AggregateSignature(milagro::AggregateSignature);
+
+impl TSecretKey<PublicKey, Signature> for SecretKey {
+    fn random() -> Self {
+        Self(milagro::SecretKey::random(&mut rand::thread_rng()))
+    }
+
+    fn public_key(&self) -> PublicKey {
+        PublicKey(milagro::PublicKey::from_secret_key(&self.0))
+    }
+
+    fn sign(&self, msg: &[u8]) -> Signature {
+        Signature(milagro::Signature::new(msg, &self.0))
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.as_bytes()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        milagro::SecretKey::from_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TPublicKey for PublicKey {
+    fn serialize(&self) -> Vec<u8> {
+        self.0.as_bytes()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        milagro::PublicKey::from_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    fn serialize_uncompressed(&self) -> Vec<u8> {
+        milagro::PublicKey::as_uncompressed_bytes(&mut self.0.clone())
+    }
+
+    fn deserialize_uncompressed(bytes: &[u8]) -> Result<Self, String> {
+        milagro::PublicKey::from_uncompressed_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TAggregatePublicKey<PublicKey> for AggregatePublicKey {
+    fn infinity() -> Self {
+        Self(milagro::AggregatePublicKey::new())
+    }
+
+    fn add_assign(&mut self, pubkey: &PublicKey) {
+        self.0.add(&pubkey.0)
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.as_bytes()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        milagro::AggregatePublicKey::from_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TSignature<PublicKey> for Signature {
+    fn verify(&self, pubkey: &PublicKey, msg: &[u8]) -> bool {
+        self.0.verify(msg, &pubkey.0)
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.as_bytes()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        milagro::Signature::from_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl TAggregateSignature<PublicKey, AggregatePublicKey, Signature> for AggregateSignature {
+    fn infinity() -> Self {
+        Self(milagro::AggregateSignature::new())
+    }
+
+    fn add_assign(&mut self, signature: &Signature) {
+        self.0.add(&signature.0)
+    }
+
+    fn add_assign_aggregate(&mut self, other: &Self) {
+        self.0.add_aggregate(&other.0)
+    }
+
+    fn fast_aggregate_verify_pre_aggregated(
+        &self,
+        msg: &[u8],
+        pubkey: &AggregatePublicKey,
+    ) -> bool {
+        self.0.fast_aggregate_verify_pre_aggregated(msg, &pubkey.0)
+    }
+
+    fn fast_aggregate_verify(&self, msg: &[u8], pubkeys: &[&PublicKey]) -> bool {
+        let pubkeys = pubkeys.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+        self.0.fast_aggregate_verify(msg, &pubkeys)
+    }
+
+    fn aggregate_verify(&self, msgs: &[&[u8]], pubkeys: &[&PublicKey]) -> bool {
+        let pubkeys = pubkeys.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+        self.0.aggregate_verify(msgs, &pubkeys)
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        self.0.as_bytes()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, String> {
+        milagro::AggregateSignature::from_bytes(bytes)
+            .map(Self)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+/// Verifies all the given `(signature, signing_keys, message)` sets in a single batch, returning
+/// `true` only if all are valid.
+pub fn verify_signature_sets<'a>(
+    sets: impl Iterator<Item = (&'a AggregateSignature, &'a AggregatePublicKey, &'a [u8])>,
+) -> bool {
+    let rng = &mut rand::thread_rng();
+    milagro::AggregateSignature::verify_multiple_aggregate_signatures(
+        rng,
+        sets.map(|(signature, signing_keys, message)| (&signature.0, &signing_keys.0, message)),
+    )
+}
@@ -0,0 +1,300 @@
+//! The cryptographic backends which may be used by the types in this crate (e.g., `PublicKey`,
+//! `Signature`).
+//!
+//! Each backend is a module which provides:
+//!
+//! - Types named `SecretKey`, `PublicKey`, `AggregatePublicKey`, `Signature` and
+//! `AggregateSignature` which implement the corresponding traits in this module.
+//! - A `NAME` constant, used to identify the backend in logs.
+//! - A `verify_signature_sets` function, which verifies a batch of signatures at once.
+//!
+//! The backend used by this crate is selected at compile-time via the `milagro` and
+//! `supranational` features. If both are enabled then `supranational` (i.e., `blst`) is used,
+//! however both backends are still compiled so they can be tested against one another.
+
+#[cfg(feature = "supranational")]
+pub mod blst;
+#[cfg(feature = "milagro")]
+pub mod milagro;
+
+/// A BLS secret key.
+pub trait TSecretKey<Pub, Sig>: Sized + Clone {
+    /// Generate a new key using `rand::thread_rng`.
+    fn random() -> Self;
+
+    /// Returns the public key which corresponds to `self`.
+    fn public_key(&self) -> Pub;
+
+    /// Sign `msg` using the Ethereum 2.0 domain separation tag.
+    fn sign(&self, msg: &[u8]) -> Sig;
+
+    /// Returns the big-endian bytes of the scalar.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Instantiate `Self` from big-endian bytes.
+    fn deserialize(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// A BLS public key (i.e., a point on G1).
+pub trait TPublicKey: Sized + Clone {
+    /// Returns the compressed serialization of the point.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Decodes and validates a compressed point.
+    fn deserialize(bytes: &[u8]) -> Result<Self, String>;
+
+    /// Returns the uncompressed (i.e., `(x, y)`) serialization of the point.
+    fn serialize_uncompressed(&self) -> Vec<u8>;
+
+    /// Decodes and validates an uncompressed point.
+    fn deserialize_uncompressed(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// The sum of zero or more BLS public keys.
+pub trait TAggregatePublicKey<Pub>: Sized + Clone {
+    /// Returns the point at infinity (i.e., the aggregate of no keys).
+    fn infinity() -> Self;
+
+    /// Adds `pubkey` to `self`.
+    fn add_assign(&mut self, pubkey: &Pub);
+
+    /// Returns the sum of all `pubkeys`.
+    fn aggregate(pubkeys: &[&Pub]) -> Self {
+        pubkeys.iter().fold(Self::infinity(), |mut agg, pubkey| {
+            agg.add_assign(pubkey);
+            agg
+        })
+    }
+
+    /// Returns the compressed serialization of the point.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Decodes and validates a compressed point.
+    fn deserialize(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// A BLS signature (i.e., a point on G2).
+pub trait TSignature<Pub>: Sized + Clone {
+    /// Returns `true` if `self` is a valid signature of `msg` by `pubkey`.
+    fn verify(&self, pubkey: &Pub, msg: &[u8]) -> bool;
+
+    /// Returns the compressed serialization of the point.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Decodes a compressed point.
+    fn deserialize(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// The sum of zero or more BLS signatures.
+pub trait TAggregateSignature<Pub, AggPub, Sig>: Sized + Clone {
+    /// Returns the point at infinity (i.e., the aggregate of no signatures).
+    fn infinity() -> Self;
+
+    /// Adds `signature` to `self`.
+    fn add_assign(&mut self, signature: &Sig);
+
+    /// Adds `other` to `self`.
+    fn add_assign_aggregate(&mut self, other: &Self);
+
+    /// Returns `true` if `self` is a valid signature of `msg` by all the keys in `pubkey`.
+    fn fast_aggregate_verify_pre_aggregated(&self, msg: &[u8], pubkey: &AggPub) -> bool;
+
+    /// Returns `true` if `self` is a valid signature of `msg` by all `pubkeys`.
+    fn fast_aggregate_verify(&self, msg: &[u8], pubkeys: &[&Pub]) -> bool;
+
+    /// Returns `true` if `self` is a valid signature where each of `pubkeys` signed the message at
+    /// the same index in `msgs`.
+    fn aggregate_verify(&self, msgs: &[&[u8]], pubkeys: &[&Pub]) -> bool;
+
+    /// Returns the compressed serialization of the point.
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Decodes a compressed point.
+    fn deserialize(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// Checks that the `milagro` and `blst` backends produce identical results.
+#[cfg(all(test, feature = "milagro", feature = "supranational"))]
+mod cross_backend_tests {
+    use super::{
+        blst, milagro, TAggregatePublicKey, TAggregateSignature, TPublicKey, TSecretKey, TSignature,
+    };
+
+    const NUM_KEYS: usize = 8;
+
+    fn secret_keys() -> Vec<(milagro::SecretKey, blst::SecretKey)> {
+        (0..NUM_KEYS)
+            .map(|_| {
+                let milagro_sk = milagro::SecretKey::random();
+                let blst_sk = blst::SecretKey::deserialize(&milagro_sk.serialize())
+                    .expect("blst should decode milagro secret key");
+                (milagro_sk, blst_sk)
+            })
+            .collect()
+    }
+
+    fn messages() -> Vec<Vec<u8>> {
+        (0..NUM_KEYS).map(|i| vec![i as u8; 32]).collect()
+    }
+
+    #[test]
+    fn keys_and_signatures_match() {
+        for ((milagro_sk, blst_sk), msg) in secret_keys().iter().zip(messages().iter()) {
+            assert_eq!(milagro_sk.serialize(), blst_sk.serialize());
+
+            let milagro_pk = milagro_sk.public_key();
+            let blst_pk = blst_sk.public_key();
+            assert_eq!(milagro_pk.serialize(), blst_pk.serialize());
+            assert_eq!(
+                milagro_pk.serialize_uncompressed(),
+                blst_pk.serialize_uncompressed()
+            );
+
+            let milagro_sig = milagro_sk.sign(msg);
+            let blst_sig = blst_sk.sign(msg);
+            assert_eq!(milagro_sig.serialize(), blst_sig.serialize());
+
+            assert!(milagro_sig.verify(&milagro_pk, msg));
+            assert!(blst_sig.verify(&blst_pk, msg));
+            assert!(!milagro_sig.verify(&milagro_pk, &[42]));
+            assert!(!blst_sig.verify(&blst_pk, &[42]));
+        }
+    }
+
+    #[test]
+    fn aggregates_match() {
+        let keys = secret_keys();
+        let msg = [42; 32];
+
+        let milagro_pks = keys
+            .iter()
+            .map(|(sk, _)| sk.public_key())
+            .collect::<Vec<_>>();
+        let blst_pks = keys
+            .iter()
+            .map(|(_, sk)| sk.public_key())
+            .collect::<Vec<_>>();
+        let milagro_pk_refs = milagro_pks.iter().collect::<Vec<_>>();
+        let blst_pk_refs = blst_pks.iter().collect::<Vec<_>>();
+
+        let milagro_agg_pk = milagro::AggregatePublicKey::aggregate(&milagro_pk_refs);
+        let blst_agg_pk = blst::AggregatePublicKey::aggregate(&blst_pk_refs);
+        assert_eq!(milagro_agg_pk.serialize(), blst_agg_pk.serialize());
+
+        let mut milagro_agg_sig = milagro::AggregateSignature::infinity();
+        let mut blst_agg_sig = blst::AggregateSignature::infinity();
+        assert_eq!(milagro_agg_sig.serialize(), blst_agg_sig.serialize());
+
+        for (milagro_sk, blst_sk) in &keys {
+            milagro_agg_sig.add_assign(&milagro_sk.sign(&msg));
+            blst_agg_sig.add_assign(&blst_sk.sign(&msg));
+        }
+        assert_eq!(milagro_agg_sig.serialize(), blst_agg_sig.serialize());
+
+        assert!(milagro_agg_sig.fast_aggregate_verify(&msg, &milagro_pk_refs));
+        assert!(blst_agg_sig.fast_aggregate_verify(&msg, &blst_pk_refs));
+        assert!(milagro_agg_sig.fast_aggregate_verify_pre_aggregated(&msg, &milagro_agg_pk));
+        assert!(blst_agg_sig.fast_aggregate_verify_pre_aggregated(&msg, &blst_agg_pk));
+        assert!(!milagro_agg_sig.fast_aggregate_verify(&msg, &milagro_pk_refs[1..]));
+        assert!(!blst_agg_sig.fast_aggregate_verify(&msg, &blst_pk_refs[1..]));
+    }
+
+    #[test]
+    fn aggregate_verify_matches() {
+        let keys = secret_keys();
+        let msgs = messages();
+        let msg_refs = msgs.iter().map(|msg| msg.as_slice()).collect::<Vec<_>>();
+
+        let milagro_pks = keys
+            .iter()
+            .map(|(sk, _)| sk.public_key())
+            .collect::<Vec<_>>();
+        let blst_pks = keys
+            .iter()
+            .map(|(_, sk)| sk.public_key())
+            .collect::<Vec<_>>();
+
+        let mut milagro_agg_sig = milagro::AggregateSignature::infinity();
+        let mut blst_agg_sig = blst::AggregateSignature::infinity();
+        for ((milagro_sk, blst_sk), msg) in keys.iter().zip(msgs.iter()) {
+            milagro_agg_sig.add_assign(&milagro_sk.sign(msg));
+            blst_agg_sig.add_assign(&blst_sk.sign(msg));
+        }
+
+        assert!(
+            milagro_agg_sig.aggregate_verify(&msg_refs, &milagro_pks.iter().collect::<Vec<_>>())
+        );
+        assert!(blst_agg_sig.aggregate_verify(&msg_refs, &blst_pks.iter().collect::<Vec<_>>()));
+
+        let mut milagro_verify_sets = vec![];
+        let mut blst_verify_sets = vec![];
+        let milagro_agg_pks = milagro_pks
+            .iter()
+            .map(|pk| milagro::AggregatePublicKey::aggregate(&[pk]))
+            .collect::<Vec<_>>();
+        let blst_agg_pks = blst_pks
+            .iter()
+            .map(|pk| blst::AggregatePublicKey::aggregate(&[pk]))
+            .collect::<Vec<_>>();
+        let milagro_sigs = keys
+            .iter()
+            .zip(msgs.iter())
+            .map(|((sk, _), msg)| {
+                let mut agg = milagro::AggregateSignature::infinity();
+                agg.add_assign(&sk.sign(msg));
+                agg
+            })
+            .collect::<Vec<_>>();
+        let blst_sigs = keys
+            .iter()
+            .zip(msgs.iter())
+            .map(|((_, sk), msg)| {
+                let mut agg = blst::AggregateSignature::infinity();
+                agg.add_assign(&sk.sign(msg));
+                agg
+            })
+            .collect::<Vec<_>>();
+        for i in 0..NUM_KEYS {
+            milagro_verify_sets.push((&milagro_sigs[i], &milagro_agg_pks[i], msg_refs[i]));
+            blst_verify_sets.push((&blst_sigs[i], &blst_agg_pks[i], msg_refs[i]));
+        }
+
+        assert!(milagro::verify_signature_sets(
+            milagro_verify_sets.into_iter()
+        ));
+        assert!(blst::verify_signature_sets(blst_verify_sets.into_iter()));
+    }
+
+    #[test]
+    fn invalid_points_rejected_by_both() {
+        let invalid_pubkeys: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0; 48],
+            vec![0xff; 48],
+            // The point at infinity.
+            {
+                let mut bytes = vec![0; 48];
+                bytes[0] = 0xc0;
+                bytes
+            },
+        ];
+
+        for bytes in invalid_pubkeys {
+            assert_eq!(
+                milagro::PublicKey::deserialize(&bytes).is_ok(),
+                blst::PublicKey::deserialize(&bytes).is_ok(),
+                "backends disagree on public key {:?}",
+                bytes
+            );
+        }
+
+        for bytes in &[vec![], vec![0xff; 96]] {
+            assert_eq!(
+                milagro::Signature::deserialize(bytes).is_ok(),
+                blst::Signature::deserialize(bytes).is_ok(),
+                "backends disagree on signature {:?}",
+                bytes
+            );
+        }
+    }
+}
@@ -21,6 +21,12 @@ impl Keypair {
     }
 }
 
+impl PartialEq for Keypair {
+    fn eq(&self, other: &Keypair) -> bool {
+        self.pk == other.pk
+    }
+}
+
 #[allow(clippy::derive_hash_xor_eq)]
 impl Hash for Keypair {
     /// Note: this is distinct from consensus serialization, it will produce a different hash.
@@ -39,3 +45,10 @@ impl fmt::Display for Keypair {
         write!(f, "{}", self.pk)
     }
 }
+
+impl fmt::Debug for Keypair {
+    /// Only the public key is displayed, the secret key is never included.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Keypair {{ pk: {:?} }}", self.pk)
+    }
+}
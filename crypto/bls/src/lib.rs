@@ -1,8 +1,8 @@
-extern crate milagro_bls;
 extern crate ssz;
 
 #[macro_use]
 mod macros;
+pub mod impls;
 mod keypair;
 mod plain_text;
 mod public_key_bytes;
@@ -14,13 +14,26 @@ pub use crate::keypair::Keypair;
 pub use crate::public_key_bytes::PublicKeyBytes;
 pub use crate::secret_key::SecretKey;
 pub use crate::signature_bytes::SignatureBytes;
-pub use milagro_bls::{compress_g2, hash_to_curve_g2};
 pub use plain_text::PlainText;
 pub use signature_set::{verify_signature_sets, SignatureSet};
 
 #[cfg(feature = "arbitrary")]
 pub use arbitrary;
 
+#[cfg(not(any(feature = "milagro", feature = "supranational")))]
+compile_error!("at least one of the `milagro` or `supranational` features must be enabled");
+
+// The cryptographic backend used by this crate, selected at compile-time.
+//
+// If both backends are compiled, `blst` is preferred.
+#[cfg(feature = "supranational")]
+use impls::blst as backend;
+#[cfg(all(feature = "milagro", not(feature = "supranational")))]
+use impls::milagro as backend;
+
+/// The name of the cryptographic backend in use (e.g., `"blst"`).
+pub const BACKEND_NAME: &str = backend::NAME;
+
 #[cfg(feature = "fake_crypto")]
 mod fake_aggregate_public_key;
 #[cfg(feature = "fake_crypto")]
@@ -1,5 +1,6 @@
 use super::{SecretKey, BLS_PUBLIC_KEY_BYTE_SIZE};
-use milagro_bls::{G1Point, PublicKey as RawPublicKey};
+use crate::backend::PublicKey as RawPublicKey;
+use crate::impls::{TPublicKey, TSecretKey};
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -17,36 +18,22 @@ pub struct PublicKey(RawPublicKey);
 
 impl PublicKey {
     pub fn from_secret_key(secret_key: &SecretKey) -> Self {
-        PublicKey(RawPublicKey::from_secret_key(secret_key.as_raw()))
+        PublicKey(secret_key.as_raw().public_key())
     }
 
-    pub fn from_raw(raw: RawPublicKey) -> Self {
-        Self(raw)
-    }
-
-    /// Returns a reference to the underlying signature.
-    pub fn as_raw(&self) -> &RawPublicKey {
+    /// Returns a reference to the underlying public key.
+    pub(crate) fn as_raw(&self) -> &RawPublicKey {
         &self.0
     }
 
-    /// Consumes self and returns the underlying signature.
-    pub fn as_point(&self) -> &G1Point {
-        &self.0.point
-    }
-
-    /// Consumes self and returns the underlying signature.
-    pub fn into_point(self) -> G1Point {
-        self.0.point
-    }
-
     /// Returns the underlying point as compressed bytes.
     pub fn as_bytes(&self) -> Vec<u8> {
-        self.as_raw().as_bytes()
+        self.as_raw().serialize()
     }
 
     /// Converts compressed bytes to PublicKey
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        let pubkey = RawPublicKey::from_bytes(&bytes).map_err(|_| {
+        let pubkey = RawPublicKey::deserialize(&bytes).map_err(|_| {
             DecodeError::BytesInvalid(format!("Invalid PublicKey bytes: {:?}", bytes))
         })?;
 
@@ -55,12 +42,12 @@ impl PublicKey {
 
     /// Returns the PublicKey as (x, y) bytes
     pub fn as_uncompressed_bytes(&self) -> Vec<u8> {
-        RawPublicKey::as_uncompressed_bytes(&mut self.0.clone())
+        self.as_raw().serialize_uncompressed()
     }
 
     /// Converts (x, y) bytes to PublicKey
     pub fn from_uncompressed_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        let pubkey = RawPublicKey::from_uncompressed_bytes(&bytes).map_err(|_| {
+        let pubkey = RawPublicKey::deserialize_uncompressed(&bytes).map_err(|_| {
             DecodeError::BytesInvalid("Invalid PublicKey uncompressed bytes.".to_string())
         })?;
         Ok(PublicKey(pubkey))
@@ -109,7 +96,7 @@ impl Serialize for PublicKey {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&hex_encode(self.as_raw().as_bytes()))
+        serializer.serialize_str(&hex_encode(self.as_bytes()))
     }
 }
 
@@ -1,7 +1,8 @@
 extern crate rand;
 
+use crate::backend::SecretKey as RawSecretKey;
+use crate::impls::TSecretKey;
 use crate::PlainText;
-use milagro_bls::SecretKey as RawSecretKey;
 use ssz::DecodeError;
 
 /// A single BLS signature.
@@ -14,11 +15,7 @@ pub struct SecretKey(RawSecretKey);
 impl SecretKey {
     /// Generate a new `Self` using `rand::thread_rng`.
     pub fn random() -> Self {
-        SecretKey(RawSecretKey::random(&mut rand::thread_rng()))
-    }
-
-    pub fn from_raw(raw: RawSecretKey) -> Self {
-        Self(raw)
+        SecretKey(RawSecretKey::random())
     }
 
     /// Returns the secret key as a byte array (wrapped in `PlainText` wrapper so it is zeroized on
@@ -27,19 +24,21 @@ impl SecretKey {
     /// Extreme care should be taken not to leak these bytes as they are the unencrypted secret
     /// key.
     pub fn as_bytes(&self) -> PlainText {
-        self.as_raw().as_bytes().into()
+        self.as_raw().serialize().into()
     }
 
     /// Instantiate a SecretKey from existing bytes.
     ///
     /// Note: this is _not_ SSZ decoding.
     pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, DecodeError> {
-        Ok(SecretKey(RawSecretKey::from_bytes(bytes).map_err(|e| {
-            DecodeError::BytesInvalid(format!(
-                "Invalid SecretKey bytes: {:?} Error: {:?}",
-                bytes, e
-            ))
-        })?))
+        Ok(SecretKey(RawSecretKey::deserialize(bytes).map_err(
+            |e| {
+                DecodeError::BytesInvalid(format!(
+                    "Invalid SecretKey bytes: {:?} Error: {:?}",
+                    bytes, e
+                ))
+            },
+        )?))
     }
 
     /// Returns the underlying secret key.
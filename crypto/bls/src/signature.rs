@@ -1,5 +1,6 @@
 use super::{PublicKey, SecretKey, BLS_SIG_BYTE_SIZE};
-use milagro_bls::Signature as RawSignature;
+use crate::backend::Signature as RawSignature;
+use crate::impls::{TSecretKey, TSignature};
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -19,7 +20,7 @@ impl Signature {
     /// Instantiate a new Signature from a message and a SecretKey.
     pub fn new(msg: &[u8], sk: &SecretKey) -> Self {
         Signature {
-            signature: RawSignature::new(msg, sk.as_raw()),
+            signature: sk.as_raw().sign(msg),
             is_empty: false,
         }
     }
@@ -29,11 +30,11 @@ impl Signature {
         if self.is_empty {
             return false;
         }
-        self.signature.verify(msg, pk.as_raw())
+        self.signature.verify(pk.as_raw(), msg)
     }
 
     /// Returns the underlying signature.
-    pub fn as_raw(&self) -> &RawSignature {
+    pub(crate) fn as_raw(&self) -> &RawSignature {
         &self.signature
     }
 
@@ -43,7 +44,7 @@ impl Signature {
         let mut empty: Vec<u8> = vec![0; BLS_SIG_BYTE_SIZE];
         empty[0] += u8::pow(2, 6) + u8::pow(2, 7);
         Signature {
-            signature: RawSignature::from_bytes(&empty).unwrap(),
+            signature: RawSignature::deserialize(&empty).unwrap(),
             is_empty: true,
         }
     }
@@ -53,14 +54,14 @@ impl Signature {
         if self.is_empty {
             return vec![0; 96];
         }
-        self.signature.as_bytes()
+        self.signature.serialize()
     }
 
     // Convert bytes to BLS Signature
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         for byte in bytes {
             if *byte != 0 {
-                let raw_signature = RawSignature::from_bytes(&bytes).map_err(|_| {
+                let raw_signature = RawSignature::deserialize(&bytes).map_err(|_| {
                     DecodeError::BytesInvalid(format!("Invalid Signature bytes: {:?}", bytes))
                 })?;
                 return Ok(Signature {
@@ -151,7 +152,7 @@ mod tests {
     pub fn test_empty_signature() {
         let sig = Signature::empty_signature();
 
-        let sig_as_bytes: Vec<u8> = sig.as_raw().as_bytes();
+        let sig_as_bytes: Vec<u8> = sig.as_raw().serialize();
 
         assert_eq!(sig_as_bytes.len(), BLS_SIG_BYTE_SIZE);
         for (i, one_byte) in sig_as_bytes.iter().enumerate() {
@@ -277,9 +277,27 @@ pub fn recover_validator_secret(
     index: u32,
     key_type: KeyType,
 ) -> Result<(PlainText, ValidatorPath), Error> {
-    let path = ValidatorPath::new(index, key_type);
     let secret = wallet.decrypt_seed(wallet_password)?;
-    let master = DerivedKey::from_seed(secret.as_bytes()).map_err(|()| Error::EmptyPassword)?;
+
+    recover_validator_secret_from_seed(secret.as_bytes(), index, key_type)
+}
+
+/// Returns `(secret, path)` for the `key_type` for the validator at `index`, derived from the
+/// master `seed` of a wallet (e.g., the BIP-39 seed of its mnemonic).
+///
+/// As with `recover_validator_secret`, this function should only be used for recovering lost
+/// keys.
+///
+/// ## Errors
+///
+/// Returns `Error::EmptySeed` if `seed` is empty.
+pub fn recover_validator_secret_from_seed(
+    seed: &[u8],
+    index: u32,
+    key_type: KeyType,
+) -> Result<(PlainText, ValidatorPath), Error> {
+    let path = ValidatorPath::new(index, key_type);
+    let master = DerivedKey::from_seed(seed).map_err(|()| Error::EmptySeed)?;
 
     let destination = path.iter_nodes().fold(master, |dk, i| dk.child(*i));
 
@@ -7,11 +7,12 @@
 //! `Context` which can be handed to any service that wishes to start async tasks or perform
 //! logging.
 
-use eth2_config::Eth2Config;
+use eth2_config::{ChainSpecOverrides, Eth2Config};
 use eth2_testnet_config::Eth2TestnetConfig;
 use futures::channel::oneshot;
 
 pub use executor::TaskExecutor;
+use logging::{LogLevels, ModuleLevelFilter};
 use slog::{info, o, Drain, Level, Logger};
 use sloggers::{null::NullLoggerBuilder, Build};
 use std::cell::RefCell;
@@ -30,6 +31,7 @@ pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
 pub struct EnvironmentBuilder<E: EthSpec> {
     runtime: Option<Runtime>,
     log: Option<Logger>,
+    log_levels: LogLevels,
     eth_spec_instance: E,
     eth2_config: Eth2Config,
     testnet: Option<Eth2TestnetConfig<E>>,
@@ -41,6 +43,7 @@ impl EnvironmentBuilder<MinimalEthSpec> {
         Self {
             runtime: None,
             log: None,
+            log_levels: LogLevels::new(Level::Info),
             eth_spec_instance: MinimalEthSpec,
             eth2_config: Eth2Config::minimal(),
             testnet: None,
@@ -54,6 +57,7 @@ impl EnvironmentBuilder<MainnetEthSpec> {
         Self {
             runtime: None,
             log: None,
+            log_levels: LogLevels::new(Level::Info),
             eth_spec_instance: MainnetEthSpec,
             eth2_config: Eth2Config::mainnet(),
             testnet: None,
@@ -67,6 +71,7 @@ impl EnvironmentBuilder<InteropEthSpec> {
         Self {
             runtime: None,
             log: None,
+            log_levels: LogLevels::new(Level::Info),
             eth_spec_instance: InteropEthSpec,
             eth2_config: Eth2Config::interop(),
             testnet: None,
@@ -124,7 +129,7 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
         let drain = if let Some(format) = log_format {
             match format.to_uppercase().as_str() {
                 "JSON" => {
-                    let drain = slog_json::Json::default(std::io::stdout()).fuse();
+                    let drain = logging::json_drain(std::io::stdout()).fuse();
                     slog_async::Async::new(drain).build()
                 }
                 _ => return Err("Logging format provided is not supported".to_string()),
@@ -137,20 +142,23 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
             slog_async::Async::new(drain).build()
         };
 
-        let drain = match debug_level {
-            "info" => drain.filter_level(Level::Info),
-            "debug" => drain.filter_level(Level::Debug),
-            "trace" => drain.filter_level(Level::Trace),
-            "warn" => drain.filter_level(Level::Warning),
-            "error" => drain.filter_level(Level::Error),
-            "crit" => drain.filter_level(Level::Critical),
-            unknown => return Err(format!("Unknown debug-level: {}", unknown)),
-        };
+        self.log_levels
+            .set_default_level(logging::parse_level(debug_level)?);
+        let drain = ModuleLevelFilter::new(drain, self.log_levels.clone());
 
         self.log = Some(Logger::root(drain.fuse(), o!()));
         Ok(self)
     }
 
+    /// Overrides the log level of specific modules, using a comma-separated list of
+    /// `module=level` pairs (e.g., `peer_manager=debug,sync=trace`).
+    ///
+    /// The overrides may be modified at runtime via the `LogLevels` in the `RuntimeContext`.
+    pub fn module_log_levels(self, overrides: &str) -> Result<Self, String> {
+        self.log_levels.set_overrides_from_str(overrides)?;
+        Ok(self)
+    }
+
     /// Setups eth2 config using the CLI arguments.
     pub fn eth2_testnet_config(
         mut self,
@@ -174,6 +182,31 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
         Ok(self)
     }
 
+    /// Applies `overrides` to the chain spec. Must be called after `eth2_testnet_config`, so the
+    /// overrides take precedence over the testnet's spec config.
+    ///
+    /// Returns an error if the testnet has a genesis state which was not built with the same
+    /// overrides, since its fork would not match the fork digest used for gossip.
+    pub fn chain_spec_overrides(mut self, overrides: &ChainSpecOverrides) -> Result<Self, String> {
+        overrides
+            .apply(&mut self.eth2_config.spec)
+            .map_err(|e| format!("Invalid chain spec override: {}", e))?;
+
+        let genesis_fork_version = self
+            .testnet
+            .as_ref()
+            .and_then(|testnet| testnet.genesis_state.as_ref())
+            .map(|state| state.fork.current_version);
+        match genesis_fork_version {
+            Some(version) if version != self.eth2_config.spec.genesis_fork_version => Err(format!(
+                "The testnet genesis state has fork version {:?}, the chain spec overrides \
+                 require {:?}",
+                version, self.eth2_config.spec.genesis_fork_version
+            )),
+            _ => Ok(self),
+        }
+    }
+
     /// Consumes the builder, returning an `Environment`.
     pub fn build(self) -> Result<Environment<E>, String> {
         let (signal, exit) = exit_future::signal();
@@ -186,6 +219,7 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
             log: self
                 .log
                 .ok_or_else(|| "Cannot build environment without log".to_string())?,
+            log_levels: self.log_levels,
             eth_spec_instance: self.eth_spec_instance,
             eth2_config: self.eth2_config,
             testnet: self.testnet,
@@ -202,6 +236,8 @@ pub struct RuntimeContext<E: EthSpec> {
     pub executor: TaskExecutor,
     pub eth_spec_instance: E,
     pub eth2_config: Eth2Config,
+    /// The log levels of the logger in `executor`, which may be modified at runtime.
+    pub log_levels: LogLevels,
 }
 
 impl<E: EthSpec> RuntimeContext<E> {
@@ -217,6 +253,7 @@ impl<E: EthSpec> RuntimeContext<E> {
             },
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            log_levels: self.log_levels.clone(),
         }
     }
 
@@ -238,6 +275,7 @@ pub struct Environment<E: EthSpec> {
     signal: Option<exit_future::Signal>,
     exit: exit_future::Exit,
     log: Logger,
+    log_levels: LogLevels,
     eth_spec_instance: E,
     pub eth2_config: Eth2Config,
     pub testnet: Option<Eth2TestnetConfig<E>>,
@@ -262,6 +300,7 @@ impl<E: EthSpec> Environment<E> {
             },
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            log_levels: self.log_levels.clone(),
         }
     }
 
@@ -275,6 +314,7 @@ impl<E: EthSpec> Environment<E> {
             },
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            log_levels: self.log_levels.clone(),
         }
     }
 
@@ -343,21 +383,15 @@ impl<E: EthSpec> Environment<E> {
         let log_format = log_format.unwrap_or("JSON");
         let drain = match log_format.to_uppercase().as_str() {
             "JSON" => {
-                let drain = slog_json::Json::default(file).fuse();
+                let drain = logging::json_drain(file).fuse();
                 slog_async::Async::new(drain).build()
             }
             _ => return Err("Logging format provided is not supported".to_string()),
         };
 
-        let drain = match debug_level {
-            "info" => drain.filter_level(Level::Info),
-            "debug" => drain.filter_level(Level::Debug),
-            "trace" => drain.filter_level(Level::Trace),
-            "warn" => drain.filter_level(Level::Warning),
-            "error" => drain.filter_level(Level::Error),
-            "crit" => drain.filter_level(Level::Critical),
-            unknown => return Err(format!("Unknown debug-level: {}", unknown)),
-        };
+        self.log_levels
+            .set_default_level(logging::parse_level(debug_level)?);
+        let drain = ModuleLevelFilter::new(drain, self.log_levels.clone());
 
         self.log = Logger::root(drain.fuse(), o!());
 
@@ -6,6 +6,7 @@ use clap::{App, Arg, ArgMatches};
 use clap_utils;
 use env_logger::{Builder, Env};
 use environment::EnvironmentBuilder;
+use eth2_config::ChainSpecOverrides;
 use eth2_testnet_config::HARDCODED_TESTNET;
 use slog::{crit, info, warn};
 use std::path::PathBuf;
@@ -72,6 +73,19 @@ fn main() {
                 .global(true)
                 .default_value("info"),
         )
+        .arg(
+            Arg::with_name("debug-level-overrides")
+                .long("debug-level-overrides")
+                .value_name("MODULE=LEVEL,...")
+                .help(
+                    "Overrides the verbosity level for specific modules, e.g. \
+                     \"peer_manager=debug,sync=trace\". Modules without an override use \
+                     --debug-level. The levels may be changed at runtime via the \
+                     /lighthouse/log_levels HTTP API endpoint.",
+                )
+                .takes_value(true)
+                .global(true),
+        )
         .arg(
             Arg::with_name("datadir")
                 .long("datadir")
@@ -94,10 +108,38 @@ fn main() {
                 .takes_value(true)
                 .global(true),
         )
+        .arg(
+            Arg::with_name("spec-seconds-per-slot")
+                .long("spec-seconds-per-slot")
+                .value_name("SECONDS")
+                .help(
+                    "Overrides the number of seconds per slot of the chain spec. For ephemeral \
+                     development networks only; slots per epoch is set by the --spec preset. \
+                     Changes the genesis fork version, so every node, validator client and \
+                     deposit on the network must use the same overrides.",
+                )
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("spec-genesis-delay")
+                .long("spec-genesis-delay")
+                .value_name("SECONDS")
+                .help(
+                    "Overrides the minimum genesis delay of the chain spec. For ephemeral \
+                     development networks only. Changes the genesis fork version, so every \
+                     node, validator client and deposit on the network must use the same \
+                     overrides.",
+                )
+                .takes_value(true)
+                .global(true),
+        )
         .subcommand(beacon_node::cli_app())
         .subcommand(boot_node::cli_app())
         .subcommand(validator_client::cli_app())
         .subcommand(account_manager::cli_app())
+        .subcommand(bench::cli_app())
+        .subcommand(beacon_node::database_manager::cli_app())
         .get_matches();
 
     // boot node subcommand circumvents the environment
@@ -157,11 +199,21 @@ fn run<E: EthSpec>(
     let log_format = matches.value_of("log-format");
     let eth2_testnet_config =
         clap_utils::parse_testnet_dir_with_hardcoded_default(matches, "testnet-dir")?;
+    let chain_spec_overrides = ChainSpecOverrides {
+        seconds_per_slot: clap_utils::parse_optional(matches, "spec-seconds-per-slot")?,
+        genesis_delay: clap_utils::parse_optional(matches, "spec-genesis-delay")?,
+    };
+
+    let mut environment_builder = environment_builder.async_logger(debug_level, log_format)?;
+
+    if let Some(overrides) = matches.value_of("debug-level-overrides") {
+        environment_builder = environment_builder.module_log_levels(overrides)?;
+    }
 
     let mut environment = environment_builder
-        .async_logger(debug_level, log_format)?
         .multi_threaded_tokio_runtime()?
         .eth2_testnet_config(eth2_testnet_config)?
+        .chain_spec_overrides(&chain_spec_overrides)?
         .build()?;
 
     let log = environment.core_context().log().clone();
@@ -198,11 +250,51 @@ fn run<E: EthSpec>(
         return Ok(());
     };
 
+    if let Some(sub_matches) = matches.subcommand_matches(bench::CMD) {
+        bench::run(sub_matches, environment)?;
+
+        return Ok(());
+    };
+
+    // The database must not be in use by a running beacon node.
+    if let Some(sub_matches) = matches.subcommand_matches(beacon_node::database_manager::CMD) {
+        beacon_node::database_manager::run(sub_matches, environment)?;
+
+        return Ok(());
+    };
+
+    // Querying the status of a running beacon node does not start a beacon node.
+    if let Some(sub_matches) = matches
+        .subcommand_matches("beacon_node")
+        .and_then(|matches| matches.subcommand_matches(beacon_node::status::CMD))
+    {
+        beacon_node::status::run(sub_matches, environment)?;
+
+        return Ok(());
+    };
+
     warn!(
         log,
         "Ethereum 2.0 is pre-release. This software is experimental."
     );
 
+    info!(
+        log,
+        "Using BLS backend";
+        "backend" => bls::BACKEND_NAME
+    );
+
+    if !chain_spec_overrides.is_empty() {
+        warn!(
+            log,
+            "Using chain spec overrides";
+            "info" => "only suitable for development networks",
+            "seconds_per_slot" => format!("{:?}", chain_spec_overrides.seconds_per_slot),
+            "genesis_delay" => format!("{:?}", chain_spec_overrides.genesis_delay),
+            "genesis_fork_version" => format!("{:?}", environment.eth2_config().spec.genesis_fork_version),
+        );
+    }
+
     if !matches.is_present("testnet-dir") {
         info!(
             log,
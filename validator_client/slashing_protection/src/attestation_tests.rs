@@ -387,3 +387,39 @@ fn invalid_prev_surrounds_new_multiple() {
     }
     .run()
 }
+
+#[test]
+fn invalid_source_less_than_lower_bound() {
+    StreamTest {
+        cases: vec![
+            Test::single(attestation_data_builder(5, 6)),
+            // Any attestation with a lower source and a greater target would surround the first,
+            // so the target must also be below the bound. The source bound is checked first.
+            Test::single(attestation_data_builder(4, 5)).expect_invalid_att(
+                InvalidAttestation::SourceLessThanLowerBound {
+                    source_epoch: Epoch::new(4),
+                    bound_epoch: Epoch::new(5),
+                },
+            ),
+        ],
+        ..StreamTest::default()
+    }
+    .run()
+}
+
+#[test]
+fn invalid_target_less_than_lower_bound() {
+    StreamTest {
+        cases: vec![
+            Test::single(attestation_data_builder(5, 6)),
+            Test::single(attestation_data_builder(5, 5)).expect_invalid_att(
+                InvalidAttestation::TargetLessThanLowerBound {
+                    target_epoch: Epoch::new(5),
+                    bound_epoch: Epoch::new(6),
+                },
+            ),
+        ],
+        ..StreamTest::default()
+    }
+    .run()
+}
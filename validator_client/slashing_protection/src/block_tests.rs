@@ -122,3 +122,18 @@ fn invalid_unregistered_validator() {
     }
     .run()
 }
+
+#[test]
+fn invalid_slot_less_than_lower_bound() {
+    StreamTest {
+        cases: vec![
+            Test::single(block(5)),
+            Test::single(block(4)).expect_invalid_block(InvalidBlock::SlotLessThanLowerBound {
+                slot: Slot::new(4),
+                bound: Slot::new(5),
+            }),
+        ],
+        ..StreamTest::default()
+    }
+    .run()
+}
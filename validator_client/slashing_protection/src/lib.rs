@@ -3,17 +3,22 @@ mod block_tests;
 mod parallel_tests;
 mod signed_attestation;
 mod signed_block;
+mod signing_lease;
 mod slashing_database;
 mod test_utils;
 
 pub use crate::signed_attestation::{InvalidAttestation, SignedAttestation};
 pub use crate::signed_block::{InvalidBlock, SignedBlock};
+pub use crate::signing_lease::SigningLease;
 pub use crate::slashing_database::SlashingDatabase;
 use rusqlite::Error as SQLError;
 use std::io::{Error as IOError, ErrorKind};
 use std::string::ToString;
 use types::{Hash256, PublicKey};
 
+/// Path to the slashing protection database within the validator client's datadir.
+pub const SLASHING_PROTECTION_FILENAME: &str = "slashing_protection.sqlite";
+
 /// The attestation or block is not safe to sign.
 ///
 /// This could be because it's slashable, or because an error occurred.
@@ -25,6 +30,10 @@ pub enum NotSafe {
     IOError(ErrorKind),
     SQLError(String),
     SQLPoolError(String),
+    /// The database is shared with other validator clients and the signing lease is not held.
+    LeaseNotHeld,
+    /// The system clock is prior to the UNIX epoch, so lease expiry cannot be determined.
+    InvalidSystemTime,
 }
 
 /// The attestation or block is safe to sign, and will not cause the signer to be slashed.
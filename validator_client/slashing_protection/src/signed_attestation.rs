@@ -20,6 +20,18 @@ pub enum InvalidAttestation {
     PrevSurroundsNew { prev: SignedAttestation },
     /// The attestation is invalid because its source epoch is greater than its target epoch.
     SourceExceedsTarget,
+    /// The attestation's source epoch is less than the lowest source epoch in the database,
+    /// so it cannot be checked against attestations which may have been pruned.
+    SourceLessThanLowerBound {
+        source_epoch: Epoch,
+        bound_epoch: Epoch,
+    },
+    /// The attestation's target epoch is less than the lowest target epoch in the database,
+    /// so it cannot be checked against attestations which may have been pruned.
+    TargetLessThanLowerBound {
+        target_epoch: Epoch,
+        bound_epoch: Epoch,
+    },
 }
 
 impl SignedAttestation {
@@ -12,6 +12,12 @@ pub struct SignedBlock {
 #[derive(PartialEq, Debug)]
 pub enum InvalidBlock {
     DoubleBlockProposal(SignedBlock),
+    /// The block's slot is less than the lowest slot in the database, so it cannot be checked
+    /// against blocks which may have been pruned.
+    SlotLessThanLowerBound {
+        slot: Slot,
+        bound: Slot,
+    },
 }
 
 impl SignedBlock {
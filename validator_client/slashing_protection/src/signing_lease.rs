@@ -0,0 +1,110 @@
+//! Leases allow several validator clients to share the same keys and slashing protection
+//! database, with only the holder of the lease permitted to sign.
+//!
+//! The lease is stored in the database itself and checked in the same transaction that records
+//! each signed message, so a validator client which loses the lease cannot sign even if it has
+//! not yet noticed.
+use std::time::Duration;
+
+/// The identity of a validator client competing for the signing lease.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigningLease {
+    /// A name for the validator client which is unique amongst those sharing the database.
+    pub holder: String,
+    /// The period for which the lease remains valid after being acquired or extended. Another
+    /// validator client may take over the lease once it has expired.
+    pub duration: Duration,
+}
+
+impl SigningLease {
+    pub fn new(holder: String, duration: Duration) -> Self {
+        Self { holder, duration }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_tests::block;
+    use crate::test_utils::*;
+    use crate::*;
+    use tempfile::tempdir;
+
+    fn lease(holder: &str, duration_secs: u64) -> SigningLease {
+        SigningLease::new(holder.to_string(), Duration::from_secs(duration_secs))
+    }
+
+    #[test]
+    fn only_one_holder() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("db.sqlite");
+        let db1 = SlashingDatabase::open_or_create_shared(&file, lease("a", 60)).unwrap();
+        let db2 = SlashingDatabase::open_or_create_shared(&file, lease("b", 60)).unwrap();
+
+        let pk = pubkey(0);
+        db1.register_validator(&pk).unwrap();
+
+        // Nobody may sign until the lease is acquired.
+        assert_eq!(
+            db1.check_and_insert_block_proposal(&pk, &block(1), DEFAULT_DOMAIN),
+            Err(NotSafe::LeaseNotHeld)
+        );
+
+        assert!(db1.acquire_lease().unwrap());
+        assert!(!db2.acquire_lease().unwrap());
+        // Extending the lease is always possible for the holder.
+        assert!(db1.acquire_lease().unwrap());
+
+        assert_eq!(
+            db1.check_and_insert_block_proposal(&pk, &block(1), DEFAULT_DOMAIN),
+            Ok(Safe::Valid)
+        );
+        assert_eq!(
+            db2.check_and_insert_block_proposal(&pk, &block(2), DEFAULT_DOMAIN),
+            Err(NotSafe::LeaseNotHeld)
+        );
+
+        // Releasing the lease allows the other client to take over immediately.
+        db1.release_lease().unwrap();
+        assert!(db2.acquire_lease().unwrap());
+        assert!(!db1.acquire_lease().unwrap());
+        assert_eq!(
+            db2.check_and_insert_block_proposal(&pk, &block(2), DEFAULT_DOMAIN),
+            Ok(Safe::Valid)
+        );
+    }
+
+    #[test]
+    fn expired_lease_is_taken_over() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("db.sqlite");
+        let db1 = SlashingDatabase::open_or_create_shared(&file, lease("a", 0)).unwrap();
+        let db2 = SlashingDatabase::open_or_create_shared(&file, lease("b", 60)).unwrap();
+
+        let pk = pubkey(0);
+        db1.register_validator(&pk).unwrap();
+
+        // A zero-length lease expires immediately.
+        assert!(db1.acquire_lease().unwrap());
+        assert_eq!(
+            db1.check_and_insert_block_proposal(&pk, &block(1), DEFAULT_DOMAIN),
+            Err(NotSafe::LeaseNotHeld)
+        );
+
+        assert!(db2.acquire_lease().unwrap());
+        assert_eq!(
+            db2.check_and_insert_block_proposal(&pk, &block(1), DEFAULT_DOMAIN),
+            Ok(Safe::Valid)
+        );
+    }
+
+    #[test]
+    fn unshared_database_needs_no_lease() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("db.sqlite");
+        let db = SlashingDatabase::create(&file).unwrap();
+
+        assert!(db.acquire_lease().unwrap());
+        db.release_lease().unwrap();
+    }
+}
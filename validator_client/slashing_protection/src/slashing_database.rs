@@ -1,12 +1,12 @@
 use crate::signed_attestation::InvalidAttestation;
 use crate::signed_block::InvalidBlock;
-use crate::{NotSafe, Safe, SignedAttestation, SignedBlock};
+use crate::{NotSafe, Safe, SignedAttestation, SignedBlock, SigningLease};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{params, OptionalExtension, Transaction, TransactionBehavior};
 use std::fs::{File, OpenOptions};
 use std::path::Path;
-use std::time::Duration;
-use types::{AttestationData, BeaconBlockHeader, Hash256, PublicKey, SignedRoot};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use types::{AttestationData, BeaconBlockHeader, Epoch, Hash256, PublicKey, SignedRoot, Slot};
 
 type Pool = r2d2::Pool<SqliteConnectionManager>;
 
@@ -20,9 +20,21 @@ pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
 #[cfg(test)]
 pub const CONNECTION_TIMEOUT: Duration = Duration::from_millis(100);
 
+/// The SQLite locking mode of the connections to a `SlashingDatabase`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LockingMode {
+    /// Never release the lock on the database file, preventing its use by other processes.
+    Exclusive,
+    /// Release the lock at the end of each transaction, allowing the database to be shared by
+    /// several validator clients.
+    Normal,
+}
+
 #[derive(Debug, Clone)]
 pub struct SlashingDatabase {
     conn_pool: Pool,
+    /// The lease which must be held in order to sign, if the database is shared.
+    lease: Option<SigningLease>,
 }
 
 impl SlashingDatabase {
@@ -39,6 +51,52 @@ impl SlashingDatabase {
     ///
     /// Error if a database (or any file) already exists at `path`.
     pub fn create(path: &Path) -> Result<Self, NotSafe> {
+        let conn_pool = Self::create_conn_pool(path, LockingMode::Exclusive)?;
+        Ok(Self {
+            conn_pool,
+            lease: None,
+        })
+    }
+
+    /// Open an existing `SlashingDatabase` from disk.
+    pub fn open(path: &Path) -> Result<Self, NotSafe> {
+        let conn_pool = Self::open_conn_pool(&path, LockingMode::Exclusive)?;
+        Ok(Self {
+            conn_pool,
+            lease: None,
+        })
+    }
+
+    /// Open or create a database which is shared with other validator clients using the same
+    /// keys.
+    ///
+    /// Unlike a database opened with `open_or_create`, the database remains accessible to other
+    /// processes. Only the holder of `lease` is permitted to sign, see `Self::acquire_lease`.
+    pub fn open_or_create_shared(path: &Path, lease: SigningLease) -> Result<Self, NotSafe> {
+        let conn_pool = if path.exists() {
+            Self::open_conn_pool(path, LockingMode::Normal)?
+        } else {
+            Self::create_conn_pool(path, LockingMode::Normal)?
+        };
+
+        // Databases created prior to the introduction of leases lack the table.
+        conn_pool.get()?.execute(
+            "CREATE TABLE IF NOT EXISTS signing_lease (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                holder TEXT NOT NULL,
+                expires_at INTEGER NOT NULL
+            )",
+            params![],
+        )?;
+
+        Ok(Self {
+            conn_pool,
+            lease: Some(lease),
+        })
+    }
+
+    /// Create the database file and schema, returning a connection pool for it.
+    fn create_conn_pool(path: &Path, locking_mode: LockingMode) -> Result<Pool, NotSafe> {
         let file = OpenOptions::new()
             .write(true)
             .read(true)
@@ -46,7 +104,7 @@ impl SlashingDatabase {
             .open(path)?;
 
         Self::set_db_file_permissions(&file)?;
-        let conn_pool = Self::open_conn_pool(path)?;
+        let conn_pool = Self::open_conn_pool(path, locking_mode)?;
         let conn = conn_pool.get()?;
 
         conn.execute(
@@ -80,20 +138,18 @@ impl SlashingDatabase {
             params![],
         )?;
 
-        Ok(Self { conn_pool })
-    }
-
-    /// Open an existing `SlashingDatabase` from disk.
-    pub fn open(path: &Path) -> Result<Self, NotSafe> {
-        let conn_pool = Self::open_conn_pool(&path)?;
-        Ok(Self { conn_pool })
+        drop(conn);
+        Ok(conn_pool)
     }
 
     /// Open a new connection pool with all of the necessary settings and tweaks.
-    fn open_conn_pool(path: &Path) -> Result<Pool, NotSafe> {
+    fn open_conn_pool(path: &Path, locking_mode: LockingMode) -> Result<Pool, NotSafe> {
         let manager = SqliteConnectionManager::file(path)
             .with_flags(rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE)
-            .with_init(Self::apply_pragmas);
+            .with_init(move |conn| match locking_mode {
+                LockingMode::Exclusive => Self::apply_pragmas(conn),
+                LockingMode::Normal => Self::apply_shared_pragmas(conn),
+            });
         let conn_pool = Pool::builder()
             .max_size(POOL_SIZE)
             .connection_timeout(CONNECTION_TIMEOUT)
@@ -114,6 +170,18 @@ impl SlashingDatabase {
         Ok(())
     }
 
+    /// Apply the settings for a database shared by several processes.
+    ///
+    /// Transactions remain exclusive, but the lock is released once each completes. Rather than
+    /// failing immediately, a transaction waits for up to `CONNECTION_TIMEOUT` for another
+    /// process to release its lock.
+    fn apply_shared_pragmas(conn: &mut rusqlite::Connection) -> Result<(), rusqlite::Error> {
+        conn.pragma_update(None, "foreign_keys", &true)?;
+        conn.pragma_update(None, "locking_mode", &"NORMAL")?;
+        conn.busy_timeout(CONNECTION_TIMEOUT)?;
+        Ok(())
+    }
+
     /// Set the database file to readable and writable only by its owner (0600).
     #[cfg(unix)]
     fn set_db_file_permissions(file: &File) -> Result<(), NotSafe> {
@@ -195,16 +263,32 @@ impl SlashingDatabase {
         if let Some(existing_block) = existing_block {
             if existing_block.signing_root == block_header.signing_root(domain) {
                 // Same slot and same hash -> we're re-broadcasting a previously signed block
-                Ok(Safe::SameData)
+                return Ok(Safe::SameData);
             } else {
                 // Same epoch but not the same hash -> it's a DoubleBlockProposal
-                Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal(
+                return Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal(
                     existing_block,
-                )))
+                )));
+            }
+        }
+
+        // Blocks prior to the lowest slot may have been pruned, so they can't be checked.
+        let min_slot: Option<Slot> = txn
+            .prepare("SELECT MIN(slot) FROM signed_blocks WHERE validator_id = ?1")?
+            .query_row(params![validator_id], |row| row.get(0))?;
+
+        if let Some(bound) = min_slot {
+            if block_header.slot < bound {
+                return Err(NotSafe::InvalidBlock(
+                    InvalidBlock::SlotLessThanLowerBound {
+                        slot: block_header.slot,
+                        bound,
+                    },
+                ));
             }
-        } else {
-            Ok(Safe::Valid)
         }
+
+        Ok(Safe::Valid)
     }
 
     /// Check an attestation from `validator_pubkey` for slash safety.
@@ -299,6 +383,37 @@ impl SlashingDatabase {
             ));
         }
 
+        // 4. Check that the attestation is not below the lowest source and target epochs, as
+        //    attestations below these may have been pruned and so can't be checked.
+        let (min_source, min_target): (Option<Epoch>, Option<Epoch>) = txn
+            .prepare(
+                "SELECT MIN(source_epoch), MIN(target_epoch)
+                 FROM signed_attestations
+                 WHERE validator_id = ?1",
+            )?
+            .query_row(params![validator_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        if let Some(bound_epoch) = min_source {
+            if att_source_epoch < bound_epoch {
+                return Err(NotSafe::InvalidAttestation(
+                    InvalidAttestation::SourceLessThanLowerBound {
+                        source_epoch: att_source_epoch,
+                        bound_epoch,
+                    },
+                ));
+            }
+        }
+        if let Some(bound_epoch) = min_target {
+            if att_target_epoch < bound_epoch {
+                return Err(NotSafe::InvalidAttestation(
+                    InvalidAttestation::TargetLessThanLowerBound {
+                        target_epoch: att_target_epoch,
+                        bound_epoch,
+                    },
+                ));
+            }
+        }
+
         // Everything has been checked, return Valid
         Ok(Safe::Valid)
     }
@@ -354,6 +469,121 @@ impl SlashingDatabase {
         Ok(())
     }
 
+    /// Remove all records which are not required for slashing protection, returning the number
+    /// of records removed.
+    ///
+    /// For each validator, only the block with the highest slot and the attestation with the
+    /// highest target epoch are kept. New blocks and attestations are checked against these,
+    /// and those below them are refused, as they can no longer be checked against the pruned
+    /// records.
+    ///
+    /// This is safe because the database never contains slashable attestations: every pruned
+    /// attestation has a source epoch no greater than that of the attestation which is kept.
+    pub fn prune(&self) -> Result<usize, NotSafe> {
+        let mut conn = self.conn_pool.get()?;
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+
+        let pruned_blocks = txn.execute(
+            "DELETE FROM signed_blocks
+             WHERE slot < (
+                 SELECT MAX(slot) FROM signed_blocks AS latest
+                 WHERE latest.validator_id = signed_blocks.validator_id
+             )",
+            params![],
+        )?;
+        let pruned_attestations = txn.execute(
+            "DELETE FROM signed_attestations
+             WHERE target_epoch < (
+                 SELECT MAX(target_epoch) FROM signed_attestations AS latest
+                 WHERE latest.validator_id = signed_attestations.validator_id
+             )",
+            params![],
+        )?;
+
+        txn.commit()?;
+        Ok(pruned_blocks + pruned_attestations)
+    }
+
+    /// Prune the database (see `Self::prune`) and then shrink the database file to fit the
+    /// remaining records, returning the number of records removed.
+    pub fn compact(&self) -> Result<usize, NotSafe> {
+        let pruned = self.prune()?;
+        self.conn_pool.get()?.execute("VACUUM", params![])?;
+        Ok(pruned)
+    }
+
+    /// Acquire the signing lease, or extend it if it is already held.
+    ///
+    /// Returns `false` if another validator client holds an unexpired lease. Always returns `true`
+    /// if the database is not shared.
+    pub fn acquire_lease(&self) -> Result<bool, NotSafe> {
+        let lease = match &self.lease {
+            Some(lease) => lease,
+            None => return Ok(true),
+        };
+
+        let mut conn = self.conn_pool.get()?;
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+        let now = unix_time_now()?;
+
+        let is_held_by_other = Self::current_lease(&txn)?.map_or(false, |(holder, expires_at)| {
+            holder != lease.holder && expires_at > now
+        });
+
+        if !is_held_by_other {
+            txn.execute(
+                "INSERT OR REPLACE INTO signing_lease (id, holder, expires_at)
+                 VALUES (0, ?1, ?2)",
+                params![&lease.holder, now + lease.duration.as_secs() as i64],
+            )?;
+        }
+
+        txn.commit()?;
+        Ok(!is_held_by_other)
+    }
+
+    /// Release the signing lease if it is held, allowing another validator client to acquire it
+    /// immediately.
+    pub fn release_lease(&self) -> Result<(), NotSafe> {
+        if let Some(lease) = &self.lease {
+            let conn = self.conn_pool.get()?;
+            conn.execute(
+                "DELETE FROM signing_lease WHERE holder = ?1",
+                params![&lease.holder],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the holder and expiry time of the signing lease, if any.
+    fn current_lease(txn: &Transaction) -> Result<Option<(String, i64)>, NotSafe> {
+        Ok(txn
+            .query_row(
+                "SELECT holder, expires_at FROM signing_lease WHERE id = 0",
+                params![],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    /// Check that `lease` is held and has not expired, if the database is shared.
+    ///
+    /// This should be called in the same (exclusive) transaction that records the signed message,
+    /// so that the lease cannot be taken over before the message is recorded.
+    fn check_lease(txn: &Transaction, lease: Option<&SigningLease>) -> Result<(), NotSafe> {
+        if let Some(lease) = lease {
+            let now = unix_time_now()?;
+            let is_held = Self::current_lease(txn)?.map_or(false, |(holder, expires_at)| {
+                holder == lease.holder && expires_at > now
+            });
+
+            if !is_held {
+                return Err(NotSafe::LeaseNotHeld);
+            }
+        }
+        Ok(())
+    }
+
     /// Check a block proposal for slash safety, and if it is safe, record it in the database.
     ///
     /// The checking and inserting happen atomically and exclusively. We enforce exclusivity
@@ -369,6 +599,7 @@ impl SlashingDatabase {
         let mut conn = self.conn_pool.get()?;
         let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
 
+        Self::check_lease(&txn, self.lease.as_ref())?;
         let safe = self.check_block_proposal(&txn, validator_pubkey, block_header, domain)?;
 
         if safe != Safe::SameData {
@@ -394,6 +625,7 @@ impl SlashingDatabase {
         let mut conn = self.conn_pool.get()?;
         let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
 
+        Self::check_lease(&txn, self.lease.as_ref())?;
         let safe = self.check_attestation(&txn, validator_pubkey, attestation, domain)?;
 
         if safe != Safe::SameData {
@@ -405,6 +637,14 @@ impl SlashingDatabase {
     }
 }
 
+/// Returns the number of seconds since the UNIX epoch, according to the system clock.
+fn unix_time_now() -> Result<i64, NotSafe> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .map_err(|_| NotSafe::InvalidSystemTime)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -468,4 +708,56 @@ mod tests {
         let db2 = SlashingDatabase::open(&file).unwrap();
         check(&db2);
     }
+
+    // Pruning keeps the latest block and attestation, which continue to protect against
+    // slashable messages, including those which conflict with pruned records.
+    #[test]
+    fn prune_keeps_latest_records() {
+        use crate::attestation_tests::attestation_data_builder;
+        use crate::block_tests::block;
+        use crate::test_utils::DEFAULT_DOMAIN;
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("db.sqlite");
+        let db = SlashingDatabase::create(&file).unwrap();
+        let pk = pubkey(0);
+        db.register_validator(&pk).unwrap();
+
+        for slot in 1..=3 {
+            db.check_and_insert_block_proposal(&pk, &block(slot), DEFAULT_DOMAIN)
+                .unwrap();
+        }
+        for epoch in 1..=3 {
+            let att = attestation_data_builder(epoch - 1, epoch);
+            db.check_and_insert_attestation(&pk, &att, DEFAULT_DOMAIN)
+                .unwrap();
+        }
+
+        assert_eq!(db.compact().unwrap(), 4);
+        assert_eq!(db.prune().unwrap(), 0);
+
+        // conflicts with pruned records are refused
+        assert!(db
+            .check_and_insert_block_proposal(&pk, &block(2), DEFAULT_DOMAIN)
+            .is_err());
+        assert!(db
+            .check_and_insert_attestation(&pk, &attestation_data_builder(0, 2), DEFAULT_DOMAIN)
+            .is_err());
+        assert!(db
+            .check_and_insert_attestation(&pk, &attestation_data_builder(0, 4), DEFAULT_DOMAIN)
+            .is_err());
+
+        // conflicts with the kept records are refused, and later messages are allowed
+        assert!(db
+            .check_and_insert_block_proposal(&pk, &block(3), DEFAULT_DOMAIN)
+            .is_err());
+        assert_eq!(
+            db.check_and_insert_block_proposal(&pk, &block(4), DEFAULT_DOMAIN),
+            Ok(Safe::Valid)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation(&pk, &attestation_data_builder(2, 4), DEFAULT_DOMAIN),
+            Ok(Safe::Valid)
+        );
+    }
 }
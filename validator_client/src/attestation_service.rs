@@ -1,10 +1,12 @@
 use crate::{
+    beacon_node_fallback::BeaconNodeFallback,
     duties_service::{DutiesService, DutyAndProof},
+    metrics,
     validator_store::ValidatorStore,
 };
 use environment::RuntimeContext;
 use futures::StreamExt;
-use remote_beacon_node::{PublishStatus, RemoteBeaconNode};
+use remote_beacon_node::PublishStatus;
 use slog::{crit, debug, info, trace};
 use slot_clock::SlotClock;
 use std::collections::HashMap;
@@ -18,7 +20,7 @@ pub struct AttestationServiceBuilder<T, E: EthSpec> {
     duties_service: Option<DutiesService<T, E>>,
     validator_store: Option<ValidatorStore<T, E>>,
     slot_clock: Option<T>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<BeaconNodeFallback<T, E>>,
     context: Option<RuntimeContext<E>>,
 }
 
@@ -28,7 +30,7 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationServiceBuilder<T, E> {
             duties_service: None,
             validator_store: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
         }
     }
@@ -48,8 +50,8 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationServiceBuilder<T, E> {
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: BeaconNodeFallback<T, E>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -70,9 +72,9 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build AttestationService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build AttestationService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build AttestationService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build AttestationService without runtime_context")?,
@@ -86,7 +88,7 @@ pub struct Inner<T, E: EthSpec> {
     duties_service: DutiesService<T, E>,
     validator_store: ValidatorStore<T, E>,
     slot_clock: T,
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: BeaconNodeFallback<T, E>,
     context: RuntimeContext<E>,
 }
 
@@ -172,6 +174,11 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
             .slot_clock
             .now()
             .ok_or_else(|| "Failed to read slot clock".to_string())?;
+
+        // Another validator client is responsible for our duties whilst it holds the lease.
+        if !self.validator_store.is_signing_lease_held() {
+            return Ok(());
+        }
         let duration_to_next_slot = self
             .slot_clock
             .duration_to_next_slot()
@@ -318,13 +325,21 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
             .ok_or_else(|| "Unable to determine current slot from clock".to_string())?
             .epoch(E::slots_per_epoch());
 
-        let attestation = self
-            .beacon_node
-            .http
-            .validator()
-            .produce_attestation(slot, committee_index)
-            .await
-            .map_err(|e| format!("Failed to produce attestation: {:?}", e))?;
+        // Refuse to attest to data from a beacon node which is syncing or has a stale head.
+        let beacon_node = self.beacon_nodes.first_healthy().await?;
+
+        let attestation = {
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::PRODUCE_ATTESTATION],
+            );
+            beacon_node
+                .http
+                .validator()
+                .produce_attestation(slot, committee_index)
+                .await
+                .map_err(|e| format!("Failed to produce attestation: {:?}", e))?
+        };
 
         // For each validator in `validator_duties`, clone the `attestation` and add
         // their signature.
@@ -381,22 +396,46 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
             let num_attestations = signed_attestations.len();
             let beacon_block_root = attestation.data.beacon_block_root;
 
-            self.beacon_node
-                .http
-                .validator()
-                .publish_attestations(signed_attestations)
-                .await
+            metrics::observe_slot_delay(&metrics::ATTESTATION_SIGNED_DELAY, &self.slot_clock, slot);
+
+            let publish_result = {
+                let _timer = metrics::start_timer_vec(
+                    &metrics::BEACON_NODE_RESPONSE_TIMES,
+                    &[metrics::PUBLISH_ATTESTATIONS],
+                );
+                self.beacon_nodes
+                    .publish(&beacon_node, |beacon_node| {
+                        let signed_attestations = signed_attestations.clone();
+                        async move {
+                            beacon_node
+                                .http
+                                .validator()
+                                .publish_attestations(signed_attestations)
+                                .await
+                        }
+                    })
+                    .await
+            };
+
+            publish_result
                 .map_err(|e| format!("Failed to publish attestation: {:?}", e))
                 .map(move |publish_status| match publish_status {
-                    PublishStatus::Valid => info!(
-                        log,
-                        "Successfully published attestations";
-                        "count" => num_attestations,
-                        "head_block" => format!("{:?}", beacon_block_root),
-                        "committee_index" => committee_index,
-                        "slot" => slot.as_u64(),
-                        "type" => "unaggregated",
-                    ),
+                    PublishStatus::Valid => {
+                        metrics::observe_slot_delay(
+                            &metrics::ATTESTATION_PUBLISHED_DELAY,
+                            &self.slot_clock,
+                            slot,
+                        );
+                        info!(
+                            log,
+                            "Successfully published attestations";
+                            "count" => num_attestations,
+                            "head_block" => format!("{:?}", beacon_block_root),
+                            "committee_index" => committee_index,
+                            "slot" => slot.as_u64(),
+                            "type" => "unaggregated",
+                        )
+                    }
                     PublishStatus::Invalid(msg) => crit!(
                         log,
                         "Published attestation was invalid";
@@ -442,13 +481,20 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
     ) -> Result<(), String> {
         let log = self.context.log();
 
-        let aggregated_attestation = self
-            .beacon_node
-            .http
-            .validator()
-            .produce_aggregate_attestation(&attestation.data)
-            .await
-            .map_err(|e| format!("Failed to produce an aggregate attestation: {:?}", e))?;
+        let beacon_node = self.beacon_nodes.first_healthy().await?;
+
+        let aggregated_attestation = {
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::PRODUCE_AGGREGATE],
+            );
+            beacon_node
+                .http
+                .validator()
+                .produce_aggregate_attestation(&attestation.data)
+                .await
+                .map_err(|e| format!("Failed to produce an aggregate attestation: {:?}", e))?
+        };
 
         // For each validator, clone the `aggregated_attestation` and convert it into
         // a `SignedAggregateAndProof`
@@ -495,23 +541,42 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
         if let Some(first) = signed_aggregate_and_proofs.first().cloned() {
             let attestation = first.message.aggregate;
 
-            let publish_status = self
-                .beacon_node
-                .http
-                .validator()
-                .publish_aggregate_and_proof(signed_aggregate_and_proofs)
-                .await
-                .map_err(|e| format!("Failed to publish aggregate and proofs: {:?}", e))?;
+            let publish_status = {
+                let _timer = metrics::start_timer_vec(
+                    &metrics::BEACON_NODE_RESPONSE_TIMES,
+                    &[metrics::PUBLISH_AGGREGATES],
+                );
+                self.beacon_nodes
+                    .publish(&beacon_node, |beacon_node| {
+                        let signed_aggregate_and_proofs = signed_aggregate_and_proofs.clone();
+                        async move {
+                            beacon_node
+                                .http
+                                .validator()
+                                .publish_aggregate_and_proof(signed_aggregate_and_proofs)
+                                .await
+                        }
+                    })
+                    .await
+                    .map_err(|e| format!("Failed to publish aggregate and proofs: {:?}", e))?
+            };
             match publish_status {
-                PublishStatus::Valid => info!(
-                    log,
-                    "Successfully published attestations";
-                    "signatures" => attestation.aggregation_bits.num_set_bits(),
-                    "head_block" => format!("{:?}", attestation.data.beacon_block_root),
-                    "committee_index" => attestation.data.index,
-                    "slot" => attestation.data.slot.as_u64(),
-                    "type" => "aggregated",
-                ),
+                PublishStatus::Valid => {
+                    metrics::observe_slot_delay(
+                        &metrics::AGGREGATE_PUBLISHED_DELAY,
+                        &self.slot_clock,
+                        attestation.data.slot,
+                    );
+                    info!(
+                        log,
+                        "Successfully published attestations";
+                        "signatures" => attestation.aggregation_bits.num_set_bits(),
+                        "head_block" => format!("{:?}", attestation.data.beacon_block_root),
+                        "committee_index" => attestation.data.index,
+                        "slot" => attestation.data.slot.as_u64(),
+                        "type" => "aggregated",
+                    )
+                }
                 PublishStatus::Invalid(msg) => crit!(
                     log,
                     "Published attestation was invalid";
@@ -0,0 +1,465 @@
+//! Selects a beacon node which is fit to provide the data that validators sign.
+//!
+//! The validator client may be configured with several beacon nodes: the primary `--server` and
+//! zero or more `--fallback-servers`. The health of every candidate is checked concurrently once
+//! per slot in the background and each duty uses the first candidate which was last seen to be
+//! synced with a recent head, so duties are not delayed by unresponsive beacon nodes.
+//!
+//! Optionally, signed blocks and attestations are also published to every other beacon node, so
+//! that they reach the network even if the peers of the beacon node in use are poor. Beacon nodes
+//! already ignore messages they have seen on gossip, so the duplicates are harmless. These
+//! broadcasts run in the background, so a slow beacon node cannot delay the duty.
+
+use crate::is_synced::is_synced;
+use environment::TaskExecutor;
+use futures::future::join_all;
+use futures::StreamExt;
+use parking_lot::RwLock;
+use remote_beacon_node::RemoteBeaconNode;
+use slog::{debug, info, warn, Logger};
+use slot_clock::SlotClock;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::time::{interval_at, Duration, Instant};
+use types::{ChainSpec, EthSpec};
+
+/// The health of a beacon node when it was last checked.
+#[derive(Debug, Clone, PartialEq)]
+enum Health {
+    /// The beacon node has not been checked yet.
+    Unknown,
+    Healthy,
+    Unhealthy(String),
+}
+
+/// A beacon node and its health.
+struct Candidate<E: EthSpec> {
+    beacon_node: RemoteBeaconNode<E>,
+    health: RwLock<Health>,
+}
+
+/// A list of beacon nodes in order of preference, the first being the primary.
+#[derive(Clone)]
+pub struct BeaconNodeFallback<T, E: EthSpec> {
+    candidates: Arc<Vec<Candidate<E>>>,
+    /// The index of the candidate used for the last duty, so changes can be logged.
+    in_use: Arc<AtomicUsize>,
+    slot_clock: T,
+    /// The maximum number of slots the head of a beacon node may be behind the wall clock.
+    max_head_distance: u64,
+    /// If true, the primary is used when no candidate is healthy.
+    allow_unsynced: bool,
+    /// If true, signed messages are published to every candidate rather than just the one in use.
+    broadcast: bool,
+    executor: TaskExecutor,
+    log: Logger,
+}
+
+impl<T: SlotClock, E: EthSpec> BeaconNodeFallback<T, E> {
+    /// Returns `None` if `candidates` is empty.
+    pub fn new(
+        candidates: Vec<RemoteBeaconNode<E>>,
+        slot_clock: T,
+        max_head_distance: u64,
+        allow_unsynced: bool,
+        broadcast: bool,
+        executor: TaskExecutor,
+    ) -> Option<Self> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let candidates = candidates
+            .into_iter()
+            .map(|beacon_node| Candidate {
+                beacon_node,
+                health: RwLock::new(Health::Unknown),
+            })
+            .collect();
+
+        Some(Self {
+            candidates: Arc::new(candidates),
+            in_use: Arc::new(AtomicUsize::new(0)),
+            slot_clock,
+            max_head_distance,
+            allow_unsynced,
+            broadcast,
+            log: executor.log().clone(),
+            executor,
+        })
+    }
+
+    /// Returns the primary beacon node, regardless of its health.
+    pub fn primary(&self) -> &RemoteBeaconNode<E> {
+        &self.candidates[0].beacon_node
+    }
+
+    /// Starts the service that checks the health of every beacon node half way through each
+    /// slot, after the beacon nodes should have imported the block of the slot.
+    pub fn start_update_service(self, spec: &ChainSpec) -> Result<(), String>
+    where
+        T: 'static,
+    {
+        let slot_duration = Duration::from_millis(spec.milliseconds_per_slot);
+        let duration_to_next_slot = self
+            .slot_clock
+            .duration_to_next_slot()
+            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
+
+        // Note: `interval_at` panics if `slot_duration` is 0
+        let mut interval = interval_at(
+            Instant::now() + duration_to_next_slot + slot_duration / 2,
+            slot_duration,
+        );
+
+        let executor = self.executor.clone();
+
+        let interval_fut = async move {
+            self.update_health().await;
+            while interval.next().await.is_some() {
+                self.update_health().await;
+            }
+        };
+
+        executor.spawn(interval_fut, "beacon_node_fallback");
+
+        Ok(())
+    }
+
+    /// Checks the health of every beacon node concurrently, storing the results for use by
+    /// `Self::first_healthy`.
+    pub async fn update_health(&self) {
+        join_all(self.candidates.iter().map(|candidate| async move {
+            let health = match self.check_health(&candidate.beacon_node).await {
+                Ok(()) => Health::Healthy,
+                Err(e) => {
+                    debug!(
+                        self.log,
+                        "Beacon node is unhealthy";
+                        "beacon_node" => candidate.beacon_node.http.server_url().as_str(),
+                        "reason" => &e,
+                    );
+                    Health::Unhealthy(e)
+                }
+            };
+            *candidate.health.write() = health;
+        }))
+        .await;
+    }
+
+    /// Returns the first beacon node which was synced and had a head no more than
+    /// `max_head_distance` slots behind the wall clock when its health was last checked.
+    ///
+    /// The health of the beacon nodes is only checked here if it has never been checked before,
+    /// otherwise the results of the last `Self::update_health` are used.
+    ///
+    /// Returns an error if no beacon node is healthy, unless unsynced beacon nodes are allowed,
+    /// in which case the primary is returned.
+    pub async fn first_healthy(&self) -> Result<RemoteBeaconNode<E>, String> {
+        if self
+            .candidates
+            .iter()
+            .any(|candidate| *candidate.health.read() == Health::Unknown)
+        {
+            self.update_health().await;
+        }
+
+        let healthy = self
+            .candidates
+            .iter()
+            .position(|candidate| *candidate.health.read() == Health::Healthy);
+
+        if let Some(index) = healthy {
+            let beacon_node = &self.candidates[index].beacon_node;
+            let previous = self.in_use.swap(index, Ordering::Relaxed);
+            if previous != index {
+                if index == 0 {
+                    info!(
+                        self.log,
+                        "Switched back to primary beacon node";
+                        "beacon_node" => beacon_node.http.server_url().as_str(),
+                    );
+                } else {
+                    warn!(
+                        self.log,
+                        "Switched to fallback beacon node";
+                        "beacon_node" => beacon_node.http.server_url().as_str(),
+                    );
+                }
+            }
+            return Ok(beacon_node.clone());
+        }
+
+        if self.allow_unsynced {
+            self.in_use.store(0, Ordering::Relaxed);
+            Ok(self.primary().clone())
+        } else {
+            Err(format!(
+                "None of the {} beacon node(s) are synced with a recent head",
+                self.candidates.len()
+            ))
+        }
+    }
+
+    /// Publishes a signed message by calling `publish` with `beacon_node`, which should be the
+    /// beacon node that produced the message, returning its result.
+    ///
+    /// If broadcasting is enabled, `publish` is also called with every other candidate in a
+    /// spawned task, so that the result of `beacon_node` is returned without waiting for them.
+    /// Their results are only logged, as a candidate may reject a message which it has already
+    /// received via gossip from the beacon node in use.
+    pub async fn publish<F, R, U, V>(
+        &self,
+        beacon_node: &RemoteBeaconNode<E>,
+        publish: F,
+    ) -> Result<U, V>
+    where
+        F: Fn(RemoteBeaconNode<E>) -> R,
+        R: Future<Output = Result<U, V>> + Send + 'static,
+        U: Debug,
+        V: Debug,
+    {
+        if self.broadcast {
+            let others = self
+                .candidates
+                .iter()
+                .map(|candidate| &candidate.beacon_node)
+                .filter(|candidate| candidate.http.server_url() != beacon_node.http.server_url());
+
+            for other in others {
+                let other_publish = publish(other.clone());
+                let server_url = other.http.server_url().to_string();
+                let log = self.log.clone();
+
+                self.executor.spawn(
+                    async move {
+                        match other_publish.await {
+                            Ok(status) => debug!(
+                                log,
+                                "Broadcast to beacon node";
+                                "beacon_node" => server_url,
+                                "status" => format!("{:?}", status),
+                            ),
+                            Err(e) => warn!(
+                                log,
+                                "Failed to broadcast to beacon node";
+                                "beacon_node" => server_url,
+                                "error" => format!("{:?}", e),
+                            ),
+                        }
+                    },
+                    "beacon_node_broadcast",
+                );
+            }
+        }
+
+        publish(beacon_node.clone()).await
+    }
+
+    /// Returns an error if `beacon_node` is unreachable, syncing or has a stale head.
+    async fn check_health(&self, beacon_node: &RemoteBeaconNode<E>) -> Result<(), String> {
+        if !is_synced(beacon_node, &self.slot_clock, None).await {
+            return Err("syncing or unreachable".to_string());
+        }
+
+        let head_slot = beacon_node
+            .http
+            .beacon()
+            .get_head()
+            .await
+            .map_err(|e| format!("unable to read head: {:?}", e))?
+            .slot;
+        let current_slot = self
+            .slot_clock
+            .now()
+            .ok_or_else(|| "unable to read slot clock".to_string())?;
+
+        if head_slot + self.max_head_distance < current_slot {
+            Err(format!(
+                "head slot {} is more than {} slots behind the current slot {}",
+                head_slot, self.max_head_distance, current_slot
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use rest_types::{CanonicalHeadResponse, SyncingResponse, SyncingStatus};
+    use slot_clock::ManualSlotClock;
+    use std::convert::Infallible;
+    use types::{Hash256, MainnetEthSpec, Slot};
+
+    type E = MainnetEthSpec;
+
+    const CURRENT_SLOT: u64 = 100;
+
+    /// Serves the endpoints used for health checks, reporting a synced node with a head at
+    /// `CURRENT_SLOT`. Returns the URL of the server.
+    fn spawn_healthy_node() -> String {
+        let slot = Slot::new(CURRENT_SLOT);
+        let make_service = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| async move {
+                let body = if req.uri().path().ends_with("/syncing") {
+                    serde_json::to_string(&SyncingResponse {
+                        is_syncing: false,
+                        sync_status: SyncingStatus {
+                            starting_slot: slot,
+                            current_slot: slot,
+                            highest_slot: slot,
+                        },
+                    })
+                } else {
+                    serde_json::to_string(&CanonicalHeadResponse {
+                        slot,
+                        block_root: Hash256::zero(),
+                        state_root: Hash256::zero(),
+                        finalized_slot: Slot::new(0),
+                        finalized_block_root: Hash256::zero(),
+                        justified_slot: Slot::new(0),
+                        justified_block_root: Hash256::zero(),
+                        previous_justified_slot: Slot::new(0),
+                        previous_justified_block_root: Hash256::zero(),
+                    })
+                };
+                Ok::<_, Infallible>(Response::new(Body::from(body.unwrap())))
+            }))
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    /// Returns the URL of a port which nothing is listening on.
+    fn unreachable_node() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    /// Returns a fallback for the given `urls` along with the signal which must be held to keep
+    /// its executor running.
+    fn fallback(
+        urls: &[String],
+        allow_unsynced: bool,
+        broadcast: bool,
+    ) -> (BeaconNodeFallback<ManualSlotClock, E>, exit_future::Signal) {
+        let candidates = urls
+            .iter()
+            .map(|url| {
+                RemoteBeaconNode::new_with_timeout(url.clone(), Duration::from_secs(1)).unwrap()
+            })
+            .collect();
+        let slot_clock = ManualSlotClock::new(
+            Slot::new(0),
+            Duration::from_secs(0),
+            Duration::from_secs(12),
+        );
+        slot_clock.set_slot(CURRENT_SLOT);
+
+        let (signal, exit) = exit_future::signal();
+        let executor = TaskExecutor::new(
+            tokio::runtime::Handle::current(),
+            exit,
+            Logger::root(slog::Discard, slog::o!()),
+        );
+        let beacon_nodes = BeaconNodeFallback::new(
+            candidates,
+            slot_clock,
+            1,
+            allow_unsynced,
+            broadcast,
+            executor,
+        )
+        .unwrap();
+
+        (beacon_nodes, signal)
+    }
+
+    fn url_of(beacon_node: &RemoteBeaconNode<E>) -> String {
+        beacon_node
+            .http
+            .server_url()
+            .as_str()
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn uses_primary_when_healthy() {
+        let urls = vec![spawn_healthy_node(), spawn_healthy_node()];
+        let (beacon_nodes, _signal) = fallback(&urls, false, false);
+
+        let beacon_node = beacon_nodes.first_healthy().await.unwrap();
+        assert_eq!(url_of(&beacon_node), urls[0]);
+    }
+
+    #[tokio::test]
+    async fn uses_fallback_when_primary_is_down() {
+        let urls = vec![unreachable_node(), spawn_healthy_node()];
+        let (beacon_nodes, _signal) = fallback(&urls, false, false);
+
+        let beacon_node = beacon_nodes.first_healthy().await.unwrap();
+        assert_eq!(url_of(&beacon_node), urls[1]);
+        assert_eq!(beacon_nodes.in_use.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn errors_when_all_are_down() {
+        let urls = vec![unreachable_node(), unreachable_node()];
+
+        let (beacon_nodes, _signal) = fallback(&urls, false, false);
+        assert!(beacon_nodes.first_healthy().await.is_err());
+
+        let (beacon_nodes, _signal) = fallback(&urls, true, false);
+        let beacon_node = beacon_nodes.first_healthy().await.unwrap();
+        assert_eq!(url_of(&beacon_node), urls[0]);
+    }
+
+    #[tokio::test]
+    async fn uses_cached_health() {
+        let urls = vec![unreachable_node(), spawn_healthy_node()];
+        let (beacon_nodes, _signal) = fallback(&urls, false, false);
+        beacon_nodes.update_health().await;
+
+        // A cached result is used even if the beacon node has since changed.
+        *beacon_nodes.candidates[0].health.write() = Health::Healthy;
+        let beacon_node = beacon_nodes.first_healthy().await.unwrap();
+        assert_eq!(url_of(&beacon_node), urls[0]);
+
+        *beacon_nodes.candidates[0].health.write() = Health::Unhealthy("down".to_string());
+        *beacon_nodes.candidates[1].health.write() = Health::Unhealthy("down".to_string());
+        assert!(beacon_nodes.first_healthy().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn publish_does_not_wait_for_broadcasts() {
+        let urls = vec![unreachable_node(), unreachable_node()];
+        let (beacon_nodes, _signal) = fallback(&urls, false, true);
+        let primary = beacon_nodes.primary().clone();
+        let primary_url = url_of(&primary);
+
+        let result = beacon_nodes
+            .publish(&primary, |beacon_node| {
+                let is_primary = url_of(&beacon_node) == primary_url;
+                async move {
+                    if !is_primary {
+                        // The broadcast to the other beacon node never completes.
+                        futures::future::pending::<()>().await;
+                    }
+                    Ok::<_, ()>(is_primary)
+                }
+            })
+            .await;
+
+        assert_eq!(result, Ok(true));
+    }
+}
@@ -1,7 +1,10 @@
-use crate::{duties_service::DutiesService, validator_store::ValidatorStore};
+use crate::{
+    beacon_node_fallback::BeaconNodeFallback, duties_service::DutiesService, metrics,
+    validator_store::ValidatorStore,
+};
 use environment::RuntimeContext;
 use futures::{StreamExt, TryFutureExt};
-use remote_beacon_node::{PublishStatus, RemoteBeaconNode};
+use remote_beacon_node::PublishStatus;
 use slog::{crit, error, info, trace};
 use slot_clock::SlotClock;
 use std::ops::Deref;
@@ -17,7 +20,7 @@ pub struct BlockServiceBuilder<T, E: EthSpec> {
     duties_service: Option<DutiesService<T, E>>,
     validator_store: Option<ValidatorStore<T, E>>,
     slot_clock: Option<Arc<T>>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<BeaconNodeFallback<T, E>>,
     context: Option<RuntimeContext<E>>,
 }
 
@@ -27,7 +30,7 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
             duties_service: None,
             validator_store: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
         }
     }
@@ -47,8 +50,8 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: BeaconNodeFallback<T, E>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -69,9 +72,9 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build BlockService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build BlockService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build BlockService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build BlockService without runtime_context")?,
@@ -85,7 +88,7 @@ pub struct Inner<T, E: EthSpec> {
     duties_service: DutiesService<T, E>,
     validator_store: ValidatorStore<T, E>,
     slot_clock: Arc<T>,
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: BeaconNodeFallback<T, E>,
     context: RuntimeContext<E>,
 }
 
@@ -162,6 +165,16 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
             "slot" => slot.as_u64()
         );
 
+        // Another validator client is responsible for our duties whilst it holds the lease.
+        if !self.validator_store.is_signing_lease_held() {
+            trace!(
+                log,
+                "Not producing blocks without the signing lease";
+                "slot" => slot.as_u64()
+            );
+            return Ok(());
+        }
+
         let iter = self.duties_service.block_producers(slot).into_iter();
 
         if iter.len() == 0 {
@@ -200,7 +213,11 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
     }
 
     /// Produce a block at the given slot for validator_pubkey
-    async fn publish_block(self, slot: Slot, validator_pubkey: PublicKey) -> Result<(), String> {
+    pub(crate) async fn publish_block(
+        self,
+        slot: Slot,
+        validator_pubkey: PublicKey,
+    ) -> Result<(), String> {
         let log = self.context.log();
 
         let current_slot = self
@@ -213,35 +230,64 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
             .randao_reveal(&validator_pubkey, slot.epoch(E::slots_per_epoch()))
             .ok_or_else(|| "Unable to produce randao reveal".to_string())?;
 
-        let block = self
-            .beacon_node
-            .http
-            .validator()
-            .produce_block(slot, randao_reveal)
-            .await
-            .map_err(|e| format!("Error from beacon node when producing block: {:?}", e))?;
+        let graffiti = self.validator_store.graffiti(&validator_pubkey);
+
+        // Refuse to sign a block produced by a beacon node which is syncing or has a stale head.
+        let beacon_node = self.beacon_nodes.first_healthy().await?;
+
+        let block = {
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::PRODUCE_BLOCK],
+            );
+            beacon_node
+                .http
+                .validator()
+                .produce_block(slot, randao_reveal, graffiti)
+                .await
+                .map_err(|e| format!("Error from beacon node when producing block: {:?}", e))?
+        };
 
         let signed_block = self
             .validator_store
             .sign_block(&validator_pubkey, block, current_slot)
             .ok_or_else(|| "Unable to sign block".to_string())?;
 
-        let publish_status = self
-            .beacon_node
-            .http
-            .validator()
-            .publish_block(signed_block.clone())
-            .await
-            .map_err(|e| format!("Error from beacon node when publishing block: {:?}", e))?;
+        let publish_status = {
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::PUBLISH_BLOCK],
+            );
+            self.beacon_nodes
+                .publish(&beacon_node, |beacon_node| {
+                    let signed_block = signed_block.clone();
+                    async move {
+                        beacon_node
+                            .http
+                            .validator()
+                            .publish_block(signed_block)
+                            .await
+                    }
+                })
+                .await
+                .map_err(|e| format!("Error from beacon node when publishing block: {:?}", e))?
+        };
 
         match publish_status {
-            PublishStatus::Valid => info!(
-                log,
-                "Successfully published block";
-                "deposits" => signed_block.message.body.deposits.len(),
-                "attestations" => signed_block.message.body.attestations.len(),
-                "slot" => signed_block.slot().as_u64(),
-            ),
+            PublishStatus::Valid => {
+                metrics::observe_slot_delay(
+                    &metrics::BLOCK_PUBLISHED_DELAY,
+                    &self.slot_clock,
+                    slot,
+                );
+                info!(
+                    log,
+                    "Successfully published block";
+                    "deposits" => signed_block.message.body.deposits.len(),
+                    "attestations" => signed_block.message.body.attestations.len(),
+                    "slot" => signed_block.slot().as_u64(),
+                )
+            }
             PublishStatus::Invalid(msg) => crit!(
                 log,
                 "Published block was invalid";
@@ -16,6 +16,36 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value(&DEFAULT_HTTP_SERVER)
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("fallback-servers")
+                .long("fallback-servers")
+                .value_name("NETWORK_ADDRESSES")
+                .help(
+                    "Comma-separated addresses of beacon nodes to use, in order of preference, \
+                    when the beacon node given by --server is syncing, unreachable or has a \
+                    stale head.",
+                )
+                .takes_value(true),
+        )
+        .arg(Arg::with_name("broadcast").long("broadcast").help(
+            "Publish signed blocks and attestations to the beacon nodes given by \
+                    --server and --fallback-servers at the same time, rather than just the \
+                    beacon node which produced them. This improves the chance of inclusion if \
+                    the beacon node in use is poorly connected.",
+        ))
+        .arg(
+            Arg::with_name("max-head-distance")
+                .long("max-head-distance")
+                .value_name("SLOTS")
+                .help(
+                    "The maximum number of slots that the head of a beacon node may be behind \
+                    the current slot. The validator client will not sign blocks or attestations \
+                    using data from a beacon node with an older head, switching to a fallback \
+                    server if possible.",
+                )
+                .default_value("8")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("secrets-dir")
                 .long("secrets-dir")
@@ -36,6 +66,22 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                        the validator client, or if you're certain there are no other \
                        nodes using the same key.",
         ))
+        .arg(Arg::with_name("key-cache").long("key-cache").help(
+            "If present, the decrypted voting keys are stored in a single encrypted cache \
+                       in --datadir, whose random password is stored in --secrets-dir. This \
+                       avoids decrypting every keystore at startup, which may take several \
+                       minutes for hundreds of validators.",
+        ))
+        .arg(
+            Arg::with_name("lazy-decryption")
+                .long("lazy-decryption")
+                .help(
+                    "If present, voting keystores are not decrypted at startup. Each keystore is \
+                       decrypted when its validator is first assigned a duty, allowing the \
+                       validator client to start quickly with many validators. The key cache \
+                       (--key-cache) is read but not updated in this mode.",
+                ),
+        )
         .arg(
             Arg::with_name("allow-unsynced")
                 .long("allow-unsynced")
@@ -44,4 +90,77 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                       node is not synced.",
                 ),
         )
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .help("Enable the Prometheus metrics HTTP server. Disabled by default.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("metrics-address")
+                .long("metrics-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the Prometheus metrics HTTP server.")
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("metrics-port")
+                .long("metrics-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the Prometheus metrics HTTP server.")
+                .default_value("5064")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("http")
+                .long("http")
+                .help(
+                    "Enable the HTTP API for inspecting and updating the settings of each \
+                       validator, such as its graffiti. The API is unauthenticated, so it should \
+                       not be exposed beyond the local machine. Disabled by default.",
+                )
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("http-address")
+                .long("http-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the validator HTTP API server.")
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("http-port")
+                .long("http-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the validator HTTP API server.")
+                .default_value("5062")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("distributed")
+                .long("distributed")
+                .help(
+                    "Share the validator keys and slashing protection database with other \
+                    validator clients also running with this flag. Only the validator client \
+                    holding the signing lease will sign; the others remain on standby and take \
+                    over if the lease is not renewed. The slashing protection database in the \
+                    --datadir must be shared by all of the validator clients.",
+                )
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("lease-expiry")
+                .long("lease-expiry")
+                .value_name("SECONDS")
+                .help(
+                    "The number of seconds after which a signing lease which has not been \
+                    renewed may be taken over by another validator client. Only used with \
+                    --distributed. The system clocks of all validator clients should be \
+                    synchronized to well within this period.",
+                )
+                .default_value("24")
+                .takes_value(true),
+        )
 }
@@ -1,13 +1,22 @@
 use clap::ArgMatches;
 use clap_utils::{parse_optional, parse_path_with_default_in_home_dir};
 use serde_derive::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
+pub use slashing_protection::SLASHING_PROTECTION_FILENAME;
+
 pub const DEFAULT_HTTP_SERVER: &str = "http://localhost:5052/";
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse/validators";
 pub const DEFAULT_SECRETS_DIR: &str = ".lighthouse/secrets";
-/// Path to the slashing protection database within the datadir.
-pub const SLASHING_PROTECTION_FILENAME: &str = "slashing_protection.sqlite";
+pub const DEFAULT_METRICS_PORT: u16 = 5064;
+pub const DEFAULT_HTTP_API_PORT: u16 = 5062;
+pub const DEFAULT_LEASE_EXPIRY_SECS: u64 = 24;
+pub const DEFAULT_MAX_HEAD_DISTANCE: u64 = 8;
+/// Path to the encrypted cache of voting keypairs within the datadir.
+pub const KEY_CACHE_FILENAME: &str = "validator_key_cache.json";
+/// Path to the password of the key cache within the secrets dir.
+pub const KEY_CACHE_PASSWORD_FILENAME: &str = "validator_key_cache";
 
 /// Stores the core configuration for this validator instance.
 #[derive(Clone, Serialize, Deserialize)]
@@ -20,11 +29,44 @@ pub struct Config {
     ///
     /// Should be similar to `http://localhost:8080`
     pub http_server: String,
+    /// The http endpoints of beacon nodes to use, in order of preference, if the node at
+    /// `http_server` is unhealthy.
+    pub fallback_http_servers: Vec<String>,
+    /// The maximum number of slots the head of a beacon node may be behind the current slot for
+    /// it to be used when signing.
+    pub max_head_distance: u64,
+    /// If true, publish signed blocks and attestations to every beacon node rather than just the
+    /// one which produced them.
+    pub broadcast: bool,
     /// If true, the validator client will still poll for duties and produce blocks even if the
     /// beacon node is not synced at startup.
     pub allow_unsynced_beacon_node: bool,
     /// If true, register new validator keys with the slashing protection database.
     pub auto_register: bool,
+    /// If true, load voting keypairs from an encrypted cache rather than decrypting each
+    /// keystore, which is much faster for many validators.
+    pub key_cache: bool,
+    /// If true, voting keystores are decrypted when their validator is first assigned a duty,
+    /// rather than at startup.
+    pub lazy_decryption: bool,
+    /// If true, serve Prometheus metrics via HTTP.
+    pub metrics_enabled: bool,
+    /// The IPv4 address the metrics HTTP server will listen on.
+    pub metrics_listen_address: Ipv4Addr,
+    /// The port the metrics HTTP server will listen on.
+    pub metrics_port: u16,
+    /// If true, serve the validator HTTP API.
+    pub http_api_enabled: bool,
+    /// The IPv4 address the validator HTTP API server will listen on.
+    pub http_api_listen_address: Ipv4Addr,
+    /// The port the validator HTTP API server will listen on.
+    pub http_api_port: u16,
+    /// If true, share the slashing protection database with other validator clients using the
+    /// same keys, signing only whilst holding the signing lease.
+    pub distributed: bool,
+    /// The number of seconds after which the signing lease may be taken over by another
+    /// validator client, if it has not been renewed.
+    pub lease_expiry_secs: u64,
 }
 
 impl Default for Config {
@@ -40,8 +82,21 @@ impl Default for Config {
             data_dir,
             secrets_dir,
             http_server: DEFAULT_HTTP_SERVER.to_string(),
+            fallback_http_servers: vec![],
+            max_head_distance: DEFAULT_MAX_HEAD_DISTANCE,
+            broadcast: false,
             allow_unsynced_beacon_node: false,
             auto_register: false,
+            key_cache: false,
+            lazy_decryption: false,
+            metrics_enabled: false,
+            metrics_listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            metrics_port: DEFAULT_METRICS_PORT,
+            http_api_enabled: false,
+            http_api_listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            http_api_port: DEFAULT_HTTP_API_PORT,
+            distributed: false,
+            lease_expiry_secs: DEFAULT_LEASE_EXPIRY_SECS,
         }
     }
 }
@@ -69,8 +124,24 @@ impl Config {
             config.http_server = server;
         }
 
+        if let Some(servers) = cli_args.value_of("fallback-servers") {
+            config.fallback_http_servers = servers
+                .split(',')
+                .map(str::trim)
+                .filter(|server| !server.is_empty())
+                .map(String::from)
+                .collect();
+        }
+
+        if let Some(max_head_distance) = parse_optional(cli_args, "max-head-distance")? {
+            config.max_head_distance = max_head_distance;
+        }
+
+        config.broadcast = cli_args.is_present("broadcast");
         config.allow_unsynced_beacon_node = cli_args.is_present("allow-unsynced");
         config.auto_register = cli_args.is_present("auto-register");
+        config.key_cache = cli_args.is_present("key-cache");
+        config.lazy_decryption = cli_args.is_present("lazy-decryption");
 
         if let Some(secrets_dir) = parse_optional(cli_args, "secrets-dir")? {
             config.secrets_dir = secrets_dir;
@@ -83,6 +154,44 @@ impl Config {
             ));
         }
 
+        config.metrics_enabled = cli_args.is_present("metrics");
+
+        if let Some(address) = cli_args.value_of("metrics-address") {
+            config.metrics_listen_address = address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| "metrics-address is not a valid IPv4 address.")?;
+        }
+
+        if let Some(port) = cli_args.value_of("metrics-port") {
+            config.metrics_port = port
+                .parse::<u16>()
+                .map_err(|_| "metrics-port is not a valid u16.")?;
+        }
+
+        config.http_api_enabled = cli_args.is_present("http");
+
+        if let Some(address) = cli_args.value_of("http-address") {
+            config.http_api_listen_address = address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| "http-address is not a valid IPv4 address.")?;
+        }
+
+        if let Some(port) = cli_args.value_of("http-port") {
+            config.http_api_port = port
+                .parse::<u16>()
+                .map_err(|_| "http-port is not a valid u16.")?;
+        }
+
+        config.distributed = cli_args.is_present("distributed");
+
+        if let Some(lease_expiry_secs) = parse_optional(cli_args, "lease-expiry")? {
+            config.lease_expiry_secs = lease_expiry_secs;
+        }
+
+        if config.lease_expiry_secs == 0 {
+            return Err("lease-expiry must be greater than zero".to_string());
+        }
+
         Ok(config)
     }
 }
@@ -1,4 +1,4 @@
-use crate::{is_synced::is_synced, validator_store::ValidatorStore};
+use crate::{beacon_node_fallback::BeaconNodeFallback, metrics, validator_store::ValidatorStore};
 use environment::RuntimeContext;
 use futures::StreamExt;
 use parking_lot::RwLock;
@@ -308,9 +308,8 @@ impl DutiesStore {
 pub struct DutiesServiceBuilder<T, E: EthSpec> {
     validator_store: Option<ValidatorStore<T, E>>,
     slot_clock: Option<T>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<BeaconNodeFallback<T, E>>,
     context: Option<RuntimeContext<E>>,
-    allow_unsynced_beacon_node: bool,
 }
 
 impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
@@ -318,9 +317,8 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
         Self {
             validator_store: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
-            allow_unsynced_beacon_node: false,
         }
     }
 
@@ -334,8 +332,8 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: BeaconNodeFallback<T, E>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -344,12 +342,6 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
         self
     }
 
-    /// Set to `true` to allow polling for duties when the beacon node is not synced.
-    pub fn allow_unsynced_beacon_node(mut self, allow_unsynced_beacon_node: bool) -> Self {
-        self.allow_unsynced_beacon_node = allow_unsynced_beacon_node;
-        self
-    }
-
     pub fn build(self) -> Result<DutiesService<T, E>, String> {
         Ok(DutiesService {
             inner: Arc::new(Inner {
@@ -360,13 +352,13 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build DutiesService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build DutiesService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build DutiesService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build DutiesService without runtime_context")?,
-                allow_unsynced_beacon_node: self.allow_unsynced_beacon_node,
+                balances_epoch: RwLock::new(None),
             }),
         })
     }
@@ -377,11 +369,10 @@ pub struct Inner<T, E: EthSpec> {
     store: Arc<DutiesStore>,
     validator_store: ValidatorStore<T, E>,
     pub(crate) slot_clock: T,
-    pub(crate) beacon_node: RemoteBeaconNode<E>,
+    pub(crate) beacon_nodes: BeaconNodeFallback<T, E>,
     context: RuntimeContext<E>,
-    /// If true, the duties service will poll for duties from the beacon node even if it is not
-    /// synced.
-    allow_unsynced_beacon_node: bool,
+    /// The epoch in which the validator balances were last read from the beacon node.
+    balances_epoch: RwLock<Option<Epoch>>,
 }
 
 /// Maintains a store of the duties for all voting validators in the `validator_store`.
@@ -477,11 +468,13 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
     async fn do_update(self) -> Result<(), ()> {
         let log = self.context.log();
 
-        if !is_synced(&self.beacon_node, &self.slot_clock, None).await
-            && !self.allow_unsynced_beacon_node
-        {
-            return Ok(());
-        }
+        let beacon_node = self.beacon_nodes.first_healthy().await.map_err(|e| {
+            debug!(
+                log,
+                "Not polling for duties";
+                "error" => e,
+            )
+        })?;
 
         let current_epoch = self
             .slot_clock
@@ -503,12 +496,41 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
                     );
 
                     self.store.prune(prune_below);
+
+                    match self.validator_store.prune_slashing_protection() {
+                        Ok(pruned) => trace!(
+                            log,
+                            "Pruned slashing protection database";
+                            "records_removed" => pruned,
+                        ),
+                        Err(e) => warn!(
+                            log,
+                            "Failed to prune slashing protection database";
+                            "error" => e,
+                        ),
+                    }
                 }
 
                 epoch
             })?;
 
-        let result = self.clone().update_epoch(current_epoch).await;
+        // Read the validator balances once per epoch.
+        let balances_outdated = *self.balances_epoch.read() != Some(current_epoch);
+        if balances_outdated {
+            if let Err(e) = self
+                .clone()
+                .update_balances(&beacon_node, current_epoch)
+                .await
+            {
+                warn!(
+                    log,
+                    "Failed to update validator balances";
+                    "error" => e
+                );
+            }
+        }
+
+        let result = self.clone().update_epoch(&beacon_node, current_epoch).await;
         if let Err(e) = result {
             error!(
                 log,
@@ -518,7 +540,7 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
         }
 
         self.clone()
-            .update_epoch(current_epoch + 1)
+            .update_epoch(&beacon_node, current_epoch + 1)
             .await
             .map_err(move |e| {
                 error!(
@@ -531,16 +553,75 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
         Ok(())
     }
 
+    /// Attempt to download the balances of all managed validators from the beacon node, exposing
+    /// them as metrics.
+    async fn update_balances(
+        self,
+        beacon_node: &RemoteBeaconNode<E>,
+        epoch: Epoch,
+    ) -> Result<(), String> {
+        let pubkeys = self.validator_store.voting_pubkeys();
+        let validators = {
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::GET_VALIDATORS],
+            );
+            beacon_node
+                .http
+                .beacon()
+                .get_validators(pubkeys, None)
+                .await
+                .map_err(|e| format!("Failed to get validators: {:?}", e))?
+        };
+
+        for validator in validators {
+            if let Some(balance) = validator.balance {
+                metrics::set_validator_balance(
+                    &format!("0x{}", hex::encode(validator.pubkey.as_slice())),
+                    balance,
+                );
+            }
+        }
+
+        *self.balances_epoch.write() = Some(epoch);
+
+        Ok(())
+    }
+
     /// Attempt to download the duties of all managed validators for the given `epoch`.
-    async fn update_epoch(self, epoch: Epoch) -> Result<(), String> {
+    async fn update_epoch(
+        self,
+        beacon_node: &RemoteBeaconNode<E>,
+        epoch: Epoch,
+    ) -> Result<(), String> {
         let pubkeys = self.validator_store.voting_pubkeys();
-        let all_duties = self
-            .beacon_node
-            .http
-            .validator()
-            .get_duties(epoch, pubkeys.as_slice())
-            .await
-            .map_err(move |e| format!("Failed to get duties for epoch {}: {:?}", epoch, e))?;
+        let all_duties = {
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::GET_DUTIES],
+            );
+            beacon_node
+                .http
+                .validator()
+                .get_duties(epoch, pubkeys.as_slice())
+                .await
+                .map_err(move |e| format!("Failed to get duties for epoch {}: {:?}", epoch, e))?
+        };
+
+        // Decrypt the keypairs of the validators with duties (if keystores are decrypted lazily)
+        // before computing their selection proofs, since signing never decrypts a keystore.
+        // Decryption is deliberately slow, so it is run on the blocking thread pool.
+        let pubkeys_with_duties = all_duties
+            .iter()
+            .filter(|duties| duties.validator_index.is_some())
+            .filter_map(|duties| (&duties.validator_pubkey).try_into().ok())
+            .collect::<Vec<PublicKey>>();
+        let validator_store = self.validator_store.clone();
+        tokio::task::spawn_blocking(move || {
+            validator_store.decrypt_voting_keypairs(&pubkeys_with_duties)
+        })
+        .await
+        .map_err(|e| format!("Failed to decrypt validator keystores: {:?}", e))?;
 
         let log = self.context.log().clone();
 
@@ -652,7 +733,11 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
 
             Ok(())
         } else {
-            self.beacon_node
+            let _timer = metrics::start_timer_vec(
+                &metrics::BEACON_NODE_RESPONSE_TIMES,
+                &[metrics::SUBSCRIBE],
+            );
+            beacon_node
                 .http
                 .validator()
                 .subscribe(validator_subscriptions)
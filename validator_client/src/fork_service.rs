@@ -1,7 +1,7 @@
+use crate::{beacon_node_fallback::BeaconNodeFallback, metrics};
 use environment::RuntimeContext;
 use futures::StreamExt;
 use parking_lot::RwLock;
-use remote_beacon_node::RemoteBeaconNode;
 use slog::{debug, trace};
 use slot_clock::SlotClock;
 use std::ops::Deref;
@@ -16,7 +16,7 @@ const TIME_DELAY_FROM_SLOT: Duration = Duration::from_millis(80);
 pub struct ForkServiceBuilder<T, E: EthSpec> {
     fork: Option<Fork>,
     slot_clock: Option<T>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<BeaconNodeFallback<T, E>>,
     context: Option<RuntimeContext<E>>,
 }
 
@@ -25,18 +25,24 @@ impl<T: SlotClock + 'static, E: EthSpec> ForkServiceBuilder<T, E> {
         Self {
             fork: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
         }
     }
 
+    /// Sets the fork to use until it is first downloaded from the beacon node.
+    pub fn fork(mut self, fork: Fork) -> Self {
+        self.fork = Some(fork);
+        self
+    }
+
     pub fn slot_clock(mut self, slot_clock: T) -> Self {
         self.slot_clock = Some(slot_clock);
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: BeaconNodeFallback<T, E>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -52,9 +58,9 @@ impl<T: SlotClock + 'static, E: EthSpec> ForkServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build ForkService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build ForkService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build ForkService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build ForkService without runtime_context")?,
@@ -66,7 +72,7 @@ impl<T: SlotClock + 'static, E: EthSpec> ForkServiceBuilder<T, E> {
 /// Helper to minimise `Arc` usage.
 pub struct Inner<T, E: EthSpec> {
     fork: RwLock<Option<Fork>>,
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: BeaconNodeFallback<T, E>,
     context: RuntimeContext<E>,
     slot_clock: T,
 }
@@ -134,24 +140,30 @@ impl<T: SlotClock + 'static, E: EthSpec> ForkService<T, E> {
         Ok(())
     }
 
-    /// Attempts to download the `Fork` from the server.
+    /// Attempts to download the `Fork` from the first healthy beacon node.
     async fn do_update(self) -> Result<(), ()> {
         let log = self.context.log();
 
-        let fork = self
-            .inner
-            .beacon_node
-            .http
-            .beacon()
-            .get_fork()
-            .await
-            .map_err(|e| {
-                trace!(
-                    log,
-                    "Fork update failed";
-                    "error" => format!("Error retrieving fork: {:?}", e)
-                )
-            })?;
+        let beacon_node = self.beacon_nodes.first_healthy().await.map_err(|e| {
+            trace!(
+                log,
+                "Fork update failed";
+                "error" => format!("No healthy beacon node: {}", e)
+            )
+        })?;
+
+        let timer =
+            metrics::start_timer_vec(&metrics::BEACON_NODE_RESPONSE_TIMES, &[metrics::GET_FORK]);
+
+        let fork = beacon_node.http.beacon().get_fork().await.map_err(|e| {
+            trace!(
+                log,
+                "Fork update failed";
+                "error" => format!("Error retrieving fork: {:?}", e)
+            )
+        })?;
+
+        metrics::stop_timer(timer);
 
         if self.fork.read().as_ref() != Some(&fork) {
             *(self.fork.write()) = Some(fork);
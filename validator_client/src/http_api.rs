@@ -0,0 +1,362 @@
+//! Serves a HTTP API for inspecting and updating the settings of each validator whilst the
+//! validator client is running.
+//!
+//! Changes are saved in the validator's directory, so they survive a restart.
+
+use crate::config::Config;
+use crate::validator_store::ValidatorStore;
+use environment::RuntimeContext;
+use futures::future::TryFutureExt;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_derive::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use slot_clock::SlotClock;
+use std::net::SocketAddr;
+use types::{EthSpec, PublicKey};
+
+/// A validator and its settings, as returned by the API.
+#[derive(Serialize)]
+struct ValidatorSettings {
+    voting_pubkey: PublicKey,
+    /// `None` if the beacon node's default graffiti is used.
+    graffiti: Option<String>,
+}
+
+/// The body of a request to update the graffiti of a validator.
+#[derive(Deserialize)]
+struct GraffitiRequest {
+    /// `None` to use the beacon node's default graffiti.
+    graffiti: Option<String>,
+}
+
+/// Starts a HTTP server which serves the validator API, returning the address it is listening on.
+pub fn serve<T: SlotClock + 'static, E: EthSpec>(
+    context: RuntimeContext<E>,
+    config: &Config,
+    validator_store: ValidatorStore<T, E>,
+) -> Result<SocketAddr, String> {
+    let log = context.log().clone();
+    let executor = context.executor.clone();
+
+    let inner_log = log.clone();
+    let make_service = make_service_fn(move |_socket: &AddrStream| {
+        let validator_store = validator_store.clone();
+        let log = inner_log.clone();
+
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
+                let validator_store = validator_store.clone();
+                let log = log.clone();
+                async move { Ok::<_, hyper::Error>(handle_request(req, validator_store, log).await) }
+            }))
+        }
+    });
+
+    let bind_addr = (config.http_api_listen_address, config.http_api_port).into();
+    let server = Server::try_bind(&bind_addr)
+        .map_err(|e| format!("Unable to bind HTTP API server to {}: {:?}", bind_addr, e))?
+        .serve(make_service);
+
+    // Determine the address the server is actually listening on.
+    //
+    // This may be different to `bind_addr` if bind port was 0 (this allows the OS to choose a free
+    // port).
+    let actual_listen_addr = server.local_addr();
+
+    let exit = executor.exit();
+    let inner_log = log.clone();
+    let server_future = server
+        .with_graceful_shutdown(async {
+            let _ = exit.await;
+        })
+        .map_err(move |e| {
+            warn!(
+                inner_log,
+                "HTTP API server failed";
+                "error" => format!("{:?}", e)
+            )
+        })
+        .unwrap_or_else(|_| ());
+
+    info!(
+        log,
+        "HTTP API server started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+    );
+
+    executor.spawn_without_exit(server_future, "http_api");
+
+    Ok(actual_listen_addr)
+}
+
+async fn handle_request<T: SlotClock + 'static, E: EthSpec>(
+    req: Request<Body>,
+    validator_store: ValidatorStore<T, E>,
+    log: Logger,
+) -> Response<Body> {
+    let path = req.uri().path().to_string();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    match (req.method(), segments.as_slice()) {
+        (&Method::GET, ["lighthouse", "validators"]) => {
+            let mut validators = validator_store
+                .all_graffiti()
+                .into_iter()
+                .map(|(voting_pubkey, graffiti)| ValidatorSettings {
+                    voting_pubkey,
+                    graffiti,
+                })
+                .collect::<Vec<_>>();
+            validators.sort_by_key(|validator| validator.voting_pubkey.as_hex_string());
+
+            json_response(&validators)
+        }
+        (&Method::PUT, ["lighthouse", "validators", pubkey, "graffiti"]) => {
+            let voting_pubkey = match parse_pubkey(pubkey) {
+                Ok(voting_pubkey) => voting_pubkey,
+                Err(e) => return response(StatusCode::BAD_REQUEST, e),
+            };
+            if !validator_store.has_validator(&voting_pubkey) {
+                return response(StatusCode::NOT_FOUND, "Unknown validator".to_string());
+            }
+
+            let request = match hyper::body::to_bytes(req.into_body())
+                .await
+                .map_err(|e| format!("Unable to read request body: {:?}", e))
+                .and_then(|bytes| {
+                    serde_json::from_slice::<GraffitiRequest>(&bytes)
+                        .map_err(|e| format!("Invalid request body: {:?}", e))
+                }) {
+                Ok(request) => request,
+                Err(e) => return response(StatusCode::BAD_REQUEST, e),
+            };
+
+            match validator_store.set_graffiti(&voting_pubkey, request.graffiti.clone()) {
+                Ok(()) => {
+                    info!(
+                        log,
+                        "Updated validator graffiti";
+                        "voting_pubkey" => format!("{:?}", voting_pubkey),
+                        "graffiti" => &request.graffiti,
+                    );
+                    json_response(&ValidatorSettings {
+                        voting_pubkey,
+                        graffiti: request.graffiti,
+                    })
+                }
+                Err(e) => response(StatusCode::BAD_REQUEST, e),
+            }
+        }
+        _ => response(StatusCode::NOT_FOUND, "Not found".to_string()),
+    }
+}
+
+/// Parses a 0x-prefixed hex public key.
+fn parse_pubkey(string: &str) -> Result<PublicKey, String> {
+    if !string.starts_with("0x") {
+        return Err("Public key must have a 0x prefix".to_string());
+    }
+
+    let bytes =
+        hex::decode(&string[2..]).map_err(|e| format!("Invalid public key hex: {:?}", e))?;
+    PublicKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {:?}", e))
+}
+
+fn json_response<T: serde::Serialize>(item: &T) -> Response<Body> {
+    match serde_json::to_string(item) {
+        Ok(body) => Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .expect("response built from static parts should be valid"),
+        Err(e) => response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unable to serialize response: {:?}", e),
+        ),
+    }
+}
+
+fn response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "text/plain")
+        .body(Body::from(body))
+        .expect("response built from static parts should be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon_node_fallback::BeaconNodeFallback;
+    use crate::block_service::BlockServiceBuilder;
+    use crate::duties_service::DutiesServiceBuilder;
+    use crate::fork_service::ForkServiceBuilder;
+    use crate::validator_store::graffiti_bytes;
+    use environment::EnvironmentBuilder;
+    use hyper::Client;
+    use parking_lot::Mutex;
+    use remote_beacon_node::RemoteBeaconNode;
+    use slot_clock::ManualSlotClock;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempdir::TempDir;
+    use types::{BeaconBlock, Fork, Hash256, MinimalEthSpec, Slot};
+    use validator_dir::insecure_keys::build_deterministic_validator_dirs;
+
+    type E = MinimalEthSpec;
+
+    const SLOT: u64 = 1;
+
+    /// Serves the block endpoints of a beacon node, returning a block at `SLOT` and accepting
+    /// every published block. Returns the URL of the server and the query strings of the
+    /// requests to produce a block.
+    fn spawn_beacon_node(block: BeaconBlock<E>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let block_requests = Arc::new(Mutex::new(vec![]));
+        let requests = block_requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let requests = requests.clone();
+            let block = block.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let requests = requests.clone();
+                    let block = block.clone();
+                    async move {
+                        let reply = match (req.method(), req.uri().path()) {
+                            (&Method::GET, "/validator/block") => {
+                                requests
+                                    .lock()
+                                    .push(req.uri().query().unwrap_or_default().to_string());
+                                json_response(&block)
+                            }
+                            (&Method::POST, "/validator/block") => Response::new(Body::empty()),
+                            _ => response(StatusCode::NOT_FOUND, "Not found".to_string()),
+                        };
+                        Ok::<_, Infallible>(reply)
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        (url, block_requests)
+    }
+
+    #[test]
+    fn graffiti_is_used_by_next_block() {
+        let mut env = EnvironmentBuilder::minimal()
+            .null_logger()
+            .unwrap()
+            .multi_threaded_tokio_runtime()
+            .unwrap()
+            .build()
+            .unwrap();
+        let context = env.core_context();
+        let spec = context.eth2_config.spec.clone();
+        let log = context.log().clone();
+
+        let validators_dir = TempDir::new("validators").unwrap();
+        let secrets_dir = TempDir::new("secrets").unwrap();
+        build_deterministic_validator_dirs(
+            validators_dir.path().into(),
+            secrets_dir.path().into(),
+            &[0],
+        )
+        .unwrap();
+        let config = Config {
+            data_dir: validators_dir.path().into(),
+            secrets_dir: secrets_dir.path().into(),
+            http_api_port: 0,
+            ..Config::default()
+        };
+
+        env.runtime().block_on(async move {
+            let mut block = BeaconBlock::<E>::empty(&spec);
+            block.slot = Slot::new(SLOT);
+            let (url, block_requests) = spawn_beacon_node(block);
+
+            let slot_clock =
+                ManualSlotClock::new(Slot::new(0), Duration::from_secs(0), Duration::from_secs(6));
+            slot_clock.set_slot(SLOT);
+            let beacon_nodes = BeaconNodeFallback::new(
+                vec![RemoteBeaconNode::new_with_timeout(url, Duration::from_secs(1)).unwrap()],
+                slot_clock.clone(),
+                1,
+                true,
+                false,
+                context.executor.clone(),
+            )
+            .unwrap();
+            let fork_service = ForkServiceBuilder::new()
+                .fork(Fork::default())
+                .slot_clock(slot_clock.clone())
+                .beacon_nodes(beacon_nodes.clone())
+                .runtime_context(context.clone())
+                .build()
+                .unwrap();
+            let validator_store = ValidatorStore::load_from_disk(
+                &config,
+                Hash256::zero(),
+                spec.clone(),
+                fork_service,
+                log,
+            )
+            .unwrap();
+            validator_store
+                .register_all_validators_for_slashing_protection()
+                .unwrap();
+            let duties_service = DutiesServiceBuilder::new()
+                .validator_store(validator_store.clone())
+                .slot_clock(slot_clock.clone())
+                .beacon_nodes(beacon_nodes.clone())
+                .runtime_context(context.clone())
+                .build()
+                .unwrap();
+            let block_service = BlockServiceBuilder::new()
+                .duties_service(duties_service)
+                .validator_store(validator_store.clone())
+                .slot_clock(slot_clock)
+                .beacon_nodes(beacon_nodes)
+                .runtime_context(context.clone())
+                .build()
+                .unwrap();
+
+            let listen_addr = serve(context, &config, validator_store.clone()).unwrap();
+            let voting_pubkey = validator_store.voting_pubkeys().remove(0);
+
+            let request = Request::builder()
+                .method(Method::PUT)
+                .uri(format!(
+                    "http://{}/lighthouse/validators/{}/graffiti",
+                    listen_addr,
+                    voting_pubkey.as_hex_string()
+                ))
+                .body(Body::from(r#"{"graffiti":"runtime graffiti"}"#))
+                .unwrap();
+            let response = Client::new().request(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            block_service
+                .publish_block(Slot::new(SLOT), voting_pubkey)
+                .await
+                .unwrap();
+
+            let expected = format!(
+                "graffiti=0x{}",
+                hex::encode(graffiti_bytes("runtime graffiti").unwrap())
+            );
+            let block_requests = block_requests.lock();
+            assert_eq!(block_requests.len(), 1);
+            assert!(
+                block_requests[0].split('&').any(|param| param == expected),
+                "block should be produced with the new graffiti: {}",
+                block_requests[0]
+            );
+        });
+    }
+}
@@ -0,0 +1,105 @@
+//! Serves the validator client metrics in the Prometheus text format via HTTP.
+
+use crate::config::Config;
+use environment::RuntimeContext;
+use futures::future::TryFutureExt;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use lighthouse_metrics::{Encoder, TextEncoder};
+use slog::{info, warn};
+use slot_clock::SlotClock;
+use std::net::SocketAddr;
+use types::EthSpec;
+
+/// Starts a HTTP server which responds to `GET /metrics`, returning the address it is listening
+/// on.
+pub fn serve<T: SlotClock + Clone + 'static, E: EthSpec>(
+    context: RuntimeContext<E>,
+    config: &Config,
+    slot_clock: T,
+) -> Result<SocketAddr, String> {
+    let log = context.log().clone();
+    let executor = context.executor.clone();
+
+    let make_service = make_service_fn(move |_socket: &AddrStream| {
+        let slot_clock = slot_clock.clone();
+
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
+                let response = handle_request::<T, E>(req, &slot_clock);
+                async move { Ok::<_, hyper::Error>(response) }
+            }))
+        }
+    });
+
+    let bind_addr = (config.metrics_listen_address, config.metrics_port).into();
+    let server = Server::try_bind(&bind_addr)
+        .map_err(|e| format!("Unable to bind metrics server to {}: {:?}", bind_addr, e))?
+        .serve(make_service);
+
+    // Determine the address the server is actually listening on.
+    //
+    // This may be different to `bind_addr` if bind port was 0 (this allows the OS to choose a free
+    // port).
+    let actual_listen_addr = server.local_addr();
+
+    let exit = executor.exit();
+    let inner_log = log.clone();
+    let server_future = server
+        .with_graceful_shutdown(async {
+            let _ = exit.await;
+        })
+        .map_err(move |e| {
+            warn!(
+                inner_log,
+                "Metrics server failed";
+                "error" => format!("{:?}", e)
+            )
+        })
+        .unwrap_or_else(|_| ());
+
+    info!(
+        log,
+        "Metrics server started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+    );
+
+    executor.spawn_without_exit(server_future, "metrics_http");
+
+    Ok(actual_listen_addr)
+}
+
+fn handle_request<T: SlotClock, E: EthSpec>(req: Request<Body>, slot_clock: &T) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            // Most metrics are updated as duties are performed, only the slot clock needs to be
+            // scraped.
+            slot_clock::scrape_for_metrics::<E, T>(slot_clock);
+
+            let mut buffer = vec![];
+            let encoded = TextEncoder::new()
+                .encode(&lighthouse_metrics::gather(), &mut buffer)
+                .map_err(|e| format!("{:?}", e))
+                .and_then(|()| String::from_utf8(buffer).map_err(|e| format!("{:?}", e)));
+
+            match encoded {
+                Ok(body) => response(StatusCode::OK, body),
+                Err(e) => response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unable to encode metrics: {}", e),
+                ),
+            }
+        }
+        _ => response(StatusCode::NOT_FOUND, "Not found".to_string()),
+    }
+}
+
+fn response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "text/plain")
+        .body(Body::from(body))
+        .expect("response built from static parts should be valid")
+}
@@ -0,0 +1,221 @@
+//! An encrypted cache of the decrypted voting keypairs, so that the validator client does not
+//! need to decrypt every keystore each time it starts.
+//!
+//! Decrypting an EIP-2335 keystore deliberately takes around a second of scrypt, which adds up
+//! to minutes for hundreds of validators. The cache encrypts all of the secret keys together,
+//! so only a single key derivation is needed to load them.
+//!
+//! The cache is encrypted with a random password stored in the secrets directory, alongside the
+//! keystore passwords. Anyone able to read the secrets directory can therefore decrypt the
+//! cache, just as they could decrypt the keystores.
+
+use crate::config::{KEY_CACHE_FILENAME, KEY_CACHE_PASSWORD_FILENAME};
+use bls::{Keypair, PlainText};
+use eth2_keystore::json_keystore::{
+    Aes128Ctr, ChecksumModule, Cipher, CipherModule, Crypto, EmptyMap, EmptyString, KdfModule,
+    Sha256Checksum,
+};
+use eth2_keystore::{decrypt, default_kdf, encrypt, keypair_from_secret, IV_SIZE, SALT_SIZE};
+use rand::{distributions::Alphanumeric, Rng};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// The length of a BLS secret key, in bytes.
+const SECRET_KEY_LEN: usize = 32;
+
+/// The length of the randomly generated password used to encrypt the cache.
+const PASSWORD_LEN: usize = 48;
+
+/// The contents of the cache file.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    /// The public key of each cached keypair, in the order of their secret keys.
+    pubkeys: Vec<String>,
+    /// The concatenated secret keys, encrypted as per EIP-2335.
+    crypto: Crypto,
+}
+
+/// The keypairs loaded from the cache, keyed by their hex-encoded public keys without a `0x`
+/// prefix, as in the `pubkey` field of a keystore.
+#[derive(Default)]
+pub struct KeyCache {
+    keypairs: HashMap<String, Keypair>,
+}
+
+impl KeyCache {
+    /// Loads the cache from `data_dir`, using the password in `secrets_dir`.
+    ///
+    /// Returns an empty cache if either the cache or its password does not exist.
+    pub fn open(data_dir: &Path, secrets_dir: &Path) -> Result<Self, String> {
+        let cache_path = data_dir.join(KEY_CACHE_FILENAME);
+        let password_path = secrets_dir.join(KEY_CACHE_PASSWORD_FILENAME);
+        if !cache_path.exists() || !password_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let password = PlainText::from(
+            fs::read(&password_path)
+                .map_err(|e| format!("Unable to read {:?}: {:?}", password_path, e))?,
+        );
+        let cache_file: CacheFile = serde_json::from_slice(
+            &fs::read(&cache_path)
+                .map_err(|e| format!("Unable to read {:?}: {:?}", cache_path, e))?,
+        )
+        .map_err(|e| format!("Unable to parse {:?}: {:?}", cache_path, e))?;
+
+        let secrets = decrypt(password.as_bytes(), &cache_file.crypto)
+            .map_err(|e| format!("Unable to decrypt {:?}: {:?}", cache_path, e))?;
+        if secrets.len() != cache_file.pubkeys.len() * SECRET_KEY_LEN {
+            return Err(format!("Invalid length of {:?}", cache_path));
+        }
+
+        let keypairs = cache_file
+            .pubkeys
+            .into_iter()
+            .zip(secrets.as_bytes().chunks(SECRET_KEY_LEN))
+            .map(|(pubkey, secret)| {
+                let keypair = keypair_from_secret(secret)
+                    .map_err(|e| format!("Invalid secret key in key cache: {:?}", e))?;
+                if keypair.pk.as_hex_string()[2..] != pubkey[..] {
+                    return Err(format!("Mismatched public key in key cache: {}", pubkey));
+                }
+                Ok((pubkey, keypair))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { keypairs })
+    }
+
+    /// Returns the cached keypairs, keyed by their hex-encoded public keys.
+    pub fn keypairs(&self) -> &HashMap<String, Keypair> {
+        &self.keypairs
+    }
+
+    /// Returns true if the cache holds exactly the given `keypairs`.
+    pub fn matches<'a>(&self, keypairs: impl ExactSizeIterator<Item = &'a Keypair>) -> bool {
+        keypairs.len() == self.keypairs.len()
+            && keypairs
+                .into_iter()
+                .all(|keypair| self.keypairs.contains_key(&keypair.pk.as_hex_string()[2..]))
+    }
+
+    /// Writes a cache of `keypairs` to `data_dir`, replacing any existing cache. A new password
+    /// is generated and stored in `secrets_dir`.
+    pub fn save<'a>(
+        data_dir: &Path,
+        secrets_dir: &Path,
+        keypairs: impl ExactSizeIterator<Item = &'a Keypair>,
+    ) -> Result<(), String> {
+        let mut pubkeys = Vec::with_capacity(keypairs.len());
+        let mut secrets = PlainText::zero(keypairs.len() * SECRET_KEY_LEN);
+        for (keypair, chunk) in keypairs.zip(secrets.as_mut_bytes().chunks_mut(SECRET_KEY_LEN)) {
+            pubkeys.push(keypair.pk.as_hex_string()[2..].to_string());
+            chunk.copy_from_slice(keypair.sk.as_bytes().as_bytes());
+        }
+
+        let password = PlainText::from(
+            rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(PASSWORD_LEN)
+                .collect::<String>()
+                .into_bytes(),
+        );
+
+        let salt = rand::thread_rng().gen::<[u8; SALT_SIZE]>();
+        let iv = rand::thread_rng().gen::<[u8; IV_SIZE]>().to_vec().into();
+        let kdf = default_kdf(salt.to_vec());
+        let cipher = Cipher::Aes128Ctr(Aes128Ctr { iv });
+        let (cipher_text, checksum) =
+            encrypt(secrets.as_bytes(), password.as_bytes(), &kdf, &cipher)
+                .map_err(|e| format!("Unable to encrypt key cache: {:?}", e))?;
+
+        let cache_file = CacheFile {
+            pubkeys,
+            crypto: Crypto {
+                kdf: KdfModule {
+                    function: kdf.function(),
+                    params: kdf,
+                    message: EmptyString,
+                },
+                checksum: ChecksumModule {
+                    function: Sha256Checksum::function(),
+                    params: EmptyMap,
+                    message: checksum.to_vec().into(),
+                },
+                cipher: CipherModule {
+                    function: cipher.function(),
+                    params: cipher,
+                    message: cipher_text.into(),
+                },
+            },
+        };
+        let cache_bytes = serde_json::to_vec(&cache_file)
+            .map_err(|e| format!("Unable to encode key cache: {:?}", e))?;
+
+        write_with_600_perms(
+            &secrets_dir.join(KEY_CACHE_PASSWORD_FILENAME),
+            password.as_bytes(),
+        )?;
+        write_with_600_perms(&data_dir.join(KEY_CACHE_FILENAME), &cache_bytes)
+    }
+}
+
+/// Writes `bytes` to the file at `path`, which is only readable by the current user on unix. On
+/// other platforms the file has the default permissions.
+fn write_with_600_perms(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|e| format!("Unable to write {:?}: {:?}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+    use types::test_utils::generate_deterministic_keypair;
+
+    #[test]
+    fn round_trip() {
+        let data_dir = TempDir::new("data").unwrap();
+        let secrets_dir = TempDir::new("secrets").unwrap();
+        let keypairs = (0..3)
+            .map(generate_deterministic_keypair)
+            .collect::<Vec<_>>();
+
+        let empty = KeyCache::open(data_dir.path(), secrets_dir.path()).unwrap();
+        assert!(empty.keypairs().is_empty());
+        assert!(!empty.matches(keypairs.iter()));
+
+        KeyCache::save(data_dir.path(), secrets_dir.path(), keypairs.iter()).unwrap();
+        let cache = KeyCache::open(data_dir.path(), secrets_dir.path()).unwrap();
+        assert!(cache.matches(keypairs.iter()));
+        assert!(!cache.matches(keypairs[..2].iter()));
+        for keypair in &keypairs {
+            let cached = &cache.keypairs()[&keypair.pk.as_hex_string()[2..]];
+            assert_eq!(
+                cached.sk.as_bytes().as_bytes(),
+                keypair.sk.as_bytes().as_bytes()
+            );
+        }
+
+        // The cache cannot be decrypted with another password.
+        fs::write(
+            secrets_dir.path().join(KEY_CACHE_PASSWORD_FILENAME),
+            "wrong",
+        )
+        .unwrap();
+        assert!(KeyCache::open(data_dir.path(), secrets_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{metrics, ProductionValidatorClient};
+use futures::StreamExt;
+use slog::{error, info, warn};
+use tokio::time::{interval_at, Duration, Instant};
+use types::EthSpec;
+
+/// The number of attempts to renew the signing lease before it expires.
+const RENEWALS_PER_LEASE: u32 = 3;
+
+/// Spawns a service which acquires, and then regularly extends, the signing lease shared with the
+/// other validator clients using the same slashing protection database.
+///
+/// Whilst another validator client holds the lease, this one remains on standby. It takes over
+/// once the lease expires without being renewed (e.g., because the other validator client has
+/// stopped).
+pub fn spawn_lease_service<T: EthSpec>(
+    client: &ProductionValidatorClient<T>,
+) -> Result<(), String> {
+    let context = client.context.service_context("lease".into());
+    let executor = context.executor.clone();
+    let validator_store = client.validator_store.clone();
+
+    let lease_expiry = Duration::from_secs(client.config.lease_expiry_secs);
+    let renewal_interval = std::cmp::max(lease_expiry / RENEWALS_PER_LEASE, Duration::from_secs(1));
+    let mut interval = interval_at(Instant::now(), renewal_interval);
+
+    info!(
+        context.log(),
+        "Signing lease service started";
+        "lease_expiry_secs" => lease_expiry.as_secs(),
+    );
+
+    let interval_fut = async move {
+        let log = context.log();
+        let mut was_held = None;
+
+        while interval.next().await.is_some() {
+            let is_held = match validator_store.acquire_signing_lease() {
+                Ok(is_held) => is_held,
+                Err(e) => {
+                    error!(
+                        log,
+                        "Unable to renew signing lease";
+                        "error" => e
+                    );
+                    false
+                }
+            };
+
+            match (was_held, is_held) {
+                (Some(true), true) | (Some(false), false) => (),
+                (_, true) => info!(log, "Acquired signing lease, performing duties"),
+                (Some(true), false) => warn!(log, "Lost signing lease, standing by"),
+                (None, false) => info!(
+                    log,
+                    "Signing lease held by another validator client, standing by"
+                ),
+            }
+
+            metrics::set_gauge(&metrics::SIGNING_LEASE_HELD, is_held as i64);
+            was_held = Some(is_held);
+        }
+    };
+
+    executor.spawn(interval_fut, "lease_service");
+
+    Ok(())
+}
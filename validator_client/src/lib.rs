@@ -1,10 +1,19 @@
+#[macro_use]
+extern crate lazy_static;
+
 mod attestation_service;
+mod beacon_node_fallback;
 mod block_service;
 mod cli;
 mod config;
 mod duties_service;
 mod fork_service;
+mod http_api;
+mod http_metrics;
 mod is_synced;
+mod key_cache;
+mod lease_service;
+mod metrics;
 mod notifier;
 mod validator_store;
 
@@ -12,12 +21,14 @@ pub use cli::cli_app;
 pub use config::Config;
 
 use attestation_service::{AttestationService, AttestationServiceBuilder};
+use beacon_node_fallback::BeaconNodeFallback;
 use block_service::{BlockService, BlockServiceBuilder};
 use clap::ArgMatches;
 use config::SLASHING_PROTECTION_FILENAME;
 use duties_service::{DutiesService, DutiesServiceBuilder};
 use environment::RuntimeContext;
 use fork_service::{ForkService, ForkServiceBuilder};
+use lease_service::spawn_lease_service;
 use notifier::spawn_notifier;
 use remote_beacon_node::RemoteBeaconNode;
 use slog::{error, info, warn, Logger};
@@ -40,6 +51,7 @@ pub struct ProductionValidatorClient<T: EthSpec> {
     fork_service: ForkService<SystemTimeSlotClock, T>,
     block_service: BlockService<SystemTimeSlotClock, T>,
     attestation_service: AttestationService<SystemTimeSlotClock, T>,
+    validator_store: ValidatorStore<SystemTimeSlotClock, T>,
     config: Config,
 }
 
@@ -67,6 +79,7 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             log_1,
             "Starting validator client";
             "beacon_node" => &config.http_server,
+            "fallback_beacon_nodes" => config.fallback_http_servers.len(),
             "datadir" => format!("{:?}", config.data_dir),
         );
 
@@ -78,12 +91,16 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             );
         }
 
-        let beacon_node =
-            RemoteBeaconNode::new_with_timeout(config.http_server.clone(), HTTP_TIMEOUT)
-                .map_err(|e| format!("Unable to init beacon node http client: {}", e))?;
+        let beacon_nodes = std::iter::once(&config.http_server)
+            .chain(config.fallback_http_servers.iter())
+            .map(|server| {
+                RemoteBeaconNode::new_with_timeout(server.clone(), HTTP_TIMEOUT)
+                    .map_err(|e| format!("Unable to init beacon node http client: {}", e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         // TODO: check if all logs in wait_for_node are produed while awaiting
-        let beacon_node = wait_for_node(beacon_node, log_2).await?;
+        let beacon_node = wait_for_node(&beacon_nodes, log_2).await?;
         let eth2_config = beacon_node
             .http
             .spec()
@@ -158,9 +175,19 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             Duration::from_millis(context.eth2_config.spec.milliseconds_per_slot),
         );
 
+        let beacon_nodes = BeaconNodeFallback::new(
+            beacon_nodes,
+            slot_clock.clone(),
+            config.max_head_distance,
+            config.allow_unsynced_beacon_node,
+            config.broadcast,
+            context.service_context("beacon_nodes".into()).executor,
+        )
+        .ok_or_else(|| "No beacon nodes configured".to_string())?;
+
         let fork_service = ForkServiceBuilder::new()
             .slot_clock(slot_clock.clone())
-            .beacon_node(beacon_node.clone())
+            .beacon_nodes(beacon_nodes.clone())
             .runtime_context(context.service_context("fork".into()))
             .build()?;
 
@@ -188,24 +215,23 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
         let duties_service = DutiesServiceBuilder::new()
             .slot_clock(slot_clock.clone())
             .validator_store(validator_store.clone())
-            .beacon_node(beacon_node.clone())
+            .beacon_nodes(beacon_nodes.clone())
             .runtime_context(context.service_context("duties".into()))
-            .allow_unsynced_beacon_node(config.allow_unsynced_beacon_node)
             .build()?;
 
         let block_service = BlockServiceBuilder::new()
             .duties_service(duties_service.clone())
             .slot_clock(slot_clock.clone())
             .validator_store(validator_store.clone())
-            .beacon_node(beacon_node.clone())
+            .beacon_nodes(beacon_nodes.clone())
             .runtime_context(context.service_context("block".into()))
             .build()?;
 
         let attestation_service = AttestationServiceBuilder::new()
             .duties_service(duties_service.clone())
             .slot_clock(slot_clock)
-            .validator_store(validator_store)
-            .beacon_node(beacon_node)
+            .validator_store(validator_store.clone())
+            .beacon_nodes(beacon_nodes)
             .runtime_context(context.service_context("attestation".into()))
             .build()?;
 
@@ -215,11 +241,23 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
             fork_service,
             block_service,
             attestation_service,
+            validator_store,
             config,
         })
     }
 
     pub fn start_service(&mut self) -> Result<(), String> {
+        if self.config.distributed {
+            spawn_lease_service(self)
+                .map_err(|e| format!("Unable to start lease service: {}", e))?;
+        }
+
+        self.duties_service
+            .beacon_nodes
+            .clone()
+            .start_update_service(&self.context.eth2_config.spec)
+            .map_err(|e| format!("Unable to start beacon node health service: {}", e))?;
+
         self.duties_service
             .clone()
             .start_update_service(&self.context.eth2_config.spec)
@@ -242,45 +280,68 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
 
         spawn_notifier(self).map_err(|e| format!("Failed to start notifier: {}", e))?;
 
+        if self.config.metrics_enabled {
+            http_metrics::serve(
+                self.context.service_context("metrics".into()),
+                &self.config,
+                self.duties_service.slot_clock.clone(),
+            )
+            .map_err(|e| format!("Unable to start metrics server: {}", e))?;
+        }
+
+        if self.config.http_api_enabled {
+            http_api::serve(
+                self.context.service_context("http_api".into()),
+                &self.config,
+                self.validator_store.clone(),
+            )
+            .map_err(|e| format!("Unable to start HTTP API server: {}", e))?;
+        }
+
         Ok(())
     }
 }
 
-/// Request the version from the node, looping back and trying again on failure. Exit once the node
-/// has been contacted.
+/// Request the version from each node in turn, looping back and trying again on failure. Exit once
+/// a node has been contacted, returning that node.
 async fn wait_for_node<E: EthSpec>(
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: &[RemoteBeaconNode<E>],
     log: Logger,
 ) -> Result<RemoteBeaconNode<E>, String> {
-    // Try to get the version string from the node, looping until success is returned.
+    // Try to get the version string from a node, looping until success is returned.
     loop {
-        let log = log.clone();
-        let result = beacon_node
-            .clone()
-            .http
-            .node()
-            .get_version()
-            .await
-            .map_err(|e| format!("{:?}", e));
-
-        match result {
-            Ok(version) => {
-                info!(
-                    log,
-                    "Connected to beacon node";
-                    "version" => version,
-                );
-
-                return Ok(beacon_node);
-            }
-            Err(e) => {
-                error!(
-                    log,
-                    "Unable to connect to beacon node";
-                    "error" => format!("{:?}", e),
-                );
-                delay_for(RETRY_DELAY).await;
+        for beacon_node in beacon_nodes {
+            let log = log.clone();
+            let result = beacon_node
+                .clone()
+                .http
+                .node()
+                .get_version()
+                .await
+                .map_err(|e| format!("{:?}", e));
+
+            match result {
+                Ok(version) => {
+                    info!(
+                        log,
+                        "Connected to beacon node";
+                        "beacon_node" => beacon_node.http.server_url().as_str(),
+                        "version" => version,
+                    );
+
+                    return Ok(beacon_node.clone());
+                }
+                Err(e) => {
+                    error!(
+                        log,
+                        "Unable to connect to beacon node";
+                        "beacon_node" => beacon_node.http.server_url().as_str(),
+                        "error" => format!("{:?}", e),
+                    );
+                }
             }
         }
+
+        delay_for(RETRY_DELAY).await;
     }
 }
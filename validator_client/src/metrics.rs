@@ -0,0 +1,104 @@
+use slot_clock::SlotClock;
+use types::Slot;
+
+pub use lighthouse_metrics::*;
+
+/*
+ * Labels for the `SIGNING_TIMES` histogram.
+ */
+pub const BLOCK: &str = "block";
+pub const ATTESTATION: &str = "attestation";
+pub const AGGREGATE_AND_PROOF: &str = "aggregate_and_proof";
+pub const RANDAO_REVEAL: &str = "randao_reveal";
+pub const SELECTION_PROOF: &str = "selection_proof";
+
+/*
+ * Labels for the `BEACON_NODE_RESPONSE_TIMES` histogram.
+ */
+pub const PRODUCE_BLOCK: &str = "produce_block";
+pub const PUBLISH_BLOCK: &str = "publish_block";
+pub const PRODUCE_ATTESTATION: &str = "produce_attestation";
+pub const PUBLISH_ATTESTATIONS: &str = "publish_attestations";
+pub const PRODUCE_AGGREGATE: &str = "produce_aggregate";
+pub const PUBLISH_AGGREGATES: &str = "publish_aggregates";
+pub const GET_DUTIES: &str = "get_duties";
+pub const SUBSCRIBE: &str = "subscribe";
+pub const GET_FORK: &str = "get_fork";
+pub const GET_VALIDATORS: &str = "get_validators";
+
+lazy_static! {
+    /*
+     * Duty timeliness
+     */
+    pub static ref BLOCK_PUBLISHED_DELAY: Result<Histogram> = try_create_histogram(
+        "vc_block_published_delay_seconds",
+        "Time between the start of the slot and a block being published to the beacon node"
+    );
+    pub static ref ATTESTATION_SIGNED_DELAY: Result<Histogram> = try_create_histogram(
+        "vc_attestation_signed_delay_seconds",
+        "Time between the start of the slot and the attestations for that slot being signed"
+    );
+    pub static ref ATTESTATION_PUBLISHED_DELAY: Result<Histogram> = try_create_histogram(
+        "vc_attestation_published_delay_seconds",
+        "Time between the start of the slot and attestations being published to the beacon node"
+    );
+    pub static ref AGGREGATE_PUBLISHED_DELAY: Result<Histogram> = try_create_histogram(
+        "vc_aggregate_published_delay_seconds",
+        "Time between the start of the slot and aggregates being published to the beacon node"
+    );
+
+    /*
+     * Signing
+     */
+    pub static ref SIGNING_TIMES: Result<HistogramVec> = try_create_histogram_vec(
+        "vc_signing_duration_seconds",
+        "Time taken to check slashing protection and sign a message",
+        &["type"]
+    );
+
+    /*
+     * Beacon node
+     */
+    pub static ref BEACON_NODE_RESPONSE_TIMES: Result<HistogramVec> = try_create_histogram_vec(
+        "vc_beacon_node_response_duration_seconds",
+        "Time taken for the beacon node to respond to a HTTP request",
+        &["endpoint"]
+    );
+
+    /*
+     * Validators
+     */
+    pub static ref VALIDATOR_BALANCE_GWEI: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "vc_validator_balance_gwei",
+        "The balance of each validator, read from the beacon node once per epoch",
+        &["validator"]
+    );
+
+    /*
+     * Distributed validator clients
+     */
+    pub static ref SIGNING_LEASE_HELD: Result<IntGauge> = try_create_int_gauge(
+        "vc_signing_lease_held",
+        "Set to 1 if this validator client holds the signing lease, 0 if it is on standby"
+    );
+}
+
+/// Records the time that has elapsed since the start of `slot` in `histogram`.
+///
+/// Does nothing if the time cannot be determined (e.g., `slot` is in the future).
+pub fn observe_slot_delay<T: SlotClock>(histogram: &Result<Histogram>, slot_clock: &T, slot: Slot) {
+    if let Some(delay) = slot_clock
+        .now_duration()
+        .and_then(|now| now.checked_sub(slot_clock.start_of(slot)?))
+    {
+        observe(histogram, delay.as_secs_f64());
+    }
+}
+
+/// Sets the balance gauge for the validator identified by `validator` (e.g., a hex-encoded public
+/// key).
+pub fn set_validator_balance(validator: &str, balance: u64) {
+    if let Some(gauge) = get_int_gauge(&VALIDATOR_BALANCE_GWEI, &[validator]) {
+        gauge.set(balance as i64);
+    }
+}
@@ -1,23 +1,52 @@
 use crate::config::SLASHING_PROTECTION_FILENAME;
+use crate::key_cache::KeyCache;
+use crate::metrics;
 use crate::{config::Config, fork_service::ForkService};
 use parking_lot::RwLock;
-use slashing_protection::{NotSafe, Safe, SlashingDatabase};
-use slog::{crit, error, warn, Logger};
+use rayon::prelude::*;
+use slashing_protection::{NotSafe, Safe, SigningLease, SlashingDatabase};
+use slog::{crit, debug, error, info, warn, Logger};
 use slot_clock::SlotClock;
 use std::collections::HashMap;
 use std::iter::FromIterator;
 use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempdir::TempDir;
 use types::{
-    Attestation, BeaconBlock, ChainSpec, Domain, Epoch, EthSpec, Fork, Hash256, Keypair, PublicKey,
-    SelectionProof, Signature, SignedAggregateAndProof, SignedBeaconBlock, SignedRoot, Slot,
+    utils::GRAFFITI_BYTES_LEN, Attestation, BeaconBlock, ChainSpec, Domain, Epoch, EthSpec, Fork,
+    Hash256, Keypair, PublicKey, SelectionProof, Signature, SignedAggregateAndProof,
+    SignedBeaconBlock, SignedRoot, Slot,
 };
-use validator_dir::{Manager as ValidatorManager, ValidatorDir};
+use validator_dir::{unlock_keystore, Manager as ValidatorManager, ValidatorDir};
 
 struct LocalValidator {
     validator_dir: ValidatorDir,
-    voting_keypair: Keypair,
+    /// The decrypted voting keypair, or `None` if it has not yet been decrypted (only when
+    /// keystores are decrypted lazily).
+    voting_keypair: Option<Keypair>,
+    /// The graffiti to include in proposed blocks, or `None` for the beacon node's default.
+    graffiti: Option<String>,
+}
+
+/// Returns `graffiti` as the bytes of a block's graffiti field, right-padded with zeros.
+///
+/// Returns an error if `graffiti` is longer than `GRAFFITI_BYTES_LEN` bytes.
+pub fn graffiti_bytes(graffiti: &str) -> Result<[u8; GRAFFITI_BYTES_LEN], String> {
+    let bytes = graffiti.as_bytes();
+    if bytes.len() > GRAFFITI_BYTES_LEN {
+        return Err(format!(
+            "Graffiti is {} bytes, the maximum is {}",
+            bytes.len(),
+            GRAFFITI_BYTES_LEN
+        ));
+    }
+
+    let mut graffiti = [0; GRAFFITI_BYTES_LEN];
+    graffiti[..bytes.len()].copy_from_slice(bytes);
+    Ok(graffiti)
 }
 
 /// We derive our own `PartialEq` to avoid doing equality checks between secret keys.
@@ -36,7 +65,8 @@ struct LocalValidator {
 impl PartialEq for LocalValidator {
     fn eq(&self, other: &Self) -> bool {
         self.validator_dir == other.validator_dir
-            && self.voting_keypair.pk == other.voting_keypair.pk
+            && self.voting_keypair.as_ref().map(|kp| &kp.pk)
+                == other.voting_keypair.as_ref().map(|kp| &kp.pk)
     }
 }
 
@@ -44,6 +74,12 @@ impl PartialEq for LocalValidator {
 pub struct ValidatorStore<T, E: EthSpec> {
     validators: Arc<RwLock<HashMap<PublicKey, LocalValidator>>>,
     slashing_protection: SlashingDatabase,
+    /// The directory containing the passwords to the voting keystores, used to decrypt them
+    /// lazily.
+    secrets_dir: PathBuf,
+    /// True if this validator client may sign, which is always the case unless it shares its
+    /// slashing protection database with other validator clients.
+    signing_lease_held: Arc<AtomicBool>,
     genesis_validators_root: Hash256,
     spec: Arc<ChainSpec>,
     log: Logger,
@@ -61,32 +97,123 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         log: Logger,
     ) -> Result<Self, String> {
         let slashing_db_path = config.data_dir.join(SLASHING_PROTECTION_FILENAME);
-        let slashing_protection =
-            SlashingDatabase::open_or_create(&slashing_db_path).map_err(|e| {
-                format!(
-                    "Failed to open or create slashing protection database: {:?}",
-                    e
-                )
-            })?;
-
-        let validator_key_values = ValidatorManager::open(&config.data_dir)
-            .map_err(|e| format!("unable to read data_dir: {:?}", e))?
-            .decrypt_all_validators(config.secrets_dir.clone(), Some(&log))
-            .map_err(|e| format!("unable to decrypt all validator directories: {:?}", e))?
-            .into_iter()
-            .map(|(kp, dir)| {
-                (
-                    kp.pk.clone(),
-                    LocalValidator {
-                        validator_dir: dir,
-                        voting_keypair: kp,
-                    },
+        let slashing_protection = if config.distributed {
+            let lease = SigningLease::new(
+                lease_holder_name(),
+                Duration::from_secs(config.lease_expiry_secs),
+            );
+            SlashingDatabase::open_or_create_shared(&slashing_db_path, lease)
+        } else {
+            SlashingDatabase::open_or_create(&slashing_db_path)
+        }
+        .map_err(|e| {
+            format!(
+                "Failed to open or create slashing protection database: {:?}",
+                e
+            )
+        })?;
+
+        let key_cache = if config.key_cache {
+            KeyCache::open(&config.data_dir, &config.secrets_dir).unwrap_or_else(|e| {
+                warn!(
+                    log,
+                    "Ignoring unreadable key cache";
+                    "error" => e,
+                );
+                KeyCache::default()
+            })
+        } else {
+            KeyCache::default()
+        };
+
+        let validator_manager = ValidatorManager::open(&config.data_dir)
+            .map_err(|e| format!("unable to read data_dir: {:?}", e))?;
+
+        // When decrypting lazily, only the cached keypairs are available at startup. The others
+        // are decrypted when the validator is first assigned a duty.
+        let validators = if config.lazy_decryption {
+            validator_manager
+                .open_all_validators()
+                .map_err(|e| format!("unable to open all validator directories: {:?}", e))?
+                .into_iter()
+                .map(|dir| {
+                    let pubkey = dir
+                        .voting_pubkey()
+                        .map_err(|e| format!("unable to read voting keystore: {:?}", e))?;
+                    let keypair = key_cache
+                        .keypairs()
+                        .get(&pubkey.as_hex_string()[2..])
+                        .cloned();
+                    Ok((pubkey, keypair, dir))
+                })
+                .collect::<Result<Vec<_>, String>>()?
+        } else {
+            let validators = validator_manager
+                .decrypt_all_validators_with_cache(
+                    config.secrets_dir.clone(),
+                    key_cache.keypairs(),
+                    Some(&log),
                 )
-            });
+                .map_err(|e| format!("unable to decrypt all validator directories: {:?}", e))?;
+
+            if config.key_cache && !key_cache.matches(validators.iter().map(|(kp, _)| kp)) {
+                match KeyCache::save(
+                    &config.data_dir,
+                    &config.secrets_dir,
+                    validators.iter().map(|(kp, _)| kp),
+                ) {
+                    Ok(()) => debug!(
+                        log,
+                        "Updated key cache";
+                        "validators" => validators.len(),
+                    ),
+                    Err(e) => warn!(
+                        log,
+                        "Unable to update key cache";
+                        "error" => e,
+                    ),
+                }
+            }
+
+            validators
+                .into_iter()
+                .map(|(kp, dir)| (kp.pk.clone(), Some(kp), dir))
+                .collect()
+        };
+
+        let validator_key_values = validators.into_iter().map(|(pubkey, kp, dir)| {
+            let graffiti = dir
+                .graffiti()
+                .map_err(|e| format!("{:?}", e))
+                .and_then(|graffiti| match graffiti {
+                    Some(graffiti) => graffiti_bytes(&graffiti).map(|_| Some(graffiti)),
+                    None => Ok(None),
+                })
+                .unwrap_or_else(|e| {
+                    warn!(
+                        log,
+                        "Ignoring invalid validator graffiti";
+                        "error" => e,
+                        "validator_dir" => format!("{:?}", dir.dir()),
+                    );
+                    None
+                });
+
+            (
+                pubkey,
+                LocalValidator {
+                    validator_dir: dir,
+                    voting_keypair: kp,
+                    graffiti,
+                },
+            )
+        });
 
         Ok(Self {
             validators: Arc::new(RwLock::new(HashMap::from_iter(validator_key_values))),
             slashing_protection,
+            secrets_dir: config.secrets_dir.clone(),
+            signing_lease_held: Arc::new(AtomicBool::new(!config.distributed)),
             genesis_validators_root,
             spec: Arc::new(spec),
             log,
@@ -106,6 +233,37 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
             .map_err(|e| format!("Error while registering validators: {:?}", e))
     }
 
+    /// Removes the records from the slashing protection database which are no longer required
+    /// for slashing protection, returning the number of records removed.
+    pub fn prune_slashing_protection(&self) -> Result<usize, String> {
+        self.slashing_protection
+            .prune()
+            .map_err(|e| format!("Error while pruning slashing protection: {:?}", e))
+    }
+
+    /// Acquires or extends the signing lease, returning `true` if it is held.
+    ///
+    /// Always returns `true` if the slashing protection database is not shared.
+    pub fn acquire_signing_lease(&self) -> Result<bool, String> {
+        let result = self
+            .slashing_protection
+            .acquire_lease()
+            .map_err(|e| format!("Unable to acquire signing lease: {:?}", e));
+
+        self.signing_lease_held
+            .store(result == Ok(true), Ordering::Relaxed);
+
+        result
+    }
+
+    /// Returns `true` if this validator client held the signing lease when it was last acquired.
+    ///
+    /// The lease is checked again whenever a block or attestation is signed, so this is only
+    /// useful for avoiding wasted work whilst on standby.
+    pub fn is_signing_lease_held(&self) -> bool {
+        self.signing_lease_held.load(Ordering::Relaxed)
+    }
+
     pub fn voting_pubkeys(&self) -> Vec<PublicKey> {
         self.validators
             .read()
@@ -118,6 +276,163 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         self.validators.read().len()
     }
 
+    /// Returns true if `validator_pubkey` is a known voting public key.
+    pub fn has_validator(&self, validator_pubkey: &PublicKey) -> bool {
+        self.validators.read().contains_key(validator_pubkey)
+    }
+
+    /// Returns the graffiti of every validator.
+    pub fn all_graffiti(&self) -> Vec<(PublicKey, Option<String>)> {
+        self.validators
+            .read()
+            .iter()
+            .map(|(pubkey, validator)| (pubkey.clone(), validator.graffiti.clone()))
+            .collect()
+    }
+
+    /// Returns the graffiti to include in blocks proposed by `validator_pubkey`, if it has any.
+    pub fn graffiti(&self, validator_pubkey: &PublicKey) -> Option<[u8; GRAFFITI_BYTES_LEN]> {
+        self.validators
+            .read()
+            .get(validator_pubkey)?
+            .graffiti
+            .as_ref()
+            .and_then(|graffiti| graffiti_bytes(graffiti).ok())
+    }
+
+    /// Sets the graffiti of `validator_pubkey`, saving it in the validator's directory so that it
+    /// survives a restart. Removes the graffiti if `graffiti` is `None`.
+    pub fn set_graffiti(
+        &self,
+        validator_pubkey: &PublicKey,
+        graffiti: Option<String>,
+    ) -> Result<(), String> {
+        if let Some(graffiti) = &graffiti {
+            graffiti_bytes(graffiti)?;
+        }
+
+        let mut validators = self.validators.write();
+        let validator = validators
+            .get_mut(validator_pubkey)
+            .ok_or_else(|| format!("Unknown validator {:?}", validator_pubkey))?;
+
+        validator
+            .validator_dir
+            .set_graffiti(graffiti.as_deref())
+            .map_err(|e| format!("Unable to save graffiti: {:?}", e))?;
+        validator.graffiti = graffiti;
+
+        Ok(())
+    }
+
+    /// Decrypts the voting keypairs of any of `validator_pubkeys` which have not yet been
+    /// decrypted, in parallel. This is a no-op unless keystores are decrypted lazily.
+    ///
+    /// Decryption blocks for around a second per keystore, so this should be called from the
+    /// blocking thread pool rather than an async task.
+    ///
+    /// Keypairs which cannot be decrypted are logged and left encrypted, so that decryption is
+    /// retried when they are next needed.
+    ///
+    /// The keystores are read while holding the validators lock, but decrypted after it has been
+    /// released, so that signing is not blocked on decryption.
+    pub fn decrypt_voting_keypairs(&self, validator_pubkeys: &[PublicKey]) {
+        let log = &self.log;
+
+        let keystores = {
+            let validators = self.validators.read();
+            validator_pubkeys
+                .iter()
+                .filter_map(|pubkey| {
+                    let validator = validators.get(pubkey)?;
+                    if validator.voting_keypair.is_some() {
+                        return None;
+                    }
+
+                    validator
+                        .validator_dir
+                        .voting_keystore()
+                        .map(|keystore| (pubkey.clone(), keystore))
+                        .map_err(|e| {
+                            error!(
+                                log,
+                                "Unable to read validator keystore";
+                                "error" => format!("{:?}", e),
+                                "voting_pubkey" => pubkey.as_hex_string(),
+                            )
+                        })
+                        .ok()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let secrets_dir = &self.secrets_dir;
+        let decrypted = keystores
+            .into_par_iter()
+            .filter_map(|(pubkey, keystore)| {
+                unlock_keystore(&keystore, secrets_dir)
+                    .map(|keypair| (pubkey.clone(), keypair))
+                    .map_err(|e| {
+                        error!(
+                            log,
+                            "Unable to decrypt validator keystore";
+                            "error" => format!("{:?}", e),
+                            "voting_pubkey" => pubkey.as_hex_string(),
+                        )
+                    })
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+
+        if decrypted.is_empty() {
+            return;
+        }
+
+        let count = decrypted.len();
+        let mut validators = self.validators.write();
+        for (pubkey, keypair) in decrypted {
+            if let Some(validator) = validators.get_mut(&pubkey) {
+                validator.voting_keypair.get_or_insert(keypair);
+            }
+        }
+        let remaining = validators
+            .values()
+            .filter(|validator| validator.voting_keypair.is_none())
+            .count();
+
+        info!(
+            self.log,
+            "Decrypted validator keystores";
+            "count" => count,
+            "remaining" => remaining,
+        );
+    }
+
+    /// Calls `func` with the voting keypair of `validator_pubkey`. Returns `None` if the validator
+    /// is unknown or its keypair has not been decrypted.
+    ///
+    /// Keystores are never decrypted here, as that would stall signing. When decrypting lazily,
+    /// they are decrypted by the duties service once the validator is assigned a duty.
+    fn with_voting_keypair<F, R>(&self, validator_pubkey: &PublicKey, func: F) -> Option<R>
+    where
+        F: FnOnce(&Keypair) -> Option<R>,
+    {
+        let validators = self.validators.read();
+        let validator = validators.get(validator_pubkey)?;
+        match validator.voting_keypair.as_ref() {
+            Some(voting_keypair) => func(voting_keypair),
+            None => {
+                warn!(
+                    self.log,
+                    "Voting keystore not yet decrypted";
+                    "msg" => "the keystore is decrypted when the validator's duties are known",
+                    "voting_pubkey" => validator_pubkey.as_hex_string(),
+                );
+                None
+            }
+        }
+    }
+
     fn fork(&self) -> Option<Fork> {
         if self.fork_service.fork().is_none() {
             error!(
@@ -129,22 +444,20 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
     }
 
     pub fn randao_reveal(&self, validator_pubkey: &PublicKey, epoch: Epoch) -> Option<Signature> {
+        let _timer = metrics::start_timer_vec(&metrics::SIGNING_TIMES, &[metrics::RANDAO_REVEAL]);
+
         // TODO: check this against the slot clock to make sure it's not an early reveal?
-        self.validators
-            .read()
-            .get(validator_pubkey)
-            .and_then(|local_validator| {
-                let voting_keypair = &local_validator.voting_keypair;
-                let domain = self.spec.get_domain(
-                    epoch,
-                    Domain::Randao,
-                    &self.fork()?,
-                    self.genesis_validators_root,
-                );
-                let message = epoch.signing_root(domain);
+        self.with_voting_keypair(validator_pubkey, |voting_keypair| {
+            let domain = self.spec.get_domain(
+                epoch,
+                Domain::Randao,
+                &self.fork()?,
+                self.genesis_validators_root,
+            );
+            let message = epoch.signing_root(domain);
 
-                Some(Signature::new(message.as_bytes(), &voting_keypair.sk))
-            })
+            Some(Signature::new(message.as_bytes(), &voting_keypair.sk))
+        })
     }
 
     pub fn sign_block(
@@ -153,6 +466,8 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         block: BeaconBlock<E>,
         current_slot: Slot,
     ) -> Option<SignedBeaconBlock<E>> {
+        let _timer = metrics::start_timer_vec(&metrics::SIGNING_TIMES, &[metrics::BLOCK]);
+
         // Make sure the block slot is not higher than the current slot to avoid potential attacks.
         if block.slot > current_slot {
             warn!(
@@ -181,18 +496,14 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
 
         match slashing_status {
             // We can safely sign this block.
-            Ok(Safe::Valid) => {
-                let validators = self.validators.read();
-                let validator = validators.get(validator_pubkey)?;
-                let voting_keypair = &validator.voting_keypair;
-
+            Ok(Safe::Valid) => self.with_voting_keypair(validator_pubkey, |voting_keypair| {
                 Some(block.sign(
                     &voting_keypair.sk,
                     &fork,
                     self.genesis_validators_root,
                     &self.spec,
                 ))
-            }
+            }),
             Ok(Safe::SameData) => {
                 warn!(
                     self.log,
@@ -200,6 +511,14 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
                 );
                 None
             }
+            Err(NotSafe::LeaseNotHeld) => {
+                debug!(
+                    self.log,
+                    "Not signing block without the signing lease";
+                    "slot" => block.slot.as_u64()
+                );
+                None
+            }
             Err(NotSafe::UnregisteredValidator(pk)) => {
                 warn!(
                     self.log,
@@ -227,6 +546,8 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         attestation: &mut Attestation<E>,
         current_epoch: Epoch,
     ) -> Option<()> {
+        let _timer = metrics::start_timer_vec(&metrics::SIGNING_TIMES, &[metrics::ATTESTATION]);
+
         // Make sure the target epoch is not higher than the current epoch to avoid potential attacks.
         if attestation.data.target.epoch > current_epoch {
             return None;
@@ -249,11 +570,7 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
 
         match slashing_status {
             // We can safely sign this attestation.
-            Ok(Safe::Valid) => {
-                let validators = self.validators.read();
-                let validator = validators.get(validator_pubkey)?;
-                let voting_keypair = &validator.voting_keypair;
-
+            Ok(Safe::Valid) => self.with_voting_keypair(validator_pubkey, |voting_keypair| {
                 attestation
                     .sign(
                         &voting_keypair.sk,
@@ -272,7 +589,7 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
                     .ok()?;
 
                 Some(())
-            }
+            }),
             Ok(Safe::SameData) => {
                 warn!(
                     self.log,
@@ -280,6 +597,14 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
                 );
                 None
             }
+            Err(NotSafe::LeaseNotHeld) => {
+                debug!(
+                    self.log,
+                    "Not signing attestation without the signing lease";
+                    "slot" => attestation.data.slot.as_u64()
+                );
+                None
+            }
             Err(NotSafe::UnregisteredValidator(pk)) => {
                 warn!(
                     self.log,
@@ -312,18 +637,20 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         aggregate: Attestation<E>,
         selection_proof: SelectionProof,
     ) -> Option<SignedAggregateAndProof<E>> {
-        let validators = self.validators.read();
-        let voting_keypair = &validators.get(validator_pubkey)?.voting_keypair;
-
-        Some(SignedAggregateAndProof::from_aggregate(
-            validator_index,
-            aggregate,
-            Some(selection_proof),
-            &voting_keypair.sk,
-            &self.fork()?,
-            self.genesis_validators_root,
-            &self.spec,
-        ))
+        let _timer =
+            metrics::start_timer_vec(&metrics::SIGNING_TIMES, &[metrics::AGGREGATE_AND_PROOF]);
+
+        self.with_voting_keypair(validator_pubkey, |voting_keypair| {
+            Some(SignedAggregateAndProof::from_aggregate(
+                validator_index,
+                aggregate,
+                Some(selection_proof),
+                &voting_keypair.sk,
+                &self.fork()?,
+                self.genesis_validators_root,
+                &self.spec,
+            ))
+        })
     }
 
     /// Produces a `SelectionProof` for the `slot`, signed by with corresponding secret key to
@@ -333,15 +660,26 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         validator_pubkey: &PublicKey,
         slot: Slot,
     ) -> Option<SelectionProof> {
-        let validators = self.validators.read();
-        let voting_keypair = &validators.get(validator_pubkey)?.voting_keypair;
+        let _timer = metrics::start_timer_vec(&metrics::SIGNING_TIMES, &[metrics::SELECTION_PROOF]);
 
-        Some(SelectionProof::new::<E>(
-            slot,
-            &voting_keypair.sk,
-            &self.fork()?,
-            self.genesis_validators_root,
-            &self.spec,
-        ))
+        self.with_voting_keypair(validator_pubkey, |voting_keypair| {
+            Some(SelectionProof::new::<E>(
+                slot,
+                &voting_keypair.sk,
+                &self.fork()?,
+                self.genesis_validators_root,
+                &self.spec,
+            ))
+        })
     }
 }
+
+/// Returns a name for this process which is very likely to be unique amongst the validator clients
+/// sharing a slashing protection database.
+fn lease_holder_name() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    format!("{}-{}", std::process::id(), nanos)
+}